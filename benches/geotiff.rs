@@ -0,0 +1,345 @@
+#[cfg(feature = "decode")]
+use std::io::Cursor;
+
+#[cfg(feature = "decode")]
+use criterion::BatchSize;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use geo_types::Coord;
+use geotiff::{GeoKeyDirectory, GeoKeyDirectoryRef, TransformTags};
+#[cfg(feature = "decode")]
+use geotiff::{GeoTiff, ReadOptions, Window};
+#[cfg(feature = "decode")]
+use tiff::encoder::{colortype, TiffEncoder};
+#[cfg(feature = "decode")]
+use tiff::tags::Tag;
+
+/// The size of the generated rasters.
+#[cfg(feature = "decode")]
+const SIZE: u32 = 1024;
+/// The size of the tiles of the generated tiled raster.
+#[cfg(feature = "decode")]
+const TILE_SIZE: u32 = 256;
+
+/// The geo keys of a UTM zone with citations and an ellipsoid given by its double parameters.
+fn geo_key_tag_data() -> (Vec<u16>, Vec<f64>, String) {
+    #[rustfmt::skip]
+    let directory = vec![
+        1, 1, 0, 8,
+        1024, 0, 1, 1,
+        1025, 0, 1, 1,
+        1026, 34737, 22, 0,
+        2049, 34737, 7, 22,
+        2054, 0, 1, 9102,
+        2057, 34736, 1, 0,
+        3072, 0, 1, 32632,
+        3076, 0, 1, 9001,
+    ];
+    let ascii_params = "WGS 84 / UTM zone 32N|WGS 84|".to_string();
+    (directory, vec![6378137.0], ascii_params)
+}
+
+#[cfg(feature = "decode")]
+fn pixel_values() -> Vec<i16> {
+    (0..SIZE * SIZE).map(|i| (i % 4093) as i16 - 2000).collect()
+}
+
+/// Encodes a georeferenced raster of 16-bit integers organized in strips of one row.
+#[cfg(feature = "decode")]
+fn encode_striped() -> Vec<u8> {
+    let mut buffer = Cursor::new(Vec::new());
+    let mut encoder = TiffEncoder::new(&mut buffer).unwrap();
+    let mut image = encoder.new_image::<colortype::GrayI16>(SIZE, SIZE).unwrap();
+    let (directory, double_params, ascii_params) = geo_key_tag_data();
+    image
+        .encoder()
+        .write_tag(Tag::GeoKeyDirectoryTag, &directory[..])
+        .unwrap();
+    image
+        .encoder()
+        .write_tag(Tag::GeoDoubleParamsTag, &double_params[..])
+        .unwrap();
+    image
+        .encoder()
+        .write_tag(Tag::GeoAsciiParamsTag, &ascii_params[..])
+        .unwrap();
+    image
+        .encoder()
+        .write_tag(Tag::ModelPixelScaleTag, &[25.0, 25.0, 0.0][..])
+        .unwrap();
+    image
+        .encoder()
+        .write_tag(
+            Tag::ModelTiepointTag,
+            &[0.0, 0.0, 0.0, 400000.0, 5200000.0, 0.0][..],
+        )
+        .unwrap();
+    image.rows_per_strip(1).unwrap();
+    image.write_data(&pixel_values()).unwrap();
+    buffer.into_inner()
+}
+
+/// Encodes an ungeoreferenced little endian raster of 16-bit integers organized in tiles, which
+/// the tiff crate cannot encode.
+#[cfg(feature = "decode")]
+fn encode_tiled() -> Vec<u8> {
+    let values = pixel_values();
+    let tiles_across = SIZE / TILE_SIZE;
+    let num_tiles = tiles_across * tiles_across;
+    let tile_bytes = TILE_SIZE * TILE_SIZE * 2;
+
+    // The header is followed by the IFD, the offsets and byte counts, then the tiles
+    let entries: [(u16, u16, u32, u32); 11] = [
+        (256, 4, 1, SIZE),
+        (257, 4, 1, SIZE),
+        (258, 3, 1, 16),
+        (259, 3, 1, 1),
+        (262, 3, 1, 1),
+        (277, 3, 1, 1),
+        (322, 4, 1, TILE_SIZE),
+        (323, 4, 1, TILE_SIZE),
+        (324, 4, num_tiles, 0),
+        (325, 4, num_tiles, 0),
+        (339, 3, 1, 2),
+    ];
+    let offsets_start = 8 + 2 + entries.len() as u32 * 12 + 4;
+    let byte_counts_start = offsets_start + num_tiles * 4;
+    let data_start = byte_counts_start + num_tiles * 4;
+
+    let mut bytes = b"II*\0".to_vec();
+    bytes.extend(8u32.to_le_bytes());
+    bytes.extend((entries.len() as u16).to_le_bytes());
+    for (tag, field_type, count, value) in entries {
+        let value = match tag {
+            324 => offsets_start,
+            325 => byte_counts_start,
+            _ => value,
+        };
+        bytes.extend(tag.to_le_bytes());
+        bytes.extend(field_type.to_le_bytes());
+        bytes.extend(count.to_le_bytes());
+        match field_type {
+            3 => bytes.extend([(value as u16).to_le_bytes(), [0, 0]].concat()),
+            _ => bytes.extend(value.to_le_bytes()),
+        }
+    }
+    bytes.extend(0u32.to_le_bytes());
+    for tile in 0..num_tiles {
+        bytes.extend((data_start + tile * tile_bytes).to_le_bytes());
+    }
+    for _ in 0..num_tiles {
+        bytes.extend(tile_bytes.to_le_bytes());
+    }
+    for tile in 0..num_tiles {
+        let (x0, y0) = (
+            tile % tiles_across * TILE_SIZE,
+            tile / tiles_across * TILE_SIZE,
+        );
+        for y in y0..y0 + TILE_SIZE {
+            for x in x0..x0 + TILE_SIZE {
+                bytes.extend(values[(y * SIZE + x) as usize].to_le_bytes());
+            }
+        }
+    }
+    bytes
+}
+
+fn bench_geo_keys(c: &mut Criterion) {
+    let (directory, double_params, ascii_params) = geo_key_tag_data();
+    c.bench_function("geo_key_directory/from_tag_data", |b| {
+        b.iter(|| {
+            GeoKeyDirectory::from_tag_data(
+                black_box(&directory),
+                black_box(&double_params),
+                black_box(&ascii_params),
+            )
+            .unwrap()
+        })
+    });
+    c.bench_function("geo_key_directory_ref/from_tag_data", |b| {
+        b.iter(|| {
+            GeoKeyDirectoryRef::from_tag_data(
+                black_box(&directory),
+                black_box(&double_params),
+                black_box(&ascii_params),
+            )
+            .unwrap()
+        })
+    });
+}
+
+fn bench_transforms(c: &mut Criterion) {
+    let coords = (0..10_000)
+        .map(|i| Coord {
+            x: (i % 100) as f64 + 0.5,
+            y: (i / 100) as f64 + 0.5,
+        })
+        .collect::<Vec<_>>();
+    let tie_point_and_pixel_scale = TransformTags {
+        pixel_scale: Some(vec![25.0, 25.0, 0.0]),
+        tie_points: Some(vec![0.0, 0.0, 0.0, 400000.0, 5200000.0, 0.0]),
+        model_transformation: None,
+    };
+    #[rustfmt::skip]
+    let affine = TransformTags {
+        model_transformation: Some(vec![
+            24.9, 0.5, 0.0, 400000.0,
+            0.4, -25.1, 0.0, 5200000.0,
+            0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        ]),
+        ..Default::default()
+    };
+
+    #[allow(unused_mut)]
+    let mut transforms = vec![
+        ("tie_point_and_pixel_scale", tie_point_and_pixel_scale),
+        ("affine", affine),
+    ];
+    #[cfg(feature = "tie-points")]
+    {
+        // Grid of 21 x 21 ground control points every 5 pixels, slightly warped
+        let mut tie_points = vec![];
+        for row in 0..=20 {
+            for column in 0..=20 {
+                let (x, y) = (column as f64 * 5.0, row as f64 * 5.0);
+                tie_points.extend([x, y, 0.0]);
+                tie_points.extend([400000.0 + 25.0 * x + 0.01 * x * y, 5200000.0 - 25.0 * y]);
+                tie_points.push(0.0);
+            }
+        }
+        transforms.push((
+            "tie_point_grid",
+            TransformTags {
+                tie_points: Some(tie_points),
+                ..Default::default()
+            },
+        ));
+    }
+
+    for (name, tags) in transforms {
+        let transform = tags.to_coordinate_transform(1e-9).unwrap().unwrap();
+        let model = coords
+            .iter()
+            .map(|coord| transform.transform_to_model(coord))
+            .collect::<Vec<_>>();
+        c.bench_function(&format!("transform/{name}/to_model"), |b| {
+            b.iter(|| {
+                for coord in &coords {
+                    black_box(transform.transform_to_model(black_box(coord)));
+                }
+            })
+        });
+        c.bench_function(&format!("transform/{name}/to_raster"), |b| {
+            b.iter(|| {
+                for coord in &model {
+                    black_box(transform.transform_to_raster(black_box(coord)).unwrap());
+                }
+            })
+        });
+    }
+}
+
+/// Transforms by 2000 tie points scattered over the raster, whose faces are found with the
+/// R-tree of the mesh.
+#[cfg(feature = "tie-points")]
+fn bench_tie_points(c: &mut Criterion) {
+    // Deterministic pseudo-random raster points
+    let mut state = 1u64;
+    let mut next = || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 11) as f64 / (1u64 << 53) as f64
+    };
+    let mut tie_points = vec![];
+    for _ in 0..2000 {
+        let (x, y) = (next() * 1000.0, next() * 1000.0);
+        tie_points.extend([x, y, 0.0, 400000.0 + 25.0 * x, 5200000.0 - 25.0 * y, 0.0]);
+    }
+    let tags = TransformTags {
+        tie_points: Some(tie_points),
+        ..Default::default()
+    };
+    let transform = tags.to_coordinate_transform(1e-9).unwrap().unwrap();
+    let coords = (0..10_000)
+        .map(|i| Coord {
+            x: 100.0 + (i % 100) as f64 * 8.0,
+            y: 100.0 + (i / 100) as f64 * 8.0,
+        })
+        .collect::<Vec<_>>();
+    let model = coords
+        .iter()
+        .map(|coord| transform.transform_to_model(coord))
+        .collect::<Vec<_>>();
+
+    c.bench_function("transform/tie_points/to_model", |b| {
+        b.iter(|| {
+            for coord in &coords {
+                black_box(transform.transform_to_model(black_box(coord)));
+            }
+        })
+    });
+    c.bench_function("transform/tie_points/to_raster", |b| {
+        b.iter(|| {
+            for coord in &model {
+                black_box(transform.transform_to_raster(black_box(coord)).ok());
+            }
+        })
+    });
+}
+
+#[cfg(not(feature = "tie-points"))]
+fn bench_tie_points(_: &mut Criterion) {}
+
+#[cfg(feature = "decode")]
+fn bench_reads(c: &mut Criterion) {
+    for (name, bytes) in [("striped", encode_striped()), ("tiled", encode_tiled())] {
+        c.bench_function(&format!("read/{name}/open"), |b| {
+            b.iter(|| GeoTiff::from_bytes(black_box(&bytes)).unwrap())
+        });
+
+        let geotiff = GeoTiff::from_bytes(&bytes).unwrap();
+        let options = ReadOptions::default();
+        let window = Window::new(100, 200, 512, 512);
+        c.bench_function(&format!("read/{name}/window"), |b| {
+            b.iter(|| {
+                geotiff
+                    .read_window::<f64>(black_box(window), &options)
+                    .unwrap()
+            })
+        });
+        c.bench_function(&format!("read/{name}/window_into"), |b| {
+            b.iter_batched_ref(
+                || vec![0.0f64; 512 * 512],
+                |buffer| {
+                    geotiff
+                        .read_window_into(black_box(window), buffer, 512, &options)
+                        .unwrap()
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        c.bench_function(&format!("read/{name}/window_decimated"), |b| {
+            b.iter(|| {
+                geotiff
+                    .read_window_decimated::<f64>(geotiff.primary().window(), 256, 256, &options)
+                    .unwrap()
+            })
+        });
+        c.bench_function(&format!("read/{name}/optimal_read_windows"), |b| {
+            b.iter(|| geotiff.optimal_read_windows(black_box(geotiff.primary().window()), 1 << 16))
+        });
+    }
+}
+
+#[cfg(not(feature = "decode"))]
+fn bench_reads(_: &mut Criterion) {}
+
+criterion_group!(
+    benches,
+    bench_geo_keys,
+    bench_transforms,
+    bench_tie_points,
+    bench_reads
+);
+criterion_main!(benches);
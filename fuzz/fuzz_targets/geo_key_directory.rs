@@ -0,0 +1,21 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use geotiff::GeoKeyDirectory;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, Arbitrary)]
+struct TagData {
+    directory: Vec<u16>,
+    double_params: Vec<f64>,
+    ascii_params: String,
+}
+
+fuzz_target!(|data: TagData| {
+    if let Ok(directory) =
+        GeoKeyDirectory::from_tag_data(&data.directory, &data.double_params, &data.ascii_params)
+    {
+        let (directory_data, double_params, ascii_params) = directory.to_tag_data();
+        let _ = GeoKeyDirectory::from_tag_data(&directory_data, &double_params, &ascii_params);
+    }
+});
@@ -0,0 +1,31 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use geo_types::Coord;
+use geotiff::TransformTags;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    pixel_scale: Option<Vec<f64>>,
+    tie_points: Option<Vec<f64>>,
+    model_transformation: Option<Vec<f64>>,
+    invertibility_tolerance: f64,
+    coord: (f64, f64),
+}
+
+fuzz_target!(|input: Input| {
+    let tags = TransformTags {
+        pixel_scale: input.pixel_scale,
+        tie_points: input.tie_points,
+        model_transformation: input.model_transformation,
+    };
+    if let Ok(Some(transform)) = tags.to_coordinate_transform(input.invertibility_tolerance) {
+        let coord = Coord {
+            x: input.coord.0,
+            y: input.coord.1,
+        };
+        let _ = transform.transform_to_model(&coord);
+        let _ = transform.transform_to_raster(&coord);
+    }
+});
@@ -0,0 +1,147 @@
+use geo_types::Coord;
+
+use crate::{GeoKeyDirectory, GeoKeyId, GeoTiff, Image, KeyDiff};
+
+const USER_DEFINED: u16 = 32767;
+
+/// The result of comparing the grids of two images, see [Image::is_aligned_with].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlignmentReport {
+    pub mismatches: Vec<Mismatch>,
+}
+
+/// A difference preventing two grids from being aligned.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch {
+    /// One of the images has no transformation between raster space and model space.
+    MissingTransform,
+    /// The CRS differ, with the differing geo keys if the CRS are not both given by EPSG codes.
+    Crs {
+        ours: Option<u16>,
+        theirs: Option<u16>,
+        diffs: Vec<KeyDiff>,
+    },
+    /// The pixels differ in size or orientation, given by the model space vectors of the pixel
+    /// edges along the raster axes.
+    Resolution {
+        ours: [Coord; 2],
+        theirs: [Coord; 2],
+    },
+    /// The pixel corners of the other image are offset by the given fractions of a pixel, between
+    /// -0.5 and 0.5, from those of this image.
+    GridPhase { offset: Coord },
+}
+
+impl AlignmentReport {
+    pub fn is_aligned(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+impl Image {
+    /// Checks whether the pixels of this image and `other` coincide where they overlap, so that
+    /// they can be combined pixel by pixel.
+    ///
+    /// This requires the same CRS, the same pixel size and orientation, and pixel corners falling
+    /// on each other. The `tolerance` is relative to the pixel size.
+    pub fn is_aligned_with(&self, other: &Image, tolerance: f64) -> AlignmentReport {
+        let mut mismatches = Vec::new();
+
+        let ours = crs_code(self.geo_key_directory());
+        let theirs = crs_code(other.geo_key_directory());
+        if ours.is_none() || ours != theirs {
+            let default = GeoKeyDirectory::default();
+            let diffs = self
+                .geo_key_directory()
+                .unwrap_or(&default)
+                .diff(other.geo_key_directory().unwrap_or(&default))
+                .into_iter()
+                .filter(|diff| {
+                    !matches!(
+                        diff.key(),
+                        GeoKeyId::RasterType
+                            | GeoKeyId::Citation
+                            | GeoKeyId::GeogCitation
+                            | GeoKeyId::ProjCitation
+                            | GeoKeyId::VerticalCitation
+                    )
+                })
+                .collect::<Vec<_>>();
+            if ours != theirs || !diffs.is_empty() {
+                mismatches.push(Mismatch::Crs {
+                    ours,
+                    theirs,
+                    diffs,
+                });
+            }
+        }
+
+        let (Some(transform), Some(other_transform)) =
+            (self.coordinate_transform(), other.coordinate_transform())
+        else {
+            mismatches.push(Mismatch::MissingTransform);
+            return AlignmentReport { mismatches };
+        };
+
+        let origin = |image: &Image| {
+            let offset = image.raster_offset();
+            Coord {
+                x: offset,
+                y: offset,
+            }
+        };
+        let pixel_edges = |image: &Image| {
+            let transform = image.coordinate_transform().unwrap();
+            let origin = origin(image);
+            let corner = transform.transform_to_model(&origin);
+            [(1.0, 0.0), (0.0, 1.0)].map(|(x, y)| {
+                let end = transform.transform_to_model(&Coord {
+                    x: origin.x + x,
+                    y: origin.y + y,
+                });
+                end - corner
+            })
+        };
+        let ours = pixel_edges(self);
+        let theirs = pixel_edges(other);
+        let length = |coord: Coord| coord.x.hypot(coord.y);
+        if (0..2).any(|i| length(ours[i] - theirs[i]) > tolerance * length(ours[i])) {
+            mismatches.push(Mismatch::Resolution { ours, theirs });
+            return AlignmentReport { mismatches };
+        }
+
+        let other_corner = other_transform.transform_to_model(&origin(other));
+        if let Ok(corner) = transform.transform_to_raster(&other_corner) {
+            let corner = corner - origin(self);
+            let offset = Coord {
+                x: corner.x - corner.x.round(),
+                y: corner.y - corner.y.round(),
+            };
+            if offset.x.abs() > tolerance || offset.y.abs() > tolerance {
+                mismatches.push(Mismatch::GridPhase { offset });
+            }
+        }
+
+        AlignmentReport { mismatches }
+    }
+}
+
+impl GeoTiff {
+    /// Checks whether the primary images of this GeoTIFF and `other` are aligned, see
+    /// [Image::is_aligned_with].
+    pub fn is_aligned_with(&self, other: &GeoTiff, tolerance: f64) -> AlignmentReport {
+        self.primary().is_aligned_with(other.primary(), tolerance)
+    }
+}
+
+/// Returns the EPSG code of the CRS, if any.
+fn crs_code(directory: Option<&GeoKeyDirectory>) -> Option<u16> {
+    let directory = directory?;
+    if directory.is_geographic() {
+        directory
+            .geographic_type
+            .filter(|code| *code != USER_DEFINED)
+    } else {
+        directory.infer_epsg().map(|inferred| inferred.code)
+    }
+}
@@ -0,0 +1,100 @@
+use geo_types::{coord, MultiPolygon, Rect};
+
+use crate::{GeoTiff, Image};
+
+/// How to represent the geographic extent of an image crossing the antimeridian, see
+/// [Image::geographic_extent].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AntimeridianPolicy {
+    /// Split the extent into one part on each side of the antimeridian.
+    Split,
+    /// Return a single bounding box whose west edge is east of its east edge when crossing.
+    Crossing,
+}
+
+/// The extent of an image in geographic coordinates, with longitudes in the range [-180, 180].
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeographicExtent {
+    /// One rectangle, or two if the extent crosses the antimeridian.
+    Split(MultiPolygon),
+    /// A bounding box which crosses the antimeridian if `west > east`, following the convention
+    /// of GeoJSON and STAC.
+    Crossing {
+        west: f64,
+        south: f64,
+        east: f64,
+        north: f64,
+    },
+}
+
+impl GeographicExtent {
+    /// Returns whether the extent crosses the antimeridian.
+    pub fn crosses_antimeridian(&self) -> bool {
+        match self {
+            GeographicExtent::Split(polygons) => polygons.0.len() > 1,
+            GeographicExtent::Crossing { west, east, .. } => west > east,
+        }
+    }
+}
+
+impl Image {
+    /// Returns the extent of the image for a geographic CRS, with longitudes wrapped to the range
+    /// [-180, 180] according to the given policy.
+    ///
+    /// The outer bounds of the image are used, in longitude/latitude order regardless of the
+    /// [crate::AxisOrder]. Returns `None` if the CRS is not geographic.
+    pub fn geographic_extent(&self, policy: AntimeridianPolicy) -> Option<GeographicExtent> {
+        if !self.geo_key_directory()?.is_geographic() {
+            return None;
+        }
+
+        let bounds = self.stored_bounds_outer();
+        let (south, north) = (bounds.min().y, bounds.max().y);
+        let mut west = bounds.min().x;
+        let mut east = bounds.max().x;
+
+        if east - west >= 360.0 {
+            west = -180.0;
+            east = 180.0;
+        } else {
+            let shift = ((west + 180.0) / 360.0).floor() * 360.0;
+            west -= shift;
+            east -= shift;
+        }
+        let crosses_antimeridian = east > 180.0;
+
+        Some(match policy {
+            AntimeridianPolicy::Split => {
+                let rect = |west, east| {
+                    Rect::new(coord! { x: west, y: south }, coord! { x: east, y: north })
+                        .to_polygon()
+                };
+                if crosses_antimeridian {
+                    GeographicExtent::Split(MultiPolygon::new(vec![
+                        rect(west, 180.0),
+                        rect(-180.0, east - 360.0),
+                    ]))
+                } else {
+                    GeographicExtent::Split(MultiPolygon::new(vec![rect(west, east)]))
+                }
+            }
+            AntimeridianPolicy::Crossing => GeographicExtent::Crossing {
+                west,
+                south,
+                east: if crosses_antimeridian {
+                    east - 360.0
+                } else {
+                    east
+                },
+                north,
+            },
+        })
+    }
+}
+
+impl GeoTiff {
+    /// See [Image::geographic_extent].
+    pub fn geographic_extent(&self, policy: AntimeridianPolicy) -> Option<GeographicExtent> {
+        self.primary().geographic_extent(policy)
+    }
+}
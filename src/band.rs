@@ -0,0 +1,69 @@
+use tiff::TiffResult;
+
+use crate::{GeoTiff, Image, OutOfBounds, ReadOptions, Window};
+
+/// A single sample of the pixels of an image, the input of the raster operations.
+#[derive(Debug, Clone, Copy)]
+pub struct Band<'a> {
+    image: &'a Image,
+    sample: usize,
+}
+
+impl<'a> Band<'a> {
+    pub fn image(&self) -> &'a Image {
+        self.image
+    }
+
+    pub fn sample(&self) -> usize {
+        self.sample
+    }
+
+    pub fn width(&self) -> usize {
+        self.image.raster_width
+    }
+
+    pub fn height(&self) -> usize {
+        self.image.raster_height
+    }
+
+    pub fn nodata(&self) -> Option<f64> {
+        self.image.nodata()
+    }
+
+    /// Returns whether the value is the nodata value of the band or NaN.
+    pub fn is_nodata(&self, value: f64) -> bool {
+        value.is_nan() || Some(value) == self.nodata()
+    }
+
+    /// Reads the values of the band in the given window, with NaN outside the raster.
+    pub fn read_window(&self, window: Window) -> TiffResult<Vec<f64>> {
+        let options = ReadOptions {
+            out_of_bounds: OutOfBounds::Fill(f64::NAN),
+            ..Default::default()
+        };
+        let data = self.image.read_window::<f64>(window, &options)?;
+        Ok(data
+            .data
+            .into_iter()
+            .skip(self.sample)
+            .step_by(data.num_samples)
+            .collect())
+    }
+}
+
+impl Image {
+    /// Returns the band of the given sample, or `None` if there is no such sample.
+    pub fn band(&self, sample: usize) -> Option<Band<'_>> {
+        (sample < self.num_samples).then_some(Band {
+            image: self,
+            sample,
+        })
+    }
+}
+
+impl GeoTiff {
+    /// See [Image::band].
+    pub fn band(&self, sample: usize) -> Option<Band<'_>> {
+        self.primary().band(sample)
+    }
+}
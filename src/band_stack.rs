@@ -0,0 +1,262 @@
+use geo_types::Coord;
+use num_traits::{Bounded, NumCast};
+use tiff::{TiffError, TiffFormatError, TiffResult};
+
+use crate::{
+    AffineTransform, CoordinateTransform, GeoTiff, Image, Interleave, Mismatch, Orientation,
+    OutOfBounds, ReadOptions, Resampling, Window, WindowData,
+};
+
+/// The relative tolerance on the alignment of the axes of the bands with those of the reference.
+const AXIS_TOLERANCE: f64 = 1e-9;
+
+/// Several single-band GeoTIFFs presented as one multi-band dataset, e.g. the per-band files of
+/// a Sentinel-2 product.
+///
+/// Windows are given on the grid of the stack, by default the grid of the first band, the
+/// reference, see [BandStack::with_grid]. The bands must be in the CRS of the reference with axes
+/// parallel to those of the grid, but may have other resolutions or extents, in which case they
+/// are resampled to the grid when read, e.g. to read 20 m bands along 10 m bands.
+#[derive(Debug)]
+pub struct BandStack {
+    bands: Vec<GeoTiff>,
+    /// The transformation from the pixel corners of the grid, at integer raster coordinates, to
+    /// model space.
+    grid: AffineTransform,
+    grid_size: (usize, usize),
+    /// The mapping from the pixel positions of the grid to those of each band.
+    mappings: Vec<BandMapping>,
+    /// The resampling of each band, overriding the one of the read options.
+    resamplings: Vec<Option<Resampling>>,
+}
+
+/// The affine mapping `band = scale * grid + offset` of pixel positions, axis by axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BandMapping {
+    scale: Coord,
+    offset: Coord,
+}
+
+impl BandStack {
+    /// Creates a stack of the primary images of the given GeoTIFFs, in band order.
+    ///
+    /// Returns an error if there are no bands, if a band has several samples or no affine
+    /// transformation, or if it is not in the CRS of the reference or rotated or flipped
+    /// relative to it.
+    pub fn new(bands: Vec<GeoTiff>) -> TiffResult<Self> {
+        let Some(reference) = bands.first() else {
+            return Err(TiffError::FormatError(TiffFormatError::Format(
+                "A band stack needs at least one band".into(),
+            )));
+        };
+        for (index, band) in bands.iter().enumerate() {
+            if band.primary().num_samples != 1 {
+                return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+                    "Band {} has {} samples instead of 1",
+                    index,
+                    band.primary().num_samples
+                ))));
+            }
+            let report = reference.is_aligned_with(band, AXIS_TOLERANCE);
+            if report
+                .mismatches
+                .iter()
+                .any(|mismatch| matches!(mismatch, Mismatch::Crs { .. }))
+            {
+                return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+                    "Band {index} is not in the CRS of the reference"
+                ))));
+            }
+        }
+
+        let primary = reference.primary();
+        let [a, b, c, d, e, f] = affine_transform(primary, 0)?.affine_terms().unwrap();
+        let offset = primary.raster_offset();
+        #[rustfmt::skip]
+        let grid = AffineTransform::from_tag_matrix([
+            a, b, 0.0, c,
+            d, e, 0.0, f,
+            0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        ])
+        .cropped(offset, offset);
+        let grid_size = (primary.raster_width, primary.raster_height);
+        let resamplings = vec![None; bands.len()];
+        Self {
+            bands,
+            grid,
+            grid_size,
+            mappings: Vec::new(),
+            resamplings,
+        }
+        .with_grid_mappings()
+    }
+
+    /// Replaces the grid of the stack, e.g. by the grid of the finest band or by a common output
+    /// grid, given by the transformation from the pixel corners of the grid, at integer raster
+    /// coordinates, to the model space of the bands, and its size in pixels.
+    ///
+    /// Returns an error if a band is rotated or flipped relative to the grid.
+    pub fn with_grid(
+        self,
+        transform: AffineTransform,
+        width: usize,
+        height: usize,
+    ) -> TiffResult<Self> {
+        Self {
+            grid: transform,
+            grid_size: (width, height),
+            ..self
+        }
+        .with_grid_mappings()
+    }
+
+    fn with_grid_mappings(self) -> TiffResult<Self> {
+        let mappings = self
+            .bands
+            .iter()
+            .enumerate()
+            .map(|(index, band)| BandMapping::between(&self.grid, band.primary(), index))
+            .collect::<TiffResult<_>>()?;
+        Ok(Self { mappings, ..self })
+    }
+
+    /// Sets the resampling of the given band, which overrides [ReadOptions::resampling], e.g.
+    /// [Resampling::Nearest] for a classification band among bands of reflectances.
+    ///
+    /// Panics if there is no such band.
+    pub fn set_resampling(&mut self, band: usize, resampling: Resampling) {
+        self.resamplings[band] = Some(resampling);
+    }
+
+    pub fn num_bands(&self) -> usize {
+        self.bands.len()
+    }
+
+    /// Returns the GeoTIFFs of the bands, in band order.
+    pub fn bands(&self) -> &[GeoTiff] {
+        &self.bands
+    }
+
+    /// Returns the GeoTIFF of the first band, whose grid is the default grid of the stack.
+    pub fn reference(&self) -> &GeoTiff {
+        &self.bands[0]
+    }
+
+    /// Returns the transformation from the pixel corners of the grid to model space.
+    pub fn grid_transform(&self) -> &AffineTransform {
+        &self.grid
+    }
+
+    /// Returns the window covering the whole grid.
+    pub fn window(&self) -> Window {
+        Window::full(self.grid_size.0, self.grid_size.1)
+    }
+
+    /// Returns the size of the pixels of the grid in pixels of the given band, e.g. 0.5 for a
+    /// 20 m band of a stack on a 10 m grid.
+    pub fn band_scale(&self, band: usize) -> Option<Coord> {
+        self.mappings.get(band).map(|mapping| mapping.scale)
+    }
+
+    /// Reads the pixels of the given window of the grid, with one sample per band.
+    ///
+    /// The bands whose pixels differ from those of the grid are resampled as given by
+    /// [BandStack::set_resampling], or else [ReadOptions::resampling], see
+    /// [Image::read_window_decimated], and filled outside of their extent with their own fill
+    /// value. The out of bounds policy of the options applies to the window relative to the grid.
+    pub fn read_window<T: NumCast + Bounded + Copy + 'static>(
+        &self,
+        window: Window,
+        options: &ReadOptions,
+    ) -> TiffResult<WindowData<T>> {
+        let bounds = self.window();
+        let window = match options.out_of_bounds {
+            OutOfBounds::Error if !window.is_within(&bounds) => {
+                return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+                    "Window {:?} is outside of the grid of size {}x{}",
+                    window, bounds.width, bounds.height
+                ))))
+            }
+            OutOfBounds::Clamp => window
+                .intersection(&bounds)
+                .unwrap_or(Window::new(window.x, window.y, 0, 0)),
+            _ => window,
+        };
+        let mut data = Vec::with_capacity(window.width * window.height * self.bands.len());
+        for ((band, mapping), resampling) in
+            self.bands.iter().zip(&self.mappings).zip(&self.resamplings)
+        {
+            let image = band.primary();
+            let options = ReadOptions {
+                resampling: resampling.as_ref().unwrap_or(&options.resampling).clone(),
+                ..options.clone()
+            };
+            let (_, fill) = image.resolve_window::<T>(Window::new(0, 0, 0, 0), &options)?;
+            let area = [
+                mapping.scale.x * window.x as f64 + mapping.offset.x,
+                mapping.scale.y * window.y as f64 + mapping.offset.y,
+                mapping.scale.x * window.width as f64,
+                mapping.scale.y * window.height as f64,
+            ];
+            data.extend(image.sample(area, window.width, window.height, fill, &options)?);
+        }
+        Ok(WindowData {
+            window,
+            width: window.width,
+            height: window.height,
+            num_samples: self.bands.len(),
+            interleave: Interleave::Band,
+            orientation: Orientation::default(),
+            data,
+        }
+        .with_interleave(options.interleave)
+        .with_orientation(options.orientation))
+    }
+}
+
+impl BandMapping {
+    /// Computes the mapping from the pixel positions of the grid to those of the band.
+    fn between(grid: &AffineTransform, band: &Image, index: usize) -> TiffResult<Self> {
+        let band_transform = affine_transform(band, index)?;
+        let to_band = |x: f64, y: f64| {
+            let model = grid.to_model(&Coord { x, y });
+            let raster = band_transform.transform_to_raster(&model)?;
+            let offset = band.raster_offset();
+            Ok::<_, TiffError>(Coord {
+                x: raster.x - offset,
+                y: raster.y - offset,
+            })
+        };
+        let origin = to_band(0.0, 0.0)?;
+        let x_axis = to_band(1.0, 0.0)? - origin;
+        let y_axis = to_band(0.0, 1.0)? - origin;
+        if x_axis.y.abs() > AXIS_TOLERANCE * x_axis.x.abs()
+            || y_axis.x.abs() > AXIS_TOLERANCE * y_axis.y.abs()
+            || x_axis.x <= 0.0
+            || y_axis.y <= 0.0
+        {
+            return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+                "Band {index} is rotated or flipped relative to the grid"
+            ))));
+        }
+        Ok(Self {
+            scale: Coord {
+                x: x_axis.x,
+                y: y_axis.y,
+            },
+            offset: origin,
+        })
+    }
+}
+
+fn affine_transform(image: &Image, index: usize) -> TiffResult<&CoordinateTransform> {
+    image
+        .coordinate_transform()
+        .filter(|transform| transform.affine_terms().is_some())
+        .ok_or_else(|| {
+            TiffError::FormatError(TiffFormatError::Format(format!(
+                "Band {index} has no affine transformation"
+            )))
+        })
+}
@@ -0,0 +1,192 @@
+use std::io::{Seek, Write};
+
+use num_traits::{Bounded, NumCast};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use tiff::TiffResult;
+
+use crate::{
+    GeoTiff, Image, InMemoryRaster, Orientation, OutOfBounds, ReadOptions, Window, WindowData,
+    WriteOptions,
+};
+
+/// The maximum number of pixels computed at once by [BlockMap].
+const BLOCK_PIXELS: usize = 1 << 20;
+
+/// Options for processing a window block by block, see [Image::process_blocks].
+#[derive(Debug, Clone, Default)]
+pub struct BlockOptions {
+    /// The number of ghost pixels read around each block on each side, from the neighboring
+    /// blocks, e.g. for convolutions to be computed across the boundaries of the blocks.
+    ///
+    /// The ghost pixels outside the raster are filled with the nodata value, or as given by
+    /// [ReadOptions::out_of_bounds] for [OutOfBounds::Fill] and [OutOfBounds::Clamp]. For
+    /// [OutOfBounds::Error], only the window must be within the raster.
+    pub halo: usize,
+    /// Whether the blocks are processed in parallel, with the `rayon` feature. Otherwise, they
+    /// are processed one after the other.
+    pub parallel: bool,
+    /// The options reading the blocks, whose orientation is ignored.
+    pub read_options: ReadOptions,
+}
+
+/// A block of pixels given to the function of [Image::process_blocks].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block<T> {
+    /// The index of the block, in row-major order.
+    pub index: usize,
+    /// The pixels of the block, without the halo.
+    pub window: Window,
+    /// The number of ghost pixels around the block on each side, see [BlockOptions::halo].
+    pub halo: usize,
+    /// The pixels of the block with the halo around it, see [BlockOptions::halo].
+    pub data: WindowData<T>,
+}
+
+impl<T: Copy> Block<T> {
+    /// Returns the value of the given sample at the given coordinates relative to the block,
+    /// which are negative or past the size of the block in the halo. Returns `None` outside the
+    /// data, e.g. for pixels of the halo outside the raster with [OutOfBounds::Clamp], or beyond
+    /// the halo.
+    pub fn get(&self, x: i64, y: i64, sample: usize) -> Option<T> {
+        let x = self.window.x + x - self.data.window.x;
+        let y = self.window.y + y - self.data.window.y;
+        let inside = (0..self.data.width as i64).contains(&x)
+            && (0..self.data.height as i64).contains(&y)
+            && sample < self.data.num_samples;
+        inside.then(|| self.data.get(x as usize, y as usize, sample))
+    }
+}
+
+/// A band computed block by block on the grid of an image, e.g. the result of a raster
+/// operation, whose nodata value is NaN.
+pub(crate) struct BlockMap<'a, F> {
+    image: &'a Image,
+    /// Returns the values of the pixels of the window, which lies within the raster, in
+    /// row-major order.
+    func: F,
+}
+
+impl<'a, F: FnMut(Window) -> TiffResult<Vec<f64>>> BlockMap<'a, F> {
+    pub(crate) fn new(image: &'a Image, func: F) -> Self {
+        Self { image, func }
+    }
+
+    /// Computes the band over the windows of [Image::optimal_read_windows], so that the blocks
+    /// of the image are read once.
+    pub(crate) fn collect(mut self) -> TiffResult<InMemoryRaster> {
+        let (width, height) = (self.image.raster_width, self.image.raster_height);
+        let mut data = vec![f64::NAN; width * height];
+        let full = Window::full(width, height);
+        for window in self.image.optimal_read_windows(full, BLOCK_PIXELS) {
+            let values = (self.func)(window)?;
+            for (y, row) in values.chunks_exact(window.width).enumerate() {
+                let start = (window.y as usize + y) * width + window.x as usize;
+                data[start..start + window.width].copy_from_slice(row);
+            }
+        }
+        Ok(InMemoryRaster::with_grid_of(
+            self.image,
+            data,
+            Some(f64::NAN),
+        ))
+    }
+
+    /// Writes the band as a GeoTIFF like [InMemoryRaster::write_with_options], computing the
+    /// rows of the chunks while they are written, so that the band is never held in memory.
+    ///
+    /// Fails if a mask or overviews are requested.
+    pub(crate) fn write<W: Write + Seek>(
+        mut self,
+        writer: W,
+        options: &WriteOptions,
+    ) -> TiffResult<()> {
+        let width = self.image.raster_width;
+        InMemoryRaster::grid_of(self.image, Some(f64::NAN)).write_rows(
+            writer,
+            &mut |rows| {
+                let window = Window::new(0, rows.start as i64, width, rows.len());
+                (self.func)(window)
+            },
+            options,
+        )
+    }
+}
+
+impl Image {
+    /// Calls `func` on the blocks covering the part of the window within the raster, and returns
+    /// the results in the order of the blocks, e.g. to run custom algorithms on large rasters.
+    ///
+    /// The blocks are the windows of [Image::optimal_read_windows] covering at most `max_pixels`
+    /// each, clipped to the window, so that their boundaries within the window are those of the
+    /// tiles or strips. Each block is read with a halo of pixels around it, converted to `T`.
+    pub fn process_blocks<T, R, F>(
+        &self,
+        window: Window,
+        max_pixels: usize,
+        options: &BlockOptions,
+        func: F,
+    ) -> TiffResult<Vec<R>>
+    where
+        T: NumCast + Bounded + Copy + Send + 'static,
+        R: Send,
+        F: Fn(Block<T>) -> R + Send + Sync,
+    {
+        let windows = self
+            .optimal_read_windows(window, max_pixels)
+            .into_iter()
+            .filter_map(|block| block.intersection(&window))
+            .collect::<Vec<_>>();
+        // The window is checked first, since the ghost pixels may lie outside the raster
+        self.resolve_window::<T>(window, &options.read_options)?;
+        let out_of_bounds = match options.read_options.out_of_bounds {
+            OutOfBounds::Error => OutOfBounds::FillNodata,
+            out_of_bounds => out_of_bounds,
+        };
+        let read_options = ReadOptions {
+            out_of_bounds,
+            orientation: Orientation::default(),
+            ..options.read_options.clone()
+        };
+        let halo = options.halo;
+        let process = |(index, &window): (usize, &Window)| {
+            let padded = Window::new(
+                window.x - halo as i64,
+                window.y - halo as i64,
+                window.width + 2 * halo,
+                window.height + 2 * halo,
+            );
+            let data = self.read_window(padded, &read_options)?;
+            Ok(func(Block {
+                index,
+                window,
+                halo,
+                data,
+            }))
+        };
+        #[cfg(feature = "rayon")]
+        if options.parallel {
+            return windows.par_iter().enumerate().map(process).collect();
+        }
+        windows.iter().enumerate().map(process).collect()
+    }
+}
+
+impl GeoTiff {
+    /// See [Image::process_blocks].
+    pub fn process_blocks<T, R, F>(
+        &self,
+        window: Window,
+        max_pixels: usize,
+        options: &BlockOptions,
+        func: F,
+    ) -> TiffResult<Vec<R>>
+    where
+        T: NumCast + Bounded + Copy + Send + 'static,
+        R: Send,
+        F: Fn(Block<T>) -> R + Send + Sync,
+    {
+        self.primary()
+            .process_blocks(window, max_pixels, options, func)
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{GeoKeyDirectory, GeoTiff};
+
+const COMPRESSION_NONE: u16 = 1;
+const PREDICTOR_NONE: u16 = 1;
+
+/// Compression schemes the underlying `tiff` decoder can decompress.
+const SUPPORTED_COMPRESSIONS: [u16; 6] = [
+    COMPRESSION_NONE,
+    5,     // LZW
+    7,     // JPEG
+    8,     // Deflate
+    32773, // PackBits
+    32946, // Deflate (obsolete code)
+];
+
+/// Predictors the underlying `tiff` decoder can undo.
+const SUPPORTED_PREDICTORS: [u16; 3] = [
+    PREDICTOR_NONE,
+    2, // Horizontal differencing
+    3, // Floating point
+];
+
+/// Describes what this crate can do with a GeoTIFF, see [GeoTiff::capabilities].
+///
+/// Compression and predictor refer to the primary image and hold the raw tag values so that
+/// unsupported schemes can still be reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Value of the Compression tag.
+    pub compression: u16,
+    pub compression_supported: bool,
+    /// Value of the Predictor tag.
+    pub predictor: u16,
+    pub predictor_supported: bool,
+    /// Whether the raster data is organized in tiles rather than strips.
+    pub tiled: bool,
+    /// Number of reduced resolution images.
+    pub overviews: usize,
+    /// Indices of the images whose raster data could not be decoded, e.g. complex values.
+    pub undecodable_images: Vec<usize>,
+    /// Whether the CRS of the primary image can be resolved.
+    ///
+    /// With the `proj4rs` feature, this means a projection can be built from the geo keys.
+    /// Otherwise, the geo keys need to reference the CRS by an EPSG code.
+    pub crs_resolvable: bool,
+}
+
+impl Capabilities {
+    /// Returns whether the file can be fully handled by this crate.
+    pub fn is_fully_supported(&self) -> bool {
+        self.compression_supported
+            && self.predictor_supported
+            && self.undecodable_images.is_empty()
+            && self.crs_resolvable
+    }
+}
+
+impl GeoTiff {
+    /// Reports what this crate can do with this file.
+    pub fn capabilities(&self) -> Capabilities {
+        let primary = self.primary();
+        Capabilities {
+            compression: primary.compression,
+            compression_supported: SUPPORTED_COMPRESSIONS.contains(&primary.compression),
+            predictor: primary.predictor,
+            predictor_supported: SUPPORTED_PREDICTORS.contains(&primary.predictor),
+            tiled: primary.tiled,
+            overviews: self.overviews().count(),
+            undecodable_images: self
+                .images()
+                .iter()
+                .filter(|image| !image.has_raster_data() && image.complex_data.is_none())
+                .map(|image| image.index)
+                .collect(),
+            crs_resolvable: primary.geo_key_directory().is_some_and(crs_resolvable),
+        }
+    }
+}
+
+#[cfg(feature = "proj4rs")]
+fn crs_resolvable(geo_key_directory: &GeoKeyDirectory) -> bool {
+    geo_key_directory.to_proj4rs().is_ok()
+}
+
+#[cfg(not(feature = "proj4rs"))]
+fn crs_resolvable(geo_key_directory: &GeoKeyDirectory) -> bool {
+    const USER_DEFINED: u16 = 32767;
+    geo_key_directory
+        .projected_type
+        .or(geo_key_directory.geographic_type)
+        .is_some_and(|code| code != USER_DEFINED)
+}
@@ -0,0 +1,112 @@
+use std::io::{Read, Seek};
+use std::ops::Range;
+
+use tiff::decoder::Decoder;
+use tiff::tags::Tag;
+use tiff::{TiffError, TiffFormatError, TiffResult};
+
+use crate::{GeoTiff, Image, Window};
+
+/// The byte ranges of the chunks, i.e. tiles or strips, of an image in its file, read when the
+/// file is opened with [crate::OpenOptions::preload_index].
+///
+/// The chunks are indexed in row-major order, one plane after the other for images with
+/// separate planes of samples.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkIndex {
+    pub chunk_width: usize,
+    pub chunk_height: usize,
+    /// The number of chunks in a row of chunks.
+    pub chunks_across: usize,
+    /// The number of rows of chunks.
+    pub chunks_down: usize,
+    pub offsets: Vec<u64>,
+    pub byte_counts: Vec<u64>,
+}
+
+impl ChunkIndex {
+    /// Reads the offsets and byte counts of the chunks of the current IFD of the decoder.
+    pub(crate) fn read<R: Read + Seek>(
+        decoder: &mut Decoder<R>,
+        tiled: bool,
+        raster_size: (usize, usize),
+        chunk_size: (usize, usize),
+    ) -> TiffResult<Self> {
+        let (offsets_tag, byte_counts_tag) = if tiled {
+            (Tag::TileOffsets, Tag::TileByteCounts)
+        } else {
+            (Tag::StripOffsets, Tag::StripByteCounts)
+        };
+        let offsets = decoder.get_tag_u64_vec(offsets_tag)?;
+        let byte_counts = decoder.get_tag_u64_vec(byte_counts_tag)?;
+        let (chunk_width, chunk_height) = (chunk_size.0.max(1), chunk_size.1.max(1));
+        let chunks_across = raster_size.0.div_ceil(chunk_width);
+        let chunks_down = raster_size.1.div_ceil(chunk_height);
+        let per_plane = chunks_across * chunks_down;
+        if offsets.len() != byte_counts.len()
+            || per_plane == 0
+            || !offsets.len().is_multiple_of(per_plane)
+        {
+            return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+                "The image has {} chunk offsets and {} byte counts for {} chunks per plane",
+                offsets.len(),
+                byte_counts.len(),
+                per_plane
+            ))));
+        }
+        Ok(Self {
+            chunk_width,
+            chunk_height,
+            chunks_across,
+            chunks_down,
+            offsets,
+            byte_counts,
+        })
+    }
+
+    /// Returns the range of bytes of the chunk with the given index in the file.
+    pub fn byte_range(&self, chunk: usize) -> Option<Range<u64>> {
+        let offset = *self.offsets.get(chunk)?;
+        Some(offset..offset + self.byte_counts[chunk])
+    }
+
+    /// Returns the indices of the chunks intersecting the window, in all the planes, in
+    /// row-major order.
+    pub fn chunks_in_window(&self, window: Window) -> Vec<usize> {
+        let raster = Window::full(
+            self.chunks_across * self.chunk_width,
+            self.chunks_down * self.chunk_height,
+        );
+        let Some(window) = window.intersection(&raster) else {
+            return Vec::new();
+        };
+        let (x, y) = (window.x as usize, window.y as usize);
+        let columns = x / self.chunk_width..(x + window.width).div_ceil(self.chunk_width);
+        let rows = y / self.chunk_height..(y + window.height).div_ceil(self.chunk_height);
+        let per_plane = self.chunks_across * self.chunks_down;
+        let mut chunks = Vec::new();
+        for plane in 0..self.offsets.len() / per_plane {
+            for row in rows.clone() {
+                for column in columns.clone() {
+                    chunks.push(plane * per_plane + row * self.chunks_across + column);
+                }
+            }
+        }
+        chunks
+    }
+}
+
+impl Image {
+    /// Returns the byte ranges of the chunks of this image, if the file was opened with
+    /// [crate::OpenOptions::preload_index].
+    pub fn chunk_index(&self) -> Option<&ChunkIndex> {
+        self.chunk_index.as_ref()
+    }
+}
+
+impl GeoTiff {
+    /// See [Image::chunk_index].
+    pub fn chunk_index(&self) -> Option<&ChunkIndex> {
+        self.primary().chunk_index()
+    }
+}
@@ -0,0 +1,733 @@
+//! Decoding of the raster data chunk by chunk through reusable buffers, including the sample
+//! types which are not supported by the tiff crate, i.e. 1-bit and 12-bit unsigned values, and
+//! complex values.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use flate2::{Decompress, FlushDecompress, Status};
+use tiff::decoder::{ChunkType, Decoder, Limits};
+use tiff::tags::{CompressionMethod, PhotometricInterpretation, Tag};
+use tiff::{TiffError, TiffFormatError, TiffResult, TiffUnsupportedError};
+
+use crate::raster_data::RasterData;
+use crate::{DType, Window};
+
+thread_local! {
+    /// The context of the reads of each thread which are not given one.
+    static THREAD_CONTEXT: RefCell<DecodeContext> = RefCell::new(DecodeContext::new());
+}
+
+/// The compressions which [DecodeContext] decompresses, the others being left to the tiff crate.
+const COMPRESSIONS: [u16; 5] = [1, 5, 8, 32773, 32946];
+
+/// A reader shared by the decoder of the tiff crate, which reads the tags, and the decoding of the
+/// chunks, which reads the bytes of each chunk at once into the buffers of the context.
+pub(crate) struct SharedReader<R>(Rc<RefCell<R>>);
+
+impl<R: Read + Seek> SharedReader<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self(Rc::new(RefCell::new(reader)))
+    }
+
+    /// Returns whether the file is big-endian, which the decoder does not expose.
+    pub(crate) fn is_big_endian(&self) -> io::Result<bool> {
+        let mut order = [0; 1];
+        self.read_exact_at(0, &mut order)?;
+        Ok(&order == b"M")
+    }
+
+    /// Fills the buffer with the bytes at the given offset.
+    fn read_exact_at(&self, offset: u64, buffer: &mut [u8]) -> io::Result<()> {
+        let mut reader = self.0.borrow_mut();
+        reader.seek(SeekFrom::Start(offset))?;
+        reader.read_exact(buffer)
+    }
+}
+
+impl<R> Clone for SharedReader<R> {
+    fn clone(&self) -> Self {
+        Self(Rc::clone(&self.0))
+    }
+}
+
+impl<R: Read> Read for SharedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.borrow_mut().read(buf)
+    }
+}
+
+impl<R: Seek> Seek for SharedReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.0.borrow_mut().seek(pos)
+    }
+}
+
+/// The scratch buffers and decompressors reused from one chunk to the next when decoding the
+/// raster data, see [crate::OpenOptions::read_with_context].
+///
+/// The buffers grow to the size of the largest chunk decoded and are kept for the next reads,
+/// so that long-running readers, e.g. servers, do not allocate for each chunk. The reads without
+/// a context use one per thread. The chunks of the compressions which this crate does not
+/// decompress itself, e.g. JPEG, are decoded by the tiff crate instead.
+pub struct DecodeContext {
+    compressed: Vec<u8>,
+    decompressed: Vec<u8>,
+    lzw: weezl::decode::Decoder,
+    deflate: Decompress,
+}
+
+impl DecodeContext {
+    pub fn new() -> Self {
+        Self {
+            compressed: Vec::new(),
+            decompressed: Vec::new(),
+            lzw: weezl::decode::Decoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8),
+            deflate: Decompress::new(true),
+        }
+    }
+
+    /// Calls `f` with the context of the current thread, or with a new context if it is in use.
+    pub(crate) fn with_thread_context<T>(f: impl FnOnce(&mut DecodeContext) -> T) -> T {
+        THREAD_CONTEXT.with(|context| match context.try_borrow_mut() {
+            Ok(mut context) => f(&mut context),
+            Err(_) => f(&mut DecodeContext::new()),
+        })
+    }
+
+    /// Reads the bytes of a chunk and decompresses them. Fails if they decompress to more than
+    /// `max_len` bytes, the size of the chunk, rather than growing the buffer without limit.
+    fn decompress<R: Read + Seek>(
+        &mut self,
+        reader: &SharedReader<R>,
+        offset: u64,
+        byte_count: u64,
+        compression: u16,
+        max_len: usize,
+    ) -> TiffResult<&mut [u8]> {
+        self.compressed.resize(chunk_len(byte_count)?, 0);
+        reader.read_exact_at(offset, &mut self.compressed)?;
+        let raw = &self.compressed[..];
+        let bytes = &mut self.decompressed;
+        // One more byte than the chunk to detect the streams which decompress to more
+        bytes.resize(max_len + 1, 0);
+        let len = match compression {
+            1 => return Ok(&mut self.compressed[..]),
+            5 => {
+                self.lzw.reset();
+                decode_lzw(&mut self.lzw, raw, bytes)?
+            }
+            8 | 32946 => inflate(&mut self.deflate, raw, bytes)?,
+            32773 => unpack_bits(raw, bytes),
+            _ => {
+                return Err(TiffError::UnsupportedError(
+                    TiffUnsupportedError::UnsupportedCompressionMethod(
+                        CompressionMethod::from_u16_exhaustive(compression),
+                    ),
+                ))
+            }
+        };
+        if len > max_len {
+            return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+                "A chunk decompresses to more than its {} bytes",
+                max_len
+            ))));
+        }
+        Ok(&mut bytes[..len])
+    }
+}
+
+impl Default for DecodeContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for DecodeContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DecodeContext")
+            .field("compressed_capacity", &self.compressed.capacity())
+            .field("decompressed_capacity", &self.decompressed.capacity())
+            .finish()
+    }
+}
+
+/// The tags of the current IFD of a decoder describing how its samples are encoded.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ChunkFormat {
+    /// The type of all the samples.
+    pub dtype: DType,
+    pub num_samples: usize,
+    pub compression: u16,
+    pub predictor: u16,
+    pub photometric_interpretation: Option<PhotometricInterpretation>,
+}
+
+impl ChunkFormat {
+    /// Returns whether the samples are decoded by [decode_window], rather than by the tiff crate,
+    /// e.g. for JPEG or YCbCr. Complex values are decoded by [read_complex] instead.
+    pub(crate) fn is_decodable(&self) -> bool {
+        let predictor_supported = match self.predictor {
+            1 => true,
+            2 => !self.dtype.is_float() && !self.dtype.is_packed(),
+            3 => self.dtype.is_float(),
+            _ => false,
+        };
+        COMPRESSIONS.contains(&self.compression)
+            && predictor_supported
+            && self.photometric_interpretation != Some(PhotometricInterpretation::YCbCr)
+            && !self.dtype.is_complex()
+            && !matches!(self.dtype, DType::F16 | DType::Other { .. })
+    }
+}
+
+/// The chunks, i.e. strips or tiles, of an image.
+#[derive(Debug)]
+pub(crate) struct Chunks {
+    width: usize,
+    height: usize,
+    chunk_width: usize,
+    chunk_height: usize,
+    /// The number of samples per pixel in a chunk, which is one for planar images.
+    chunk_samples: usize,
+    planar: bool,
+    offsets: Vec<u64>,
+    byte_counts: Vec<u64>,
+}
+
+impl Chunks {
+    /// Returns the chunks of the current IFD of the decoder.
+    pub(crate) fn new<R: Read + Seek>(
+        decoder: &mut Decoder<R>,
+        num_samples: usize,
+    ) -> TiffResult<Self> {
+        let (width, height) = decoder.dimensions()?;
+        let planar = match decoder.find_tag(Tag::PlanarConfiguration)? {
+            Some(value) => value.into_u16()? == 2,
+            None => false,
+        };
+        let (offsets_tag, byte_counts_tag) = match decoder.get_chunk_type() {
+            ChunkType::Strip => (Tag::StripOffsets, Tag::StripByteCounts),
+            ChunkType::Tile => (Tag::TileOffsets, Tag::TileByteCounts),
+        };
+        let (chunk_width, chunk_height) = decoder.chunk_dimensions();
+        Ok(Self {
+            width: width as usize,
+            height: height as usize,
+            chunk_width: chunk_width as usize,
+            chunk_height: chunk_height as usize,
+            chunk_samples: if planar { 1 } else { num_samples },
+            planar,
+            offsets: decoder.get_tag_u64_vec(offsets_tag)?,
+            byte_counts: decoder.get_tag_u64_vec(byte_counts_tag)?,
+        })
+    }
+
+    /// Returns the window covering the whole raster.
+    fn window(&self) -> Window {
+        Window::full(self.width, self.height)
+    }
+
+    /// Returns the number of bytes of a row of a chunk.
+    fn row_bytes(&self, bits: usize) -> usize {
+        (self.chunk_width * self.chunk_samples * bits).div_ceil(8)
+    }
+
+    /// Decompresses the chunks intersecting the window in turn, calling `f` with the bytes of
+    /// each chunk and the position of its samples in the window, given as a function of the
+    /// pixel within the chunk and the sample within the pixel. Pixels of the padding of the chunk
+    /// or outside the window are skipped.
+    ///
+    /// The window must lie within the raster.
+    fn read<R: Read + Seek, F>(
+        &self,
+        reader: &SharedReader<R>,
+        format: &ChunkFormat,
+        window: Window,
+        context: &mut DecodeContext,
+        mut f: F,
+    ) -> TiffResult<()>
+    where
+        F: FnMut(&mut [u8], &mut dyn Iterator<Item = ((usize, usize, usize), usize)>),
+    {
+        if window.is_empty() {
+            return Ok(());
+        }
+        let num_samples = format.num_samples;
+        let chunks_across = self.width.div_ceil(self.chunk_width);
+        let chunks_per_plane = chunks_across * self.height.div_ceil(self.chunk_height);
+        let planes = if self.planar { num_samples } else { 1 };
+        let chunk_bytes =
+            self.row_bytes(format.dtype.bits_per_sample() as usize) * self.chunk_height;
+        let (x, y) = (window.x as usize, window.y as usize);
+        let (x_end, y_end) = (x + window.width, y + window.height);
+        for plane in 0..planes {
+            for chunk_y in y / self.chunk_height..y_end.div_ceil(self.chunk_height) {
+                for chunk_x in x / self.chunk_width..x_end.div_ceil(self.chunk_width) {
+                    let chunk = plane * chunks_per_plane + chunk_y * chunks_across + chunk_x;
+                    let (Some(offset), Some(byte_count)) =
+                        (self.offsets.get(chunk), self.byte_counts.get(chunk))
+                    else {
+                        continue;
+                    };
+                    let x0 = chunk_x * self.chunk_width;
+                    let y0 = chunk_y * self.chunk_height;
+                    let bytes = context.decompress(
+                        reader,
+                        *offset,
+                        *byte_count,
+                        format.compression,
+                        chunk_bytes,
+                    )?;
+
+                    let rows = y.max(y0) - y0..y_end.min(y0 + self.chunk_height) - y0;
+                    let columns = x.max(x0) - x0..x_end.min(x0 + self.chunk_width) - x0;
+                    let mut positions = rows.flat_map(|row| {
+                        columns.clone().flat_map(move |column| {
+                            (0..self.chunk_samples).map(move |s| {
+                                let sample = if self.planar { plane } else { s };
+                                (
+                                    (row, column, s),
+                                    ((y0 + row - y) * window.width + x0 + column - x) * num_samples
+                                        + sample,
+                                )
+                            })
+                        })
+                    });
+                    f(bytes, &mut positions);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Decodes the samples of whole bytes of the window, converted from their bits in the byte
+    /// order of the host by `from_bits`, after undoing the predictor of the chunks.
+    fn decode<R: Read + Seek, T: Copy + Default>(
+        &self,
+        reader: &SharedReader<R>,
+        format: &ChunkFormat,
+        big_endian: bool,
+        window: Window,
+        context: &mut DecodeContext,
+        from_bits: fn(u64) -> T,
+    ) -> TiffResult<Vec<T>> {
+        let sample_bytes = format.dtype.bits_per_sample() as usize / 8;
+        let row_bytes = self.row_bytes(8 * sample_bytes);
+        let samples = self.chunk_samples;
+        // The floating point predictor stores the bytes of the values from the most significant
+        // one, whatever the byte order
+        let big_endian = big_endian || format.predictor == 3;
+        // Allocated once for all the chunks
+        let mut planes = Vec::new();
+        let mut data = vec![T::default(); window.width * window.height * format.num_samples];
+        self.read(reader, format, window, context, |bytes, positions| {
+            for row in bytes.chunks_mut(row_bytes) {
+                match format.predictor {
+                    2 => undo_differencing(row, sample_bytes, samples, big_endian),
+                    3 => {
+                        undo_differencing(row, 1, samples, false);
+                        planes.clear();
+                        planes.extend_from_slice(row);
+                        let count = row.len() / sample_bytes;
+                        for (i, value) in row.chunks_exact_mut(sample_bytes).enumerate() {
+                            for (plane, byte) in value.iter_mut().enumerate() {
+                                *byte = planes[plane * count + i];
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            for ((y, x, s), target) in positions {
+                let start = y * row_bytes + (x * samples + s) * sample_bytes;
+                if let Some(value) = bytes.get(start..start + sample_bytes) {
+                    data[target] = from_bits(sample_bits(value, big_endian));
+                }
+            }
+        })?;
+        Ok(data)
+    }
+}
+
+/// The chunks of an image whose raster data is decoded on demand, one window at a time, because
+/// it does not fit in the memory budget, see [crate::OpenOptions::memory_budget].
+#[derive(Debug)]
+pub(crate) struct LazyChunks {
+    source: ChunkSource,
+    chunks: Chunks,
+    format: ChunkFormat,
+    big_endian: bool,
+}
+
+/// Where the bytes of [LazyChunks] are read from.
+enum ChunkSource {
+    /// The file which the image was read from, opened again for each read.
+    File(PathBuf),
+    /// The compressed chunks, copied from a reader which is not kept, with the offsets of the
+    /// chunks rebased on them.
+    Bytes(Vec<u8>),
+}
+
+impl fmt::Debug for ChunkSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChunkSource::File(path) => f.debug_tuple("File").field(path).finish(),
+            ChunkSource::Bytes(bytes) => f.debug_tuple("Bytes").field(&bytes.len()).finish(),
+        }
+    }
+}
+
+impl LazyChunks {
+    /// Keeps the chunks of an image read from the file at the given path, if any, or copies
+    /// their compressed bytes from the reader otherwise.
+    pub(crate) fn new<R: Read + Seek>(
+        reader: &SharedReader<R>,
+        path: Option<&Path>,
+        mut chunks: Chunks,
+        format: ChunkFormat,
+    ) -> TiffResult<Self> {
+        let big_endian = reader.is_big_endian()?;
+        let source = match path {
+            Some(path) => ChunkSource::File(path.to_path_buf()),
+            None => {
+                let mut bytes = Vec::new();
+                for (offset, byte_count) in chunks.offsets.iter_mut().zip(&chunks.byte_counts) {
+                    let start = bytes.len();
+                    bytes.resize(start + chunk_len(*byte_count)?, 0);
+                    reader.read_exact_at(*offset, &mut bytes[start..])?;
+                    *offset = start as u64;
+                }
+                ChunkSource::Bytes(bytes)
+            }
+        };
+        Ok(Self {
+            source,
+            chunks,
+            format,
+            big_endian,
+        })
+    }
+
+    /// Decodes the samples of the pixels of the window, which must lie within the raster, with
+    /// the context of the current thread.
+    pub(crate) fn decode(&self, window: Window) -> TiffResult<RasterData> {
+        DecodeContext::with_thread_context(|context| match &self.source {
+            ChunkSource::File(path) => {
+                let reader = SharedReader::new(File::open(path)?);
+                self.decode_with(&reader, window, context)
+            }
+            ChunkSource::Bytes(bytes) => {
+                let reader = SharedReader::new(Cursor::new(&bytes[..]));
+                self.decode_with(&reader, window, context)
+            }
+        })
+    }
+
+    fn decode_with<R: Read + Seek>(
+        &self,
+        reader: &SharedReader<R>,
+        window: Window,
+        context: &mut DecodeContext,
+    ) -> TiffResult<RasterData> {
+        let Self {
+            chunks,
+            format,
+            big_endian,
+            ..
+        } = self;
+        decode_window(chunks, reader, format, *big_endian, window, context)
+    }
+}
+
+/// Decodes the samples of the pixels of the window, which must lie within the raster, for the
+/// formats which are [ChunkFormat::is_decodable]. The samples are interleaved.
+pub(crate) fn decode_window<R: Read + Seek>(
+    chunks: &Chunks,
+    reader: &SharedReader<R>,
+    format: &ChunkFormat,
+    big_endian: bool,
+    window: Window,
+    context: &mut DecodeContext,
+) -> TiffResult<RasterData> {
+    match format.dtype {
+        DType::Bit | DType::U12 => read_packed(chunks, reader, format, window, context),
+        _ => read_samples(chunks, reader, format, big_endian, window, context),
+    }
+}
+
+/// Decodes the samples of the window, which all have the given packed type. The samples are
+/// interleaved as for the other types.
+fn read_packed<R: Read + Seek>(
+    chunks: &Chunks,
+    reader: &SharedReader<R>,
+    format: &ChunkFormat,
+    window: Window,
+    context: &mut DecodeContext,
+) -> TiffResult<RasterData> {
+    let bits = format.dtype.bits_per_sample() as usize;
+    let max_value = (1u16 << bits) - 1;
+    let white_is_zero =
+        format.photometric_interpretation == Some(PhotometricInterpretation::WhiteIsZero);
+
+    let row_bytes = chunks.row_bytes(bits);
+    let mut data = vec![0u16; window.width * window.height * format.num_samples];
+    chunks.read(reader, format, window, context, |bytes, positions| {
+        for ((y, x, s), target) in positions {
+            let row = &bytes[(y * row_bytes).min(bytes.len())..];
+            let value = unpack(row, (x * chunks.chunk_samples + s) * bits, bits);
+            data[target] = if white_is_zero {
+                max_value - value
+            } else {
+                value
+            };
+        }
+    })?;
+
+    Ok(match format.dtype {
+        DType::U12 => RasterData::U16(data),
+        _ => RasterData::U8(data.into_iter().map(|value| value as u8).collect()),
+    })
+}
+
+/// Decodes the raster data of an image whose samples all have the given complex type, as
+/// interleaved pairs of real and imaginary parts.
+///
+/// Returns `None` if the compression or the predictor is not supported.
+pub(crate) fn read_complex<R: Read + Seek>(
+    chunks: &Chunks,
+    reader: &SharedReader<R>,
+    format: &ChunkFormat,
+    context: &mut DecodeContext,
+) -> TiffResult<Option<RasterData>> {
+    if format.predictor != 1 || !COMPRESSIONS.contains(&format.compression) {
+        return Ok(None);
+    }
+    let big_endian = reader.is_big_endian()?;
+
+    let dtype = format.dtype;
+    let part_bytes = dtype.bits_per_sample() as usize / 16;
+    let row_bytes = chunks.row_bytes(dtype.bits_per_sample() as usize);
+    let mut parts = vec![0u8; chunks.width * chunks.height * format.num_samples * 2 * part_bytes];
+    chunks.read(
+        reader,
+        format,
+        chunks.window(),
+        context,
+        |bytes, positions| {
+            let sample_bytes = 2 * part_bytes;
+            for ((y, x, s), target) in positions {
+                let start = y * row_bytes + (x * chunks.chunk_samples + s) * sample_bytes;
+                let Some(source) = bytes.get(start..start + sample_bytes) else {
+                    continue;
+                };
+                let target = &mut parts[target * sample_bytes..(target + 1) * sample_bytes];
+                target.copy_from_slice(source);
+                if big_endian {
+                    target[..part_bytes].reverse();
+                    target[part_bytes..].reverse();
+                }
+            }
+        },
+    )?;
+
+    Ok(Some(match dtype {
+        DType::ComplexI16 => RasterData::I16(from_le_bytes(&parts, i16::from_le_bytes)),
+        DType::ComplexI32 => RasterData::I32(from_le_bytes(&parts, i32::from_le_bytes)),
+        DType::ComplexF32 => RasterData::F32(from_le_bytes(&parts, f32::from_le_bytes)),
+        DType::ComplexF64 => RasterData::F64(from_le_bytes(&parts, f64::from_le_bytes)),
+        _ => return Ok(None),
+    }))
+}
+
+/// Decodes the samples of the window, which all have the given type of whole bytes, e.g. 16-bit
+/// integers, like the tiff crate but through the buffers of the context. Gray values are
+/// inverted for WhiteIsZero, like the tiff crate does.
+fn read_samples<R: Read + Seek>(
+    chunks: &Chunks,
+    reader: &SharedReader<R>,
+    format: &ChunkFormat,
+    big_endian: bool,
+    window: Window,
+    context: &mut DecodeContext,
+) -> TiffResult<RasterData> {
+    macro_rules! decode {
+        ($variant: ident, $from_bits: expr) => {
+            RasterData::$variant(
+                chunks.decode(reader, format, big_endian, window, context, $from_bits)?,
+            )
+        };
+    }
+    let data = match format.dtype {
+        DType::U8 => decode!(U8, |bits| bits as u8),
+        DType::U16 => decode!(U16, |bits| bits as u16),
+        DType::U32 => decode!(U32, |bits| bits as u32),
+        DType::U64 => decode!(U64, |bits| bits),
+        DType::I8 => decode!(I8, |bits| bits as i8),
+        DType::I16 => decode!(I16, |bits| bits as i16),
+        DType::I32 => decode!(I32, |bits| bits as i32),
+        DType::I64 => decode!(I64, |bits| bits as i64),
+        DType::F32 => decode!(F32, |bits| f32::from_bits(bits as u32)),
+        DType::F64 => decode!(F64, f64::from_bits),
+        _ => {
+            return Err(TiffError::UnsupportedError(
+                TiffUnsupportedError::UnsupportedDataType,
+            ))
+        }
+    };
+
+    let gray = format.num_samples == 1;
+    let white_is_zero =
+        format.photometric_interpretation == Some(PhotometricInterpretation::WhiteIsZero);
+    Ok(if gray && white_is_zero {
+        invert(data)
+    } else {
+        data
+    })
+}
+
+/// Inverts the gray values of the unsigned and floating point types, whose range is assumed to
+/// be from 0 to 1 for floats, like the tiff crate. The signed values are kept.
+fn invert(data: RasterData) -> RasterData {
+    match data {
+        RasterData::U8(data) => RasterData::U8(data.into_iter().map(|v| !v).collect()),
+        RasterData::U16(data) => RasterData::U16(data.into_iter().map(|v| !v).collect()),
+        RasterData::U32(data) => RasterData::U32(data.into_iter().map(|v| !v).collect()),
+        RasterData::U64(data) => RasterData::U64(data.into_iter().map(|v| !v).collect()),
+        RasterData::F32(data) => RasterData::F32(data.into_iter().map(|v| 1.0 - v).collect()),
+        RasterData::F64(data) => RasterData::F64(data.into_iter().map(|v| 1.0 - v).collect()),
+        data => data,
+    }
+}
+
+/// Returns the bits of a sample of whole bytes in the given byte order.
+fn sample_bits(bytes: &[u8], big_endian: bool) -> u64 {
+    let push = |bits: u64, byte: &u8| bits << 8 | u64::from(*byte);
+    if big_endian {
+        bytes.iter().fold(0, push)
+    } else {
+        bytes.iter().rev().fold(0, push)
+    }
+}
+
+/// Undoes the horizontal differencing of a row of samples of whole bytes in the given byte
+/// order, with the given number of samples per pixel. A truncated last sample is kept.
+fn undo_differencing(row: &mut [u8], sample_bytes: usize, samples: usize, big_endian: bool) {
+    let mask = u64::MAX >> (64 - 8 * sample_bytes);
+    let stride = samples * sample_bytes;
+    for start in (stride..row.len() / sample_bytes * sample_bytes).step_by(sample_bytes) {
+        let previous = sample_bits(
+            &row[start - stride..start - stride + sample_bytes],
+            big_endian,
+        );
+        let value = &mut row[start..start + sample_bytes];
+        let bits = sample_bits(value, big_endian).wrapping_add(previous) & mask;
+        for (i, byte) in value.iter_mut().enumerate() {
+            let shift = if big_endian { sample_bytes - 1 - i } else { i };
+            *byte = (bits >> (8 * shift)) as u8;
+        }
+    }
+}
+
+/// Converts the byte count of a chunk, bounded like the tiff crate bounds the buffers of the
+/// chunks.
+fn chunk_len(byte_count: u64) -> TiffResult<usize> {
+    let byte_count = usize::try_from(byte_count)?;
+    if byte_count > Limits::default().intermediate_buffer_size {
+        return Err(TiffError::LimitsExceeded);
+    }
+    Ok(byte_count)
+}
+
+fn from_le_bytes<T, const N: usize>(bytes: &[u8], f: fn([u8; N]) -> T) -> Vec<T> {
+    bytes
+        .chunks_exact(N)
+        .map(|chunk| f(chunk.try_into().unwrap()))
+        .collect()
+}
+
+/// Returns the `bits` bits starting at the given bit of the row, most significant bit first.
+/// Missing bits of a truncated chunk are zero.
+fn unpack(row: &[u8], start: usize, bits: usize) -> u16 {
+    (start..start + bits).fold(0, |value, bit| {
+        let byte = row.get(bit / 8).copied().unwrap_or(0);
+        value << 1 | ((byte >> (7 - bit % 8)) & 1) as u16
+    })
+}
+
+/// Decodes an LZW stream into the output, and returns the number of bytes decoded, which is the
+/// length of the output if the stream has more. A truncated stream gives the bytes decoded until
+/// its end.
+fn decode_lzw(
+    lzw: &mut weezl::decode::Decoder,
+    raw: &[u8],
+    output: &mut [u8],
+) -> io::Result<usize> {
+    let (mut read, mut written) = (0, 0);
+    while written < output.len() {
+        let result = lzw.decode_bytes(&raw[read..], &mut output[written..]);
+        read += result.consumed_in;
+        written += result.consumed_out;
+        let status = result
+            .status
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if !matches!(status, weezl::LzwStatus::Ok) {
+            break;
+        }
+    }
+    Ok(written)
+}
+
+/// Decompresses a zlib stream into the output, and returns the number of bytes decompressed,
+/// which is the length of the output if the stream has more. A truncated stream gives the bytes
+/// decompressed until its end.
+fn inflate(deflate: &mut Decompress, raw: &[u8], output: &mut [u8]) -> io::Result<usize> {
+    deflate.reset(true);
+    while (deflate.total_out() as usize) < output.len() {
+        let progress = (deflate.total_in(), deflate.total_out());
+        let status = deflate
+            .decompress(
+                &raw[deflate.total_in() as usize..],
+                &mut output[deflate.total_out() as usize..],
+                FlushDecompress::None,
+            )
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if status == Status::StreamEnd || (deflate.total_in(), deflate.total_out()) == progress {
+            break;
+        }
+    }
+    Ok(deflate.total_out() as usize)
+}
+
+/// Decodes the PackBits run-length encoding into the output, and returns the number of bytes
+/// decoded, which is the length of the output if the runs have more.
+fn unpack_bits(raw: &[u8], output: &mut [u8]) -> usize {
+    let (mut i, mut written) = (0, 0);
+    while i < raw.len() && written < output.len() {
+        let header = raw[i] as i8;
+        i += 1;
+        match header {
+            0.. => {
+                let end = (i + header as usize + 1).min(raw.len());
+                let len = (end - i).min(output.len() - written);
+                output[written..written + len].copy_from_slice(&raw[i..i + len]);
+                written += len;
+                i = end;
+            }
+            -127..=-1 => {
+                if let Some(byte) = raw.get(i) {
+                    let len = ((1 - header as isize) as usize).min(output.len() - written);
+                    output[written..written + len].fill(*byte);
+                    written += len;
+                }
+                i += 1;
+            }
+            -128 => {}
+        }
+    }
+    written
+}
@@ -0,0 +1,167 @@
+//! Operations on classification rasters, e.g. land-cover products, whose values are classes.
+//!
+//! The nodata value of the results is NaN.
+
+use std::io::{Seek, Write};
+use std::ops::RangeInclusive;
+
+use tiff::TiffResult;
+
+use crate::simd;
+use crate::{Band, BlockMap, InMemoryRaster, Window, WriteOptions};
+
+/// The pixels considered adjacent to a pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Connectivity {
+    /// The pixels sharing an edge.
+    #[default]
+    Four,
+    /// The pixels sharing an edge or a corner.
+    Eight,
+}
+
+impl Connectivity {
+    fn offsets(&self) -> &'static [(i64, i64)] {
+        match self {
+            Connectivity::Four => &[(1, 0), (0, 1), (-1, 0), (0, -1)],
+            Connectivity::Eight => &[
+                (1, 0),
+                (1, 1),
+                (0, 1),
+                (-1, 1),
+                (-1, 0),
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+            ],
+        }
+    }
+}
+
+/// Maps the values of the band to classes, given by the first rule whose range contains the
+/// value, e.g. `(0.0..=500.0, 1.0)` to map the values between 0 and 500 to the class 1.
+///
+/// Nodata pixels, and pixels whose value is not in any range, are nodata in the result.
+pub fn reclassify(band: Band, rules: &[(RangeInclusive<f64>, f64)]) -> TiffResult<InMemoryRaster> {
+    reclassify_map(band, rules).collect()
+}
+
+/// Maps the values of the band to classes like [reclassify], writing the result as a GeoTIFF of
+/// 64-bit floats block by block rather than holding it in memory.
+///
+/// Fails if [WriteOptions::mask] or [WriteOptions::overviews] are set, since they depend on the
+/// whole result.
+pub fn reclassify_into<W: Write + Seek>(
+    band: Band,
+    rules: &[(RangeInclusive<f64>, f64)],
+    writer: W,
+    options: &WriteOptions,
+) -> TiffResult<()> {
+    reclassify_map(band, rules).write(writer, options)
+}
+
+/// Returns the classes of the band, computed block by block.
+fn reclassify_map<'a>(
+    band: Band<'a>,
+    rules: &'a [(RangeInclusive<f64>, f64)],
+) -> BlockMap<'a, impl FnMut(Window) -> TiffResult<Vec<f64>> + 'a> {
+    BlockMap::new(band.image(), move |window| {
+        let mut values = band.read_window(window)?;
+        for value in values.iter_mut() {
+            let class = rules.iter().find(|(range, _)| range.contains(value));
+            *value = match class {
+                Some((_, class)) if !band.is_nodata(*value) => *class,
+                _ => f64::NAN,
+            };
+        }
+        Ok(values)
+    })
+}
+
+/// A group of connected pixels of the same value.
+struct Component {
+    value: f64,
+    pixels: Vec<usize>,
+}
+
+/// Removes the connected components of less than `min_pixels` pixels, by merging each of them
+/// into the largest adjacent component. Smaller components are merged first.
+///
+/// Nodata pixels are left unchanged, and components without valid neighbors are kept.
+pub fn sieve(
+    band: Band,
+    min_pixels: usize,
+    connectivity: Connectivity,
+) -> TiffResult<InMemoryRaster> {
+    let (width, height) = (band.width(), band.height());
+    let mut data = band.read_window(Window::full(width, height))?;
+    simd::nodata_to_nan(&mut data, band.nodata());
+
+    let neighbors = |i: usize| {
+        let (x, y) = ((i % width) as i64, (i / width) as i64);
+        connectivity.offsets().iter().filter_map(move |(dx, dy)| {
+            let (x, y) = (x + dx, y + dy);
+            ((0..width as i64).contains(&x) && (0..height as i64).contains(&y))
+                .then(|| y as usize * width + x as usize)
+        })
+    };
+
+    // Label the components by flood filling
+    let mut labels = vec![usize::MAX; data.len()];
+    let mut components = Vec::new();
+    for start in 0..data.len() {
+        if labels[start] != usize::MAX || data[start].is_nan() {
+            continue;
+        }
+        let label = components.len();
+        let mut component = Component {
+            value: data[start],
+            pixels: Vec::new(),
+        };
+        labels[start] = label;
+        let mut stack = vec![start];
+        while let Some(i) = stack.pop() {
+            component.pixels.push(i);
+            for neighbor in neighbors(i) {
+                if labels[neighbor] == usize::MAX && data[neighbor] == component.value {
+                    labels[neighbor] = label;
+                    stack.push(neighbor);
+                }
+            }
+        }
+        components.push(component);
+    }
+
+    let mut small = (0..components.len())
+        .filter(|label| components[*label].pixels.len() < min_pixels)
+        .collect::<Vec<_>>();
+    small.sort_by_key(|label| components[*label].pixels.len());
+    for label in small {
+        // The component may have grown from smaller components merged into it
+        if components[label].pixels.len() >= min_pixels {
+            continue;
+        }
+        let target = components[label]
+            .pixels
+            .iter()
+            .flat_map(|i| neighbors(*i))
+            .map(|neighbor| labels[neighbor])
+            .filter(|other| *other != usize::MAX && *other != label)
+            .max_by_key(|other| (components[*other].pixels.len(), usize::MAX - other));
+        let Some(target) = target else {
+            continue;
+        };
+        let pixels = std::mem::take(&mut components[label].pixels);
+        for i in &pixels {
+            labels[*i] = target;
+            data[*i] = components[target].value;
+        }
+        components[target].pixels.extend(pixels);
+    }
+
+    Ok(InMemoryRaster::with_grid_of(
+        band.image(),
+        data,
+        Some(f64::NAN),
+    ))
+}
@@ -0,0 +1,188 @@
+//! Names of commonly used codes of the GeoTIFF and EPSG code tables, for display purposes.
+//!
+//! Ref: https://docs.ogc.org/is/19-008r4/19-008r4.html#_geokeys_code_tables
+
+use crate::GeoKeyId;
+
+const USER_DEFINED: u16 = 32767;
+
+/// Returns the name of the given code of a SHORT key, if known.
+pub(crate) fn code_name(key: GeoKeyId, code: u16) -> Option<String> {
+    if code == USER_DEFINED {
+        return Some("User-defined".into());
+    }
+
+    let name = match key {
+        GeoKeyId::ModelType => model_type_name(code),
+        GeoKeyId::RasterType => raster_type_name(code),
+        GeoKeyId::GeographicType => geographic_type_name(code),
+        GeoKeyId::GeogGeodeticDatum => geodetic_datum_name(code),
+        GeoKeyId::GeogPrimeMeridian => prime_meridian_name(code),
+        GeoKeyId::GeogEllipsoid => ellipsoid_name(code),
+        GeoKeyId::GeogLinearUnits
+        | GeoKeyId::GeogAngularUnits
+        | GeoKeyId::GeogAzimuthUnits
+        | GeoKeyId::ProjLinearUnits
+        | GeoKeyId::VerticalUnits => unit_name(code),
+        GeoKeyId::ProjectedType => return projected_type_name(code),
+        GeoKeyId::ProjCoordTrans => coord_trans_name(code),
+        GeoKeyId::Vertical => vertical_name(code),
+        GeoKeyId::VerticalDatum => vertical_datum_name(code),
+        _ => None,
+    };
+    name.map(String::from)
+}
+
+fn model_type_name(code: u16) -> Option<&'static str> {
+    Some(match code {
+        1 => "Projected",
+        2 => "Geographic",
+        3 => "Geocentric",
+        _ => return None,
+    })
+}
+
+fn raster_type_name(code: u16) -> Option<&'static str> {
+    Some(match code {
+        1 => "PixelIsArea",
+        2 => "PixelIsPoint",
+        _ => return None,
+    })
+}
+
+fn geographic_type_name(code: u16) -> Option<&'static str> {
+    Some(match code {
+        4150 => "CH1903+",
+        4149 => "CH1903",
+        4230 => "ED50",
+        4258 => "ETRS89",
+        4267 => "NAD27",
+        4269 => "NAD83",
+        4283 => "GDA94",
+        4322 => "WGS 72",
+        4326 => "WGS 84",
+        4612 => "JGD2000",
+        _ => return None,
+    })
+}
+
+fn geodetic_datum_name(code: u16) -> Option<&'static str> {
+    Some(match code {
+        6149 => "CH1903",
+        6150 => "CH1903+",
+        6230 => "European Datum 1950",
+        6258 => "European Terrestrial Reference System 1989",
+        6267 => "North American Datum 1927",
+        6269 => "North American Datum 1983",
+        6283 => "Geocentric Datum of Australia 1994",
+        6322 => "World Geodetic System 1972",
+        6326 => "World Geodetic System 1984",
+        _ => return None,
+    })
+}
+
+fn prime_meridian_name(code: u16) -> Option<&'static str> {
+    Some(match code {
+        8901 => "Greenwich",
+        8903 => "Paris",
+        8904 => "Bogota",
+        8908 => "Jakarta",
+        _ => return None,
+    })
+}
+
+fn ellipsoid_name(code: u16) -> Option<&'static str> {
+    Some(match code {
+        7004 => "Bessel 1841",
+        7008 => "Clarke 1866",
+        7019 => "GRS 1980",
+        7022 => "International 1924",
+        7030 => "WGS 84",
+        7043 => "WGS 72",
+        _ => return None,
+    })
+}
+
+fn unit_name(code: u16) -> Option<&'static str> {
+    Some(match code {
+        9001 => "metre",
+        9002 => "foot",
+        9003 => "US survey foot",
+        9036 => "kilometre",
+        9101 => "radian",
+        9102 => "degree",
+        9103 => "arc-minute",
+        9104 => "arc-second",
+        9105 => "grad",
+        9122 => "degree (supplier to define representation)",
+        _ => return None,
+    })
+}
+
+fn projected_type_name(code: u16) -> Option<String> {
+    Some(match code {
+        2056 => "CH1903+ / LV95".into(),
+        3035 => "ETRS89-extended / LAEA Europe".into(),
+        3857 => "WGS 84 / Pseudo-Mercator".into(),
+        21781 => "CH1903 / LV03".into(),
+        25828..=25838 => format!("ETRS89 / UTM zone {}N", code - 25800),
+        26701..=26722 => format!("NAD27 / UTM zone {}N", code - 26700),
+        26901..=26923 => format!("NAD83 / UTM zone {}N", code - 26900),
+        32601..=32660 => format!("WGS 84 / UTM zone {}N", code - 32600),
+        32701..=32760 => format!("WGS 84 / UTM zone {}S", code - 32700),
+        _ => return None,
+    })
+}
+
+fn coord_trans_name(code: u16) -> Option<&'static str> {
+    Some(match code {
+        1 => "Transverse Mercator",
+        2 => "Transverse Mercator (Modified Alaska)",
+        3 => "Oblique Mercator",
+        4 => "Oblique Mercator (Laborde)",
+        5 => "Oblique Mercator (Rosenmund)",
+        6 => "Oblique Mercator (Spherical)",
+        7 => "Mercator",
+        8 => "Lambert Conformal Conic (2SP)",
+        9 => "Lambert Conformal Conic (1SP)",
+        10 => "Lambert Azimuthal Equal Area",
+        11 => "Albers Equal Area",
+        12 => "Azimuthal Equidistant",
+        13 => "Equidistant Conic",
+        14 => "Stereographic",
+        15 => "Polar Stereographic",
+        16 => "Oblique Stereographic",
+        17 => "Equirectangular",
+        18 => "Cassini-Soldner",
+        19 => "Gnomonic",
+        20 => "Miller Cylindrical",
+        21 => "Orthographic",
+        22 => "Polyconic",
+        23 => "Robinson",
+        24 => "Sinusoidal",
+        25 => "Van der Grinten",
+        26 => "New Zealand Map Grid",
+        27 => "Transverse Mercator (South Oriented)",
+        _ => return None,
+    })
+}
+
+fn vertical_name(code: u16) -> Option<&'static str> {
+    Some(match code {
+        3855 => "EGM2008 height",
+        5701 => "ODN height",
+        5703 => "NAVD88 height",
+        5773 => "EGM96 height",
+        _ => return None,
+    })
+}
+
+fn vertical_datum_name(code: u16) -> Option<&'static str> {
+    Some(match code {
+        1027 => "EGM2008 geoid",
+        5101 => "Ordnance Datum Newlyn",
+        5103 => "North American Vertical Datum 1988",
+        5171 => "EGM96 geoid",
+        _ => return None,
+    })
+}
@@ -0,0 +1,220 @@
+use tiff::TiffResult;
+
+use crate::{Band, Stretch, Window};
+
+/// The maximum number of pixels read at once.
+const BLOCK_PIXELS: usize = 1 << 20;
+
+/// A histogram with bins of equal width over a fixed range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub min: f64,
+    pub max: f64,
+    /// The number of values in each bin, the last bin including the maximum.
+    pub counts: Vec<u64>,
+    /// The number of values below the minimum.
+    pub below: u64,
+    /// The number of values above the maximum.
+    pub above: u64,
+}
+
+impl Histogram {
+    /// Creates an empty histogram of the given number of bins over `[min, max]`.
+    ///
+    /// Panics if there are no bins or if the range is empty.
+    pub fn new(min: f64, max: f64, bins: usize) -> Self {
+        assert!(
+            bins > 0 && max > min,
+            "the histogram range must not be empty"
+        );
+        Self {
+            min,
+            max,
+            counts: vec![0; bins],
+            below: 0,
+            above: 0,
+        }
+    }
+
+    pub fn bin_width(&self) -> f64 {
+        (self.max - self.min) / self.counts.len() as f64
+    }
+
+    pub fn add(&mut self, value: f64) {
+        if value < self.min {
+            self.below += 1;
+        } else if value > self.max {
+            self.above += 1;
+        } else {
+            let last = self.counts.len() - 1;
+            let bin = ((value - self.min) / self.bin_width()) as usize;
+            self.counts[bin.min(last)] += 1;
+        }
+    }
+
+    /// Adds the counts of another histogram with the same bins.
+    ///
+    /// Panics if the bins differ.
+    pub fn merge(&mut self, other: &Histogram) {
+        assert!(
+            self.min == other.min
+                && self.max == other.max
+                && self.counts.len() == other.counts.len(),
+            "the histograms have different bins"
+        );
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+        self.below += other.below;
+        self.above += other.above;
+    }
+
+    /// Returns the approximate value below which the given percentage of the values lie,
+    /// interpolated linearly within its bin, or `None` if the percentile falls outside of the
+    /// range or if the histogram is empty.
+    pub fn percentile(&self, percent: f64) -> Option<f64> {
+        let total = self.below + self.counts.iter().sum::<u64>() + self.above;
+        if total == 0 {
+            return None;
+        }
+        let target = percent.clamp(0.0, 100.0) / 100.0 * total as f64;
+        let mut cumulative = self.below as f64;
+        if target < cumulative {
+            return None;
+        }
+        for (bin, &count) in self.counts.iter().enumerate() {
+            let next = cumulative + count as f64;
+            if target <= next && count > 0 {
+                let fraction = (target - cumulative) / count as f64;
+                return Some(self.min + (bin as f64 + fraction) * self.bin_width());
+            }
+            cumulative = next;
+        }
+        (target <= cumulative).then_some(self.max)
+    }
+}
+
+/// Statistics of values accumulated across many bands, e.g. the files of a mosaic, to stretch
+/// all of them consistently.
+///
+/// The mean and variance are accumulated with the algorithm of Welford, and statistics
+/// accumulated separately, e.g. in parallel, are combined with [CollectionStats::merge]. The
+/// histogram has fixed bins so that histograms of different files can be combined.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionStats {
+    count: u64,
+    mean: f64,
+    /// The sum of the squared differences from the mean.
+    m2: f64,
+    min: f64,
+    max: f64,
+    histogram: Histogram,
+}
+
+impl CollectionStats {
+    /// Creates empty statistics whose histogram has the given number of bins over `[min, max]`,
+    /// see [Histogram::new].
+    pub fn new(min: f64, max: f64, bins: usize) -> Self {
+        Self {
+            count: 0,
+            mean: 0.0,
+            m2: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            histogram: Histogram::new(min, max, bins),
+        }
+    }
+
+    /// Adds a value, which is ignored if it is NaN.
+    pub fn add(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.histogram.add(value);
+    }
+
+    /// Adds the valid values of the band, reading it block by block.
+    pub fn add_band(&mut self, band: Band) -> TiffResult<()> {
+        let full = Window::full(band.width(), band.height());
+        for window in band.image().optimal_read_windows(full, BLOCK_PIXELS) {
+            for value in band.read_window(window)? {
+                if !band.is_nodata(value) {
+                    self.add(value);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Combines the statistics of other values, whose histogram must have the same bins.
+    ///
+    /// Panics if the bins of the histograms differ.
+    pub fn merge(&mut self, other: &CollectionStats) {
+        self.histogram.merge(&other.histogram);
+        if other.count == 0 {
+            return;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        self.mean += delta * other.count as f64 / count as f64;
+        self.m2 +=
+            other.m2 + delta * delta * (self.count as f64 * other.count as f64) / count as f64;
+        self.count = count;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.min)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.max)
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.mean)
+    }
+
+    /// Returns the population variance of the values.
+    pub fn variance(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.m2 / self.count as f64)
+    }
+
+    /// Returns the population standard deviation of the values.
+    pub fn std_dev(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+
+    pub fn histogram(&self) -> &Histogram {
+        &self.histogram
+    }
+
+    /// Returns the values mapped to black and white by the stretch over all the values, to be
+    /// used with [Stretch::Range], or `None` if there are no values.
+    ///
+    /// Percentiles are approximated from the histogram, and fall back to the minimum and maximum
+    /// outside of its range.
+    pub fn stretch_range(&self, stretch: Stretch) -> Option<(f64, f64)> {
+        let (min, max, mean, std_dev) = (self.min()?, self.max()?, self.mean()?, self.std_dev()?);
+        Some(match stretch {
+            Stretch::MinMax => (min, max),
+            Stretch::PercentClip { low, high } => (
+                self.histogram.percentile(low).unwrap_or(min),
+                self.histogram.percentile(high).unwrap_or(max),
+            ),
+            Stretch::StdDev(factor) => (mean - factor * std_dev, mean + factor * std_dev),
+            Stretch::Range { low, high } => (low, high),
+        })
+    }
+}
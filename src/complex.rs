@@ -0,0 +1,104 @@
+use num_complex::Complex;
+use tiff::{TiffError, TiffFormatError, TiffResult};
+
+use crate::{
+    Band, GeoTiff, Image, Interleave, Orientation, OutOfBounds, ReadOptions, Window, WindowData,
+};
+
+impl Image {
+    /// Reads the pixels of the given window of an image of complex values, e.g. a single look
+    /// complex SAR product, see [DType::is_complex](crate::DType::is_complex).
+    ///
+    /// Pixels of the window outside the raster are handled according to
+    /// [ReadOptions::out_of_bounds], with a real fill value. The values are converted to `f32`
+    /// regardless of [ReadOptions::conversion].
+    pub fn read_complex_window(
+        &self,
+        window: Window,
+        options: &ReadOptions,
+    ) -> TiffResult<WindowData<Complex<f32>>> {
+        let complex_data = self.complex_data.as_ref().ok_or_else(|| {
+            TiffError::FormatError(TiffFormatError::Format(format!(
+                "Image {} does not have decoded complex values",
+                self.index
+            )))
+        })?;
+        let (window, fill) = self.resolve_window::<f32>(window, options)?;
+        let num_samples = self.num_samples;
+        let mut data = vec![Complex::new(fill, 0.0); window.width * window.height * num_samples];
+
+        if let Some(inside) = window.intersection(&self.window()) {
+            let row_len = inside.width * num_samples;
+            for y in inside.y..inside.y + inside.height as i64 {
+                let source = ((y as usize * self.raster_width) + inside.x as usize) * num_samples;
+                let target = ((y - window.y) as usize * window.width
+                    + (inside.x - window.x) as usize)
+                    * num_samples;
+                for (i, value) in data[target..target + row_len].iter_mut().enumerate() {
+                    let index = 2 * (source + i);
+                    *value = Complex::new(complex_data.get(index), complex_data.get(index + 1));
+                }
+            }
+        }
+
+        Ok(WindowData {
+            window,
+            width: window.width,
+            height: window.height,
+            num_samples,
+            interleave: Interleave::Pixel,
+            orientation: Orientation::default(),
+            data,
+        }
+        .with_interleave(options.interleave)
+        .with_orientation(options.orientation))
+    }
+}
+
+impl GeoTiff {
+    /// See [Image::read_complex_window].
+    pub fn read_complex_window(
+        &self,
+        window: Window,
+        options: &ReadOptions,
+    ) -> TiffResult<WindowData<Complex<f32>>> {
+        self.primary().read_complex_window(window, options)
+    }
+}
+
+impl Band<'_> {
+    /// Reads the complex values of the band in the given window, with NaN outside the raster.
+    pub fn read_complex(&self, window: Window) -> TiffResult<Vec<Complex<f32>>> {
+        let options = ReadOptions {
+            out_of_bounds: OutOfBounds::Fill(f64::NAN),
+            ..Default::default()
+        };
+        let data = self.image().read_complex_window(window, &options)?;
+        Ok(data
+            .data
+            .into_iter()
+            .skip(self.sample())
+            .step_by(data.num_samples)
+            .collect())
+    }
+
+    /// Reads the amplitudes, i.e. the moduli, of the complex values of the band in the given
+    /// window, with NaN outside the raster.
+    pub fn read_amplitude(&self, window: Window) -> TiffResult<Vec<f32>> {
+        Ok(self
+            .read_complex(window)?
+            .into_iter()
+            .map(|value| value.norm())
+            .collect())
+    }
+
+    /// Reads the phases of the complex values of the band in the given window, in radians in
+    /// [-π, π], with NaN outside the raster.
+    pub fn read_phase(&self, window: Window) -> TiffResult<Vec<f32>> {
+        Ok(self
+            .read_complex(window)?
+            .into_iter()
+            .map(|value| value.arg())
+            .collect())
+    }
+}
@@ -0,0 +1,197 @@
+//! Per-pixel composites of co-registered images, e.g. cloud-free mosaics of the scenes of a
+//! season.
+//!
+//! The composite is computed tile by tile on the grid of the first input, see
+//! [crate::Image::optimal_read_windows]. The observations of a pixel are the values of the inputs
+//! which are neither nodata, outside of the input nor masked out by its QA band. A pixel is nodata
+//! in the result if it has no observations. The nodata value of the result is NaN.
+
+use tiff::{TiffError, TiffFormatError, TiffResult};
+
+use crate::raster_ops::aligned_offset;
+use crate::simd;
+use crate::{Band, InMemoryRaster, QaSpec, Window};
+
+/// The maximum number of pixels read at once from each input.
+const BLOCK_PIXELS: usize = 1 << 18;
+
+/// How the observations of a pixel are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompositeMethod {
+    /// The median of the observations, or the mean of the two middle ones for an even count.
+    Median,
+    Max,
+    /// The observation whose NDVI, computed from the given samples of the image of its band, is
+    /// the lowest. Observations whose red or near-infrared value is nodata are excluded.
+    MinNdvi {
+        red: usize,
+        nir: usize,
+    },
+    /// The observation of the first input which has one.
+    First,
+}
+
+/// A band to composite, with the QA band masking out some of its pixels, e.g. clouds.
+#[derive(Debug, Clone, Copy)]
+pub struct CompositeInput<'a> {
+    pub band: Band<'a>,
+    /// A QA band aligned with the band and the decoding of its values.
+    pub qa: Option<(Band<'a>, &'a QaSpec)>,
+}
+
+impl<'a> From<Band<'a>> for CompositeInput<'a> {
+    fn from(band: Band<'a>) -> Self {
+        CompositeInput { band, qa: None }
+    }
+}
+
+/// The aligned bands read for one input, with their offset from the grid of the first input.
+struct AlignedInput<'a> {
+    input: CompositeInput<'a>,
+    offset: (i64, i64),
+    ndvi_bands: Option<(Band<'a>, Band<'a>)>,
+}
+
+/// Composites the inputs, which must be aligned with the first one, see
+/// [crate::Image::is_aligned_with].
+pub fn composite(inputs: &[CompositeInput], method: CompositeMethod) -> TiffResult<InMemoryRaster> {
+    let Some(first) = inputs.first().map(|input| input.band) else {
+        return Err(TiffError::FormatError(TiffFormatError::Format(
+            "A composite needs at least one input".into(),
+        )));
+    };
+    let aligned = inputs
+        .iter()
+        .map(|input| {
+            let offset = aligned_offset(first, input.band)?;
+            if let Some((qa, _)) = input.qa {
+                if aligned_offset(input.band, qa)? != (0, 0) {
+                    return Err(TiffError::FormatError(TiffFormatError::Format(
+                        "The QA band must be on the grid of its band".into(),
+                    )));
+                }
+            }
+            let ndvi_bands = match method {
+                CompositeMethod::MinNdvi { red, nir } => {
+                    let image = input.band.image();
+                    match (image.band(red), image.band(nir)) {
+                        (Some(red), Some(nir)) => Some((red, nir)),
+                        _ => {
+                            return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+                                "Image {} has no samples {} and {} for the NDVI",
+                                image.index, red, nir
+                            ))))
+                        }
+                    }
+                }
+                _ => None,
+            };
+            Ok(AlignedInput {
+                input: *input,
+                offset,
+                ndvi_bands,
+            })
+        })
+        .collect::<TiffResult<Vec<_>>>()?;
+
+    let mut data = vec![f64::NAN; first.width() * first.height()];
+    let full = Window::full(first.width(), first.height());
+    let mut observations = Vec::with_capacity(inputs.len());
+    for window in first.image().optimal_read_windows(full, BLOCK_PIXELS) {
+        let blocks = aligned
+            .iter()
+            .map(|aligned| aligned.read_block(window))
+            .collect::<TiffResult<Vec<_>>>()?;
+        for i in 0..window.width * window.height {
+            observations.clear();
+            observations.extend(blocks.iter().filter_map(|(values, ndvi)| {
+                let ndvi = ndvi.as_ref().map_or(0.0, |ndvi| ndvi[i]);
+                (!values[i].is_nan() && !ndvi.is_nan()).then_some((values[i], ndvi))
+            }));
+            let (x, y) = (i % window.width, i / window.width);
+            data[(window.y as usize + y) * first.width() + window.x as usize + x] =
+                combine(method, &mut observations);
+        }
+    }
+    Ok(InMemoryRaster::with_grid_of(
+        first.image(),
+        data,
+        Some(f64::NAN),
+    ))
+}
+
+impl AlignedInput<'_> {
+    /// Reads the values of the window of the grid of the first input, with NaN for the pixels
+    /// which are not observations, and their NDVI if required.
+    fn read_block(&self, window: Window) -> TiffResult<(Vec<f64>, Option<Vec<f64>>)> {
+        let window = Window {
+            x: window.x + self.offset.0,
+            y: window.y + self.offset.1,
+            ..window
+        };
+        let band = self.input.band;
+        let mut values = band.read_window(window)?;
+        simd::nodata_to_nan(&mut values, band.nodata());
+        if let Some((qa, spec)) = self.input.qa {
+            let valid = spec.valid_mask(qa, window)?;
+            for (value, _) in values.iter_mut().zip(valid).filter(|(_, valid)| !valid) {
+                *value = f64::NAN;
+            }
+        }
+        let ndvi = match self.ndvi_bands {
+            Some((red, nir)) => {
+                let (red_values, nir_values) = (red.read_window(window)?, nir.read_window(window)?);
+                Some(
+                    red_values
+                        .into_iter()
+                        .zip(nir_values)
+                        .map(|(red_value, nir_value)| {
+                            if red.is_nodata(red_value) || nir.is_nodata(nir_value) {
+                                f64::NAN
+                            } else {
+                                (nir_value - red_value) / (nir_value + red_value)
+                            }
+                        })
+                        .collect(),
+                )
+            }
+            None => None,
+        };
+        Ok((values, ndvi))
+    }
+}
+
+/// Combines the `(value, ndvi)` observations of a pixel, in input order.
+fn combine(method: CompositeMethod, observations: &mut [(f64, f64)]) -> f64 {
+    if observations.is_empty() {
+        return f64::NAN;
+    }
+    match method {
+        CompositeMethod::Median => {
+            observations.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let middle = observations.len() / 2;
+            if observations.len().is_multiple_of(2) {
+                (observations[middle - 1].0 + observations[middle].0) / 2.0
+            } else {
+                observations[middle].0
+            }
+        }
+        CompositeMethod::Max => observations
+            .iter()
+            .map(|observation| observation.0)
+            .fold(f64::NEG_INFINITY, f64::max),
+        CompositeMethod::MinNdvi { .. } => {
+            observations
+                .iter()
+                .fold(observations[0], |min, &observation| {
+                    if observation.1 < min.1 {
+                        observation
+                    } else {
+                        min
+                    }
+                })
+                .0
+        }
+        CompositeMethod::First => observations[0].0,
+    }
+}
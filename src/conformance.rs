@@ -0,0 +1,455 @@
+use crate::{GeoKeyDirectory, RasterType};
+
+const USER_DEFINED: u16 = 32767;
+
+const MODEL_TYPE_PROJECTED: u16 = 1;
+const MODEL_TYPE_GEOGRAPHIC: u16 = 2;
+const MODEL_TYPE_GEOCENTRIC: u16 = 3;
+
+/// Requirements classes of the OGC GeoTIFF standard which are checked by this crate.
+///
+/// Ref: https://docs.ogc.org/is/19-008r4/19-008r4.html#_requirements_classes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequirementsClass {
+    GeoKeyDirectoryTag,
+    GTModelTypeGeoKey,
+    GTRasterTypeGeoKey,
+    GeodeticCRSGeoKey,
+    ProjectedCRSGeoKey,
+    VerticalGeoKey,
+    Raster2ModelCRSTransformation,
+}
+
+impl RequirementsClass {
+    const ALL: [RequirementsClass; 7] = [
+        RequirementsClass::GeoKeyDirectoryTag,
+        RequirementsClass::GTModelTypeGeoKey,
+        RequirementsClass::GTRasterTypeGeoKey,
+        RequirementsClass::GeodeticCRSGeoKey,
+        RequirementsClass::ProjectedCRSGeoKey,
+        RequirementsClass::VerticalGeoKey,
+        RequirementsClass::Raster2ModelCRSTransformation,
+    ];
+}
+
+/// Outcome of checking a file against a single requirements class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conformance {
+    Conformant,
+    /// The requirements class does not apply, e.g. [RequirementsClass::ProjectedCRSGeoKey] for a
+    /// geographic model.
+    NotApplicable,
+    /// The requirements class applies but is violated, with a description of each violation.
+    NonConformant(Vec<String>),
+}
+
+/// Conformance of an image to the requirements classes of the GeoTIFF standard.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConformanceReport {
+    classes: Vec<(RequirementsClass, Conformance)>,
+}
+
+impl ConformanceReport {
+    /// Checks the raw GeoTIFF tags of an image. `key_directory` is the content of the
+    /// GeoKeyDirectoryTag and `geo_key_directory` its parsed form.
+    ///
+    /// This is the report of [crate::Image::conformance] with the `decode` feature, and checks
+    /// the tags read by other TIFF readers without it.
+    pub fn check(
+        key_directory: Option<&[u16]>,
+        geo_key_directory: Option<&GeoKeyDirectory>,
+        pixel_scale: Option<&[f64]>,
+        tie_points: Option<&[f64]>,
+        model_transformation: Option<&[f64]>,
+    ) -> Self {
+        let default_directory = GeoKeyDirectory::default();
+        let classes = RequirementsClass::ALL
+            .into_iter()
+            .map(|class| {
+                let conformance = match (class, key_directory) {
+                    (RequirementsClass::Raster2ModelCRSTransformation, _) => {
+                        check_raster_to_model(pixel_scale, tie_points, model_transformation)
+                    }
+                    (RequirementsClass::GeoKeyDirectoryTag, None) => {
+                        Conformance::NonConformant(vec!["GeoKeyDirectoryTag is missing".into()])
+                    }
+                    // Without geo keys, the remaining classes are checked against an empty
+                    // directory, so that missing required keys are reported
+                    (_, key_directory) => check_geo_keys(
+                        class,
+                        key_directory.unwrap_or_default(),
+                        geo_key_directory.unwrap_or(&default_directory),
+                    ),
+                };
+                (class, conformance)
+            })
+            .collect();
+        Self { classes }
+    }
+
+    /// Returns the outcome for each requirements class.
+    pub fn iter(&self) -> impl Iterator<Item = (RequirementsClass, &Conformance)> {
+        self.classes
+            .iter()
+            .map(|(class, conformance)| (*class, conformance))
+    }
+
+    /// Returns the outcome for the given requirements class.
+    pub fn get(&self, class: RequirementsClass) -> &Conformance {
+        self.iter()
+            .find_map(|(c, conformance)| (c == class).then_some(conformance))
+            .expect("all requirements classes are checked")
+    }
+
+    /// Returns whether the requirements class applies and is satisfied.
+    pub fn conforms_to(&self, class: RequirementsClass) -> bool {
+        self.get(class) == &Conformance::Conformant
+    }
+
+    /// Returns the requirements classes which apply and are satisfied.
+    pub fn conformant_classes(&self) -> impl Iterator<Item = RequirementsClass> + '_ {
+        self.iter()
+            .filter(|(_, conformance)| **conformance == Conformance::Conformant)
+            .map(|(class, _)| class)
+    }
+
+    /// Returns whether none of the applicable requirements classes is violated.
+    pub fn is_conformant(&self) -> bool {
+        self.iter()
+            .all(|(_, conformance)| !matches!(conformance, Conformance::NonConformant(_)))
+    }
+}
+
+fn check_geo_keys(
+    class: RequirementsClass,
+    key_directory: &[u16],
+    directory: &GeoKeyDirectory,
+) -> Conformance {
+    let mut violations = Vec::new();
+    let model_type = directory.model_type;
+
+    match class {
+        RequirementsClass::GeoKeyDirectoryTag => {
+            if directory.key_directory_version != 1 {
+                violations.push(format!(
+                    "KeyDirectoryVersion is {} instead of 1",
+                    directory.key_directory_version
+                ));
+            }
+            if directory.key_revision != 1 {
+                violations.push(format!(
+                    "KeyRevision is {} instead of 1",
+                    directory.key_revision
+                ));
+            }
+            if directory.minor_revision > 1 {
+                violations.push(format!(
+                    "MinorRevision is {} instead of 0 or 1",
+                    directory.minor_revision
+                ));
+            }
+            let key_ids = key_directory
+                .get(4..)
+                .unwrap_or_default()
+                .chunks_exact(4)
+                .map(|entry| entry[0])
+                .collect::<Vec<_>>();
+            if key_ids.windows(2).any(|ids| ids[0] >= ids[1]) {
+                violations.push("Keys are not sorted in strictly ascending order".into());
+            }
+        }
+        RequirementsClass::GTModelTypeGeoKey => match model_type {
+            None => violations.push("GTModelTypeGeoKey is missing".into()),
+            Some(MODEL_TYPE_PROJECTED | MODEL_TYPE_GEOGRAPHIC | MODEL_TYPE_GEOCENTRIC) => {}
+            Some(USER_DEFINED) => {
+                if directory.citation.is_none() {
+                    violations.push("User-defined model type without GTCitationGeoKey".into());
+                }
+            }
+            Some(model_type) => violations.push(format!("Invalid model type: {model_type}")),
+        },
+        RequirementsClass::GTRasterTypeGeoKey => match directory.raster_type {
+            None => violations.push("GTRasterTypeGeoKey is missing".into()),
+            Some(RasterType::RasterPixelIsArea | RasterType::RasterPixelIsPoint) => {}
+            Some(raster_type) => violations.push(format!("Invalid raster type: {raster_type:?}")),
+        },
+        RequirementsClass::GeodeticCRSGeoKey => {
+            if model_type != Some(MODEL_TYPE_GEOGRAPHIC) && directory.geographic_type.is_none() {
+                return Conformance::NotApplicable;
+            }
+            match directory.geographic_type {
+                None => violations.push("GeodeticCRSGeoKey is missing".into()),
+                Some(USER_DEFINED) => {
+                    if directory.geog_citation.is_none() {
+                        violations.push(
+                            "User-defined geodetic CRS without GeodeticCitationGeoKey".into(),
+                        );
+                    }
+                    if directory.geog_geodetic_datum.is_none() {
+                        violations
+                            .push("User-defined geodetic CRS without GeodeticDatumGeoKey".into());
+                    }
+                }
+                Some(code) => check_code_range("GeodeticCRSGeoKey", code, &mut violations),
+            }
+        }
+        RequirementsClass::ProjectedCRSGeoKey => {
+            if model_type != Some(MODEL_TYPE_PROJECTED) {
+                return Conformance::NotApplicable;
+            }
+            match directory.projected_type {
+                None => violations.push("ProjectedCRSGeoKey is missing".into()),
+                Some(USER_DEFINED) => {
+                    if directory.projection.is_none() && directory.proj_coord_trans.is_none() {
+                        violations.push(
+                            "User-defined projected CRS without ProjectionGeoKey or ProjMethodGeoKey"
+                                .into(),
+                        );
+                    }
+                    if directory.geographic_type.is_none() {
+                        violations
+                            .push("User-defined projected CRS without GeodeticCRSGeoKey".into());
+                    }
+                }
+                Some(code) => check_code_range("ProjectedCRSGeoKey", code, &mut violations),
+            }
+        }
+        RequirementsClass::VerticalGeoKey => match directory.vertical {
+            None => return Conformance::NotApplicable,
+            Some(USER_DEFINED) => {
+                if directory.vertical_citation.is_none() {
+                    violations
+                        .push("User-defined vertical CRS without VerticalCitationGeoKey".into());
+                }
+                if directory.vertical_datum.is_none() {
+                    violations.push("User-defined vertical CRS without VerticalDatumGeoKey".into());
+                }
+            }
+            Some(code) => check_code_range("VerticalGeoKey", code, &mut violations),
+        },
+        RequirementsClass::Raster2ModelCRSTransformation => unreachable!(),
+    }
+
+    if violations.is_empty() {
+        Conformance::Conformant
+    } else {
+        Conformance::NonConformant(violations)
+    }
+}
+
+/// Codes below 1024 are reserved, codes above 32767 are private.
+fn check_code_range(key: &str, code: u16, violations: &mut Vec<String>) {
+    if !(1024..USER_DEFINED).contains(&code) {
+        violations.push(format!("{key} has a reserved or private value: {code}"));
+    }
+}
+
+/// The number of values and the combination of the tags are already validated when reading the
+/// coordinate transformation, so only the presence of the tags is left to check.
+fn check_raster_to_model(
+    pixel_scale: Option<&[f64]>,
+    tie_points: Option<&[f64]>,
+    model_transformation: Option<&[f64]>,
+) -> Conformance {
+    match (pixel_scale, tie_points, model_transformation) {
+        (None, None, None) => Conformance::NonConformant(vec![
+            "Neither ModelTiepointTag nor ModelTransformationTag is present".into(),
+        ]),
+        (Some(_), None, None) => Conformance::NonConformant(vec![
+            "ModelPixelScaleTag is present without ModelTiepointTag".into(),
+        ]),
+        _ => Conformance::Conformant,
+    }
+}
+
+/// Baseline compression schemes: none, CCITT modified Huffman RLE and PackBits.
+const BASELINE_COMPRESSIONS: [u32; 3] = [1, 2, 32773];
+
+/// Required fields of baseline TIFF 6.0 which have no default value.
+const REQUIRED_TAGS: [(u16, &str); 7] = [
+    (256, "ImageWidth"),
+    (257, "ImageLength"),
+    (262, "PhotometricInterpretation"),
+    (273, "StripOffsets"),
+    (279, "StripByteCounts"),
+    (282, "XResolution"),
+    (283, "YResolution"),
+];
+
+/// The tags of tiled images: TileWidth, TileLength, TileOffsets and TileByteCounts.
+const TILE_TAGS: [u16; 4] = [322, 323, 324, 325];
+
+/// Checks that the bytes of a TIFF file only rely on the layout and the features which baseline
+/// TIFF 6.0 readers must support, e.g. to validate files written with
+/// [crate::InMemoryRaster::write_with_options] for picky legacy readers:
+/// - the file is a classic TIFF, not a BigTIFF,
+/// - the IFDs and the values which do not fit in their entries start on word boundaries,
+/// - the entries of each IFD are sorted by tag, without duplicates,
+/// - the required fields without default values are present,
+/// - the data is in strips within the file, compressed with a baseline scheme and no predictor.
+///
+/// The sample formats are not checked, since GeoTIFF rasters commonly use floating point samples,
+/// which are an extension of TIFF 6.0.
+pub fn check_baseline_tiff(bytes: &[u8]) -> Conformance {
+    let little_endian = match bytes.get(..2) {
+        Some(b"II") => true,
+        Some(b"MM") => false,
+        _ => return Conformance::NonConformant(vec!["The byte order is neither II nor MM".into()]),
+    };
+    let u16_at = |offset: usize| {
+        let value = [*bytes.get(offset)?, *bytes.get(offset + 1)?];
+        Some(if little_endian {
+            u16::from_le_bytes(value)
+        } else {
+            u16::from_be_bytes(value)
+        })
+    };
+    let u32_at = |offset: usize| {
+        let value = bytes.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if little_endian {
+            u32::from_le_bytes(value)
+        } else {
+            u32::from_be_bytes(value)
+        })
+    };
+    match u16_at(2) {
+        Some(42) => {}
+        Some(43) => {
+            return Conformance::NonConformant(vec!["BigTIFF is not part of TIFF 6.0".into()])
+        }
+        _ => return Conformance::NonConformant(vec!["The magic number is not 42".into()]),
+    }
+
+    let mut violations = Vec::new();
+    let mut visited = Vec::new();
+    let mut offset = u32_at(4).unwrap_or(0) as usize;
+    if offset == 0 {
+        violations.push("The file has no IFD".into());
+    }
+    while offset != 0 {
+        let index = visited.len();
+        if visited.contains(&offset) {
+            violations.push(format!("IFD {index} loops back to a previous IFD"));
+            break;
+        }
+        visited.push(offset);
+        if !offset.is_multiple_of(2) {
+            violations.push(format!(
+                "IFD {index} at offset {offset} is not word-aligned"
+            ));
+        }
+        let Some(count) = u16_at(offset) else {
+            violations.push(format!(
+                "IFD {index} at offset {offset} is outside of the file"
+            ));
+            break;
+        };
+        let entries_end = offset + 2 + 12 * count as usize;
+        let Some(next) = u32_at(entries_end) else {
+            violations.push(format!("IFD {index} is truncated"));
+            break;
+        };
+
+        // The tags with their values as unsigned integers, if they are bytes, shorts or longs
+        let mut tags: Vec<(u16, Vec<u32>)> = Vec::new();
+        for entry in (offset + 2..entries_end).step_by(12) {
+            let (tag, field_type) = (u16_at(entry).unwrap(), u16_at(entry + 2).unwrap());
+            let count = u32_at(entry + 4).unwrap() as usize;
+            if tags.last().is_some_and(|(previous, _)| *previous >= tag) {
+                violations.push(format!(
+                    "Tag {tag} of IFD {index} is not sorted in strictly ascending order"
+                ));
+            }
+            let size = match field_type {
+                1 | 2 | 6 | 7 => 1,
+                3 | 8 => 2,
+                4 | 9 | 11 => 4,
+                5 | 10 | 12 => 8,
+                _ => {
+                    violations.push(format!(
+                        "Tag {tag} of IFD {index} has the unknown field type {field_type}"
+                    ));
+                    continue;
+                }
+            };
+            let mut value_offset = entry + 8;
+            if size * count > 4 {
+                value_offset = u32_at(entry + 8).unwrap() as usize;
+                if !value_offset.is_multiple_of(2) {
+                    violations.push(format!(
+                        "The value of tag {tag} of IFD {index} is not word-aligned"
+                    ));
+                }
+                if value_offset + size * count > bytes.len() {
+                    violations.push(format!(
+                        "The value of tag {tag} of IFD {index} is outside of the file"
+                    ));
+                    tags.push((tag, Vec::new()));
+                    continue;
+                }
+            }
+            let values = (0..count)
+                .filter_map(|i| match field_type {
+                    1 => bytes.get(value_offset + i).map(|&value| value as u32),
+                    3 => u16_at(value_offset + 2 * i).map(u32::from),
+                    4 => u32_at(value_offset + 4 * i),
+                    _ => None,
+                })
+                .collect();
+            tags.push((tag, values));
+        }
+        let get = |tag: u16| {
+            tags.iter()
+                .find(|(t, _)| *t == tag)
+                .map(|(_, values)| &values[..])
+        };
+
+        for (tag, name) in REQUIRED_TAGS {
+            if get(tag).is_none() {
+                violations.push(format!("IFD {index} lacks the required {name} tag"));
+            }
+        }
+        if TILE_TAGS.iter().any(|&tag| get(tag).is_some()) {
+            violations.push(format!(
+                "IFD {index} is tiled, which is an extension of TIFF 6.0"
+            ));
+        }
+        if let Some(&[compression, ..]) = get(259) {
+            if !BASELINE_COMPRESSIONS.contains(&compression) {
+                violations.push(format!(
+                    "IFD {index} has the compression {compression}, which is not a baseline scheme"
+                ));
+            }
+        }
+        if let Some(&[predictor, ..]) = get(317) {
+            if predictor != 1 {
+                violations.push(format!(
+                    "IFD {index} has the predictor {predictor}, which is an extension of TIFF 6.0"
+                ));
+            }
+        }
+        if let (Some(offsets), Some(byte_counts)) = (get(273), get(279)) {
+            if offsets.len() != byte_counts.len() {
+                violations.push(format!(
+                    "IFD {index} has {} strip offsets but {} strip byte counts",
+                    offsets.len(),
+                    byte_counts.len()
+                ));
+            }
+            for (strip, (&offset, &byte_count)) in offsets.iter().zip(byte_counts).enumerate() {
+                if offset as usize + byte_count as usize > bytes.len() {
+                    violations.push(format!(
+                        "Strip {strip} of IFD {index} is outside of the file"
+                    ));
+                }
+            }
+        }
+        offset = next as usize;
+    }
+
+    if violations.is_empty() {
+        Conformance::Conformant
+    } else {
+        Conformance::NonConformant(violations)
+    }
+}
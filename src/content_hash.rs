@@ -0,0 +1,152 @@
+use tiff::TiffResult;
+
+use crate::{GeoTiff, Image, ReadOptions, SubfileType, TransformTags};
+
+/// The size of the chunks of pixels decoded at once to hash the raster data.
+const CHUNK_BUDGET: usize = 16 << 20;
+
+impl Image {
+    /// Returns a hash of the logical content of the image: its size, sample types, nodata value,
+    /// geo keys, transformation to model space and decoded pixel values.
+    ///
+    /// Unlike a hash of the bytes of the file, it does not depend on the compression, the tiling,
+    /// the descriptive tags or the order of the tags, so that files which only differ by their
+    /// encoding have the same hash, e.g. to deduplicate catalogs. The transformation is compared
+    /// by its affine terms, whether it is given by a pixel scale or a transformation matrix. The
+    /// hash is stable across platforms, but it is not meant to resist deliberate collisions.
+    pub fn content_hash(&self) -> TiffResult<u64> {
+        let mut hasher = Fnv1a::new();
+        self.hash_content(&mut hasher)?;
+        Ok(hasher.finish())
+    }
+
+    fn hash_content(&self, hasher: &mut Fnv1a) -> TiffResult<()> {
+        hasher.write_str(&format!("{:?}", self.subfile_type));
+        hasher.write_usize(self.raster_width);
+        hasher.write_usize(self.raster_height);
+        hasher.write_usize(self.num_samples);
+        for dtype in &self.dtypes {
+            hasher.write_str(&format!("{dtype:?}"));
+        }
+        hasher.write_option_f64(self.nodata);
+
+        match self.geo_key_directory() {
+            Some(directory) => {
+                let (directory_data, double_params, ascii_params) = directory.to_tag_data()?;
+                hasher.write_usize(directory_data.len());
+                for value in directory_data {
+                    hasher.write(&value.to_le_bytes());
+                }
+                hasher.write_f64s(&double_params);
+                hasher.write_str(&ascii_params);
+            }
+            None => hasher.write_usize(0),
+        }
+        match self
+            .coordinate_transform()
+            .and_then(|transform| transform.affine_terms())
+        {
+            Some(terms) => {
+                hasher.write_usize(1);
+                hasher.write_f64s(&terms);
+            }
+            None => {
+                hasher.write_usize(0);
+                let TransformTags {
+                    pixel_scale,
+                    tie_points,
+                    model_transformation,
+                } = self.transform_tags();
+                for values in [pixel_scale, tie_points, model_transformation] {
+                    hasher.write_f64s(values.as_deref().unwrap_or_default());
+                }
+            }
+        }
+
+        self.read_window_chunked::<f64, _>(
+            self.window(),
+            &ReadOptions::default(),
+            CHUNK_BUDGET,
+            |chunk| {
+                for value in chunk.data {
+                    hasher.write_f64(value);
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+impl GeoTiff {
+    /// Returns a hash of the logical content of the images of the file, see
+    /// [Image::content_hash], in IFD order.
+    ///
+    /// The overviews are excluded, since they are derived from the full resolution images, so
+    /// that adding or regenerating them does not change the hash.
+    pub fn content_hash(&self) -> TiffResult<u64> {
+        let mut hasher = Fnv1a::new();
+        for image in self.images() {
+            if image.subfile_type != SubfileType::ReducedResolution {
+                image.hash_content(&mut hasher)?;
+            }
+        }
+        Ok(hasher.finish())
+    }
+}
+
+/// The 64-bit FNV-1a hash, which unlike the hashers of the standard library is specified, and
+/// therefore stable across platforms and releases.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write(&(value as u64).to_le_bytes());
+    }
+
+    fn write_str(&mut self, value: &str) {
+        self.write_usize(value.len());
+        self.write(value.as_bytes());
+    }
+
+    /// Writes the value with a single representation of NaN, and of zero since adding zero turns
+    /// negative zero into positive zero.
+    fn write_f64(&mut self, value: f64) {
+        let value = if value.is_nan() {
+            f64::NAN
+        } else {
+            value + 0.0
+        };
+        self.write(&value.to_bits().to_le_bytes());
+    }
+
+    fn write_f64s(&mut self, values: &[f64]) {
+        self.write_usize(values.len());
+        for &value in values {
+            self.write_f64(value);
+        }
+    }
+
+    fn write_option_f64(&mut self, value: Option<f64>) {
+        match value {
+            Some(value) => {
+                self.write_usize(1);
+                self.write_f64(value);
+            }
+            None => self.write_usize(0),
+        }
+    }
+}
@@ -1,6 +1,28 @@
+use std::fmt;
+#[cfg(feature = "decode")]
+use std::io::{Read, Seek};
+
 use geo_types::Coord;
-use tiff::{TiffError, TiffFormatError, TiffResult};
+#[cfg(feature = "decode")]
+use tiff::decoder::Decoder;
+#[cfg(feature = "decode")]
+use tiff::tags::Tag;
+#[cfg(feature = "decode")]
+use tiff::{TiffError, TiffResult};
+
+use crate::{MetadataError, MetadataResult, RasterType};
 
+pub use fit::{Residuals, TiePoint};
+pub use geometry::TransformCoords;
+pub use pipeline::{compose, Reprojection, TransformPipeline};
+pub use precision::Precision;
+#[cfg(feature = "tie-points")]
+pub use tie_points::{TiePointGrid, TiePoints};
+
+mod fit;
+mod geometry;
+mod pipeline;
+mod precision;
 #[cfg(feature = "tie-points")]
 mod tie_points;
 
@@ -8,15 +30,170 @@ const MODEL_TIE_POINT_TAG: &str = "ModelTiePointTag";
 const MODEL_PIXEL_SCALE_TAG: &str = "ModelPixelScaleTag";
 const MODEL_TRANSFORMATION_TAG: &str = "ModelTransformationTag";
 
+/// Default relative tolerance below which an affine transformation is considered not invertible.
+pub const DEFAULT_INVERTIBILITY_TOLERANCE: f64 = 1e-12;
+
+/// How the sign of the Y value of the ModelPixelScaleTag is interpreted, see
+/// [TransformTags::to_coordinate_transform_with_convention].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PixelScaleConvention {
+    /// The values are used as stored, following the GeoTIFF specification: the model Y
+    /// coordinate decreases by ScaleY from one row to the next, so a negative ScaleY gives a
+    /// raster whose rows go from south to north.
+    #[default]
+    AsStored,
+    /// The absolute value of ScaleY is used, for producers which store the signed Y resolution
+    /// like in a GDAL geotransform: the rows always go from north to south.
+    NorthUp,
+}
+
 /// Defines the transformation between raster space and model space.
 ///
 /// Ref: https://docs.ogc.org/is/19-008r4/19-008r4.html#_raster_to_model_coordinate_transformation_requirements
 #[derive(Debug)]
 pub enum CoordinateTransform {
     AffineTransform(AffineTransform),
+    Affine3D(Affine3DTransform),
     TiePointAndPixelScale(TiePointAndPixelScale),
     #[cfg(feature = "tie-points")]
-    TiePoints(tie_points::TiePoints),
+    TiePoints(TiePoints),
+}
+
+/// The values of the tags defining the transformation between raster space and model space, as
+/// they are stored in a file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransformTags {
+    pub pixel_scale: Option<Vec<f64>>,
+    pub tie_points: Option<Vec<f64>>,
+    pub model_transformation: Option<Vec<f64>>,
+}
+
+/// Reads the tags from the current IFD of a decoder. Missing tags are `None`.
+#[cfg(feature = "decode")]
+impl<R: Read + Seek> TryFrom<&mut Decoder<R>> for TransformTags {
+    type Error = TiffError;
+
+    fn try_from(decoder: &mut Decoder<R>) -> TiffResult<Self> {
+        let mut read = |tag| match decoder.find_tag(tag)? {
+            Some(v) => v.into_f64_vec().map(Some),
+            None => Ok(None),
+        };
+        Ok(Self {
+            pixel_scale: read(Tag::ModelPixelScaleTag)?,
+            tie_points: read(Tag::ModelTiepointTag)?,
+            model_transformation: read(Tag::ModelTransformationTag)?,
+        })
+    }
+}
+
+/// Reads the transformation from the tags of the current IFD of a decoder, with the default
+/// invertibility tolerance. Fails if the IFD has none of the tags.
+#[cfg(feature = "decode")]
+impl<R: Read + Seek> TryFrom<&mut Decoder<R>> for CoordinateTransform {
+    type Error = TiffError;
+
+    fn try_from(decoder: &mut Decoder<R>) -> TiffResult<Self> {
+        let transform = TransformTags::try_from(decoder)?
+            .to_coordinate_transform(DEFAULT_INVERTIBILITY_TOLERANCE)?
+            .ok_or_else(|| {
+                MetadataError::Format(format!(
+                    "None of the {MODEL_PIXEL_SCALE_TAG}, {MODEL_TIE_POINT_TAG} and \
+                     {MODEL_TRANSFORMATION_TAG} tags is present"
+                ))
+            })?;
+        Ok(transform)
+    }
+}
+
+impl TransformTags {
+    pub fn is_empty(&self) -> bool {
+        self.pixel_scale.is_none()
+            && self.tie_points.is_none()
+            && self.model_transformation.is_none()
+    }
+
+    /// Returns the tie points of the ModelTiepointTag, see [TiePoint::from_tag_data].
+    pub fn parsed_tie_points(&self) -> Vec<TiePoint> {
+        self.tie_points
+            .as_deref()
+            .map(TiePoint::from_tag_data)
+            .unwrap_or_default()
+    }
+
+    /// Returns the transformation defined by the tags, or `None` if there are none.
+    pub fn to_coordinate_transform(
+        &self,
+        invertibility_tolerance: f64,
+    ) -> MetadataResult<Option<CoordinateTransform>> {
+        self.to_coordinate_transform_with_convention(
+            invertibility_tolerance,
+            PixelScaleConvention::AsStored,
+        )
+    }
+
+    /// Returns the transformation defined by the tags, or `None` if there are none, with the
+    /// given interpretation of the sign of the Y pixel scale.
+    ///
+    /// Fails if the X or Y pixel scale is zero.
+    pub fn to_coordinate_transform_with_convention(
+        &self,
+        invertibility_tolerance: f64,
+        convention: PixelScaleConvention,
+    ) -> MetadataResult<Option<CoordinateTransform>> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        CoordinateTransform::from_tag_data(
+            self.pixel_scale.clone(),
+            self.tie_points.clone(),
+            self.model_transformation.clone(),
+            invertibility_tolerance,
+            convention,
+        )
+        .map(Some)
+    }
+
+    /// Returns the tags of the grid whose pixels cover `factor` x `factor` pixels of this grid,
+    /// starting from the same corner. The `raster_offset` is the raster coordinate of the pixel
+    /// corner, i.e. -0.5 for [crate::RasterType::RasterPixelIsPoint].
+    #[cfg(feature = "decode")]
+    pub(crate) fn downsampled(&self, factor: usize, raster_offset: f64) -> Self {
+        let factor = factor as f64;
+        // The raster coordinates `p` of this grid are `factor * p' + shift` in the new grid
+        let shift = -raster_offset * (factor - 1.0);
+        let pixel_scale = self.pixel_scale.as_ref().map(|pixel_scale| {
+            let mut pixel_scale = pixel_scale.clone();
+            pixel_scale
+                .iter_mut()
+                .take(2)
+                .for_each(|scale| *scale *= factor);
+            pixel_scale
+        });
+        let tie_points = self.tie_points.as_ref().map(|tie_points| {
+            let mut tie_points = tie_points.clone();
+            for tie_point in tie_points.chunks_mut(6) {
+                tie_point
+                    .iter_mut()
+                    .take(2)
+                    .for_each(|coord| *coord = (*coord - shift) / factor);
+            }
+            tie_points
+        });
+        let model_transformation = self.model_transformation.as_ref().map(|matrix| {
+            let mut matrix = matrix.clone();
+            for row in matrix.chunks_mut(4).take(3) {
+                row[3] += (row[0] + row[1]) * shift;
+                row[0] *= factor;
+                row[1] *= factor;
+            }
+            matrix
+        });
+        Self {
+            pixel_scale,
+            tie_points,
+            model_transformation,
+        }
+    }
 }
 
 impl CoordinateTransform {
@@ -24,13 +201,15 @@ impl CoordinateTransform {
         pixel_scale_data: Option<Vec<f64>>,
         model_tie_points_data: Option<Vec<f64>>,
         model_transformation_data: Option<Vec<f64>>,
-    ) -> TiffResult<Self> {
+        invertibility_tolerance: f64,
+        convention: PixelScaleConvention,
+    ) -> MetadataResult<Self> {
         let pixel_scale = pixel_scale_data
             .map(|data| {
                 <[f64; 3]>::try_from(data).map_err(|_| {
-                    TiffError::FormatError(TiffFormatError::Format(format!(
+                    MetadataError::Format(format!(
                         "Number values in {MODEL_PIXEL_SCALE_TAG} must be equal to 3"
-                    )))
+                    ))
                 })
             })
             .transpose()?;
@@ -38,15 +217,21 @@ impl CoordinateTransform {
             .map(|data| {
                 let len = data.len();
                 if len == 0 {
-                    return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+                    return Err(MetadataError::Format(format!(
                         "Number of values in {MODEL_TIE_POINT_TAG} must be greater than 0"
-                    ))));
+                    )));
                 }
 
                 if len % 6 != 0 {
-                    return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+                    return Err(MetadataError::Format(format!(
                         "Number of values in {MODEL_TIE_POINT_TAG} must be divisible by 6"
-                    ))));
+                    )));
+                }
+
+                if data.iter().any(|value| !value.is_finite()) {
+                    return Err(MetadataError::Format(format!(
+                        "Values in {MODEL_TIE_POINT_TAG} must be finite"
+                    )));
                 }
 
                 Ok(data)
@@ -55,41 +240,69 @@ impl CoordinateTransform {
         let transformation_matrix = model_transformation_data
             .map(|data| {
                 <[f64; 16]>::try_from(data).map_err(|_| {
-                    TiffError::FormatError(TiffFormatError::Format(format!(
+                    MetadataError::Format(format!(
                         "Number of values in {MODEL_TRANSFORMATION_TAG} must be equal to 16"
-                    )))
+                    ))
                 })
             })
             .transpose()?;
 
         if let Some(transformation_matrix) = transformation_matrix {
             if pixel_scale.is_some() {
-                return Err(TiffError::FormatError(TiffFormatError::Format(
+                return Err(MetadataError::Format(
                     format!("{MODEL_PIXEL_SCALE_TAG} must not be specified when {MODEL_TRANSFORMATION_TAG} is present"),
-                )));
+                ));
             }
             if tie_points.is_some() {
-                return Err(TiffError::FormatError(TiffFormatError::Format(
+                return Err(MetadataError::Format(
                     format!("{MODEL_TIE_POINT_TAG} must not be specified when {MODEL_TRANSFORMATION_TAG} is present"),
-                )));
+                ));
+            }
+
+            // The last row must be [0, 0, 0, 1] for the transformation to be affine
+            if transformation_matrix[12..16] != [0.0, 0.0, 0.0, 1.0] {
+                log::warn!(
+                    "Ignoring non-affine last row {:?} of {MODEL_TRANSFORMATION_TAG}",
+                    &transformation_matrix[12..16]
+                );
             }
 
-            Ok(CoordinateTransform::AffineTransform(
-                AffineTransform::from_tag_matrix(transformation_matrix)?,
-            ))
+            if Affine3DTransform::has_z_terms(&transformation_matrix) {
+                Ok(CoordinateTransform::Affine3D(
+                    Affine3DTransform::from_tag_matrix_with_tolerance(
+                        transformation_matrix,
+                        invertibility_tolerance,
+                    ),
+                ))
+            } else {
+                Ok(CoordinateTransform::AffineTransform(
+                    AffineTransform::from_tag_matrix_with_tolerance(
+                        transformation_matrix,
+                        invertibility_tolerance,
+                    ),
+                ))
+            }
         } else {
             let Some(tie_points) = tie_points else {
-                return Err(TiffError::FormatError(TiffFormatError::Format(
+                return Err(MetadataError::Format(
                     format!("{MODEL_TIE_POINT_TAG} must be present when {MODEL_TRANSFORMATION_TAG} is missing"),
-                )));
+                ));
             };
 
             if tie_points.len() == 6 {
-                let Some(pixel_scale) = pixel_scale else {
-                    return Err(TiffError::FormatError(TiffFormatError::Format(
+                let Some(mut pixel_scale) = pixel_scale else {
+                    return Err(MetadataError::Format(
                         format!("{MODEL_PIXEL_SCALE_TAG} must be specified when {MODEL_TIE_POINT_TAG} contains 6 values"),
-                    )));
+                    ));
                 };
+                if pixel_scale[0] == 0.0 || pixel_scale[1] == 0.0 {
+                    return Err(MetadataError::Format(format!(
+                        "The X and Y values of {MODEL_PIXEL_SCALE_TAG} must not be zero, got {pixel_scale:?}"
+                    )));
+                }
+                if convention == PixelScaleConvention::NorthUp {
+                    pixel_scale[1] = pixel_scale[1].abs();
+                }
 
                 Ok(CoordinateTransform::TiePointAndPixelScale(
                     TiePointAndPixelScale::from_tag_data(&tie_points, &pixel_scale),
@@ -97,71 +310,174 @@ impl CoordinateTransform {
             } else {
                 #[cfg(feature = "tie-points")]
                 {
-                    Ok(CoordinateTransform::TiePoints(
-                        tie_points::TiePoints::from_tie_points(&tie_points),
-                    ))
+                    TiePoints::from_tie_points(&tie_points)
+                        .map(CoordinateTransform::TiePoints)
+                        .ok_or_else(|| {
+                            MetadataError::Format(format!(
+                                "The raster points of {MODEL_TIE_POINT_TAG} must not be collinear"
+                            ))
+                        })
                 }
                 #[cfg(not(feature = "tie-points"))]
                 {
-                    Err(TiffError::FormatError(TiffFormatError::Format(
+                    Err(MetadataError::Format(
                         "Transformation by tie points is not supported".into(),
-                    )))
+                    ))
                 }
             }
         }
     }
 
+    /// Returns whether the raster is north up, i.e. the model X coordinate only depends on the
+    /// column and increases with it, and the model Y coordinate only depends on the row and
+    /// decreases with it. Always `false` for transformations by tie points.
+    pub fn is_north_up(&self) -> bool {
+        #[cfg(feature = "tie-points")]
+        if let CoordinateTransform::TiePoints(_) = self {
+            return false;
+        }
+        let origin = self.transform_to_model(&Coord { x: 0.0, y: 0.0 });
+        let column = self.transform_to_model(&Coord { x: 1.0, y: 0.0 }) - origin;
+        let row = self.transform_to_model(&Coord { x: 0.0, y: 1.0 }) - origin;
+        column.x > 0.0 && column.y == 0.0 && row.x == 0.0 && row.y < 0.0
+    }
+
+    /// Returns whether both transformations map the raster coordinates to the same model X and
+    /// Y coordinates, i.e. the terms of their affine functions differ by at most `epsilon`,
+    /// whatever the variants. Transformations by tie points are never equal.
+    pub fn approx_eq(&self, other: &CoordinateTransform, epsilon: f64) -> bool {
+        match (self.affine_terms(), other.affine_terms()) {
+            (Some(terms), Some(other_terms)) => terms_approx_eq(&terms, &other_terms, epsilon),
+            _ => false,
+        }
+    }
+
+    /// Returns the terms `[a, b, c, d, e, f]` of the transformation if it is affine, where
+    /// `x = a * i + b * j + c` and `y = d * i + e * j + f`.
+    pub(crate) fn affine_terms(&self) -> Option<[f64; 6]> {
+        match self {
+            CoordinateTransform::AffineTransform(transform) => Some(transform.transform),
+            CoordinateTransform::Affine3D(transform) => Some(transform.transform_2d.transform),
+            CoordinateTransform::TiePointAndPixelScale(transform) => {
+                let origin = transform.to_model(&Coord { x: 0.0, y: 0.0 });
+                Some([
+                    transform.pixel_scale.x,
+                    0.0,
+                    origin.x,
+                    0.0,
+                    -transform.pixel_scale.y,
+                    origin.y,
+                ])
+            }
+            #[cfg(feature = "tie-points")]
+            CoordinateTransform::TiePoints(_) => None,
+        }
+    }
+
     pub fn transform_to_model(&self, coord: &Coord) -> Coord {
         match self {
             CoordinateTransform::AffineTransform(transform) => transform.to_model(coord),
+            CoordinateTransform::Affine3D(transform) => transform.to_model(coord),
             CoordinateTransform::TiePointAndPixelScale(transform) => transform.to_model(coord),
             #[cfg(feature = "tie-points")]
             CoordinateTransform::TiePoints(transform) => transform.to_model(coord),
         }
     }
 
-    pub(super) fn transform_to_raster(&self, coord: &Coord) -> Coord {
+    /// Transforms the given model coordinates to raster space.
+    ///
+    /// Fails if the transformation is not invertible.
+    pub fn transform_to_raster(&self, coord: &Coord) -> MetadataResult<Coord> {
         match self {
             CoordinateTransform::AffineTransform(transform) => transform.to_raster(coord),
+            CoordinateTransform::Affine3D(transform) => transform.to_raster(coord),
             CoordinateTransform::TiePointAndPixelScale(transform) => transform.to_raster(coord),
             #[cfg(feature = "tie-points")]
             CoordinateTransform::TiePoints(transform) => transform.to_raster(coord),
         }
     }
+
+    /// Transforms all coordinates of the given geometry from raster space to model space.
+    pub fn geometry_to_model<G: TransformCoords>(&self, geometry: &G) -> G::Output {
+        geometry.map_coords(&|coord| self.transform_to_model(&coord))
+    }
+
+    /// Transforms all coordinates of the given geometry from model space to raster space.
+    pub fn geometry_to_raster<G: TransformCoords>(
+        &self,
+        geometry: &G,
+    ) -> MetadataResult<G::Output> {
+        geometry.try_map_coords(&|coord| self.transform_to_raster(&coord))
+    }
+}
+
+impl fmt::Display for CoordinateTransform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoordinateTransform::AffineTransform(transform) => transform.fmt(f),
+            CoordinateTransform::Affine3D(transform) => transform.fmt(f),
+            CoordinateTransform::TiePointAndPixelScale(transform) => transform.fmt(f),
+            #[cfg(feature = "tie-points")]
+            CoordinateTransform::TiePoints(transform) => transform.fmt(f),
+        }
+    }
+}
+
+fn terms_approx_eq(terms: &[f64; 6], other_terms: &[f64; 6], epsilon: f64) -> bool {
+    terms
+        .iter()
+        .zip(other_terms)
+        .all(|(term, other_term)| (term - other_term).abs() <= epsilon)
 }
 
+/// An affine transformation between raster space and model space.
+///
+/// The transformation may not be invertible, in which case [AffineTransform::to_raster] fails.
 #[derive(Debug)]
 pub struct AffineTransform {
     transform: [f64; 6],
-    inverse_transform: [f64; 6],
+    inverse_transform: Option<[f64; 6]>,
 }
 
 impl AffineTransform {
-    pub fn from_tag_matrix(matrix: [f64; 16]) -> TiffResult<Self> {
+    pub fn from_tag_matrix(matrix: [f64; 16]) -> Self {
+        Self::from_tag_matrix_with_tolerance(matrix, DEFAULT_INVERTIBILITY_TOLERANCE)
+    }
+
+    /// Creates the transformation from the given ModelTransformationTag matrix.
+    ///
+    /// The matrix is considered not invertible if its determinant is smaller than `tolerance`
+    /// relative to the magnitude of the terms it is computed from, which makes the check
+    /// independent of the pixel size.
+    pub fn from_tag_matrix_with_tolerance(matrix: [f64; 16], tolerance: f64) -> Self {
         let transform = [
             matrix[0], matrix[1], matrix[3], matrix[4], matrix[5], matrix[7],
         ];
 
         let det = transform[0] * transform[4] - transform[1] * transform[3];
-        if det.abs() < 0.000000000000001 {
-            return Err(TiffError::FormatError(TiffFormatError::Format(
-                String::from("Provided transformation matrix is not invertible"),
-            )));
-        }
-
-        let inverse_transform = [
-            transform[4] / det,
-            -transform[1] / det,
-            (transform[1] * transform[5] - transform[2] * transform[4]) / det,
-            -transform[3] / det,
-            transform[0] / det,
-            (-transform[0] * transform[5] + transform[2] * transform[3]) / det,
-        ];
+        let magnitude = (transform[0] * transform[4]).abs() + (transform[1] * transform[3]).abs();
+        let inverse_transform = if magnitude == 0.0 || det.abs() <= tolerance * magnitude {
+            None
+        } else {
+            Some([
+                transform[4] / det,
+                -transform[1] / det,
+                (transform[1] * transform[5] - transform[2] * transform[4]) / det,
+                -transform[3] / det,
+                transform[0] / det,
+                (-transform[0] * transform[5] + transform[2] * transform[3]) / det,
+            ])
+        };
 
-        Ok(AffineTransform {
+        AffineTransform {
             transform,
             inverse_transform,
-        })
+        }
+    }
+
+    /// Returns whether the transformation from model space to raster space is defined.
+    pub fn is_invertible(&self) -> bool {
+        self.inverse_transform.is_some()
     }
 
     fn transform(matrix: &[f64; 6], coord: &Coord) -> Coord {
@@ -175,8 +491,173 @@ impl AffineTransform {
         Self::transform(&self.transform, coord)
     }
 
-    pub fn to_raster(&self, coord: &Coord) -> Coord {
-        Self::transform(&self.inverse_transform, coord)
+    pub fn to_raster(&self, coord: &Coord) -> MetadataResult<Coord> {
+        match &self.inverse_transform {
+            Some(inverse_transform) => Ok(Self::transform(inverse_transform, coord)),
+            None => Err(MetadataError::Format(
+                "Provided transformation matrix is not invertible".into(),
+            )),
+        }
+    }
+
+    /// Returns whether the terms of both transformations differ by at most `epsilon`, see
+    /// [CoordinateTransform::approx_eq].
+    pub fn approx_eq(&self, other: &AffineTransform, epsilon: f64) -> bool {
+        terms_approx_eq(&self.transform, &other.transform, epsilon)
+    }
+
+    /// Returns the ModelTransformationTag matrix of the transformation, e.g. to attach it to a
+    /// derived raster.
+    pub fn to_tag_matrix(&self) -> [f64; 16] {
+        let [a, b, c, d, e, f] = self.transform;
+        #[rustfmt::skip]
+        let matrix = [
+            a, b, 0.0, c,
+            d, e, 0.0, f,
+            0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        ];
+        matrix
+    }
+
+    /// Returns the transformation of the raster whose pixel `(0, 0)` is the pixel `(x, y)` of
+    /// this raster, e.g. a window of it.
+    pub fn cropped(&self, x: f64, y: f64) -> Self {
+        self.with_raster_mapping(1.0, Coord { x, y })
+    }
+
+    /// Returns the transformation of the raster with `left` columns and `top` rows added before
+    /// those of this raster. Columns and rows added after them do not change the transformation.
+    pub fn padded(&self, left: f64, top: f64) -> Self {
+        self.cropped(-left, -top)
+    }
+
+    /// Returns the transformation of the raster whose pixels cover `factor` x `factor` pixels of
+    /// this raster, starting from the same corner, e.g. an overview.
+    ///
+    /// The pixel corners are at the raster coordinates -0.5 for
+    /// [RasterType::RasterPixelIsPoint], and 0 otherwise.
+    pub fn decimated(&self, factor: f64, raster_type: RasterType) -> Self {
+        let raster_offset = match raster_type {
+            RasterType::RasterPixelIsPoint => -0.5,
+            _ => 0.0,
+        };
+        let shift = -raster_offset * (factor - 1.0);
+        self.with_raster_mapping(factor, Coord { x: shift, y: shift })
+    }
+
+    /// Returns the transformation of the raster whose coordinates `p` are `scale * p + offset`
+    /// in this raster.
+    fn with_raster_mapping(&self, scale: f64, offset: Coord) -> Self {
+        let [a, b, c, d, e, f] = self.transform;
+        let transform = [
+            a * scale,
+            b * scale,
+            a * offset.x + b * offset.y + c,
+            d * scale,
+            e * scale,
+            d * offset.x + e * offset.y + f,
+        ];
+        let inverse_transform = self.inverse_transform.map(|[a, b, c, d, e, f]| {
+            [
+                a / scale,
+                b / scale,
+                (c - offset.x) / scale,
+                d / scale,
+                e / scale,
+                (f - offset.y) / scale,
+            ]
+        });
+        AffineTransform {
+            transform,
+            inverse_transform,
+        }
+    }
+}
+
+/// Shows the model coordinates `(x, y)` as a function of the raster coordinates `(i, j)`.
+impl fmt::Display for AffineTransform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.transform;
+        write!(
+            f,
+            "Affine transformation: x = {a} * i + {b} * j + {c}, y = {d} * i + {e} * j + {g}"
+        )?;
+        if !self.is_invertible() {
+            write!(f, " (not invertible)")?;
+        }
+        Ok(())
+    }
+}
+
+/// An affine transformation which also maps the Z axis, as given by a ModelTransformationTag
+/// whose third row or column is in use.
+///
+/// Raster coordinates are 2D with an implicit Z of 0, so [Affine3DTransform::to_model] and
+/// [Affine3DTransform::to_raster] behave like [AffineTransform]. The full matrix is available
+/// through [Affine3DTransform::matrix] and [Affine3DTransform::to_model_3d].
+#[derive(Debug)]
+pub struct Affine3DTransform {
+    matrix: [f64; 16],
+    transform_2d: AffineTransform,
+}
+
+impl Affine3DTransform {
+    pub fn from_tag_matrix(matrix: [f64; 16]) -> Self {
+        Self::from_tag_matrix_with_tolerance(matrix, DEFAULT_INVERTIBILITY_TOLERANCE)
+    }
+
+    /// See [AffineTransform::from_tag_matrix_with_tolerance].
+    pub fn from_tag_matrix_with_tolerance(matrix: [f64; 16], tolerance: f64) -> Self {
+        Affine3DTransform {
+            matrix,
+            transform_2d: AffineTransform::from_tag_matrix_with_tolerance(matrix, tolerance),
+        }
+    }
+
+    /// Returns whether the matrix maps Z in any other way than the identity or the zero map.
+    fn has_z_terms(matrix: &[f64; 16]) -> bool {
+        [matrix[2], matrix[6], matrix[8], matrix[9], matrix[11]]
+            .iter()
+            .any(|value| *value != 0.0)
+            || (matrix[10] != 0.0 && matrix[10] != 1.0)
+    }
+
+    /// Returns the full 4x4 matrix in row-major order.
+    pub fn matrix(&self) -> &[f64; 16] {
+        &self.matrix
+    }
+
+    pub fn to_model(&self, coord: &Coord) -> Coord {
+        self.transform_2d.to_model(coord)
+    }
+
+    pub fn to_raster(&self, coord: &Coord) -> MetadataResult<Coord> {
+        self.transform_2d.to_raster(coord)
+    }
+
+    /// Transforms the given raster coordinates including Z to model space.
+    pub fn to_model_3d(&self, coord: [f64; 3]) -> [f64; 3] {
+        let m = &self.matrix;
+        let [x, y, z] = coord;
+        [
+            m[0] * x + m[1] * y + m[2] * z + m[3],
+            m[4] * x + m[5] * y + m[6] * z + m[7],
+            m[8] * x + m[9] * y + m[10] * z + m[11],
+        ]
+    }
+}
+
+impl fmt::Display for Affine3DTransform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Affine 3D transformation: [")?;
+        for (index, row) in self.matrix.chunks_exact(4).enumerate() {
+            if index > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}, {}, {}, {}", row[0], row[1], row[2], row[3])?;
+        }
+        write!(f, "]")
     }
 }
 
@@ -212,10 +693,39 @@ impl TiePointAndPixelScale {
         }
     }
 
-    pub fn to_raster(&self, coord: &Coord) -> Coord {
-        Coord {
+    /// Returns whether the transformation from model space to raster space is defined, i.e. the
+    /// X and Y pixel scales are finite and not zero.
+    pub fn is_invertible(&self) -> bool {
+        [self.pixel_scale.x, self.pixel_scale.y]
+            .iter()
+            .all(|scale| scale.is_finite() && *scale != 0.0)
+    }
+
+    pub fn to_raster(&self, coord: &Coord) -> MetadataResult<Coord> {
+        if !self.is_invertible() {
+            return Err(MetadataError::Format(format!(
+                "Pixel scale ({}, {}) is not invertible",
+                self.pixel_scale.x, self.pixel_scale.y
+            )));
+        }
+        Ok(Coord {
             x: (coord.x - self.model_point.x) / self.pixel_scale.x + self.raster_point.x,
             y: (coord.y - self.model_point.y) / -self.pixel_scale.y + self.raster_point.y,
-        }
+        })
+    }
+}
+
+impl fmt::Display for TiePointAndPixelScale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Tie point ({}, {}) -> ({}, {}), pixel scale ({}, {})",
+            self.raster_point.x,
+            self.raster_point.y,
+            self.model_point.x,
+            self.model_point.y,
+            self.pixel_scale.x,
+            self.pixel_scale.y
+        )
     }
 }
@@ -0,0 +1,157 @@
+use geo_types::Coord;
+
+use crate::{
+    AffineTransform, CoordinateTransform, MetadataError, MetadataResult,
+    DEFAULT_INVERTIBILITY_TOLERANCE,
+};
+
+/// A tie point of the ModelTiepointTag, which ties the raster coordinates `(I, J, K)` to the
+/// model coordinates `(X, Y, Z)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TiePoint {
+    pub raster: [f64; 3],
+    pub model: [f64; 3],
+}
+
+/// The differences between the model coordinates of tie points and those given by a
+/// transformation of their raster coordinates, see [CoordinateTransform::residuals].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Residuals {
+    /// The transformed minus the tied model coordinates of each tie point, in the same order.
+    pub errors: Vec<Coord>,
+}
+
+impl TiePoint {
+    /// Parses the values of a ModelTiepointTag, ignoring a trailing incomplete tie point.
+    pub fn from_tag_data(tie_points: &[f64]) -> Vec<TiePoint> {
+        tie_points
+            .chunks_exact(6)
+            .map(|values| TiePoint {
+                raster: [values[0], values[1], values[2]],
+                model: [values[3], values[4], values[5]],
+            })
+            .collect()
+    }
+
+    pub fn raster_coord(&self) -> Coord {
+        Coord {
+            x: self.raster[0],
+            y: self.raster[1],
+        }
+    }
+
+    pub fn model_coord(&self) -> Coord {
+        Coord {
+            x: self.model[0],
+            y: self.model[1],
+        }
+    }
+}
+
+impl Residuals {
+    /// Returns the root mean square of the lengths of the errors, or 0 if there are none.
+    pub fn rms(&self) -> f64 {
+        if self.errors.is_empty() {
+            return 0.0;
+        }
+        let sum = self
+            .errors
+            .iter()
+            .map(|error| error.x * error.x + error.y * error.y)
+            .sum::<f64>();
+        (sum / self.errors.len() as f64).sqrt()
+    }
+
+    /// Returns the largest length of the errors, or 0 if there are none.
+    pub fn max(&self) -> f64 {
+        self.errors
+            .iter()
+            .map(|error| error.x.hypot(error.y))
+            .fold(0.0, f64::max)
+    }
+}
+
+impl CoordinateTransform {
+    /// Returns the residuals of the given tie points for this transformation, e.g. to audit a
+    /// transformation estimated from them. The Z coordinates are ignored.
+    pub fn residuals(&self, tie_points: &[TiePoint]) -> Residuals {
+        Residuals {
+            errors: tie_points
+                .iter()
+                .map(|tie_point| {
+                    self.transform_to_model(&tie_point.raster_coord()) - tie_point.model_coord()
+                })
+                .collect(),
+        }
+    }
+}
+
+impl AffineTransform {
+    pub fn fit_from_tie_points(tie_points: &[TiePoint]) -> MetadataResult<(Self, Residuals)> {
+        Self::fit_from_tie_points_with_tolerance(tie_points, DEFAULT_INVERTIBILITY_TOLERANCE)
+    }
+
+    /// Estimates the affine transformation which best fits the given tie points by least
+    /// squares, along with its residuals. The Z coordinates are ignored.
+    ///
+    /// Fails if the raster points are collinear, e.g. if there are fewer than 3 of them. See
+    /// [AffineTransform::from_tag_matrix_with_tolerance] for the `tolerance`.
+    pub fn fit_from_tie_points_with_tolerance(
+        tie_points: &[TiePoint],
+        tolerance: f64,
+    ) -> MetadataResult<(Self, Residuals)> {
+        let count = tie_points.len() as f64;
+        let mean = |coord: fn(&TiePoint) -> Coord| {
+            tie_points
+                .iter()
+                .map(coord)
+                .fold(Coord::zero(), |a, b| a + b)
+                / count
+        };
+        let raster_mean = mean(TiePoint::raster_coord);
+        let model_mean = mean(TiePoint::model_coord);
+
+        // Normal equations of the centered coordinates
+        let (mut sii, mut sij, mut sjj) = (0.0, 0.0, 0.0);
+        let (mut si, mut sj) = (Coord::zero(), Coord::zero());
+        for tie_point in tie_points {
+            let raster = tie_point.raster_coord() - raster_mean;
+            let model = tie_point.model_coord() - model_mean;
+            sii += raster.x * raster.x;
+            sij += raster.x * raster.y;
+            sjj += raster.y * raster.y;
+            si = si + model * raster.x;
+            sj = sj + model * raster.y;
+        }
+        let det = sii * sjj - sij * sij;
+        if det.is_nan() || det <= tolerance * sii * sjj {
+            return Err(MetadataError::Format(format!(
+                "Cannot fit an affine transformation to {} collinear tie points",
+                tie_points.len()
+            )));
+        }
+        let a = (si * sjj - sj * sij) / det;
+        let b = (sj * sii - si * sij) / det;
+        let c = model_mean - a * raster_mean.x - b * raster_mean.y;
+
+        #[rustfmt::skip]
+        let transform = AffineTransform::from_tag_matrix_with_tolerance(
+            [
+                a.x, b.x, 0.0, c.x,
+                a.y, b.y, 0.0, c.y,
+                0.0, 0.0, 0.0, 0.0,
+                0.0, 0.0, 0.0, 1.0,
+            ],
+            tolerance,
+        );
+        let residuals = Residuals {
+            errors: tie_points
+                .iter()
+                .map(|tie_point| {
+                    transform.to_model(&tie_point.raster_coord()) - tie_point.model_coord()
+                })
+                .collect(),
+        };
+        Ok((transform, residuals))
+    }
+}
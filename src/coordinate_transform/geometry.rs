@@ -0,0 +1,170 @@
+use std::convert::Infallible;
+
+use geo_types::{
+    Coord, Geometry, GeometryCollection, Line, LineString, MultiLineString, MultiPoint,
+    MultiPolygon, Point, Polygon, Triangle,
+};
+
+/// Geometries whose coordinates can be mapped one by one, e.g. between raster and model space.
+///
+/// This mirrors the `MapCoords` trait of the `geo` crate, which is not part of `geo-types`.
+pub trait TransformCoords: Sized {
+    /// Output type of the mapping. Only differs from `Self` for [Geometry] as a [geo_types::Rect]
+    /// does not stay axis-aligned under a general transformation.
+    type Output;
+
+    fn try_map_coords<E>(
+        &self,
+        func: &impl Fn(Coord) -> Result<Coord, E>,
+    ) -> Result<Self::Output, E>;
+
+    fn map_coords(&self, func: &impl Fn(Coord) -> Coord) -> Self::Output {
+        match self.try_map_coords(&|coord| Ok::<_, Infallible>(func(coord))) {
+            Ok(output) => output,
+            Err(infallible) => match infallible {},
+        }
+    }
+}
+
+impl TransformCoords for Coord {
+    type Output = Coord;
+
+    fn try_map_coords<E>(&self, func: &impl Fn(Coord) -> Result<Coord, E>) -> Result<Coord, E> {
+        func(*self)
+    }
+}
+
+impl TransformCoords for Point {
+    type Output = Point;
+
+    fn try_map_coords<E>(&self, func: &impl Fn(Coord) -> Result<Coord, E>) -> Result<Point, E> {
+        Ok(Point(func(self.0)?))
+    }
+}
+
+impl TransformCoords for Line {
+    type Output = Line;
+
+    fn try_map_coords<E>(&self, func: &impl Fn(Coord) -> Result<Coord, E>) -> Result<Line, E> {
+        Ok(Line::new(func(self.start)?, func(self.end)?))
+    }
+}
+
+impl TransformCoords for LineString {
+    type Output = LineString;
+
+    fn try_map_coords<E>(
+        &self,
+        func: &impl Fn(Coord) -> Result<Coord, E>,
+    ) -> Result<LineString, E> {
+        self.0
+            .iter()
+            .map(|coord| func(*coord))
+            .collect::<Result<Vec<_>, _>>()
+            .map(LineString::new)
+    }
+}
+
+impl TransformCoords for Polygon {
+    type Output = Polygon;
+
+    fn try_map_coords<E>(&self, func: &impl Fn(Coord) -> Result<Coord, E>) -> Result<Polygon, E> {
+        Ok(Polygon::new(
+            self.exterior().try_map_coords(func)?,
+            self.interiors()
+                .iter()
+                .map(|interior| interior.try_map_coords(func))
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+}
+
+impl TransformCoords for Triangle {
+    type Output = Triangle;
+
+    fn try_map_coords<E>(&self, func: &impl Fn(Coord) -> Result<Coord, E>) -> Result<Triangle, E> {
+        let [v1, v2, v3] = self.to_array();
+        Ok(Triangle::new(func(v1)?, func(v2)?, func(v3)?))
+    }
+}
+
+impl TransformCoords for MultiPoint {
+    type Output = MultiPoint;
+
+    fn try_map_coords<E>(
+        &self,
+        func: &impl Fn(Coord) -> Result<Coord, E>,
+    ) -> Result<MultiPoint, E> {
+        self.0
+            .iter()
+            .map(|point| point.try_map_coords(func))
+            .collect::<Result<Vec<_>, _>>()
+            .map(MultiPoint::new)
+    }
+}
+
+impl TransformCoords for MultiLineString {
+    type Output = MultiLineString;
+
+    fn try_map_coords<E>(
+        &self,
+        func: &impl Fn(Coord) -> Result<Coord, E>,
+    ) -> Result<MultiLineString, E> {
+        self.0
+            .iter()
+            .map(|line_string| line_string.try_map_coords(func))
+            .collect::<Result<Vec<_>, _>>()
+            .map(MultiLineString::new)
+    }
+}
+
+impl TransformCoords for MultiPolygon {
+    type Output = MultiPolygon;
+
+    fn try_map_coords<E>(
+        &self,
+        func: &impl Fn(Coord) -> Result<Coord, E>,
+    ) -> Result<MultiPolygon, E> {
+        self.0
+            .iter()
+            .map(|polygon| polygon.try_map_coords(func))
+            .collect::<Result<Vec<_>, _>>()
+            .map(MultiPolygon::new)
+    }
+}
+
+impl TransformCoords for GeometryCollection {
+    type Output = GeometryCollection;
+
+    fn try_map_coords<E>(
+        &self,
+        func: &impl Fn(Coord) -> Result<Coord, E>,
+    ) -> Result<GeometryCollection, E> {
+        self.0
+            .iter()
+            .map(|geometry| geometry.try_map_coords(func))
+            .collect::<Result<Vec<_>, _>>()
+            .map(GeometryCollection::new_from)
+    }
+}
+
+impl TransformCoords for Geometry {
+    type Output = Geometry;
+
+    fn try_map_coords<E>(&self, func: &impl Fn(Coord) -> Result<Coord, E>) -> Result<Geometry, E> {
+        Ok(match self {
+            Geometry::Point(g) => Geometry::Point(g.try_map_coords(func)?),
+            Geometry::Line(g) => Geometry::Line(g.try_map_coords(func)?),
+            Geometry::LineString(g) => Geometry::LineString(g.try_map_coords(func)?),
+            Geometry::Polygon(g) => Geometry::Polygon(g.try_map_coords(func)?),
+            Geometry::MultiPoint(g) => Geometry::MultiPoint(g.try_map_coords(func)?),
+            Geometry::MultiLineString(g) => Geometry::MultiLineString(g.try_map_coords(func)?),
+            Geometry::MultiPolygon(g) => Geometry::MultiPolygon(g.try_map_coords(func)?),
+            Geometry::GeometryCollection(g) => {
+                Geometry::GeometryCollection(g.try_map_coords(func)?)
+            }
+            Geometry::Rect(g) => Geometry::Polygon(g.to_polygon().try_map_coords(func)?),
+            Geometry::Triangle(g) => Geometry::Triangle(g.try_map_coords(func)?),
+        })
+    }
+}
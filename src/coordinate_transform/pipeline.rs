@@ -0,0 +1,96 @@
+use std::fmt;
+
+use geo_types::Coord;
+
+use super::{CoordinateTransform, Precision, TransformCoords};
+use crate::MetadataResult;
+
+/// A reprojection of model coordinates from one CRS to another.
+pub type Reprojection<'a> = Box<dyn Fn(Coord) -> MetadataResult<Coord> + 'a>;
+
+/// A transformation from the raster space of one image to the raster space of another, going
+/// through model space and an optional reprojection between their CRS.
+///
+/// See [CoordinateTransform::then] and [compose].
+pub struct TransformPipeline<'a> {
+    source: &'a CoordinateTransform,
+    reprojection: Option<Reprojection<'a>>,
+    target: &'a CoordinateTransform,
+}
+
+impl CoordinateTransform {
+    /// Returns the transformation from raster space of this transform to raster space of `other`,
+    /// for images sharing the same CRS.
+    pub fn then<'a>(&'a self, other: &'a CoordinateTransform) -> TransformPipeline<'a> {
+        TransformPipeline {
+            source: self,
+            reprojection: None,
+            target: other,
+        }
+    }
+}
+
+/// Returns the transformation from raster space of `source` to raster space of `target`, applying
+/// `reprojection` to the model coordinates of `source` to obtain those of `target`.
+pub fn compose<'a, F>(
+    source: &'a CoordinateTransform,
+    reprojection: F,
+    target: &'a CoordinateTransform,
+) -> TransformPipeline<'a>
+where
+    F: Fn(Coord) -> MetadataResult<Coord> + 'a,
+{
+    TransformPipeline {
+        source,
+        reprojection: Some(Box::new(reprojection)),
+        target,
+    }
+}
+
+impl TransformPipeline<'_> {
+    /// Transforms the given raster coordinates of the source to raster coordinates of the target.
+    ///
+    /// Fails if the reprojection fails or if the target transformation is not invertible.
+    pub fn transform(&self, coord: &Coord) -> MetadataResult<Coord> {
+        let model = self.source.transform_to_model(coord);
+        let model = match &self.reprojection {
+            Some(reprojection) => reprojection(model)?,
+            None => model,
+        };
+        self.target.transform_to_raster(&model)
+    }
+
+    /// Transforms all coordinates of the given geometry, see [TransformPipeline::transform].
+    pub fn transform_geometry<G: TransformCoords>(
+        &self,
+        geometry: &G,
+    ) -> MetadataResult<G::Output> {
+        geometry.try_map_coords(&|coord| self.transform(&coord))
+    }
+
+    /// Transforms the given raster coordinates of the source to raster coordinates of the target
+    /// in place, with the given precision. The reprojection is applied to each coordinate in
+    /// `f64`, and the errors of [Precision::F32] add up for both transformations.
+    ///
+    /// Fails like [TransformPipeline::transform], in which case the coordinates are left in an
+    /// unspecified state.
+    pub fn transform_bulk(&self, coords: &mut [Coord], precision: Precision) -> MetadataResult<()> {
+        self.source.transform_to_model_bulk(coords, precision);
+        if let Some(reprojection) = &self.reprojection {
+            for coord in coords.iter_mut() {
+                *coord = reprojection(*coord)?;
+            }
+        }
+        self.target.transform_to_raster_bulk(coords, precision)
+    }
+}
+
+impl fmt::Debug for TransformPipeline<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransformPipeline")
+            .field("source", self.source)
+            .field("reprojection", &self.reprojection.is_some())
+            .field("target", self.target)
+            .finish()
+    }
+}
@@ -0,0 +1,74 @@
+use geo_types::Coord;
+
+use super::CoordinateTransform;
+use crate::simd::apply_linear_f32;
+use crate::MetadataResult;
+
+/// The floating point precision of the bulk transformations, e.g.
+/// [CoordinateTransform::transform_to_model_bulk].
+///
+/// The single coordinate transformations always compute in `f64`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Precision {
+    /// All the computations are in `f64`, giving the same results as the single coordinate
+    /// transformations.
+    #[default]
+    F64,
+    /// The affine transformations are applied in `f64` to the first coordinate, and in `f32` to
+    /// the offsets of the others from it, e.g. for the vertices of a mesh or a tile, which are
+    /// close to each other.
+    ///
+    /// The offsets from the first coordinate are rounded to `f32`, as are the linear terms of
+    /// the transformation, so that with `x = a * i + b * j + c`, the output X coordinate is off
+    /// by at most about `2^-22 * (|a * di| + |b * dj|)`, where `di` and `dj` are the offsets
+    /// from the first input coordinate, and likewise for Y. For a north up raster, this is
+    /// `2^-22` times the offset from the first output coordinate, e.g. 2.4 mm for a point 10 km
+    /// away in a projected CRS in meters, whatever the absolute position, e.g. millions of
+    /// meters in UTM. The transformations by tie points are always computed in `f64`.
+    F32,
+}
+
+impl CoordinateTransform {
+    /// Transforms the given raster coordinates to model space in place, with the given
+    /// precision.
+    pub fn transform_to_model_bulk(&self, coords: &mut [Coord], precision: Precision) {
+        match (precision, self.affine_terms(), coords.first().copied()) {
+            (Precision::F32, Some([a, b, _, d, e, _]), Some(origin)) => {
+                let model_origin = self.transform_to_model(&origin);
+                apply_linear_f32(coords, [a, b, d, e], origin, model_origin);
+            }
+            _ => {
+                for coord in coords {
+                    *coord = self.transform_to_model(coord);
+                }
+            }
+        }
+    }
+
+    /// Transforms the given model coordinates to raster space in place, with the given
+    /// precision.
+    ///
+    /// Fails if the transformation is not invertible, in which case the coordinates are left in
+    /// an unspecified state.
+    pub fn transform_to_raster_bulk(
+        &self,
+        coords: &mut [Coord],
+        precision: Precision,
+    ) -> MetadataResult<()> {
+        match (precision, self.affine_terms(), coords.first().copied()) {
+            (Precision::F32, Some([a, b, _, d, e, _]), Some(origin)) => {
+                // Fails first if the determinant is zero
+                let raster_origin = self.transform_to_raster(&origin)?;
+                let det = a * e - b * d;
+                let inverse = [e / det, -b / det, -d / det, a / det];
+                apply_linear_f32(coords, inverse, origin, raster_origin);
+            }
+            _ => {
+                for coord in coords {
+                    *coord = self.transform_to_raster(coord)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,22 +1,46 @@
 use std::array;
-use std::rc::Rc;
+use std::fmt;
+use std::sync::Arc;
 
 use delaunator::{Point, Triangulation};
 use geo_index::rtree::sort::STRSort;
 use geo_index::rtree::{OwnedRTree, RTreeBuilder, RTreeIndex};
 use geo_types::Coord;
-use tiff::TiffResult;
 
+use crate::{MetadataError, MetadataResult};
+
+/// A transformation by tie points, interpolated over a triangular mesh of the points.
+///
+/// The faces of the meshes are indexed by R-trees of their envelopes, so the face containing
+/// some coordinates is found in logarithmic time of the number of tie points.
+///
+/// If the raster points form a regular grid, see [TiePoints::grid], the transformation to model
+/// space is instead interpolated bilinearly in the cell of the grid containing the raster
+/// coordinates, which is found in constant time.
 #[derive(Debug)]
 pub struct TiePoints {
-    raster_mesh: Rc<Vec<Face>>,
+    raster_mesh: Arc<Vec<Face>>,
     raster_index: OwnedRTree<f64>,
-    model_mesh: Rc<Vec<Face>>,
+    model_mesh: Arc<Vec<Face>>,
     model_index: OwnedRTree<f64>,
+    grid: Option<TiePointGrid>,
+}
+
+/// Tie points whose raster points are `origin + (column * spacing.x, row * spacing.y)` for all
+/// the columns and rows of a grid, e.g. the ground control points of satellite products.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TiePointGrid {
+    origin: Coord,
+    spacing: Coord,
+    columns: usize,
+    rows: usize,
+    model_points: Vec<Coord>,
 }
 
 impl TiePoints {
-    pub fn from_tie_points(tie_points: &[f64]) -> Self {
+    /// Builds the meshes of the tie points, or returns `None` if the raster points are all
+    /// collinear.
+    pub fn from_tie_points(tie_points: &[f64]) -> Option<Self> {
         let capacity = tie_points.iter().len() / 6;
         let mut raster_points = Vec::with_capacity(capacity);
         let mut model_points = Vec::with_capacity(capacity);
@@ -33,51 +57,249 @@ impl TiePoints {
         }
 
         let triangulation = delaunator::triangulate(&raster_points);
-        let raster_mesh = Rc::new(build_faces(raster_points, &triangulation));
-        let model_mesh = Rc::new(build_faces(model_points, &triangulation));
+        if triangulation.triangles.is_empty() {
+            return None;
+        }
+        let grid = TiePointGrid::from_points(&raster_points, &model_points);
+        let raster_mesh = Arc::new(build_faces(raster_points, &triangulation));
+        let model_mesh = Arc::new(build_faces(model_points, &triangulation));
         let raster_index = build_index(&raster_mesh);
         let model_index = build_index(&model_mesh);
 
-        TiePoints {
+        Some(TiePoints {
             raster_mesh: raster_mesh.clone(),
             raster_index,
             model_mesh: model_mesh.clone(),
             model_index,
-        }
+            grid,
+        })
+    }
+
+    /// Returns the grid formed by the tie points, if they form a regular grid.
+    pub fn grid(&self) -> Option<&TiePointGrid> {
+        self.grid.as_ref()
     }
 
+    /// Returns NaN coordinates if no face of the mesh contains the raster coordinates, e.g. if
+    /// they are not finite.
     pub fn to_model(&self, coord: &Coord) -> Coord {
+        if let Some(grid) = &self.grid {
+            return grid.to_model(coord);
+        }
         transform_by_tie_points(
             &self.raster_index,
             &self.raster_mesh,
             &self.model_mesh,
             coord,
         )
+        .unwrap_or(Coord {
+            x: f64::NAN,
+            y: f64::NAN,
+        })
     }
 
-    pub fn to_raster(&self, coord: &Coord) -> Coord {
-        transform_by_tie_points(
-            &self.model_index,
-            &self.model_mesh,
-            &self.raster_mesh,
-            coord,
-        )
+    /// Fails if no face of the mesh contains the model coordinates, e.g. if they are not finite.
+    ///
+    /// For a grid, the bilinear interpolation of [TiePoints::to_model] is inverted instead, and
+    /// the mesh is only used if this does not converge.
+    pub fn to_raster(&self, coord: &Coord) -> MetadataResult<Coord> {
+        self.grid
+            .as_ref()
+            .and_then(|grid| grid.to_raster(coord))
+            .or_else(|| {
+                transform_by_tie_points(
+                    &self.model_index,
+                    &self.model_mesh,
+                    &self.raster_mesh,
+                    coord,
+                )
+            })
+            .ok_or_else(|| {
+                MetadataError::Format(format!(
+                    "Model coordinates ({}, {}) are not covered by the tie points mesh",
+                    coord.x, coord.y
+                ))
+            })
+    }
+}
+
+impl TiePointGrid {
+    /// Returns the grid formed by the raster points, or `None` if they do not form a regular grid
+    /// of at least 2 x 2 points, each point appearing once.
+    fn from_points(raster_points: &[Point], model_points: &[Point]) -> Option<Self> {
+        let (origin_x, spacing_x, columns) = regular_axis(raster_points.iter().map(|p| p.x))?;
+        let (origin_y, spacing_y, rows) = regular_axis(raster_points.iter().map(|p| p.y))?;
+        if columns * rows != raster_points.len() {
+            return None;
+        }
+
+        let mut grid_points = vec![None; raster_points.len()];
+        for (raster_point, model_point) in raster_points.iter().zip(model_points) {
+            let column = ((raster_point.x - origin_x) / spacing_x).round() as usize;
+            let row = ((raster_point.y - origin_y) / spacing_y).round() as usize;
+            let grid_point = grid_points.get_mut(row * columns + column)?;
+            if grid_point.is_some() {
+                return None;
+            }
+            *grid_point = Some(Coord {
+                x: model_point.x,
+                y: model_point.y,
+            });
+        }
+
+        Some(TiePointGrid {
+            origin: Coord {
+                x: origin_x,
+                y: origin_y,
+            },
+            spacing: Coord {
+                x: spacing_x,
+                y: spacing_y,
+            },
+            columns,
+            rows,
+            model_points: grid_points.into_iter().collect::<Option<_>>()?,
+        })
+    }
+
+    /// Returns the raster coordinates of the first grid point.
+    pub fn origin(&self) -> Coord {
+        self.origin
+    }
+
+    /// Returns the distance in raster space between consecutive columns and rows of points.
+    pub fn spacing(&self) -> Coord {
+        self.spacing
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Returns the model point tied to the raster point at the given column and row.
+    pub fn model_point(&self, column: usize, row: usize) -> Option<Coord> {
+        if column >= self.columns || row >= self.rows {
+            return None;
+        }
+        Some(self.model_points[row * self.columns + column])
+    }
+
+    /// Interpolates the model points bilinearly, extrapolating the outer cells beyond the grid.
+    fn to_model(&self, coord: &Coord) -> Coord {
+        self.to_model_with_derivatives(coord).0
+    }
+
+    /// Returns the model coordinates along with their derivatives with respect to the raster X
+    /// and Y coordinates.
+    fn to_model_with_derivatives(&self, coord: &Coord) -> (Coord, Coord, Coord) {
+        let u = (coord.x - self.origin.x) / self.spacing.x;
+        let v = (coord.y - self.origin.y) / self.spacing.y;
+        // NaN coordinates are cast to 0 and remain NaN after interpolation
+        let column = u.floor().clamp(0.0, (self.columns - 2) as f64) as usize;
+        let row = v.floor().clamp(0.0, (self.rows - 2) as f64) as usize;
+        let (fu, fv) = (u - column as f64, v - row as f64);
+
+        let index = row * self.columns + column;
+        let p00 = self.model_points[index];
+        let p10 = self.model_points[index + 1];
+        let p01 = self.model_points[index + self.columns];
+        let p11 = self.model_points[index + self.columns + 1];
+
+        let model = p00 * ((1.0 - fu) * (1.0 - fv))
+            + p10 * (fu * (1.0 - fv))
+            + p01 * ((1.0 - fu) * fv)
+            + p11 * (fu * fv);
+        let dx = ((p10 - p00) * (1.0 - fv) + (p11 - p01) * fv) / self.spacing.x;
+        let dy = ((p01 - p00) * (1.0 - fu) + (p11 - p10) * fu) / self.spacing.y;
+        (model, dx, dy)
+    }
+
+    /// Inverts the bilinear interpolation with Newton's method, starting from the affine
+    /// transformation defined by the corners of the grid. Returns `None` if it does not converge.
+    fn to_raster(&self, model: &Coord) -> Option<Coord> {
+        let origin = self.model_points[0];
+        let last_column = self.model_points[self.columns - 1];
+        let last_row = self.model_points[(self.rows - 1) * self.columns];
+        let dx = (last_column - origin) / ((self.columns - 1) as f64 * self.spacing.x);
+        let dy = (last_row - origin) / ((self.rows - 1) as f64 * self.spacing.y);
+        let mut raster = self.origin + solve(dx, dy, *model - origin)?;
+
+        for _ in 0..MAX_ITERATIONS {
+            let (value, dx, dy) = self.to_model_with_derivatives(&raster);
+            let step = solve(dx, dy, value - *model)?;
+            raster = raster - step;
+            if step.x.abs() <= 1e-9 * self.spacing.x && step.y.abs() <= 1e-9 * self.spacing.y {
+                return Some(raster);
+            }
+        }
+        None
+    }
+}
+
+const MAX_ITERATIONS: usize = 20;
+
+/// Solves `x * dx + y * dy = value` for `(x, y)`, or returns `None` if the system is singular.
+fn solve(dx: Coord, dy: Coord, value: Coord) -> Option<Coord> {
+    let det = dx.x * dy.y - dy.x * dx.y;
+    let solution = Coord {
+        x: (dy.y * value.x - dy.x * value.y) / det,
+        y: (dx.x * value.y - dx.y * value.x) / det,
+    };
+    (solution.x.is_finite() && solution.y.is_finite()).then_some(solution)
+}
+
+/// Returns the first value, the spacing and the number of the distinct values if they are
+/// evenly spaced and at least 2.
+fn regular_axis(values: impl Iterator<Item = f64>) -> Option<(f64, f64, usize)> {
+    let mut values = values.collect::<Vec<_>>();
+    values.sort_by(f64::total_cmp);
+    values.dedup();
+    let count = values.len();
+    if count < 2 {
+        return None;
+    }
+    let first = values[0];
+    let spacing = (values[count - 1] - first) / (count - 1) as f64;
+    values
+        .iter()
+        .enumerate()
+        .all(|(index, value)| (value - (first + index as f64 * spacing)).abs() <= 1e-9 * spacing)
+        .then_some((first, spacing, count))
+}
+
+impl fmt::Display for TiePoints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Tie points mesh of {} faces", self.raster_mesh.len())?;
+        if let Some(grid) = &self.grid {
+            write!(f, " (grid of {} x {} points)", grid.columns, grid.rows)?;
+        }
+        Ok(())
     }
 }
 
 fn transform_by_tie_points(
     source_index: &OwnedRTree<f64>,
-    source_mesh: &Rc<Vec<Face>>,
-    target_mesh: &Rc<Vec<Face>>,
+    source_mesh: &Arc<Vec<Face>>,
+    target_mesh: &Arc<Vec<Face>>,
     coord: &Coord,
-) -> Coord {
+) -> Option<Coord> {
+    if !coord.x.is_finite() || !coord.y.is_finite() {
+        return None;
+    }
     let index = source_index
         .search(coord.x, coord.y, coord.x, coord.y)
         .into_iter()
-        .find(|face_index| source_mesh[*face_index].contains(coord))
-        .unwrap();
+        .find(|face_index| {
+            source_mesh
+                .get(*face_index)
+                .is_some_and(|face| face.contains(coord))
+        })?;
     let uv = source_mesh[index].locate(coord);
-    target_mesh[index].interpolate(uv)
+    Some(target_mesh[index].interpolate(uv))
 }
 
 fn build_faces(points: Vec<Point>, triangulation: &Triangulation) -> Vec<Face> {
@@ -211,8 +433,10 @@ fn build_faces(points: Vec<Point>, triangulation: &Triangulation) -> Vec<Face> {
 }
 
 fn build_index(mesh: &[Face]) -> OwnedRTree<f64> {
-    let mut builder = RTreeBuilder::new(mesh.len());
-    for face in mesh.iter() {
+    // geo-index cannot build a tree of a single item, so a single face is added twice
+    let num_items = mesh.len().max(2);
+    let mut builder = RTreeBuilder::new(num_items);
+    for face in mesh.iter().cycle().take(num_items) {
         let (min_x, min_y, max_x, max_y) = face.compute_envelope();
         builder.add(min_x, min_y, max_x, max_y);
     }
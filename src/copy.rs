@@ -0,0 +1,325 @@
+use std::io::{Read, Write};
+
+use tiff::tags::Tag;
+use tiff::{TiffError, TiffFormatError, TiffResult};
+
+use crate::document_info::XMP_TAG;
+use crate::exif::{EXIF_IFD_TAG, GPS_IFD_TAG, INTEROPERABILITY_IFD_TAG};
+use crate::gdal_metadata::GDAL_METADATA_TAG;
+use crate::in_memory::{gdal_nodata, write_geo_key_directory, write_transform_tags};
+use crate::tiff_writer::{field_size, write_tiff, Directory, TagValue};
+use crate::{
+    ByteOrder, DocumentInfo, GdalMetadata, GeoKeyDirectory, IfdPlacement, Lineage, TransformTags,
+    WriteOptions,
+};
+
+/// The tags pointing to IFDs without image data, which are copied after the IFD of their tag.
+const SUB_IFD_TAGS: [u16; 3] = [EXIF_IFD_TAG, GPS_IFD_TAG, INTEROPERABILITY_IFD_TAG];
+
+/// The tags pointing to data outside of their IFD which cannot be relocated by a copy: SubIFDs,
+/// which have their own image data, and JPEGInterchangeFormat.
+const UNRELOCATABLE_TAGS: [u16; 2] = [330, 513];
+
+/// The FreeOffsets and FreeByteCounts tags, describing the unused space of a file.
+const FREE_SPACE_TAGS: [u16; 2] = [288, 289];
+
+/// The edits of the metadata of the primary image applied by [copy_with]. The fields which are
+/// `None` are left as they are in the source.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetadataEdits {
+    /// Replaces the GeoKeyDirectory, GeoDoubleParams and GeoAsciiParams tags.
+    pub geo_key_directory: Option<GeoKeyDirectory>,
+    /// Replaces the tags of the transformation, removing those which are `None`.
+    pub transform_tags: Option<TransformTags>,
+    /// Replaces the GDAL_NODATA tag, which is removed for `Some(None)`.
+    pub nodata: Option<Option<f64>>,
+    /// Replaces the descriptive tags, removing those which are `None`.
+    pub document_info: Option<DocumentInfo>,
+    /// Replaces the lineage items of the GDAL_METADATA tag, keeping its other items.
+    pub lineage: Option<Lineage>,
+}
+
+/// Copies a TIFF file while applying edits to the metadata of its primary image, i.e. of its
+/// first IFD, e.g. to fix the georeferencing of archived files.
+///
+/// The copy is lossless: the strips and tiles are copied without being decoded, and the other
+/// tags are copied as is, including those this crate does not know, e.g. vendor tags, XMP packets
+/// or ICC profiles, as well as the Exif, GPS and Interoperability IFDs. The copy has the byte
+/// order of the source, and has its IFDs at the start of the file if they precede all the data in
+/// the source, like Cloud Optimized GeoTIFFs.
+///
+/// BigTIFF files and files with tags pointing to data which cannot be relocated, e.g. SubIFDs,
+/// are not supported. The FreeOffsets and FreeByteCounts tags are dropped, since the unused space
+/// of the source is not copied.
+pub fn copy_with<R: Read, W: Write>(mut src: R, dst: W, edits: &MetadataEdits) -> TiffResult<()> {
+    let mut bytes = Vec::new();
+    src.read_to_end(&mut bytes)?;
+    let (mut directories, options) = read_directories(&bytes)?;
+    let Some(primary) = directories.first_mut() else {
+        return Err(format_error("The file has no IFD".into()));
+    };
+
+    if let Some(geo_key_directory) = &edits.geo_key_directory {
+        for tag in [
+            Tag::GeoKeyDirectoryTag,
+            Tag::GeoDoubleParamsTag,
+            Tag::GeoAsciiParamsTag,
+        ] {
+            primary.remove(tag);
+        }
+        write_geo_key_directory(primary, geo_key_directory)?;
+    }
+    if let Some(transform_tags) = &edits.transform_tags {
+        for tag in [
+            Tag::ModelPixelScaleTag,
+            Tag::ModelTiepointTag,
+            Tag::ModelTransformationTag,
+        ] {
+            primary.remove(tag);
+        }
+        write_transform_tags(primary, transform_tags);
+    }
+    if let Some(nodata) = edits.nodata {
+        primary.remove(Tag::GdalNodata);
+        if let Some(nodata) = nodata {
+            primary.set(Tag::GdalNodata, &gdal_nodata(nodata)[..]);
+        }
+    }
+    if let Some(document_info) = &edits.document_info {
+        for tag in [
+            Tag::ImageDescription,
+            Tag::Artist,
+            Tag::Copyright,
+            Tag::DateTime,
+            Tag::Software,
+            Tag::Unknown(XMP_TAG),
+        ] {
+            primary.remove(tag);
+        }
+        document_info.write_tags(primary);
+    }
+    if let Some(lineage) = &edits.lineage {
+        let tag = Tag::Unknown(GDAL_METADATA_TAG);
+        let mut metadata = match primary.get(tag) {
+            Some(TagValue::Raw {
+                field_type: 2,
+                bytes,
+                ..
+            }) => GdalMetadata::parse(&String::from_utf8_lossy(bytes)),
+            _ => GdalMetadata::default(),
+        };
+        lineage.replace_in(&mut metadata);
+        primary.set(tag, &metadata.to_xml()[..]);
+    }
+    write_tiff(dst, directories, &options)
+}
+
+fn format_error(message: String) -> TiffError {
+    TiffError::FormatError(TiffFormatError::Format(message))
+}
+
+/// The bytes of a classic TIFF file.
+struct RawTiff<'a> {
+    bytes: &'a [u8],
+    order: ByteOrder,
+}
+
+impl RawTiff<'_> {
+    fn value_at(&self, offset: usize, size: usize) -> TiffResult<&[u8]> {
+        self.bytes
+            .get(offset..offset + size)
+            .ok_or_else(|| format_error(format!("The file is truncated at offset {}", offset)))
+    }
+
+    fn u16_at(&self, offset: usize) -> TiffResult<u16> {
+        Ok(u16_value(self.value_at(offset, 2)?, self.order))
+    }
+
+    fn u32_at(&self, offset: usize) -> TiffResult<u32> {
+        Ok(u32_value(self.value_at(offset, 4)?, self.order))
+    }
+
+    /// Reads the entries of the IFD at the given offset, named in errors, with the IFDs pointed
+    /// to by its tags, and returns the offset of its pointer to the next IFD.
+    fn read_directory(
+        &self,
+        offset: usize,
+        name: &str,
+        visited: &mut Vec<usize>,
+    ) -> TiffResult<(Directory, usize)> {
+        if visited.contains(&offset) {
+            return Err(format_error(format!(
+                "{} loops back to a previous IFD",
+                name
+            )));
+        }
+        visited.push(offset);
+        let entries_end = offset + 2 + 12 * self.u16_at(offset)? as usize;
+        let mut directory = Directory::default();
+        for entry in (offset + 2..entries_end).step_by(12) {
+            let tag = self.u16_at(entry)?;
+            let field_type = self.u16_at(entry + 2)?;
+            let count = self.u32_at(entry + 4)?;
+            if SUB_IFD_TAGS.contains(&tag) && matches!(field_type, 4 | 13) && count == 1 {
+                let sub_name = format!("the IFD of tag {} of {}", tag, name);
+                let sub_offset = self.u32_at(entry + 8)? as usize;
+                let (sub_directory, _) = self.read_directory(sub_offset, &sub_name, visited)?;
+                directory.set_sub_directory(tag, sub_directory);
+                continue;
+            }
+            if UNRELOCATABLE_TAGS.contains(&tag) || field_type == 13 {
+                return Err(format_error(format!(
+                    "Tag {} of {} points to data which cannot be relocated",
+                    tag, name
+                )));
+            }
+            if FREE_SPACE_TAGS.contains(&tag) {
+                continue;
+            }
+            let Some(size) = field_size(field_type) else {
+                return Err(format_error(format!(
+                    "Tag {} of {} has the unknown field type {}",
+                    tag, name, field_type
+                )));
+            };
+            let length = size * count as usize;
+            let value_offset = if length > 4 {
+                self.u32_at(entry + 8)? as usize
+            } else {
+                entry + 8
+            };
+            let value = TagValue::Raw {
+                field_type,
+                count,
+                bytes: self.value_at(value_offset, length)?.to_vec(),
+            };
+            directory.set_u16(tag, value);
+        }
+        Ok((directory, entries_end))
+    }
+}
+
+/// Reads the IFDs of a classic TIFF with the raw values of their tags, their chunks and the IFDs
+/// pointed to by their tags, and the options writing them with the byte order and IFD placement
+/// of the file.
+fn read_directories(bytes: &[u8]) -> TiffResult<(Vec<Directory>, WriteOptions)> {
+    let order = match bytes.get(..2) {
+        Some(b"II") => ByteOrder::LittleEndian,
+        Some(b"MM") => ByteOrder::BigEndian,
+        _ => return Err(format_error("The byte order is neither II nor MM".into())),
+    };
+    let tiff = RawTiff { bytes, order };
+    match tiff.u16_at(2)? {
+        42 => {}
+        43 => return Err(format_error("BigTIFF files cannot be copied".into())),
+        _ => return Err(format_error("The magic number is not 42".into())),
+    }
+
+    let mut directories = Vec::new();
+    let mut directory_offsets = Vec::new();
+    let mut visited = Vec::new();
+    let mut data_start = usize::MAX;
+    let mut offset = tiff.u32_at(4)? as usize;
+    while offset != 0 {
+        let index = directory_offsets.len();
+        directory_offsets.push(offset);
+        let (mut directory, entries_end) =
+            tiff.read_directory(offset, &format!("IFD {}", index), &mut visited)?;
+
+        for (offsets_tag, byte_counts_tag) in [
+            (Tag::StripOffsets, Tag::StripByteCounts),
+            (Tag::TileOffsets, Tag::TileByteCounts),
+        ] {
+            let (Some(offsets), Some(byte_counts)) =
+                (directory.get(offsets_tag), directory.get(byte_counts_tag))
+            else {
+                continue;
+            };
+            let (Some(offsets), Some(byte_counts)) = (
+                unsigned_values(offsets, order),
+                unsigned_values(byte_counts, order),
+            ) else {
+                return Err(format_error(format!(
+                    "The chunk offsets or byte counts of IFD {} are not integers",
+                    index
+                )));
+            };
+            if offsets.len() != byte_counts.len() {
+                return Err(format_error(format!(
+                    "IFD {} has {} chunk offsets but {} byte counts",
+                    index,
+                    offsets.len(),
+                    byte_counts.len()
+                )));
+            }
+            let chunks = offsets
+                .iter()
+                .zip(&byte_counts)
+                .map(|(&offset, &byte_count)| Ok(tiff.value_at(offset, byte_count)?.to_vec()))
+                .collect::<TiffResult<Vec<_>>>()?;
+            for (&offset, &byte_count) in offsets.iter().zip(&byte_counts) {
+                if byte_count > 0 {
+                    data_start = data_start.min(offset);
+                }
+            }
+            directory.set_chunks(offsets_tag, chunks);
+        }
+        directories.push(directory);
+        offset = tiff.u32_at(entries_end)? as usize;
+    }
+
+    let ifds_first = directory_offsets.iter().all(|&offset| offset < data_start);
+    let options = WriteOptions {
+        byte_order: order,
+        ifd_placement: if ifds_first {
+            IfdPlacement::Start
+        } else {
+            IfdPlacement::AfterData
+        },
+        ..Default::default()
+    };
+    Ok((directories, options))
+}
+
+/// Returns the values of a raw tag of shorts or longs.
+fn unsigned_values(value: &TagValue, order: ByteOrder) -> Option<Vec<usize>> {
+    match value {
+        TagValue::Raw {
+            field_type: 3,
+            bytes,
+            ..
+        } => Some(
+            bytes
+                .chunks_exact(2)
+                .map(|value| u16_value(value, order) as usize)
+                .collect(),
+        ),
+        TagValue::Raw {
+            field_type: 4,
+            bytes,
+            ..
+        } => Some(
+            bytes
+                .chunks_exact(4)
+                .map(|value| u32_value(value, order) as usize)
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+fn u16_value(bytes: &[u8], order: ByteOrder) -> u16 {
+    let bytes = bytes.try_into().unwrap();
+    match order {
+        ByteOrder::LittleEndian => u16::from_le_bytes(bytes),
+        ByteOrder::BigEndian => u16::from_be_bytes(bytes),
+    }
+}
+
+fn u32_value(bytes: &[u8], order: ByteOrder) -> u32 {
+    let bytes = bytes.try_into().unwrap();
+    match order {
+        ByteOrder::LittleEndian => u32::from_le_bytes(bytes),
+        ByteOrder::BigEndian => u32::from_be_bytes(bytes),
+    }
+}
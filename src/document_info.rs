@@ -0,0 +1,111 @@
+use std::io::{Read, Seek};
+
+use tiff::decoder::ifd::Value;
+use tiff::decoder::Decoder;
+use tiff::tags::Tag;
+use tiff::TiffResult;
+
+use crate::tiff_writer::Directory;
+use crate::{DateTime, GeoTiff, Image};
+
+pub(crate) const XMP_TAG: u16 = 700;
+
+/// The descriptive tags of an image, e.g. to record the provenance of published data.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocumentInfo {
+    /// The ImageDescription tag.
+    pub description: Option<String>,
+    /// The Artist tag, i.e. the creator of the image.
+    pub artist: Option<String>,
+    pub copyright: Option<String>,
+    /// The DateTime tag, i.e. the date and time of creation of the image. It is `None` if the tag
+    /// is not in the `YYYY:MM:DD HH:MM:SS` format.
+    pub date_time: Option<DateTime>,
+    /// The Software tag, i.e. the software which created the image.
+    pub software: Option<String>,
+    /// The XMP packet of tag 700, an XML document of descriptive metadata, e.g. the camera
+    /// orientation of drone imagery.
+    pub xmp: Option<Vec<u8>>,
+}
+
+impl DocumentInfo {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub(crate) fn read<R: Read + Seek>(decoder: &mut Decoder<R>) -> TiffResult<Self> {
+        let mut read_string = |tag| -> TiffResult<Option<String>> {
+            Ok(match decoder.find_tag(tag)? {
+                Some(value) => Some(value.into_string()?.trim_end_matches('\0').to_string()),
+                None => None,
+            })
+        };
+        Ok(Self {
+            description: read_string(Tag::ImageDescription)?,
+            artist: read_string(Tag::Artist)?,
+            copyright: read_string(Tag::Copyright)?,
+            date_time: read_string(Tag::DateTime)?.and_then(|value| DateTime::parse(&value)),
+            software: read_string(Tag::Software)?,
+            xmp: match decoder.find_tag(Tag::Unknown(XMP_TAG))? {
+                Some(value) => Some(value_bytes(value)?),
+                None => None,
+            },
+        })
+    }
+
+    pub(crate) fn write_tags(&self, directory: &mut Directory) {
+        for (tag, value) in [
+            (Tag::ImageDescription, &self.description),
+            (Tag::Artist, &self.artist),
+            (Tag::Copyright, &self.copyright),
+            (Tag::Software, &self.software),
+        ] {
+            if let Some(value) = value {
+                directory.set(tag, &value[..]);
+            }
+        }
+        if let Some(date_time) = &self.date_time {
+            let value = format!(
+                "{:04}:{:02}:{:02} {:02}:{:02}:{:02}",
+                date_time.year,
+                date_time.month,
+                date_time.day,
+                date_time.hour,
+                date_time.minute,
+                date_time.second as u8
+            );
+            directory.set(Tag::DateTime, &value[..]);
+        }
+        if let Some(xmp) = &self.xmp {
+            directory.set(Tag::Unknown(XMP_TAG), &xmp[..]);
+        }
+    }
+}
+
+/// Returns the bytes of a value of the BYTE or UNDEFINED field type, which the decoder represents
+/// with different variants depending on the type and the count.
+pub(crate) fn value_bytes(value: Value) -> TiffResult<Vec<u8>> {
+    let byte = |value: Value| match value {
+        Value::Byte(byte) => Ok(byte),
+        value => Ok(value.into_u64()? as u8),
+    };
+    match value {
+        Value::List(values) => values.into_iter().map(byte).collect(),
+        Value::Ascii(value) => Ok(value.into_bytes()),
+        value => Ok(vec![byte(value)?]),
+    }
+}
+
+impl Image {
+    /// Returns the descriptive tags of this image.
+    pub fn document_info(&self) -> &DocumentInfo {
+        &self.document_info
+    }
+}
+
+impl GeoTiff {
+    /// See [Image::document_info].
+    pub fn document_info(&self) -> &DocumentInfo {
+        self.primary().document_info()
+    }
+}
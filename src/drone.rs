@@ -0,0 +1,184 @@
+//! Approximate georeferencing of drone frames without geo keys, e.g. plain TIFF or JPEG-in-TIFF
+//! frames, from the position and heading of the drone and the intrinsics of its camera, for
+//! quicklook placement before photogrammetry.
+//!
+//! The camera is assumed to point straight down over flat ground, so that the frame is a
+//! rectangle centered below the drone and rotated by its heading. The transformation is to
+//! longitude and latitude in degrees on WGS 84, i.e. EPSG:4326, with a local spherical
+//! approximation of the meters per degree. The errors grow with the tilt of the camera and the
+//! relief of the ground, and are typically of a few percent of the footprint.
+
+use crate::{AffineTransform, CoordinateTransform, Exif, GeoKeyDirectory, Image};
+
+const FOCAL_LENGTH_TAG: u16 = 37386;
+const FOCAL_PLANE_X_RESOLUTION_TAG: u16 = 41486;
+const FOCAL_PLANE_Y_RESOLUTION_TAG: u16 = 41487;
+const FOCAL_PLANE_RESOLUTION_UNIT_TAG: u16 = 41488;
+const FOCAL_LENGTH_IN_35MM_FILM_TAG: u16 = 41989;
+const GPS_IMG_DIRECTION_TAG: u16 = 17;
+
+/// The XMP properties of DJI drones with the heading of the camera, from the most relevant one.
+const XMP_YAW_PROPERTIES: [&str; 2] = ["GimbalYawDegree", "FlightYawDegree"];
+/// The XMP property of DJI drones with the height above the takeoff point.
+const XMP_RELATIVE_ALTITUDE_PROPERTY: &str = "RelativeAltitude";
+
+/// The width of a 35 mm film frame, in millimeters.
+const FILM_35MM_WIDTH: f64 = 36.0;
+
+/// The meters per degree of latitude on a sphere of the equatorial radius of WGS 84.
+const METERS_PER_DEGREE: f64 = 6_378_137.0 * std::f64::consts::PI / 180.0;
+
+/// The intrinsics of a camera, as a pinhole.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraModel {
+    /// The focal length in millimeters.
+    pub focal_length: f64,
+    /// The width and height of the pixels on the sensor, in millimeters.
+    pub pixel_size: (f64, f64),
+}
+
+impl CameraModel {
+    /// Reads the camera of a frame of the given width and height in pixels from the FocalLength
+    /// and FocalPlaneResolution tags, or else from the FocalLengthIn35mmFilm tag, taking the
+    /// width of the film for the longest side of the frame.
+    pub fn from_exif(exif: &Exif, width: usize, height: usize) -> Option<Self> {
+        let rational = |tag| exif.rationals(exif.get(tag)?)?.first().copied();
+        let focal_plane = (|| {
+            let millimeters = match exif
+                .unsigned(exif.get(FOCAL_PLANE_RESOLUTION_UNIT_TAG)?)?
+                .first()?
+            {
+                2 => 25.4,
+                3 => 10.0,
+                4 => 1.0,
+                5 => 0.001,
+                _ => return None,
+            };
+            let x_resolution = rational(FOCAL_PLANE_X_RESOLUTION_TAG)?;
+            let y_resolution = rational(FOCAL_PLANE_Y_RESOLUTION_TAG).unwrap_or(x_resolution);
+            Some(Self {
+                focal_length: rational(FOCAL_LENGTH_TAG)?,
+                pixel_size: (millimeters / x_resolution, millimeters / y_resolution),
+            })
+        })();
+        focal_plane
+            .or_else(|| {
+                let focal_length = *exif
+                    .unsigned(exif.get(FOCAL_LENGTH_IN_35MM_FILM_TAG)?)?
+                    .first()?;
+                let pixel_size = FILM_35MM_WIDTH / width.max(height) as f64;
+                Some(Self {
+                    focal_length: focal_length as f64,
+                    pixel_size: (pixel_size, pixel_size),
+                })
+            })
+            .filter(|camera| {
+                camera.focal_length > 0.0
+                    && camera.pixel_size.0.is_finite()
+                    && camera.pixel_size.1.is_finite()
+            })
+    }
+}
+
+/// The pose of a drone when it captured a frame, with its camera.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DroneFrame {
+    pub longitude: f64,
+    pub latitude: f64,
+    /// The height of the camera above the ground, in meters.
+    pub height_above_ground: f64,
+    /// The heading of the top of the frame, clockwise from north, in degrees.
+    pub yaw: f64,
+    pub camera: CameraModel,
+}
+
+impl DroneFrame {
+    /// Reads the pose of the drone from the Exif and XMP metadata of the image.
+    ///
+    /// The height above the ground is the GPS altitude minus the elevation of the ground if it
+    /// is given, or else the height above the takeoff point recorded in the XMP metadata of DJI
+    /// drones. The heading is the GPSImgDirection tag, or else the gimbal or flight yaw of DJI
+    /// drones, and north if none is known. Magnetic headings are not corrected.
+    pub fn from_image(image: &Image, ground_elevation: Option<f64>) -> Option<Self> {
+        let exif = image.exif()?;
+        let position = exif.gps_position()?;
+        let xmp = image
+            .document_info()
+            .xmp
+            .as_deref()
+            .map(String::from_utf8_lossy);
+        let xmp_value = |name: &str| xmp.as_deref().and_then(|xmp| xmp_value(xmp, name));
+        let height_above_ground = match (ground_elevation, position.altitude) {
+            (Some(ground_elevation), Some(altitude)) => altitude - ground_elevation,
+            _ => xmp_value(XMP_RELATIVE_ALTITUDE_PROPERTY)?,
+        };
+        let yaw = exif
+            .get_gps(GPS_IMG_DIRECTION_TAG)
+            .and_then(|entry| exif.rationals(entry)?.first().copied())
+            .or_else(|| XMP_YAW_PROPERTIES.iter().find_map(|name| xmp_value(name)))
+            .unwrap_or(0.0);
+        Some(Self {
+            longitude: position.longitude,
+            latitude: position.latitude,
+            height_above_ground,
+            yaw,
+            camera: CameraModel::from_exif(exif, image.raster_width, image.raster_height)?,
+        })
+    }
+
+    /// Returns the size of the pixels on the ground, in meters.
+    pub fn ground_sample_distance(&self) -> (f64, f64) {
+        let scale = self.height_above_ground / self.camera.focal_length;
+        (
+            self.camera.pixel_size.0 * scale,
+            self.camera.pixel_size.1 * scale,
+        )
+    }
+
+    /// Returns the approximate transformation from the raster space of a frame of the given size
+    /// to longitude and latitude, see [DroneFrame::geo_key_directory].
+    pub fn coordinate_transform(&self, width: usize, height: usize) -> CoordinateTransform {
+        let (gsd_x, gsd_y) = self.ground_sample_distance();
+        let (sin, cos) = self.yaw.to_radians().sin_cos();
+        let degrees_east = 1.0 / (METERS_PER_DEGREE * self.latitude.to_radians().cos());
+        let degrees_north = 1.0 / METERS_PER_DEGREE;
+        // The columns go along the right of the heading and the rows against it
+        let (a, b) = (gsd_x * cos * degrees_east, -gsd_y * sin * degrees_east);
+        let (d, e) = (-gsd_x * sin * degrees_north, -gsd_y * cos * degrees_north);
+        let (center_x, center_y) = (width as f64 / 2.0, height as f64 / 2.0);
+        let c = self.longitude - a * center_x - b * center_y;
+        let f = self.latitude - d * center_x - e * center_y;
+        CoordinateTransform::AffineTransform(AffineTransform::from_tag_matrix([
+            a, b, 0.0, c, d, e, 0.0, f, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+        ]))
+    }
+
+    /// Returns the geo keys of the model space of [DroneFrame::coordinate_transform].
+    pub fn geo_key_directory(&self) -> GeoKeyDirectory {
+        GeoKeyDirectory::from_epsg(4326)
+    }
+}
+
+/// Returns the value of a numeric XMP property, either as an attribute, e.g.
+/// `drone-dji:RelativeAltitude="+50.10"`, or as an element, with any namespace prefix.
+fn xmp_value(xmp: &str, name: &str) -> Option<f64> {
+    let mut rest = xmp;
+    while let Some(start) = rest.find(name) {
+        let preceded_by_prefix = rest[..start].ends_with(':');
+        rest = &rest[start + name.len()..];
+        if !preceded_by_prefix {
+            continue;
+        }
+        let value = if let Some(value) = rest.strip_prefix("=\"") {
+            value.split('"').next()
+        } else if let Some(value) = rest.strip_prefix('>') {
+            value.split('<').next()
+        } else {
+            continue;
+        };
+        if let Some(value) = value.and_then(|value| value.trim().parse().ok()) {
+            return Some(value);
+        }
+    }
+    None
+}
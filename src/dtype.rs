@@ -0,0 +1,117 @@
+use crate::{Band, GeoTiff, Image};
+
+/// The type of the values of a band, given by the SampleFormat and BitsPerSample tags.
+///
+/// Complex types are stored as pairs of real and imaginary parts, the BitsPerSample tag giving
+/// the size of the pair, e.g. 32 bits for [DType::ComplexI16].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DType {
+    /// 1-bit values, e.g. bilevel masks.
+    Bit,
+    U8,
+    /// 12-bit unsigned values, packed in 1.5 bytes.
+    U12,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    F16,
+    F32,
+    F64,
+    ComplexI16,
+    ComplexI32,
+    ComplexF32,
+    ComplexF64,
+    /// Any other combination of the tags.
+    Other {
+        sample_format: u16,
+        bits_per_sample: u16,
+    },
+}
+
+impl DType {
+    /// Resolves the type from the values of the SampleFormat and BitsPerSample tags of a sample.
+    pub fn from_tags(sample_format: u16, bits_per_sample: u16) -> Self {
+        match (sample_format, bits_per_sample) {
+            (1, 1) => DType::Bit,
+            (1, 8) => DType::U8,
+            (1, 12) => DType::U12,
+            (1, 16) => DType::U16,
+            (1, 32) => DType::U32,
+            (1, 64) => DType::U64,
+            (2, 8) => DType::I8,
+            (2, 16) => DType::I16,
+            (2, 32) => DType::I32,
+            (2, 64) => DType::I64,
+            (3, 16) => DType::F16,
+            (3, 32) => DType::F32,
+            (3, 64) => DType::F64,
+            (5, 32) => DType::ComplexI16,
+            (5, 64) => DType::ComplexI32,
+            (6, 64) => DType::ComplexF32,
+            (6, 128) => DType::ComplexF64,
+            _ => DType::Other {
+                sample_format,
+                bits_per_sample,
+            },
+        }
+    }
+
+    /// Returns the value of the BitsPerSample tag for this type.
+    pub fn bits_per_sample(&self) -> u16 {
+        match self {
+            DType::Bit => 1,
+            DType::U8 | DType::I8 => 8,
+            DType::U12 => 12,
+            DType::U16 | DType::I16 | DType::F16 => 16,
+            DType::U32 | DType::I32 | DType::F32 | DType::ComplexI16 => 32,
+            DType::U64 | DType::I64 | DType::F64 | DType::ComplexI32 | DType::ComplexF32 => 64,
+            DType::ComplexF64 => 128,
+            DType::Other {
+                bits_per_sample, ..
+            } => *bits_per_sample,
+        }
+    }
+
+    /// Returns whether the values are packed, i.e. do not start on byte boundaries.
+    pub fn is_packed(&self) -> bool {
+        !self.bits_per_sample().is_multiple_of(8)
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(
+            self,
+            DType::F16 | DType::F32 | DType::F64 | DType::ComplexF32 | DType::ComplexF64
+        )
+    }
+
+    pub fn is_complex(&self) -> bool {
+        matches!(
+            self,
+            DType::ComplexI16 | DType::ComplexI32 | DType::ComplexF32 | DType::ComplexF64
+        )
+    }
+}
+
+impl Image {
+    /// Returns the type of the values of the given band, or `None` if there is no such band.
+    pub fn band_dtype(&self, band: usize) -> Option<DType> {
+        self.dtypes.get(band).copied()
+    }
+}
+
+impl GeoTiff {
+    /// See [Image::band_dtype].
+    pub fn band_dtype(&self, band: usize) -> Option<DType> {
+        self.primary().band_dtype(band)
+    }
+}
+
+impl Band<'_> {
+    pub fn dtype(&self) -> DType {
+        self.image().dtypes[self.sample()]
+    }
+}
@@ -0,0 +1,132 @@
+use crate::GeoKeyDirectory;
+
+const USER_DEFINED: u16 = 32767;
+
+/// An EPSG code inferred from the geo keys, see [GeoKeyDirectory::infer_epsg].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InferredEpsg {
+    pub code: u16,
+    pub confidence: Confidence,
+}
+
+/// How an EPSG code was obtained, from the most to the least reliable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Confidence {
+    /// The code is given by the ProjectedCRSGeoKey.
+    Exact,
+    /// The zone and the datum are given by codes of the ProjectionGeoKey and of the geodetic
+    /// CRS or datum keys.
+    High,
+    /// The zone or the datum were recognized from the projection parameters or the ellipsoid.
+    Low,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Datum {
+    Wgs84,
+    Nad83,
+}
+
+impl GeoKeyDirectory {
+    /// Returns the EPSG code of the projected CRS, inferring it for user-defined CRS which match
+    /// a UTM zone on WGS 84 or NAD83.
+    ///
+    /// Many producers describe UTM zones by their projection parameters rather than by a code.
+    /// Returns `None` if the CRS is not projected or not recognized.
+    pub fn infer_epsg(&self) -> Option<InferredEpsg> {
+        if !self.is_projected() {
+            return None;
+        }
+        if let Some(code) = self.projected_type.filter(|code| *code != USER_DEFINED) {
+            return Some(InferredEpsg {
+                code,
+                confidence: Confidence::Exact,
+            });
+        }
+        if self.proj_linear_units.is_some_and(|units| units != 9001) {
+            return None;
+        }
+
+        let (datum, datum_from_code) = self.utm_datum()?;
+        let (zone, north, zone_from_code) = self.utm_zone()?;
+        let code = match (datum, north) {
+            (Datum::Wgs84, true) => 32600 + zone,
+            (Datum::Wgs84, false) => 32700 + zone,
+            (Datum::Nad83, true) if zone <= 23 => 26900 + zone,
+            (Datum::Nad83, _) => return None,
+        };
+        Some(InferredEpsg {
+            code,
+            confidence: if datum_from_code && zone_from_code {
+                Confidence::High
+            } else {
+                Confidence::Low
+            },
+        })
+    }
+
+    /// Returns the datum and whether it is given by a code rather than by the ellipsoid.
+    fn utm_datum(&self) -> Option<(Datum, bool)> {
+        match (self.geographic_type, self.geog_geodetic_datum) {
+            (Some(4326), _) | (_, Some(6326)) => return Some((Datum::Wgs84, true)),
+            (Some(4269), _) | (_, Some(6269)) => return Some((Datum::Nad83, true)),
+            _ => {}
+        }
+
+        let datum = match self.geog_ellipsoid {
+            Some(7030) => Datum::Wgs84,
+            Some(7019) => Datum::Nad83,
+            _ => {
+                if self.geog_semi_major_axis != Some(6378137.0) {
+                    return None;
+                }
+                let inv_flattening = self.geog_inv_flattening?;
+                if (inv_flattening - 298.257223563).abs() < 1e-6 {
+                    Datum::Wgs84
+                } else if (inv_flattening - 298.257222101).abs() < 1e-6 {
+                    Datum::Nad83
+                } else {
+                    return None;
+                }
+            }
+        };
+        Some((datum, false))
+    }
+
+    /// Returns the zone, whether it is in the northern hemisphere, and whether it is given by a
+    /// code rather than by the projection parameters.
+    fn utm_zone(&self) -> Option<(u16, bool, bool)> {
+        match self.projection {
+            Some(code @ 16001..=16060) => return Some((code - 16000, true, true)),
+            Some(code @ 16101..=16160) => return Some((code - 16100, false, true)),
+            _ => {}
+        }
+
+        // Transverse Mercator
+        if self.proj_coord_trans != Some(1) {
+            return None;
+        }
+        let close = |value: Option<f64>, expected: f64| {
+            value.is_some_and(|value| (value - expected).abs() < 1e-6)
+        };
+        if !close(self.proj_scale_at_nat_origin, 0.9996)
+            || !close(self.proj_false_easting, 500000.0)
+            || !close(Some(self.proj_nat_origin_lat.unwrap_or(0.0)), 0.0)
+        {
+            return None;
+        }
+        let north = if close(self.proj_false_northing, 0.0) {
+            true
+        } else if close(self.proj_false_northing, 10000000.0) {
+            false
+        } else {
+            return None;
+        };
+
+        let zone = (self.proj_nat_origin_long? + 183.0) / 6.0;
+        if (zone - zone.round()).abs() > 1e-6 || !(1.0..=60.0).contains(&zone.round()) {
+            return None;
+        }
+        Some((zone.round() as u16, north, false))
+    }
+}
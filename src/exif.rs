@@ -0,0 +1,289 @@
+use std::io::{Read, Seek};
+
+use tiff::decoder::Decoder;
+use tiff::tags::Tag;
+use tiff::TiffResult;
+
+use crate::tiff_writer::{field_size, swap_byte_order, Directory, TagValue};
+use crate::{ByteOrder, DateTime, GeoTiff, Image};
+
+pub(crate) const EXIF_IFD_TAG: u16 = 34665;
+pub(crate) const GPS_IFD_TAG: u16 = 34853;
+pub(crate) const INTEROPERABILITY_IFD_TAG: u16 = 40965;
+
+const DATE_TIME_ORIGINAL_TAG: u16 = 36867;
+const DATE_TIME_DIGITIZED_TAG: u16 = 36868;
+const GPS_LATITUDE_REF_TAG: u16 = 1;
+const GPS_LATITUDE_TAG: u16 = 2;
+const GPS_LONGITUDE_REF_TAG: u16 = 3;
+const GPS_LONGITUDE_TAG: u16 = 4;
+const GPS_ALTITUDE_REF_TAG: u16 = 5;
+const GPS_ALTITUDE_TAG: u16 = 6;
+
+/// An entry of an IFD as stored in the file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawTag {
+    pub tag: u16,
+    /// The TIFF field type, e.g. 2 for ASCII or 5 for RATIONAL.
+    pub field_type: u16,
+    pub count: u32,
+    /// The values, in the byte order of the [Exif] they belong to.
+    pub bytes: Vec<u8>,
+}
+
+/// The Exif metadata of an image, from its Exif and GPS IFDs, e.g. the capture time and position
+/// of drone imagery, which complement its geo keys.
+///
+/// The entries are kept as stored in the file, except the pointer to the Interoperability IFD,
+/// which is not read. The MakerNote tag is kept as is, although the formats of some vendors have
+/// offsets which are only valid in their original file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exif {
+    pub byte_order: ByteOrder,
+    /// The entries of the Exif IFD.
+    pub exif: Vec<RawTag>,
+    /// The entries of the GPS IFD.
+    pub gps: Vec<RawTag>,
+}
+
+/// A position from the GPS IFD, in degrees and meters above sea level.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpsPosition {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: Option<f64>,
+}
+
+impl Exif {
+    /// Returns the entry of the Exif IFD with the given tag.
+    pub fn get(&self, tag: u16) -> Option<&RawTag> {
+        self.exif.iter().find(|entry| entry.tag == tag)
+    }
+
+    /// Returns the entry of the GPS IFD with the given tag.
+    pub fn get_gps(&self, tag: u16) -> Option<&RawTag> {
+        self.gps.iter().find(|entry| entry.tag == tag)
+    }
+
+    /// Returns the value of an ASCII entry, without its terminating NUL.
+    pub fn ascii(&self, entry: &RawTag) -> Option<String> {
+        (entry.field_type == 2).then(|| {
+            String::from_utf8_lossy(&entry.bytes)
+                .trim_end_matches('\0')
+                .to_string()
+        })
+    }
+
+    /// Returns the values of a BYTE, SHORT or LONG entry.
+    pub fn unsigned(&self, entry: &RawTag) -> Option<Vec<u32>> {
+        let size = match entry.field_type {
+            1 => 1,
+            3 => 2,
+            4 => 4,
+            _ => return None,
+        };
+        Some(
+            entry
+                .bytes
+                .chunks_exact(size)
+                .map(|value| {
+                    let mut bytes = [0; 4];
+                    match self.byte_order {
+                        ByteOrder::LittleEndian => {
+                            bytes[..size].copy_from_slice(value);
+                            u32::from_le_bytes(bytes)
+                        }
+                        ByteOrder::BigEndian => {
+                            bytes[4 - size..].copy_from_slice(value);
+                            u32::from_be_bytes(bytes)
+                        }
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the values of a RATIONAL entry.
+    pub fn rationals(&self, entry: &RawTag) -> Option<Vec<f64>> {
+        if entry.field_type != 5 {
+            return None;
+        }
+        let long = |bytes: &[u8]| {
+            let bytes = bytes.try_into().unwrap();
+            match self.byte_order {
+                ByteOrder::LittleEndian => u32::from_le_bytes(bytes),
+                ByteOrder::BigEndian => u32::from_be_bytes(bytes),
+            }
+        };
+        Some(
+            entry
+                .bytes
+                .chunks_exact(8)
+                .map(|value| long(&value[..4]) as f64 / long(&value[4..]) as f64)
+                .collect(),
+        )
+    }
+
+    /// Returns the DateTimeOriginal tag, i.e. the capture time, or else the DateTimeDigitized
+    /// tag. Exif dates have no time zone.
+    pub fn capture_date_time(&self) -> Option<DateTime> {
+        [DATE_TIME_ORIGINAL_TAG, DATE_TIME_DIGITIZED_TAG]
+            .into_iter()
+            .filter_map(|tag| self.ascii(self.get(tag)?))
+            .find_map(|value| DateTime::parse(&value))
+    }
+
+    /// Returns the position of the GPS IFD, if it has a latitude and a longitude.
+    pub fn gps_position(&self) -> Option<GpsPosition> {
+        let coordinate = |tag: u16, ref_tag: u16, negative: &str| {
+            let degrees = match self.rationals(self.get_gps(tag)?)?[..] {
+                [degrees, minutes, seconds] => degrees + minutes / 60.0 + seconds / 3600.0,
+                [degrees] => degrees,
+                _ => return None,
+            };
+            let reference = self.get_gps(ref_tag).and_then(|entry| self.ascii(entry));
+            Some(if reference.as_deref() == Some(negative) {
+                -degrees
+            } else {
+                degrees
+            })
+        };
+        let altitude = self
+            .get_gps(GPS_ALTITUDE_TAG)
+            .and_then(|entry| self.rationals(entry)?.first().copied())
+            .map(|altitude| {
+                // 1 for below sea level
+                match self.get_gps(GPS_ALTITUDE_REF_TAG) {
+                    Some(entry) if entry.bytes.first() == Some(&1) => -altitude,
+                    _ => altitude,
+                }
+            });
+        Some(GpsPosition {
+            latitude: coordinate(GPS_LATITUDE_TAG, GPS_LATITUDE_REF_TAG, "S")?,
+            longitude: coordinate(GPS_LONGITUDE_TAG, GPS_LONGITUDE_REF_TAG, "W")?,
+            altitude,
+        })
+    }
+
+    /// Reads the Exif and GPS IFDs pointed to by the current IFD of the decoder, if any.
+    pub(crate) fn read<R: Read + Seek>(decoder: &mut Decoder<R>) -> TiffResult<Option<Self>> {
+        let exif_offset = decoder.find_tag_unsigned::<u64>(Tag::Unknown(EXIF_IFD_TAG))?;
+        let gps_offset = decoder.find_tag_unsigned::<u64>(Tag::Unknown(GPS_IFD_TAG))?;
+        if exif_offset.is_none() && gps_offset.is_none() {
+            return Ok(None);
+        }
+        decoder.goto_offset_u64(0)?;
+        let byte_order = if decoder.read_byte()? == b'M' {
+            ByteOrder::BigEndian
+        } else {
+            ByteOrder::LittleEndian
+        };
+        decoder.goto_offset_u64(2)?;
+        let big_tiff = decoder.read_short()? == 43;
+        let mut read = |offset: Option<u64>| match offset {
+            Some(offset) => read_raw_ifd(decoder, offset, big_tiff),
+            None => Ok(Vec::new()),
+        };
+        Ok(Some(Self {
+            byte_order,
+            exif: read(exif_offset)?,
+            gps: read(gps_offset)?,
+        }))
+    }
+
+    /// Sets the Exif and GPS IFDs as sub-IFDs of the directory, written with the given byte
+    /// order.
+    pub(crate) fn write_tags(&self, directory: &mut Directory, byte_order: ByteOrder) {
+        let sub_directory = |entries: &[RawTag]| {
+            let mut sub_directory = Directory::default();
+            for entry in entries {
+                let bytes = if byte_order == self.byte_order {
+                    entry.bytes.clone()
+                } else {
+                    swap_byte_order(entry.field_type, &entry.bytes)
+                };
+                let value = TagValue::Raw {
+                    field_type: entry.field_type,
+                    count: entry.count,
+                    bytes,
+                };
+                sub_directory.set_u16(entry.tag, value);
+            }
+            sub_directory
+        };
+        if !self.exif.is_empty() {
+            directory.set_sub_directory(EXIF_IFD_TAG, sub_directory(&self.exif));
+        }
+        if !self.gps.is_empty() {
+            directory.set_sub_directory(GPS_IFD_TAG, sub_directory(&self.gps));
+        }
+    }
+}
+
+/// Reads the entries of the IFD at the given offset, skipping those pointing to other IFDs and
+/// those of unknown field types.
+fn read_raw_ifd<R: Read + Seek>(
+    decoder: &mut Decoder<R>,
+    offset: u64,
+    big_tiff: bool,
+) -> TiffResult<Vec<RawTag>> {
+    let (count_size, entry_size, inline_size) = if big_tiff { (8, 20, 8) } else { (2, 12, 4) };
+    decoder.goto_offset_u64(offset)?;
+    let count = if big_tiff {
+        decoder.read_long8()?
+    } else {
+        decoder.read_short()?.into()
+    };
+    let mut entries = Vec::new();
+    for index in 0..count {
+        decoder.goto_offset_u64(offset + count_size + index * entry_size)?;
+        let (tag, field_type) = (decoder.read_short()?, decoder.read_short()?);
+        let count = if big_tiff {
+            decoder.read_long8()?
+        } else {
+            decoder.read_long()?.into()
+        };
+        let (Some(size), Ok(count)) = (field_size(field_type), u32::try_from(count)) else {
+            log::warn!("Skipping Exif tag {tag} with the field type {field_type}");
+            continue;
+        };
+        if field_type == 13 || tag == INTEROPERABILITY_IFD_TAG {
+            continue;
+        }
+        let length = size * count as usize;
+        if length > inline_size {
+            let value_offset = if big_tiff {
+                decoder.read_long8()?
+            } else {
+                decoder.read_long()?.into()
+            };
+            decoder.goto_offset_u64(value_offset)?;
+        }
+        // Read byte by byte so that a corrupt count fails at the end of the file rather than
+        // allocating its size
+        let bytes = (0..length)
+            .map(|_| decoder.read_byte())
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.push(RawTag {
+            tag,
+            field_type,
+            count,
+            bytes,
+        });
+    }
+    Ok(entries)
+}
+
+impl Image {
+    /// Returns the Exif metadata of this image, if it has an Exif or GPS IFD.
+    pub fn exif(&self) -> Option<&Exif> {
+        self.exif.as_ref()
+    }
+}
+
+impl GeoTiff {
+    /// See [Image::exif].
+    pub fn exif(&self) -> Option<&Exif> {
+        self.primary().exif()
+    }
+}
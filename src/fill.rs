@@ -0,0 +1,102 @@
+//! Interpolation of the nodata holes of a band, e.g. DEM voids or cloud-masked gaps.
+//!
+//! Like `gdal_fillnodata`, each nodata pixel is interpolated by inverse distance weighting from
+//! the nearest valid pixels, searched along the 8 directions around it. The filled pixels may
+//! then be smoothed. The nodata value of the result is NaN.
+
+use tiff::TiffResult;
+
+use crate::simd;
+use crate::{Band, InMemoryRaster, Window};
+
+/// The directions along which valid pixels are searched.
+const DIRECTIONS: [(i64, i64); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+/// Fills the nodata pixels of the band from the valid pixels within `max_search_distance`
+/// pixels, then applies `smoothing_iterations` passes of a 3x3 mean filter to the filled
+/// pixels.
+///
+/// Valid pixels are copied unchanged. Nodata pixels without any valid pixel within the search
+/// distance stay nodata.
+pub fn fill_nodata(
+    band: Band,
+    max_search_distance: f64,
+    smoothing_iterations: usize,
+) -> TiffResult<InMemoryRaster> {
+    let (width, height) = (band.width(), band.height());
+    let mut data = band.read_window(Window::full(width, height))?;
+    simd::nodata_to_nan(&mut data, band.nodata());
+
+    let index = |x: i64, y: i64| {
+        ((0..width as i64).contains(&x) && (0..height as i64).contains(&y))
+            .then(|| y as usize * width + x as usize)
+    };
+    let mut filled = Vec::new();
+    let mut result = data.clone();
+    for y in 0..height as i64 {
+        for x in 0..width as i64 {
+            let i = index(x, y).unwrap();
+            if !data[i].is_nan() {
+                continue;
+            }
+            let mut sum = 0.0;
+            let mut weights = 0.0;
+            for (dx, dy) in DIRECTIONS {
+                let step = (dx as f64).hypot(dy as f64);
+                let mut distance = step;
+                let (mut nx, mut ny) = (x + dx, y + dy);
+                while distance <= max_search_distance {
+                    let Some(neighbor) = index(nx, ny) else {
+                        break;
+                    };
+                    if !data[neighbor].is_nan() {
+                        let weight = 1.0 / (distance * distance);
+                        sum += weight * data[neighbor];
+                        weights += weight;
+                        break;
+                    }
+                    (nx, ny) = (nx + dx, ny + dy);
+                    distance += step;
+                }
+            }
+            if weights > 0.0 {
+                result[i] = sum / weights;
+                filled.push((x, y));
+            }
+        }
+    }
+
+    for _ in 0..smoothing_iterations {
+        let previous = result.clone();
+        for (x, y) in &filled {
+            let (mut sum, mut count) = (0.0, 0);
+            for ny in y - 1..=y + 1 {
+                for nx in x - 1..=x + 1 {
+                    if let Some(value) = index(nx, ny)
+                        .map(|neighbor| previous[neighbor])
+                        .filter(|value| !value.is_nan())
+                    {
+                        sum += value;
+                        count += 1;
+                    }
+                }
+            }
+            result[index(*x, *y).unwrap()] = sum / count as f64;
+        }
+    }
+
+    Ok(InMemoryRaster::with_grid_of(
+        band.image(),
+        result,
+        Some(f64::NAN),
+    ))
+}
@@ -0,0 +1,161 @@
+//! Moving window operations on a band, e.g. to smooth a DEM.
+//!
+//! The result is computed block by block, reading a halo of neighboring pixels around each
+//! block. Neighbors which are nodata or outside the raster are ignored, and pixels which are
+//! nodata stay nodata. The nodata value of the result is NaN.
+
+use tiff::{TiffError, TiffFormatError, TiffResult};
+
+use crate::{Band, BlockMap, InMemoryRaster, Window};
+
+/// A statistic of the neighborhood of each pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocalOperation {
+    Mean,
+    Min,
+    Max,
+    Median,
+}
+
+/// A convolution kernel of odd width and height, centered on the pixel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Kernel {
+    width: usize,
+    height: usize,
+    weights: Vec<f64>,
+    normalized: bool,
+}
+
+impl Kernel {
+    /// Creates a kernel from its weights in row-major order. A pixel is nodata in the result if
+    /// any of its neighbors with a non-zero weight is missing.
+    ///
+    /// Fails if the width or height is even, or if the number of weights does not match.
+    pub fn new(width: usize, height: usize, weights: Vec<f64>) -> TiffResult<Self> {
+        if width.is_multiple_of(2) || height.is_multiple_of(2) {
+            return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+                "The kernel size {}x{} is not odd",
+                width, height
+            ))));
+        }
+        if weights.len() != width * height {
+            return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+                "The kernel has {} weights instead of {} for its size {}x{}",
+                weights.len(),
+                width * height,
+                width,
+                height
+            ))));
+        }
+        Ok(Self {
+            width,
+            height,
+            weights,
+            normalized: false,
+        })
+    }
+
+    /// Creates a kernel whose result is divided by the sum of the weights of the neighbors which
+    /// are present, e.g. for smoothing, see [Kernel::new].
+    pub fn new_normalized(width: usize, height: usize, weights: Vec<f64>) -> TiffResult<Self> {
+        Ok(Self {
+            normalized: true,
+            ..Self::new(width, height, weights)?
+        })
+    }
+}
+
+/// Computes the statistic over the square neighborhood of each pixel extending `radius` pixels
+/// on each side.
+pub fn focal(band: Band, radius: usize, operation: FocalOperation) -> TiffResult<InMemoryRaster> {
+    let mut values = Vec::new();
+    process_neighborhoods(band, (radius, radius), |neighbors| {
+        values.clear();
+        values.extend(neighbors.iter().filter_map(|(_, value)| *value));
+        match operation {
+            FocalOperation::Mean => values.iter().sum::<f64>() / values.len() as f64,
+            FocalOperation::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+            FocalOperation::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            FocalOperation::Median => {
+                values.sort_by(f64::total_cmp);
+                let middle = values.len() / 2;
+                if values.len() % 2 == 0 {
+                    (values[middle - 1] + values[middle]) / 2.0
+                } else {
+                    values[middle]
+                }
+            }
+        }
+    })
+}
+
+/// Convolves the band with the kernel.
+pub fn convolve(band: Band, kernel: &Kernel) -> TiffResult<InMemoryRaster> {
+    let halo = (kernel.width / 2, kernel.height / 2);
+    process_neighborhoods(band, halo, |neighbors| {
+        let mut sum = 0.0;
+        let mut weights = 0.0;
+        for (index, value) in neighbors {
+            match value {
+                Some(value) => {
+                    sum += kernel.weights[*index] * value;
+                    weights += kernel.weights[*index];
+                }
+                None if !kernel.normalized && kernel.weights[*index] != 0.0 => return f64::NAN,
+                None => {}
+            }
+        }
+        if kernel.normalized {
+            sum / weights
+        } else {
+            sum
+        }
+    })
+}
+
+/// Calls `func` on the neighborhood of each valid pixel, given as `(index, value)` pairs with
+/// the index in row-major order within the neighborhood and `None` for missing values.
+fn process_neighborhoods<F>(
+    band: Band,
+    halo: (usize, usize),
+    mut func: F,
+) -> TiffResult<InMemoryRaster>
+where
+    F: FnMut(&[(usize, Option<f64>)]) -> f64,
+{
+    let (halo_x, halo_y) = halo;
+    let (size_x, size_y) = (2 * halo_x + 1, 2 * halo_y + 1);
+    let mut neighbors = Vec::with_capacity(size_x * size_y);
+
+    BlockMap::new(band.image(), |window: Window| {
+        let padded = Window::new(
+            window.x - halo_x as i64,
+            window.y - halo_y as i64,
+            window.width + 2 * halo_x,
+            window.height + 2 * halo_y,
+        );
+        let values = band.read_window(padded)?;
+        let value_at = |x: usize, y: usize| {
+            let value = values[y * padded.width + x];
+            (!band.is_nodata(value)).then_some(value)
+        };
+
+        let mut data = vec![f64::NAN; window.width * window.height];
+        for y in 0..window.height {
+            for x in 0..window.width {
+                if value_at(x + halo_x, y + halo_y).is_none() {
+                    continue;
+                }
+                neighbors.clear();
+                for j in 0..size_y {
+                    for i in 0..size_x {
+                        neighbors.push((j * size_x + i, value_at(x + i, y + j)));
+                    }
+                }
+                data[y * window.width + x] = func(&neighbors);
+            }
+        }
+        Ok(data)
+    })
+    .collect()
+}
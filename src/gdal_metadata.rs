@@ -0,0 +1,143 @@
+use crate::{GeoTiff, Image};
+
+/// The GDAL_METADATA tag, which is not known to the tiff crate.
+pub(crate) const GDAL_METADATA_TAG: u16 = 42112;
+
+/// The metadata stored by GDAL in the GDAL_METADATA tag, as XML items of the dataset or of its
+/// bands.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GdalMetadata {
+    pub items: Vec<MetadataItem>,
+}
+
+/// An item of [GdalMetadata], e.g. `<Item name="OFFSET" sample="0" role="offset">0</Item>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataItem {
+    pub name: String,
+    /// The band of the item, or `None` for dataset items.
+    pub sample: Option<usize>,
+    pub role: Option<String>,
+    pub value: String,
+}
+
+impl GdalMetadata {
+    /// Parses the XML content of the tag. Items which cannot be parsed are skipped.
+    pub fn parse(xml: &str) -> Self {
+        let mut items = Vec::new();
+        let mut rest = xml;
+        while let Some(start) = rest.find("<Item") {
+            rest = &rest[start + "<Item".len()..];
+            let Some(end) = rest.find('>') else {
+                break;
+            };
+            let attributes = &rest[..end];
+            rest = &rest[end + 1..];
+            let value = if attributes.ends_with('/') {
+                ""
+            } else {
+                let Some(close) = rest.find("</Item>") else {
+                    break;
+                };
+                let value = &rest[..close];
+                rest = &rest[close + "</Item>".len()..];
+                value
+            };
+            let Some(name) = attribute(attributes, "name") else {
+                continue;
+            };
+            items.push(MetadataItem {
+                name,
+                sample: attribute(attributes, "sample").and_then(|sample| sample.parse().ok()),
+                role: attribute(attributes, "role"),
+                value: unescape(value.trim()),
+            });
+        }
+        Self { items }
+    }
+
+    /// Formats the items as the XML content of the tag.
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::from("<GDALMetadata>\n");
+        for item in &self.items {
+            xml += &format!("  <Item name=\"{}\"", escape(&item.name));
+            if let Some(sample) = item.sample {
+                xml += &format!(" sample=\"{}\"", sample);
+            }
+            if let Some(role) = &item.role {
+                xml += &format!(" role=\"{}\"", escape(role));
+            }
+            xml += &format!(">{}</Item>\n", escape(&item.value));
+        }
+        xml + "</GDALMetadata>"
+    }
+
+    /// Returns the value of the dataset item with the given name, if any.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.find(None, name)
+    }
+
+    /// Returns the value of the item of the band with the given name, if any.
+    pub fn band_item(&self, sample: usize, name: &str) -> Option<&str> {
+        self.find(Some(sample), name)
+    }
+
+    fn find(&self, sample: Option<usize>, name: &str) -> Option<&str> {
+        self.items
+            .iter()
+            .find(|item| item.sample == sample && item.role.is_none() && item.name == name)
+            .map(|item| item.value.as_str())
+    }
+}
+
+impl Image {
+    /// Returns the metadata of the GDAL_METADATA tag of this image, if any.
+    pub fn gdal_metadata(&self) -> Option<&GdalMetadata> {
+        self.gdal_metadata.as_ref()
+    }
+}
+
+impl GeoTiff {
+    /// See [Image::gdal_metadata].
+    pub fn gdal_metadata(&self) -> Option<&GdalMetadata> {
+        self.primary().gdal_metadata()
+    }
+}
+
+/// Returns the unescaped value of the attribute, e.g. `name="value"`.
+fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let mut rest = attributes;
+    while let Some(start) = rest.find(name) {
+        let preceded_by_space = rest[..start]
+            .chars()
+            .next_back()
+            .is_none_or(char::is_whitespace);
+        rest = &rest[start + name.len()..];
+        let Some(value) = rest.trim_start().strip_prefix('=') else {
+            continue;
+        };
+        if !preceded_by_space {
+            continue;
+        }
+        let value = value.trim_start();
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value = &value[1..];
+        return Some(unescape(&value[..value.find(quote)?]));
+    }
+    None
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
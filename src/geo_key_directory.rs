@@ -1,12 +1,28 @@
+use std::fmt;
+#[cfg(feature = "decode")]
+use std::io::{Read, Seek};
+
 use num_enum::{IntoPrimitive, TryFromPrimitive};
+#[cfg(feature = "decode")]
+use tiff::decoder::Decoder;
+#[cfg(feature = "decode")]
 use tiff::tags::Tag;
-use tiff::{TiffError, TiffFormatError, TiffResult};
+#[cfg(feature = "decode")]
+use tiff::{TiffError, TiffResult};
+
+use crate::code_tables::code_name;
+use crate::{GeoTag, MetadataError, MetadataResult};
+
+const USER_DEFINED: u16 = 32767;
+
+const MODEL_TYPE_PROJECTED: u16 = 1;
+const MODEL_TYPE_GEOGRAPHIC: u16 = 2;
 
 /// The GeoKeyDirectoryTag Requirements Class specifies the requirements for
 /// implementing the reserved GeoKeyDirectoryTag TIFF tag.
 ///
 /// Ref: https://docs.ogc.org/is/19-008r4/19-008r4.html#_requirements_class_geokeydirectorytag
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct GeoKeyDirectory {
     pub key_directory_version: u16,
     pub key_revision: u16,
@@ -63,260 +79,750 @@ impl GeoKeyDirectory {
         directory_data: &[u16],
         double_params: &[f64],
         ascii_params: &str,
-    ) -> TiffResult<Self> {
+    ) -> MetadataResult<Self> {
+        Self::parse(directory_data, double_params, |entry| {
+            entry.string(ascii_params).map(Some)
+        })
+    }
+
+    /// Like [GeoKeyDirectory::from_tag_data], but reads the ASCII values leniently, see
+    /// [DirectoryEntry::string_lenient]. ASCII keys whose value cannot be read at all, e.g.
+    /// because their offset is out of bounds, are skipped with a warning.
+    pub fn from_tag_data_lenient(
+        directory_data: &[u16],
+        double_params: &[f64],
+        ascii_params: &[u8],
+    ) -> MetadataResult<Self> {
+        Self::parse(directory_data, double_params, |entry| {
+            match entry.string_lenient(ascii_params) {
+                Ok(value) => Ok(Some(value)),
+                Err(e) => {
+                    log::warn!("Skipping key `{:?}`: {e}", entry.key_id);
+                    Ok(None)
+                }
+            }
+        })
+    }
+
+    /// Parses the directory, reading the ASCII values with `string`.
+    fn parse<F>(
+        directory_data: &[u16],
+        double_params: &[f64],
+        mut string: F,
+    ) -> MetadataResult<Self>
+    where
+        F: FnMut(&DirectoryEntry) -> MetadataResult<Option<String>>,
+    {
         let mut directory = Self::default();
-        if directory_data.len() < 4 {
-            return Err(TiffError::FormatError(TiffFormatError::Format(
-                "Unexpected length of directory data: must be at least 4.".into(),
-            )));
-        }
+        let entries = DirectoryEntry::from_directory_data(directory_data)?;
 
         directory.key_directory_version = directory_data[0];
         directory.key_revision = directory_data[1];
         directory.minor_revision = directory_data[2];
-        let number_of_keys = directory_data[3] as usize;
 
-        if directory_data.len() - 4 != 4 * number_of_keys {
-            return Err(TiffError::FormatError(TiffFormatError::Format(
-                "Unexpected length of directory data: number of keys does not match length of directory data.".into())
-            ));
-        }
-
-        for entry in directory_data[4..].chunks_exact(4) {
-            let entry = DirectoryEntry {
-                key_tag: GeoKeyDirectoryTag::try_from(entry[0]).map_err(|_| {
-                    TiffError::FormatError(TiffFormatError::Format(format!(
-                        "Unknown GeoKeyDirectoryTag: {}",
-                        entry[0]
-                    )))
-                })?,
-                location_tag: Tag::from_u16(entry[1]),
-                count: entry[2],
-                value_or_offset: entry[3],
-            };
-
-            match entry.key_tag {
-                GeoKeyDirectoryTag::ModelType => directory.model_type = Some(entry.short()?),
-                GeoKeyDirectoryTag::RasterType => {
+        for entry in entries {
+            match entry.key_id {
+                GeoKeyId::ModelType => directory.model_type = Some(entry.short()?),
+                GeoKeyId::RasterType => {
                     let raster_type = entry.short()?;
                     directory.raster_type =
                         Some(RasterType::try_from(raster_type).map_err(|_| {
-                            TiffError::FormatError(TiffFormatError::Format(format!(
-                                "Unknown raster type: {raster_type}"
-                            )))
+                            MetadataError::Format(format!("Unknown raster type: {raster_type}"))
                         })?)
                 }
-                GeoKeyDirectoryTag::Citation => {
-                    directory.citation = Some(entry.string(ascii_params)?)
-                }
-                GeoKeyDirectoryTag::GeographicType => {
-                    directory.geographic_type = Some(entry.short()?)
-                }
-                GeoKeyDirectoryTag::GeogCitation => {
-                    directory.geog_citation = Some(entry.string(ascii_params)?)
-                }
-                GeoKeyDirectoryTag::GeogGeodeticDatum => {
-                    directory.geog_geodetic_datum = Some(entry.short()?)
-                }
-                GeoKeyDirectoryTag::GeogPrimeMeridian => {
-                    directory.geog_prime_meridian = Some(entry.short()?)
-                }
-                GeoKeyDirectoryTag::GeogLinearUnits => {
-                    directory.geog_linear_units = Some(entry.short()?)
-                }
-                GeoKeyDirectoryTag::GeogLinearUnitSize => {
+                GeoKeyId::Citation => directory.citation = string(&entry)?,
+                GeoKeyId::GeographicType => directory.geographic_type = Some(entry.short()?),
+                GeoKeyId::GeogCitation => directory.geog_citation = string(&entry)?,
+                GeoKeyId::GeogGeodeticDatum => directory.geog_geodetic_datum = Some(entry.short()?),
+                GeoKeyId::GeogPrimeMeridian => directory.geog_prime_meridian = Some(entry.short()?),
+                GeoKeyId::GeogLinearUnits => directory.geog_linear_units = Some(entry.short()?),
+                GeoKeyId::GeogLinearUnitSize => {
                     directory.geog_linear_unit_size = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::GeogAngularUnits => {
-                    directory.geog_angular_units = Some(entry.short()?)
-                }
-                GeoKeyDirectoryTag::GeogAngularUnitSize => {
+                GeoKeyId::GeogAngularUnits => directory.geog_angular_units = Some(entry.short()?),
+                GeoKeyId::GeogAngularUnitSize => {
                     directory.geog_angular_unit_size = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::GeogEllipsoid => {
-                    directory.geog_ellipsoid = Some(entry.short()?)
-                }
-                GeoKeyDirectoryTag::GeogSemiMajorAxis => {
+                GeoKeyId::GeogEllipsoid => directory.geog_ellipsoid = Some(entry.short()?),
+                GeoKeyId::GeogSemiMajorAxis => {
                     directory.geog_semi_major_axis = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::GeogSemiMinorAxis => {
+                GeoKeyId::GeogSemiMinorAxis => {
                     directory.geog_semi_minor_axis = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::GeogInvFlattening => {
+                GeoKeyId::GeogInvFlattening => {
                     directory.geog_inv_flattening = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::GeogAzimuthUnits => {
-                    directory.geog_azimuth_units = Some(entry.short()?)
-                }
-                GeoKeyDirectoryTag::GeogPrimeMeridianLong => {
+                GeoKeyId::GeogAzimuthUnits => directory.geog_azimuth_units = Some(entry.short()?),
+                GeoKeyId::GeogPrimeMeridianLong => {
                     directory.geog_prime_meridian_long = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::ProjectedType => {
-                    directory.projected_type = Some(entry.short()?)
-                }
-                GeoKeyDirectoryTag::ProjCitation => {
-                    directory.proj_citation = Some(entry.string(ascii_params)?)
-                }
-                GeoKeyDirectoryTag::Projection => directory.projection = Some(entry.short()?),
-                GeoKeyDirectoryTag::ProjCoordTrans => {
-                    directory.proj_coord_trans = Some(entry.short()?)
-                }
-                GeoKeyDirectoryTag::ProjLinearUnits => {
-                    directory.proj_linear_units = Some(entry.short()?)
-                }
-                GeoKeyDirectoryTag::ProjLinearUnitSize => {
+                GeoKeyId::ProjectedType => directory.projected_type = Some(entry.short()?),
+                GeoKeyId::ProjCitation => directory.proj_citation = string(&entry)?,
+                GeoKeyId::Projection => directory.projection = Some(entry.short()?),
+                GeoKeyId::ProjCoordTrans => directory.proj_coord_trans = Some(entry.short()?),
+                GeoKeyId::ProjLinearUnits => directory.proj_linear_units = Some(entry.short()?),
+                GeoKeyId::ProjLinearUnitSize => {
                     directory.proj_linear_unit_size = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::ProjStdParallel1 => {
+                GeoKeyId::ProjStdParallel1 => {
                     directory.proj_std_parallel1 = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::ProjStdParallel2 => {
+                GeoKeyId::ProjStdParallel2 => {
                     directory.proj_std_parallel2 = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::ProjNatOriginLong => {
+                GeoKeyId::ProjNatOriginLong => {
                     directory.proj_nat_origin_long = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::ProjNatOriginLat => {
+                GeoKeyId::ProjNatOriginLat => {
                     directory.proj_nat_origin_lat = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::ProjFalseEasting => {
+                GeoKeyId::ProjFalseEasting => {
                     directory.proj_false_easting = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::ProjFalseNorthing => {
+                GeoKeyId::ProjFalseNorthing => {
                     directory.proj_false_northing = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::ProjFalseOriginLong => {
+                GeoKeyId::ProjFalseOriginLong => {
                     directory.proj_false_origin_long = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::ProjFalseOriginLat => {
+                GeoKeyId::ProjFalseOriginLat => {
                     directory.proj_false_origin_lat = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::ProjFalseOriginEasting => {
+                GeoKeyId::ProjFalseOriginEasting => {
                     directory.proj_false_origin_easting = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::ProjFalseOriginNorthing => {
+                GeoKeyId::ProjFalseOriginNorthing => {
                     directory.proj_false_origin_northing = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::ProjCenterLong => {
+                GeoKeyId::ProjCenterLong => {
                     directory.proj_center_long = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::ProjCenterLat => {
+                GeoKeyId::ProjCenterLat => {
                     directory.proj_center_lat = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::ProjCenterEasting => {
+                GeoKeyId::ProjCenterEasting => {
                     directory.proj_center_easting = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::ProjCenterNorthing => {
+                GeoKeyId::ProjCenterNorthing => {
                     directory.proj_center_northing = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::ProjScaleAtNatOrigin => {
+                GeoKeyId::ProjScaleAtNatOrigin => {
                     directory.proj_scale_at_nat_origin = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::ProjScaleAtCenter => {
+                GeoKeyId::ProjScaleAtCenter => {
                     directory.proj_scale_at_center = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::ProjAzimuthAngle => {
+                GeoKeyId::ProjAzimuthAngle => {
                     directory.proj_azimuth_angle = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::ProjStraightVertPoleLong => {
+                GeoKeyId::ProjStraightVertPoleLong => {
                     directory.proj_straight_vert_pole_long = Some(entry.double(double_params)?)
                 }
-                GeoKeyDirectoryTag::Vertical => directory.vertical = Some(entry.short()?),
-                GeoKeyDirectoryTag::VerticalCitation => {
-                    directory.vertical_citation = Some(entry.string(ascii_params)?)
-                }
-                GeoKeyDirectoryTag::VerticalDatum => {
-                    directory.vertical_datum = Some(entry.short()?)
-                }
-                GeoKeyDirectoryTag::VerticalUnits => {
-                    directory.vertical_units = Some(entry.short()?)
-                }
+                GeoKeyId::Vertical => directory.vertical = Some(entry.short()?),
+                GeoKeyId::VerticalCitation => directory.vertical_citation = string(&entry)?,
+                GeoKeyId::VerticalDatum => directory.vertical_datum = Some(entry.short()?),
+                GeoKeyId::VerticalUnits => directory.vertical_units = Some(entry.short()?),
             }
         }
 
         Ok(directory)
     }
+
+    /// Returns the geo keys of the CRS with the given EPSG code, assuming codes from 4000 to 4999
+    /// are geographic CRS and others projected CRS, as is the case for the most common codes.
+    pub fn from_epsg(code: u16) -> Self {
+        if (4000..5000).contains(&code) {
+            Self {
+                model_type: Some(MODEL_TYPE_GEOGRAPHIC),
+                geographic_type: Some(code),
+                ..Default::default()
+            }
+        } else {
+            Self {
+                model_type: Some(MODEL_TYPE_PROJECTED),
+                projected_type: Some(code),
+                ..Default::default()
+            }
+        }
+    }
+
+    /// Returns whether the model is a projected CRS, as given by the GTModelTypeGeoKey or, if it
+    /// is missing, by the presence of the ProjectedCRSGeoKey.
+    pub fn is_projected(&self) -> bool {
+        match self.model_type {
+            Some(model_type) => model_type == MODEL_TYPE_PROJECTED,
+            None => self.projected_type.is_some(),
+        }
+    }
+
+    /// Returns whether the model is a geographic CRS, as given by the GTModelTypeGeoKey or, if
+    /// it is missing, by the presence of the GeodeticCRSGeoKey alone.
+    pub fn is_geographic(&self) -> bool {
+        match self.model_type {
+            Some(model_type) => model_type == MODEL_TYPE_GEOGRAPHIC,
+            None => self.projected_type.is_none() && self.geographic_type.is_some(),
+        }
+    }
+
+    /// Returns whether the CRS is geographic and defined by an EPSG code, whose axis order is
+    /// latitude first.
+    pub fn has_latitude_first_axis_order(&self) -> bool {
+        self.is_geographic()
+            && self
+                .geographic_type
+                .is_some_and(|code| (1024..USER_DEFINED).contains(&code))
+    }
+
+    /// Lists the keys which differ between this directory and `other`, ordered by key ID.
+    ///
+    /// Only the keys are compared, not the version numbers of the directories. Doubles are
+    /// compared by their bits, so that a NaN value is unchanged when both directories carry it.
+    pub fn diff(&self, other: &GeoKeyDirectory) -> Vec<KeyDiff> {
+        let old_entries = self.iter().collect::<Vec<_>>();
+        let new_entries = other.iter().collect::<Vec<_>>();
+        let find = |entries: &[(GeoKeyId, GeoKeyValue)], key| {
+            entries
+                .iter()
+                .find_map(|(k, value)| (*k == key).then_some(value))
+                .cloned()
+        };
+
+        let mut diffs = Vec::new();
+        for (key, old) in &old_entries {
+            match find(&new_entries, *key) {
+                None => diffs.push(KeyDiff::Removed {
+                    key: *key,
+                    value: old.clone(),
+                }),
+                Some(new) if !new.is_same_as(old) => diffs.push(KeyDiff::Changed {
+                    key: *key,
+                    old: old.clone(),
+                    new,
+                }),
+                Some(_) => {}
+            }
+        }
+        for (key, new) in &new_entries {
+            if find(&old_entries, *key).is_none() {
+                diffs.push(KeyDiff::Added {
+                    key: *key,
+                    value: new.clone(),
+                });
+            }
+        }
+        diffs.sort_by_key(|diff| u16::from(diff.key()));
+        diffs
+    }
+
+    /// Overlays `overrides` on this directory: the keys set in `overrides` replace those of this
+    /// directory, the others are kept. The version numbers of this directory are kept.
+    ///
+    /// Also returns the conflicts, i.e. the keys set in both directories with different values,
+    /// as [KeyDiff::Changed] from the value of this directory to the overriding value.
+    ///
+    /// Fails if the merged keys do not fit in a directory, see [GeoKeyDirectory::to_tag_data].
+    pub fn merged_with(
+        &self,
+        overrides: &GeoKeyDirectory,
+    ) -> MetadataResult<(GeoKeyDirectory, Vec<KeyDiff>)> {
+        let conflicts = self
+            .diff(overrides)
+            .into_iter()
+            .filter(|diff| matches!(diff, KeyDiff::Changed { .. }))
+            .collect();
+
+        let mut entries = overrides.iter().collect::<Vec<_>>();
+        for (key, value) in self.iter() {
+            if !entries.iter().any(|(k, _)| *k == key) {
+                entries.push((key, value));
+            }
+        }
+        entries.sort_by_key(|(key, _)| u16::from(*key));
+        let (directory_data, double_params, ascii_params) = encode_tag_data(
+            [
+                self.key_directory_version,
+                self.key_revision,
+                self.minor_revision,
+            ],
+            entries,
+        )?;
+        let merged =
+            GeoKeyDirectory::from_tag_data(&directory_data, &double_params, &ascii_params)?;
+        Ok((merged, conflicts))
+    }
+
+    /// Returns the values of the GeoKeyDirectoryTag, GeoDoubleParamsTag and GeoAsciiParamsTag
+    /// encoding this directory, the inverse of [GeoKeyDirectory::from_tag_data].
+    ///
+    /// Fails if the parameters are too long for their offsets and counts, which are 16-bit.
+    pub fn to_tag_data(&self) -> MetadataResult<(Vec<u16>, Vec<f64>, String)> {
+        encode_tag_data(
+            [
+                self.key_directory_version,
+                self.key_revision,
+                self.minor_revision,
+            ],
+            self.iter(),
+        )
+    }
+
+    /// Iterates over the keys which are set along with their values, in the order of their IDs.
+    pub fn iter(&self) -> impl Iterator<Item = (GeoKeyId, GeoKeyValue)> {
+        [
+            (GeoKeyId::ModelType, self.model_type.map(GeoKeyValue::Short)),
+            (
+                GeoKeyId::RasterType,
+                self.raster_type
+                    .map(|raster_type| GeoKeyValue::Short(raster_type.into())),
+            ),
+            (
+                GeoKeyId::Citation,
+                self.citation.clone().map(GeoKeyValue::Ascii),
+            ),
+            (
+                GeoKeyId::GeographicType,
+                self.geographic_type.map(GeoKeyValue::Short),
+            ),
+            (
+                GeoKeyId::GeogCitation,
+                self.geog_citation.clone().map(GeoKeyValue::Ascii),
+            ),
+            (
+                GeoKeyId::GeogGeodeticDatum,
+                self.geog_geodetic_datum.map(GeoKeyValue::Short),
+            ),
+            (
+                GeoKeyId::GeogPrimeMeridian,
+                self.geog_prime_meridian.map(GeoKeyValue::Short),
+            ),
+            (
+                GeoKeyId::GeogLinearUnits,
+                self.geog_linear_units.map(GeoKeyValue::Short),
+            ),
+            (
+                GeoKeyId::GeogLinearUnitSize,
+                self.geog_linear_unit_size.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::GeogAngularUnits,
+                self.geog_angular_units.map(GeoKeyValue::Short),
+            ),
+            (
+                GeoKeyId::GeogAngularUnitSize,
+                self.geog_angular_unit_size.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::GeogEllipsoid,
+                self.geog_ellipsoid.map(GeoKeyValue::Short),
+            ),
+            (
+                GeoKeyId::GeogSemiMajorAxis,
+                self.geog_semi_major_axis.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::GeogSemiMinorAxis,
+                self.geog_semi_minor_axis.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::GeogInvFlattening,
+                self.geog_inv_flattening.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::GeogAzimuthUnits,
+                self.geog_azimuth_units.map(GeoKeyValue::Short),
+            ),
+            (
+                GeoKeyId::GeogPrimeMeridianLong,
+                self.geog_prime_meridian_long.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::ProjectedType,
+                self.projected_type.map(GeoKeyValue::Short),
+            ),
+            (
+                GeoKeyId::ProjCitation,
+                self.proj_citation.clone().map(GeoKeyValue::Ascii),
+            ),
+            (
+                GeoKeyId::Projection,
+                self.projection.map(GeoKeyValue::Short),
+            ),
+            (
+                GeoKeyId::ProjCoordTrans,
+                self.proj_coord_trans.map(GeoKeyValue::Short),
+            ),
+            (
+                GeoKeyId::ProjLinearUnits,
+                self.proj_linear_units.map(GeoKeyValue::Short),
+            ),
+            (
+                GeoKeyId::ProjLinearUnitSize,
+                self.proj_linear_unit_size.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::ProjStdParallel1,
+                self.proj_std_parallel1.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::ProjStdParallel2,
+                self.proj_std_parallel2.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::ProjNatOriginLong,
+                self.proj_nat_origin_long.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::ProjNatOriginLat,
+                self.proj_nat_origin_lat.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::ProjFalseEasting,
+                self.proj_false_easting.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::ProjFalseNorthing,
+                self.proj_false_northing.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::ProjFalseOriginLong,
+                self.proj_false_origin_long.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::ProjFalseOriginLat,
+                self.proj_false_origin_lat.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::ProjFalseOriginEasting,
+                self.proj_false_origin_easting.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::ProjFalseOriginNorthing,
+                self.proj_false_origin_northing.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::ProjCenterLong,
+                self.proj_center_long.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::ProjCenterLat,
+                self.proj_center_lat.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::ProjCenterEasting,
+                self.proj_center_easting.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::ProjCenterNorthing,
+                self.proj_center_northing.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::ProjScaleAtNatOrigin,
+                self.proj_scale_at_nat_origin.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::ProjScaleAtCenter,
+                self.proj_scale_at_center.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::ProjAzimuthAngle,
+                self.proj_azimuth_angle.map(GeoKeyValue::Double),
+            ),
+            (
+                GeoKeyId::ProjStraightVertPoleLong,
+                self.proj_straight_vert_pole_long.map(GeoKeyValue::Double),
+            ),
+            (GeoKeyId::Vertical, self.vertical.map(GeoKeyValue::Short)),
+            (
+                GeoKeyId::VerticalCitation,
+                self.vertical_citation.clone().map(GeoKeyValue::Ascii),
+            ),
+            (
+                GeoKeyId::VerticalDatum,
+                self.vertical_datum.map(GeoKeyValue::Short),
+            ),
+            (
+                GeoKeyId::VerticalUnits,
+                self.vertical_units.map(GeoKeyValue::Short),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value?)))
+    }
+}
+
+/// Encodes the given keys, in order, after the given version numbers of a directory.
+fn encode_tag_data(
+    header: [u16; 3],
+    entries: impl IntoIterator<Item = (GeoKeyId, GeoKeyValue)>,
+) -> MetadataResult<(Vec<u16>, Vec<f64>, String)> {
+    let to_u16 = |value: usize, name: &str| {
+        u16::try_from(value).map_err(|_| {
+            MetadataError::Format(format!(
+                "The {} {} does not fit in a geo key directory",
+                name, value
+            ))
+        })
+    };
+    let mut double_params = Vec::new();
+    let mut ascii_params = String::new();
+    let mut encoded = Vec::new();
+    for (key, value) in entries {
+        let entry = match value {
+            GeoKeyValue::Short(value) => [key.into(), 0, 1, value],
+            GeoKeyValue::Double(value) => {
+                let offset = to_u16(double_params.len(), "double offset")?;
+                double_params.push(value);
+                [key.into(), GeoTag::GeoDoubleParams.to_u16(), 1, offset]
+            }
+            GeoKeyValue::Ascii(value) => {
+                let offset = to_u16(ascii_params.len(), "ASCII offset")?;
+                let count = to_u16(value.len() + 1, "ASCII count")?;
+                ascii_params.push_str(&value);
+                ascii_params.push('|');
+                [key.into(), GeoTag::GeoAsciiParams.to_u16(), count, offset]
+            }
+        };
+        encoded.push(entry);
+    }
+
+    let mut directory_data = header.to_vec();
+    directory_data.push(to_u16(encoded.len(), "number of keys")?);
+    directory_data.extend(encoded.into_iter().flatten());
+    Ok((directory_data, double_params, ascii_params))
+}
+
+/// Reads the geo keys from the tags of the current IFD of a decoder. Fails if the IFD has no
+/// GeoKeyDirectoryTag.
+#[cfg(feature = "decode")]
+impl<R: Read + Seek> TryFrom<&mut Decoder<R>> for GeoKeyDirectory {
+    type Error = TiffError;
+
+    fn try_from(decoder: &mut Decoder<R>) -> TiffResult<Self> {
+        let directory_data = decoder.get_tag_u16_vec(Tag::GeoKeyDirectoryTag)?;
+        let double_params = match decoder.find_tag(Tag::GeoDoubleParamsTag)? {
+            Some(v) => v.into_f64_vec()?,
+            None => Vec::new(),
+        };
+        let ascii_params = match decoder.find_tag(Tag::GeoAsciiParamsTag)? {
+            Some(v) => v.into_string()?,
+            None => String::new(),
+        };
+        Ok(Self::from_tag_data(
+            &directory_data,
+            &double_params,
+            &ascii_params,
+        )?)
+    }
 }
 
-struct DirectoryEntry {
-    key_tag: GeoKeyDirectoryTag,
-    location_tag: Option<Tag>,
-    count: u16,
-    value_or_offset: u16,
+/// Lists the keys which are set, one per line, with the names of known codes.
+impl fmt::Display for GeoKeyDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "GeoKeyDirectory version {}, revision {}.{}",
+            self.key_directory_version, self.key_revision, self.minor_revision
+        )?;
+        for (key, value) in self.iter() {
+            write!(f, "\n  {key:?}: {value}")?;
+            if let GeoKeyValue::Short(code) = value {
+                if let Some(name) = code_name(key, code) {
+                    write!(f, " ({name})")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A raw entry of the GeoKeyDirectoryTag.
+///
+/// The value of the key is either stored in the entry itself, or at an offset in the
+/// GeoDoubleParamsTag or GeoAsciiParamsTag, depending on `location`.
+///
+/// Ref: https://docs.ogc.org/is/19-008r4/19-008r4.html#_requirements_class_geokeydirectorytag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirectoryEntry {
+    pub key_id: GeoKeyId,
+    /// The tag holding the value, `None` if the value is a SHORT stored in `value_or_offset`, or
+    /// if the tag is not a GeoTIFF tag.
+    pub location: Option<GeoTag>,
+    pub count: u16,
+    pub value_or_offset: u16,
 }
 
 impl DirectoryEntry {
-    fn short(&self) -> TiffResult<u16> {
+    /// Parses the entries of a GeoKeyDirectoryTag, after checking the length given in its header.
+    pub fn from_directory_data(directory_data: &[u16]) -> MetadataResult<Vec<Self>> {
+        if directory_data.len() < 4 {
+            return Err(MetadataError::Format(
+                "Unexpected length of directory data: must be at least 4.".into(),
+            ));
+        }
+
+        let number_of_keys = directory_data[3] as usize;
+        if directory_data.len() - 4 != 4 * number_of_keys {
+            return Err(MetadataError::Format(
+                "Unexpected length of directory data: number of keys does not match length of directory data.".into())
+            );
+        }
+
+        directory_data[4..]
+            .chunks_exact(4)
+            .map(|entry| Self::from_raw([entry[0], entry[1], entry[2], entry[3]]))
+            .collect()
+    }
+
+    /// Parses an entry from its four SHORT values: key ID, location, count and value or offset.
+    pub fn from_raw(entry: [u16; 4]) -> MetadataResult<Self> {
+        Ok(Self {
+            key_id: GeoKeyId::try_from(entry[0])
+                .map_err(|_| MetadataError::Format(format!("Unknown GeoKey ID: {}", entry[0])))?,
+            location: GeoTag::from_u16(entry[1]),
+            count: entry[2],
+            value_or_offset: entry[3],
+        })
+    }
+
+    /// Returns the four SHORT values of the entry as stored in the GeoKeyDirectoryTag.
+    pub fn to_raw(&self) -> [u16; 4] {
+        [
+            self.key_id.into(),
+            self.location.map_or(0, |tag| tag.to_u16()),
+            self.count,
+            self.value_or_offset,
+        ]
+    }
+
+    /// Returns the SHORT value stored in the entry itself.
+    pub fn short(&self) -> MetadataResult<u16> {
         // Check that TIFFTagLocation == 0 so value is of SHORT type
-        if self.location_tag.is_some() {
-            return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+        if self.location.is_some() {
+            return Err(MetadataError::Format(format!(
                 "Key `{:?}` did not have the expected SHORT value type.",
-                self.key_tag
-            ))));
+                self.key_id
+            )));
         }
 
         if self.count != 1 {
-            return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+            return Err(MetadataError::Format(format!(
                 "Unexpected count: expected 1, got {}.",
                 self.count
-            ))));
+            )));
         }
 
         Ok(self.value_or_offset)
     }
 
-    fn double(&self, data: &[f64]) -> TiffResult<f64> {
-        if self.location_tag != Some(Tag::GeoDoubleParamsTag) {
-            return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+    /// Returns the DOUBLE value from the given GeoDoubleParamsTag data.
+    pub fn double(&self, data: &[f64]) -> MetadataResult<f64> {
+        if self.location != Some(GeoTag::GeoDoubleParams) {
+            return Err(MetadataError::Format(format!(
                 "Key `{:?}` did not have the expected DOUBLE value type.",
-                self.key_tag
-            ))));
+                self.key_id
+            )));
         }
 
         if self.count != 1 {
-            return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+            return Err(MetadataError::Format(format!(
                 "Unexpected count: expected 1, got {}.",
                 self.count
-            ))));
+            )));
         }
 
         match data.get(self.value_or_offset as usize) {
-            None => Err(TiffError::FormatError(TiffFormatError::Format(format!(
+            None => Err(MetadataError::Format(format!(
                 "Offset out of bounds: the length is {} but the offset is {}",
                 data.len(),
                 self.value_or_offset
-            )))),
+            ))),
             Some(value) => Ok(*value),
         }
     }
 
-    fn string(&self, data: &str) -> TiffResult<String> {
-        if self.location_tag != Some(Tag::GeoAsciiParamsTag) {
-            return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+    /// Returns the ASCII value from the given GeoAsciiParamsTag data, without its terminating
+    /// `|` separator.
+    pub fn string(&self, data: &str) -> MetadataResult<String> {
+        self.str(data).map(String::from)
+    }
+
+    /// Like [DirectoryEntry::string], but borrows the value from the data.
+    pub fn str<'a>(&self, data: &'a str) -> MetadataResult<&'a str> {
+        if self.location != Some(GeoTag::GeoAsciiParams) {
+            return Err(MetadataError::Format(format!(
                 "Key `{:?}` did not have the expected ASCII value type.",
-                self.key_tag
-            ))));
+                self.key_id
+            )));
         }
 
         let start = self.value_or_offset as usize;
         if start >= data.len() {
-            return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+            return Err(MetadataError::Format(format!(
                 "Start offset out of bounds: the length is {} but the offset is {}.",
                 data.len(),
                 self.value_or_offset
-            ))));
+            )));
         }
 
-        let end = (self.value_or_offset + self.count - 1) as usize;
+        // The count includes the terminating `|`
+        if self.count == 0 {
+            return Err(MetadataError::Format(format!(
+                "Unexpected count: expected at least 1, got {}.",
+                self.count
+            )));
+        }
+        let end = start + self.count as usize - 1;
         if end >= data.len() {
-            return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+            return Err(MetadataError::Format(format!(
                 "End offset out of bounds: the length is {} but the offset is {}.",
                 data.len(),
                 self.value_or_offset
-            ))));
+            )));
         }
 
-        Ok(String::from(&data[start..end]))
+        match data.get(start..end) {
+            Some(value) => Ok(value),
+            None => Err(MetadataError::Format(format!(
+                "Offsets {start} and {end} are not on character boundaries."
+            ))),
+        }
+    }
+
+    /// Returns the ASCII value from the given GeoAsciiParamsTag data, following the conventions
+    /// of real files rather than the exact offsets and count:
+    /// - the value ends at the first `|` or NUL separator, whether the count includes the
+    ///   terminator or not, or at the end of the data,
+    /// - a count of zero reads up to the next separator,
+    /// - bytes which are not valid UTF-8 are replaced by U+FFFD.
+    ///
+    /// Only a wrong location or a start offset past the end of the data are errors.
+    pub fn string_lenient(&self, data: &[u8]) -> MetadataResult<String> {
+        if self.location != Some(GeoTag::GeoAsciiParams) {
+            return Err(MetadataError::Format(format!(
+                "Key `{:?}` did not have the expected ASCII value type.",
+                self.key_id
+            )));
+        }
+
+        let start = self.value_or_offset as usize;
+        if start > data.len() {
+            return Err(MetadataError::Format(format!(
+                "Start offset out of bounds: the length is {} but the offset is {}.",
+                data.len(),
+                self.value_or_offset
+            )));
+        }
+
+        let end = match self.count {
+            0 => data.len(),
+            count => (start + count as usize).min(data.len()),
+        };
+        let value = data[start..end]
+            .split(|byte| matches!(byte, b'|' | 0))
+            .next()
+            .unwrap_or_default();
+        Ok(String::from_utf8_lossy(value).into_owned())
     }
 }
 
@@ -382,9 +888,9 @@ impl Default for GeoKeyDirectory {
 /// GeoTIFF key names and IDs.
 ///
 /// Ref: https://docs.ogc.org/is/19-008r4/19-008r4.html#_summary_of_geokey_ids_and_names
-#[derive(Debug, TryFromPrimitive, IntoPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
 #[repr(u16)]
-enum GeoKeyDirectoryTag {
+pub enum GeoKeyId {
     // GeoTIFF configuration keys
     ModelType = 1024,
     RasterType = 1025,
@@ -439,6 +945,101 @@ enum GeoKeyDirectoryTag {
     VerticalUnits = 4099,
 }
 
+impl GeoKeyId {
+    /// Returns the tag in which the value of the key is stored, `None` for SHORT values stored in
+    /// the directory entry itself.
+    pub fn location(self) -> Option<GeoTag> {
+        match self {
+            GeoKeyId::Citation
+            | GeoKeyId::GeogCitation
+            | GeoKeyId::ProjCitation
+            | GeoKeyId::VerticalCitation => Some(GeoTag::GeoAsciiParams),
+            GeoKeyId::GeogLinearUnitSize
+            | GeoKeyId::GeogAngularUnitSize
+            | GeoKeyId::GeogSemiMajorAxis
+            | GeoKeyId::GeogSemiMinorAxis
+            | GeoKeyId::GeogInvFlattening
+            | GeoKeyId::GeogPrimeMeridianLong
+            | GeoKeyId::ProjLinearUnitSize
+            | GeoKeyId::ProjStdParallel1
+            | GeoKeyId::ProjStdParallel2
+            | GeoKeyId::ProjNatOriginLong
+            | GeoKeyId::ProjNatOriginLat
+            | GeoKeyId::ProjFalseEasting
+            | GeoKeyId::ProjFalseNorthing
+            | GeoKeyId::ProjFalseOriginLong
+            | GeoKeyId::ProjFalseOriginLat
+            | GeoKeyId::ProjFalseOriginEasting
+            | GeoKeyId::ProjFalseOriginNorthing
+            | GeoKeyId::ProjCenterLong
+            | GeoKeyId::ProjCenterLat
+            | GeoKeyId::ProjCenterEasting
+            | GeoKeyId::ProjCenterNorthing
+            | GeoKeyId::ProjScaleAtNatOrigin
+            | GeoKeyId::ProjScaleAtCenter
+            | GeoKeyId::ProjAzimuthAngle
+            | GeoKeyId::ProjStraightVertPoleLong => Some(GeoTag::GeoDoubleParams),
+            _ => None,
+        }
+    }
+}
+
+/// The value of a geo key, according to the type it is stored as.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeoKeyValue {
+    Short(u16),
+    Double(f64),
+    Ascii(String),
+}
+
+impl GeoKeyValue {
+    /// Returns whether the values are identical, comparing doubles by their bits.
+    fn is_same_as(&self, other: &GeoKeyValue) -> bool {
+        match (self, other) {
+            (GeoKeyValue::Double(a), GeoKeyValue::Double(b)) => a.to_bits() == b.to_bits(),
+            (a, b) => a == b,
+        }
+    }
+}
+
+impl fmt::Display for GeoKeyValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeoKeyValue::Short(value) => write!(f, "{value}"),
+            GeoKeyValue::Double(value) => write!(f, "{value}"),
+            GeoKeyValue::Ascii(value) => write!(f, "{value:?}"),
+        }
+    }
+}
+
+/// A difference between two [GeoKeyDirectory], see [GeoKeyDirectory::diff].
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyDiff {
+    Added {
+        key: GeoKeyId,
+        value: GeoKeyValue,
+    },
+    Removed {
+        key: GeoKeyId,
+        value: GeoKeyValue,
+    },
+    Changed {
+        key: GeoKeyId,
+        old: GeoKeyValue,
+        new: GeoKeyValue,
+    },
+}
+
+impl KeyDiff {
+    pub fn key(&self) -> GeoKeyId {
+        match self {
+            KeyDiff::Added { key, .. }
+            | KeyDiff::Removed { key, .. }
+            | KeyDiff::Changed { key, .. } => *key,
+        }
+    }
+}
+
 /// The raster type establishes the raster space used.
 ///
 /// Ref: https://docs.ogc.org/is/19-008r4/19-008r4.html#_requirements_class_gtrastertypegeokey
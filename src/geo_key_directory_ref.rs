@@ -0,0 +1,154 @@
+use crate::{
+    DirectoryEntry, GeoKeyDirectory, GeoKeyId, GeoKeyValue, GeoTag, MetadataError, MetadataResult,
+    RasterType,
+};
+
+/// A view of the geo keys borrowing the values of the GeoKeyDirectoryTag, GeoDoubleParamsTag and
+/// GeoAsciiParamsTag, which parses them without allocating, e.g. to crawl the headers of many
+/// files.
+///
+/// The tag data is validated once by [GeoKeyDirectoryRef::from_tag_data], with the same rules
+/// as [GeoKeyDirectory::from_tag_data], then the values are read from it on demand.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoKeyDirectoryRef<'a> {
+    directory_data: &'a [u16],
+    double_params: &'a [f64],
+    ascii_params: &'a str,
+}
+
+/// The value of a geo key borrowed from the tag data, see [GeoKeyDirectoryRef].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoKeyValueRef<'a> {
+    Short(u16),
+    Double(f64),
+    Ascii(&'a str),
+}
+
+impl<'a> GeoKeyDirectoryRef<'a> {
+    pub fn from_tag_data(
+        directory_data: &'a [u16],
+        double_params: &'a [f64],
+        ascii_params: &'a str,
+    ) -> MetadataResult<Self> {
+        let directory = Self {
+            directory_data,
+            double_params,
+            ascii_params,
+        };
+        DirectoryEntry::from_directory_data(directory_data)?;
+        for entry in directory.raw_entries() {
+            let entry = DirectoryEntry::from_raw(entry)?;
+            let value = directory.value(&entry)?;
+            if let (GeoKeyId::RasterType, GeoKeyValueRef::Short(raster_type)) =
+                (entry.key_id, value)
+            {
+                RasterType::try_from(raster_type).map_err(|_| {
+                    MetadataError::Format(format!("Unknown raster type: {raster_type}"))
+                })?;
+            }
+        }
+        Ok(directory)
+    }
+
+    pub fn key_directory_version(&self) -> u16 {
+        self.directory_data[0]
+    }
+
+    pub fn key_revision(&self) -> u16 {
+        self.directory_data[1]
+    }
+
+    pub fn minor_revision(&self) -> u16 {
+        self.directory_data[2]
+    }
+
+    /// Iterates over the keys along with their values, in the order in which they are stored.
+    pub fn iter(&self) -> impl Iterator<Item = (GeoKeyId, GeoKeyValueRef<'a>)> + 'a {
+        let directory = *self;
+        self.raw_entries().map(move |entry| {
+            // The entries were validated on construction
+            let entry = DirectoryEntry::from_raw(entry).unwrap();
+            (entry.key_id, directory.value(&entry).unwrap())
+        })
+    }
+
+    /// Returns the value of the given key, if it is set.
+    pub fn get(&self, key: GeoKeyId) -> Option<GeoKeyValueRef<'a>> {
+        self.iter()
+            .find_map(|(k, value)| (k == key).then_some(value))
+    }
+
+    /// Returns the value of the given key if it is a SHORT value.
+    pub fn short(&self, key: GeoKeyId) -> Option<u16> {
+        match self.get(key)? {
+            GeoKeyValueRef::Short(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the given key if it is a DOUBLE value.
+    pub fn double(&self, key: GeoKeyId) -> Option<f64> {
+        match self.get(key)? {
+            GeoKeyValueRef::Double(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the given key if it is an ASCII value.
+    pub fn ascii(&self, key: GeoKeyId) -> Option<&'a str> {
+        match self.get(key)? {
+            GeoKeyValueRef::Ascii(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn citation(&self) -> Option<&'a str> {
+        self.ascii(GeoKeyId::Citation)
+    }
+
+    pub fn geog_citation(&self) -> Option<&'a str> {
+        self.ascii(GeoKeyId::GeogCitation)
+    }
+
+    pub fn proj_citation(&self) -> Option<&'a str> {
+        self.ascii(GeoKeyId::ProjCitation)
+    }
+
+    pub fn vertical_citation(&self) -> Option<&'a str> {
+        self.ascii(GeoKeyId::VerticalCitation)
+    }
+
+    /// Parses the tag data into an owned [GeoKeyDirectory].
+    pub fn to_directory(&self) -> GeoKeyDirectory {
+        // The tag data was validated on construction
+        GeoKeyDirectory::from_tag_data(self.directory_data, self.double_params, self.ascii_params)
+            .unwrap()
+    }
+
+    fn raw_entries(&self) -> impl Iterator<Item = [u16; 4]> + 'a {
+        self.directory_data[4..]
+            .chunks_exact(4)
+            .map(|entry| [entry[0], entry[1], entry[2], entry[3]])
+    }
+
+    /// Reads the value of an entry, checking that it is stored where the key expects it.
+    fn value(&self, entry: &DirectoryEntry) -> MetadataResult<GeoKeyValueRef<'a>> {
+        match entry.key_id.location() {
+            None => entry.short().map(GeoKeyValueRef::Short),
+            Some(GeoTag::GeoDoubleParams) => {
+                entry.double(self.double_params).map(GeoKeyValueRef::Double)
+            }
+            _ => entry.str(self.ascii_params).map(GeoKeyValueRef::Ascii),
+        }
+    }
+}
+
+impl GeoKeyValueRef<'_> {
+    pub fn to_owned_value(&self) -> GeoKeyValue {
+        match *self {
+            GeoKeyValueRef::Short(value) => GeoKeyValue::Short(value),
+            GeoKeyValueRef::Double(value) => GeoKeyValue::Double(value),
+            GeoKeyValueRef::Ascii(value) => GeoKeyValue::Ascii(value.into()),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+/// The TIFF tags defined by GeoTIFF, e.g. to locate the values of the geo keys, see
+/// [crate::DirectoryEntry::location], without depending on the tags of the tiff crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GeoTag {
+    ModelPixelScale,
+    ModelTiepoint,
+    ModelTransformation,
+    GeoKeyDirectory,
+    GeoDoubleParams,
+    GeoAsciiParams,
+}
+
+impl GeoTag {
+    /// Returns the tag with the given code, or `None` if it is not a GeoTIFF tag.
+    pub fn from_u16(code: u16) -> Option<Self> {
+        Some(match code {
+            33550 => GeoTag::ModelPixelScale,
+            33922 => GeoTag::ModelTiepoint,
+            34264 => GeoTag::ModelTransformation,
+            34735 => GeoTag::GeoKeyDirectory,
+            34736 => GeoTag::GeoDoubleParams,
+            34737 => GeoTag::GeoAsciiParams,
+            _ => return None,
+        })
+    }
+
+    /// Returns the code of the tag.
+    pub fn to_u16(self) -> u16 {
+        match self {
+            GeoTag::ModelPixelScale => 33550,
+            GeoTag::ModelTiepoint => 33922,
+            GeoTag::ModelTransformation => 34264,
+            GeoTag::GeoKeyDirectory => 34735,
+            GeoTag::GeoDoubleParams => 34736,
+            GeoTag::GeoAsciiParams => 34737,
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use geo_types::Coord;
+use proj4rs::Proj;
+use tiff::{TiffError, TiffFormatError, TiffResult};
+
+use crate::proj4::proj_from_epsg_code;
+use crate::{GeoTiff, Image};
+
+/// The number of points along each edge of a polygon, so that its shape is preserved by the
+/// reprojection.
+const EDGE_POINTS: usize = 16;
+
+impl Image {
+    /// Returns the outline of the raster in WGS84 as a GeoJSON feature, e.g. to visualize the
+    /// coverage of a dataset.
+    ///
+    /// The edges are densified so that their shape is preserved by the reprojection.
+    pub fn footprint_geojson(&self) -> TiffResult<String> {
+        let to_wgs84 = ToWgs84::new(self)?;
+        let ring = to_wgs84.ring(self.raster_rect())?;
+        Ok(feature(&polygon(&ring), None, "{}"))
+    }
+
+    /// Returns the bounding box of the raster in WGS84 as a GeoJSON feature, with a `bbox`
+    /// member.
+    pub fn bbox_geojson(&self) -> TiffResult<String> {
+        let to_wgs84 = ToWgs84::new(self)?;
+        let ring = to_wgs84.ring(self.raster_rect())?;
+        let (mut west, mut south) = (f64::INFINITY, f64::INFINITY);
+        let (mut east, mut north) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for coord in &ring {
+            west = west.min(coord.x);
+            south = south.min(coord.y);
+            east = east.max(coord.x);
+            north = north.max(coord.y);
+        }
+        let corners = [
+            Coord { x: west, y: south },
+            Coord { x: east, y: south },
+            Coord { x: east, y: north },
+            Coord { x: west, y: north },
+            Coord { x: west, y: south },
+        ];
+        Ok(feature(
+            &polygon(&corners),
+            Some([west, south, east, north]),
+            "{}",
+        ))
+    }
+
+    /// Returns the tiles or strips of the raster in WGS84 as a GeoJSON feature collection, with
+    /// the `column` and `row` of each block as properties, see [Image::block_size].
+    pub fn tile_grid_geojson(&self) -> TiffResult<String> {
+        let to_wgs84 = ToWgs84::new(self)?;
+        let (block_width, block_height) = self.block_size();
+        let (block_width, block_height) = (block_width.max(1), block_height.max(1));
+        let [x0, y0, _, _] = self.raster_rect();
+        let mut features = Vec::new();
+        for row in 0..self.raster_height.div_ceil(block_height) {
+            for column in 0..self.raster_width.div_ceil(block_width) {
+                let (x, y) = (column * block_width, row * block_height);
+                let rect = [
+                    x0 + x as f64,
+                    y0 + y as f64,
+                    x0 + (x + block_width).min(self.raster_width) as f64,
+                    y0 + (y + block_height).min(self.raster_height) as f64,
+                ];
+                let ring = to_wgs84.ring(rect)?;
+                features.push(feature(
+                    &polygon(&ring),
+                    None,
+                    &format!("{{\"column\":{column},\"row\":{row}}}"),
+                ));
+            }
+        }
+        Ok(format!(
+            "{{\"type\":\"FeatureCollection\",\"features\":[{}]}}",
+            features.join(",")
+        ))
+    }
+
+    /// Returns the outer edges of the raster in raster space, as `[x0, y0, x1, y1]`.
+    fn raster_rect(&self) -> [f64; 4] {
+        let offset = self.raster_offset();
+        [
+            offset,
+            offset,
+            self.raster_width as f64 + offset,
+            self.raster_height as f64 + offset,
+        ]
+    }
+}
+
+impl GeoTiff {
+    /// See [Image::footprint_geojson].
+    pub fn footprint_geojson(&self) -> TiffResult<String> {
+        self.primary().footprint_geojson()
+    }
+
+    /// See [Image::bbox_geojson].
+    pub fn bbox_geojson(&self) -> TiffResult<String> {
+        self.primary().bbox_geojson()
+    }
+
+    /// See [Image::tile_grid_geojson].
+    pub fn tile_grid_geojson(&self) -> TiffResult<String> {
+        self.primary().tile_grid_geojson()
+    }
+}
+
+/// The transformation from the raster space of an image to WGS84 longitude/latitude.
+struct ToWgs84<'a> {
+    image: &'a Image,
+    source: Proj,
+    target: Proj,
+}
+
+impl<'a> ToWgs84<'a> {
+    fn new(image: &'a Image) -> TiffResult<Self> {
+        let (Some(geo_key_directory), Some(_)) =
+            (image.geo_key_directory(), image.coordinate_transform())
+        else {
+            return Err(TiffError::FormatError(TiffFormatError::Format(
+                "Image is not georeferenced".into(),
+            )));
+        };
+        Ok(Self {
+            image,
+            source: geo_key_directory.to_proj4rs()?,
+            target: proj_from_epsg_code(4326)?,
+        })
+    }
+
+    fn transform(&self, coord: Coord) -> TiffResult<Coord> {
+        let model = self
+            .image
+            .coordinate_transform()
+            .unwrap()
+            .transform_to_model(&coord);
+        let mut point = if self.source.is_latlong() {
+            (model.x.to_radians(), model.y.to_radians(), 0.0)
+        } else {
+            (model.x, model.y, 0.0)
+        };
+        proj4rs::transform::transform(&self.source, &self.target, &mut point).map_err(|e| {
+            TiffError::FormatError(TiffFormatError::Format(format!(
+                "Unable to transform {model:?} to WGS84: {e}"
+            )))
+        })?;
+        Ok(Coord {
+            x: point.0.to_degrees(),
+            y: point.1.to_degrees(),
+        })
+    }
+
+    /// Returns the closed ring of the given rectangle `[x0, y0, x1, y1]` in raster space, with
+    /// densified edges.
+    fn ring(&self, [x0, y0, x1, y1]: [f64; 4]) -> TiffResult<Vec<Coord>> {
+        let corners = [(x0, y0), (x1, y0), (x1, y1), (x0, y1), (x0, y0)];
+        let mut ring = Vec::with_capacity(4 * EDGE_POINTS + 1);
+        for edge in corners.windows(2) {
+            let ((start_x, start_y), (end_x, end_y)) = (edge[0], edge[1]);
+            for i in 0..EDGE_POINTS {
+                let t = i as f64 / EDGE_POINTS as f64;
+                ring.push(self.transform(Coord {
+                    x: start_x + t * (end_x - start_x),
+                    y: start_y + t * (end_y - start_y),
+                })?);
+            }
+        }
+        ring.push(ring[0]);
+        Ok(ring)
+    }
+}
+
+fn polygon(ring: &[Coord]) -> String {
+    let coordinates = ring
+        .iter()
+        .map(|coord| format!("[{},{}]", coord.x, coord.y))
+        .collect::<Vec<_>>();
+    format!(
+        "{{\"type\":\"Polygon\",\"coordinates\":[[{}]]}}",
+        coordinates.join(",")
+    )
+}
+
+fn feature(geometry: &str, bbox: Option<[f64; 4]>, properties: &str) -> String {
+    let bbox = bbox
+        .map(|[west, south, east, north]| format!("\"bbox\":[{west},{south},{east},{north}],"))
+        .unwrap_or_default();
+    format!("{{\"type\":\"Feature\",{bbox}\"geometry\":{geometry},\"properties\":{properties}}}")
+}
@@ -0,0 +1,54 @@
+use tiff::{TiffError, TiffFormatError, TiffResult};
+
+use crate::tiff_writer::{Directory, TagValue};
+use crate::{GeoTiff, Image};
+
+/// The InterColorProfile tag, which is not known to the tiff crate.
+pub(crate) const ICC_PROFILE_TAG: u16 = 34675;
+
+/// The size of the header of ICC profiles.
+const ICC_HEADER_SIZE: usize = 128;
+
+/// Returns the data color space of an ICC profile from its header, e.g. `RGB ` or `GRAY`, or
+/// `None` if the profile is too short to have a header.
+pub fn icc_color_space(profile: &[u8]) -> Option<&str> {
+    if profile.len() < ICC_HEADER_SIZE {
+        return None;
+    }
+    std::str::from_utf8(&profile[16..20]).ok()
+}
+
+/// Sets the ICC profile of an RGB image, which must be an RGB profile.
+pub(crate) fn write_rgb_profile(directory: &mut Directory, profile: &[u8]) -> TiffResult<()> {
+    match icc_color_space(profile) {
+        Some("RGB ") => {}
+        color_space => {
+            return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+                "The ICC profile of an RGB image must have the RGB color space, not {:?}",
+                color_space
+            ))))
+        }
+    }
+    let value = TagValue::Raw {
+        field_type: 7,
+        count: profile.len() as u32,
+        bytes: profile.to_vec(),
+    };
+    directory.set_u16(ICC_PROFILE_TAG, value);
+    Ok(())
+}
+
+impl Image {
+    /// Returns the ICC profile of the InterColorProfile tag, which defines the colors of the
+    /// samples for color-managed applications, e.g. print workflows.
+    pub fn icc_profile(&self) -> Option<&[u8]> {
+        self.icc_profile.as_deref()
+    }
+}
+
+impl GeoTiff {
+    /// See [Image::icc_profile].
+    pub fn icc_profile(&self) -> Option<&[u8]> {
+        self.primary().icc_profile()
+    }
+}
@@ -0,0 +1,754 @@
+use std::io::{Read, Seek};
+use std::path::Path;
+
+use geo_types::{Coord, Rect};
+use num_traits::FromPrimitive;
+use tiff::decoder::{Decoder, Limits};
+use tiff::tags::{PhotometricInterpretation, Tag};
+use tiff::{TiffError, TiffResult, TiffUnsupportedError};
+
+use crate::chunks::{
+    decode_window, read_complex, ChunkFormat, Chunks, DecodeContext, LazyChunks, SharedReader,
+};
+use crate::document_info::value_bytes;
+use crate::gdal_metadata::GDAL_METADATA_TAG;
+use crate::icc_profile::ICC_PROFILE_TAG;
+use crate::memory::estimate_raster_memory;
+use crate::open_options::TransformOverride;
+use crate::raster_data::RasterData;
+use crate::world_file::world_file_matrix;
+use crate::{
+    AffineTransform, AxisOrder, ChunkIndex, ConformanceReport, CoordinateTransform, CrsOverride,
+    DType, DocumentInfo, Exif, GdalMetadata, GeoKeyDirectory, LinearUnit, NormalizedTransform,
+    OpenOptions, RasterType, Residuals, TiePoint, TransformTags, Window,
+};
+
+/// Applies a CRS override to the geo keys of a file, if any.
+fn override_crs(
+    directory: Option<GeoKeyDirectory>,
+    crs: &CrsOverride,
+) -> TiffResult<GeoKeyDirectory> {
+    Ok(match crs {
+        CrsOverride::Epsg(code) => GeoKeyDirectory {
+            raster_type: directory.and_then(|directory| directory.raster_type),
+            ..GeoKeyDirectory::from_epsg(*code)
+        },
+        CrsOverride::GeoKeys(overrides) => {
+            let (merged, conflicts) = directory.unwrap_or_default().merged_with(overrides)?;
+            for conflict in conflicts {
+                log::info!("Overriding geo key: {conflict:?}");
+            }
+            merged
+        }
+    })
+}
+
+/// An image of a TIFF file, stored in its own Image File Directory (IFD).
+///
+/// Each image carries its own georeferencing, if any. Overviews usually do not, in which case
+/// they have to be related to the image they were derived from by their dimensions.
+#[derive(Debug)]
+pub struct Image {
+    /// The index of the IFD in the file.
+    pub index: usize,
+    pub subfile_type: SubfileType,
+    pub raster_width: usize,
+    pub raster_height: usize,
+    pub num_samples: usize,
+    geo_key_directory: Option<GeoKeyDirectory>,
+    coordinate_transform: Option<CoordinateTransform>,
+    transform_tags: TransformTags,
+    tie_points: Vec<TiePoint>,
+    conformance: ConformanceReport,
+    model_linear_unit: Option<LinearUnit>,
+    swap_axes: bool,
+    pub(crate) nodata: Option<f64>,
+    pub(crate) dtypes: Vec<DType>,
+    pub(crate) gdal_metadata: Option<GdalMetadata>,
+    pub(crate) document_info: DocumentInfo,
+    pub(crate) exif: Option<Exif>,
+    pub(crate) icc_profile: Option<Vec<u8>>,
+    pub(crate) chunk_index: Option<ChunkIndex>,
+    pub(crate) photometric_interpretation: Option<PhotometricInterpretation>,
+    pub(crate) compression: u16,
+    pub(crate) predictor: u16,
+    pub(crate) tiled: bool,
+    /// The size of the tiles, or of the strips for images which are not tiled.
+    pub(crate) block_size: (usize, usize),
+    /// `None` if the image data is in a layout that cannot be decoded, e.g. complex values, or
+    /// if it is decoded on demand.
+    pub(crate) raster_data: Option<RasterData>,
+    /// The chunks of the raster data which did not fit in the memory budget, decoded by the
+    /// reads of pixels.
+    pub(crate) lazy_chunks: Option<LazyChunks>,
+    /// The pairs of real and imaginary parts of the samples of images of complex values, which
+    /// are only decoded with the `num-complex` feature.
+    pub(crate) complex_data: Option<RasterData>,
+    /// Why the tiff crate could not decode the raster data, reported by the reads of pixels.
+    pub(crate) unsupported: Option<TiffUnsupportedError>,
+}
+
+impl Image {
+    /// Reads the image at the current IFD of the decoder.
+    ///
+    /// Raster data in an unsupported layout, e.g. with an unknown compression, is skipped rather
+    /// than reported as an error, which is reported by the reads of its pixels instead.
+    pub(crate) fn read<R: Read + Seek>(
+        decoder: &mut Decoder<SharedReader<R>>,
+        reader: &SharedReader<R>,
+        path: Option<&Path>,
+        index: usize,
+        options: &OpenOptions,
+        memory_budget: Option<usize>,
+        context: &mut DecodeContext,
+    ) -> TiffResult<Self> {
+        let subfile_type = match decoder.find_tag(Tag::NewSubfileType)? {
+            Some(value) => SubfileType::from_new_subfile_type(value.into_u32()?),
+            None => match decoder.find_tag(Tag::SubfileType)? {
+                Some(value) => SubfileType::from_subfile_type(value.into_u16()?),
+                None => SubfileType::FullResolution,
+            },
+        };
+
+        let key_directory = match decoder.find_tag(Tag::GeoKeyDirectoryTag)? {
+            Some(v) => Some(v.into_u16_vec()?),
+            None => None,
+        };
+        let mut geo_key_directory = match &key_directory {
+            Some(key_directory) => {
+                let double_params = match decoder.find_tag(Tag::GeoDoubleParamsTag)? {
+                    Some(v) => v.into_f64_vec()?,
+                    None => Vec::new(),
+                };
+                if options.lenient_ascii_params {
+                    // The tiff crate truncates the value at the first NUL, and rejects it if it
+                    // is not valid UTF-8
+                    let ascii_params = decoder
+                        .find_tag(Tag::GeoAsciiParamsTag)
+                        .and_then(|v| v.map(|v| v.into_string()).transpose())
+                        .unwrap_or_else(|e| {
+                            log::warn!("Skipping the GeoAsciiParamsTag: {e}");
+                            None
+                        })
+                        .unwrap_or_default();
+                    Some(GeoKeyDirectory::from_tag_data_lenient(
+                        key_directory,
+                        &double_params,
+                        ascii_params.as_bytes(),
+                    )?)
+                } else {
+                    let ascii_params = match decoder.find_tag(Tag::GeoAsciiParamsTag)? {
+                        Some(v) => v.into_string()?,
+                        None => String::new(),
+                    };
+                    Some(GeoKeyDirectory::from_tag_data(
+                        key_directory,
+                        &double_params,
+                        &ascii_params,
+                    )?)
+                }
+            }
+            None => None,
+        };
+
+        let (raster_width, raster_height) = decoder.dimensions()?;
+        let raster_width = raster_width as usize;
+        let raster_height = raster_height as usize;
+
+        let TransformTags {
+            mut pixel_scale,
+            mut tie_points,
+            mut model_transformation,
+        } = TransformTags::try_from(&mut *decoder)?;
+
+        let conformance = ConformanceReport::check(
+            key_directory.as_deref(),
+            geo_key_directory.as_ref(),
+            pixel_scale.as_deref(),
+            tie_points.as_deref(),
+            model_transformation.as_deref(),
+        );
+
+        if subfile_type == SubfileType::FullResolution || index == 0 {
+            if let Some(crs) = &options.crs_override {
+                geo_key_directory = Some(override_crs(geo_key_directory, crs)?);
+            }
+            match &options.transform_override {
+                Some(TransformOverride::Tags(tags)) => {
+                    pixel_scale = tags.pixel_scale.clone();
+                    tie_points = tags.tie_points.clone();
+                    model_transformation = tags.model_transformation.clone();
+                }
+                Some(TransformOverride::Bounds {
+                    upper_left,
+                    lower_right,
+                }) => {
+                    pixel_scale = Some(vec![
+                        (lower_right.x - upper_left.x) / raster_width as f64,
+                        (upper_left.y - lower_right.y) / raster_height as f64,
+                        0.0,
+                    ]);
+                    tie_points = Some(vec![0.0, 0.0, 0.0, upper_left.x, upper_left.y, 0.0]);
+                    model_transformation = None;
+                }
+                None => {}
+            }
+        }
+
+        let mut model_linear_unit = geo_key_directory
+            .as_ref()
+            .and_then(|directory| directory.projected_linear_unit());
+        if options.normalize_linear_units {
+            if let Some(unit) = model_linear_unit {
+                let factor = unit.metres();
+                if let Some(pixel_scale) = &mut pixel_scale {
+                    pixel_scale.iter_mut().take(2).for_each(|v| *v *= factor);
+                }
+                if let Some(tie_points) = &mut tie_points {
+                    for tie_point in tie_points.chunks_mut(6) {
+                        tie_point
+                            .iter_mut()
+                            .skip(3)
+                            .take(2)
+                            .for_each(|v| *v *= factor);
+                    }
+                }
+                if let Some(model_transformation) = &mut model_transformation {
+                    // The first two rows of the matrix yield the model X and Y coordinates
+                    model_transformation
+                        .iter_mut()
+                        .take(8)
+                        .for_each(|v| *v *= factor);
+                }
+                model_linear_unit = Some(LinearUnit::Metre);
+            }
+        }
+
+        let swap_axes = options.axis_order == AxisOrder::Authority
+            && geo_key_directory
+                .as_ref()
+                .is_some_and(|directory| directory.has_latitude_first_axis_order());
+
+        let transform_tags = TransformTags {
+            pixel_scale,
+            tie_points,
+            model_transformation,
+        };
+        let tie_points = transform_tags.parsed_tie_points();
+        let coordinate_transform = if options.fit_tie_points
+            && tie_points.len() > 1
+            && transform_tags.model_transformation.is_none()
+        {
+            let (transform, _) = AffineTransform::fit_from_tie_points_with_tolerance(
+                &tie_points,
+                options.invertibility_tolerance,
+            )?;
+            Some(CoordinateTransform::AffineTransform(transform))
+        } else {
+            transform_tags.to_coordinate_transform_with_convention(
+                options.invertibility_tolerance,
+                options.pixel_scale_convention,
+            )?
+        };
+
+        let num_samples = match decoder.find_tag(Tag::SamplesPerPixel)? {
+            None => 1,
+            Some(value) => value.into_u16()? as usize,
+        };
+
+        let photometric_interpretation = match decoder.find_tag(Tag::PhotometricInterpretation)? {
+            Some(value) => PhotometricInterpretation::from_u16(value.into_u16()?),
+            None => None,
+        };
+        let compression = match decoder.find_tag(Tag::Compression)? {
+            None => 1,
+            Some(value) => value.into_u16()?,
+        };
+        let predictor = match decoder.find_tag(Tag::Predictor)? {
+            None => 1,
+            Some(value) => value.into_u16()?,
+        };
+        let tile_width = decoder.find_tag(Tag::TileWidth)?;
+        let tiled = tile_width.is_some();
+        let block_size = match (tile_width, decoder.find_tag(Tag::TileLength)?) {
+            (Some(width), Some(length)) => {
+                (width.into_u32()? as usize, length.into_u32()? as usize)
+            }
+            _ => match decoder.find_tag(Tag::RowsPerStrip)? {
+                Some(rows) => (raster_width, (rows.into_u32()? as usize).min(raster_height)),
+                None => (raster_width, raster_height),
+            },
+        };
+
+        let nodata = match decoder.find_tag(Tag::GdalNodata)? {
+            Some(value) => value.into_string()?.trim().parse().ok(),
+            None => None,
+        };
+        let gdal_metadata = match decoder.find_tag(Tag::Unknown(GDAL_METADATA_TAG))? {
+            Some(value) => Some(GdalMetadata::parse(&value.into_string()?)),
+            None => None,
+        };
+        let document_info = DocumentInfo::read(decoder)?;
+        let exif = Exif::read(decoder)?;
+        let icc_profile = match decoder.find_tag(Tag::Unknown(ICC_PROFILE_TAG))? {
+            Some(value) => Some(value_bytes(value)?),
+            None => None,
+        };
+        let chunk_index = if options.preload_index {
+            Some(ChunkIndex::read(
+                decoder,
+                tiled,
+                (raster_width, raster_height),
+                block_size,
+            )?)
+        } else {
+            None
+        };
+
+        let sample_formats = decoder
+            .find_tag_unsigned_vec::<u16>(Tag::SampleFormat)?
+            .unwrap_or_else(|| vec![1]);
+        let bits_per_sample = decoder
+            .find_tag_unsigned_vec::<u16>(Tag::BitsPerSample)?
+            .unwrap_or_else(|| vec![1]);
+        // A single value applies to all the samples
+        let per_sample = |values: &[u16], sample: usize| {
+            values.get(sample).or(values.first()).copied().unwrap_or(1)
+        };
+        let dtypes = (0..num_samples)
+            .map(|sample| {
+                DType::from_tags(
+                    per_sample(&sample_formats, sample),
+                    per_sample(&bits_per_sample, sample),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // Without a budget, the default limit of the tiff crate on the size of an image applies
+        let budget = memory_budget.unwrap_or(Limits::default().decoding_buffer_size);
+        let over_budget = estimate_raster_memory(raster_width, raster_height, &dtypes) > budget;
+
+        let dtype = dtypes
+            .first()
+            .copied()
+            .filter(|dtype| dtypes.iter().all(|other| other == dtype));
+        let format = dtype.map(|dtype| ChunkFormat {
+            dtype,
+            num_samples,
+            compression,
+            predictor,
+            photometric_interpretation,
+        });
+        // The color types which the tiff crate does not support are left to it to report, e.g.
+        // 8-bit masks
+        let decodable = format.filter(|format| {
+            format.is_decodable() && (format.dtype.is_packed() || decoder.colortype().is_ok())
+        });
+        let chunks = Chunks::new(decoder, num_samples)?;
+        let mut complex_data = None;
+        let mut lazy_chunks = None;
+        let mut unsupported = None;
+        let raster_data = match (format, decodable) {
+            // Only the chunks of the layouts decoded by this crate can be decoded on demand
+            (_, Some(format)) if over_budget => {
+                lazy_chunks = Some(LazyChunks::new(reader, path, chunks, format)?);
+                None
+            }
+            _ if over_budget => return Err(TiffError::LimitsExceeded),
+            (_, Some(format)) => Some(decode_window(
+                &chunks,
+                reader,
+                &format,
+                reader.is_big_endian()?,
+                Window::full(raster_width, raster_height),
+                context,
+            )?),
+            (Some(format), None) if format.dtype.is_complex() => {
+                if cfg!(feature = "num-complex") {
+                    complex_data = read_complex(&chunks, reader, &format, context)?;
+                }
+                None
+            }
+            (Some(format), None) if format.dtype.is_packed() => None,
+            // Left to the tiff crate, e.g. JPEG
+            _ => match decoder.read_image() {
+                Ok(result) => Some(RasterData::from(result)),
+                Err(TiffError::UnsupportedError(e)) => {
+                    unsupported = Some(e);
+                    None
+                }
+                Err(e) => return Err(e),
+            },
+        };
+
+        Ok(Self {
+            index,
+            subfile_type,
+            raster_width,
+            raster_height,
+            num_samples,
+            geo_key_directory,
+            coordinate_transform,
+            tie_points,
+            transform_tags,
+            conformance,
+            model_linear_unit,
+            swap_axes,
+            nodata,
+            dtypes,
+            gdal_metadata,
+            document_info,
+            exif,
+            icc_profile,
+            chunk_index,
+            photometric_interpretation,
+            compression,
+            predictor,
+            tiled,
+            block_size,
+            raster_data,
+            lazy_chunks,
+            complex_data,
+            unsupported,
+        })
+    }
+
+    /// Returns the geo keys stored in the IFD of this image, if any.
+    pub fn geo_key_directory(&self) -> Option<&GeoKeyDirectory> {
+        self.geo_key_directory.as_ref()
+    }
+
+    /// Returns the transformation between raster space and model space stored in the IFD of this
+    /// image, if any.
+    pub fn coordinate_transform(&self) -> Option<&CoordinateTransform> {
+        self.coordinate_transform.as_ref()
+    }
+
+    /// Returns the tags defining the transformation between raster space and model space, after
+    /// the normalization of linear units if enabled.
+    pub fn transform_tags(&self) -> &TransformTags {
+        &self.transform_tags
+    }
+
+    /// Returns the tie points of the ModelTiepointTag, after the normalization of linear units if
+    /// enabled.
+    pub fn tie_points(&self) -> &[TiePoint] {
+        &self.tie_points
+    }
+
+    /// Returns the residuals of the tie points for the transformation between raster space and
+    /// model space, or `None` if there is no transformation or no tie points.
+    pub fn tie_point_residuals(&self) -> Option<Residuals> {
+        if self.tie_points.is_empty() {
+            return None;
+        }
+        Some(
+            self.coordinate_transform
+                .as_ref()?
+                .residuals(&self.tie_points),
+        )
+    }
+
+    /// Returns whether the image has geo keys or a transformation between raster space and model
+    /// space. Otherwise, the raster space is used as model space.
+    pub fn is_georeferenced(&self) -> bool {
+        self.geo_key_directory.is_some() || self.coordinate_transform.is_some()
+    }
+
+    /// Sets the transformation given by the parameters of a world file, see
+    /// [OpenOptions::open_path].
+    pub(crate) fn set_world_file_transform(
+        &mut self,
+        params: [f64; 6],
+        options: &OpenOptions,
+    ) -> TiffResult<()> {
+        let transform_tags = TransformTags {
+            model_transformation: Some(world_file_matrix(params)),
+            ..Default::default()
+        };
+        self.coordinate_transform = transform_tags.to_coordinate_transform_with_convention(
+            options.invertibility_tolerance,
+            options.pixel_scale_convention,
+        )?;
+        self.tie_points = transform_tags.parsed_tie_points();
+        self.transform_tags = transform_tags;
+        Ok(())
+    }
+
+    /// Returns a view of the coordinate transformation whose model coordinates are in degrees for
+    /// geographic CRS and in metres for projected CRS, if the units are given by the geo keys.
+    pub fn normalized(&self) -> Option<NormalizedTransform<'_>> {
+        Some(NormalizedTransform::new(
+            self.coordinate_transform.as_ref()?,
+            self.geo_key_directory.as_ref(),
+            self.model_linear_unit,
+        ))
+    }
+
+    /// Returns whether the model coordinates of the bounds and sampling methods are swapped
+    /// relative to the stored coordinates, see [OpenOptions::axis_order].
+    pub fn model_axes_swapped(&self) -> bool {
+        self.swap_axes
+    }
+
+    /// Returns the linear unit of the model coordinates of a projected CRS, if known.
+    ///
+    /// This is [LinearUnit::Metre] if the coordinates were normalized on reading, see
+    /// [OpenOptions::normalize_linear_units].
+    pub fn model_linear_unit(&self) -> Option<LinearUnit> {
+        self.model_linear_unit
+    }
+
+    /// Returns the conformance of the GeoTIFF tags of this image to the requirements classes of
+    /// the standard.
+    pub fn conformance(&self) -> &ConformanceReport {
+        &self.conformance
+    }
+
+    /// Returns whether the raster data of this image can be decoded, when the file is read or on
+    /// demand.
+    pub fn has_raster_data(&self) -> bool {
+        self.raster_data.is_some() || self.lazy_chunks.is_some()
+    }
+
+    /// Returns the width and height of a pixel in model space, if the image is georeferenced.
+    ///
+    /// For rotated or sheared rasters, these are the lengths of the pixel edges.
+    pub fn pixel_size(&self) -> Option<(f64, f64)> {
+        let transform = self.coordinate_transform.as_ref()?;
+        let origin = transform.transform_to_model(&Coord { x: 0.0, y: 0.0 });
+        let length = |coord: Coord| {
+            let corner = transform.transform_to_model(&coord);
+            (corner.x - origin.x).hypot(corner.y - origin.y)
+        };
+        Some((
+            length(Coord { x: 1.0, y: 0.0 }),
+            length(Coord { x: 0.0, y: 1.0 }),
+        ))
+    }
+
+    /// Returns the bounds in model space of the area covered by the raster, i.e. up to the outer
+    /// edges of the border pixels.
+    ///
+    /// For [RasterType::RasterPixelIsArea], the pixel at raster coordinates (0, 0) spans the area
+    /// from (0, 0) to (1, 1). For [RasterType::RasterPixelIsPoint], the raster coordinates refer
+    /// to the pixel centers instead, so the area spans from (-0.5, -0.5) to (0.5, 0.5) and the
+    /// bounds are shifted by half a pixel accordingly.
+    pub fn model_bounds_outer(&self) -> Rect {
+        let offset = self.raster_offset();
+        self.raster_rect_to_model(Rect::new(
+            Coord {
+                x: offset,
+                y: offset,
+            },
+            Coord {
+                x: self.raster_width as f64 + offset,
+                y: self.raster_height as f64 + offset,
+            },
+        ))
+    }
+
+    /// Returns the bounds in model space of the centers of the border pixels.
+    ///
+    /// These bounds are half a pixel smaller on each side than [Image::model_bounds_outer].
+    pub fn model_bounds_center(&self) -> Rect {
+        let offset = self.raster_offset() + 0.5;
+        self.raster_rect_to_model(Rect::new(
+            Coord {
+                x: offset,
+                y: offset,
+            },
+            Coord {
+                x: self.raster_width as f64 - 1.0 + offset,
+                y: self.raster_height as f64 - 1.0 + offset,
+            },
+        ))
+    }
+
+    /// Returns the outer bounds in model space with the axes in stored order, regardless of
+    /// [OpenOptions::axis_order].
+    pub(crate) fn stored_bounds_outer(&self) -> Rect {
+        let offset = self.raster_offset();
+        self.raster_rect_to_stored(Rect::new(
+            Coord {
+                x: offset,
+                y: offset,
+            },
+            Coord {
+                x: self.raster_width as f64 + offset,
+                y: self.raster_height as f64 + offset,
+            },
+        ))
+    }
+
+    /// Transforms all corners of the given rectangle to model space and returns their bounds.
+    fn raster_rect_to_model(&self, rect: Rect) -> Rect {
+        let rect = self.raster_rect_to_stored(rect);
+        Rect::new(
+            self.stored_to_model(rect.min()),
+            self.stored_to_model(rect.max()),
+        )
+    }
+
+    fn raster_rect_to_stored(&self, rect: Rect) -> Rect {
+        let Some(coordinate_transform) = &self.coordinate_transform else {
+            return rect;
+        };
+
+        let (min, max) = (rect.min(), rect.max());
+        let corners = [
+            min,
+            Coord { x: max.x, y: min.y },
+            max,
+            Coord { x: min.x, y: max.y },
+        ]
+        .map(|corner| coordinate_transform.transform_to_model(&corner));
+
+        let (mut min, mut max) = (corners[0], corners[0]);
+        for corner in &corners[1..] {
+            min.x = min.x.min(corner.x);
+            min.y = min.y.min(corner.y);
+            max.x = max.x.max(corner.x);
+            max.y = max.y.max(corner.y);
+        }
+        Rect::new(min, max)
+    }
+
+    /// Returns the value at the given location for the specified sample.
+    /// The coordinates are in model space.
+    ///
+    /// Returns `None` if the location is outside of the raster, cannot be transformed to raster
+    /// space, or if the raster data of this image could not be decoded.
+    pub fn get_value_at<T: FromPrimitive + 'static>(
+        &self,
+        coord: &Coord,
+        sample: usize,
+    ) -> Option<T> {
+        let (column, row) = self.compute_pixel(coord, sample)?;
+        let decoded = self
+            .decode_area(Window::new(column as i64, row as i64, 1, 1))
+            .ok()?;
+        Some(decoded.data.get(decoded.index(column, row) + sample))
+    }
+
+    fn compute_pixel(&self, coord: &Coord, sample: usize) -> Option<(usize, usize)> {
+        let Image {
+            raster_width,
+            raster_height,
+            num_samples,
+            ..
+        } = self;
+
+        if &sample >= num_samples {
+            panic!(
+                "sample out of bounds: the number of samples is {} but the sample is {}",
+                num_samples, sample
+            )
+        }
+
+        let coord = self.model_to_pixel(coord)?;
+        if coord.x < 0.0
+            || coord.x >= *raster_width as f64
+            || coord.y < 0.0
+            || coord.y >= *raster_height as f64
+        {
+            return None;
+        }
+
+        Some((coord.x as usize, coord.y as usize))
+    }
+
+    /// Returns the raster coordinates of a model location, such that the pixel `(x, y)` covers
+    /// `[x, x + 1) x [y, y + 1)`, or `None` if it cannot be transformed to raster space.
+    pub(crate) fn model_to_pixel(&self, coord: &Coord) -> Option<Coord> {
+        let mut coord = match &self.coordinate_transform {
+            None => *coord,
+            Some(transform) => transform
+                .transform_to_raster(&self.stored_to_model(*coord))
+                .ok()?,
+        };
+
+        // See https://docs.ogc.org/is/19-008r4/19-008r4.html#_raster_space for reference
+        let raster_offset = self.raster_offset();
+        coord.x -= raster_offset;
+        coord.y -= raster_offset;
+        Some(coord)
+    }
+
+    /// Returns the model coordinates of the center of a pixel, or `None` if this image is not
+    /// georeferenced.
+    pub(crate) fn pixel_center(&self, x: usize, y: usize) -> Option<Coord> {
+        let transform = self.coordinate_transform.as_ref()?;
+        let center = 0.5 + self.raster_offset();
+        Some(self.stored_to_model(transform.transform_to_model(&Coord {
+            x: x as f64 + center,
+            y: y as f64 + center,
+        })))
+    }
+
+    /// Swaps the axes if required by the axis order, which is its own inverse.
+    fn stored_to_model(&self, coord: Coord) -> Coord {
+        if self.swap_axes {
+            Coord {
+                x: coord.y,
+                y: coord.x,
+            }
+        } else {
+            coord
+        }
+    }
+
+    pub(crate) fn raster_offset(&self) -> f64 {
+        match self
+            .geo_key_directory
+            .as_ref()
+            .and_then(|directory| directory.raster_type)
+        {
+            Some(RasterType::RasterPixelIsPoint) => -0.5,
+            _ => 0.0,
+        }
+    }
+}
+
+/// The kind of an image in a TIFF file containing several images.
+///
+/// Ref: https://www.awaresystems.be/imaging/tiff/tifftags/newsubfiletype.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubfileType {
+    /// A full resolution image.
+    FullResolution,
+    /// A reduced resolution version of another image, i.e. an overview.
+    ReducedResolution,
+    /// A single page of a multi-page image.
+    Page,
+    /// A transparency mask for another image.
+    Mask,
+}
+
+impl SubfileType {
+    const REDUCED_RESOLUTION_BIT: u32 = 1;
+    const PAGE_BIT: u32 = 2;
+    const MASK_BIT: u32 = 4;
+
+    fn from_new_subfile_type(value: u32) -> Self {
+        // Masks of overviews have both bits set, they are classified as masks
+        if value & Self::MASK_BIT != 0 {
+            SubfileType::Mask
+        } else if value & Self::REDUCED_RESOLUTION_BIT != 0 {
+            SubfileType::ReducedResolution
+        } else if value & Self::PAGE_BIT != 0 {
+            SubfileType::Page
+        } else {
+            SubfileType::FullResolution
+        }
+    }
+
+    /// Interprets the deprecated SubfileType tag.
+    fn from_subfile_type(value: u16) -> Self {
+        match value {
+            2 => SubfileType::ReducedResolution,
+            3 => SubfileType::Page,
+            _ => SubfileType::FullResolution,
+        }
+    }
+}
@@ -0,0 +1,523 @@
+use std::fs::File;
+use std::io::{BufWriter, Cursor, Seek, Write};
+use std::ops::Range;
+use std::path::Path;
+
+use tiff::tags::{PhotometricInterpretation, Tag};
+use tiff::{TiffError, TiffFormatError, TiffResult};
+
+use crate::gdal_metadata::GDAL_METADATA_TAG;
+use crate::icc_profile::write_rgb_profile;
+use crate::pyramid::Levels;
+use crate::tiff_writer::{
+    image_directory, stream_tiff, Directory, OverviewLevels, RasterBytes, SAMPLE_FORMAT_IEEEFP,
+    SAMPLE_FORMAT_UINT,
+};
+use crate::{
+    DocumentInfo, Exif, GeoKeyDirectory, Image, Layout, Lineage, Overviews, TransformTags,
+    WriteMask, WriteOptions,
+};
+
+/// A single-band raster of `f64` values held in memory, e.g. the result of a raster operation.
+///
+/// It carries the georeferencing of the image it was derived from, so that it can be written as
+/// a GeoTIFF.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InMemoryRaster {
+    pub width: usize,
+    pub height: usize,
+    /// The data has a size of width * height, in row-major order.
+    pub data: Vec<f64>,
+    pub nodata: Option<f64>,
+    pub geo_key_directory: Option<GeoKeyDirectory>,
+    pub transform_tags: TransformTags,
+    /// The descriptive tags to write, empty for rasters derived from an image.
+    pub document_info: DocumentInfo,
+    /// The processing history to write in the GDAL_METADATA tag, `None` for rasters derived from
+    /// an image.
+    pub lineage: Option<Lineage>,
+    /// The Exif and GPS IFDs to write, `None` for rasters derived from an image, since they
+    /// describe the capture of the image.
+    pub exif: Option<Exif>,
+}
+
+impl InMemoryRaster {
+    /// Creates a raster on the grid of the given image, with its georeferencing.
+    pub fn with_grid_of(image: &Image, data: Vec<f64>, nodata: Option<f64>) -> Self {
+        assert_eq!(
+            data.len(),
+            image.raster_width * image.raster_height,
+            "the data does not match the size of the raster"
+        );
+        Self {
+            data,
+            ..Self::grid_of(image, nodata)
+        }
+    }
+
+    /// Creates a raster on the grid of the given image without data, e.g. to write it with
+    /// [InMemoryRaster::write_rows].
+    pub(crate) fn grid_of(image: &Image, nodata: Option<f64>) -> Self {
+        Self {
+            width: image.raster_width,
+            height: image.raster_height,
+            data: Vec::new(),
+            nodata,
+            geo_key_directory: image.geo_key_directory().cloned(),
+            transform_tags: image.transform_tags().clone(),
+            document_info: DocumentInfo::default(),
+            lineage: None,
+            exif: None,
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> f64 {
+        self.data[y * self.width + x]
+    }
+
+    /// Returns whether the value is the nodata value of the raster or NaN.
+    pub fn is_nodata(&self, value: f64) -> bool {
+        value.is_nan() || Some(value) == self.nodata
+    }
+
+    /// Returns the raster with the given processing history, e.g. the inputs and parameters of
+    /// the operation which produced it.
+    pub fn with_lineage(self, lineage: Lineage) -> Self {
+        Self {
+            lineage: Some(lineage),
+            ..self
+        }
+    }
+
+    /// Writes the raster as a GeoTIFF of 64-bit floats, with its geo keys, transformation tags,
+    /// nodata value, descriptive tags and lineage.
+    pub fn write<W: Write + Seek>(&self, writer: W) -> TiffResult<()> {
+        self.write_with_options(writer, &WriteOptions::default())
+    }
+
+    /// Writes the raster like [InMemoryRaster::write], organized, compressed and followed by its
+    /// mask and overviews as requested.
+    pub fn write_with_options<W: Write + Seek>(
+        &self,
+        writer: W,
+        options: &WriteOptions,
+    ) -> TiffResult<()> {
+        let mask = resolve_mask(&options.mask, &[self])?;
+        let factors = overview_factors(self.width, self.height, options)?;
+        let float_bytes = |data: &[f64]| RasterBytes {
+            width: 0,
+            height: 0,
+            samples_per_pixel: 1,
+            bits_per_sample: 64,
+            sample_format: SAMPLE_FORMAT_IEEEFP,
+            data: data.iter().flat_map(|value| value.to_le_bytes()).collect(),
+        };
+
+        let image = self.float_directory(float_bytes(&self.data).data, options)?;
+        let mut directories = vec![image];
+        if let Some(mask) = mask {
+            directories.push(mask_directory(self.width, self.height, &mask, options)?);
+        }
+
+        let first = directories.len();
+        for &factor in &factors {
+            let mut overview = image_directory(
+                RasterBytes {
+                    width: self.width.div_ceil(factor),
+                    height: self.height.div_ceil(factor),
+                    ..float_bytes(&[])
+                },
+                options,
+            )?;
+            overview.set(Tag::NewSubfileType, 1u32);
+            overview.set(
+                Tag::PhotometricInterpretation,
+                PhotometricInterpretation::BlackIsZero.to_u16(),
+            );
+            directories.push(overview);
+        }
+        let levels = Levels::new(
+            |index| {
+                let value = self.data[index];
+                if self.is_nodata(value) {
+                    f64::NAN
+                } else {
+                    value
+                }
+            },
+            self.width,
+            self.height,
+            &factors,
+            &options.overview_resampling,
+        );
+        let nodata = self.nodata.filter(|nodata| !nodata.is_nan());
+        let overviews = OverviewLevels {
+            first,
+            bands: vec![levels],
+            samples: Box::new(move |bands| {
+                bands
+                    .into_iter()
+                    .flatten()
+                    .map(|mut level| {
+                        if let Some(nodata) = nodata {
+                            for value in level.iter_mut().filter(|value| value.is_nan()) {
+                                *value = nodata;
+                            }
+                        }
+                        float_bytes(&level).data
+                    })
+                    .collect()
+            }),
+        };
+        stream_tiff(writer, directories, Some(overviews), None, options)
+    }
+
+    /// Writes the raster like [InMemoryRaster::write_with_options], with the values of its rows
+    /// given by `rows` while they are written rather than by its data, for rasters computed
+    /// block by block which are not held in memory.
+    ///
+    /// Fails if a mask or overviews are requested, since they depend on the whole raster.
+    pub(crate) fn write_rows<W: Write + Seek>(
+        &self,
+        writer: W,
+        rows: &mut dyn FnMut(Range<usize>) -> TiffResult<Vec<f64>>,
+        options: &WriteOptions,
+    ) -> TiffResult<()> {
+        if options.mask != WriteMask::None
+            || !overview_factors(self.width, self.height, options)?.is_empty()
+        {
+            return Err(TiffError::FormatError(TiffFormatError::Format(
+                "Masks and overviews are not supported for rasters written block by block".into(),
+            )));
+        }
+        let image = self.float_directory(Vec::new(), options)?;
+        let mut rows = |range: Range<usize>| {
+            let values = rows(range)?;
+            TiffResult::Ok(
+                values
+                    .iter()
+                    .flat_map(|value| value.to_le_bytes())
+                    .collect(),
+            )
+        };
+        stream_tiff(writer, vec![image], None, Some(&mut rows), options)
+    }
+
+    /// Returns the directory of the raster as 64-bit floats with the given samples, see
+    /// [RasterBytes::data], along with its tags.
+    fn float_directory(&self, data: Vec<u8>, options: &WriteOptions) -> TiffResult<Directory> {
+        let mut image = image_directory(
+            RasterBytes {
+                width: self.width,
+                height: self.height,
+                samples_per_pixel: 1,
+                bits_per_sample: 64,
+                sample_format: SAMPLE_FORMAT_IEEEFP,
+                data,
+            },
+            options,
+        )?;
+        image.set(
+            Tag::PhotometricInterpretation,
+            PhotometricInterpretation::BlackIsZero.to_u16(),
+        );
+        self.write_tags(&mut image, self.nodata, options)?;
+        Ok(image)
+    }
+
+    /// Writes the rasters, which must have the same size, as the red, green and blue bands of a
+    /// GeoTIFF of 8-bit samples, with the georeferencing, descriptive tags and lineage of the red
+    /// one. The values are rounded and clamped to the range of 8-bit samples, with 0 for NaN.
+    ///
+    /// For [WriteMask::FromNodata], a pixel is nodata if it is nodata in the three bands. If
+    /// [WriteOptions::alpha] is set, the mask is written as a fourth band of unassociated alpha
+    /// instead, and the nodata value is omitted. Otherwise, the nodata value of the red band is
+    /// written if it is an 8-bit value. The pixels of the overviews are combined from the valid
+    /// pixels only.
+    ///
+    /// The ICC profile of [WriteOptions::icc_profile] is written in the InterColorProfile tag of
+    /// the full resolution image, and must be an RGB profile.
+    pub fn write_rgb<W: Write + Seek>(
+        bands: [&InMemoryRaster; 3],
+        writer: W,
+        options: &WriteOptions,
+    ) -> TiffResult<()> {
+        let [red, green, blue] = bands;
+        if bands
+            .iter()
+            .any(|band| (band.width, band.height) != (red.width, red.height))
+        {
+            return Err(TiffError::FormatError(TiffFormatError::Format(
+                "The red, green and blue bands must have the same size".into(),
+            )));
+        }
+        let mask = resolve_mask(&options.mask, &bands)?;
+        let factors = overview_factors(red.width, red.height, options)?;
+        let to_u8 = |value: f64| value.round().clamp(0.0, 255.0) as u8;
+        let samples = if options.alpha { 4 } else { 3 };
+        let rgb_bytes = move |data: [&[f64]; 3], valid: &[bool]| {
+            let mut bytes = Vec::with_capacity(valid.len() * samples);
+            for (index, valid) in valid.iter().enumerate() {
+                bytes.extend(data.iter().map(|band| to_u8(band[index])));
+                if options.alpha {
+                    bytes.push(if *valid { 255 } else { 0 });
+                }
+            }
+            bytes
+        };
+        let rgb_directory = |width: usize, height: usize, bytes: Vec<u8>| {
+            let mut directory = image_directory(
+                RasterBytes {
+                    width,
+                    height,
+                    samples_per_pixel: samples,
+                    bits_per_sample: 8,
+                    sample_format: SAMPLE_FORMAT_UINT,
+                    data: bytes,
+                },
+                options,
+            )?;
+            directory.set(
+                Tag::PhotometricInterpretation,
+                PhotometricInterpretation::RGB.to_u16(),
+            );
+            if options.alpha {
+                directory.set(Tag::ExtraSamples, EXTRA_SAMPLE_UNASSOCIATED_ALPHA);
+            }
+            TiffResult::Ok(directory)
+        };
+
+        let (width, height) = (red.width, red.height);
+        let valid = mask.clone().unwrap_or_else(|| vec![true; width * height]);
+        let bytes = rgb_bytes([&red.data, &green.data, &blue.data], &valid);
+        let mut image = rgb_directory(width, height, bytes)?;
+        let nodata = if options.alpha {
+            None
+        } else {
+            red.nodata.filter(|&nodata| nodata == to_u8(nodata) as f64)
+        };
+        red.write_tags(&mut image, nodata, options)?;
+        if let Some(profile) = &options.icc_profile {
+            write_rgb_profile(&mut image, profile)?;
+        }
+        let mut directories = vec![image];
+        if let (Some(mask), false) = (mask, options.alpha) {
+            directories.push(mask_directory(width, height, &mask, options)?);
+        }
+
+        let first = directories.len();
+        for &factor in &factors {
+            let mut overview =
+                rgb_directory(width.div_ceil(factor), height.div_ceil(factor), Vec::new())?;
+            overview.set(Tag::NewSubfileType, 1u32);
+            directories.push(overview);
+        }
+        let valid = &valid;
+        let levels = bands.map(|band| {
+            Levels::new(
+                |index| {
+                    if valid[index] {
+                        band.data[index]
+                    } else {
+                        f64::NAN
+                    }
+                },
+                width,
+                height,
+                &factors,
+                &options.overview_resampling,
+            )
+        });
+        let overviews = OverviewLevels {
+            first,
+            bands: levels.into(),
+            samples: Box::new(move |bands| {
+                // Each band has one level per factor
+                (0..bands[0].len())
+                    .map(|level| {
+                        let levels = [0, 1, 2].map(|band| &bands[band][level][..]);
+                        let level_valid = (0..levels[0].len())
+                            .map(|index| levels.iter().any(|level| !level[index].is_nan()))
+                            .collect::<Vec<_>>();
+                        rgb_bytes(levels, &level_valid)
+                    })
+                    .collect()
+            }),
+        };
+        stream_tiff(writer, directories, Some(overviews), None, options)
+    }
+
+    /// Sets the geo keys, transformation tags, given nodata value, descriptive tags and lineage.
+    fn write_tags(
+        &self,
+        directory: &mut Directory,
+        nodata: Option<f64>,
+        options: &WriteOptions,
+    ) -> TiffResult<()> {
+        if let Some(geo_key_directory) = &self.geo_key_directory {
+            write_geo_key_directory(directory, geo_key_directory)?;
+        }
+        write_transform_tags(directory, &self.transform_tags);
+        if let (Some(nodata), true) = (nodata, options.gdal_tags) {
+            directory.set(Tag::GdalNodata, &gdal_nodata(nodata)[..]);
+        }
+        if let (Some(lineage), true) = (&self.lineage, options.gdal_tags) {
+            let xml = lineage.to_gdal_metadata().to_xml();
+            directory.set(Tag::Unknown(GDAL_METADATA_TAG), &xml[..]);
+        }
+        if let Some(exif) = &self.exif {
+            exif.write_tags(directory, options.byte_order);
+        }
+        if options.reproducible {
+            DocumentInfo {
+                date_time: None,
+                ..self.document_info.clone()
+            }
+            .write_tags(directory)
+        } else {
+            self.document_info.write_tags(directory)
+        }
+        Ok(())
+    }
+
+    /// Writes the raster as a GeoTIFF to the file at the given path, see [InMemoryRaster::write].
+    pub fn write_path<P: AsRef<Path>>(&self, path: P) -> TiffResult<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Encodes the raster as a GeoTIFF in memory, see [InMemoryRaster::write].
+    pub fn to_bytes(&self) -> TiffResult<Vec<u8>> {
+        let mut buffer = Cursor::new(Vec::new());
+        self.write(&mut buffer)?;
+        Ok(buffer.into_inner())
+    }
+}
+
+pub(crate) fn write_geo_key_directory(
+    directory: &mut Directory,
+    geo_key_directory: &GeoKeyDirectory,
+) -> TiffResult<()> {
+    let (directory_data, double_params, ascii_params) = geo_key_directory.to_tag_data()?;
+    directory.set(Tag::GeoKeyDirectoryTag, &directory_data[..]);
+    if !double_params.is_empty() {
+        directory.set(Tag::GeoDoubleParamsTag, &double_params[..]);
+    }
+    if !ascii_params.is_empty() {
+        directory.set(Tag::GeoAsciiParamsTag, &ascii_params[..]);
+    }
+    Ok(())
+}
+
+pub(crate) fn write_transform_tags(directory: &mut Directory, transform_tags: &TransformTags) {
+    let TransformTags {
+        pixel_scale,
+        tie_points,
+        model_transformation,
+    } = transform_tags;
+    if let Some(pixel_scale) = pixel_scale {
+        directory.set(Tag::ModelPixelScaleTag, &pixel_scale[..]);
+    }
+    if let Some(tie_points) = tie_points {
+        directory.set(Tag::ModelTiepointTag, &tie_points[..]);
+    }
+    if let Some(model_transformation) = model_transformation {
+        directory.set(Tag::ModelTransformationTag, &model_transformation[..]);
+    }
+}
+
+/// Returns the value of the GDAL_NODATA tag.
+pub(crate) fn gdal_nodata(nodata: f64) -> String {
+    if nodata.is_nan() {
+        "nan".to_string()
+    } else {
+        nodata.to_string()
+    }
+}
+
+/// The value of the ExtraSamples tag for unassociated alpha, i.e. not premultiplied.
+const EXTRA_SAMPLE_UNASSOCIATED_ALPHA: u16 = 2;
+
+/// Returns the validity of the pixels of the bands, which have the same size, for the given mask.
+fn resolve_mask(mask: &WriteMask, bands: &[&InMemoryRaster]) -> TiffResult<Option<Vec<bool>>> {
+    let first = bands[0];
+    match mask {
+        WriteMask::None => Ok(None),
+        WriteMask::FromNodata => Ok(Some(
+            (0..first.data.len())
+                .map(|index| !bands.iter().all(|band| band.is_nodata(band.data[index])))
+                .collect(),
+        )),
+        WriteMask::Explicit(mask) if mask.len() == first.data.len() => Ok(Some(mask.clone())),
+        WriteMask::Explicit(mask) => Err(TiffError::FormatError(TiffFormatError::Format(format!(
+            "The mask has {} values instead of {} for a raster of {} x {} pixels",
+            mask.len(),
+            first.data.len(),
+            first.width,
+            first.height
+        )))),
+    }
+}
+
+/// Returns the directory of the mask, with bits packed from the most significant one, rows
+/// padded to whole bytes and 1 for the valid pixels.
+fn mask_directory(
+    width: usize,
+    height: usize,
+    mask: &[bool],
+    options: &WriteOptions,
+) -> TiffResult<Directory> {
+    let row_bytes = width.div_ceil(8);
+    let mut bits = vec![0u8; row_bytes * height];
+    for (index, _) in mask.iter().enumerate().filter(|(_, &valid)| valid) {
+        let (x, y) = (index % width, index / width);
+        bits[y * row_bytes + x / 8] |= 0x80 >> (x % 8);
+    }
+    let mut directory = image_directory(
+        RasterBytes {
+            width,
+            height,
+            samples_per_pixel: 1,
+            bits_per_sample: 1,
+            sample_format: SAMPLE_FORMAT_UINT,
+            data: bits,
+        },
+        options,
+    )?;
+    directory.set(Tag::NewSubfileType, 4u32);
+    directory.set(
+        Tag::PhotometricInterpretation,
+        PhotometricInterpretation::TransparencyMask.to_u16(),
+    );
+    Ok(directory)
+}
+
+/// Returns the factors of the overviews of a raster of the given size.
+///
+/// Fails if a given factor is zero.
+fn overview_factors(width: usize, height: usize, options: &WriteOptions) -> TiffResult<Vec<usize>> {
+    Ok(match &options.overviews {
+        Overviews::None => Vec::new(),
+        Overviews::Factors(factors) if factors.contains(&0) => {
+            return Err(TiffError::FormatError(TiffFormatError::Format(
+                "The overview factors must be positive".into(),
+            )));
+        }
+        Overviews::Factors(factors) => factors.clone(),
+        Overviews::Auto => {
+            let block = match options.layout {
+                Layout::Strips => 256,
+                Layout::Tiles(size) => size.max(1) as usize,
+            };
+            let mut factors = Vec::new();
+            let mut factor = 1;
+            while width.max(height).div_ceil(factor) > block {
+                factor *= 2;
+                factors.push(factor);
+            }
+            factors
+        }
+    })
+}
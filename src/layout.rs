@@ -0,0 +1,114 @@
+use crate::{GeoTiff, IfdPlacement, Image, Layout, WriteOptions};
+
+/// The uncompressed size of a strip above which reading a few pixels decodes too much data.
+const MAX_STRIP_BYTES: usize = 1 << 20;
+
+/// The number of pixels of a tile below which the overhead of each tile dominates, e.g. the
+/// requests of remote readers and the lookups of the block caches of GDAL.
+const MIN_TILE_PIXELS: usize = 128 * 128;
+
+/// The tile size suggested for rewriting, the default of GDAL for tiled GeoTIFFs.
+const SUGGESTED_TILE_SIZE: u32 = 256;
+
+/// A layout of an image which slows down random access, see [GeoTiff::layout_warnings].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutWarning {
+    /// The strips of the image hold more than 1 MiB of uncompressed data each, assuming the
+    /// samples of each pixel are interleaved, so that reading any pixel decodes a whole strip.
+    HugeStrips { image: usize, strip_bytes: usize },
+    /// The tiles of the image have less than 128 x 128 pixels, and the image has more than one.
+    TinyTiles {
+        image: usize,
+        tile_width: usize,
+        tile_height: usize,
+    },
+    /// Some chunks of the image are stored before the first IFD, so that readers of remote files
+    /// cannot get the layout of the file with the first request, unlike Cloud Optimized
+    /// GeoTIFFs. Only detected for files opened with [crate::OpenOptions::preload_index].
+    DataBeforeIfds { image: usize },
+}
+
+impl LayoutWarning {
+    /// Returns the index of the image the warning is about, see [Image::index].
+    pub fn image(&self) -> usize {
+        match *self {
+            LayoutWarning::HugeStrips { image, .. }
+            | LayoutWarning::TinyTiles { image, .. }
+            | LayoutWarning::DataBeforeIfds { image } => image,
+        }
+    }
+
+    /// Returns the given write options changed to fix the warning when the image is written
+    /// again, e.g. with [crate::InMemoryRaster::write_with_options]: tiles of 256 x 256 pixels
+    /// for strips and tiny tiles, and the IFDs at the start of the file for data before them.
+    pub fn suggested_options(&self, options: WriteOptions) -> WriteOptions {
+        match self {
+            LayoutWarning::HugeStrips { .. } | LayoutWarning::TinyTiles { .. } => WriteOptions {
+                layout: Layout::Tiles(SUGGESTED_TILE_SIZE),
+                ..options
+            },
+            LayoutWarning::DataBeforeIfds { .. } => WriteOptions {
+                ifd_placement: IfdPlacement::Start,
+                ..options
+            },
+        }
+    }
+}
+
+impl Image {
+    /// Returns the warnings about the layout of this image, for a file whose first IFD is at the
+    /// given offset.
+    fn layout_warnings(&self, first_ifd_offset: u64) -> Vec<LayoutWarning> {
+        let mut warnings = Vec::new();
+        let (block_width, block_height) = self.block_size;
+        if self.tiled {
+            let single_tile =
+                block_width >= self.raster_width && block_height >= self.raster_height;
+            if block_width * block_height < MIN_TILE_PIXELS && !single_tile {
+                warnings.push(LayoutWarning::TinyTiles {
+                    image: self.index,
+                    tile_width: block_width,
+                    tile_height: block_height,
+                });
+            }
+        } else {
+            let pixel_bits: usize = self
+                .dtypes
+                .iter()
+                .map(|dtype| dtype.bits_per_sample() as usize)
+                .sum();
+            let row_bytes = (self.raster_width * pixel_bits).div_ceil(8);
+            let strip_bytes = row_bytes * block_height;
+            if strip_bytes > MAX_STRIP_BYTES {
+                warnings.push(LayoutWarning::HugeStrips {
+                    image: self.index,
+                    strip_bytes,
+                });
+            }
+        }
+        let data_before_ifds = self.chunk_index.as_ref().is_some_and(|index| {
+            index
+                .offsets
+                .iter()
+                .zip(&index.byte_counts)
+                .any(|(&offset, &byte_count)| byte_count > 0 && offset < first_ifd_offset)
+        });
+        if data_before_ifds {
+            warnings.push(LayoutWarning::DataBeforeIfds { image: self.index });
+        }
+        warnings
+    }
+}
+
+impl GeoTiff {
+    /// Returns the warnings about the layouts of the images of the file which slow down random
+    /// access, e.g. through GDAL or remote readers, in IFD order, to diagnose slow datasets.
+    ///
+    /// See [LayoutWarning::suggested_options] for rewriting the file with a better layout.
+    pub fn layout_warnings(&self) -> Vec<LayoutWarning> {
+        self.images()
+            .iter()
+            .flat_map(|image| image.layout_warnings(self.first_ifd_offset))
+            .collect()
+    }
+}
@@ -1,214 +1,447 @@
 //! A [GeoTIFF](https://www.ogc.org/standard/geotiff) library for Rust
-use std::any::type_name;
+#[cfg(feature = "decode")]
 use std::io::{Read, Seek};
+#[cfg(feature = "decode")]
+use std::path::Path;
+#[cfg(feature = "decode")]
+use std::sync::Arc;
 
+#[cfg(feature = "decode")]
 use geo_types::{Coord, Rect};
+#[cfg(feature = "decode")]
 use num_traits::FromPrimitive;
-use tiff::decoder::{Decoder, DecodingResult};
-use tiff::tags::Tag;
-use tiff::TiffResult;
+#[cfg(feature = "decode")]
+use tiff::decoder::{Decoder, Limits};
+#[cfg(feature = "decode")]
+use tiff::{TiffError, TiffFormatError, TiffResult};
 
+#[cfg(feature = "decode")]
+pub use crate::alignment::*;
+#[cfg(feature = "decode")]
+pub use crate::antimeridian::*;
+#[cfg(feature = "decode")]
+pub use crate::band::*;
+#[cfg(feature = "decode")]
+pub use crate::band_stack::*;
+#[cfg(feature = "decode")]
+pub use crate::blocks::*;
+#[cfg(feature = "decode")]
+pub use crate::capabilities::*;
+#[cfg(feature = "decode")]
+pub use crate::chunk_index::*;
+#[cfg(feature = "decode")]
+pub use crate::chunks::*;
+#[cfg(feature = "decode")]
+pub use crate::collection_stats::*;
+pub use crate::conformance::*;
+pub use crate::coordinate_transform::*;
+#[cfg(feature = "decode")]
+pub use crate::copy::*;
+#[cfg(feature = "decode")]
+pub use crate::document_info::*;
+#[cfg(feature = "decode")]
+pub use crate::dtype::*;
+pub use crate::epsg_inference::*;
+#[cfg(feature = "decode")]
+pub use crate::exif::*;
+#[cfg(feature = "decode")]
+pub use crate::gdal_metadata::*;
 pub use crate::geo_key_directory::*;
+pub use crate::geo_key_directory_ref::*;
+pub use crate::geo_tag::*;
+#[cfg(feature = "decode")]
+pub use crate::icc_profile::*;
+#[cfg(feature = "decode")]
+pub use crate::image::*;
+#[cfg(feature = "decode")]
+pub use crate::in_memory::*;
+#[cfg(feature = "decode")]
+pub use crate::layout::*;
+#[cfg(feature = "decode")]
+pub use crate::line_profile::*;
+#[cfg(feature = "decode")]
+pub use crate::lineage::*;
+pub use crate::metadata_error::*;
+#[cfg(feature = "decode")]
+pub use crate::open_options::*;
+#[cfg(feature = "pmtiles")]
+pub use crate::pmtiles::*;
+#[cfg(feature = "decode")]
+pub use crate::pool::*;
+#[cfg(feature = "decode")]
+pub use crate::qa::*;
+#[cfg(feature = "decode")]
+pub use crate::render::*;
+#[cfg(feature = "decode")]
+pub use crate::resampling::*;
+#[cfg(feature = "decode")]
+pub use crate::search::*;
+#[cfg(feature = "decode")]
+pub use crate::time_series::*;
+pub use crate::units::*;
+#[cfg(feature = "decode")]
+pub use crate::window::*;
+#[cfg(feature = "decode")]
+pub use crate::write_options::*;
 
-use crate::coordinate_transform::*;
-use crate::raster_data::*;
-
+#[cfg(feature = "decode")]
+mod alignment;
+#[cfg(feature = "decode")]
+mod antimeridian;
+#[cfg(feature = "decode")]
+mod band;
+#[cfg(feature = "decode")]
+mod band_stack;
+#[cfg(feature = "decode")]
+mod blocks;
+#[cfg(feature = "decode")]
+mod capabilities;
+#[cfg(feature = "decode")]
+mod chunk_index;
+#[cfg(feature = "decode")]
+mod chunks;
+#[cfg(feature = "decode")]
+pub mod classification;
+mod code_tables;
+#[cfg(feature = "decode")]
+mod collection_stats;
+#[cfg(feature = "num-complex")]
+mod complex;
+#[cfg(feature = "decode")]
+pub mod composite;
+mod conformance;
+#[cfg(feature = "decode")]
+mod content_hash;
 mod coordinate_transform;
+#[cfg(feature = "decode")]
+mod copy;
+#[cfg(feature = "decode")]
+mod document_info;
+#[cfg(feature = "drone")]
+pub mod drone;
+#[cfg(feature = "decode")]
+mod dtype;
+mod epsg_inference;
+#[cfg(feature = "decode")]
+mod exif;
+#[cfg(feature = "decode")]
+pub mod fill;
+#[cfg(feature = "decode")]
+pub mod focal;
+#[cfg(feature = "decode")]
+mod gdal_metadata;
 mod geo_key_directory;
+mod geo_key_directory_ref;
+mod geo_tag;
+#[cfg(feature = "geojson")]
+mod geojson;
+#[cfg(feature = "decode")]
+mod icc_profile;
+#[cfg(feature = "decode")]
+mod image;
+#[cfg(feature = "decode")]
+mod in_memory;
+#[cfg(feature = "decode")]
+mod layout;
+#[cfg(feature = "decode")]
+mod line_profile;
+#[cfg(feature = "decode")]
+mod lineage;
+#[cfg(feature = "decode")]
+mod memory;
+#[cfg(feature = "mesh")]
+pub mod mesh;
+mod metadata_error;
+#[cfg(feature = "decode")]
+mod open_options;
+#[cfg(feature = "pmtiles")]
+mod pmtiles;
+#[cfg(feature = "decode")]
+mod pool;
+#[cfg(feature = "proj4rs")]
+mod proj4;
+#[cfg(feature = "decode")]
+pub mod pyramid;
+#[cfg(feature = "decode")]
+mod qa;
+#[cfg(feature = "decode")]
 mod raster_data;
-
-macro_rules! unwrap_primitive_type {
-    ($result: expr, $actual: ty, $expected: ty) => {
-        $result
-            .ok_or_else(|| {
-                format!(
-                    "Cannot represent {} as {}",
-                    type_name::<$actual>(),
-                    type_name::<$expected>()
-                )
-            })
-            .unwrap()
-    };
-}
+#[cfg(feature = "decode")]
+pub mod raster_ops;
+#[cfg(feature = "decode")]
+mod render;
+#[cfg(feature = "decode")]
+mod resampling;
+#[cfg(feature = "decode")]
+mod search;
+mod simd;
+#[cfg(feature = "decode")]
+mod tiff_writer;
+#[cfg(feature = "decode")]
+mod time_series;
+mod units;
+pub mod webmercator;
+#[cfg(feature = "decode")]
+mod window;
+#[cfg(feature = "decode")]
+mod world_file;
+#[cfg(feature = "decode")]
+mod write_options;
 
 /// The basic GeoTIFF struct. This includes any metadata as well as the actual raster data.
 ///
+/// A TIFF file may contain several images, see [GeoTiff::images]. The metadata and the
+/// sampling methods of this struct refer to the primary image, see [GeoTiff::primary].
+///
 /// The raster data has a size of raster_width * raster_height * num_samples
-#[derive(Debug)]
+///
+/// The images are shared between clones, which are cheap, e.g. to give each worker thread its
+/// own handle without reading the file again.
+#[cfg(feature = "decode")]
+#[derive(Debug, Clone)]
 pub struct GeoTiff {
     pub geo_key_directory: GeoKeyDirectory,
     pub raster_width: usize,
     pub raster_height: usize,
     pub num_samples: usize,
-    coordinate_transform: Option<CoordinateTransform>,
-    raster_data: RasterData,
+    images: Arc<[Image]>,
+    primary_index: usize,
+    byte_order: ByteOrder,
+    first_ifd_offset: u64,
 }
 
+#[cfg(feature = "decode")]
 impl GeoTiff {
     /// Reads a GeoTIFF from the given source.
     pub fn read<R: Read + Seek>(reader: R) -> TiffResult<Self> {
-        let mut decoder = Decoder::new(reader)?;
-
-        let geo_key_directory = {
-            if let Some(directory) = decoder.find_tag(Tag::GeoKeyDirectoryTag)? {
-                let directory = directory.into_u16_vec()?;
-                let double_params = match decoder.find_tag(Tag::GeoDoubleParamsTag)? {
-                    Some(v) => v.into_f64_vec()?,
-                    None => Vec::new(),
-                };
-                let ascii_params = match decoder.find_tag(Tag::GeoAsciiParamsTag)? {
-                    Some(v) => v.into_string()?,
-                    None => String::new(),
-                };
-                GeoKeyDirectory::from_tag_data(&directory, &double_params, &ascii_params)?
-            } else {
-                GeoKeyDirectory::default()
+        OpenOptions::new().read(reader)
+    }
+
+    /// Reads a GeoTIFF from the file at the given path.
+    pub fn open_path<P: AsRef<Path>>(path: P) -> TiffResult<Self> {
+        OpenOptions::new().open_path(path)
+    }
+
+    /// Reads a GeoTIFF from an in-memory buffer.
+    pub fn from_bytes(bytes: &[u8]) -> TiffResult<Self> {
+        OpenOptions::new().read_bytes(bytes)
+    }
+
+    pub(crate) fn read_with_options<R: Read + Seek>(
+        reader: R,
+        options: &OpenOptions,
+        path: Option<&Path>,
+        world_file: Option<[f64; 6]>,
+        context: &mut DecodeContext,
+    ) -> TiffResult<Self> {
+        let reader = SharedReader::new(reader);
+        let mut decoder = Decoder::new(reader.clone())?;
+        if let Some(budget) = options.memory_budget {
+            let mut limits = Limits::default();
+            limits.decoding_buffer_size = budget;
+            decoder = decoder.with_limits(limits);
+        }
+        // The byte order is not exposed by the decoder
+        decoder.goto_offset_u64(0)?;
+        let byte_order = if decoder.read_byte()? == b'M' {
+            ByteOrder::BigEndian
+        } else {
+            ByteOrder::LittleEndian
+        };
+        decoder.goto_offset_u64(2)?;
+        let first_ifd_offset = match decoder.read_short()? {
+            43 => {
+                decoder.goto_offset_u64(8)?;
+                decoder.read_long8()?
+            }
+            _ => {
+                decoder.goto_offset_u64(4)?;
+                decoder.read_long()?.into()
             }
         };
 
-        let coordinate_transform = {
-            let pixel_scale = match decoder.find_tag(Tag::ModelPixelScaleTag)? {
-                Some(v) => Some(v.into_f64_vec()?),
-                None => None,
-            };
-            let tie_points = match decoder.find_tag(Tag::ModelTiepointTag)? {
-                Some(v) => Some(v.into_f64_vec()?),
-                None => None,
-            };
-            let model_transformation = match decoder.find_tag(Tag::ModelTransformationTag)? {
-                Some(v) => Some(v.into_f64_vec()?),
-                None => None,
-            };
-
-            if (&pixel_scale, &tie_points, &model_transformation) == (&None, &None, &None) {
-                None
-            } else {
-                Some(CoordinateTransform::from_tag_data(
-                    pixel_scale,
-                    tie_points,
-                    model_transformation,
-                )?)
+        let mut images = Vec::new();
+        let mut remaining_budget = options.memory_budget;
+        loop {
+            let image = Image::read(
+                &mut decoder,
+                &reader,
+                path,
+                images.len(),
+                options,
+                remaining_budget,
+                context,
+            )?;
+            remaining_budget =
+                remaining_budget.map(|budget| budget.saturating_sub(image.raster_memory()));
+            images.push(image);
+            if !decoder.more_images() {
+                break;
             }
-        };
+            decoder.next_image()?;
+        }
+
+        let primary_index = images
+            .iter()
+            .position(|image| image.subfile_type == SubfileType::FullResolution)
+            .unwrap_or(0);
+        let primary = &mut images[primary_index];
+        if let (None, Some(world_file)) = (primary.coordinate_transform(), world_file) {
+            primary.set_world_file_transform(world_file, options)?;
+        }
+        if !options.allow_ungeoreferenced && !primary.is_georeferenced() {
+            return Err(TiffError::FormatError(TiffFormatError::Format(
+                "The image has neither geo keys nor a raster to model transformation".to_string(),
+            )));
+        }
 
-        let (raster_width, raster_height) = decoder.dimensions()?;
-        let raster_width = raster_width as usize;
-        let raster_height = raster_height as usize;
+        Ok(Self {
+            geo_key_directory: primary.geo_key_directory().cloned().unwrap_or_default(),
+            raster_width: primary.raster_width,
+            raster_height: primary.raster_height,
+            num_samples: primary.num_samples,
+            images: images.into(),
+            primary_index,
+            byte_order,
+            first_ifd_offset,
+        })
+    }
 
-        let num_samples = match decoder.find_tag(Tag::SamplesPerPixel)? {
-            None => 1,
-            Some(value) => value.into_u16()? as usize,
-        };
+    /// Returns the geo keys of the primary image, if any.
+    ///
+    /// Unlike the `geo_key_directory` field, this distinguishes files without geo keys.
+    pub fn geo_key_directory(&self) -> Option<&GeoKeyDirectory> {
+        self.primary().geo_key_directory()
+    }
+
+    /// See [Image::is_georeferenced].
+    pub fn is_georeferenced(&self) -> bool {
+        self.primary().is_georeferenced()
+    }
+
+    /// Returns the byte order of the file.
+    pub fn byte_order(&self) -> ByteOrder {
+        self.byte_order
+    }
 
-        let raster_data = match decoder.read_image()? {
-            DecodingResult::U8(data) => RasterData::U8(data),
-            DecodingResult::U16(data) => RasterData::U16(data),
-            DecodingResult::U32(data) => RasterData::U32(data),
-            DecodingResult::U64(data) => RasterData::U64(data),
-            DecodingResult::F32(data) => RasterData::F32(data),
-            DecodingResult::F64(data) => RasterData::F64(data),
-            DecodingResult::I8(data) => RasterData::I8(data),
-            DecodingResult::I16(data) => RasterData::I16(data),
-            DecodingResult::I32(data) => RasterData::I32(data),
-            DecodingResult::I64(data) => RasterData::I64(data),
+    /// Returns all images of the file in IFD order.
+    pub fn images(&self) -> &[Image] {
+        &self.images
+    }
+
+    /// Returns the image stored in the IFD with the given index.
+    pub fn image(&self, index: usize) -> Option<&Image> {
+        self.images.get(index)
+    }
+
+    /// Returns the primary image, i.e. the first full resolution image of the file.
+    pub fn primary(&self) -> &Image {
+        &self.images[self.primary_index]
+    }
+
+    /// Returns the reduced resolution versions of the images, in IFD order.
+    ///
+    /// Overviews stored in SubIFDs are not supported.
+    pub fn overviews(&self) -> impl Iterator<Item = &Image> {
+        self.images_of_type(SubfileType::ReducedResolution)
+    }
+
+    /// Returns the transparency masks, including the masks of overviews, in IFD order.
+    pub fn masks(&self) -> impl Iterator<Item = &Image> {
+        self.images_of_type(SubfileType::Mask)
+    }
+
+    /// Returns the pages of a multi-page file, in IFD order.
+    pub fn pages(&self) -> impl Iterator<Item = &Image> {
+        self.images_of_type(SubfileType::Page)
+    }
+
+    /// Returns the lowest resolution level, among the primary image and the overviews, whose
+    /// pixels are at most `target_px_size` wide and high in model space.
+    ///
+    /// The pixel size of an overview without georeferencing of its own is derived from the
+    /// primary image and the ratio of their dimensions. Without georeferencing, the sizes are in
+    /// pixels of the primary image. The primary image is returned if no level is fine enough.
+    pub fn best_level_for_resolution(&self, target_px_size: f64) -> &Image {
+        let primary = self.primary();
+        let primary_size = primary.pixel_size().unwrap_or((1.0, 1.0));
+        let level_size = |image: &Image| {
+            image.pixel_size().unwrap_or((
+                primary_size.0 * primary.raster_width as f64 / image.raster_width as f64,
+                primary_size.1 * primary.raster_height as f64 / image.raster_height as f64,
+            ))
         };
+        let tolerance = 1.0 + 1e-9;
+        self.overviews()
+            .map(|image| (image, level_size(image)))
+            .filter(|(_, (width, height))| width.max(*height) <= target_px_size * tolerance)
+            .max_by(|(_, a), (_, b)| a.0.max(a.1).total_cmp(&b.0.max(b.1)))
+            .map_or(primary, |(image, _)| image)
+    }
 
-        Ok(Self {
-            geo_key_directory,
-            raster_width,
-            raster_height,
-            num_samples,
-            coordinate_transform,
-            raster_data,
-        })
+    fn images_of_type(&self, subfile_type: SubfileType) -> impl Iterator<Item = &Image> {
+        self.images
+            .iter()
+            .filter(move |image| image.subfile_type == subfile_type)
+    }
+
+    /// Converts a length from `from` to `to`, see [LinearUnit::convert].
+    pub fn convert_linear(value: f64, from: LinearUnit, to: LinearUnit) -> f64 {
+        LinearUnit::convert(value, from, to)
+    }
+
+    /// Returns the transformation between raster space and model space, if any.
+    pub fn coordinate_transform(&self) -> Option<&CoordinateTransform> {
+        self.primary().coordinate_transform()
+    }
+
+    /// See [Image::tie_points].
+    pub fn tie_points(&self) -> &[TiePoint] {
+        self.primary().tie_points()
+    }
+
+    /// See [Image::tie_point_residuals].
+    pub fn tie_point_residuals(&self) -> Option<Residuals> {
+        self.primary().tie_point_residuals()
+    }
+
+    /// See [Image::conformance].
+    pub fn conformance(&self) -> &ConformanceReport {
+        self.primary().conformance()
+    }
+
+    /// See [Image::normalized].
+    pub fn normalized(&self) -> Option<NormalizedTransform<'_>> {
+        self.primary().normalized()
     }
 
     /// Returns the extent of the image in model space.
+    ///
+    /// This is the same as [GeoTiff::model_bounds_outer].
     pub fn model_extent(&self) -> Rect {
-        let offset = self.raster_offset();
-        let lower = Coord {
-            x: offset,
-            y: offset,
-        };
-        let upper = Coord {
-            x: self.raster_width as f64 + offset,
-            y: self.raster_height as f64 + offset,
-        };
+        self.model_bounds_outer()
+    }
 
-        if let Some(coordinate_transform) = &self.coordinate_transform {
-            Rect::new(
-                coordinate_transform.transform_to_model(&lower),
-                coordinate_transform.transform_to_model(&upper),
-            )
-        } else {
-            Rect::new(lower, upper)
-        }
+    /// See [Image::model_bounds_outer].
+    pub fn model_bounds_outer(&self) -> Rect {
+        self.primary().model_bounds_outer()
+    }
+
+    /// See [Image::model_bounds_center].
+    pub fn model_bounds_center(&self) -> Rect {
+        self.primary().model_bounds_center()
     }
 
     /// Returns the value at the given location for the specified sample.
     /// The coordinates are in model space.
+    ///
+    /// Returns `None` if the location is outside of the raster or cannot be transformed to
+    /// raster space.
     pub fn get_value_at<T: FromPrimitive + 'static>(
         &self,
         coord: &Coord,
         sample: usize,
     ) -> Option<T> {
-        let index = self.compute_index(coord, sample)?;
-
-        Some(match &self.raster_data {
-            RasterData::U8(data) => unwrap_primitive_type!(T::from_u8(data[index]), u8, T),
-            RasterData::U16(data) => unwrap_primitive_type!(T::from_u16(data[index]), u16, T),
-            RasterData::U32(data) => unwrap_primitive_type!(T::from_u32(data[index]), u32, T),
-            RasterData::U64(data) => unwrap_primitive_type!(T::from_u64(data[index]), u64, T),
-            RasterData::F32(data) => unwrap_primitive_type!(T::from_f32(data[index]), f32, T),
-            RasterData::F64(data) => unwrap_primitive_type!(T::from_f64(data[index]), f64, T),
-            RasterData::I8(data) => unwrap_primitive_type!(T::from_i8(data[index]), i8, T),
-            RasterData::I16(data) => unwrap_primitive_type!(T::from_i16(data[index]), i16, T),
-            RasterData::I32(data) => unwrap_primitive_type!(T::from_i32(data[index]), i32, T),
-            RasterData::I64(data) => unwrap_primitive_type!(T::from_i64(data[index]), i64, T),
-        })
-    }
-
-    fn compute_index(&self, coord: &Coord, sample: usize) -> Option<usize> {
-        let GeoTiff {
-            raster_width,
-            raster_height,
-            num_samples,
-            coordinate_transform,
-            ..
-        } = self;
-
-        if &sample >= num_samples {
-            panic!(
-                "sample out of bounds: the number of samples is {} but the sample is {}",
-                num_samples, sample
-            )
-        }
-
-        let mut coord = match coordinate_transform {
-            None => *coord,
-            Some(transform) => transform.transform_to_raster(coord),
-        };
-
-        // See https://docs.ogc.org/is/19-008r4/19-008r4.html#_raster_space for reference
-        let raster_offset = self.raster_offset();
-        coord.x -= raster_offset;
-        coord.y -= raster_offset;
-
-        if coord.x < 0.0
-            || coord.x >= *raster_width as f64
-            || coord.y < 0.0
-            || coord.y >= *raster_height as f64
-        {
-            return None;
-        }
-
-        Some((coord.y as usize * raster_width + coord.x as usize) * num_samples + sample)
-    }
-
-    fn raster_offset(&self) -> f64 {
-        match self.geo_key_directory.raster_type {
-            Some(RasterType::RasterPixelIsPoint) => -0.5,
-            _ => 0.0,
-        }
+        self.primary().get_value_at(coord, sample)
     }
 }
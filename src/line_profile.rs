@@ -0,0 +1,121 @@
+use geo_types::{Coord, LineString};
+use tiff::{TiffError, TiffFormatError, TiffResult};
+
+use crate::{Band, Resampling};
+
+/// A sample of [Band::profile].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProfilePoint {
+    /// The distance along the line from its start, in model units.
+    pub distance: f64,
+    /// The model coordinates of the sample.
+    pub coord: Coord,
+    /// The value of the band, or NaN if it is nodata or outside the raster.
+    pub value: f64,
+}
+
+impl Band<'_> {
+    /// Samples the band along a line in model coordinates every `spacing` model units from its
+    /// start, and at its end, e.g. the elevation profile of a DEM along a path. The profile along
+    /// the boundary of a polygon is the profile of its exterior ring.
+    ///
+    /// The values are interpolated at the samples as given by the resampling, e.g.
+    /// [Resampling::Bilinear], and the distances are measured in model space, i.e. in degrees
+    /// for geographic coordinates.
+    pub fn profile(
+        &self,
+        line: &LineString,
+        spacing: f64,
+        resampling: Resampling,
+    ) -> TiffResult<Vec<ProfilePoint>> {
+        if !(spacing > 0.0 && spacing.is_finite()) {
+            return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+                "The spacing of a profile must be positive, not {}",
+                spacing
+            ))));
+        }
+        let Some(&start) = line.0.first() else {
+            return Ok(Vec::new());
+        };
+        let mut points = vec![(0.0, start)];
+        // The distance from the start of the line to the start of the segment
+        let mut length = 0.0;
+        let mut next = spacing;
+        for segment in line.lines() {
+            let delta = segment.delta();
+            let segment_length = delta.x.hypot(delta.y);
+            while next < length + segment_length {
+                let t = (next - length) / segment_length;
+                points.push((next, segment.start + delta * t));
+                next += spacing;
+            }
+            length += segment_length;
+        }
+        if length > points.last().map_or(0.0, |&(distance, _)| distance) {
+            points.push((length, *line.0.last().unwrap()));
+        }
+
+        let image = self.image();
+        let (width, height) = (self.width(), self.height());
+        let pixels = points
+            .iter()
+            .map(|(_, coord)| {
+                image.model_to_pixel(coord).filter(|pixel| {
+                    pixel.x >= 0.0
+                        && pixel.y >= 0.0
+                        && pixel.x < width as f64
+                        && pixel.y < height as f64
+                })
+            })
+            .collect::<Vec<_>>();
+        // Only the pixels around the line are decoded for the images decoded on demand
+        let (min, max) = pixels.iter().flatten().fold(
+            (
+                Coord::from((f64::MAX, f64::MAX)),
+                Coord::from((f64::MIN, f64::MIN)),
+            ),
+            |(min, max), pixel| {
+                (
+                    Coord::from((min.x.min(pixel.x), min.y.min(pixel.y))),
+                    Coord::from((max.x.max(pixel.x), max.y.max(pixel.y))),
+                )
+            },
+        );
+        let area = if min.x <= max.x {
+            [min.x, min.y, max.x - min.x, max.y - min.y]
+        } else {
+            [0.0; 4]
+        };
+        let decoded = image.decode_area(image.area_window(area, resampling.radius()))?;
+        let get = |column: usize, row: usize| {
+            let value: f64 = decoded.data.get(decoded.index(column, row) + self.sample());
+            if self.is_nodata(value) {
+                f64::NAN
+            } else {
+                value
+            }
+        };
+        Ok(points
+            .into_iter()
+            .zip(pixels)
+            .map(|((distance, coord), pixel)| {
+                let value = match pixel {
+                    Some(pixel) => {
+                        resampling.resample(get, width, height, [pixel.x, pixel.y, 0.0, 0.0])
+                    }
+                    None => f64::NAN,
+                };
+                let value = if self.is_nodata(value) {
+                    f64::NAN
+                } else {
+                    value
+                };
+                ProfilePoint {
+                    distance,
+                    coord,
+                    value,
+                }
+            })
+            .collect())
+    }
+}
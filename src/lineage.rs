@@ -0,0 +1,126 @@
+use crate::{GdalMetadata, GeoTiff, Image, MetadataItem};
+
+const OPERATION_ITEM: &str = "LINEAGE_OPERATION";
+const SOFTWARE_ITEM: &str = "LINEAGE_SOFTWARE";
+const INPUT_ITEM_PREFIX: &str = "LINEAGE_INPUT_";
+const PARAMETER_ITEM_PREFIX: &str = "LINEAGE_PARAMETER_";
+
+/// The processing history of a derived raster, e.g. the result of a warp, band math or composite,
+/// written with it to trace its pixels back to their sources, see [crate::InMemoryRaster::lineage].
+///
+/// It is stored as dataset items of the GDAL_METADATA tag, so that it is listed by `gdalinfo`:
+/// `LINEAGE_OPERATION`, `LINEAGE_SOFTWARE`, `LINEAGE_INPUT_<index>` for each input and
+/// `LINEAGE_PARAMETER_<name>` for each parameter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lineage {
+    /// The name of the operation which produced the raster, e.g. `composite`.
+    pub operation: String,
+    /// The software which produced the raster, with its version.
+    pub software: String,
+    /// The sources of the raster, e.g. the paths or URLs of the input files.
+    pub inputs: Vec<String>,
+    /// The parameters of the operation, by name.
+    pub parameters: Vec<(String, String)>,
+}
+
+impl Lineage {
+    /// Creates the lineage of an operation of this crate, without inputs and parameters.
+    pub fn new(operation: &str) -> Self {
+        Self {
+            operation: operation.into(),
+            software: concat!("geotiff ", env!("CARGO_PKG_VERSION")).into(),
+            inputs: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn with_input(mut self, input: &str) -> Self {
+        self.inputs.push(input.into());
+        self
+    }
+
+    pub fn with_parameter(mut self, name: &str, value: impl ToString) -> Self {
+        self.parameters.push((name.into(), value.to_string()));
+        self
+    }
+
+    /// Reads the lineage from the dataset items of the metadata, if it has an operation.
+    pub fn from_gdal_metadata(metadata: &GdalMetadata) -> Option<Self> {
+        let mut lineage = Self {
+            operation: metadata.get(OPERATION_ITEM)?.into(),
+            software: metadata.get(SOFTWARE_ITEM).unwrap_or_default().into(),
+            inputs: Vec::new(),
+            parameters: Vec::new(),
+        };
+        let mut inputs = Vec::new();
+        for item in metadata
+            .items
+            .iter()
+            .filter(|item| item.sample.is_none() && item.role.is_none())
+        {
+            if let Some(index) = item.name.strip_prefix(INPUT_ITEM_PREFIX) {
+                if let Ok(index) = index.parse::<usize>() {
+                    inputs.push((index, item.value.clone()));
+                }
+            } else if let Some(name) = item.name.strip_prefix(PARAMETER_ITEM_PREFIX) {
+                lineage.parameters.push((name.into(), item.value.clone()));
+            }
+        }
+        inputs.sort_by_key(|(index, _)| *index);
+        lineage.inputs = inputs.into_iter().map(|(_, input)| input).collect();
+        Some(lineage)
+    }
+
+    /// Returns the dataset items of the GDAL_METADATA tag holding the lineage.
+    pub fn to_gdal_metadata(&self) -> GdalMetadata {
+        let item = |name: String, value: &str| MetadataItem {
+            name,
+            sample: None,
+            role: None,
+            value: value.into(),
+        };
+        let mut items = vec![
+            item(OPERATION_ITEM.into(), &self.operation),
+            item(SOFTWARE_ITEM.into(), &self.software),
+        ];
+        items.extend(
+            self.inputs
+                .iter()
+                .enumerate()
+                .map(|(index, input)| item(format!("{}{}", INPUT_ITEM_PREFIX, index), input)),
+        );
+        items.extend(
+            self.parameters
+                .iter()
+                .map(|(name, value)| item(format!("{}{}", PARAMETER_ITEM_PREFIX, name), value)),
+        );
+        GdalMetadata { items }
+    }
+
+    /// Replaces the lineage in the dataset items of the metadata, keeping its other items.
+    pub(crate) fn replace_in(&self, metadata: &mut GdalMetadata) {
+        metadata.items.retain(|item| {
+            item.sample.is_some()
+                || item.role.is_some()
+                || !(item.name == OPERATION_ITEM
+                    || item.name == SOFTWARE_ITEM
+                    || item.name.starts_with(INPUT_ITEM_PREFIX)
+                    || item.name.starts_with(PARAMETER_ITEM_PREFIX))
+        });
+        metadata.items.extend(self.to_gdal_metadata().items);
+    }
+}
+
+impl Image {
+    /// Returns the processing history recorded in the GDAL_METADATA tag of this image, if any.
+    pub fn lineage(&self) -> Option<Lineage> {
+        Lineage::from_gdal_metadata(self.gdal_metadata()?)
+    }
+}
+
+impl GeoTiff {
+    /// See [Image::lineage].
+    pub fn lineage(&self) -> Option<Lineage> {
+        self.primary().lineage()
+    }
+}
@@ -0,0 +1,116 @@
+use std::mem::size_of;
+
+use num_traits::{Bounded, NumCast};
+use tiff::TiffResult;
+
+use crate::{DType, GeoTiff, Image, ReadOptions, Window, WindowData};
+
+impl Image {
+    /// Returns the number of bytes of the decoded raster data held by this image, which is
+    /// decoded when the file is read unless it exceeds the budget, see
+    /// [crate::OpenOptions::memory_budget].
+    pub fn raster_memory(&self) -> usize {
+        self.raster_data
+            .as_ref()
+            .map_or(0, |data| data.size_in_bytes())
+            + self
+                .complex_data
+                .as_ref()
+                .map_or(0, |data| data.size_in_bytes())
+    }
+
+    /// Returns the number of bytes allocated by [Image::read_window] with the same arguments,
+    /// i.e. the size of the returned data, in addition to the raster data held by the image, and
+    /// the samples of the window for the images decoded on demand.
+    pub fn estimate_memory<T: NumCast + Bounded + Copy + 'static>(
+        &self,
+        window: Window,
+        options: &ReadOptions,
+    ) -> TiffResult<usize> {
+        let (window, _) = self.resolve_window::<T>(window, options)?;
+        let decoded = match (&self.lazy_chunks, window.intersection(&self.window())) {
+            (Some(_), Some(area)) => estimate_raster_memory(area.width, area.height, &self.dtypes),
+            _ => 0,
+        };
+        Ok(window.width * window.height * self.num_samples * size_of::<T>() + decoded)
+    }
+
+    /// Reads the pixels of the given window like [Image::read_window], calling `f` with the data
+    /// of consecutive bands of rows whose size is at most `budget` bytes, in top to bottom order.
+    ///
+    /// The window is read at once if it fits in the budget. Otherwise, the bands span whole
+    /// tiles or strips if the budget allows it, and at least one row otherwise.
+    pub fn read_window_chunked<T, F>(
+        &self,
+        window: Window,
+        options: &ReadOptions,
+        budget: usize,
+        mut f: F,
+    ) -> TiffResult<()>
+    where
+        T: NumCast + Bounded + Copy + 'static,
+        F: FnMut(WindowData<T>) -> TiffResult<()>,
+    {
+        let (window, _) = self.resolve_window::<T>(window, options)?;
+        let row_bytes = window.width * self.num_samples * size_of::<T>();
+        let mut rows = (budget / row_bytes.max(1)).clamp(1, window.height.max(1));
+        let block_height = self.block_size.1.max(1);
+        if rows >= block_height {
+            rows -= rows % block_height;
+        }
+
+        let mut y = 0;
+        loop {
+            let height = rows.min(window.height - y);
+            let chunk = Window::new(window.x, window.y + y as i64, window.width, height);
+            f(self.read_window(chunk, options)?)?;
+            y += height;
+            if y >= window.height {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl GeoTiff {
+    /// Returns the number of bytes of the decoded raster data held by all the images, see
+    /// [Image::raster_memory].
+    pub fn raster_memory(&self) -> usize {
+        self.images().iter().map(Image::raster_memory).sum()
+    }
+
+    /// See [Image::estimate_memory].
+    pub fn estimate_memory<T: NumCast + Bounded + Copy + 'static>(
+        &self,
+        window: Window,
+        options: &ReadOptions,
+    ) -> TiffResult<usize> {
+        self.primary().estimate_memory::<T>(window, options)
+    }
+
+    /// See [Image::read_window_chunked].
+    pub fn read_window_chunked<T, F>(
+        &self,
+        window: Window,
+        options: &ReadOptions,
+        budget: usize,
+        f: F,
+    ) -> TiffResult<()>
+    where
+        T: NumCast + Bounded + Copy + 'static,
+        F: FnMut(WindowData<T>) -> TiffResult<()>,
+    {
+        self.primary()
+            .read_window_chunked(window, options, budget, f)
+    }
+}
+
+/// Returns the number of bytes of the decoded raster data of an image of the given size and
+/// sample types, before it is decoded.
+pub(crate) fn estimate_raster_memory(width: usize, height: usize, dtypes: &[DType]) -> usize {
+    let pixel_bytes = dtypes
+        .iter()
+        .map(|dtype| (dtype.bits_per_sample() as usize).div_ceil(8))
+        .sum::<usize>();
+    width.saturating_mul(height).saturating_mul(pixel_bytes)
+}
@@ -0,0 +1,175 @@
+//! Triangle meshes of DEMs for 3D terrain visualization, with an export to binary glTF (GLB),
+//! which most 3D viewers and engines load.
+//!
+//! The vertices are the centers of the valid pixels, with their height given by the value of the
+//! DEM. Each block of 2 x 2 pixels is split into two triangles, and the triangles with a nodata
+//! vertex are omitted, so that holes in the DEM are holes in the mesh.
+
+use std::io::{self, Write};
+
+use tiff::{TiffError, TiffFormatError, TiffResult};
+
+use crate::{Band, Window};
+
+/// The magic number of GLB files, `glTF`.
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_JSON_CHUNK: u32 = 0x4E4F_534A;
+const GLB_BIN_CHUNK: u32 = 0x004E_4942;
+
+/// A mesh of triangles in model space.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TriangleMesh {
+    /// The x and y model coordinates of the vertices, and their height.
+    pub positions: Vec<[f64; 3]>,
+    /// The indices of the vertices of the triangles, by three, counter-clockwise seen from above.
+    pub indices: Vec<u32>,
+}
+
+/// Returns the mesh of the pixels of a DEM in the given window, with the values multiplied by
+/// the vertical exaggeration as heights, e.g. 3 to emphasize the relief of flat areas.
+///
+/// The heights are in the unit of the values, which should be the linear unit of the model space
+/// for the mesh to have its true shape. Returns an error if the image is not georeferenced or if
+/// the mesh has more vertices than 32-bit indices can address.
+pub fn to_mesh(band: Band, window: Window, vertical_exaggeration: f64) -> TiffResult<TriangleMesh> {
+    let image = band.image();
+    if image.coordinate_transform().is_none() {
+        return Err(TiffError::FormatError(TiffFormatError::Format(
+            "Image is not georeferenced".into(),
+        )));
+    }
+    let mut mesh = TriangleMesh::default();
+    let Some(window) = window.intersection(&Window::full(band.width(), band.height())) else {
+        return Ok(mesh);
+    };
+    let values = band.read_window(window)?;
+    let center = |x, y| image.pixel_center(x, y).unwrap();
+
+    // The index of the vertex of each pixel of the window, if it is valid
+    let mut vertices = Vec::with_capacity(values.len());
+    for (index, value) in values.into_iter().enumerate() {
+        if band.is_nodata(value) {
+            vertices.push(None);
+            continue;
+        }
+        let x = window.x as usize + index % window.width;
+        let y = window.y as usize + index / window.width;
+        let position = center(x, y);
+        let vertex = u32::try_from(mesh.positions.len()).map_err(|_| {
+            TiffError::FormatError(TiffFormatError::Format(format!(
+                "The mesh of window {:?} has too many vertices",
+                window
+            )))
+        })?;
+        vertices.push(Some(vertex));
+        mesh.positions
+            .push([position.x, position.y, value * vertical_exaggeration]);
+    }
+
+    // Whether the rows of the raster go down in model space, as they usually do, in which case
+    // the triangles are counter-clockwise in model space when they are clockwise in raster space
+    let (origin, right, below) = (center(0, 0), center(1, 0), center(0, 1));
+    let rows_down = (right.x - origin.x) * (below.y - origin.y)
+        - (right.y - origin.y) * (below.x - origin.x)
+        < 0.0;
+    for y in 1..window.height {
+        for x in 1..window.width {
+            let vertex = |dx: usize, dy: usize| vertices[(y - dy) * window.width + x - dx];
+            // The top left, top right, bottom left and bottom right pixels
+            let quad = [vertex(1, 1), vertex(0, 1), vertex(1, 0), vertex(0, 0)];
+            for [a, b, c] in [[0, 2, 1], [1, 2, 3]] {
+                let (Some(a), Some(b), Some(c)) = (quad[a], quad[b], quad[c]) else {
+                    continue;
+                };
+                if rows_down {
+                    mesh.indices.extend([a, b, c]);
+                } else {
+                    mesh.indices.extend([a, c, b]);
+                }
+            }
+        }
+    }
+    Ok(mesh)
+}
+
+impl TriangleMesh {
+    /// Writes the mesh as a binary glTF file, with a single node.
+    ///
+    /// glTF has its y axis up, so that the model x, y and height are its x, -z and y. The
+    /// positions are stored as 32-bit floats relative to the center of the mesh, which is the
+    /// translation of the node, so that model coordinates of large magnitude keep their
+    /// precision.
+    pub fn write_glb<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let (mut min, mut max) = ([f64::INFINITY; 3], [f64::NEG_INFINITY; 3]);
+        for position in &self.positions {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(position[axis]);
+                max[axis] = max[axis].max(position[axis]);
+            }
+        }
+        let center = if self.positions.is_empty() {
+            [0.0; 3]
+        } else {
+            [0, 1, 2].map(|axis| (min[axis] + max[axis]) / 2.0)
+        };
+        let to_gltf = |[x, y, z]: [f64; 3]| [x, z, -y];
+
+        let mut bin = Vec::with_capacity(self.positions.len() * 12 + self.indices.len() * 4);
+        let (mut local_min, mut local_max) = ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]);
+        for &position in &self.positions {
+            let local = to_gltf([0, 1, 2].map(|axis| position[axis] - center[axis]));
+            for axis in 0..3 {
+                let value = local[axis] as f32;
+                local_min[axis] = local_min[axis].min(value);
+                local_max[axis] = local_max[axis].max(value);
+                bin.extend(value.to_le_bytes());
+            }
+        }
+        let positions_length = bin.len();
+        for index in &self.indices {
+            bin.extend(index.to_le_bytes());
+        }
+        let indices_length = bin.len() - positions_length;
+
+        let [x, y, z] = to_gltf(center);
+        let (vertex_count, index_count) = (self.positions.len(), self.indices.len());
+        let bounds = |values: [f32; 3]| {
+            let [x, y, z] = values.map(|value| if value.is_finite() { value } else { 0.0 });
+            format!("[{x},{y},{z}]")
+        };
+        let json = format!(
+            "{{\"asset\":{{\"version\":\"2.0\",\"generator\":\"geotiff\"}},\
+             \"scene\":0,\"scenes\":[{{\"nodes\":[0]}}],\
+             \"nodes\":[{{\"mesh\":0,\"translation\":[{x},{y},{z}]}}],\
+             \"meshes\":[{{\"primitives\":[{{\"attributes\":{{\"POSITION\":0}},\"indices\":1,\"mode\":4}}]}}],\
+             \"buffers\":[{{\"byteLength\":{}}}],\
+             \"bufferViews\":[\
+             {{\"buffer\":0,\"byteOffset\":0,\"byteLength\":{positions_length},\"target\":34962}},\
+             {{\"buffer\":0,\"byteOffset\":{positions_length},\"byteLength\":{indices_length},\"target\":34963}}],\
+             \"accessors\":[\
+             {{\"bufferView\":0,\"componentType\":5126,\"count\":{vertex_count},\"type\":\"VEC3\",\
+             \"min\":{},\"max\":{}}},\
+             {{\"bufferView\":1,\"componentType\":5125,\"count\":{index_count},\"type\":\"SCALAR\"}}]}}",
+            bin.len(),
+            bounds(local_min),
+            bounds(local_max),
+        );
+
+        // The chunks are padded to 4 bytes, with spaces for JSON
+        let mut json = json.into_bytes();
+        json.resize(json.len().next_multiple_of(4), b' ');
+        bin.resize(bin.len().next_multiple_of(4), 0);
+        let length = 12 + 8 + json.len() + 8 + bin.len();
+        let length = u32::try_from(length)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "The mesh is too large"))?;
+        writer.write_all(&GLB_MAGIC.to_le_bytes())?;
+        writer.write_all(&2u32.to_le_bytes())?;
+        writer.write_all(&length.to_le_bytes())?;
+        for (chunk_type, chunk) in [(GLB_JSON_CHUNK, &json), (GLB_BIN_CHUNK, &bin)] {
+            writer.write_all(&(chunk.len() as u32).to_le_bytes())?;
+            writer.write_all(&chunk_type.to_le_bytes())?;
+            writer.write_all(chunk)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use std::error::Error;
+use std::fmt;
+
+/// An error in the geo keys or in the tags of the transformation between raster space and model
+/// space, which are parsed without the tiff crate, e.g. by the metadata-only build without the
+/// `decode` feature.
+///
+/// With the `decode` feature, it converts to the format error of the tiff crate with the same
+/// message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetadataError {
+    /// The values are invalid or inconsistent, with the reason.
+    Format(String),
+}
+
+pub type MetadataResult<T> = Result<T, MetadataError>;
+
+impl fmt::Display for MetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetadataError::Format(message) => write!(f, "Invalid format: {message:?}."),
+        }
+    }
+}
+
+impl Error for MetadataError {}
+
+#[cfg(feature = "decode")]
+impl From<MetadataError> for tiff::TiffError {
+    fn from(error: MetadataError) -> Self {
+        match error {
+            MetadataError::Format(message) => {
+                tiff::TiffError::FormatError(tiff::TiffFormatError::Format(message))
+            }
+        }
+    }
+}
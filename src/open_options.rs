@@ -0,0 +1,266 @@
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek};
+use std::path::Path;
+
+use geo_types::Coord;
+use tiff::TiffResult;
+
+use crate::coordinate_transform::DEFAULT_INVERTIBILITY_TOLERANCE;
+use crate::world_file::read_world_file;
+use crate::{DecodeContext, GeoKeyDirectory, GeoTiff, PixelScaleConvention, TransformTags};
+
+/// Options which can be used to configure how a GeoTIFF is read.
+///
+/// [GeoTiff::read] uses the default options.
+#[derive(Debug, Clone)]
+pub struct OpenOptions {
+    pub(crate) invertibility_tolerance: f64,
+    pub(crate) pixel_scale_convention: PixelScaleConvention,
+    pub(crate) fit_tie_points: bool,
+    pub(crate) normalize_linear_units: bool,
+    pub(crate) axis_order: AxisOrder,
+    pub(crate) allow_ungeoreferenced: bool,
+    pub(crate) lenient_ascii_params: bool,
+    pub(crate) crs_override: Option<CrsOverride>,
+    pub(crate) transform_override: Option<TransformOverride>,
+    pub(crate) memory_budget: Option<usize>,
+    pub(crate) preload_index: bool,
+}
+
+/// A CRS replacing the one stored in a file, see [OpenOptions::override_crs].
+#[derive(Debug, Clone, PartialEq)]
+pub enum CrsOverride {
+    /// An EPSG code, see [GeoKeyDirectory::from_epsg]. The raster type of the file is kept.
+    Epsg(u16),
+    /// Geo keys overlaid on those of the file, see [GeoKeyDirectory::merged_with].
+    GeoKeys(Box<GeoKeyDirectory>),
+}
+
+impl From<u16> for CrsOverride {
+    fn from(code: u16) -> Self {
+        CrsOverride::Epsg(code)
+    }
+}
+
+impl From<GeoKeyDirectory> for CrsOverride {
+    fn from(directory: GeoKeyDirectory) -> Self {
+        CrsOverride::GeoKeys(Box::new(directory))
+    }
+}
+
+/// A transformation replacing the one stored in a file, see [OpenOptions::override_transform]
+/// and [OpenOptions::override_bounds].
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TransformOverride {
+    Tags(TransformTags),
+    Bounds {
+        upper_left: Coord<f64>,
+        lower_right: Coord<f64>,
+    },
+}
+
+/// Order of the axes of model coordinates, see [OpenOptions::axis_order].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AxisOrder {
+    /// The order in which the coordinates are stored in GeoTIFF files, i.e. easting/longitude
+    /// first.
+    #[default]
+    AsStored,
+    /// The order defined by the authority of the CRS, i.e. latitude first for geographic CRS
+    /// given by an EPSG code.
+    Authority,
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        Self {
+            invertibility_tolerance: DEFAULT_INVERTIBILITY_TOLERANCE,
+            pixel_scale_convention: PixelScaleConvention::AsStored,
+            fit_tie_points: false,
+            normalize_linear_units: false,
+            axis_order: AxisOrder::AsStored,
+            allow_ungeoreferenced: true,
+            lenient_ascii_params: false,
+            crs_override: None,
+            transform_override: None,
+            memory_budget: None,
+            preload_index: false,
+        }
+    }
+}
+
+impl OpenOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the relative tolerance below which an affine transformation is considered not
+    /// invertible, in which case transforming from model space to raster space fails.
+    pub fn invertibility_tolerance(&mut self, tolerance: f64) -> &mut Self {
+        self.invertibility_tolerance = tolerance;
+        self
+    }
+
+    /// Sets how the sign of the Y value of the ModelPixelScaleTag is interpreted.
+    ///
+    /// By default, the values are used as stored, so a file with a negative ScaleY is read as
+    /// south up. Use [PixelScaleConvention::NorthUp] for files from producers which store the
+    /// signed Y resolution instead.
+    pub fn pixel_scale_convention(&mut self, convention: PixelScaleConvention) -> &mut Self {
+        self.pixel_scale_convention = convention;
+        self
+    }
+
+    /// Sets whether the transformation defined by several tie points is the affine
+    /// transformation fitted to them by least squares, see
+    /// [crate::AffineTransform::fit_from_tie_points], instead of their interpolation.
+    ///
+    /// Many files with several tie points are affine up to the precision of the points. The
+    /// fitted transformation does not need the `tie-points` feature, and its residuals are given
+    /// by [crate::Image::tie_point_residuals].
+    pub fn fit_tie_points(&mut self, fit: bool) -> &mut Self {
+        self.fit_tie_points = fit;
+        self
+    }
+
+    /// Sets whether the model coordinates of projected CRS in other linear units than metres,
+    /// e.g. US survey feet, are converted to metres.
+    ///
+    /// Only the coordinate transformation is affected: the geo keys still describe the CRS as
+    /// stored in the file. See [crate::Image::model_linear_unit] for the unit of the model coordinates.
+    pub fn normalize_linear_units(&mut self, normalize: bool) -> &mut Self {
+        self.normalize_linear_units = normalize;
+        self
+    }
+
+    /// Sets the order of the axes of the model coordinates taken and returned by the bounds and
+    /// sampling methods of [GeoTiff] and [crate::Image].
+    ///
+    /// The coordinate transformations always use the stored order.
+    pub fn axis_order(&mut self, axis_order: AxisOrder) -> &mut Self {
+        self.axis_order = axis_order;
+        self
+    }
+
+    /// Sets whether files whose primary image has neither geo keys nor a transformation between
+    /// raster space and model space can be read, e.g. plain TIFF files.
+    ///
+    /// This is allowed by default. The raster space is then used as model space.
+    pub fn allow_ungeoreferenced(&mut self, allow: bool) -> &mut Self {
+        self.allow_ungeoreferenced = allow;
+        self
+    }
+
+    /// Sets whether the values of the ASCII geo keys are read leniently, see
+    /// [crate::GeoKeyDirectory::from_tag_data_lenient].
+    ///
+    /// Many files do not follow the exact offsets and counts of the GeoTIFF specification, e.g.
+    /// they separate the values with NULs, which are rejected by default. In lenient mode, the
+    /// keys whose value cannot be read are skipped, as well as all ASCII keys if the
+    /// GeoAsciiParamsTag itself is not valid ASCII.
+    pub fn lenient_ascii_params(&mut self, lenient: bool) -> &mut Self {
+        self.lenient_ascii_params = lenient;
+        self
+    }
+
+    /// Sets the CRS of the file, replacing the one given by its geo keys if any, like the
+    /// `-a_srs` option of GDAL. The file is not modified.
+    ///
+    /// Overrides apply to the full-resolution images and to the first image of the file.
+    pub fn override_crs<C: Into<CrsOverride>>(&mut self, crs: C) -> &mut Self {
+        self.crs_override = Some(crs.into());
+        self
+    }
+
+    /// Sets the tags defining the transformation between raster space and model space,
+    /// replacing those of the file if any. The file is not modified.
+    ///
+    /// The tags are interpreted like stored tags, e.g. in the linear unit of the CRS. See
+    /// [OpenOptions::override_crs] for the images they apply to.
+    pub fn override_transform(&mut self, transform_tags: TransformTags) -> &mut Self {
+        self.transform_override = Some(TransformOverride::Tags(transform_tags));
+        self
+    }
+
+    /// Sets the transformation between raster space and model space from the model coordinates
+    /// of the upper left and lower right corners of the raster, like the `-a_ullr` option of
+    /// GDAL. The file is not modified.
+    ///
+    /// This replaces [OpenOptions::override_transform], and conversely.
+    pub fn override_bounds(
+        &mut self,
+        upper_left: Coord<f64>,
+        lower_right: Coord<f64>,
+    ) -> &mut Self {
+        self.transform_override = Some(TransformOverride::Bounds {
+            upper_left,
+            lower_right,
+        });
+        self
+    }
+
+    /// Sets the maximum number of bytes of the raster data decoded when reading the file, for all
+    /// its images, see [crate::Image::raster_memory].
+    ///
+    /// The images whose raster data would exceed the remaining budget are not decoded when the
+    /// file is read, but chunk by chunk on demand, decoding only the chunks intersecting each
+    /// window which is read. The chunks are read again from the file for [OpenOptions::open_path],
+    /// and their compressed bytes are kept in memory for the other sources. Reading fails with
+    /// [tiff::TiffError::LimitsExceeded] for the images which cannot be decoded on demand, i.e.
+    /// those decoded by the tiff crate, e.g. JPEG, and those of complex values. This also
+    /// replaces the default limit of the tiff crate on the size of an image, which is 256 MiB,
+    /// beyond which images are decoded on demand without a budget.
+    ///
+    /// [crate::Image::read_window_chunked] bounds the memory allocated by reads, in addition to
+    /// the decoded raster data.
+    pub fn memory_budget(&mut self, bytes: usize) -> &mut Self {
+        self.memory_budget = Some(bytes);
+        self
+    }
+
+    /// Sets whether the offsets and byte counts of the chunks of all the images are kept when
+    /// reading the file, see [crate::Image::chunk_index], e.g. for servers forwarding byte
+    /// ranges of tiles to clients. They are not kept by default, which suits the uses of the
+    /// metadata and raster data only.
+    pub fn preload_index(&mut self, preload: bool) -> &mut Self {
+        self.preload_index = preload;
+        self
+    }
+
+    /// Reads a GeoTIFF from the given source with these options.
+    pub fn read<R: Read + Seek>(&self, reader: R) -> TiffResult<GeoTiff> {
+        DecodeContext::with_thread_context(|context| {
+            GeoTiff::read_with_options(reader, self, None, None, context)
+        })
+    }
+
+    /// Reads a GeoTIFF from the given source with these options, reusing the scratch buffers of
+    /// the given context rather than those of the current thread, e.g. to bound the memory kept
+    /// by each worker of a server.
+    pub fn read_with_context<R: Read + Seek>(
+        &self,
+        reader: R,
+        context: &mut DecodeContext,
+    ) -> TiffResult<GeoTiff> {
+        GeoTiff::read_with_options(reader, self, None, None, context)
+    }
+
+    /// Reads a GeoTIFF from the file at the given path with these options.
+    ///
+    /// If the primary image has no transformation between raster space and model space, it is
+    /// read from a world file next to the file if there is one, e.g. "image.tfw" for
+    /// "image.tif".
+    pub fn open_path<P: AsRef<Path>>(&self, path: P) -> TiffResult<GeoTiff> {
+        let path = path.as_ref();
+        let world_file = read_world_file(path)?;
+        let reader = BufReader::new(File::open(path)?);
+        DecodeContext::with_thread_context(|context| {
+            GeoTiff::read_with_options(reader, self, Some(path), world_file, context)
+        })
+    }
+
+    /// Reads a GeoTIFF from an in-memory buffer with these options.
+    pub fn read_bytes(&self, bytes: &[u8]) -> TiffResult<GeoTiff> {
+        self.read(Cursor::new(bytes))
+    }
+}
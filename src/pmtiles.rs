@@ -0,0 +1,459 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use geo_types::Coord;
+use tiff::tags::PhotometricInterpretation;
+use tiff::{TiffError, TiffFormatError, TiffResult};
+
+use crate::render::{render, stretch_range};
+use crate::webmercator;
+use crate::{
+    GeoTiff, Image, Interleave, Orientation, OutOfBounds, ReadOptions, RenderOptions, RgbaImage,
+    Window, WindowData,
+};
+
+/// The EPSG codes of Web Mercator.
+const WEB_MERCATOR: [u16; 2] = [3857, 3785];
+/// The largest dimension of the sample of the image used to compute the stretch of the tiles.
+const STRETCH_SAMPLE_DIM: usize = 1024;
+
+const HEADER_LENGTH: usize = 127;
+/// The maximum length of the header and root directory, which are fetched at once by readers.
+const ROOT_LENGTH: usize = 16384;
+/// The number of entries of each leaf directory, if the root directory is too large.
+const LEAF_ENTRIES: usize = 4096;
+
+/// Options of [GeoTiff::export_pmtiles].
+#[derive(Debug, Clone)]
+pub struct TileExportOptions {
+    /// The width and height of the tiles in pixels.
+    pub tile_size: usize,
+    /// The options of the rendering of the tiles. Unless the color ramp is given by data values,
+    /// the stretch is computed once over the whole image so that the tiles match.
+    pub render: RenderOptions,
+}
+
+impl Default for TileExportOptions {
+    fn default() -> Self {
+        Self {
+            tile_size: 256,
+            render: RenderOptions::default(),
+        }
+    }
+}
+
+impl GeoTiff {
+    /// Renders the primary image into a [PMTiles](https://github.com/protomaps/PMTiles) archive of
+    /// PNG tiles for the given zoom levels, e.g. to prepare a basemap.
+    ///
+    /// The image must be in Web Mercator (EPSG:3857). Tiles without any valid pixel are omitted.
+    /// The tiles are held in memory until the archive is written.
+    pub fn export_pmtiles<W: Write>(
+        &self,
+        mut writer: W,
+        zooms: RangeInclusive<u8>,
+        options: &TileExportOptions,
+    ) -> TiffResult<()> {
+        let primary = self.primary();
+        let is_web_mercator = primary
+            .geo_key_directory()
+            .and_then(|directory| directory.projected_type)
+            .is_some_and(|code| WEB_MERCATOR.contains(&code));
+        if primary.coordinate_transform().is_none() || !is_web_mercator {
+            return Err(TiffError::FormatError(TiffFormatError::Format(
+                "Tile export requires a georeferenced image in Web Mercator (EPSG:3857)".into(),
+            )));
+        }
+        let is_rgb = primary.photometric_interpretation == Some(PhotometricInterpretation::RGB)
+            && primary.num_samples >= 3;
+        let ranges = self.stretch_ranges(is_rgb, &options.render)?;
+
+        let bounds = primary.model_bounds_outer();
+        let mut tiles = Vec::new();
+        for zoom in zooms.clone() {
+            let (columns, rows) = webmercator::tile_range(bounds, zoom);
+            for y in rows {
+                for x in columns.clone() {
+                    let tile = self.render_tile(zoom, x, y, is_rgb, ranges.as_deref(), options)?;
+                    if tile.data.chunks(4).any(|pixel| pixel[3] > 0) {
+                        tiles.push((tile_id(zoom, x, y), encode_png(&tile)?));
+                    }
+                }
+            }
+        }
+        tiles.sort_by_key(|(id, _)| *id);
+
+        // The metadata and the header refer to the bounds in longitude/latitude
+        let min = webmercator::meters_to_lon_lat(bounds.min());
+        let max = webmercator::meters_to_lon_lat(bounds.max());
+        let archive = Archive {
+            tiles,
+            zooms,
+            min,
+            max,
+        };
+        writer.write_all(&archive.to_bytes()?)?;
+        Ok(())
+    }
+
+    /// Writes the archive to the file at the given path, see [GeoTiff::export_pmtiles].
+    pub fn export_pmtiles_path<P: AsRef<Path>>(
+        &self,
+        path: P,
+        zooms: RangeInclusive<u8>,
+        options: &TileExportOptions,
+    ) -> TiffResult<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.export_pmtiles(&mut writer, zooms, options)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Returns the range of each rendered band over a sample of the whole image, or `None` if
+    /// the colors are given by data values.
+    fn stretch_ranges(
+        &self,
+        is_rgb: bool,
+        options: &RenderOptions,
+    ) -> TiffResult<Option<Vec<(f64, f64)>>> {
+        if !is_rgb
+            && options
+                .color_ramp
+                .as_ref()
+                .is_some_and(|ramp| ramp.has_data_values())
+        {
+            return Ok(None);
+        }
+        let primary = self.primary();
+        let scale = (STRETCH_SAMPLE_DIM as f64
+            / primary.raster_width.max(primary.raster_height) as f64)
+            .min(1.0);
+        let sample = self.read_window_decimated::<f64>(
+            primary.window(),
+            ((primary.raster_width as f64 * scale).round() as usize).max(1),
+            ((primary.raster_height as f64 * scale).round() as usize).max(1),
+            &ReadOptions::default(),
+        )?;
+        let band = |sample_index: usize| {
+            sample
+                .data
+                .iter()
+                .skip(sample_index)
+                .step_by(sample.num_samples)
+                .copied()
+                .collect::<Vec<_>>()
+        };
+        let valid = band(0)
+            .iter()
+            .map(|value| !value.is_nan() && Some(*value) != primary.nodata())
+            .collect::<Vec<_>>();
+        let bands = if is_rgb { 0..3 } else { 0..1 };
+        Ok(Some(
+            bands
+                .map(|sample_index| {
+                    stretch_range(&band(sample_index), &valid, options.stretch)
+                        .unwrap_or((0.0, 0.0))
+                })
+                .collect(),
+        ))
+    }
+
+    /// Renders the tile, sampling the nearest pixel of the smallest overview with sufficient
+    /// resolution.
+    fn render_tile(
+        &self,
+        zoom: u8,
+        x: u32,
+        y: u32,
+        is_rgb: bool,
+        ranges: Option<&[(f64, f64)]>,
+        options: &TileExportOptions,
+    ) -> TiffResult<RgbaImage> {
+        let primary = self.primary();
+        let size = options.tile_size;
+        let bounds = webmercator::tile_bounds(zoom, x, y);
+        let origin = Coord {
+            x: bounds.min().x,
+            y: bounds.max().y,
+        };
+        let pixel_extent = webmercator::resolution(zoom, size);
+
+        // The position of the center of each pixel of the tile in the raster space of the image
+        let mut positions = Vec::with_capacity(size * size);
+        for j in 0..size {
+            for i in 0..size {
+                let model = Coord {
+                    x: origin.x + (i as f64 + 0.5) * pixel_extent,
+                    y: origin.y - (j as f64 + 0.5) * pixel_extent,
+                };
+                positions.push(raster_position(primary, model)?);
+            }
+        }
+
+        let num_samples = primary.num_samples;
+        let mut data = vec![f64::NAN; size * size * num_samples];
+        let (min_x, min_y, max_x, max_y) = positions.iter().fold(
+            (
+                f64::INFINITY,
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+                f64::NEG_INFINITY,
+            ),
+            |(min_x, min_y, max_x, max_y), position| {
+                (
+                    min_x.min(position.x),
+                    min_y.min(position.y),
+                    max_x.max(position.x),
+                    max_y.max(position.y),
+                )
+            },
+        );
+        let covered = Window::new(
+            min_x.floor() as i64,
+            min_y.floor() as i64,
+            (max_x.ceil() - min_x.floor()) as usize + 1,
+            (max_y.ceil() - min_y.floor()) as usize + 1,
+        )
+        .intersection(&primary.window());
+        if let Some(covered) = covered {
+            // Read at about the resolution of the tile
+            let scale = (size as f64 / (max_x - min_x).max(max_y - min_y).max(1.0)).min(1.0);
+            let (out_width, out_height) = (
+                ((covered.width as f64 * scale).ceil() as usize).max(1),
+                ((covered.height as f64 * scale).ceil() as usize).max(1),
+            );
+            let read_options = ReadOptions {
+                out_of_bounds: OutOfBounds::Fill(f64::NAN),
+                ..Default::default()
+            };
+            let window =
+                self.read_window_decimated::<f64>(covered, out_width, out_height, &read_options)?;
+            for (i, position) in positions.iter().enumerate() {
+                let column = (position.x - covered.x as f64) / covered.width as f64;
+                let row = (position.y - covered.y as f64) / covered.height as f64;
+                if !(0.0..1.0).contains(&column) || !(0.0..1.0).contains(&row) {
+                    continue;
+                }
+                let (column, row) = (
+                    (column * out_width as f64) as usize,
+                    (row * out_height as f64) as usize,
+                );
+                for sample in 0..num_samples {
+                    data[i * num_samples + sample] = window.get(column, row, sample);
+                }
+            }
+        }
+
+        let tile = WindowData {
+            window: Window::new(0, 0, size, size),
+            width: size,
+            height: size,
+            num_samples,
+            interleave: Interleave::Pixel,
+            orientation: Orientation::default(),
+            data,
+        };
+        Ok(render(
+            &tile,
+            is_rgb,
+            primary.nodata(),
+            &options.render,
+            ranges,
+        ))
+    }
+}
+
+/// Returns the position in pixels of the model coordinates in the raster of the image, such that
+/// the pixel (0, 0) spans from (0, 0) to (1, 1).
+fn raster_position(image: &Image, model: Coord) -> TiffResult<Coord> {
+    let transform = image.coordinate_transform().unwrap();
+    let raster = transform.transform_to_raster(&model)?;
+    let offset = image.raster_offset();
+    Ok(Coord {
+        x: raster.x - offset,
+        y: raster.y - offset,
+    })
+}
+
+/// Returns the PMTiles identifier of the tile, i.e. its position along the Hilbert curves of the
+/// successive zoom levels.
+fn tile_id(zoom: u8, x: u32, y: u32) -> u64 {
+    let previous_tiles = ((1u64 << (2 * zoom as u32)) - 1) / 3;
+    let (mut x, mut y) = (x as u64, y as u64);
+    let mut position = 0;
+    let mut s = (1u64 << zoom) / 2;
+    while s > 0 {
+        let rx = u64::from(x & s > 0);
+        let ry = u64::from(y & s > 0);
+        position += s * s * ((3 * rx) ^ ry);
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - (x & (s - 1));
+                y = s - 1 - (y & (s - 1));
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    previous_tiles + position
+}
+
+/// Encodes the image as an RGBA PNG.
+fn encode_png(image: &RgbaImage) -> TiffResult<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    for row in image.data.chunks(image.width * 4) {
+        // Each row starts with its filter type, none here
+        encoder.write_all(&[0])?;
+        encoder.write_all(row)?;
+    }
+    let compressed = encoder.finish()?;
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    let mut header = Vec::with_capacity(13);
+    header.extend((image.width as u32).to_be_bytes());
+    header.extend((image.height as u32).to_be_bytes());
+    // 8 bits per sample, RGBA, default compression and filtering, no interlacing
+    header.extend([8, 6, 0, 0, 0]);
+    for (chunk_type, data) in [
+        (b"IHDR", &header[..]),
+        (b"IDAT", &compressed[..]),
+        (b"IEND", &[][..]),
+    ] {
+        png.extend((data.len() as u32).to_be_bytes());
+        png.extend(chunk_type);
+        png.extend(data);
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(chunk_type);
+        hasher.update(data);
+        png.extend(hasher.finalize().to_be_bytes());
+    }
+    Ok(png)
+}
+
+/// The content of a PMTiles archive, with the tiles sorted by identifier.
+struct Archive {
+    tiles: Vec<(u64, Vec<u8>)>,
+    zooms: RangeInclusive<u8>,
+    min: Coord,
+    max: Coord,
+}
+
+/// An entry of a PMTiles directory, pointing to a tile or to a leaf directory if `run_length` is
+/// zero.
+struct Entry {
+    tile_id: u64,
+    offset: u64,
+    length: u64,
+    run_length: u64,
+}
+
+impl Archive {
+    fn to_bytes(&self) -> TiffResult<Vec<u8>> {
+        let mut tile_data: Vec<u8> = Vec::new();
+        let mut entries = Vec::with_capacity(self.tiles.len());
+        for (tile_id, tile) in &self.tiles {
+            entries.push(Entry {
+                tile_id: *tile_id,
+                offset: tile_data.len() as u64,
+                length: tile.len() as u64,
+                run_length: 1,
+            });
+            tile_data.extend(tile);
+        }
+
+        let mut root = encode_directory(&entries);
+        let mut leaves = Vec::new();
+        if HEADER_LENGTH + root.len() > ROOT_LENGTH {
+            let mut root_entries = Vec::new();
+            for chunk in entries.chunks(LEAF_ENTRIES) {
+                let leaf = encode_directory(chunk);
+                root_entries.push(Entry {
+                    tile_id: chunk[0].tile_id,
+                    offset: leaves.len() as u64,
+                    length: leaf.len() as u64,
+                    run_length: 0,
+                });
+                leaves.extend(leaf);
+            }
+            root = encode_directory(&root_entries);
+        }
+        let metadata = br#"{"format":"png"}"#;
+
+        let root_offset = HEADER_LENGTH as u64;
+        let metadata_offset = root_offset + root.len() as u64;
+        let leaves_offset = metadata_offset + metadata.len() as u64;
+        let tile_data_offset = leaves_offset + leaves.len() as u64;
+        let e7 = |degrees: f64| ((degrees * 1e7).round() as i32).to_le_bytes();
+
+        let mut bytes = Vec::with_capacity(tile_data_offset as usize + tile_data.len());
+        bytes.extend(b"PMTiles");
+        bytes.push(3);
+        for value in [
+            root_offset,
+            root.len() as u64,
+            metadata_offset,
+            metadata.len() as u64,
+            leaves_offset,
+            leaves.len() as u64,
+            tile_data_offset,
+            tile_data.len() as u64,
+            // The numbers of addressed tiles, of entries and of distinct tiles
+            entries.len() as u64,
+            entries.len() as u64,
+            entries.len() as u64,
+        ] {
+            bytes.extend(value.to_le_bytes());
+        }
+        // Clustered, without internal and tile compression, PNG tiles
+        bytes.extend([1, 1, 1, 2, *self.zooms.start(), *self.zooms.end()]);
+        bytes.extend(e7(self.min.x));
+        bytes.extend(e7(self.min.y));
+        bytes.extend(e7(self.max.x));
+        bytes.extend(e7(self.max.y));
+        bytes.push(*self.zooms.start());
+        bytes.extend(e7((self.min.x + self.max.x) / 2.0));
+        bytes.extend(e7((self.min.y + self.max.y) / 2.0));
+        debug_assert_eq!(bytes.len(), HEADER_LENGTH);
+
+        bytes.extend(root);
+        bytes.extend(metadata);
+        bytes.extend(leaves);
+        bytes.extend(tile_data);
+        Ok(bytes)
+    }
+}
+
+/// Encodes the entries of a directory, column by column as varints.
+fn encode_directory(entries: &[Entry]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write_varint(&mut bytes, entries.len() as u64);
+    let mut last_id = 0;
+    for entry in entries {
+        write_varint(&mut bytes, entry.tile_id - last_id);
+        last_id = entry.tile_id;
+    }
+    for entry in entries {
+        write_varint(&mut bytes, entry.run_length);
+    }
+    for entry in entries {
+        write_varint(&mut bytes, entry.length);
+    }
+    for (i, entry) in entries.iter().enumerate() {
+        // Zero means that the entry directly follows the previous one
+        let follows = i > 0 && entry.offset == entries[i - 1].offset + entries[i - 1].length;
+        write_varint(&mut bytes, if follows { 0 } else { entry.offset + 1 });
+    }
+    bytes
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+
+use tiff::TiffResult;
+
+use crate::{GeoTiff, OpenOptions};
+
+type Opener = dyn Fn(&str) -> TiffResult<GeoTiff> + Send + Sync;
+
+/// A pool of datasets shared between threads, e.g. by the workers of a tile server, which opens
+/// at most a given number of instances of each dataset and lends them out.
+///
+/// Since a [GeoTiff] holds its decoded raster data, the bound on the instances bounds both the
+/// memory and the file handles used by concurrent requests. The instances are opened on demand
+/// and kept open for the next checkouts.
+pub struct DatasetPool {
+    max_per_dataset: usize,
+    open: Box<Opener>,
+    datasets: Mutex<HashMap<String, Instances>>,
+    returned: Condvar,
+}
+
+/// The instances of a dataset.
+#[derive(Default)]
+struct Instances {
+    idle: Vec<GeoTiff>,
+    opened: usize,
+}
+
+/// A dataset checked out of a [DatasetPool], which is returned to the pool when dropped.
+pub struct PooledDataset<'a> {
+    pool: &'a DatasetPool,
+    key: String,
+    dataset: Option<GeoTiff>,
+}
+
+impl DatasetPool {
+    /// Creates a pool opening at most `max_per_dataset` instances of each file, given by its
+    /// path, with the given options.
+    ///
+    /// Panics if `max_per_dataset` is 0.
+    pub fn new(max_per_dataset: usize, options: OpenOptions) -> Self {
+        Self::with_opener(max_per_dataset, move |path| options.open_path(path))
+    }
+
+    /// Creates a pool opening at most `max_per_dataset` instances of each dataset with the given
+    /// function, e.g. to fetch the datasets from URLs or from an object store.
+    ///
+    /// Panics if `max_per_dataset` is 0.
+    pub fn with_opener<F>(max_per_dataset: usize, open: F) -> Self
+    where
+        F: Fn(&str) -> TiffResult<GeoTiff> + Send + Sync + 'static,
+    {
+        assert!(
+            max_per_dataset > 0,
+            "a dataset pool must allow one instance per dataset"
+        );
+        Self {
+            max_per_dataset,
+            open: Box::new(open),
+            datasets: Mutex::new(HashMap::new()),
+            returned: Condvar::new(),
+        }
+    }
+
+    /// Checks out an instance of the dataset, waiting for one to be returned if all of them are
+    /// checked out. The dataset is opened if it has less than the maximum number of instances and
+    /// none is idle.
+    pub fn checkout(&self, key: &str) -> TiffResult<PooledDataset<'_>> {
+        let mut datasets = self.lock();
+        loop {
+            match self.take_or_reserve(&mut datasets, key) {
+                Some(Some(dataset)) => return Ok(self.lend(key, dataset)),
+                Some(None) => {
+                    drop(datasets);
+                    return self.open_reserved(key);
+                }
+                None => {
+                    datasets = self
+                        .returned
+                        .wait(datasets)
+                        .unwrap_or_else(PoisonError::into_inner);
+                }
+            }
+        }
+    }
+
+    /// Checks out an instance of the dataset like [DatasetPool::checkout], but returns `None`
+    /// instead of waiting if all of them are checked out.
+    pub fn try_checkout(&self, key: &str) -> TiffResult<Option<PooledDataset<'_>>> {
+        let mut datasets = self.lock();
+        match self.take_or_reserve(&mut datasets, key) {
+            Some(Some(dataset)) => Ok(Some(self.lend(key, dataset))),
+            Some(None) => {
+                drop(datasets);
+                self.open_reserved(key).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the number of open instances of the dataset, either idle or checked out.
+    pub fn opened(&self, key: &str) -> usize {
+        self.lock().get(key).map_or(0, |instances| instances.opened)
+    }
+
+    /// Closes the idle instances of all the datasets, e.g. to release memory after a burst of
+    /// requests.
+    pub fn close_idle(&self) {
+        let mut datasets = self.lock();
+        for instances in datasets.values_mut() {
+            instances.opened -= instances.idle.len();
+            instances.idle.clear();
+        }
+        datasets.retain(|_, instances| instances.opened > 0);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Instances>> {
+        self.datasets.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Takes an idle instance of the dataset, or else reserves the opening of a new one, for
+    /// which `Some(None)` is returned. Returns `None` if all the instances are checked out.
+    fn take_or_reserve(
+        &self,
+        datasets: &mut HashMap<String, Instances>,
+        key: &str,
+    ) -> Option<Option<GeoTiff>> {
+        let instances = datasets.entry(key.to_string()).or_default();
+        if let Some(dataset) = instances.idle.pop() {
+            Some(Some(dataset))
+        } else if instances.opened < self.max_per_dataset {
+            instances.opened += 1;
+            Some(None)
+        } else {
+            None
+        }
+    }
+
+    /// Opens a reserved instance, without holding the lock, and releases the reservation if the
+    /// opening fails.
+    fn open_reserved(&self, key: &str) -> TiffResult<PooledDataset<'_>> {
+        match (self.open)(key) {
+            Ok(dataset) => Ok(self.lend(key, dataset)),
+            Err(error) => {
+                if let Some(instances) = self.lock().get_mut(key) {
+                    instances.opened -= 1;
+                }
+                self.returned.notify_all();
+                Err(error)
+            }
+        }
+    }
+
+    fn lend(&self, key: &str, dataset: GeoTiff) -> PooledDataset<'_> {
+        PooledDataset {
+            pool: self,
+            key: key.to_string(),
+            dataset: Some(dataset),
+        }
+    }
+}
+
+impl Deref for PooledDataset<'_> {
+    type Target = GeoTiff;
+
+    fn deref(&self) -> &GeoTiff {
+        self.dataset.as_ref().unwrap()
+    }
+}
+
+impl Drop for PooledDataset<'_> {
+    fn drop(&mut self) {
+        let Some(dataset) = self.dataset.take() else {
+            return;
+        };
+        if let Some(instances) = self.pool.lock().get_mut(&self.key) {
+            instances.idle.push(dataset);
+        }
+        // The waiters may wait for other datasets
+        self.pool.returned.notify_all();
+    }
+}
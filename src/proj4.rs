@@ -0,0 +1,251 @@
+use proj4rs::Proj;
+
+use crate::{GeoKeyDirectory, MetadataError, MetadataResult};
+
+const USER_DEFINED: u16 = 32767;
+
+const MODEL_TYPE_PROJECTED: u16 = 1;
+const MODEL_TYPE_GEOGRAPHIC: u16 = 2;
+const MODEL_TYPE_GEOCENTRIC: u16 = 3;
+
+impl GeoKeyDirectory {
+    /// Builds a [proj4rs](https://crates.io/crates/proj4rs) projection from the geo keys.
+    ///
+    /// EPSG codes are resolved from the definitions bundled with proj4rs, user-defined CRS are
+    /// assembled from the individual projection and datum keys. Note that proj4rs expects
+    /// geographic coordinates in radians.
+    pub fn to_proj4rs(&self) -> MetadataResult<Proj> {
+        let model_type = self.model_type.or(if self.projected_type.is_some() {
+            Some(MODEL_TYPE_PROJECTED)
+        } else if self.geographic_type.is_some() {
+            Some(MODEL_TYPE_GEOGRAPHIC)
+        } else {
+            None
+        });
+
+        match model_type {
+            Some(MODEL_TYPE_PROJECTED) => match self.projected_type {
+                Some(code) if code != USER_DEFINED => proj_from_epsg_code(code),
+                _ => proj_from_string(&self.user_defined_projected_proj_string()?),
+            },
+            Some(MODEL_TYPE_GEOGRAPHIC) => match self.geographic_type {
+                Some(code) if code != USER_DEFINED => proj_from_epsg_code(code),
+                _ => proj_from_string(&format!("+proj=longlat {}", self.datum_proj_string()?)),
+            },
+            Some(MODEL_TYPE_GEOCENTRIC) => {
+                proj_from_string(&format!("+proj=geocent {}", self.datum_proj_string()?))
+            }
+            Some(model_type) => Err(MetadataError::Format(format!(
+                "Unsupported model type: {model_type}"
+            ))),
+            None => Err(MetadataError::Format("Model type is not specified".into())),
+        }
+    }
+
+    fn user_defined_projected_proj_string(&self) -> MetadataResult<String> {
+        let param = |name: &str, value: Option<f64>| -> Option<String> {
+            value.map(|value| format!(" +{name}={value}"))
+        };
+        let params = |params: &[(&str, Option<f64>)]| -> String {
+            params
+                .iter()
+                .filter_map(|(name, value)| param(name, *value))
+                .collect()
+        };
+
+        // Ref: https://docs.ogc.org/is/19-008r4/19-008r4.html#_map_projection_methods
+        let projection = match self.proj_coord_trans {
+            Some(1) => format!(
+                "+proj=tmerc{}",
+                params(&[
+                    ("lat_0", self.proj_nat_origin_lat),
+                    ("lon_0", self.proj_nat_origin_long),
+                    ("k_0", self.proj_scale_at_nat_origin),
+                    ("x_0", self.proj_false_easting),
+                    ("y_0", self.proj_false_northing),
+                ])
+            ),
+            Some(7) => format!(
+                "+proj=merc{}",
+                params(&[
+                    ("lon_0", self.proj_nat_origin_long),
+                    ("lat_ts", self.proj_std_parallel1),
+                    ("k_0", self.proj_scale_at_nat_origin),
+                    ("x_0", self.proj_false_easting),
+                    ("y_0", self.proj_false_northing),
+                ])
+            ),
+            Some(8) => format!(
+                "+proj=lcc{}",
+                params(&[
+                    ("lat_1", self.proj_std_parallel1),
+                    ("lat_2", self.proj_std_parallel2),
+                    ("lat_0", self.proj_false_origin_lat),
+                    ("lon_0", self.proj_false_origin_long),
+                    ("x_0", self.proj_false_origin_easting),
+                    ("y_0", self.proj_false_origin_northing),
+                ])
+            ),
+            Some(9) => format!(
+                "+proj=lcc{}",
+                params(&[
+                    ("lat_1", self.proj_nat_origin_lat),
+                    ("lat_0", self.proj_nat_origin_lat),
+                    ("lon_0", self.proj_nat_origin_long),
+                    ("k_0", self.proj_scale_at_nat_origin),
+                    ("x_0", self.proj_false_easting),
+                    ("y_0", self.proj_false_northing),
+                ])
+            ),
+            Some(10) => format!(
+                "+proj=laea{}",
+                params(&[
+                    ("lat_0", self.proj_center_lat),
+                    ("lon_0", self.proj_center_long),
+                    ("x_0", self.proj_false_easting),
+                    ("y_0", self.proj_false_northing),
+                ])
+            ),
+            Some(11) => format!(
+                "+proj=aea{}",
+                params(&[
+                    ("lat_1", self.proj_std_parallel1),
+                    ("lat_2", self.proj_std_parallel2),
+                    ("lat_0", self.proj_nat_origin_lat),
+                    ("lon_0", self.proj_nat_origin_long),
+                    ("x_0", self.proj_false_easting),
+                    ("y_0", self.proj_false_northing),
+                ])
+            ),
+            Some(14) => format!(
+                "+proj=stere{}",
+                params(&[
+                    ("lat_0", self.proj_center_lat),
+                    ("lon_0", self.proj_center_long),
+                    ("k_0", self.proj_scale_at_nat_origin),
+                    ("x_0", self.proj_false_easting),
+                    ("y_0", self.proj_false_northing),
+                ])
+            ),
+            Some(15) => format!(
+                "+proj=stere{}",
+                params(&[
+                    (
+                        "lat_0",
+                        self.proj_nat_origin_lat
+                            .map(|lat| if lat < 0.0 { -90.0 } else { 90.0 }),
+                    ),
+                    ("lat_ts", self.proj_nat_origin_lat),
+                    ("lon_0", self.proj_straight_vert_pole_long),
+                    ("k_0", self.proj_scale_at_nat_origin),
+                    ("x_0", self.proj_false_easting),
+                    ("y_0", self.proj_false_northing),
+                ])
+            ),
+            Some(16) => format!(
+                "+proj=sterea{}",
+                params(&[
+                    ("lat_0", self.proj_nat_origin_lat),
+                    ("lon_0", self.proj_nat_origin_long),
+                    ("k_0", self.proj_scale_at_nat_origin),
+                    ("x_0", self.proj_false_easting),
+                    ("y_0", self.proj_false_northing),
+                ])
+            ),
+            Some(17) => format!(
+                "+proj=eqc{}",
+                params(&[
+                    ("lat_ts", self.proj_std_parallel1),
+                    ("lat_0", self.proj_center_lat),
+                    ("lon_0", self.proj_center_long),
+                    ("x_0", self.proj_false_easting),
+                    ("y_0", self.proj_false_northing),
+                ])
+            ),
+            Some(coord_trans) => {
+                return Err(MetadataError::Format(format!(
+                    "Unsupported coordinate transformation: {coord_trans}"
+                )))
+            }
+            None => {
+                return Err(MetadataError::Format(
+                    "Coordinate transformation of user-defined projected CRS is not specified"
+                        .into(),
+                ))
+            }
+        };
+
+        let units = match self.proj_linear_units {
+            Some(9001) | None => String::from("+units=m"),
+            Some(9002) => String::from("+units=ft"),
+            Some(9003) => String::from("+units=us-ft"),
+            Some(units) => match self.proj_linear_unit_size {
+                Some(size) => format!("+to_meter={size}"),
+                None => {
+                    return Err(MetadataError::Format(format!(
+                        "Unsupported linear units: {units}"
+                    )))
+                }
+            },
+        };
+
+        Ok(format!(
+            "{projection} {} {units}",
+            self.datum_proj_string()?
+        ))
+    }
+
+    fn datum_proj_string(&self) -> MetadataResult<String> {
+        let datum = match (self.geographic_type, self.geog_geodetic_datum) {
+            (Some(4326), _) | (_, Some(6326)) => Some("+datum=WGS84"),
+            (Some(4269), _) | (_, Some(6269)) => Some("+datum=NAD83"),
+            // proj4rs cannot load the NAD27 shift grids, so only its ellipsoid is used
+            (Some(4267), _) | (_, Some(6267)) => Some("+ellps=clrk66"),
+            _ => None,
+        };
+
+        let mut proj_string = match datum {
+            Some(datum) => String::from(datum),
+            None => match self.geog_ellipsoid {
+                Some(7030) => String::from("+ellps=WGS84"),
+                Some(7019) => String::from("+ellps=GRS80"),
+                Some(7008) => String::from("+ellps=clrk66"),
+                Some(7004) => String::from("+ellps=bessel"),
+                Some(7022) => String::from("+ellps=intl"),
+                _ => match (
+                    self.geog_semi_major_axis,
+                    self.geog_semi_minor_axis,
+                    self.geog_inv_flattening,
+                ) {
+                    (Some(a), Some(b), _) => format!("+a={a} +b={b}"),
+                    (Some(a), None, Some(rf)) => format!("+a={a} +rf={rf}"),
+                    (Some(a), None, None) => format!("+a={a} +b={a}"),
+                    _ => {
+                        return Err(MetadataError::Format(
+                            "Unable to determine the ellipsoid of the geographic CRS".into(),
+                        ))
+                    }
+                },
+            },
+        };
+
+        if let Some(prime_meridian_long) = self.geog_prime_meridian_long {
+            proj_string.push_str(&format!(" +pm={prime_meridian_long}"));
+        }
+
+        Ok(proj_string)
+    }
+}
+
+pub(crate) fn proj_from_epsg_code(code: u16) -> MetadataResult<Proj> {
+    Proj::from_epsg_code(code)
+        .map_err(|e| MetadataError::Format(format!("Unable to resolve EPSG code {code}: {e}")))
+}
+
+fn proj_from_string(proj_string: &str) -> MetadataResult<Proj> {
+    Proj::from_proj_string(proj_string).map_err(|e| {
+        MetadataError::Format(format!(
+            "Unable to build projection from `{proj_string}`: {e}"
+        ))
+    })
+}
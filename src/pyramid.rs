@@ -0,0 +1,262 @@
+//! Downsampled levels of a band, e.g. to fill a tile store with several zoom levels.
+//!
+//! The nodata value of the levels is NaN.
+
+use tiff::{TiffError, TiffFormatError, TiffResult};
+
+use crate::simd;
+use crate::{Band, DocumentInfo, InMemoryRaster, Resampling, Window};
+
+/// Builds one level per factor, each pixel of a level covering `factor` x `factor` pixels of the
+/// band, or less along the right and bottom edges. The levels carry the georeferencing of the
+/// band adjusted to their grid.
+///
+/// Fails if a factor is zero.
+pub fn build_pyramid(
+    band: Band,
+    factors: &[usize],
+    resampling: Resampling,
+) -> TiffResult<Vec<InMemoryRaster>> {
+    if factors.contains(&0) {
+        return Err(TiffError::FormatError(TiffFormatError::Format(
+            "The pyramid factors must be positive".into(),
+        )));
+    }
+    let (width, height) = (band.width(), band.height());
+    let mut data = band.read_window(Window::full(width, height))?;
+    simd::nodata_to_nan(&mut data, band.nodata());
+
+    let image = band.image();
+    let level_data = downsample_levels(&data, width, height, factors, &resampling);
+    let mut levels = Vec::with_capacity(factors.len());
+    for (&factor, data) in factors.iter().zip(level_data) {
+        levels.push(InMemoryRaster {
+            width: width.div_ceil(factor),
+            height: height.div_ceil(factor),
+            data,
+            nodata: Some(f64::NAN),
+            geo_key_directory: image.geo_key_directory().cloned(),
+            transform_tags: image
+                .transform_tags()
+                .downsampled(factor, image.raster_offset()),
+            document_info: DocumentInfo::default(),
+            lineage: None,
+            exif: None,
+        });
+    }
+    Ok(levels)
+}
+
+/// Downsamples a raster of the given size, whose value at each index is given by `value` with NaN
+/// for nodata, by the factor.
+fn downsample(
+    value: &dyn Fn(usize) -> f64,
+    width: usize,
+    height: usize,
+    factor: usize,
+    resampling: &Resampling,
+) -> Vec<f64> {
+    let (level_width, level_height) = (width.div_ceil(factor), height.div_ceil(factor));
+    let mut level = Vec::with_capacity(level_width * level_height);
+    for y in 0..level_height {
+        for x in 0..level_width {
+            let (x0, y0) = (x * factor, y * factor);
+            let (x1, y1) = ((x0 + factor).min(width), (y0 + factor).min(height));
+            level.push(resampling.resample(
+                |column, row| value(row * width + column),
+                width,
+                height,
+                [x0 as f64, y0 as f64, (x1 - x0) as f64, (y1 - y0) as f64],
+            ));
+        }
+    }
+    level
+}
+
+/// Downsamples the data of a raster of the given size, with NaN for nodata, like [downsample]
+/// by each of the factors, in order, see [Levels].
+pub(crate) fn downsample_levels(
+    data: &[f64],
+    width: usize,
+    height: usize,
+    factors: &[usize],
+    resampling: &Resampling,
+) -> Vec<Vec<f64>> {
+    let mut levels = Levels::new(|index| data[index], width, height, factors, resampling);
+    levels.add_chunk(0, 0, width, height);
+    levels.finish()
+}
+
+/// The levels of a raster downsampled like [downsample] by each of the factors, in order,
+/// accumulated from the chunks of the raster while they are written, see
+/// [crate::WriteOptions::overview_resampling].
+///
+/// The averages are accumulated level by level: the sums and counts of the valid pixels of a
+/// level are combined from those of the last previous level whose factor divides its factor, or
+/// else from the pixels of the chunks, so that each pixel is read once rather than once per
+/// level when the factors are successive multiples, e.g. powers of 2. They only differ from
+/// [downsample] by rounding. The other resamplings combine pixels of neighboring chunks, so
+/// their levels are downsampled from the whole raster once it is complete.
+pub(crate) struct Levels<'a> {
+    /// The value of the pixel of the given index, with NaN for nodata.
+    value: Box<dyn Fn(usize) -> f64 + 'a>,
+    width: usize,
+    height: usize,
+    factors: Vec<usize>,
+    resampling: Resampling,
+    /// The levels of averages, empty for the other resamplings.
+    levels: Vec<Level>,
+}
+
+/// A level of averages of [Levels].
+enum Level {
+    /// Accumulated from the pixels of the chunks.
+    Pixels(Buckets),
+    /// Combined from the previous level of the given index once the others are complete.
+    Coarsened(usize),
+}
+
+impl<'a> Levels<'a> {
+    pub(crate) fn new(
+        value: impl Fn(usize) -> f64 + 'a,
+        width: usize,
+        height: usize,
+        factors: &[usize],
+        resampling: &Resampling,
+    ) -> Self {
+        let mut levels = Vec::new();
+        if *resampling == Resampling::Average {
+            for (index, &factor) in factors.iter().enumerate() {
+                let source = factors[..index]
+                    .iter()
+                    .rposition(|source| factor % source == 0);
+                levels.push(match source {
+                    Some(source) => Level::Coarsened(source),
+                    None => Level::Pixels(Buckets::new(factor, width, height)),
+                });
+            }
+        }
+        Self {
+            value: Box::new(value),
+            width,
+            height,
+            factors: factors.to_vec(),
+            resampling: resampling.clone(),
+            levels,
+        }
+    }
+
+    /// Accumulates the pixels of the chunk of the given origin and size, which must lie within
+    /// the raster. Each pixel must be accumulated once.
+    pub(crate) fn add_chunk(&mut self, x: usize, y: usize, width: usize, height: usize) {
+        let mut buckets = self
+            .levels
+            .iter_mut()
+            .filter_map(|level| match level {
+                Level::Pixels(buckets) => Some(buckets),
+                Level::Coarsened(_) => None,
+            })
+            .collect::<Vec<_>>();
+        if buckets.is_empty() {
+            return;
+        }
+        for row in y..y + height {
+            for column in x..x + width {
+                let value = (self.value)(row * self.width + column);
+                if value.is_nan() {
+                    continue;
+                }
+                for buckets in buckets.iter_mut() {
+                    let index = row / buckets.factor * buckets.width + column / buckets.factor;
+                    buckets.sums[index] += value;
+                    buckets.counts[index] += 1;
+                }
+            }
+        }
+    }
+
+    /// Returns the data of the levels, with NaN for nodata.
+    pub(crate) fn finish(self) -> Vec<Vec<f64>> {
+        if self.resampling != Resampling::Average {
+            return self
+                .factors
+                .iter()
+                .map(|&factor| {
+                    downsample(
+                        &self.value,
+                        self.width,
+                        self.height,
+                        factor,
+                        &self.resampling,
+                    )
+                })
+                .collect();
+        }
+        let mut levels: Vec<Buckets> = Vec::with_capacity(self.levels.len());
+        for (level, factor) in self.levels.into_iter().zip(self.factors) {
+            let level = match level {
+                Level::Pixels(buckets) => buckets,
+                Level::Coarsened(source) => {
+                    let source = &levels[source];
+                    source.coarsened(factor / source.factor)
+                }
+            };
+            levels.push(level);
+        }
+        levels.iter().map(Buckets::averages).collect()
+    }
+}
+
+/// The sums and counts of the valid pixels covered by the pixels of a level.
+struct Buckets {
+    factor: usize,
+    width: usize,
+    height: usize,
+    sums: Vec<f64>,
+    counts: Vec<usize>,
+}
+
+impl Buckets {
+    /// Returns empty buckets for the level of a raster of the given size whose pixels cover
+    /// `factor` x `factor` pixels of the raster.
+    fn new(factor: usize, width: usize, height: usize) -> Self {
+        let (width, height) = (width.div_ceil(factor), height.div_ceil(factor));
+        Self {
+            factor,
+            width,
+            height,
+            sums: vec![0.0; width * height],
+            counts: vec![0; width * height],
+        }
+    }
+
+    /// Returns the buckets of the level whose pixels cover `ratio` x `ratio` pixels of this one.
+    fn coarsened(&self, ratio: usize) -> Self {
+        let (width, height) = (self.width.div_ceil(ratio), self.height.div_ceil(ratio));
+        let mut sums = vec![0.0; width * height];
+        let mut counts = vec![0; width * height];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (source, target) = (y * self.width + x, y / ratio * width + x / ratio);
+                sums[target] += self.sums[source];
+                counts[target] += self.counts[source];
+            }
+        }
+        Self {
+            factor: self.factor * ratio,
+            width,
+            height,
+            sums,
+            counts,
+        }
+    }
+
+    /// Returns the averages of the buckets, with NaN for those without valid pixels.
+    fn averages(&self) -> Vec<f64> {
+        self.sums
+            .iter()
+            .zip(&self.counts)
+            .map(|(sum, &count)| sum / count as f64)
+            .collect()
+    }
+}
@@ -0,0 +1,126 @@
+use num_traits::{Bounded, NumCast};
+use tiff::{TiffError, TiffFormatError, TiffResult};
+
+use crate::{Band, GeoTiff, Image, Interleave, Orientation, ReadOptions, Window, WindowData};
+
+/// A condition on a range of bits of the values of a quality assessment (QA) band, e.g. the cloud
+/// flag of a Landsat QA_PIXEL band.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QaRule {
+    /// The index of the lowest bit of the range, from the least significant bit.
+    pub offset: u32,
+    /// The number of bits of the range.
+    pub length: u32,
+    /// The values of the bits, shifted to the lowest bits, for which a pixel is masked out.
+    pub values: Vec<u64>,
+}
+
+impl QaRule {
+    /// Masks out the pixels whose given bit is set, e.g. bit 3 for the clouds of Landsat
+    /// Collection 2.
+    pub fn flag(bit: u32) -> Self {
+        Self::values(bit, 1, &[1])
+    }
+
+    /// Masks out the pixels whose range of bits has one of the given values, e.g. 3 for the high
+    /// confidence of the two bits of the cloud confidence of Landsat Collection 2 at offset 8.
+    pub fn values(offset: u32, length: u32, values: &[u64]) -> Self {
+        Self {
+            offset,
+            length,
+            values: values.to_vec(),
+        }
+    }
+
+    /// Returns the bits of the range of the value, shifted to the lowest bits.
+    pub fn extract(&self, value: u64) -> u64 {
+        let mask = 1u64
+            .checked_shl(self.length)
+            .map_or(u64::MAX, |bit| bit - 1);
+        value.checked_shr(self.offset).unwrap_or(0) & mask
+    }
+
+    pub fn matches(&self, value: u64) -> bool {
+        self.values.contains(&self.extract(value))
+    }
+}
+
+/// The decoding of a bit-packed QA band into a mask, given by the rules masking out pixels.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QaSpec {
+    /// A pixel is masked out if any of the rules matches its QA value.
+    pub rules: Vec<QaRule>,
+}
+
+impl QaSpec {
+    pub fn is_masked(&self, value: u64) -> bool {
+        self.rules.iter().any(|rule| rule.matches(value))
+    }
+
+    /// Decodes the given window of the QA band into a mask with `true` for the valid pixels, in
+    /// row-major order, e.g. to write it with [crate::WriteMask::Explicit].
+    ///
+    /// The nodata pixels of the QA band and the pixels outside of it are masked out.
+    pub fn valid_mask(&self, qa: Band, window: Window) -> TiffResult<Vec<bool>> {
+        Ok(qa
+            .read_window(window)?
+            .into_iter()
+            .map(|value| !qa.is_nodata(value) && !self.is_masked(value as u64))
+            .collect())
+    }
+}
+
+impl Image {
+    /// Reads the pixels of the given window like [Image::read_window], with the pixels masked out
+    /// by the QA band filled like the pixels outside of the raster, e.g. with the nodata value.
+    ///
+    /// The QA band, which may belong to another file, must have the size of this image.
+    pub fn read_window_masked<T: NumCast + Bounded + Copy + 'static>(
+        &self,
+        window: Window,
+        options: &ReadOptions,
+        qa: Band,
+        spec: &QaSpec,
+    ) -> TiffResult<WindowData<T>> {
+        if (qa.width(), qa.height()) != (self.raster_width, self.raster_height) {
+            return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+                "The QA band of size {}x{} does not match the raster of size {}x{}",
+                qa.width(),
+                qa.height(),
+                self.raster_width,
+                self.raster_height
+            ))));
+        }
+        let unoriented = ReadOptions {
+            orientation: Orientation::default(),
+            ..options.clone()
+        };
+        let mut data = self.read_window::<T>(window, &unoriented)?;
+        let (_, fill) = self.resolve_window::<T>(Window::new(0, 0, 0, 0), options)?;
+        let valid = spec.valid_mask(qa, data.window)?;
+        let num_pixels = data.width * data.height;
+        for (pixel, _) in valid.iter().enumerate().filter(|(_, &valid)| !valid) {
+            for sample in 0..data.num_samples {
+                let index = match data.interleave {
+                    Interleave::Pixel => pixel * data.num_samples + sample,
+                    Interleave::Band => sample * num_pixels + pixel,
+                };
+                data.data[index] = fill;
+            }
+        }
+        Ok(data.with_orientation(options.orientation))
+    }
+}
+
+impl GeoTiff {
+    /// See [Image::read_window_masked].
+    pub fn read_window_masked<T: NumCast + Bounded + Copy + 'static>(
+        &self,
+        window: Window,
+        options: &ReadOptions,
+        qa: Band,
+        spec: &QaSpec,
+    ) -> TiffResult<WindowData<T>> {
+        self.primary().read_window_masked(window, options, qa, spec)
+    }
+}
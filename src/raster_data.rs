@@ -1,6 +1,28 @@
+use std::any::type_name;
 use std::fmt;
 use std::fmt::{Debug, Formatter};
+use std::mem::size_of_val;
 
+use num_traits::{Bounded, FromPrimitive, NumCast};
+use tiff::decoder::DecodingResult;
+
+use crate::Conversion;
+
+macro_rules! unwrap_primitive_type {
+    ($result: expr, $actual: ty, $expected: ty) => {
+        $result
+            .ok_or_else(|| {
+                format!(
+                    "Cannot represent {} as {}",
+                    type_name::<$actual>(),
+                    type_name::<$expected>()
+                )
+            })
+            .unwrap()
+    };
+}
+
+#[derive(Clone)]
 pub(super) enum RasterData {
     U8(Vec<u8>),
     U16(Vec<u16>),
@@ -36,6 +58,58 @@ impl Debug for RasterData {
 }
 
 impl RasterData {
+    /// Returns the value at the given index, converted to `T`.
+    pub(super) fn get<T: FromPrimitive + 'static>(&self, index: usize) -> T {
+        match self {
+            RasterData::U8(data) => unwrap_primitive_type!(T::from_u8(data[index]), u8, T),
+            RasterData::U16(data) => unwrap_primitive_type!(T::from_u16(data[index]), u16, T),
+            RasterData::U32(data) => unwrap_primitive_type!(T::from_u32(data[index]), u32, T),
+            RasterData::U64(data) => unwrap_primitive_type!(T::from_u64(data[index]), u64, T),
+            RasterData::F32(data) => unwrap_primitive_type!(T::from_f32(data[index]), f32, T),
+            RasterData::F64(data) => unwrap_primitive_type!(T::from_f64(data[index]), f64, T),
+            RasterData::I8(data) => unwrap_primitive_type!(T::from_i8(data[index]), i8, T),
+            RasterData::I16(data) => unwrap_primitive_type!(T::from_i16(data[index]), i16, T),
+            RasterData::I32(data) => unwrap_primitive_type!(T::from_i32(data[index]), i32, T),
+            RasterData::I64(data) => unwrap_primitive_type!(T::from_i64(data[index]), i64, T),
+        }
+    }
+
+    /// Returns the value at the given index, see [convert].
+    pub(super) fn get_converted<T: NumCast + Bounded + Copy>(
+        &self,
+        index: usize,
+        conversion: Conversion,
+    ) -> Option<T> {
+        match self {
+            RasterData::U8(data) => convert(data[index], conversion),
+            RasterData::U16(data) => convert(data[index], conversion),
+            RasterData::U32(data) => convert(data[index], conversion),
+            RasterData::U64(data) => convert(data[index], conversion),
+            RasterData::F32(data) => convert(data[index], conversion),
+            RasterData::F64(data) => convert(data[index], conversion),
+            RasterData::I8(data) => convert(data[index], conversion),
+            RasterData::I16(data) => convert(data[index], conversion),
+            RasterData::I32(data) => convert(data[index], conversion),
+            RasterData::I64(data) => convert(data[index], conversion),
+        }
+    }
+
+    /// Returns the number of bytes of the values.
+    pub(super) fn size_in_bytes(&self) -> usize {
+        match self {
+            RasterData::U8(data) => size_of_val(&data[..]),
+            RasterData::U16(data) => size_of_val(&data[..]),
+            RasterData::U32(data) => size_of_val(&data[..]),
+            RasterData::U64(data) => size_of_val(&data[..]),
+            RasterData::F32(data) => size_of_val(&data[..]),
+            RasterData::F64(data) => size_of_val(&data[..]),
+            RasterData::I8(data) => size_of_val(&data[..]),
+            RasterData::I16(data) => size_of_val(&data[..]),
+            RasterData::I32(data) => size_of_val(&data[..]),
+            RasterData::I64(data) => size_of_val(&data[..]),
+        }
+    }
+
     fn len(&self) -> usize {
         match self {
             RasterData::U8(data) => data.len(),
@@ -51,3 +125,52 @@ impl RasterData {
         }
     }
 }
+
+impl From<DecodingResult> for RasterData {
+    fn from(result: DecodingResult) -> Self {
+        match result {
+            DecodingResult::U8(data) => RasterData::U8(data),
+            DecodingResult::U16(data) => RasterData::U16(data),
+            DecodingResult::U32(data) => RasterData::U32(data),
+            DecodingResult::U64(data) => RasterData::U64(data),
+            DecodingResult::F32(data) => RasterData::F32(data),
+            DecodingResult::F64(data) => RasterData::F64(data),
+            DecodingResult::I8(data) => RasterData::I8(data),
+            DecodingResult::I16(data) => RasterData::I16(data),
+            DecodingResult::I32(data) => RasterData::I32(data),
+            DecodingResult::I64(data) => RasterData::I64(data),
+        }
+    }
+}
+
+/// Converts the value to `T` according to the conversion policy, or returns `None` if the
+/// conversion fails.
+pub(crate) fn convert<S, T>(value: S, conversion: Conversion) -> Option<T>
+where
+    S: NumCast + PartialEq + Copy,
+    T: NumCast + Bounded + Copy,
+{
+    let converted = T::from(value);
+    let source = value.to_f64()?;
+    match conversion {
+        Conversion::Lossy => converted,
+        Conversion::Exact => {
+            let converted = converted?;
+            let exact = if source.is_nan() {
+                converted.to_f64().is_some_and(f64::is_nan)
+            } else {
+                S::from(converted) == Some(value)
+            };
+            exact.then_some(converted)
+        }
+        Conversion::Saturate => converted.or_else(|| {
+            if source.is_nan() {
+                T::from(0)
+            } else if source < 0.0 {
+                Some(T::min_value())
+            } else {
+                Some(T::max_value())
+            }
+        }),
+    }
+}
@@ -0,0 +1,264 @@
+//! Elementwise operations between aligned bands, or between a band and a scalar.
+//!
+//! The result is computed block by block on the grid of the first band, see
+//! [crate::Image::optimal_read_windows], and either collected in memory or written as it is
+//! computed, see [apply_into]. A pixel is nodata in the result if it is nodata in any
+//! input, or outside the second band. The nodata value of the result is NaN.
+
+use std::io::{Seek, Write};
+
+use tiff::{TiffError, TiffFormatError, TiffResult};
+
+use crate::{Band, BlockMap, InMemoryRaster, Window, WriteOptions};
+
+/// An elementwise operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Add,
+    Sub,
+    Mul,
+    /// Division, whose result is nodata when dividing by zero.
+    Div,
+    Min,
+    Max,
+}
+
+impl Operation {
+    pub fn apply(&self, a: f64, b: f64) -> f64 {
+        match self {
+            Operation::Add => a + b,
+            Operation::Sub => a - b,
+            Operation::Mul => a * b,
+            Operation::Div if b == 0.0 => f64::NAN,
+            Operation::Div => a / b,
+            Operation::Min => a.min(b),
+            Operation::Max => a.max(b),
+        }
+    }
+}
+
+/// The second operand of an operation.
+#[derive(Debug, Clone, Copy)]
+pub enum Operand<'a> {
+    Band(Band<'a>),
+    Scalar(f64),
+}
+
+impl<'a> From<Band<'a>> for Operand<'a> {
+    fn from(band: Band<'a>) -> Self {
+        Operand::Band(band)
+    }
+}
+
+impl From<f64> for Operand<'_> {
+    fn from(value: f64) -> Self {
+        Operand::Scalar(value)
+    }
+}
+
+/// Applies the operation to each pixel of `a` and the corresponding value of `b`.
+///
+/// Fails if `b` is a band which is not aligned with `a`, see [crate::Image::is_aligned_with].
+/// Bands without georeferencing must have the same size.
+pub fn apply<'a>(
+    operation: Operation,
+    a: Band<'a>,
+    b: impl Into<Operand<'a>>,
+) -> TiffResult<InMemoryRaster> {
+    block_map(operation, a, b.into())?.collect()
+}
+
+/// Applies the operation like [apply], writing the result as a GeoTIFF of 64-bit floats block by
+/// block rather than holding it in memory, e.g. for rasters larger than the memory.
+///
+/// Fails if [WriteOptions::mask] or [WriteOptions::overviews] are set, since they depend on the
+/// whole result.
+pub fn apply_into<'a, W: Write + Seek>(
+    operation: Operation,
+    a: Band<'a>,
+    b: impl Into<Operand<'a>>,
+    writer: W,
+    options: &WriteOptions,
+) -> TiffResult<()> {
+    block_map(operation, a, b.into())?.write(writer, options)
+}
+
+/// Returns the result of the operation, computed block by block.
+fn block_map<'a>(
+    operation: Operation,
+    a: Band<'a>,
+    b: Operand<'a>,
+) -> TiffResult<BlockMap<'a, impl FnMut(Window) -> TiffResult<Vec<f64>> + 'a>> {
+    let offset = match b {
+        Operand::Band(b) => aligned_offset(a, b)?,
+        Operand::Scalar(_) => (0, 0),
+    };
+    Ok(BlockMap::new(a.image(), move |window: Window| {
+        let mut values = a.read_window(window)?;
+        let values_b = match b {
+            Operand::Band(b) => Some(b.read_window(Window {
+                x: window.x + offset.0,
+                y: window.y + offset.1,
+                ..window
+            })?),
+            Operand::Scalar(_) => None,
+        };
+
+        for (i, value) in values.iter_mut().enumerate() {
+            let value_b = match (&b, &values_b) {
+                (Operand::Band(b), Some(values_b)) if !b.is_nodata(values_b[i]) => values_b[i],
+                (Operand::Scalar(value), _) => *value,
+                _ => f64::NAN,
+            };
+            *value = if a.is_nodata(*value) || value_b.is_nan() {
+                f64::NAN
+            } else {
+                operation.apply(*value, value_b)
+            };
+        }
+        Ok(values)
+    }))
+}
+
+pub fn add<'a>(a: Band<'a>, b: impl Into<Operand<'a>>) -> TiffResult<InMemoryRaster> {
+    apply(Operation::Add, a, b)
+}
+
+pub fn sub<'a>(a: Band<'a>, b: impl Into<Operand<'a>>) -> TiffResult<InMemoryRaster> {
+    apply(Operation::Sub, a, b)
+}
+
+pub fn mul<'a>(a: Band<'a>, b: impl Into<Operand<'a>>) -> TiffResult<InMemoryRaster> {
+    apply(Operation::Mul, a, b)
+}
+
+pub fn div<'a>(a: Band<'a>, b: impl Into<Operand<'a>>) -> TiffResult<InMemoryRaster> {
+    apply(Operation::Div, a, b)
+}
+
+pub fn min<'a>(a: Band<'a>, b: impl Into<Operand<'a>>) -> TiffResult<InMemoryRaster> {
+    apply(Operation::Min, a, b)
+}
+
+pub fn max<'a>(a: Band<'a>, b: impl Into<Operand<'a>>) -> TiffResult<InMemoryRaster> {
+    apply(Operation::Max, a, b)
+}
+
+/// Options of [difference].
+#[derive(Debug, Clone)]
+pub struct DifferenceOptions {
+    /// The absolute difference above which a pixel is considered changed.
+    pub threshold: f64,
+}
+
+impl Default for DifferenceOptions {
+    fn default() -> Self {
+        Self { threshold: 0.0 }
+    }
+}
+
+/// The result of [difference].
+#[derive(Debug, Clone)]
+pub struct Difference {
+    /// The difference `b - a` on the grid of `a`.
+    pub raster: InMemoryRaster,
+    pub stats: DifferenceStats,
+}
+
+/// Summary of a difference raster, over the pixels which are valid in both inputs.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DifferenceStats {
+    pub valid_pixels: usize,
+    /// The number of pixels whose absolute difference is above the threshold.
+    pub changed_pixels: usize,
+    /// The number of changed pixels whose value increased.
+    pub increased_pixels: usize,
+    /// The number of changed pixels whose value decreased.
+    pub decreased_pixels: usize,
+    /// The area of the changed pixels in model space, or `None` without georeferencing.
+    pub changed_area: Option<f64>,
+    /// The smallest, largest and mean differences, or `None` if there are no valid pixels.
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+}
+
+/// Computes the difference `b - a` between two aligned bands, e.g. two acquisitions of the same
+/// area, along with statistics on the pixels changed by more than the threshold.
+pub fn difference(a: Band, b: Band, options: &DifferenceOptions) -> TiffResult<Difference> {
+    // Subtracting `b` from `a` keeps the grid of `a`, the result is then negated
+    let mut raster = apply(Operation::Sub, a, b)?;
+    raster.data.iter_mut().for_each(|value| *value = -*value);
+
+    let mut stats = DifferenceStats::default();
+    let mut sum = 0.0;
+    for value in raster.data.iter().filter(|value| !value.is_nan()) {
+        stats.valid_pixels += 1;
+        sum += value;
+        stats.min = Some(stats.min.map_or(*value, |min| min.min(*value)));
+        stats.max = Some(stats.max.map_or(*value, |max| max.max(*value)));
+        if value.abs() > options.threshold {
+            stats.changed_pixels += 1;
+            if *value > 0.0 {
+                stats.increased_pixels += 1;
+            } else {
+                stats.decreased_pixels += 1;
+            }
+        }
+    }
+    if stats.valid_pixels > 0 {
+        stats.mean = Some(sum / stats.valid_pixels as f64);
+    }
+    stats.changed_area = pixel_area(a).map(|area| area * stats.changed_pixels as f64);
+
+    Ok(Difference { raster, stats })
+}
+
+/// Returns the area of a pixel in model space, if the band is georeferenced.
+fn pixel_area(band: Band) -> Option<f64> {
+    let transform = band.image().coordinate_transform()?;
+    let model = |x, y| transform.transform_to_model(&geo_types::Coord { x, y });
+    let origin = model(0.0, 0.0);
+    let (edge_x, edge_y) = (model(1.0, 0.0) - origin, model(0.0, 1.0) - origin);
+    Some((edge_x.x * edge_y.y - edge_x.y * edge_y.x).abs())
+}
+
+/// Returns the position of the pixel (0, 0) of `a` in the raster space of `b`, which must be
+/// aligned with `a`.
+pub(crate) fn aligned_offset(a: Band, b: Band) -> TiffResult<(i64, i64)> {
+    let (image_a, image_b) = (a.image(), b.image());
+    let (Some(transform_a), Some(transform_b)) = (
+        image_a.coordinate_transform(),
+        image_b.coordinate_transform(),
+    ) else {
+        if (a.width(), a.height()) != (b.width(), b.height()) {
+            return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+                "Bands without georeferencing must have the same size, got {}x{} and {}x{}",
+                a.width(),
+                a.height(),
+                b.width(),
+                b.height()
+            ))));
+        }
+        return Ok((0, 0));
+    };
+
+    let report = image_a.is_aligned_with(image_b, 1e-6);
+    if !report.is_aligned() {
+        return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+            "Bands are not aligned: {:?}",
+            report.mismatches
+        ))));
+    }
+    let offset_a = image_a.raster_offset();
+    let offset_b = image_b.raster_offset();
+    let origin = transform_a.transform_to_model(&geo_types::Coord {
+        x: offset_a,
+        y: offset_a,
+    });
+    let origin = transform_b.transform_to_raster(&origin)?;
+    Ok((
+        (origin.x - offset_b).round() as i64,
+        (origin.y - offset_b).round() as i64,
+    ))
+}
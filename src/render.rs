@@ -0,0 +1,334 @@
+use tiff::tags::PhotometricInterpretation;
+use tiff::TiffResult;
+
+use crate::{GeoTiff, OutOfBounds, ReadOptions, Window, WindowData};
+
+/// An 8-bit RGBA image, e.g. a thumbnail of a GeoTIFF.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+    pub width: usize,
+    pub height: usize,
+    /// The data has a size of width * height * 4, in row-major order.
+    pub data: Vec<u8>,
+}
+
+impl RgbaImage {
+    /// Returns the RGBA values of the pixel at the given coordinates.
+    pub fn get_pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        let index = (y * self.width + x) * 4;
+        [
+            self.data[index],
+            self.data[index + 1],
+            self.data[index + 2],
+            self.data[index + 3],
+        ]
+    }
+}
+
+/// How to map the values of a band to the range of 8-bit colors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stretch {
+    /// Map the minimum and maximum values to black and white.
+    MinMax,
+    /// Map the given percentiles, between 0 and 100, to black and white.
+    PercentClip { low: f64, high: f64 },
+    /// Map the mean minus and plus the given number of standard deviations to black and white.
+    StdDev(f64),
+    /// Map the given values to black and white, e.g. a range computed over many files with
+    /// [crate::CollectionStats::stretch_range].
+    Range { low: f64, high: f64 },
+}
+
+impl Default for Stretch {
+    fn default() -> Self {
+        Stretch::PercentClip {
+            low: 2.0,
+            high: 98.0,
+        }
+    }
+}
+
+/// A color ramp for single-band images, given by colors at increasing positions.
+///
+/// The positions are either between 0 and 1 and refer to the stretched values, or data values
+/// for ramps created with [ColorRamp::from_values]. The colors are interpolated linearly between
+/// the stops.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorRamp {
+    stops: Vec<(f64, [u8; 3])>,
+    data_values: bool,
+}
+
+impl ColorRamp {
+    /// Creates a ramp from stops between 0 and 1, which are sorted by position.
+    ///
+    /// Panics if there are no stops.
+    pub fn new(stops: Vec<(f64, [u8; 3])>) -> Self {
+        Self::with_stops(stops, false)
+    }
+
+    /// Creates a colormap from breakpoints given as data values, which are sorted by value.
+    ///
+    /// The values are not stretched, so the colors do not depend on the rendered window.
+    ///
+    /// Panics if there are no breakpoints.
+    pub fn from_values(breakpoints: Vec<(f64, [u8; 3])>) -> Self {
+        Self::with_stops(breakpoints, true)
+    }
+
+    fn with_stops(mut stops: Vec<(f64, [u8; 3])>, data_values: bool) -> Self {
+        assert!(!stops.is_empty(), "a color ramp needs at least one stop");
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { stops, data_values }
+    }
+
+    /// The perceptually uniform viridis ramp, from dark blue to yellow.
+    pub fn viridis() -> Self {
+        Self::new(vec![
+            (0.0, [68, 1, 84]),
+            (0.125, [71, 45, 123]),
+            (0.25, [59, 82, 139]),
+            (0.375, [44, 114, 142]),
+            (0.5, [33, 145, 140]),
+            (0.625, [40, 174, 128]),
+            (0.75, [94, 201, 98]),
+            (0.875, [173, 220, 48]),
+            (1.0, [253, 231, 37]),
+        ])
+    }
+
+    /// A ramp for elevations, from blue through green and brown to white.
+    pub fn terrain() -> Self {
+        Self::new(vec![
+            (0.0, [51, 51, 153]),
+            (0.15, [0, 153, 255]),
+            (0.25, [0, 204, 102]),
+            (0.5, [255, 255, 153]),
+            (0.75, [128, 92, 84]),
+            (1.0, [255, 255, 255]),
+        ])
+    }
+
+    /// A ramp from black to white.
+    pub fn grayscale() -> Self {
+        Self::new(vec![(0.0, [0, 0, 0]), (1.0, [255, 255, 255])])
+    }
+
+    /// Returns whether the positions of the stops are data values.
+    pub fn has_data_values(&self) -> bool {
+        self.data_values
+    }
+
+    /// Returns the color at the given position, clamped to the range of the stops.
+    pub fn color_at(&self, position: f64) -> [u8; 3] {
+        let stops = &self.stops;
+        let next = stops.partition_point(|(stop, _)| *stop <= position);
+        if next == 0 {
+            return stops[0].1;
+        }
+        if next == stops.len() {
+            return stops[stops.len() - 1].1;
+        }
+        let (start, from) = stops[next - 1];
+        let (end, to) = stops[next];
+        let t = (position - start) / (end - start);
+        [0, 1, 2].map(|i| (from[i] as f64 + t * (to[i] as f64 - from[i] as f64)).round() as u8)
+    }
+}
+
+/// Options for rendering raster data to RGBA images.
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    pub stretch: Stretch,
+    /// Gamma correction applied to the stretched values, greater than 1 to brighten.
+    pub gamma: f64,
+    /// The color ramp of single-band images, which are rendered in grayscale without one. Nodata
+    /// pixels are transparent regardless of the ramp.
+    pub color_ramp: Option<ColorRamp>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            stretch: Stretch::default(),
+            gamma: 1.0,
+            color_ramp: None,
+        }
+    }
+}
+
+impl GeoTiff {
+    /// Renders the primary image to an RGBA image whose largest dimension is at most `max_dim`,
+    /// with the default [RenderOptions].
+    pub fn thumbnail(&self, max_dim: usize) -> TiffResult<RgbaImage> {
+        self.thumbnail_with_options(max_dim, &RenderOptions::default())
+    }
+
+    /// Renders the primary image to an RGBA image whose largest dimension is at most `max_dim`,
+    /// see [GeoTiff::render_window].
+    pub fn thumbnail_with_options(
+        &self,
+        max_dim: usize,
+        options: &RenderOptions,
+    ) -> TiffResult<RgbaImage> {
+        let primary = self.primary();
+        let scale =
+            (max_dim as f64 / primary.raster_width.max(primary.raster_height) as f64).min(1.0);
+        let out_width = ((primary.raster_width as f64 * scale).round() as usize).max(1);
+        let out_height = ((primary.raster_height as f64 * scale).round() as usize).max(1);
+        self.render_window(primary.window(), out_width, out_height, options)
+    }
+
+    /// Renders a window of the primary image to an RGBA image of the given size, e.g. a map tile.
+    ///
+    /// The data is read from the smallest overview with sufficient resolution, see
+    /// [GeoTiff::read_window_decimated]. RGB images are rendered in color, with the fourth sample
+    /// as alpha if any, while other images are rendered from their first sample. Each band is
+    /// stretched separately over the window, and nodata pixels as well as pixels outside the
+    /// raster are transparent.
+    pub fn render_window(
+        &self,
+        window: Window,
+        out_width: usize,
+        out_height: usize,
+        options: &RenderOptions,
+    ) -> TiffResult<RgbaImage> {
+        let primary = self.primary();
+        let read_options = ReadOptions {
+            out_of_bounds: OutOfBounds::Fill(f64::NAN),
+            ..Default::default()
+        };
+        let window =
+            self.read_window_decimated::<f64>(window, out_width, out_height, &read_options)?;
+        let is_rgb = primary.photometric_interpretation == Some(PhotometricInterpretation::RGB)
+            && window.num_samples >= 3;
+        Ok(render(&window, is_rgb, primary.nodata, options, None))
+    }
+}
+
+/// Renders the window, with each band stretched over the window unless its range is given by
+/// `ranges`.
+pub(crate) fn render(
+    window: &WindowData<f64>,
+    is_rgb: bool,
+    nodata: Option<f64>,
+    options: &RenderOptions,
+    ranges: Option<&[(f64, f64)]>,
+) -> RgbaImage {
+    let num_pixels = window.width * window.height;
+    let band = |sample: usize| -> Vec<f64> {
+        (0..num_pixels)
+            .map(|i| window.get(i % window.width, i / window.width, sample))
+            .collect()
+    };
+    let is_valid = |value: f64| !value.is_nan() && Some(value) != nodata;
+
+    let values = band(0);
+    let valid = values
+        .iter()
+        .map(|value| is_valid(*value))
+        .collect::<Vec<_>>();
+    let color_bands = if is_rgb { vec![0, 1, 2] } else { vec![0] };
+    let stretched = color_bands
+        .into_iter()
+        .map(|sample| {
+            let values = band(sample);
+            let range = match ranges {
+                Some(ranges) => ranges.get(sample).copied(),
+                None => stretch_range(&values, &valid, options.stretch),
+            };
+            let mut values = stretch_band(&values, range);
+            if options.gamma != 1.0 {
+                values
+                    .iter_mut()
+                    .for_each(|value| *value = value.powf(1.0 / options.gamma));
+            }
+            values
+        })
+        .collect::<Vec<_>>();
+    let alpha = (is_rgb && window.num_samples >= 4).then(|| band(3));
+
+    let mut data = Vec::with_capacity(num_pixels * 4);
+    for i in 0..num_pixels {
+        if !valid[i] {
+            data.extend([0; 4]);
+            continue;
+        }
+        let color = if is_rgb {
+            [0, 1, 2].map(|band| to_u8(stretched[band][i]))
+        } else {
+            match &options.color_ramp {
+                Some(ramp) if ramp.has_data_values() => ramp.color_at(values[i]),
+                Some(ramp) => ramp.color_at(stretched[0][i]),
+                None => [to_u8(stretched[0][i]); 3],
+            }
+        };
+        data.extend(color);
+        data.push(match &alpha {
+            Some(alpha) => alpha[i].clamp(0.0, 255.0) as u8,
+            None => 255,
+        });
+    }
+    RgbaImage {
+        width: window.width,
+        height: window.height,
+        data,
+    }
+}
+
+fn to_u8(value: f64) -> u8 {
+    (value * 255.0).clamp(0.0, 255.0).round() as u8
+}
+
+/// Returns the values mapped to black and white when stretching a band, computed from the
+/// valid values, or `None` if there are none.
+pub(crate) fn stretch_range(
+    values: &[f64],
+    valid: &[bool],
+    stretch: Stretch,
+) -> Option<(f64, f64)> {
+    let mut sorted = values
+        .iter()
+        .zip(valid)
+        .filter(|(_, valid)| **valid)
+        .map(|(value, _)| *value)
+        .collect::<Vec<_>>();
+    if sorted.is_empty() {
+        return None;
+    }
+    sorted.sort_by(f64::total_cmp);
+
+    let percentile = |percent: f64| {
+        let rank = (percent / 100.0 * (sorted.len() - 1) as f64).round() as usize;
+        sorted[rank.min(sorted.len() - 1)]
+    };
+    Some(match stretch {
+        Stretch::MinMax => (sorted[0], sorted[sorted.len() - 1]),
+        Stretch::PercentClip { low, high } => (percentile(low), percentile(high)),
+        Stretch::StdDev(factor) => {
+            let count = sorted.len() as f64;
+            let mean = sorted.iter().sum::<f64>() / count;
+            let variance = sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count;
+            let deviation = factor * variance.sqrt();
+            (mean - deviation, mean + deviation)
+        }
+        Stretch::Range { low, high } => (low, high),
+    })
+}
+
+/// Maps the values of a band to the range [0, 1], given the values mapped to 0 and 1.
+fn stretch_band(values: &[f64], range: Option<(f64, f64)>) -> Vec<f64> {
+    let Some((low, high)) = range else {
+        return vec![0.0; values.len()];
+    };
+    values
+        .iter()
+        .map(|value| {
+            if high > low {
+                ((value - low) / (high - low)).clamp(0.0, 1.0)
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,206 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// A separable interpolation kernel, e.g. Lanczos or a custom spline, see [Resampling::Kernel].
+pub trait ResamplingKernel: Debug + Send + Sync {
+    /// Returns the radius of the kernel in source pixels, beyond which its weights are zero.
+    fn radius(&self) -> usize;
+
+    /// Returns the weight of the source pixels at the given signed distance in pixels from the
+    /// interpolated point, along one axis.
+    fn weight(&self, distance: f64) -> f64;
+}
+
+/// How the values of several source pixels are combined into one pixel when downsampling.
+///
+/// Nodata values are excluded: a pixel is nodata only if all the source pixels it combines are.
+#[derive(Debug, Clone, Default)]
+pub enum Resampling {
+    /// The value of the source pixel at the center of the pixel.
+    #[default]
+    Nearest,
+    /// The mean of the valid source pixels, e.g. for continuous data like DEMs, weighted by the
+    /// fraction of their area covered by the pixel, so that the footprints of the pixels are
+    /// exact for non-integer factors, which avoids moiré patterns in thumbnails.
+    Average,
+    /// The most frequent value of the valid source pixels, e.g. for classification rasters. Ties
+    /// are resolved in favor of the smallest value.
+    Mode,
+    /// The bilinear interpolation of the 2 x 2 source pixels nearest to the center of the pixel.
+    Bilinear,
+    /// The cubic convolution of the 4 x 4 source pixels nearest to the center of the pixel, with
+    /// the kernel of Keys (a = -0.5), which may overshoot the source values.
+    Cubic,
+    /// The interpolation of the source pixels within the radius of the given kernel from the
+    /// center of the pixel, like [Resampling::Bilinear] and [Resampling::Cubic]. The weights are
+    /// normalized, so that the kernel need not sum to 1.
+    Kernel(Arc<dyn ResamplingKernel>),
+}
+
+/// Kernels are equal if they are the same instance.
+impl PartialEq for Resampling {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Resampling::Kernel(a), Resampling::Kernel(b)) => Arc::ptr_eq(a, b),
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl Eq for Resampling {}
+
+impl Resampling {
+    /// Returns the number of source pixels beyond an area which are combined when resampling it.
+    pub(crate) fn radius(&self) -> usize {
+        match self {
+            Resampling::Nearest | Resampling::Average | Resampling::Mode => 0,
+            Resampling::Bilinear => 1,
+            Resampling::Cubic => 2,
+            Resampling::Kernel(kernel) => kernel.radius(),
+        }
+    }
+
+    /// Combines the source pixels covered by the `[x, y, width, height]` area in raster space,
+    /// given by `get` for the columns and rows of a raster of the given size, with NaN for
+    /// nodata. Returns NaN if there are no valid source pixels.
+    pub(crate) fn resample(
+        &self,
+        get: impl Fn(usize, usize) -> f64,
+        raster_width: usize,
+        raster_height: usize,
+        area: [f64; 4],
+    ) -> f64 {
+        let [x, y, width, height] = area;
+        let (center_x, center_y) = (x + width / 2.0, y + height / 2.0);
+        match self {
+            Resampling::Nearest => {
+                let (column, row) = (center_x.floor(), center_y.floor());
+                if column >= 0.0
+                    && row >= 0.0
+                    && column < raster_width as f64
+                    && row < raster_height as f64
+                {
+                    get(column as usize, row as usize)
+                } else {
+                    f64::NAN
+                }
+            }
+            Resampling::Average | Resampling::Mode => {
+                let range = |start: f64, size: f64, limit: usize| {
+                    let end = (start + size).ceil().clamp(0.0, limit as f64) as usize;
+                    (start.floor().clamp(0.0, limit as f64) as usize)..end
+                };
+                // The fraction of the source pixel at the given index covered by the area
+                let coverage = |index: usize, start: f64, size: f64| {
+                    ((index + 1) as f64).min(start + size) - (index as f64).max(start)
+                };
+                let columns = range(x, width, raster_width);
+                let pixels = range(y, height, raster_height)
+                    .flat_map(|row| columns.clone().map(move |column| (column, row)))
+                    .map(|(column, row)| {
+                        let weight = coverage(column, x, width) * coverage(row, y, height);
+                        (get(column, row), weight)
+                    })
+                    .filter(|(value, _)| !value.is_nan());
+                if *self == Resampling::Average {
+                    let (sum, weights) = pixels
+                        .fold((0.0, 0.0), |(sum, weights), (value, weight)| {
+                            (sum + weight * value, weights + weight)
+                        });
+                    sum / weights
+                } else {
+                    mode(pixels.map(|(value, _)| value).collect())
+                }
+            }
+            Resampling::Bilinear => interpolate(
+                get,
+                raster_width,
+                raster_height,
+                center_x,
+                center_y,
+                1,
+                |t| 1.0 - t.abs(),
+            ),
+            Resampling::Cubic => interpolate(
+                get,
+                raster_width,
+                raster_height,
+                center_x,
+                center_y,
+                2,
+                cubic_kernel,
+            ),
+            Resampling::Kernel(kernel) => interpolate(
+                get,
+                raster_width,
+                raster_height,
+                center_x,
+                center_y,
+                kernel.radius() as i64,
+                |t| kernel.weight(t),
+            ),
+        }
+    }
+}
+
+fn mode(mut values: Vec<f64>) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let mut mode = f64::NAN;
+    let mut mode_count = 0;
+    for run in values.chunk_by(|a, b| a == b) {
+        if run.len() > mode_count {
+            mode = run[0];
+            mode_count = run.len();
+        }
+    }
+    mode
+}
+
+/// Interpolates the source pixels at the given raster coordinates with a separable kernel of
+/// the given radius in pixels. The edge pixels are repeated beyond the raster, and the weights
+/// of the nodata pixels are distributed over the valid ones.
+fn interpolate(
+    get: impl Fn(usize, usize) -> f64,
+    raster_width: usize,
+    raster_height: usize,
+    x: f64,
+    y: f64,
+    radius: i64,
+    kernel: impl Fn(f64) -> f64,
+) -> f64 {
+    if raster_width == 0 || raster_height == 0 || !x.is_finite() || !y.is_finite() {
+        return f64::NAN;
+    }
+    // The pixel centers are at the half-integer raster coordinates
+    let (x, y) = (x - 0.5, y - 0.5);
+    let (column, row) = (x.floor() as i64, y.floor() as i64);
+    let (mut sum, mut weights) = (0.0, 0.0);
+    for j in row + 1 - radius..=row + radius {
+        let weight_y = kernel(y - j as f64);
+        let source_row = j.clamp(0, raster_height as i64 - 1) as usize;
+        for i in column + 1 - radius..=column + radius {
+            let weight = weight_y * kernel(x - i as f64);
+            let value = get(i.clamp(0, raster_width as i64 - 1) as usize, source_row);
+            if weight != 0.0 && !value.is_nan() {
+                sum += weight * value;
+                weights += weight;
+            }
+        }
+    }
+    if weights == 0.0 {
+        f64::NAN
+    } else {
+        sum / weights
+    }
+}
+
+fn cubic_kernel(t: f64) -> f64 {
+    let t = t.abs();
+    if t < 1.0 {
+        (1.5 * t - 2.5) * t * t + 1.0
+    } else if t < 2.0 {
+        ((-0.5 * t + 2.5) * t - 4.0) * t + 2.0
+    } else {
+        0.0
+    }
+}
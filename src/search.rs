@@ -0,0 +1,170 @@
+use std::vec::IntoIter;
+
+use geo_types::Coord;
+use tiff::TiffResult;
+
+use crate::{Band, Window};
+
+/// The maximum number of pixels read at once.
+const BLOCK_PIXELS: usize = 1 << 20;
+
+/// A pixel found by [find_pixels].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelMatch {
+    /// The column of the pixel in the raster.
+    pub x: usize,
+    /// The row of the pixel in the raster.
+    pub y: usize,
+    pub value: f64,
+    /// The model coordinates of the center of the pixel, or `None` if the image is not
+    /// georeferenced.
+    pub model: Option<Coord>,
+}
+
+/// Returns the valid pixels of the band in the given window whose value matches the predicate,
+/// e.g. `|value| value > threshold`, in row-major order of the blocks of the image.
+///
+/// The pixels are read lazily, a few blocks at a time, so that the search can be limited with
+/// [Iterator::take] or stopped at the first match without reading the whole window. The
+/// iteration ends after the first read error.
+pub fn find_pixels<'a, F: FnMut(f64) -> bool>(
+    band: Band<'a>,
+    predicate: F,
+    window: Window,
+) -> PixelSearch<'a, F> {
+    let full = Window::full(band.width(), band.height());
+    let windows = match window.intersection(&full) {
+        Some(window) => band
+            .image()
+            .optimal_read_windows(window, BLOCK_PIXELS)
+            .into_iter()
+            .filter_map(|read_window| read_window.intersection(&window))
+            .collect(),
+        None => Vec::new(),
+    };
+    PixelSearch {
+        band,
+        predicate,
+        windows: windows.into_iter(),
+        block: None,
+    }
+}
+
+/// The iterator of [find_pixels].
+pub struct PixelSearch<'a, F> {
+    band: Band<'a>,
+    predicate: F,
+    windows: IntoIter<Window>,
+    /// The window being searched, its values and the index of the next one.
+    block: Option<(Window, Vec<f64>, usize)>,
+}
+
+impl<F: FnMut(f64) -> bool> Iterator for PixelSearch<'_, F> {
+    type Item = TiffResult<PixelMatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Some((window, values, next)) = &mut self.block else {
+                let window = self.windows.next()?;
+                match self.band.read_window(window) {
+                    Ok(values) => self.block = Some((window, values, 0)),
+                    Err(error) => {
+                        self.windows = Vec::new().into_iter();
+                        return Some(Err(error));
+                    }
+                }
+                continue;
+            };
+            let Some(&value) = values.get(*next) else {
+                self.block = None;
+                continue;
+            };
+            let index = *next;
+            *next += 1;
+            if self.band.is_nodata(value) || !(self.predicate)(value) {
+                continue;
+            }
+            let x = window.x as usize + index % window.width;
+            let y = window.y as usize + index / window.width;
+            let model = self.band.image().pixel_center(x, y);
+            return Some(Ok(PixelMatch { x, y, value, model }));
+        }
+    }
+}
+
+/// The valid pixel found by [Band::sample_nearest_valid].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NearestValid {
+    pub pixel: PixelMatch,
+    /// The distance from the location to the center of the pixel, in model units, or in pixels
+    /// if the image is not georeferenced.
+    pub distance: f64,
+}
+
+impl Band<'_> {
+    /// Returns the valid pixel nearest to the given location, in model coordinates like
+    /// [crate::Image::get_value_at], within `max_radius` pixels of it, e.g. to query a coastal
+    /// point against a raster masked over the sea. The location may be outside of the raster.
+    ///
+    /// The search spirals outward from the pixel of the location, reading squares of growing
+    /// size, and the pixels are compared by their distance in pixels. Returns `None` if the
+    /// location cannot be transformed to raster space or if there is no valid pixel within the
+    /// radius.
+    pub fn sample_nearest_valid(
+        &self,
+        coord: &Coord,
+        max_radius: usize,
+    ) -> TiffResult<Option<NearestValid>> {
+        let image = self.image();
+        let Some(target) = image.model_to_pixel(coord) else {
+            return Ok(None);
+        };
+        if !target.x.is_finite() || !target.y.is_finite() {
+            return Ok(None);
+        }
+        let (column, row) = (target.x.floor() as i64, target.y.floor() as i64);
+        let full = Window::full(self.width(), self.height());
+        let mut radius = max_radius.min(1);
+        loop {
+            // All the pixels within `radius` of the location are in the square of that radius
+            let square = Window::new(
+                column - radius as i64,
+                row - radius as i64,
+                2 * radius + 1,
+                2 * radius + 1,
+            );
+            let mut nearest: Option<(f64, usize, usize, f64)> = None;
+            if let Some(window) = square.intersection(&full) {
+                let values = self.read_window(window)?;
+                for (index, value) in values.into_iter().enumerate() {
+                    if self.is_nodata(value) {
+                        continue;
+                    }
+                    let x = window.x as usize + index % window.width;
+                    let y = window.y as usize + index / window.width;
+                    let distance = (x as f64 + 0.5 - target.x).hypot(y as f64 + 0.5 - target.y);
+                    if distance <= max_radius as f64
+                        && nearest.is_none_or(|(nearest, ..)| distance < nearest)
+                    {
+                        nearest = Some((distance, x, y, value));
+                    }
+                }
+            }
+            match nearest {
+                Some((distance, x, y, value))
+                    if distance <= radius as f64 || radius == max_radius =>
+                {
+                    let model = image.pixel_center(x, y);
+                    let distance = match model {
+                        Some(model) => (model.x - coord.x).hypot(model.y - coord.y),
+                        None => distance,
+                    };
+                    let pixel = PixelMatch { x, y, value, model };
+                    return Ok(Some(NearestValid { pixel, distance }));
+                }
+                _ if radius == max_radius => return Ok(None),
+                _ => radius = (2 * radius).min(max_radius),
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! The loops over many values which dominate tiling workloads.
+//!
+//! With the `simd` feature, the values are processed in chunks of [LANES] values loaded into
+//! fixed-size arrays, with one branchless loop over the lanes per operation, which the compiler
+//! maps to SIMD instructions, and the remaining values one by one. Without it, all the values are
+//! processed one by one. Both give the same results.
+
+use geo_types::Coord;
+
+/// The number of values of the chunks, enough for the 256-bit registers of AVX with `f32`.
+#[cfg(feature = "simd")]
+const LANES: usize = 8;
+
+/// Maps each coordinate to `output_origin + linear * (coord - input_origin)`, where `linear` is
+/// `[a, b, d, e]` with `x = a * i + b * j`, with the product computed in `f32`.
+pub(crate) fn apply_linear_f32(
+    coords: &mut [Coord],
+    linear: [f64; 4],
+    input_origin: Coord,
+    output_origin: Coord,
+) {
+    let [a, b, d, e] = linear.map(|term| term as f32);
+    #[cfg(feature = "simd")]
+    let coords = {
+        let mut chunks = coords.chunks_exact_mut(LANES);
+        for chunk in &mut chunks {
+            let chunk: &mut [Coord; LANES] = chunk.try_into().unwrap();
+            // The interleaved coordinates are split into one array per axis
+            let dx: [f32; LANES] =
+                std::array::from_fn(|lane| (chunk[lane].x - input_origin.x) as f32);
+            let dy: [f32; LANES] =
+                std::array::from_fn(|lane| (chunk[lane].y - input_origin.y) as f32);
+            let x: [f32; LANES] = std::array::from_fn(|lane| a * dx[lane] + b * dy[lane]);
+            let y: [f32; LANES] = std::array::from_fn(|lane| d * dx[lane] + e * dy[lane]);
+            for lane in 0..LANES {
+                chunk[lane].x = output_origin.x + f64::from(x[lane]);
+                chunk[lane].y = output_origin.y + f64::from(y[lane]);
+            }
+        }
+        chunks.into_remainder()
+    };
+    for coord in coords {
+        let dx = (coord.x - input_origin.x) as f32;
+        let dy = (coord.y - input_origin.y) as f32;
+        coord.x = output_origin.x + f64::from(a * dx + b * dy);
+        coord.y = output_origin.y + f64::from(d * dx + e * dy);
+    }
+}
+
+/// Replaces the nodata values by NaN.
+#[cfg(feature = "decode")]
+pub(crate) fn nodata_to_nan(values: &mut [f64], nodata: Option<f64>) {
+    // NaN values are already NaN, and a NaN nodata value matches no value
+    let Some(nodata) = nodata.filter(|nodata| !nodata.is_nan()) else {
+        return;
+    };
+    #[cfg(feature = "simd")]
+    let values = {
+        let nan = f64::NAN.to_bits();
+        let mut chunks = values.chunks_exact_mut(LANES);
+        for chunk in &mut chunks {
+            let chunk: &mut [f64; LANES] = chunk.try_into().unwrap();
+            // All ones for the nodata values, to select NaN with bitwise operations
+            let masks: [u64; LANES] =
+                std::array::from_fn(|lane| u64::from(chunk[lane] == nodata).wrapping_neg());
+            for lane in 0..LANES {
+                let bits = chunk[lane].to_bits() & !masks[lane] | nan & masks[lane];
+                chunk[lane] = f64::from_bits(bits);
+            }
+        }
+        chunks.into_remainder()
+    };
+    for value in values.iter_mut().filter(|value| **value == nodata) {
+        *value = f64::NAN;
+    }
+}
+
+/// Converts the values to `f32` into the output, which has as many values.
+#[cfg(feature = "decode")]
+pub(crate) fn u16_to_f32(values: &[u16], output: &mut [f32]) {
+    #[cfg(feature = "simd")]
+    let (values, output) = {
+        let mut chunks = values.chunks_exact(LANES);
+        let mut output_chunks = output.chunks_exact_mut(LANES);
+        for (chunk, output_chunk) in (&mut chunks).zip(&mut output_chunks) {
+            let chunk: &[u16; LANES] = chunk.try_into().unwrap();
+            let output_chunk: &mut [f32; LANES] = output_chunk.try_into().unwrap();
+            *output_chunk = chunk.map(f32::from);
+        }
+        (chunks.remainder(), output_chunks.into_remainder())
+    };
+    for (value, output) in values.iter().zip(output) {
+        *output = f32::from(*value);
+    }
+}
+
+/// Converts the values to `value * scale + offset` in `f32` into the output, which has as many
+/// values, with NaN for the nodata values.
+#[cfg(feature = "decode")]
+pub(crate) fn u16_to_f32_scaled(
+    values: &[u16],
+    output: &mut [f32],
+    scale: f32,
+    offset: f32,
+    nodata: Option<f64>,
+) {
+    // Out of the range of u16 without nodata value, so that no value matches
+    let nodata = nodata
+        .filter(|nodata| nodata.fract() == 0.0 && (0.0..=u16::MAX as f64).contains(nodata))
+        .map_or(u32::MAX, |nodata| nodata as u32);
+    #[cfg(feature = "simd")]
+    let (values, output) = {
+        let nan = f32::NAN.to_bits();
+        let mut chunks = values.chunks_exact(LANES);
+        let mut output_chunks = output.chunks_exact_mut(LANES);
+        for (chunk, output_chunk) in (&mut chunks).zip(&mut output_chunks) {
+            let chunk: &[u16; LANES] = chunk.try_into().unwrap();
+            let output_chunk: &mut [f32; LANES] = output_chunk.try_into().unwrap();
+            let scaled = chunk.map(|value| f32::from(value) * scale + offset);
+            // All ones for the nodata values, to select NaN with bitwise operations
+            let masks = chunk.map(|value| u32::from(u32::from(value) == nodata).wrapping_neg());
+            for lane in 0..LANES {
+                let bits = scaled[lane].to_bits() & !masks[lane] | nan & masks[lane];
+                output_chunk[lane] = f32::from_bits(bits);
+            }
+        }
+        (chunks.remainder(), output_chunks.into_remainder())
+    };
+    for (value, output) in values.iter().zip(output) {
+        *output = if u32::from(*value) == nodata {
+            f32::NAN
+        } else {
+            f32::from(*value) * scale + offset
+        };
+    }
+}
+
+/// Replaces the values by `value * scale + offset`, with NaN for the nodata values.
+#[cfg(feature = "decode")]
+pub(crate) fn scale_f32(values: &mut [f32], scale: f32, offset: f32, nodata: Option<f64>) {
+    // A NaN nodata value matches no value, and NaN values stay NaN
+    let nodata = nodata.map_or(f32::NAN, |nodata| nodata as f32);
+    #[cfg(feature = "simd")]
+    let values = {
+        let nan = f32::NAN.to_bits();
+        let mut chunks = values.chunks_exact_mut(LANES);
+        for chunk in &mut chunks {
+            let chunk: &mut [f32; LANES] = chunk.try_into().unwrap();
+            let masks = chunk.map(|value| u32::from(value == nodata).wrapping_neg());
+            let scaled = chunk.map(|value| value * scale + offset);
+            for lane in 0..LANES {
+                let bits = scaled[lane].to_bits() & !masks[lane] | nan & masks[lane];
+                chunk[lane] = f32::from_bits(bits);
+            }
+        }
+        chunks.into_remainder()
+    };
+    for value in values {
+        *value = if *value == nodata {
+            f32::NAN
+        } else {
+            *value * scale + offset
+        };
+    }
+}
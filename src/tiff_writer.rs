@@ -0,0 +1,801 @@
+//! A TIFF writer laying out the directories and the data of the images itself, which the encoder
+//! of the tiff crate cannot do, e.g. tiles or the directories ahead of the data for Cloud
+//! Optimized GeoTIFFs.
+
+use std::io::{Seek, SeekFrom, Write};
+use std::ops::Range;
+
+use flate2::write::ZlibEncoder;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use tiff::tags::Tag;
+use tiff::{TiffError, TiffFormatError, TiffResult};
+
+use crate::pyramid::Levels;
+use crate::{ByteOrder, Compression, IfdPlacement, Layout, WriteOptions};
+
+pub(crate) const SAMPLE_FORMAT_UINT: u16 = 1;
+pub(crate) const SAMPLE_FORMAT_IEEEFP: u16 = 3;
+
+/// The uncompressed size of strips, like GDAL.
+const STRIP_BYTES: usize = 8192;
+
+/// The number of chunks compressed per thread in each batch, which bounds the compressed chunks
+/// held in memory while keeping the threads busy.
+#[cfg(feature = "rayon")]
+const CHUNKS_PER_THREAD: usize = 4;
+
+/// The value of a tag, written with the field type of its variant.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TagValue {
+    Short(Vec<u16>),
+    Long(Vec<u32>),
+    Rational(Vec<(u32, u32)>),
+    Byte(Vec<u8>),
+    Double(Vec<f64>),
+    Ascii(String),
+    /// A value copied as is from a file with the byte order of the file being written.
+    Raw {
+        field_type: u16,
+        count: u32,
+        bytes: Vec<u8>,
+    },
+}
+
+impl TagValue {
+    fn field_type(&self) -> u16 {
+        match self {
+            TagValue::Byte(_) => 1,
+            TagValue::Ascii(_) => 2,
+            TagValue::Short(_) => 3,
+            TagValue::Long(_) => 4,
+            TagValue::Rational(_) => 5,
+            TagValue::Double(_) => 12,
+            TagValue::Raw { field_type, .. } => *field_type,
+        }
+    }
+
+    fn count(&self) -> usize {
+        match self {
+            TagValue::Short(values) => values.len(),
+            TagValue::Long(values) => values.len(),
+            TagValue::Rational(values) => values.len(),
+            TagValue::Byte(values) => values.len(),
+            TagValue::Double(values) => values.len(),
+            // With the terminating NUL
+            TagValue::Ascii(value) => value.len() + 1,
+            TagValue::Raw { count, .. } => *count as usize,
+        }
+    }
+
+    fn bytes(&self, order: ByteOrder) -> Vec<u8> {
+        match self {
+            TagValue::Short(values) => values.iter().flat_map(|&v| u16_bytes(v, order)).collect(),
+            TagValue::Long(values) => values.iter().flat_map(|&v| u32_bytes(v, order)).collect(),
+            TagValue::Rational(values) => values
+                .iter()
+                .flat_map(|&(numerator, denominator)| {
+                    [u32_bytes(numerator, order), u32_bytes(denominator, order)]
+                })
+                .flatten()
+                .collect(),
+            TagValue::Byte(values) => values.clone(),
+            TagValue::Double(values) => values
+                .iter()
+                .flat_map(|v| u64_bytes(v.to_bits(), order))
+                .collect(),
+            TagValue::Ascii(value) => value.bytes().chain([0]).collect(),
+            TagValue::Raw { bytes, .. } => bytes.clone(),
+        }
+    }
+}
+
+/// Returns the size in bytes of the values of a TIFF field type, or `None` if it is unknown.
+pub(crate) fn field_size(field_type: u16) -> Option<usize> {
+    match field_type {
+        1 | 2 | 6 | 7 => Some(1),
+        3 | 8 => Some(2),
+        4 | 9 | 11 | 13 => Some(4),
+        5 | 10 | 12 => Some(8),
+        _ => None,
+    }
+}
+
+/// Converts raw values of the field type between byte orders.
+pub(crate) fn swap_byte_order(field_type: u16, bytes: &[u8]) -> Vec<u8> {
+    // Rationals are pairs of longs
+    let size = match field_type {
+        5 | 10 => 4,
+        _ => field_size(field_type).unwrap_or(1),
+    };
+    let mut bytes = bytes.to_vec();
+    for value in bytes.chunks_exact_mut(size) {
+        value.reverse();
+    }
+    bytes
+}
+
+fn u16_bytes(value: u16, order: ByteOrder) -> [u8; 2] {
+    match order {
+        ByteOrder::LittleEndian => value.to_le_bytes(),
+        ByteOrder::BigEndian => value.to_be_bytes(),
+    }
+}
+
+fn u32_bytes(value: u32, order: ByteOrder) -> [u8; 4] {
+    match order {
+        ByteOrder::LittleEndian => value.to_le_bytes(),
+        ByteOrder::BigEndian => value.to_be_bytes(),
+    }
+}
+
+fn u64_bytes(value: u64, order: ByteOrder) -> [u8; 8] {
+    match order {
+        ByteOrder::LittleEndian => value.to_le_bytes(),
+        ByteOrder::BigEndian => value.to_be_bytes(),
+    }
+}
+
+impl From<u16> for TagValue {
+    fn from(value: u16) -> Self {
+        TagValue::Short(vec![value])
+    }
+}
+
+impl From<&[u16]> for TagValue {
+    fn from(values: &[u16]) -> Self {
+        TagValue::Short(values.to_vec())
+    }
+}
+
+impl From<u32> for TagValue {
+    fn from(value: u32) -> Self {
+        TagValue::Long(vec![value])
+    }
+}
+
+impl From<&[f64]> for TagValue {
+    fn from(values: &[f64]) -> Self {
+        TagValue::Double(values.to_vec())
+    }
+}
+
+impl From<&[u8]> for TagValue {
+    fn from(values: &[u8]) -> Self {
+        TagValue::Byte(values.to_vec())
+    }
+}
+
+impl From<&str> for TagValue {
+    fn from(value: &str) -> Self {
+        TagValue::Ascii(value.into())
+    }
+}
+
+/// An image file directory (IFD) with the chunks of its image, i.e. its strips or tiles.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Directory {
+    /// The tags in the order they were first set.
+    tags: Vec<(u16, TagValue)>,
+    chunks: Vec<Vec<u8>>,
+    /// The chunks compressed while the file is written, instead of the chunks above, see
+    /// [stream_tiff].
+    encoder: Option<ChunkEncoder>,
+    /// The tag receiving the offsets of the chunks once they are laid out.
+    offsets_tag: Option<u16>,
+    /// The IFDs without chunks pointed to by tags of this one, e.g. the Exif IFD, with the tags
+    /// receiving their offsets.
+    sub_directories: Vec<(u16, Directory)>,
+}
+
+impl Directory {
+    pub(crate) fn set(&mut self, tag: Tag, value: impl Into<TagValue>) {
+        self.set_u16(tag.to_u16(), value.into());
+    }
+
+    pub(crate) fn set_u16(&mut self, tag: u16, value: TagValue) {
+        match self.tags.iter_mut().find(|(t, _)| *t == tag) {
+            Some((_, existing)) => *existing = value,
+            None => self.tags.push((tag, value)),
+        }
+    }
+
+    pub(crate) fn get(&self, tag: Tag) -> Option<&TagValue> {
+        let tag = tag.to_u16();
+        self.tags
+            .iter()
+            .find(|(t, _)| *t == tag)
+            .map(|(_, value)| value)
+    }
+
+    pub(crate) fn remove(&mut self, tag: Tag) {
+        let tag = tag.to_u16();
+        self.tags.retain(|(t, _)| *t != tag);
+    }
+
+    /// Sets the chunks of the image, whose offsets are written to the given tag once they are
+    /// laid out. Their byte counts are left to the caller.
+    pub(crate) fn set_chunks(&mut self, offsets_tag: Tag, chunks: Vec<Vec<u8>>) {
+        self.set(offsets_tag, TagValue::Long(vec![0; chunks.len()]));
+        self.offsets_tag = Some(offsets_tag.to_u16());
+        self.chunks = chunks;
+    }
+
+    /// Sets an IFD written after this one, whose offset is written to the given tag once it is
+    /// laid out.
+    pub(crate) fn set_sub_directory(&mut self, tag: u16, directory: Directory) {
+        self.set_u16(tag, TagValue::Long(vec![0]));
+        match self.sub_directories.iter_mut().find(|(t, _)| *t == tag) {
+            Some((_, existing)) => *existing = directory,
+            None => self.sub_directories.push((tag, directory)),
+        }
+    }
+
+    /// Serializes the directory at the given offset, linked to the next one and followed by the
+    /// values which do not fit in its entries.
+    fn bytes(&self, offset: usize, next: usize, options: &WriteOptions) -> Vec<u8> {
+        let mut tags = self.tags.iter().collect::<Vec<_>>();
+        if options.sort_tags {
+            tags.sort_by_key(|(tag, _)| *tag);
+        }
+        let order = options.byte_order;
+        let mut entries = Vec::new();
+        entries.extend(u16_bytes(tags.len() as u16, order));
+        let values_offset = offset + 2 + 12 * tags.len() + 4;
+        let mut values = Vec::new();
+        for (tag, value) in tags {
+            entries.extend(u16_bytes(*tag, order));
+            entries.extend(u16_bytes(value.field_type(), order));
+            entries.extend(u32_bytes(value.count() as u32, order));
+            let mut bytes = value.bytes(order);
+            if bytes.len() <= 4 {
+                bytes.resize(4, 0);
+                entries.extend(bytes);
+            } else {
+                let start = (values_offset + values.len()).next_multiple_of(options.alignment);
+                values.resize(start - values_offset, 0);
+                entries.extend(u32_bytes(start as u32, order));
+                values.extend(bytes);
+            }
+        }
+        entries.extend(u32_bytes(next as u32, order));
+        entries.extend(values);
+        entries
+    }
+}
+
+/// The samples of an image interleaved by pixel, in rows padded to whole bytes.
+#[derive(Debug, Clone)]
+pub(crate) struct RasterBytes {
+    pub width: usize,
+    pub height: usize,
+    pub samples_per_pixel: usize,
+    pub bits_per_sample: usize,
+    pub sample_format: u16,
+    /// The samples in little-endian byte order.
+    pub data: Vec<u8>,
+}
+
+impl RasterBytes {
+    fn row_bytes(&self, width: usize) -> usize {
+        (width * self.samples_per_pixel * self.bits_per_sample).div_ceil(8)
+    }
+}
+
+/// Creates the directory of the image, organized and compressed as requested, with the tags
+/// describing its layout but not the meaning of its samples, e.g. PhotometricInterpretation.
+///
+/// The predictor applies to 8-bit integers and 64-bit floats only. The chunks are compressed
+/// when the directory is written with [stream_tiff].
+pub(crate) fn image_directory(
+    raster: RasterBytes,
+    options: &WriteOptions,
+) -> TiffResult<Directory> {
+    let compression = options.compression;
+    let predictor = match (
+        options.predictor,
+        raster.sample_format,
+        raster.bits_per_sample,
+    ) {
+        (true, SAMPLE_FORMAT_UINT, 8) => 2u16,
+        (true, SAMPLE_FORMAT_IEEEFP, 64) => 3,
+        _ => 1,
+    };
+    let spp = raster.samples_per_pixel;
+    let row_bytes = raster.row_bytes(raster.width);
+
+    let mut directory = Directory::default();
+    directory.set(Tag::ImageWidth, raster.width as u32);
+    directory.set(Tag::ImageLength, raster.height as u32);
+    directory.set(
+        Tag::BitsPerSample,
+        &vec![raster.bits_per_sample as u16; spp][..],
+    );
+    directory.set(Tag::Compression, compression.to_u16());
+    directory.set(Tag::SamplesPerPixel, spp as u16);
+    directory.set(Tag::XResolution, TagValue::Rational(vec![(1, 1)]));
+    directory.set(Tag::YResolution, TagValue::Rational(vec![(1, 1)]));
+    directory.set(Tag::PlanarConfiguration, 1u16);
+    directory.set(Tag::ResolutionUnit, 1u16);
+    if predictor != 1 {
+        directory.set(Tag::Predictor, predictor);
+    }
+    directory.set(Tag::SampleFormat, &vec![raster.sample_format; spp][..]);
+
+    // The chunks as (first row, first column, rows) of the raster
+    let (chunks, chunk_width, offsets_tag, byte_counts_tag) = match options.layout {
+        Layout::Strips => {
+            let rows = (STRIP_BYTES / row_bytes.max(1)).clamp(1, raster.height.max(1));
+            directory.set(Tag::RowsPerStrip, rows as u32);
+            let strips = (0..raster.height)
+                .step_by(rows)
+                .map(|y| (y, 0, rows.min(raster.height - y)))
+                .collect::<Vec<_>>();
+            (
+                strips,
+                raster.width,
+                Tag::StripOffsets,
+                Tag::StripByteCounts,
+            )
+        }
+        Layout::Tiles(size) => {
+            if size == 0 || size % 16 != 0 {
+                return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+                    "The tile size {} is not a positive multiple of 16",
+                    size
+                ))));
+            }
+            directory.set(Tag::TileWidth, size);
+            directory.set(Tag::TileLength, size);
+            let size = size as usize;
+            let tiles = (0..raster.height)
+                .step_by(size)
+                .flat_map(|y| (0..raster.width).step_by(size).map(move |x| (y, x, size)))
+                .collect::<Vec<_>>();
+            (tiles, size, Tag::TileOffsets, Tag::TileByteCounts)
+        }
+    };
+
+    // The offsets and byte counts are set once the chunks are compressed and laid out
+    directory.set(offsets_tag, TagValue::Long(vec![0; chunks.len()]));
+    directory.set(byte_counts_tag, TagValue::Long(vec![0; chunks.len()]));
+    directory.offsets_tag = Some(offsets_tag.to_u16());
+    directory.encoder = Some(ChunkEncoder {
+        raster,
+        chunks,
+        chunk_width,
+        first_row: 0,
+        predictor,
+        compression,
+        byte_order: options.byte_order,
+        byte_counts_tag: byte_counts_tag.to_u16(),
+    });
+    Ok(directory)
+}
+
+/// The chunks of an image, compressed one by one while the file is written.
+#[derive(Debug, Clone)]
+pub(crate) struct ChunkEncoder {
+    raster: RasterBytes,
+    /// The chunks as (first row, first column, rows) of the raster.
+    chunks: Vec<(usize, usize, usize)>,
+    chunk_width: usize,
+    /// The row of the raster of the first row of its data, which holds the rows of the chunks
+    /// being compressed only for the rasters computed while they are written, see [RowSource].
+    first_row: usize,
+    predictor: u16,
+    compression: Compression,
+    byte_order: ByteOrder,
+    /// The tag receiving the byte counts of the chunks once they are compressed.
+    byte_counts_tag: u16,
+}
+
+impl ChunkEncoder {
+    /// Returns the compressed bytes of the chunk of the given index.
+    fn encode(&self, index: usize) -> TiffResult<Vec<u8>> {
+        let raster = &self.raster;
+        let (y, x, rows) = self.chunks[index];
+        let spp = raster.samples_per_pixel;
+        let row_bytes = raster.row_bytes(raster.width);
+        let chunk_row_bytes = raster.row_bytes(self.chunk_width);
+        let start = raster.row_bytes(x);
+        let copied = chunk_row_bytes.min(row_bytes - start);
+        let mut chunk = vec![0u8; chunk_row_bytes * rows];
+        for (row, chunk_row) in chunk.chunks_exact_mut(chunk_row_bytes).enumerate() {
+            if y + row < raster.height {
+                let offset = (y + row - self.first_row) * row_bytes + start;
+                chunk_row[..copied].copy_from_slice(&raster.data[offset..offset + copied]);
+            }
+            // The floating point predictor does not depend on the byte order
+            match self.predictor {
+                2 => difference(chunk_row, spp),
+                3 => difference_floats(chunk_row, spp),
+                _ if self.byte_order == ByteOrder::BigEndian && raster.bits_per_sample > 8 => {
+                    for sample in chunk_row.chunks_exact_mut(raster.bits_per_sample / 8) {
+                        sample.reverse();
+                    }
+                }
+                _ => (),
+            }
+        }
+        compress(chunk, self.compression)
+    }
+}
+
+/// Applies the horizontal differencing predictor to a row of 8-bit samples.
+fn difference(row: &mut [u8], samples: usize) {
+    for i in (samples..row.len()).rev() {
+        row[i] = row[i].wrapping_sub(row[i - samples]);
+    }
+}
+
+/// Applies the floating point predictor to a row of 64-bit floats: their bytes are split into
+/// planes, from the most significant one, and then differenced.
+fn difference_floats(row: &mut [u8], samples: usize) {
+    let count = row.len() / 8;
+    let mut planes = vec![0u8; row.len()];
+    for (i, value) in row.chunks_exact(8).enumerate() {
+        for (plane, byte) in value.iter().rev().enumerate() {
+            planes[plane * count + i] = *byte;
+        }
+    }
+    difference(&mut planes, samples);
+    row.copy_from_slice(&planes);
+}
+
+fn compress(chunk: Vec<u8>, compression: Compression) -> TiffResult<Vec<u8>> {
+    Ok(match compression {
+        Compression::None => chunk,
+        Compression::Lzw => weezl::encode::Encoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8)
+            .encode(&chunk)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+        Compression::Deflate => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&chunk)?;
+            encoder.finish()?
+        }
+    })
+}
+
+/// The overviews of the first directory given to [stream_tiff], whose levels are accumulated from
+/// the chunks of the first directory while they are compressed.
+pub(crate) struct OverviewLevels<'a> {
+    /// The index of the directory of the first overview, followed by those of the others, which
+    /// are created by [image_directory] without samples.
+    pub first: usize,
+    /// The levels of each band of the first directory.
+    pub bands: Vec<Levels<'a>>,
+    /// Returns the samples of the directory of each overview, see [RasterBytes::data], from the
+    /// data of the levels of each band.
+    #[allow(clippy::type_complexity)]
+    pub samples: Box<dyn FnOnce(Vec<Vec<Vec<f64>>>) -> Vec<Vec<u8>> + 'a>,
+}
+
+impl OverviewLevels<'_> {
+    /// Accumulates the chunks of the given indices of the encoder.
+    fn add_chunks(&mut self, encoder: &ChunkEncoder, chunks: Range<usize>) {
+        let raster = &encoder.raster;
+        for &(y, x, rows) in &encoder.chunks[chunks] {
+            let width = encoder.chunk_width.min(raster.width - x);
+            let height = rows.min(raster.height - y);
+            for levels in &mut self.bands {
+                levels.add_chunk(x, y, width, height);
+            }
+        }
+    }
+
+    /// Returns the samples of the directories of the overviews, once all the chunks are
+    /// accumulated.
+    fn finish(self) -> Vec<Vec<u8>> {
+        (self.samples)(self.bands.into_iter().map(Levels::finish).collect())
+    }
+}
+
+/// Returns the samples of the given rows of the first directory given to [stream_tiff], see
+/// [RasterBytes::data], for rasters computed while they are written rather than held in memory.
+pub(crate) type RowSource<'a> = dyn FnMut(Range<usize>) -> TiffResult<Vec<u8>> + 'a;
+
+/// Lays out the sub-IFDs of the directory from the end of the file, each followed by its own
+/// sub-IFDs, adding their bytes to the pieces of the file, and returns the tags pointing to them
+/// with their offsets.
+fn layout_sub_directories(
+    directory: &Directory,
+    end: &mut usize,
+    pieces: &mut Vec<(usize, Vec<u8>)>,
+    options: &WriteOptions,
+) -> Vec<(u16, usize)> {
+    let mut offsets = Vec::new();
+    for (tag, sub_directory) in &directory.sub_directories {
+        *end = end.next_multiple_of(options.alignment);
+        let offset = *end;
+        *end += sub_directory.bytes(offset, 0, options).len();
+        let sub_offsets = layout_sub_directories(sub_directory, end, pieces, options);
+        let mut sub_directory = sub_directory.clone();
+        for (sub_tag, sub_offset) in sub_offsets {
+            sub_directory.set_u16(sub_tag, TagValue::Long(vec![sub_offset as u32]));
+        }
+        pieces.push((offset, sub_directory.bytes(offset, 0, options)));
+        offsets.push((*tag, offset));
+    }
+    offsets
+}
+
+/// A piece of the layout of a file, for the directory of the given index.
+#[derive(Debug, Clone, Copy)]
+enum Piece {
+    Directory(usize),
+    Chunks(usize),
+}
+
+/// Returns the pieces of the given number of directories in the order of the file, as requested
+/// by the IFD placement.
+fn layout_order(count: usize, ifd_placement: IfdPlacement) -> Vec<Piece> {
+    match ifd_placement {
+        IfdPlacement::AfterData => (0..count)
+            .flat_map(|index| [Piece::Chunks(index), Piece::Directory(index)])
+            .collect(),
+        IfdPlacement::BeforeData => (0..count)
+            .flat_map(|index| [Piece::Directory(index), Piece::Chunks(index)])
+            .collect(),
+        IfdPlacement::Start => (0..count)
+            .map(Piece::Directory)
+            .chain((0..count).rev().map(Piece::Chunks))
+            .collect(),
+    }
+}
+
+/// Writes the bytes at the given offset, padded with zeros from the current position, and moves
+/// the position past them. Fails if the file exceeds the 4 GiB of a classic TIFF.
+fn write_piece<W: Write>(
+    writer: &mut W,
+    position: &mut usize,
+    offset: usize,
+    bytes: &[u8],
+) -> TiffResult<()> {
+    let end = offset + bytes.len();
+    if end > u32::MAX as usize {
+        return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+            "The file of at least {} bytes exceeds the 4 GiB of a classic TIFF",
+            end
+        ))));
+    }
+    writer.write_all(&vec![0; offset - *position])?;
+    writer.write_all(bytes)?;
+    *position = end;
+    Ok(())
+}
+
+fn write_header<W: Write>(writer: &mut W, first: usize, order: ByteOrder) -> TiffResult<()> {
+    writer.write_all(match order {
+        ByteOrder::LittleEndian => b"II",
+        ByteOrder::BigEndian => b"MM",
+    })?;
+    writer.write_all(&u16_bytes(42, order))?;
+    writer.write_all(&u32_bytes(first as u32, order))?;
+    Ok(())
+}
+
+/// Writes a classic TIFF of the directories, in order, laid out as requested by the IFD
+/// placement, tag order, alignment and byte order of the options.
+///
+/// The chunks of the directories must be compressed already, e.g. copied from a file, since
+/// they are laid out before writing anything. See [stream_tiff] for those of [image_directory].
+pub(crate) fn write_tiff<W: Write>(
+    mut writer: W,
+    mut directories: Vec<Directory>,
+    options: &WriteOptions,
+) -> TiffResult<()> {
+    if options.alignment == 0 {
+        return Err(TiffError::FormatError(TiffFormatError::Format(
+            "The alignment must be positive".into(),
+        )));
+    }
+    let align = |offset: usize| offset.next_multiple_of(options.alignment);
+
+    // The offsets of the directories and of their chunks. The size of a directory depends on
+    // the alignment of its values, but not on the offsets of the chunks
+    let mut directory_offsets = vec![0; directories.len()];
+    let mut chunk_offsets = vec![Vec::new(); directories.len()];
+    let mut sub_directory_offsets = vec![Vec::new(); directories.len()];
+    let mut sub_directory_pieces = Vec::new();
+    let mut end = 8;
+    let mut place_directory = |index: usize, end: &mut usize| {
+        *end = align(*end);
+        directory_offsets[index] = *end;
+        *end += directories[index].bytes(*end, 0, options).len();
+        sub_directory_offsets[index] =
+            layout_sub_directories(&directories[index], end, &mut sub_directory_pieces, options);
+    };
+    let mut place_chunks = |index: usize, end: &mut usize| {
+        for chunk in &directories[index].chunks {
+            *end = align(*end);
+            chunk_offsets[index].push(*end);
+            *end += chunk.len();
+        }
+    };
+    for piece in layout_order(directories.len(), options.ifd_placement) {
+        match piece {
+            Piece::Directory(index) => place_directory(index, &mut end),
+            Piece::Chunks(index) => place_chunks(index, &mut end),
+        }
+    }
+    if end > u32::MAX as usize {
+        return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+            "The file of {} bytes exceeds the 4 GiB of a classic TIFF",
+            end
+        ))));
+    }
+    for (directory, offsets) in directories.iter_mut().zip(&chunk_offsets) {
+        if let Some(tag) = directory.offsets_tag {
+            let offsets = offsets.iter().map(|&offset| offset as u32).collect();
+            directory.set_u16(tag, TagValue::Long(offsets));
+        }
+    }
+    for (directory, offsets) in directories.iter_mut().zip(sub_directory_offsets) {
+        for (tag, offset) in offsets {
+            directory.set_u16(tag, TagValue::Long(vec![offset as u32]));
+        }
+    }
+
+    // The pieces of the file in the order of their offsets
+    let mut pieces = sub_directory_pieces;
+    for (index, directory) in directories.iter().enumerate() {
+        let offset = directory_offsets[index];
+        let next = directory_offsets.get(index + 1).copied().unwrap_or(0);
+        pieces.push((offset, directory.bytes(offset, next, options)));
+    }
+    for (directory, offsets) in directories.iter_mut().zip(&chunk_offsets) {
+        pieces.extend(offsets.iter().copied().zip(directory.chunks.drain(..)));
+    }
+    pieces.sort_by_key(|(offset, _)| *offset);
+
+    let first = directory_offsets.first().copied().unwrap_or(0);
+    write_header(&mut writer, first, options.byte_order)?;
+    let mut position = 8;
+    for (offset, bytes) in pieces {
+        write_piece(&mut writer, &mut position, offset, &bytes)?;
+    }
+    Ok(())
+}
+
+/// Writes a classic TIFF of the directories like [write_tiff], compressing the chunks of the
+/// directories of [image_directory] while the file is written.
+///
+/// The chunks are compressed in batches, in parallel with the `rayon` feature, and written in
+/// order once each batch is done, so that only the chunks of one batch are held in memory. The
+/// space of each IFD is reserved in the layout, and the IFD is written there once the offsets
+/// and byte counts of the chunks are known.
+///
+/// The levels of the overviews, if any, are accumulated from the chunks of the first directory
+/// as each batch is written, and the samples of the overviews are given to their encoders once
+/// complete. With [IfdPlacement::Start], the chunks of the overviews come first, so the chunks
+/// of the first directory are accumulated in a pass of their own beforehand.
+///
+/// The samples of the first directory are given by `rows`, if any, for the rows of each batch
+/// before it is compressed. The first directory must not have overviews then.
+pub(crate) fn stream_tiff<W: Write + Seek>(
+    mut writer: W,
+    mut directories: Vec<Directory>,
+    mut overviews: Option<OverviewLevels>,
+    mut rows: Option<&mut RowSource>,
+    options: &WriteOptions,
+) -> TiffResult<()> {
+    if options.alignment == 0 {
+        return Err(TiffError::FormatError(TiffFormatError::Format(
+            "The alignment must be positive".into(),
+        )));
+    }
+    let align = |offset: usize| offset.next_multiple_of(options.alignment);
+    #[cfg(feature = "rayon")]
+    let batch_size = rayon::current_num_threads() * CHUNKS_PER_THREAD;
+    #[cfg(not(feature = "rayon"))]
+    let batch_size = 1;
+
+    // The offsets are relative to the start of the file
+    let start = writer.stream_position()?;
+    write_header(&mut writer, 0, options.byte_order)?;
+    let mut position = 8;
+    let mut directory_offsets = vec![0; directories.len()];
+    let mut sub_directory_offsets = vec![Vec::new(); directories.len()];
+    let first_overview = overviews
+        .as_ref()
+        .map_or(directories.len(), |overviews| overviews.first);
+    let mut overview_samples = Vec::new();
+    for piece in layout_order(directories.len(), options.ifd_placement) {
+        match piece {
+            Piece::Directory(index) => {
+                // The size of a directory does not depend on the offsets of the chunks
+                let offset = align(position);
+                let size = directories[index].bytes(offset, 0, options).len();
+                write_piece(&mut writer, &mut position, offset, &vec![0; size])?;
+                directory_offsets[index] = offset;
+                let mut pieces = Vec::new();
+                let mut end = position;
+                sub_directory_offsets[index] =
+                    layout_sub_directories(&directories[index], &mut end, &mut pieces, options);
+                pieces.sort_by_key(|(offset, _)| *offset);
+                for (offset, bytes) in pieces {
+                    write_piece(&mut writer, &mut position, offset, &bytes)?;
+                }
+            }
+            Piece::Chunks(index) => {
+                if let Some(mut levels) = overviews.take_if(|_| index >= first_overview) {
+                    // The chunks of the first directory are not written yet
+                    if let Some(encoder) = &directories[0].encoder {
+                        levels.add_chunks(encoder, 0..encoder.chunks.len());
+                    }
+                    overview_samples = levels.finish().into_iter().map(Some).collect();
+                }
+                let directory = &mut directories[index];
+                let (Some(mut encoder), Some(offsets_tag)) =
+                    (directory.encoder.take(), directory.offsets_tag)
+                else {
+                    continue;
+                };
+                if let Some(samples) = index
+                    .checked_sub(first_overview)
+                    .and_then(|level| overview_samples.get_mut(level)?.take())
+                {
+                    encoder.raster.data = samples;
+                }
+                let mut offsets = Vec::new();
+                let mut byte_counts = Vec::new();
+                let mut loaded = 0..0;
+                for batch in (0..encoder.chunks.len()).step_by(batch_size) {
+                    let batch = batch..(batch + batch_size).min(encoder.chunks.len());
+                    if let (0, Some(rows)) = (index, &mut rows) {
+                        // The chunks are in row-major order, and the rows of the tiles of a row
+                        // are kept for the next batches
+                        let (first, _, _) = encoder.chunks[batch.start];
+                        let (last, _, last_rows) = encoder.chunks[batch.end - 1];
+                        let end = (last + last_rows).min(encoder.raster.height);
+                        if first < loaded.start || end > loaded.end {
+                            encoder.raster.data = rows(first..end)?;
+                            encoder.first_row = first;
+                            loaded = first..end;
+                        }
+                    }
+                    #[cfg(feature = "rayon")]
+                    let compressed = batch
+                        .clone()
+                        .into_par_iter()
+                        .map(|chunk| encoder.encode(chunk))
+                        .collect::<TiffResult<Vec<_>>>()?;
+                    #[cfg(not(feature = "rayon"))]
+                    let compressed = batch
+                        .clone()
+                        .map(|chunk| encoder.encode(chunk))
+                        .collect::<TiffResult<Vec<_>>>()?;
+                    for chunk in compressed {
+                        let offset = align(position);
+                        write_piece(&mut writer, &mut position, offset, &chunk)?;
+                        offsets.push(offset as u32);
+                        byte_counts.push(chunk.len() as u32);
+                    }
+                    if let (0, Some(levels)) = (index, &mut overviews) {
+                        levels.add_chunks(&encoder, batch);
+                    }
+                }
+                directory.set_u16(offsets_tag, TagValue::Long(offsets));
+                directory.set_u16(encoder.byte_counts_tag, TagValue::Long(byte_counts));
+            }
+        }
+    }
+
+    for (directory, offsets) in directories.iter_mut().zip(sub_directory_offsets) {
+        for (tag, offset) in offsets {
+            directory.set_u16(tag, TagValue::Long(vec![offset as u32]));
+        }
+    }
+    for (index, directory) in directories.iter().enumerate() {
+        let offset = directory_offsets[index];
+        let next = directory_offsets.get(index + 1).copied().unwrap_or(0);
+        writer.seek(SeekFrom::Start(start + offset as u64))?;
+        writer.write_all(&directory.bytes(offset, next, options))?;
+    }
+    writer.seek(SeekFrom::Start(start))?;
+    let first = directory_offsets.first().copied().unwrap_or(0);
+    write_header(&mut writer, first, options.byte_order)?;
+    writer.seek(SeekFrom::Start(start + position as u64))?;
+    Ok(())
+}
@@ -0,0 +1,239 @@
+use std::fmt;
+
+use geo_types::Coord;
+use tiff::{TiffError, TiffFormatError, TiffResult};
+
+use crate::{GdalMetadata, GeoTiff, Image};
+
+/// The names of the band items of [GdalMetadata] holding the date and time of the band.
+const DATE_TIME_ITEMS: [&str; 4] = [
+    "DateTime",
+    "DATETIME",
+    "ACQUISITIONDATETIME",
+    "ACQUISITION_DATETIME",
+];
+/// The band item holding the time coordinate of bands converted from NetCDF by GDAL, whose
+/// units are given by the dataset item `time#units`, e.g. `days since 1970-01-01`.
+const NETCDF_TIME_ITEM: &str = "NETCDF_DIM_time";
+const NETCDF_TIME_UNITS_ITEM: &str = "time#units";
+
+/// A date and time in UTC, without time zone handling.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct DateTime {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: f64,
+}
+
+impl DateTime {
+    /// Parses a date with an optional time, e.g. `2020-01-31`, `2020-01-31T12:00:00Z` or the
+    /// TIFF format `2020:01:31 12:00:00`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().trim_end_matches('Z');
+        let (date, time) = match text.split_once(['T', ' ']) {
+            Some((date, time)) => (date, Some(time.trim())),
+            None => (text, None),
+        };
+        let mut date = date.split(['-', ':', '/']);
+        let year = date.next()?.parse().ok()?;
+        let month = date.next()?.parse().ok()?;
+        let day = date.next()?.parse().ok()?;
+        if date.next().is_some() {
+            return None;
+        }
+        let (hour, minute, second) = match time {
+            Some(time) => {
+                let mut time = time.split(':');
+                let hour = time.next()?.parse().ok()?;
+                let minute = time.next().map_or(Some(0), |minute| minute.parse().ok())?;
+                let second = time
+                    .next()
+                    .map_or(Some(0.0), |second| second.parse().ok())?;
+                (hour, minute, second)
+            }
+            None => (0, 0, 0.0),
+        };
+        let date_time = Self {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        };
+        date_time.is_valid().then_some(date_time)
+    }
+
+    /// Returns the date and time at the given number of seconds since 1970-01-01 00:00:00.
+    pub fn from_unix_seconds(seconds: f64) -> Self {
+        let days = (seconds / 86400.0).floor();
+        let seconds = seconds - days * 86400.0;
+        // Ref: http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let z = days as i64 + 719468;
+        let era = z.div_euclid(146097);
+        let day_of_era = z.rem_euclid(146097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let month = if month < 10 { month + 3 } else { month - 9 };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        Self {
+            year: year as i32,
+            month: month as u8,
+            day: day as u8,
+            hour: (seconds / 3600.0) as u8,
+            minute: (seconds % 3600.0 / 60.0) as u8,
+            second: seconds % 60.0,
+        }
+    }
+
+    /// Returns the number of seconds since 1970-01-01 00:00:00.
+    pub fn unix_seconds(&self) -> f64 {
+        // Ref: http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let month = i64::from(self.month);
+        let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5
+            + i64::from(self.day)
+            - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146097 + day_of_era - 719468;
+        days as f64 * 86400.0
+            + f64::from(self.hour) * 3600.0
+            + f64::from(self.minute) * 60.0
+            + self.second
+    }
+
+    fn is_valid(&self) -> bool {
+        let leap_year = self.year % 4 == 0 && (self.year % 100 != 0 || self.year % 400 == 0);
+        let days_in_month = match self.month {
+            2 if leap_year => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        };
+        (1..=12).contains(&self.month)
+            && (1..=days_in_month).contains(&self.day)
+            && self.hour < 24
+            && self.minute < 60
+            && (0.0..61.0).contains(&self.second)
+    }
+}
+
+impl fmt::Display for DateTime {
+    /// Formats the date and time in ISO 8601, e.g. `2020-01-31T12:00:00Z`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second as u8
+        )?;
+        let fraction = self.second.fract();
+        if fraction > 0.0 {
+            let fraction = format!("{fraction}");
+            write!(f, "{}", &fraction[1..])?;
+        }
+        write!(f, "Z")
+    }
+}
+
+/// The timestamps of the bands of an image storing a time series with one band per time step,
+/// following the conventions of GDAL.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeSeriesInfo {
+    /// The timestamp of each band, in band order.
+    pub timestamps: Vec<DateTime>,
+}
+
+impl TimeSeriesInfo {
+    /// Reads the timestamps from the band items of the metadata, given either by date and time
+    /// items, e.g. `DateTime`, or by the time coordinate of bands converted from NetCDF.
+    ///
+    /// Returns `None` unless each of the bands has a timestamp.
+    pub fn from_gdal_metadata(metadata: &GdalMetadata, num_samples: usize) -> Option<Self> {
+        let netcdf_units = metadata
+            .get(NETCDF_TIME_UNITS_ITEM)
+            .and_then(parse_time_units);
+        let timestamps = (0..num_samples)
+            .map(|sample| {
+                let date_time = DATE_TIME_ITEMS
+                    .iter()
+                    .find_map(|name| metadata.band_item(sample, name))
+                    .and_then(DateTime::parse);
+                date_time.or_else(|| {
+                    let (unit_seconds, epoch) = netcdf_units?;
+                    let value = metadata
+                        .band_item(sample, NETCDF_TIME_ITEM)?
+                        .parse::<f64>()
+                        .ok()?;
+                    Some(DateTime::from_unix_seconds(
+                        epoch.unix_seconds() + value * unit_seconds,
+                    ))
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        (!timestamps.is_empty()).then_some(Self { timestamps })
+    }
+}
+
+impl Image {
+    /// Returns the timestamps of the bands of this image, if it stores a time series, see
+    /// [TimeSeriesInfo::from_gdal_metadata].
+    pub fn time_series(&self) -> Option<TimeSeriesInfo> {
+        TimeSeriesInfo::from_gdal_metadata(self.gdal_metadata()?, self.num_samples)
+    }
+
+    /// Returns the values of the time series at the given location in model space, with their
+    /// timestamps in band order. Nodata values are skipped.
+    ///
+    /// Fails if the image does not store a time series. The result is empty if the location is
+    /// outside of the raster.
+    pub fn read_timeseries_at(&self, coord: &Coord) -> TiffResult<Vec<(DateTime, f64)>> {
+        let Some(time_series) = self.time_series() else {
+            return Err(TiffError::FormatError(TiffFormatError::Format(
+                "The bands of the image have no timestamps".into(),
+            )));
+        };
+        Ok(time_series
+            .timestamps
+            .into_iter()
+            .enumerate()
+            .filter_map(|(sample, timestamp)| {
+                let value = self.get_value_at::<f64>(coord, sample)?;
+                (!value.is_nan() && Some(value) != self.nodata()).then_some((timestamp, value))
+            })
+            .collect())
+    }
+}
+
+impl GeoTiff {
+    /// See [Image::time_series].
+    pub fn time_series(&self) -> Option<TimeSeriesInfo> {
+        self.primary().time_series()
+    }
+
+    /// See [Image::read_timeseries_at].
+    pub fn read_timeseries_at(&self, coord: &Coord) -> TiffResult<Vec<(DateTime, f64)>> {
+        self.primary().read_timeseries_at(coord)
+    }
+}
+
+/// Parses CF time units, e.g. `days since 1970-01-01 00:00:00`, into the number of seconds of the
+/// unit and the epoch.
+fn parse_time_units(units: &str) -> Option<(f64, DateTime)> {
+    let (unit, epoch) = units.split_once(" since ")?;
+    let unit_seconds = match unit.trim().to_lowercase().as_str() {
+        "seconds" | "second" | "secs" | "sec" | "s" => 1.0,
+        "minutes" | "minute" | "mins" | "min" => 60.0,
+        "hours" | "hour" | "hrs" | "hr" | "h" => 3600.0,
+        "days" | "day" | "d" => 86400.0,
+        _ => return None,
+    };
+    Some((unit_seconds, DateTime::parse(epoch)?))
+}
@@ -0,0 +1,199 @@
+use geo_types::Coord;
+
+use crate::{CoordinateTransform, GeoKeyDirectory, MetadataResult, TransformCoords};
+
+const USER_DEFINED: u16 = 32767;
+
+/// A unit of length, as referenced by the ProjLinearUnitsGeoKey and GeogLinearUnitsGeoKey.
+///
+/// Ref: https://docs.ogc.org/is/19-008r4/19-008r4.html#_units_of_measure_codes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinearUnit {
+    Metre,
+    Kilometre,
+    Foot,
+    UsSurveyFoot,
+    /// Any other unit, given by its length in metres.
+    Other(f64),
+}
+
+impl LinearUnit {
+    /// Resolves the unit from its EPSG code. User-defined units need their length in metres,
+    /// e.g. from the ProjLinearUnitSizeGeoKey.
+    pub fn from_code(code: u16, unit_size: Option<f64>) -> Option<Self> {
+        Some(match code {
+            9001 => LinearUnit::Metre,
+            9002 => LinearUnit::Foot,
+            9003 => LinearUnit::UsSurveyFoot,
+            9036 => LinearUnit::Kilometre,
+            9005 => LinearUnit::Other(0.3047972654),
+            9014 => LinearUnit::Other(1.8288),
+            9030 => LinearUnit::Other(1852.0),
+            9093 => LinearUnit::Other(1609.344),
+            9096 => LinearUnit::Other(0.9144),
+            USER_DEFINED => LinearUnit::Other(unit_size?),
+            _ => return None,
+        })
+    }
+
+    /// Returns the length of the unit in metres.
+    pub fn metres(&self) -> f64 {
+        match self {
+            LinearUnit::Metre => 1.0,
+            LinearUnit::Kilometre => 1000.0,
+            LinearUnit::Foot => 0.3048,
+            LinearUnit::UsSurveyFoot => 1200.0 / 3937.0,
+            LinearUnit::Other(metres) => *metres,
+        }
+    }
+
+    /// Converts a length from `from` to `to`.
+    pub fn convert(value: f64, from: LinearUnit, to: LinearUnit) -> f64 {
+        if from == to {
+            value
+        } else {
+            value * from.metres() / to.metres()
+        }
+    }
+}
+
+/// A unit of angle, as referenced by the GeogAngularUnitsGeoKey.
+///
+/// Ref: https://docs.ogc.org/is/19-008r4/19-008r4.html#_units_of_measure_codes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AngularUnit {
+    Radian,
+    Degree,
+    ArcMinute,
+    ArcSecond,
+    Grad,
+    /// Any other unit, given by its size in radians.
+    Other(f64),
+}
+
+impl AngularUnit {
+    /// Resolves the unit from its EPSG code. User-defined units need their size in radians,
+    /// e.g. from the GeogAngularUnitSizeGeoKey.
+    pub fn from_code(code: u16, unit_size: Option<f64>) -> Option<Self> {
+        Some(match code {
+            9101 => AngularUnit::Radian,
+            9102 | 9122 => AngularUnit::Degree,
+            9103 => AngularUnit::ArcMinute,
+            9104 => AngularUnit::ArcSecond,
+            9105 | 9106 => AngularUnit::Grad,
+            9109 => AngularUnit::Other(1e-6),
+            USER_DEFINED => AngularUnit::Other(unit_size?),
+            _ => return None,
+        })
+    }
+
+    /// Returns the size of the unit in degrees.
+    pub fn degrees(&self) -> f64 {
+        match self {
+            AngularUnit::Radian => 180.0 / std::f64::consts::PI,
+            AngularUnit::Degree => 1.0,
+            AngularUnit::ArcMinute => 1.0 / 60.0,
+            AngularUnit::ArcSecond => 1.0 / 3600.0,
+            AngularUnit::Grad => 0.9,
+            AngularUnit::Other(radians) => radians.to_degrees(),
+        }
+    }
+
+    /// Converts an angle from `from` to `to`.
+    pub fn convert(value: f64, from: AngularUnit, to: AngularUnit) -> f64 {
+        if from == to {
+            value
+        } else {
+            value * from.degrees() / to.degrees()
+        }
+    }
+}
+
+impl GeoKeyDirectory {
+    /// Returns the unit of the model coordinates of a geographic CRS, as given by the
+    /// GeogAngularUnitsGeoKey.
+    ///
+    /// Returns `None` if the key is not set, in which case the unit is implied by the EPSG code
+    /// of the CRS, or for other kinds of CRS.
+    pub fn geographic_angular_unit(&self) -> Option<AngularUnit> {
+        if !self.is_geographic() {
+            return None;
+        }
+        AngularUnit::from_code(self.geog_angular_units?, self.geog_angular_unit_size)
+    }
+
+    /// Returns the unit of the model coordinates of a projected CRS, as given by the
+    /// ProjLinearUnitsGeoKey.
+    ///
+    /// Returns `None` if the key is not set, in which case the unit is implied by the EPSG code
+    /// of the CRS, or for other kinds of CRS.
+    pub fn projected_linear_unit(&self) -> Option<LinearUnit> {
+        if !self.is_projected() {
+            return None;
+        }
+        LinearUnit::from_code(self.proj_linear_units?, self.proj_linear_unit_size)
+    }
+}
+
+/// A view of a [CoordinateTransform] whose model coordinates are expressed in degrees for
+/// geographic CRS and in metres for projected CRS, see [crate::Image::normalized].
+///
+/// Coordinates in an unknown unit are passed through unchanged.
+#[derive(Debug, Clone, Copy)]
+pub struct NormalizedTransform<'a> {
+    transform: &'a CoordinateTransform,
+    /// Size of the stored model unit in the normalized unit.
+    factor: f64,
+}
+
+impl<'a> NormalizedTransform<'a> {
+    /// Returns a view of the transform whose model coordinates are in the unit given by the geo
+    /// keys, or by `model_linear_unit` for projected CRS, e.g. when it was already normalized.
+    pub fn new(
+        transform: &'a CoordinateTransform,
+        geo_key_directory: Option<&GeoKeyDirectory>,
+        model_linear_unit: Option<LinearUnit>,
+    ) -> Self {
+        let angular_unit =
+            geo_key_directory.and_then(|directory| directory.geographic_angular_unit());
+        let factor = match (angular_unit, model_linear_unit) {
+            (Some(unit), _) => unit.degrees(),
+            (None, Some(unit)) => unit.metres(),
+            (None, None) => 1.0,
+        };
+        Self { transform, factor }
+    }
+
+    /// Returns the underlying transformation, whose model coordinates are in stored units.
+    pub fn transform(&self) -> &'a CoordinateTransform {
+        self.transform
+    }
+
+    pub fn transform_to_model(&self, coord: &Coord) -> Coord {
+        let coord = self.transform.transform_to_model(coord);
+        Coord {
+            x: coord.x * self.factor,
+            y: coord.y * self.factor,
+        }
+    }
+
+    pub fn transform_to_raster(&self, coord: &Coord) -> MetadataResult<Coord> {
+        self.transform.transform_to_raster(&Coord {
+            x: coord.x / self.factor,
+            y: coord.y / self.factor,
+        })
+    }
+
+    /// See [CoordinateTransform::geometry_to_model].
+    pub fn geometry_to_model<G: TransformCoords>(&self, geometry: &G) -> G::Output {
+        geometry.map_coords(&|coord| self.transform_to_model(&coord))
+    }
+
+    /// See [CoordinateTransform::geometry_to_raster].
+    pub fn geometry_to_raster<G: TransformCoords>(
+        &self,
+        geometry: &G,
+    ) -> MetadataResult<G::Output> {
+        geometry.try_map_coords(&|coord| self.transform_to_raster(&coord))
+    }
+}
@@ -0,0 +1,114 @@
+//! Conversions between longitude/latitude, Web Mercator (EPSG:3857) meters and the tiles of
+//! slippy maps, with the XYZ convention of OpenStreetMap and most web maps: the tile (0, 0) of
+//! each zoom level is at the north-west corner of the world, and the zoom level `z` has `2^z` x
+//! `2^z` tiles.
+//!
+//! The tile export of this crate uses these conventions, so that applications placing its tiles
+//! do not depend on another crate with slightly different ones.
+
+use std::f64::consts::PI;
+use std::ops::RangeInclusive;
+
+use geo_types::{Coord, Rect};
+
+/// The radius of the sphere of Web Mercator.
+pub const EARTH_RADIUS: f64 = 6378137.0;
+
+/// The latitude in degrees of the north edge of the tiles, beyond which Web Mercator is not
+/// defined, such that the world is square.
+pub const MAX_LATITUDE: f64 = 85.051_128_779_806_59;
+
+/// The largest zoom level, whose tile indices fit in 32 bits with room for arithmetic.
+pub const MAX_ZOOM: u8 = 30;
+
+/// Half the width of the world in meters.
+const HALF_WORLD: f64 = PI * EARTH_RADIUS;
+
+/// Returns the Web Mercator coordinates of a longitude and latitude in degrees, with the
+/// latitude clamped to [MAX_LATITUDE].
+pub fn lon_lat_to_meters(lon_lat: Coord) -> Coord {
+    let latitude = lon_lat.y.clamp(-MAX_LATITUDE, MAX_LATITUDE).to_radians();
+    Coord {
+        x: lon_lat.x.to_radians() * EARTH_RADIUS,
+        y: (PI / 4.0 + latitude / 2.0).tan().ln() * EARTH_RADIUS,
+    }
+}
+
+/// Returns the longitude and latitude in degrees of Web Mercator coordinates.
+pub fn meters_to_lon_lat(meters: Coord) -> Coord {
+    Coord {
+        x: (meters.x / EARTH_RADIUS).to_degrees(),
+        y: (2.0 * (meters.y / EARTH_RADIUS).exp().atan() - PI / 2.0).to_degrees(),
+    }
+}
+
+/// Returns the width of the tiles of the zoom level in meters.
+pub fn tile_extent(zoom: u8) -> f64 {
+    2.0 * HALF_WORLD / (1u64 << zoom) as f64
+}
+
+/// Returns the size of the pixels of the zoom level in meters, for tiles of `tile_size` pixels.
+/// This is the size at the equator: the ground size of the pixels shrinks with the cosine of the
+/// latitude.
+pub fn resolution(zoom: u8, tile_size: usize) -> f64 {
+    tile_extent(zoom) / tile_size as f64
+}
+
+/// Returns the smallest zoom level whose pixels are at most `resolution` meters, for tiles of
+/// `tile_size` pixels, so that an image of this resolution is not downsampled, up to
+/// [MAX_ZOOM].
+pub fn zoom_for_resolution(resolution: f64, tile_size: usize) -> u8 {
+    (0..MAX_ZOOM)
+        .find(|&zoom| self::resolution(zoom, tile_size) <= resolution)
+        .unwrap_or(MAX_ZOOM)
+}
+
+/// Returns the column and row of the tile of the zoom level containing Web Mercator coordinates,
+/// clamped to the tiles of the zoom level.
+pub fn meters_to_tile(meters: Coord, zoom: u8) -> (u32, u32) {
+    let tiles = (1u64 << zoom) as f64;
+    let tile = |position: f64| {
+        (position / (2.0 * HALF_WORLD) * tiles)
+            .floor()
+            .clamp(0.0, tiles - 1.0) as u32
+    };
+    (tile(meters.x + HALF_WORLD), tile(HALF_WORLD - meters.y))
+}
+
+/// Returns the column and row of the tile of the zoom level containing a longitude and latitude
+/// in degrees.
+pub fn lon_lat_to_tile(lon_lat: Coord, zoom: u8) -> (u32, u32) {
+    meters_to_tile(lon_lat_to_meters(lon_lat), zoom)
+}
+
+/// Returns the bounds of a tile in Web Mercator coordinates.
+pub fn tile_bounds(zoom: u8, x: u32, y: u32) -> Rect {
+    let extent = tile_extent(zoom);
+    let west = -HALF_WORLD + x as f64 * extent;
+    let north = HALF_WORLD - y as f64 * extent;
+    Rect::new(
+        Coord { x: west, y: north },
+        Coord {
+            x: west + extent,
+            y: north - extent,
+        },
+    )
+}
+
+/// Returns the bounds of a tile in longitude and latitude.
+pub fn tile_bounds_lon_lat(zoom: u8, x: u32, y: u32) -> Rect {
+    let bounds = tile_bounds(zoom, x, y);
+    Rect::new(
+        meters_to_lon_lat(bounds.min()),
+        meters_to_lon_lat(bounds.max()),
+    )
+}
+
+/// Returns the columns and rows of the tiles of the zoom level intersecting bounds in Web
+/// Mercator coordinates.
+pub fn tile_range(bounds: Rect, zoom: u8) -> (RangeInclusive<u32>, RangeInclusive<u32>) {
+    let (min, max) = (bounds.min(), bounds.max());
+    let (x0, y0) = meters_to_tile(Coord { x: min.x, y: max.y }, zoom);
+    let (x1, y1) = meters_to_tile(Coord { x: max.x, y: min.y }, zoom);
+    (x0..=x1, y0..=y1)
+}
@@ -0,0 +1,993 @@
+use std::any::{type_name, Any};
+use std::borrow::Cow;
+
+use num_traits::{Bounded, NumCast};
+use tiff::{TiffError, TiffFormatError, TiffResult};
+
+use crate::raster_data::{convert, RasterData};
+use crate::simd;
+use crate::{AffineTransform, CoordinateTransform, GeoTiff, Image, Resampling};
+
+/// A rectangular window of pixels in raster space.
+///
+/// The offset may be negative, and the window may extend past the raster, see
+/// [ReadOptions::out_of_bounds].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Window {
+    pub x: i64,
+    pub y: i64,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Window {
+    pub fn new(x: i64, y: i64, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns the window covering a whole raster of the given dimensions.
+    pub fn full(width: usize, height: usize) -> Self {
+        Self::new(0, 0, width, height)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Returns the pixels covered by both windows, or `None` if they do not overlap.
+    pub fn intersection(&self, other: &Window) -> Option<Window> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let x_end = (self.x + self.width as i64).min(other.x + other.width as i64);
+        let y_end = (self.y + self.height as i64).min(other.y + other.height as i64);
+        if x_end <= x || y_end <= y {
+            return None;
+        }
+        Some(Self::new(x, y, (x_end - x) as usize, (y_end - y) as usize))
+    }
+
+    /// Returns whether the window lies entirely within `other`.
+    pub fn is_within(&self, other: &Window) -> bool {
+        self.x >= other.x
+            && self.y >= other.y
+            && self.x + self.width as i64 <= other.x + other.width as i64
+            && self.y + self.height as i64 <= other.y + other.height as i64
+    }
+}
+
+/// What to do with the pixels of a window lying outside the raster.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OutOfBounds {
+    /// Fill with the nodata value of the image, see [Image::nodata], or zero if it has none.
+    #[default]
+    FillNodata,
+    /// Fill with the given value.
+    Fill(f64),
+    /// Fail if the window is not entirely within the raster.
+    Error,
+    /// Shrink the window to the part within the raster.
+    Clamp,
+}
+
+/// How to convert the values of the raster to the requested type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Conversion {
+    /// Fail if a value cannot be represented exactly, e.g. a 32-bit integer above 2^24 read as
+    /// `f32`, or a fractional value read as an integer.
+    Exact,
+    /// Clamp the values outside the range of the type to its bounds, and convert NaN to zero.
+    /// Precision may be lost.
+    Saturate,
+    /// Allow precision loss, but fail if a value is outside the range of the type.
+    #[default]
+    Lossy,
+}
+
+/// The order of the values of the pixels of a window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interleave {
+    /// The samples of each pixel are stored contiguously, i.e. in (height, width, sample) order.
+    #[default]
+    Pixel,
+    /// The pixels of each band are stored contiguously, i.e. in (sample, height, width) order.
+    Band,
+}
+
+/// The orientation of the returned data relative to the raster, e.g. to match the conventions of
+/// libraries expecting south up or column-major arrays.
+///
+/// The rows are flipped before the data is transposed, so that with both, the first row of the
+/// data is the first column of the window, from its bottom to its top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Orientation {
+    /// The rows are in bottom to top order, i.e. south up for a north up raster.
+    pub flip_y: bool,
+    /// The rows of the data are the columns of the window, i.e. the data is in (width, height)
+    /// order for [Interleave::Pixel].
+    pub transpose: bool,
+}
+
+impl Orientation {
+    /// Returns the position in data of this orientation of the pixel at the given position of
+    /// the window grid of the given size.
+    fn to_data(self, x: usize, y: usize, grid_height: usize) -> (usize, usize) {
+        let y = if self.flip_y { grid_height - 1 - y } else { y };
+        if self.transpose {
+            (y, x)
+        } else {
+            (x, y)
+        }
+    }
+
+    /// Returns the position in the window grid of the given size of the pixel at the given
+    /// position in data of this orientation.
+    fn to_grid(self, x: usize, y: usize, grid_height: usize) -> (usize, usize) {
+        let (x, y) = if self.transpose { (y, x) } else { (x, y) };
+        let y = if self.flip_y { grid_height - 1 - y } else { y };
+        (x, y)
+    }
+}
+
+/// Options for reading windows of raster data, see [Image::read_window].
+#[derive(Debug, Clone, Default)]
+pub struct ReadOptions {
+    pub out_of_bounds: OutOfBounds,
+    /// The conversion of the values to the requested type, which also applies to the fill value
+    /// of the pixels outside the raster.
+    pub conversion: Conversion,
+    /// The order of the values of the returned data.
+    pub interleave: Interleave,
+    /// How the source pixels are combined by decimated reads, see
+    /// [Image::read_window_decimated].
+    pub resampling: Resampling,
+    /// The orientation of the returned data, see [WindowData::coordinate_transform].
+    pub orientation: Orientation,
+}
+
+/// The pixels of a window, in row-major order.
+///
+/// The data has the size of the window, unless it was decimated, see
+/// [GeoTiff::read_window_decimated], or transposed, see [Orientation].
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowData<T> {
+    /// The window which was read, which differs from the requested one for [OutOfBounds::Clamp].
+    pub window: Window,
+    pub width: usize,
+    pub height: usize,
+    pub num_samples: usize,
+    pub interleave: Interleave,
+    pub orientation: Orientation,
+    /// The data has a size of width * height * num_samples.
+    pub data: Vec<T>,
+}
+
+impl<T: Copy> WindowData<T> {
+    /// Returns the value of the given sample at the pixel with the given coordinates in the data.
+    pub fn get(&self, x: usize, y: usize, sample: usize) -> T {
+        let pixel = y * self.width + x;
+        match self.interleave {
+            Interleave::Pixel => self.data[pixel * self.num_samples + sample],
+            Interleave::Band => self.data[sample * self.width * self.height + pixel],
+        }
+    }
+
+    /// Returns the data with its values in the given order.
+    pub fn with_interleave(self, interleave: Interleave) -> Self {
+        let data = match (self.interleave, interleave) {
+            (Interleave::Pixel, Interleave::Band) => {
+                pixel_to_band_interleaved(&self.data, self.num_samples)
+            }
+            (Interleave::Band, Interleave::Pixel) => {
+                band_to_pixel_interleaved(&self.data, self.num_samples)
+            }
+            _ => self.data,
+        };
+        Self {
+            interleave,
+            data,
+            ..self
+        }
+    }
+
+    /// Returns the data with its pixels in the given orientation.
+    pub fn with_orientation(self, orientation: Orientation) -> Self {
+        if orientation == self.orientation {
+            return self;
+        }
+        let (grid_width, grid_height) = match self.orientation.transpose {
+            false => (self.width, self.height),
+            true => (self.height, self.width),
+        };
+        let (width, height) = match orientation.transpose {
+            false => (grid_width, grid_height),
+            true => (grid_height, grid_width),
+        };
+        let value = |x: usize, y: usize, sample: usize| {
+            let (x, y) = orientation.to_grid(x, y, grid_height);
+            let (x, y) = self.orientation.to_data(x, y, grid_height);
+            self.get(x, y, sample)
+        };
+        let data = match self.interleave {
+            Interleave::Pixel => (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .flat_map(|(x, y)| (0..self.num_samples).map(move |sample| value(x, y, sample)))
+                .collect(),
+            Interleave::Band => (0..self.num_samples)
+                .flat_map(|sample| (0..height).map(move |y| (y, sample)))
+                .flat_map(|(y, sample)| (0..width).map(move |x| value(x, y, sample)))
+                .collect(),
+        };
+        Self {
+            width,
+            height,
+            orientation,
+            data,
+            ..self
+        }
+    }
+
+    /// Returns the transformation from the raster space of the data to the model space of the
+    /// given image, which must be the image it was read from, or the primary image for
+    /// [GeoTiff::read_window_decimated].
+    ///
+    /// The transformation accounts for the offset of the window, the decimation and the
+    /// orientation of the data, so that the pixel at (x, y) in the data is at the model
+    /// coordinates of the raster coordinates (x, y) of the transformation, e.g. to write the data
+    /// as a raster of its own. Returns `None` if the image has no transformation or if it is not
+    /// affine, i.e. for tie points.
+    pub fn coordinate_transform(&self, image: &Image) -> Option<CoordinateTransform> {
+        let [a, b, c, d, e, f] = image.coordinate_transform()?.affine_terms()?;
+        let offset = image.raster_offset();
+        let (grid_width, grid_height) = match self.orientation.transpose {
+            false => (self.width, self.height),
+            true => (self.height, self.width),
+        };
+        let scale_x = self.window.width as f64 / grid_width.max(1) as f64;
+        let scale_y = self.window.height as f64 / grid_height.max(1) as f64;
+        // The raster coordinates of the image from those of the data, through the pixel corners
+        let to_raster = |u: f64, v: f64| {
+            let (x, y) = (u - offset, v - offset);
+            let (x, y) = if self.orientation.transpose {
+                (y, x)
+            } else {
+                (x, y)
+            };
+            let y = if self.orientation.flip_y {
+                grid_height as f64 - y
+            } else {
+                y
+            };
+            (
+                self.window.x as f64 + x * scale_x + offset,
+                self.window.y as f64 + y * scale_y + offset,
+            )
+        };
+        let to_model = |(i, j): (f64, f64)| (a * i + b * j + c, d * i + e * j + f);
+        let origin = to_model(to_raster(0.0, 0.0));
+        let step_u = to_model(to_raster(1.0, 0.0));
+        let step_v = to_model(to_raster(0.0, 1.0));
+        Some(CoordinateTransform::AffineTransform(
+            AffineTransform::from_tag_matrix([
+                step_u.0 - origin.0,
+                step_v.0 - origin.0,
+                0.0,
+                origin.0,
+                step_u.1 - origin.1,
+                step_v.1 - origin.1,
+                0.0,
+                origin.1,
+                0.0,
+                0.0,
+                0.0,
+                0.0,
+                0.0,
+                0.0,
+                0.0,
+                1.0,
+            ]),
+        ))
+    }
+}
+
+/// Converts values where the samples of each pixel are stored contiguously to values where the
+/// pixels of each band are stored contiguously, see [Interleave].
+pub fn pixel_to_band_interleaved<T: Copy>(data: &[T], num_samples: usize) -> Vec<T> {
+    (0..num_samples)
+        .flat_map(|sample| data.iter().skip(sample).step_by(num_samples).copied())
+        .collect()
+}
+
+/// Converts values where the pixels of each band are stored contiguously to values where the
+/// samples of each pixel are stored contiguously, see [Interleave].
+pub fn band_to_pixel_interleaved<T: Copy>(data: &[T], num_samples: usize) -> Vec<T> {
+    let num_pixels = data.len() / num_samples.max(1);
+    (0..num_pixels)
+        .flat_map(|pixel| data.iter().skip(pixel).step_by(num_pixels.max(1)).copied())
+        .collect()
+}
+
+impl Image {
+    /// Returns the nodata value of the image, given by the GDAL_NODATA tag.
+    pub fn nodata(&self) -> Option<f64> {
+        self.nodata
+    }
+
+    /// Returns the window covering the whole raster.
+    pub fn window(&self) -> Window {
+        Window::full(self.raster_width, self.raster_height)
+    }
+
+    /// Returns the width and height of the tiles of the image, or of the strips if it is not
+    /// tiled.
+    pub fn block_size(&self) -> (usize, usize) {
+        self.block_size
+    }
+
+    /// Expands the window to the boundaries of the tiles or strips it overlaps, clamped to the
+    /// raster.
+    ///
+    /// Returns `None` if the window does not overlap the raster.
+    pub fn align_window(&self, window: Window) -> Option<Window> {
+        let window = window.intersection(&self.window())?;
+        let (block_width, block_height) = self.block_size;
+        let align = |start: i64, size: usize, block: usize, limit: usize| {
+            let block = block.max(1) as i64;
+            let aligned_start = start / block * block;
+            let end = (start + size as i64 + block - 1) / block * block;
+            (aligned_start, end.min(limit as i64) - aligned_start)
+        };
+        let (x, width) = align(window.x, window.width, block_width, self.raster_width);
+        let (y, height) = align(window.y, window.height, block_height, self.raster_height);
+        Some(Window::new(x, y, width as usize, height as usize))
+    }
+
+    /// Splits the aligned window, see [Image::align_window], into windows of whole tiles or
+    /// strips covering at most `max_pixels` each, in row-major order.
+    ///
+    /// Each window covers at least one block, even if it has more than `max_pixels` pixels.
+    pub fn optimal_read_windows(&self, window: Window, max_pixels: usize) -> Vec<Window> {
+        let Some(aligned) = self.align_window(window) else {
+            return Vec::new();
+        };
+        let (block_width, block_height) = (self.block_size.0.max(1), self.block_size.1.max(1));
+        let blocks_x = aligned.width.div_ceil(block_width);
+        let max_blocks = (max_pixels / (block_width * block_height)).max(1);
+        let (chunk_blocks_x, chunk_blocks_y) = if blocks_x <= max_blocks {
+            (blocks_x, max_blocks / blocks_x)
+        } else {
+            (max_blocks, 1)
+        };
+        let (chunk_width, chunk_height) = (
+            (chunk_blocks_x * block_width) as i64,
+            (chunk_blocks_y * block_height) as i64,
+        );
+
+        let mut windows = Vec::new();
+        let (x_end, y_end) = (
+            aligned.x + aligned.width as i64,
+            aligned.y + aligned.height as i64,
+        );
+        for y in (aligned.y..y_end).step_by(chunk_height as usize) {
+            for x in (aligned.x..x_end).step_by(chunk_width as usize) {
+                windows.push(Window::new(
+                    x,
+                    y,
+                    (chunk_width.min(x_end - x)) as usize,
+                    (chunk_height.min(y_end - y)) as usize,
+                ));
+            }
+        }
+        windows
+    }
+
+    /// Reads the pixels of the given window, converted to `T`.
+    ///
+    /// Pixels of the window outside the raster are handled according to
+    /// [ReadOptions::out_of_bounds].
+    pub fn read_window<T: NumCast + Bounded + Copy + 'static>(
+        &self,
+        window: Window,
+        options: &ReadOptions,
+    ) -> TiffResult<WindowData<T>> {
+        let (window, fill) = self.resolve_window(window, options)?;
+        let decoded = self.decode_area(window)?;
+        self.read_decoded(&decoded, window, fill, options)
+    }
+
+    /// Reads the pixels of the resolved window from the decoded area covering it, see
+    /// [Image::read_window].
+    fn read_decoded<T: NumCast + Bounded + Copy + 'static>(
+        &self,
+        decoded: &DecodedArea,
+        window: Window,
+        fill: T,
+        options: &ReadOptions,
+    ) -> TiffResult<WindowData<T>> {
+        let num_samples = self.num_samples;
+        let mut data = vec![fill; window.width * window.height * num_samples];
+        let row_stride = match options.interleave {
+            Interleave::Pixel => window.width * num_samples,
+            Interleave::Band => window.width,
+        };
+        // Fast path for 16-bit rasters read as f32, which cannot fail to convert
+        let f32_data = (&mut data as &mut dyn Any).downcast_mut::<Vec<f32>>();
+        match (&*decoded.data, f32_data) {
+            (RasterData::U16(values), Some(f32_data))
+                if options.interleave == Interleave::Pixel
+                    && !window.is_empty()
+                    && window.is_within(&self.window()) =>
+            {
+                for (y, row) in f32_data.chunks_exact_mut(row_stride).enumerate() {
+                    let start = decoded.index(window.x as usize, window.y as usize + y);
+                    simd::u16_to_f32(&values[start..start + row_stride], row);
+                }
+            }
+            _ => self.copy_decoded(decoded, window, fill, &mut data, row_stride, options)?,
+        }
+
+        Ok(WindowData {
+            window,
+            width: window.width,
+            height: window.height,
+            num_samples,
+            interleave: options.interleave,
+            orientation: Orientation::default(),
+            data,
+        }
+        .with_orientation(options.orientation))
+    }
+
+    /// Reads the pixels of the given window as `value * scale + offset` in `f32`, with NaN for the
+    /// nodata values, e.g. for reflectances stored as scaled 16-bit integers.
+    ///
+    /// The values given by [ReadOptions::out_of_bounds] are scaled like the others.
+    pub fn read_window_scaled(
+        &self,
+        window: Window,
+        scale: f32,
+        offset: f32,
+        options: &ReadOptions,
+    ) -> TiffResult<WindowData<f32>> {
+        let (window, fill) = self.resolve_window::<f32>(window, options)?;
+        let decoded = self.decode_area(window)?;
+        // Fast path for 16-bit rasters, converted and scaled in one pass
+        match &*decoded.data {
+            RasterData::U16(values)
+                if options.interleave == Interleave::Pixel
+                    && !window.is_empty()
+                    && window.is_within(&self.window()) =>
+            {
+                let row_stride = window.width * self.num_samples;
+                let mut data = vec![0.0; row_stride * window.height];
+                for (y, row) in data.chunks_exact_mut(row_stride).enumerate() {
+                    let start = decoded.index(window.x as usize, window.y as usize + y);
+                    let values = &values[start..start + row_stride];
+                    simd::u16_to_f32_scaled(values, row, scale, offset, self.nodata());
+                }
+                Ok(WindowData {
+                    window,
+                    width: window.width,
+                    height: window.height,
+                    num_samples: self.num_samples,
+                    interleave: options.interleave,
+                    orientation: Orientation::default(),
+                    data,
+                }
+                .with_orientation(options.orientation))
+            }
+            _ => {
+                let mut data = self.read_decoded(&decoded, window, fill, options)?;
+                simd::scale_f32(&mut data.data, scale, offset, self.nodata());
+                Ok(data)
+            }
+        }
+    }
+
+    /// Reads the pixels of the given window, converted to `T`, into the given buffer, and returns
+    /// the window which was read, see [Image::read_window].
+    ///
+    /// The rows of the window start `row_stride` values apart in the buffer. With
+    /// [Interleave::Band], the bands are stored one after the other, each as a sequence of
+    /// `height` rows. Values of the buffer between the rows are left unchanged.
+    ///
+    /// Fails if [ReadOptions::orientation] is not the default one.
+    pub fn read_window_into<T: NumCast + Bounded + Copy + 'static>(
+        &self,
+        window: Window,
+        buffer: &mut [T],
+        row_stride: usize,
+        options: &ReadOptions,
+    ) -> TiffResult<Window> {
+        if options.orientation != Orientation::default() {
+            return Err(TiffError::FormatError(TiffFormatError::Format(
+                "Windows cannot be read into buffers with another orientation".into(),
+            )));
+        }
+        let (window, fill) = self.resolve_window(window, options)?;
+        let num_samples = self.num_samples;
+        let (row_len, num_rows) = match options.interleave {
+            Interleave::Pixel => (window.width * num_samples, window.height),
+            Interleave::Band => (window.width, window.height * num_samples),
+        };
+        let required = match num_rows {
+            0 => 0,
+            _ => (num_rows - 1) * row_stride + row_len,
+        };
+        if row_stride < row_len || buffer.len() < required {
+            return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+                "Buffer of {} values with a row stride of {} is too small for window {:?} of {} \
+                 samples",
+                buffer.len(),
+                row_stride,
+                window,
+                num_samples
+            ))));
+        }
+        let decoded = self.decode_area(window)?;
+        self.copy_decoded(&decoded, window, fill, buffer, row_stride, options)?;
+        Ok(window)
+    }
+
+    /// Copies the pixels of the resolved window from the decoded area covering it into a buffer
+    /// large enough for them, see [Image::read_window_into].
+    fn copy_decoded<T: NumCast + Bounded + Copy + 'static>(
+        &self,
+        decoded: &DecodedArea,
+        window: Window,
+        fill: T,
+        buffer: &mut [T],
+        row_stride: usize,
+        options: &ReadOptions,
+    ) -> TiffResult<()> {
+        let num_samples = self.num_samples;
+        for y in 0..window.height {
+            let row = window.y + y as i64;
+            let row_inside = (0..self.raster_height as i64).contains(&row);
+            for x in 0..window.width {
+                let column = window.x + x as i64;
+                let source = (row_inside && (0..self.raster_width as i64).contains(&column))
+                    .then(|| decoded.index(column as usize, row as usize));
+                for sample in 0..num_samples {
+                    let target = match options.interleave {
+                        Interleave::Pixel => y * row_stride + x * num_samples + sample,
+                        Interleave::Band => (sample * window.height + y) * row_stride + x,
+                    };
+                    buffer[target] = match source {
+                        Some(source) => {
+                            self.convert(&decoded.data, source + sample, options.conversion)?
+                        }
+                        None => fill,
+                    };
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the pixels of the given window, subsampled to the given output size by combining
+    /// the pixels covered by each output pixel as given by [ReadOptions::resampling], by default
+    /// the pixel nearest to its center.
+    ///
+    /// The nodata pixels are excluded from the combinations, and the output pixels without valid
+    /// pixels are filled. For integer types, the combined values are rounded and clamped to the
+    /// range of the type.
+    ///
+    /// For [OutOfBounds::Clamp], the output size is reduced in proportion to the window.
+    pub fn read_window_decimated<T: NumCast + Bounded + Copy + 'static>(
+        &self,
+        window: Window,
+        out_width: usize,
+        out_height: usize,
+        options: &ReadOptions,
+    ) -> TiffResult<WindowData<T>> {
+        let (clamped, fill) = self.resolve_window(window, options)?;
+        let (out_width, out_height) = clamped_size(window, clamped, out_width, out_height);
+        let data = self.sample(
+            [
+                clamped.x as f64,
+                clamped.y as f64,
+                clamped.width as f64,
+                clamped.height as f64,
+            ],
+            out_width,
+            out_height,
+            fill,
+            options,
+        )?;
+        Ok(WindowData {
+            window: clamped,
+            width: out_width,
+            height: out_height,
+            num_samples: self.num_samples,
+            interleave: Interleave::Pixel,
+            orientation: Orientation::default(),
+            data,
+        }
+        .with_interleave(options.interleave)
+        .with_orientation(options.orientation))
+    }
+
+    /// Applies the out of bounds policy, returning the window to read and the fill value.
+    pub(crate) fn resolve_window<T: NumCast + Bounded + Copy>(
+        &self,
+        window: Window,
+        options: &ReadOptions,
+    ) -> TiffResult<(Window, T)> {
+        let bounds = self.window();
+        let fill_value = match options.out_of_bounds {
+            OutOfBounds::FillNodata => self.nodata.unwrap_or(0.0),
+            OutOfBounds::Fill(value) => value,
+            OutOfBounds::Error => {
+                if !window.is_within(&bounds) {
+                    return Err(TiffError::FormatError(TiffFormatError::Format(format!(
+                        "Window {:?} is outside of the raster of size {}x{}",
+                        window, self.raster_width, self.raster_height
+                    ))));
+                }
+                0.0
+            }
+            OutOfBounds::Clamp => 0.0,
+        };
+        let window = match options.out_of_bounds {
+            OutOfBounds::Clamp => window
+                .intersection(&bounds)
+                .unwrap_or(Window::new(window.x, window.y, 0, 0)),
+            _ => window,
+        };
+
+        let fill = convert(fill_value, options.conversion).ok_or_else(|| {
+            TiffError::FormatError(TiffFormatError::Format(format!(
+                "Cannot represent fill value {} as {}",
+                fill_value,
+                type_name::<T>()
+            )))
+        })?;
+        Ok((window, fill))
+    }
+
+    /// Samples the `[x, y, width, height]` area in raster space of this image on a grid of the
+    /// given size with the resampling of the options, filling the pixels outside the raster.
+    pub(crate) fn sample<T: NumCast + Bounded + Copy + 'static>(
+        &self,
+        area: [f64; 4],
+        out_width: usize,
+        out_height: usize,
+        fill: T,
+        options: &ReadOptions,
+    ) -> TiffResult<Vec<T>> {
+        if options.resampling == Resampling::Nearest {
+            return self.sample_nearest(area, out_width, out_height, fill, options.conversion);
+        }
+        let decoded = self.decode_area(self.area_window(area, options.resampling.radius()))?;
+        let [x, y, width, height] = area;
+        let (pixel_width, pixel_height) = (
+            width / out_width.max(1) as f64,
+            height / out_height.max(1) as f64,
+        );
+        let num_samples = self.num_samples;
+        let is_integer = T::from(0.5).and_then(|value| value.to_f64()) != Some(0.5);
+        let (min, max) = (
+            T::min_value().to_f64().unwrap_or(f64::MIN),
+            T::max_value().to_f64().unwrap_or(f64::MAX),
+        );
+
+        let mut data = Vec::with_capacity(out_width * out_height * num_samples);
+        for j in 0..out_height {
+            for i in 0..out_width {
+                let pixel = [
+                    x + i as f64 * pixel_width,
+                    y + j as f64 * pixel_height,
+                    pixel_width,
+                    pixel_height,
+                ];
+                for sample in 0..num_samples {
+                    let get = |column, row| {
+                        let value: f64 = decoded.data.get(decoded.index(column, row) + sample);
+                        if self.nodata.is_some_and(|nodata| nodata == value) {
+                            f64::NAN
+                        } else {
+                            value
+                        }
+                    };
+                    let value = options.resampling.resample(
+                        get,
+                        self.raster_width,
+                        self.raster_height,
+                        pixel,
+                    );
+                    data.push(if value.is_nan() {
+                        fill
+                    } else if is_integer {
+                        self.convert_value(value.round().clamp(min, max), options.conversion)?
+                    } else {
+                        self.convert_value(value, options.conversion)?
+                    });
+                }
+            }
+        }
+        Ok(data)
+    }
+
+    fn convert_value<T: NumCast + Bounded + Copy>(
+        &self,
+        value: f64,
+        conversion: Conversion,
+    ) -> TiffResult<T> {
+        convert(value, conversion).ok_or_else(|| {
+            TiffError::FormatError(TiffFormatError::Format(format!(
+                "Cannot convert value {} of image {} to {} with {:?} conversion",
+                value,
+                self.index,
+                type_name::<T>(),
+                conversion
+            )))
+        })
+    }
+
+    /// Samples the area like [Image::sample] with the pixel nearest to the center of each output
+    /// pixel.
+    fn sample_nearest<T: NumCast + Bounded + Copy + 'static>(
+        &self,
+        area: [f64; 4],
+        out_width: usize,
+        out_height: usize,
+        fill: T,
+        conversion: Conversion,
+    ) -> TiffResult<Vec<T>> {
+        let decoded = self.decode_area(self.area_window(area, 0))?;
+        let [x, y, width, height] = area;
+        let num_samples = self.num_samples;
+        let mut data = vec![fill; out_width * out_height * num_samples];
+
+        let source_index = |i: usize, out_size: usize, origin: f64, size: f64, limit: usize| {
+            let index = (origin + (i as f64 + 0.5) * size / out_size as f64).floor();
+            (index >= 0.0 && index < limit as f64).then_some(index as usize)
+        };
+        let columns = (0..out_width)
+            .map(|i| source_index(i, out_width, x, width, self.raster_width))
+            .collect::<Vec<_>>();
+        for j in 0..out_height {
+            let Some(row) = source_index(j, out_height, y, height, self.raster_height) else {
+                continue;
+            };
+            for (i, column) in columns.iter().enumerate() {
+                let Some(column) = column else {
+                    continue;
+                };
+                let source = decoded.index(*column, row);
+                let target = (j * out_width + i) * num_samples;
+                for sample in 0..num_samples {
+                    data[target + sample] =
+                        self.convert(&decoded.data, source + sample, conversion)?;
+                }
+            }
+        }
+        Ok(data)
+    }
+
+    fn convert<T: NumCast + Bounded + Copy>(
+        &self,
+        raster_data: &RasterData,
+        index: usize,
+        conversion: Conversion,
+    ) -> TiffResult<T> {
+        raster_data.get_converted(index, conversion).ok_or_else(|| {
+            TiffError::FormatError(TiffFormatError::Format(format!(
+                "Cannot convert value {} of image {} to {} with {:?} conversion",
+                raster_data.get::<f64>(index),
+                self.index,
+                type_name::<T>(),
+                conversion
+            )))
+        })
+    }
+
+    /// Returns the window of the pixels of the raster read when resampling the
+    /// `[x, y, width, height]` area in raster space, with the given number of pixels beyond it.
+    /// Resampling outside the raster reads its edge pixels, so the window is never empty for
+    /// rasters which are not.
+    pub(crate) fn area_window(&self, area: [f64; 4], radius: usize) -> Window {
+        let [x, y, width, height] = area;
+        let span = |start: f64, size: f64, limit: usize| {
+            let first = (start.floor() - radius as f64).clamp(0.0, limit.saturating_sub(1) as f64);
+            let end = ((start + size).ceil() + radius as f64)
+                .max(first + 1.0)
+                .min(limit as f64);
+            (first as i64, (end - first).max(0.0) as usize)
+        };
+        let (x, width) = span(x, width, self.raster_width);
+        let (y, height) = span(y, height, self.raster_height);
+        Window::new(x, y, width, height)
+    }
+
+    /// Returns the samples of the pixels of the raster within the window, which are decoded by
+    /// this call for the images decoded on demand.
+    pub(crate) fn decode_area(&self, window: Window) -> TiffResult<DecodedArea<'_>> {
+        let num_samples = self.num_samples;
+        if let Some(raster_data) = &self.raster_data {
+            return Ok(DecodedArea {
+                data: Cow::Borrowed(raster_data),
+                area: self.window(),
+                num_samples,
+            });
+        }
+        if let Some(lazy_chunks) = &self.lazy_chunks {
+            let area = window
+                .intersection(&self.window())
+                .unwrap_or(Window::new(0, 0, 0, 0));
+            return Ok(DecodedArea {
+                data: Cow::Owned(lazy_chunks.decode(area)?),
+                area,
+                num_samples,
+            });
+        }
+        Err(match &self.unsupported {
+            Some(unsupported) => TiffError::UnsupportedError(unsupported.clone()),
+            None => TiffError::FormatError(TiffFormatError::Format(format!(
+                "The raster data of image {} could not be decoded",
+                self.index
+            ))),
+        })
+    }
+}
+
+/// The decoded samples of the pixels of an area of the raster of an image, which is the whole
+/// raster unless the image is decoded on demand, see [Image::decode_area].
+pub(crate) struct DecodedArea<'a> {
+    pub data: Cow<'a, RasterData>,
+    area: Window,
+    num_samples: usize,
+}
+
+impl DecodedArea<'_> {
+    /// Returns the index in the data of the first sample of a pixel of the area.
+    pub fn index(&self, column: usize, row: usize) -> usize {
+        ((row - self.area.y as usize) * self.area.width + column - self.area.x as usize)
+            * self.num_samples
+    }
+}
+
+impl GeoTiff {
+    /// See [Image::nodata].
+    pub fn nodata(&self) -> Option<f64> {
+        self.primary().nodata()
+    }
+
+    /// See [Image::block_size].
+    pub fn block_size(&self) -> (usize, usize) {
+        self.primary().block_size()
+    }
+
+    /// See [Image::align_window].
+    pub fn align_window(&self, window: Window) -> Option<Window> {
+        self.primary().align_window(window)
+    }
+
+    /// See [Image::optimal_read_windows].
+    pub fn optimal_read_windows(&self, window: Window, max_pixels: usize) -> Vec<Window> {
+        self.primary().optimal_read_windows(window, max_pixels)
+    }
+
+    /// See [Image::read_window].
+    pub fn read_window<T: NumCast + Bounded + Copy + 'static>(
+        &self,
+        window: Window,
+        options: &ReadOptions,
+    ) -> TiffResult<WindowData<T>> {
+        self.primary().read_window(window, options)
+    }
+
+    /// See [Image::read_window_scaled].
+    pub fn read_window_scaled(
+        &self,
+        window: Window,
+        scale: f32,
+        offset: f32,
+        options: &ReadOptions,
+    ) -> TiffResult<WindowData<f32>> {
+        self.primary()
+            .read_window_scaled(window, scale, offset, options)
+    }
+
+    /// See [Image::read_window_into].
+    pub fn read_window_into<T: NumCast + Bounded + Copy + 'static>(
+        &self,
+        window: Window,
+        buffer: &mut [T],
+        row_stride: usize,
+        options: &ReadOptions,
+    ) -> TiffResult<Window> {
+        self.primary()
+            .read_window_into(window, buffer, row_stride, options)
+    }
+
+    /// Reads the pixels of the given window of the primary image, subsampled to the given output
+    /// size.
+    ///
+    /// The data is read from the overview with the lowest resolution which is still at least the
+    /// output resolution, if any, see [Image::read_window_decimated]. The nodata value of the
+    /// primary image is used as fill value.
+    pub fn read_window_decimated<T: NumCast + Bounded + Copy + 'static>(
+        &self,
+        window: Window,
+        out_width: usize,
+        out_height: usize,
+        options: &ReadOptions,
+    ) -> TiffResult<WindowData<T>> {
+        let primary = self.primary();
+        let (clamped, fill) = primary.resolve_window(window, options)?;
+        let (out_width, out_height) = clamped_size(window, clamped, out_width, out_height);
+
+        let factor_x = clamped.width as f64 / out_width.max(1) as f64;
+        let factor_y = clamped.height as f64 / out_height.max(1) as f64;
+        let scale = |image: &Image| {
+            (
+                primary.raster_width as f64 / image.raster_width as f64,
+                primary.raster_height as f64 / image.raster_height as f64,
+            )
+        };
+        let level = self
+            .overviews()
+            .filter(|overview| {
+                overview.has_raster_data() && overview.num_samples == primary.num_samples
+            })
+            .filter(|overview| {
+                let (scale_x, scale_y) = scale(overview);
+                scale_x <= factor_x * (1.0 + 1e-9) && scale_y <= factor_y * (1.0 + 1e-9)
+            })
+            .max_by(|a, b| scale(a).0.total_cmp(&scale(b).0))
+            .unwrap_or(primary);
+
+        let (scale_x, scale_y) = scale(level);
+        let data = level.sample(
+            [
+                clamped.x as f64 / scale_x,
+                clamped.y as f64 / scale_y,
+                clamped.width as f64 / scale_x,
+                clamped.height as f64 / scale_y,
+            ],
+            out_width,
+            out_height,
+            fill,
+            options,
+        )?;
+        Ok(WindowData {
+            window: clamped,
+            width: out_width,
+            height: out_height,
+            num_samples: primary.num_samples,
+            interleave: Interleave::Pixel,
+            orientation: Orientation::default(),
+            data,
+        }
+        .with_interleave(options.interleave)
+        .with_orientation(options.orientation))
+    }
+}
+
+/// Scales the output size of a decimated read along with the window when it was clamped.
+fn clamped_size(
+    window: Window,
+    clamped: Window,
+    out_width: usize,
+    out_height: usize,
+) -> (usize, usize) {
+    if clamped == window {
+        return (out_width, out_height);
+    }
+    if clamped.is_empty() {
+        return (0, 0);
+    }
+    let scale = |out_size: usize, clamped: usize, size: usize| {
+        ((out_size * clamped) as f64 / size as f64).round().max(1.0) as usize
+    };
+    (
+        scale(out_width, clamped.width, window.width),
+        scale(out_height, clamped.height, window.height),
+    )
+}
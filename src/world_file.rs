@@ -0,0 +1,76 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tiff::{TiffError, TiffFormatError, TiffResult};
+
+/// Reads the world file next to the image at the given path, if any.
+///
+/// The six parameters are returned in file order: the pixel width, the rotation terms, the
+/// negative pixel height, and the model coordinates of the center of the upper left pixel.
+///
+/// Ref: https://en.wikipedia.org/wiki/World_file
+pub(crate) fn read_world_file(image_path: &Path) -> TiffResult<Option<[f64; 6]>> {
+    let Some(path) = world_file_candidates(image_path)
+        .into_iter()
+        .find(|path| path.is_file())
+    else {
+        return Ok(None);
+    };
+
+    let contents = fs::read_to_string(&path)?;
+    let values = contents
+        .split_whitespace()
+        .map(|value| value.parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .ok()
+        .and_then(|values| <[f64; 6]>::try_from(values).ok())
+        .ok_or_else(|| {
+            TiffError::FormatError(TiffFormatError::Format(format!(
+                "World file {} must contain 6 numbers",
+                path.display()
+            )))
+        })?;
+    Ok(Some(values))
+}
+
+/// Returns the ModelTransformationTag matrix equivalent to the parameters of a world file.
+pub(crate) fn world_file_matrix(params: [f64; 6]) -> Vec<f64> {
+    let [a, d, b, e, c, f] = params;
+    // The world file refers to the center of the upper left pixel, the matrix to its corner
+    vec![
+        a,
+        b,
+        0.0,
+        c - (a + b) / 2.0,
+        d,
+        e,
+        0.0,
+        f - (d + e) / 2.0,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        1.0,
+    ]
+}
+
+/// Returns the possible paths of the world file of an image, e.g. "image.tfw", "image.tifw" and
+/// "image.wld" for "image.tif".
+fn world_file_candidates(image_path: &Path) -> Vec<PathBuf> {
+    let mut extensions = Vec::new();
+    if let Some(extension) = image_path.extension().and_then(|e| e.to_str()) {
+        let mut chars = extension.chars();
+        if let (Some(first), Some(last)) = (chars.next(), chars.next_back()) {
+            extensions.push(format!("{first}{last}w"));
+        }
+        extensions.push(format!("{extension}w"));
+    }
+    extensions.push("wld".to_string());
+    extensions
+        .into_iter()
+        .map(|extension| image_path.with_extension(extension))
+        .collect()
+}
@@ -0,0 +1,223 @@
+use crate::{GeoTiff, Resampling};
+
+/// The transparency mask written with a raster, see [WriteOptions::mask].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum WriteMask {
+    /// No mask is written.
+    #[default]
+    None,
+    /// The pixels which are not nodata are valid, see [crate::InMemoryRaster::is_nodata].
+    FromNodata,
+    /// Whether each pixel is valid, in row-major order.
+    Explicit(Vec<bool>),
+}
+
+/// How the raster data is organized in the file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Layout {
+    /// Strips of about 8 KiB of uncompressed data, like GDAL.
+    #[default]
+    Strips,
+    /// Square tiles of the given size, which must be a multiple of 16.
+    Tiles(u32),
+}
+
+/// The compression of the raster data, among those the decoder of this crate supports.
+///
+/// The chunks of an image are compressed in parallel with the `rayon` feature, which speeds up
+/// the writing of large tiled images with [Compression::Deflate] in particular. They are
+/// compressed in batches written one after the other, so that the compressed chunks of the whole
+/// image are never held in memory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    #[default]
+    None,
+    Lzw,
+    Deflate,
+}
+
+impl Compression {
+    /// Returns the value of the Compression tag.
+    pub fn to_u16(self) -> u16 {
+        match self {
+            Compression::None => 1,
+            Compression::Lzw => 5,
+            Compression::Deflate => 8,
+        }
+    }
+}
+
+/// The reduced resolution images written after the raster, see [WriteOptions::overviews].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Overviews {
+    #[default]
+    None,
+    /// One overview per factor, each pixel covering `factor` x `factor` pixels of the raster.
+    Factors(Vec<usize>),
+    /// Overviews with factors of successive powers of 2, until the smallest one fits in a tile,
+    /// or in 256 x 256 pixels for strips.
+    Auto,
+}
+
+/// The byte order of a TIFF file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ByteOrder {
+    #[default]
+    LittleEndian,
+    /// The byte order of Motorola processors, which some older archives use.
+    BigEndian,
+}
+
+/// Where the IFDs are written relative to the data of their images.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IfdPlacement {
+    /// Each IFD follows the data of its image, like libtiff.
+    #[default]
+    AfterData,
+    /// Each IFD precedes the data of its image.
+    BeforeData,
+    /// The IFDs of all the images are written at the start of the file, followed by the data of
+    /// the images in reverse order, i.e. the overviews from the smallest one and then the full
+    /// resolution image, so that readers of remote files get the whole layout of the file with
+    /// the first request.
+    Start,
+}
+
+/// Options for writing rasters, see [crate::InMemoryRaster::write_with_options].
+#[derive(Debug, Clone)]
+pub struct WriteOptions {
+    /// The internal mask written after the image, as a 1-bit IFD with the mask bit of the
+    /// NewSubfileType tag, which GDAL and QGIS use as the mask band of the image.
+    pub mask: WriteMask,
+    /// Whether the mask of RGB outputs is written as an alpha band instead, which web renderers
+    /// prefer to nodata values, see [crate::InMemoryRaster::write_rgb].
+    pub alpha: bool,
+    /// Whether the DateTime tag is omitted, so that identical rasters are written as identical
+    /// bytes, e.g. for content-addressed storage or test fixtures. The rest of the file only
+    /// depends on the raster and the options.
+    pub reproducible: bool,
+    pub layout: Layout,
+    pub compression: Compression,
+    /// Whether the samples are differenced along rows before compression, which usually makes
+    /// them more compressible: horizontal differencing for 8-bit samples and the floating point
+    /// predictor for 64-bit floats.
+    pub predictor: bool,
+    /// The overviews, which have the nodata value of the raster but no mask.
+    pub overviews: Overviews,
+    /// How the pixels of the raster are combined into the pixels of the overviews. With
+    /// [Resampling::Average], each overview is accumulated from the previous one whose factor
+    /// divides its factor, if any, rather than from the raster.
+    ///
+    /// The averages are accumulated from the chunks of the raster while they are written, or in
+    /// a pass beforehand for [IfdPlacement::Start], which writes the chunks of the overviews
+    /// first. The other resamplings combine pixels of neighboring chunks, so they are computed
+    /// from the whole raster.
+    pub overview_resampling: Resampling,
+    pub ifd_placement: IfdPlacement,
+    /// Whether the entries of the IFDs are sorted by tag, as TIFF 6.0 requires. Otherwise, they
+    /// are written in the order this crate sets them, e.g. to test the tolerance of readers.
+    pub sort_tags: bool,
+    /// The alignment in bytes of the IFDs, of the values which do not fit in their entries and of
+    /// the strips or tiles. TIFF 6.0 requires word alignment, i.e. 2, and some readers expect 4
+    /// or 8, while 1 gives the most compact files.
+    pub alignment: usize,
+    pub byte_order: ByteOrder,
+    /// Whether the private tags of GDAL are written: GDAL_NODATA for the nodata value and
+    /// GDAL_METADATA for the lineage. Baseline readers ignore them, but some picky validators
+    /// reject files with unknown tags.
+    pub gdal_tags: bool,
+    /// The ICC profile of RGB outputs, which must be an RGB profile, e.g. sRGB for
+    /// color-managed print or cartography exports, see [crate::InMemoryRaster::write_rgb]. It is
+    /// ignored for single-band rasters.
+    pub icc_profile: Option<Vec<u8>>,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self {
+            mask: WriteMask::None,
+            alpha: false,
+            reproducible: false,
+            layout: Layout::Strips,
+            compression: Compression::None,
+            predictor: false,
+            overviews: Overviews::None,
+            overview_resampling: Resampling::default(),
+            ifd_placement: IfdPlacement::AfterData,
+            sort_tags: true,
+            alignment: 2,
+            byte_order: ByteOrder::LittleEndian,
+            gdal_tags: true,
+            icc_profile: None,
+        }
+    }
+}
+
+/// Presets of [WriteOptions] producing files which the usual readers handle well, without
+/// choosing the layout of the file in detail. The mask, alpha and reproducible options are left
+/// to their defaults, e.g. `WriteOptions { mask: WriteMask::FromNodata, ..Profile::Cog.into() }`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    /// A Cloud Optimized GeoTIFF, like the COG driver of GDAL: 512 x 512 tiles compressed with
+    /// Deflate and a predictor, overviews down to a single tile with nearest neighbour
+    /// resampling, and the IFDs at the start of the file.
+    Cog,
+    /// The conventions of GDAL for general-purpose files: strips compressed with Deflate and a
+    /// predictor, with the nodata value and the lineage in the private tags of GDAL.
+    GdalCompatible,
+    /// Only baseline TIFF 6.0 and GeoTIFF 1.0 features, for strict or legacy readers:
+    /// uncompressed strips, without the private tags of GDAL.
+    BaselineGeoTiff,
+}
+
+impl From<Profile> for WriteOptions {
+    fn from(profile: Profile) -> Self {
+        match profile {
+            Profile::Cog => WriteOptions {
+                layout: Layout::Tiles(512),
+                compression: Compression::Deflate,
+                predictor: true,
+                overviews: Overviews::Auto,
+                ifd_placement: IfdPlacement::Start,
+                ..Default::default()
+            },
+            Profile::GdalCompatible => WriteOptions {
+                compression: Compression::Deflate,
+                predictor: true,
+                ..Default::default()
+            },
+            Profile::BaselineGeoTiff => WriteOptions {
+                gdal_tags: false,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl GeoTiff {
+    /// Returns options writing rasters like the primary image of this file, e.g. to copy or retile
+    /// it without changing its encoding: its byte order, tiles if they are square with a size
+    /// multiple of 16, compression if it can be written, and predictor.
+    pub fn write_options(&self) -> WriteOptions {
+        let primary = self.primary();
+        let (block_width, block_height) = primary.block_size;
+        let layout =
+            if primary.tiled && block_width == block_height && block_width.is_multiple_of(16) {
+                Layout::Tiles(block_width as u32)
+            } else {
+                Layout::Strips
+            };
+        let compression = match primary.compression {
+            5 => Compression::Lzw,
+            8 | 32946 => Compression::Deflate,
+            _ => Compression::None,
+        };
+        WriteOptions {
+            layout,
+            compression,
+            predictor: primary.predictor != 1,
+            byte_order: self.byte_order(),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,78 @@
+#![cfg(feature = "decode")]
+
+use common::encode_gray8;
+use geo_types::Coord;
+use geotiff::{GeoTiff, Mismatch};
+use tiff::tags::Tag;
+
+mod common;
+
+fn grid(projected_type: u16, scale: f64, origin: (f64, f64)) -> GeoTiff {
+    let data = encode_gray8(4, 4, &[0; 16], |encoder| {
+        encoder
+            .write_tag(
+                Tag::GeoKeyDirectoryTag,
+                &[1u16, 1, 0, 2, 1024, 0, 1, 1, 3072, 0, 1, projected_type][..],
+            )
+            .unwrap();
+        encoder
+            .write_tag(Tag::ModelPixelScaleTag, &[scale, scale, 0.0][..])
+            .unwrap();
+        encoder
+            .write_tag(
+                Tag::ModelTiepointTag,
+                &[0.0, 0.0, 0.0, origin.0, origin.1, 0.0][..],
+            )
+            .unwrap();
+    });
+    GeoTiff::from_bytes(&data).unwrap()
+}
+
+#[test]
+fn test_aligned() {
+    let reference = grid(32632, 10.0, (1000.0, 2000.0));
+    assert!(reference.is_aligned_with(&reference, 1e-6).is_aligned());
+
+    let shifted = grid(32632, 10.0, (1050.0, 1980.0));
+    assert!(reference.is_aligned_with(&shifted, 1e-6).is_aligned());
+}
+
+#[test]
+fn test_mismatches() {
+    let reference = grid(32632, 10.0, (1000.0, 2000.0));
+
+    let report = reference.is_aligned_with(&grid(32632, 10.0, (1003.0, 2000.0)), 1e-6);
+    assert_eq!(report.mismatches.len(), 1);
+    let Mismatch::GridPhase { offset } = report.mismatches[0] else {
+        panic!("unexpected mismatch {:?}", report.mismatches[0]);
+    };
+    assert!((offset.x - 0.3).abs() < 1e-9);
+    assert_eq!(offset.y, 0.0);
+    assert!(reference
+        .is_aligned_with(&grid(32632, 10.0, (1003.0, 2000.0)), 0.5)
+        .is_aligned());
+
+    let report = reference.is_aligned_with(&grid(32632, 20.0, (1000.0, 2000.0)), 1e-6);
+    assert_eq!(
+        report.mismatches,
+        vec![Mismatch::Resolution {
+            ours: [Coord { x: 10.0, y: 0.0 }, Coord { x: 0.0, y: -10.0 }],
+            theirs: [Coord { x: 20.0, y: 0.0 }, Coord { x: 0.0, y: -20.0 }],
+        }]
+    );
+
+    let report = reference.is_aligned_with(&grid(32633, 10.0, (1000.0, 2000.0)), 1e-6);
+    assert_eq!(report.mismatches.len(), 1);
+    assert!(matches!(
+        report.mismatches[0],
+        Mismatch::Crs {
+            ours: Some(32632),
+            theirs: Some(32633),
+            ..
+        }
+    ));
+
+    let plain = GeoTiff::from_bytes(&encode_gray8(4, 4, &[0; 16], |_| {})).unwrap();
+    let report = reference.is_aligned_with(&plain, 1e-6);
+    assert!(report.mismatches.contains(&Mismatch::MissingTransform));
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "decode")]
+
+use common::encode_gray8;
+use geo_types::{coord, MultiPolygon, Rect};
+use geotiff::{AntimeridianPolicy, GeoTiff, GeographicExtent};
+use tiff::tags::Tag;
+
+mod common;
+
+fn encode_geographic(west: f64, north: f64) -> Vec<u8> {
+    encode_gray8(4, 2, &[0; 8], |encoder| {
+        encoder
+            .write_tag(
+                Tag::GeoKeyDirectoryTag,
+                &[1u16, 1, 1, 2, 1024, 0, 1, 2, 2048, 0, 1, 4326][..],
+            )
+            .unwrap();
+        encoder
+            .write_tag(Tag::ModelPixelScaleTag, &[5.0, 5.0, 0.0][..])
+            .unwrap();
+        encoder
+            .write_tag(
+                Tag::ModelTiepointTag,
+                &[0.0, 0.0, 0.0, west, north, 0.0][..],
+            )
+            .unwrap();
+    })
+}
+
+#[test]
+fn test_extent_crossing_antimeridian() {
+    for west in [170.0, -190.0] {
+        let geotiff = GeoTiff::from_bytes(&encode_geographic(west, -10.0)).unwrap();
+
+        let extent = geotiff
+            .geographic_extent(AntimeridianPolicy::Split)
+            .unwrap();
+        assert!(extent.crosses_antimeridian());
+        assert_eq!(
+            extent,
+            GeographicExtent::Split(MultiPolygon::new(vec![
+                Rect::new(coord! { x: 170.0, y: -20.0 }, coord! { x: 180.0, y: -10.0 })
+                    .to_polygon(),
+                Rect::new(
+                    coord! { x: -180.0, y: -20.0 },
+                    coord! { x: -170.0, y: -10.0 }
+                )
+                .to_polygon(),
+            ]))
+        );
+
+        assert_eq!(
+            geotiff.geographic_extent(AntimeridianPolicy::Crossing),
+            Some(GeographicExtent::Crossing {
+                west: 170.0,
+                south: -20.0,
+                east: -170.0,
+                north: -10.0,
+            })
+        );
+    }
+}
+
+#[test]
+fn test_extent_not_crossing_antimeridian() {
+    let geotiff = GeoTiff::from_bytes(&encode_geographic(350.0, 50.0)).unwrap();
+    let extent = geotiff
+        .geographic_extent(AntimeridianPolicy::Crossing)
+        .unwrap();
+    assert!(!extent.crosses_antimeridian());
+    assert_eq!(
+        extent,
+        GeographicExtent::Crossing {
+            west: -10.0,
+            south: 40.0,
+            east: 10.0,
+            north: 50.0,
+        }
+    );
+
+    let geotiff = common::read_geotiff("resources/zh_dem_25.tif");
+    assert_eq!(geotiff.geographic_extent(AntimeridianPolicy::Split), None);
+}
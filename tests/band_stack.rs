@@ -0,0 +1,143 @@
+#![cfg(feature = "decode")]
+
+use common::encode_gray8;
+use geotiff::{
+    AffineTransform, BandStack, GeoTiff, Interleave, OutOfBounds, ReadOptions, Resampling, Window,
+};
+use tiff::tags::Tag;
+
+mod common;
+
+fn band(width: u32, height: u32, data: &[u8], epsg: u16, origin_x: f64, size: f64) -> GeoTiff {
+    let data = encode_gray8(width, height, data, |encoder| {
+        encoder
+            .write_tag(
+                Tag::GeoKeyDirectoryTag,
+                &[1u16, 1, 0, 2, 1024, 0, 1, 1, 3072, 0, 1, epsg][..],
+            )
+            .unwrap();
+        encoder
+            .write_tag(Tag::ModelPixelScaleTag, &[size, size, 0.0][..])
+            .unwrap();
+        encoder
+            .write_tag(
+                Tag::ModelTiepointTag,
+                &[0.0, 0.0, 0.0, origin_x, 2000.0, 0.0][..],
+            )
+            .unwrap();
+    });
+    GeoTiff::from_bytes(&data).unwrap()
+}
+
+#[test]
+fn test_band_stack() {
+    let stack = BandStack::new(vec![
+        band(4, 2, &[1, 2, 3, 4, 5, 6, 7, 8], 32632, 1000.0, 10.0),
+        band(2, 1, &[10, 20], 32632, 1000.0, 20.0),
+        band(4, 2, &[11, 12, 13, 14, 15, 16, 17, 18], 32632, 1010.0, 10.0),
+    ])
+    .unwrap();
+    assert_eq!(stack.num_bands(), 3);
+    assert_eq!(stack.window(), Window::new(0, 0, 4, 2));
+    assert_eq!(stack.band_scale(1).unwrap().x, 0.5);
+    assert_eq!(stack.band_scale(3), None);
+
+    let options = ReadOptions {
+        out_of_bounds: OutOfBounds::Fill(255.0),
+        interleave: Interleave::Band,
+        ..Default::default()
+    };
+    let data = stack.read_window::<u8>(stack.window(), &options).unwrap();
+    assert_eq!((data.width, data.height, data.num_samples), (4, 2, 3));
+    assert_eq!(
+        data.data,
+        [
+            1, 2, 3, 4, 5, 6, 7, 8, //
+            10, 10, 20, 20, 10, 10, 20, 20, //
+            255, 11, 12, 13, 255, 15, 16, 17,
+        ]
+    );
+
+    let data = stack
+        .read_window::<u8>(Window::new(2, 1, 2, 1), &ReadOptions::default())
+        .unwrap();
+    assert_eq!(data.interleave, Interleave::Pixel);
+    assert_eq!(data.data, [7, 20, 16, 8, 20, 17]);
+    let clamp = ReadOptions {
+        out_of_bounds: OutOfBounds::Clamp,
+        ..Default::default()
+    };
+    let data = stack
+        .read_window::<u8>(Window::new(3, 1, 2, 1), &clamp)
+        .unwrap();
+    assert_eq!(data.window, Window::new(3, 1, 1, 1));
+    assert_eq!(data.data, [8, 20, 17]);
+    let error = ReadOptions {
+        out_of_bounds: OutOfBounds::Error,
+        ..Default::default()
+    };
+    assert!(stack
+        .read_window::<u8>(Window::new(3, 1, 2, 1), &error)
+        .is_err());
+}
+
+#[test]
+fn test_band_stack_grid() {
+    let bands = || {
+        vec![
+            band(4, 2, &[1, 2, 3, 4, 5, 6, 7, 8], 32632, 1000.0, 10.0),
+            band(2, 1, &[10, 20], 32632, 1000.0, 20.0),
+        ]
+    };
+    #[rustfmt::skip]
+    let grid = || AffineTransform::from_tag_matrix([
+        20.0, 0.0, 0.0, 1000.0,
+        0.0, -20.0, 0.0, 2000.0,
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    ]);
+
+    let stack = BandStack::new(bands())
+        .unwrap()
+        .with_grid(grid(), 2, 1)
+        .unwrap();
+    assert_eq!(stack.window(), Window::new(0, 0, 2, 1));
+    assert_eq!(stack.band_scale(0).unwrap().x, 2.0);
+    let data = stack
+        .read_window::<f64>(stack.window(), &ReadOptions::default())
+        .unwrap();
+    assert_eq!(data.data, [6.0, 10.0, 8.0, 20.0]);
+
+    let mut stack = BandStack::new(bands())
+        .unwrap()
+        .with_grid(grid(), 2, 1)
+        .unwrap();
+    stack.set_resampling(0, Resampling::Average);
+    let options = ReadOptions {
+        resampling: Resampling::Bilinear,
+        ..Default::default()
+    };
+    let data = stack.read_window::<f64>(stack.window(), &options).unwrap();
+    assert_eq!(data.data, [3.5, 10.0, 5.5, 20.0]);
+
+    #[rustfmt::skip]
+    let rotated = AffineTransform::from_tag_matrix([
+        0.0, 20.0, 0.0, 1000.0,
+        -20.0, 0.0, 0.0, 2000.0,
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    ]);
+    assert!(BandStack::new(bands())
+        .unwrap()
+        .with_grid(rotated, 2, 1)
+        .is_err());
+}
+
+#[test]
+fn test_band_stack_errors() {
+    let reference = || band(4, 2, &[0; 8], 32632, 1000.0, 10.0);
+    assert!(BandStack::new(vec![]).is_err());
+    assert!(BandStack::new(vec![reference(), band(4, 2, &[0; 8], 32633, 1000.0, 10.0)]).is_err());
+    let ungeoreferenced = GeoTiff::from_bytes(&encode_gray8(1, 1, &[0], |_| ())).unwrap();
+    assert!(BandStack::new(vec![reference(), ungeoreferenced]).is_err());
+}
@@ -0,0 +1,129 @@
+#![cfg(feature = "decode")]
+
+use common::{raster, read_raster, Georeferencing};
+use geotiff::focal::{focal, FocalOperation};
+use geotiff::{
+    Block, BlockOptions, GeoTiff, Layout, OutOfBounds, ReadOptions, Window, WriteOptions,
+};
+
+mod common;
+
+/// A 40 x 20 raster in tiles of 16 x 16, whose values are their index in row-major order, with
+/// the nodata value -1.
+fn geotiff() -> GeoTiff {
+    let data = (0..40 * 20).map(|i| i as f64).collect();
+    let options = WriteOptions {
+        layout: Layout::Tiles(16),
+        ..Default::default()
+    };
+    read_raster(
+        &raster(Georeferencing::Wgs84, 40, 20, data, Some(-1.0)),
+        &options,
+    )
+}
+
+#[test]
+fn test_process_blocks() {
+    let geotiff = geotiff();
+    let window = Window::new(10, 5, 30, 20);
+    for parallel in [false, true] {
+        let options = BlockOptions {
+            halo: 1,
+            parallel,
+            read_options: ReadOptions {
+                out_of_bounds: OutOfBounds::Fill(-1.0),
+                ..Default::default()
+            },
+        };
+        let blocks = geotiff
+            .process_blocks(window, 16 * 16, &options, |block| {
+                // The halo extends the block by one pixel on each side
+                assert_eq!(block.data.width, block.window.width + 2);
+                let (x, y) = (block.window.x, block.window.y);
+                assert_eq!(block.get(0, 0, 0), Some((y * 40 + x) as f32));
+                let above = if y > 0 {
+                    ((y - 1) * 40 + x) as f32
+                } else {
+                    -1.0
+                };
+                assert_eq!(block.get(0, -1, 0), Some(above));
+                assert_eq!(block.get(-2, 0, 0), None);
+                let sum = (0..block.window.height as i64)
+                    .flat_map(|y| (0..block.window.width as i64).map(move |x| (x, y)))
+                    .map(|(x, y)| block.get(x, y, 0).unwrap() as f64)
+                    .sum::<f64>();
+                (block.index, block.window, sum)
+            })
+            .unwrap();
+
+        // Clipped to the window within the raster, on the boundaries of the tiles
+        let windows = blocks
+            .iter()
+            .map(|(_, window, _)| *window)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            windows,
+            [
+                Window::new(10, 5, 6, 11),
+                Window::new(16, 5, 16, 11),
+                Window::new(32, 5, 8, 11),
+                Window::new(10, 16, 6, 4),
+                Window::new(16, 16, 16, 4),
+                Window::new(32, 16, 8, 4),
+            ]
+        );
+        assert!(blocks
+            .iter()
+            .enumerate()
+            .all(|(index, block)| block.0 == index));
+        let expected = (5..20)
+            .flat_map(|y| (10..40).map(move |x| (y * 40 + x) as f64))
+            .sum::<f64>();
+        assert_eq!(blocks.iter().map(|block| block.2).sum::<f64>(), expected);
+    }
+}
+
+#[test]
+fn test_process_blocks_ghost_pixels() {
+    let geotiff = geotiff();
+    let options = BlockOptions {
+        halo: 1,
+        read_options: ReadOptions {
+            out_of_bounds: OutOfBounds::Error,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    // The ghost pixels outside the raster are nodata rather than an error
+    let means = geotiff
+        .process_blocks(Window::full(40, 20), 16 * 16, &options, |block| {
+            let mut means = Vec::new();
+            for y in 0..block.window.height as i64 {
+                for x in 0..block.window.width as i64 {
+                    let neighbors = (-1..=1)
+                        .flat_map(|j| (-1..=1).map(move |i| (x + i, y + j)))
+                        .filter_map(|(i, j)| block.get(i, j, 0))
+                        .filter(|&value: &f64| value != -1.0)
+                        .collect::<Vec<_>>();
+                    let mean = neighbors.iter().sum::<f64>() / neighbors.len() as f64;
+                    means.push((block.window.x + x, block.window.y + y, mean));
+                }
+            }
+            means
+        })
+        .unwrap();
+
+    // The means across the boundaries of the blocks match those of the whole band
+    let expected = focal(geotiff.band(0).unwrap(), 1, FocalOperation::Mean).unwrap();
+    for (x, y, mean) in means.into_iter().flatten() {
+        assert_eq!(mean, expected.get(x as usize, y as usize));
+    }
+
+    let outside = geotiff.process_blocks(
+        Window::new(30, 0, 20, 20),
+        256,
+        &options,
+        |_: Block<f64>| (),
+    );
+    assert!(outside.is_err());
+}
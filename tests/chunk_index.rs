@@ -0,0 +1,59 @@
+#![cfg(feature = "decode")]
+
+use common::{write_raster, Georeferencing};
+use geotiff::{GeoTiff, Layout, OpenOptions, Window, WriteOptions};
+
+mod common;
+
+fn write(width: usize, height: usize, layout: Layout) -> Vec<u8> {
+    let data = (0..width * height).map(|i| i as f64).collect();
+    let raster = common::raster(Georeferencing::Wgs84, width, height, data, None);
+    let options = WriteOptions {
+        layout,
+        ..Default::default()
+    };
+    write_raster(&raster, &options)
+}
+
+#[test]
+fn test_preload_index() {
+    let bytes = write(40, 20, Layout::Tiles(16));
+    assert_eq!(GeoTiff::from_bytes(&bytes).unwrap().chunk_index(), None);
+
+    let geotiff = OpenOptions::new()
+        .preload_index(true)
+        .read_bytes(&bytes)
+        .unwrap();
+    let index = geotiff.chunk_index().unwrap();
+    assert_eq!((index.chunk_width, index.chunk_height), (16, 16));
+    assert_eq!((index.chunks_across, index.chunks_down), (3, 2));
+    assert_eq!(index.offsets.len(), 6);
+    // Uncompressed tiles of 16 x 16 doubles
+    let range = index.byte_range(4).unwrap();
+    assert_eq!(range.end - range.start, 16 * 16 * 8);
+    assert!(range.end as usize <= bytes.len());
+    let first = f64::from_le_bytes(bytes[range.start as usize..][..8].try_into().unwrap());
+    assert_eq!(first, (16 * 40 + 16) as f64);
+    assert_eq!(index.byte_range(6), None);
+
+    assert_eq!(index.chunks_in_window(Window::new(10, 0, 10, 5)), [0, 1]);
+    assert_eq!(
+        index.chunks_in_window(Window::new(-5, 15, 50, 50)),
+        [0, 1, 2, 3, 4, 5]
+    );
+    assert!(index.chunks_in_window(Window::new(48, 0, 5, 5)).is_empty());
+}
+
+#[test]
+fn test_preload_strip_index() {
+    let geotiff = OpenOptions::new()
+        .preload_index(true)
+        .read_bytes(&write(400, 30, Layout::Strips))
+        .unwrap();
+    // Strips of 2 rows of 400 doubles
+    let index = geotiff.chunk_index().unwrap();
+    assert_eq!((index.chunk_width, index.chunk_height), (400, 2));
+    assert_eq!((index.chunks_across, index.chunks_down), (1, 15));
+    assert_eq!(index.offsets.len(), 15);
+    assert_eq!(index.chunks_in_window(Window::new(100, 3, 10, 2)), [1, 2]);
+}
@@ -0,0 +1,96 @@
+#![cfg(feature = "decode")]
+
+use common::encode_gray8;
+use geotiff::classification::{self, Connectivity};
+use geotiff::{GeoTiff, Layout, WriteOptions};
+use tiff::tags::Tag;
+
+mod common;
+
+fn read_image(width: u32, height: u32, data: &[u8]) -> GeoTiff {
+    let data = encode_gray8(width, height, data, |encoder| {
+        encoder.write_tag(Tag::GdalNodata, "255").unwrap();
+    });
+    GeoTiff::from_bytes(&data).unwrap()
+}
+
+#[test]
+fn test_sieve() {
+    #[rustfmt::skip]
+    let geotiff = read_image(5, 4, &[
+        1, 1, 1, 2, 2,
+        1, 3, 1, 2, 2,
+        1, 1, 4, 2, 2,
+        1, 1, 1, 2, 255,
+    ]);
+    let band = geotiff.band(0).unwrap();
+
+    let sieved = classification::sieve(band, 2, Connectivity::Four).unwrap();
+    #[rustfmt::skip]
+    let expected = [
+        1.0, 1.0, 1.0, 2.0, 2.0,
+        1.0, 1.0, 1.0, 2.0, 2.0,
+        1.0, 1.0, 1.0, 2.0, 2.0,
+        1.0, 1.0, 1.0, 2.0, f64::NAN,
+    ];
+    assert_eq!(sieved.data[..19], expected[..19]);
+    assert!(sieved.get(4, 3).is_nan());
+
+    // The diagonal pixels of value 3 only form a component with 8-connectivity
+    #[rustfmt::skip]
+    let geotiff = read_image(3, 3, &[
+        1, 1, 2,
+        1, 3, 2,
+        1, 1, 3,
+    ]);
+    let band = geotiff.band(0).unwrap();
+    let sieved = classification::sieve(band, 2, Connectivity::Eight).unwrap();
+    assert_eq!(sieved.get(1, 1), 3.0);
+    let sieved = classification::sieve(band, 2, Connectivity::Four).unwrap();
+    assert_eq!(sieved.get(1, 1), 1.0);
+    assert_eq!(sieved.get(2, 2), 1.0);
+}
+
+#[test]
+fn test_reclassify() {
+    let geotiff = read_image(3, 2, &[0, 50, 100, 150, 200, 255]);
+    let band = geotiff.band(0).unwrap();
+
+    let rules = [(0.0..=50.0, 1.0), (50.0..=150.0, 2.0), (180.0..=190.0, 3.0)];
+    let classes = classification::reclassify(band, &rules).unwrap();
+    assert_eq!(classes.data[..4], [1.0, 1.0, 2.0, 2.0]);
+    assert!(classes.get(1, 1).is_nan());
+    assert!(classes.get(2, 1).is_nan());
+
+    let bytes = classes.to_bytes().unwrap();
+    let written = GeoTiff::from_bytes(&bytes).unwrap();
+    let written = written
+        .band(0)
+        .unwrap()
+        .read_window(written.primary().window())
+        .unwrap();
+    assert_eq!(written[..4], [1.0, 1.0, 2.0, 2.0]);
+}
+
+#[test]
+fn test_reclassify_into() {
+    let geotiff = read_image(3, 2, &[0, 50, 100, 150, 200, 255]);
+    let band = geotiff.band(0).unwrap();
+
+    let rules = [(0.0..=50.0, 1.0), (50.0..=150.0, 2.0)];
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    let options = WriteOptions {
+        layout: Layout::Tiles(16),
+        ..Default::default()
+    };
+    classification::reclassify_into(band, &rules, &mut buffer, &options).unwrap();
+    let written = GeoTiff::from_bytes(&buffer.into_inner()).unwrap();
+    assert!(written.nodata().unwrap().is_nan());
+    let written = written
+        .band(0)
+        .unwrap()
+        .read_window(written.primary().window())
+        .unwrap();
+    assert_eq!(written[..4], [1.0, 1.0, 2.0, 2.0]);
+    assert!(written[4..].iter().all(|value| value.is_nan()));
+}
@@ -0,0 +1,84 @@
+#![cfg(feature = "decode")]
+
+use common::encode_gray8;
+use geotiff::{CollectionStats, GeoTiff, RenderOptions, Stretch, Window};
+use tiff::tags::Tag;
+
+mod common;
+
+fn file(data: &[u8]) -> GeoTiff {
+    GeoTiff::from_bytes(&encode_gray8(2, 2, data, |encoder| {
+        encoder.write_tag(Tag::GdalNodata, "255").unwrap();
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_collection_stats() {
+    let files = [file(&[0, 10, 20, 255]), file(&[30, 40, 50, 60])];
+    let mut stats = CollectionStats::new(0.0, 100.0, 10);
+    assert_eq!(stats.mean(), None);
+    assert_eq!(stats.stretch_range(Stretch::MinMax), None);
+    for file in &files {
+        stats.add_band(file.band(0).unwrap()).unwrap();
+    }
+    assert_eq!(stats.count(), 7);
+    assert_eq!((stats.min(), stats.max()), (Some(0.0), Some(60.0)));
+    assert_eq!(stats.mean(), Some(30.0));
+    assert!((stats.variance().unwrap() - 400.0).abs() < 1e-9);
+    assert_eq!(stats.histogram().counts, [1, 1, 1, 1, 1, 1, 1, 0, 0, 0]);
+
+    let mut merged = CollectionStats::new(0.0, 100.0, 10);
+    for file in &files {
+        let mut partial = CollectionStats::new(0.0, 100.0, 10);
+        partial.add_band(file.band(0).unwrap()).unwrap();
+        merged.merge(&partial);
+    }
+    assert_eq!(merged.count(), stats.count());
+    assert_eq!(merged.histogram(), stats.histogram());
+    assert!((merged.mean().unwrap() - 30.0).abs() < 1e-9);
+    assert!((merged.std_dev().unwrap() - 20.0).abs() < 1e-9);
+
+    assert_eq!(
+        stats.stretch_range(Stretch::StdDev(1.0)),
+        Some((10.0, 50.0))
+    );
+    let (low, high) = stats
+        .stretch_range(Stretch::PercentClip {
+            low: 0.0,
+            high: 50.0,
+        })
+        .unwrap();
+    assert_eq!(low, 0.0);
+    assert!((high - 35.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_collection_stretch() {
+    let mut stats = CollectionStats::new(0.0, 100.0, 10);
+    stats.add(0.0);
+    stats.add(f64::NAN);
+    stats.add(200.0);
+    assert_eq!(stats.count(), 2);
+    assert_eq!(stats.histogram().above, 1);
+    assert_eq!(
+        stats.stretch_range(Stretch::PercentClip {
+            low: 0.0,
+            high: 100.0
+        }),
+        Some((0.0, 200.0))
+    );
+
+    // Both files are rendered with the stretch of the collection.
+    let (low, high) = stats.stretch_range(Stretch::MinMax).unwrap();
+    let options = RenderOptions {
+        stretch: Stretch::Range { low, high },
+        ..Default::default()
+    };
+    let image = file(&[0, 100, 200, 255])
+        .render_window(Window::full(2, 2), 2, 2, &options)
+        .unwrap();
+    assert_eq!(image.get_pixel(0, 0), [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(1, 0), [128, 128, 128, 255]);
+    assert_eq!(image.get_pixel(0, 1), [255, 255, 255, 255]);
+}
@@ -1,8 +1,175 @@
-use std::fs::File;
+#![cfg(feature = "decode")]
+
+use std::io::Cursor;
 use std::path::Path;
 
-use geotiff::GeoTiff;
+use geotiff::{
+    DocumentInfo, GeoKeyDirectory, GeoTiff, InMemoryRaster, RawTag, TransformTags, WriteOptions,
+};
+use tiff::encoder::{colortype, DirectoryEncoder, TiffEncoder, TiffKindStandard};
+use tiff::tags::Tag;
 
+#[allow(dead_code)]
 pub fn read_geotiff<P: AsRef<Path>>(path: P) -> GeoTiff {
-    GeoTiff::read(File::open(path).expect("File I/O error")).expect("File I/O error")
+    GeoTiff::open_path(path).expect("File I/O error")
+}
+
+/// Encodes a single-band 8-bit TIFF in memory, letting the caller add tags.
+#[allow(dead_code)]
+pub fn encode_gray8<F>(width: u32, height: u32, data: &[u8], write_tags: F) -> Vec<u8>
+where
+    F: Fn(&mut DirectoryEncoder<&mut Cursor<Vec<u8>>, TiffKindStandard>),
+{
+    encode_gray8_images(&[(width, height, data)], |_, encoder| write_tags(encoder))
+}
+
+/// Encodes several single-band 8-bit images given as `(width, height, data)` into one TIFF in
+/// memory, letting the caller add tags to the image with the given index.
+#[allow(dead_code)]
+pub fn encode_gray8_images<F>(images: &[(u32, u32, &[u8])], write_tags: F) -> Vec<u8>
+where
+    F: Fn(usize, &mut DirectoryEncoder<&mut Cursor<Vec<u8>>, TiffKindStandard>),
+{
+    let mut buffer = Cursor::new(Vec::new());
+    let mut encoder = TiffEncoder::new(&mut buffer).expect("Encoding error");
+    for (index, (width, height, data)) in images.iter().enumerate() {
+        let mut image = encoder
+            .new_image::<colortype::Gray8>(*width, *height)
+            .expect("Encoding error");
+        write_tags(index, image.encoder());
+        image.write_data(data).expect("Encoding error");
+    }
+    buffer.into_inner()
+}
+
+/// Reads a single-band 8-bit grid of 10 m pixels in UTM zone 32N, whose top left corner is at
+/// the given easting and at northing 2000, with the nodata value 255.
+#[allow(dead_code)]
+pub fn grid(width: u32, height: u32, origin_x: f64, data: &[u8]) -> GeoTiff {
+    let data = encode_gray8(width, height, data, |encoder| {
+        encoder
+            .write_tag(
+                Tag::GeoKeyDirectoryTag,
+                &[1u16, 1, 0, 2, 1024, 0, 1, 1, 3072, 0, 1, 32632][..],
+            )
+            .unwrap();
+        encoder
+            .write_tag(Tag::ModelPixelScaleTag, &[10.0, 10.0, 0.0][..])
+            .unwrap();
+        encoder
+            .write_tag(
+                Tag::ModelTiepointTag,
+                &[0.0, 0.0, 0.0, origin_x, 2000.0, 0.0][..],
+            )
+            .unwrap();
+        encoder.write_tag(Tag::GdalNodata, "255").unwrap();
+    });
+    GeoTiff::from_bytes(&data).unwrap()
+}
+
+/// The georeferencing of the rasters of [raster].
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy)]
+pub enum Georeferencing {
+    /// Pixels of 0.1° in WGS 84, whose top left corner is at 7°E 46°N.
+    Wgs84,
+    /// Pixels of 10 m in UTM zone 32N, whose top left corner is at (1000, 2000).
+    Utm32,
+}
+
+/// Returns a raster of 64-bit floats with the given georeferencing, and the data in row-major
+/// order.
+#[allow(dead_code)]
+pub fn raster(
+    georeferencing: Georeferencing,
+    width: usize,
+    height: usize,
+    data: Vec<f64>,
+    nodata: Option<f64>,
+) -> InMemoryRaster {
+    assert_eq!(data.len(), width * height);
+    let (epsg, scale, origin) = match georeferencing {
+        Georeferencing::Wgs84 => (4326, 0.1, (7.0, 46.0)),
+        Georeferencing::Utm32 => (32632, 10.0, (1000.0, 2000.0)),
+    };
+    InMemoryRaster {
+        width,
+        height,
+        data,
+        nodata,
+        geo_key_directory: Some(GeoKeyDirectory::from_epsg(epsg)),
+        transform_tags: TransformTags {
+            pixel_scale: Some(vec![scale, scale, 0.0]),
+            tie_points: Some(vec![0.0, 0.0, 0.0, origin.0, origin.1, 0.0]),
+            model_transformation: None,
+        },
+        document_info: DocumentInfo::default(),
+        lineage: None,
+        exif: None,
+    }
+}
+
+/// Writes the raster in memory with the given options.
+#[allow(dead_code)]
+pub fn write_raster(raster: &InMemoryRaster, options: &WriteOptions) -> Vec<u8> {
+    let mut buffer = Cursor::new(Vec::new());
+    raster.write_with_options(&mut buffer, options).unwrap();
+    buffer.into_inner()
+}
+
+/// Reads the raster written in memory with the given options, see [write_raster].
+#[allow(dead_code)]
+pub fn read_raster(raster: &InMemoryRaster, options: &WriteOptions) -> GeoTiff {
+    GeoTiff::from_bytes(&write_raster(raster, options)).unwrap()
+}
+
+/// Asserts that the values are equal, NaN being equal to NaN.
+#[allow(dead_code)]
+pub fn assert_values(actual: &[f64], expected: &[f64]) {
+    assert_eq!(actual.len(), expected.len());
+    for (actual, expected) in actual.iter().zip(expected) {
+        assert!(
+            actual == expected || (actual.is_nan() && expected.is_nan()),
+            "{actual:?} != {expected:?}"
+        );
+    }
+}
+
+/// Returns an Exif entry of ASCII type with the given value.
+#[allow(dead_code)]
+pub fn ascii(tag: u16, value: &str) -> RawTag {
+    RawTag {
+        tag,
+        field_type: 2,
+        count: value.len() as u32 + 1,
+        bytes: value.bytes().chain([0]).collect(),
+    }
+}
+
+/// Returns an Exif entry of RATIONAL type with the given numerators and denominators.
+#[allow(dead_code)]
+pub fn rationals(tag: u16, values: &[(u32, u32)]) -> RawTag {
+    RawTag {
+        tag,
+        field_type: 5,
+        count: values.len() as u32,
+        bytes: values
+            .iter()
+            .flat_map(|(numerator, denominator)| {
+                [numerator.to_le_bytes(), denominator.to_le_bytes()]
+            })
+            .flatten()
+            .collect(),
+    }
+}
+
+/// Returns an Exif entry of SHORT type with the given value.
+#[allow(dead_code)]
+pub fn short(tag: u16, value: u16) -> RawTag {
+    RawTag {
+        tag,
+        field_type: 3,
+        count: 1,
+        bytes: value.to_le_bytes().to_vec(),
+    }
 }
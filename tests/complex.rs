@@ -0,0 +1,68 @@
+#![cfg(feature = "num-complex")]
+
+use common::encode_gray8;
+use geotiff::{DType, GeoTiff, ReadOptions, Window};
+use num_complex::Complex;
+use tiff::tags::Tag;
+
+mod common;
+
+/// Encodes a single-band image of complex values, given by their little endian bytes.
+fn encode_complex(width: u32, height: u32, sample_format: u16, bits: u16, data: &[u8]) -> Vec<u8> {
+    let row_bytes = width * bits as u32 / 8;
+    encode_gray8(row_bytes, height, data, |encoder| {
+        encoder.write_tag(Tag::ImageWidth, width).unwrap();
+        encoder.write_tag(Tag::BitsPerSample, bits).unwrap();
+        encoder.write_tag(Tag::SampleFormat, sample_format).unwrap();
+    })
+}
+
+#[test]
+fn test_read_complex_int() {
+    let data = [(3i16, 4i16), (-1, 0), (0, 2), (0, -5)]
+        .iter()
+        .flat_map(|(re, im)| [re.to_le_bytes(), im.to_le_bytes()].concat())
+        .collect::<Vec<_>>();
+    let geotiff = GeoTiff::from_bytes(&encode_complex(2, 2, 5, 32, &data)).unwrap();
+    assert_eq!(geotiff.band_dtype(0), Some(DType::ComplexI16));
+    assert!(geotiff.capabilities().undecodable_images.is_empty());
+
+    let window = geotiff
+        .read_complex_window(Window::new(1, 0, 2, 1), &ReadOptions::default())
+        .unwrap();
+    assert_eq!(
+        window.data,
+        [Complex::new(-1.0, 0.0), Complex::new(0.0, 0.0)]
+    );
+
+    let band = geotiff.band(0).unwrap();
+    let full = geotiff.primary().window();
+    assert_eq!(band.read_complex(full).unwrap()[0], Complex::new(3.0, 4.0));
+    assert_eq!(band.read_amplitude(full).unwrap(), [5.0, 1.0, 2.0, 5.0]);
+    let phases = band.read_phase(full).unwrap();
+    let expected = [4f32.atan2(3.0), std::f32::consts::PI, 1.5707964, -1.5707964];
+    for (phase, expected) in phases.iter().zip(expected) {
+        assert!((phase - expected).abs() < 1e-6);
+    }
+
+    // The scalar reads are not available for complex values
+    assert!(band.read_window(full).is_err());
+}
+
+#[test]
+fn test_read_complex_float() {
+    let data = [(1.5f32, -2.5f32), (0.25, 8.0)]
+        .iter()
+        .flat_map(|(re, im)| [re.to_le_bytes(), im.to_le_bytes()].concat())
+        .collect::<Vec<_>>();
+    let geotiff = GeoTiff::from_bytes(&encode_complex(1, 2, 6, 64, &data)).unwrap();
+    assert_eq!(geotiff.band_dtype(0), Some(DType::ComplexF32));
+
+    let values = geotiff
+        .band(0)
+        .unwrap()
+        .read_complex(Window::new(0, 1, 1, 2))
+        .unwrap();
+    assert_eq!(values[0], Complex::new(0.25, 8.0));
+    assert!(values[1].re.is_nan());
+}
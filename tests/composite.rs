@@ -0,0 +1,92 @@
+#![cfg(feature = "decode")]
+
+use std::io::Cursor;
+
+use common::{assert_values, encode_gray8, grid};
+use geotiff::composite::{composite, CompositeInput, CompositeMethod};
+use geotiff::{GeoTiff, QaRule, QaSpec};
+use tiff::encoder::{colortype, TiffEncoder};
+use tiff::tags::Tag;
+
+mod common;
+
+#[test]
+fn test_composite() {
+    let scenes = [
+        grid(3, 1, 1000.0, &[1, 5, 255]),
+        grid(3, 1, 1000.0, &[3, 255, 255]),
+        grid(3, 1, 1000.0, &[2, 4, 255]),
+    ];
+    let inputs = scenes
+        .iter()
+        .map(|scene| scene.band(0).unwrap().into())
+        .collect::<Vec<CompositeInput>>();
+    let run = |inputs: &[CompositeInput], method| composite(inputs, method).unwrap().data;
+
+    assert_values(
+        &run(&inputs, CompositeMethod::Median),
+        &[2.0, 4.5, f64::NAN],
+    );
+    assert_values(&run(&inputs, CompositeMethod::Max), &[3.0, 5.0, f64::NAN]);
+    assert_values(&run(&inputs, CompositeMethod::First), &[1.0, 5.0, f64::NAN]);
+
+    let qa = grid(3, 1, 1000.0, &[0b1000, 0, 0]);
+    let spec = QaSpec {
+        rules: vec![QaRule::flag(3)],
+    };
+    let mut masked = inputs.clone();
+    masked[0].qa = Some((qa.band(0).unwrap(), &spec));
+    assert_values(&run(&masked, CompositeMethod::First), &[3.0, 5.0, f64::NAN]);
+
+    // The composite is on the grid of the first input
+    let shifted = grid(3, 1, 1010.0, &[7, 8, 9]);
+    let result = composite(
+        &[inputs[1], shifted.band(0).unwrap().into()],
+        CompositeMethod::First,
+    )
+    .unwrap();
+    assert_values(&result.data, &[3.0, 7.0, 8.0]);
+
+    let other_resolution = GeoTiff::from_bytes(&encode_gray8(1, 1, &[1], |encoder| {
+        encoder
+            .write_tag(Tag::ModelPixelScaleTag, &[20.0, 20.0, 0.0][..])
+            .unwrap();
+        encoder
+            .write_tag(
+                Tag::ModelTiepointTag,
+                &[0.0, 0.0, 0.0, 1000.0, 2000.0, 0.0][..],
+            )
+            .unwrap();
+    }))
+    .unwrap();
+    assert!(composite(
+        &[inputs[0], other_resolution.band(0).unwrap().into()],
+        CompositeMethod::Max
+    )
+    .is_err());
+    assert!(composite(&[], CompositeMethod::Max).is_err());
+}
+
+#[test]
+fn test_composite_min_ndvi() {
+    // Samples of red, near-infrared and the composited value
+    let scene = |data: &[u8]| {
+        let mut buffer = Cursor::new(Vec::new());
+        let mut encoder = TiffEncoder::new(&mut buffer).unwrap();
+        encoder.write_image::<colortype::RGB8>(2, 1, data).unwrap();
+        GeoTiff::from_bytes(&buffer.into_inner()).unwrap()
+    };
+    let scenes = [
+        scene(&[10, 50, 1, 10, 20, 1]),
+        scene(&[10, 20, 2, 10, 50, 2]),
+        scene(&[40, 10, 3, 0, 0, 3]),
+    ];
+    let inputs = scenes
+        .iter()
+        .map(|scene| scene.band(2).unwrap().into())
+        .collect::<Vec<CompositeInput>>();
+
+    let result = composite(&inputs, CompositeMethod::MinNdvi { red: 0, nir: 1 }).unwrap();
+    assert_values(&result.data, &[3.0, 1.0]);
+    assert!(composite(&inputs, CompositeMethod::MinNdvi { red: 0, nir: 3 }).is_err());
+}
@@ -0,0 +1,128 @@
+#[cfg(feature = "decode")]
+use std::io::Cursor;
+
+#[cfg(feature = "decode")]
+use common::{encode_gray8, read_geotiff};
+#[cfg(feature = "decode")]
+use geotiff::GeoTiff;
+use geotiff::{Conformance, ConformanceReport, GeoKeyDirectory, RequirementsClass};
+#[cfg(feature = "decode")]
+use tiff::tags::Tag;
+
+mod common;
+
+#[test]
+fn test_check_tags() {
+    let key_directory = [
+        1u16, 1, 1, 3, 1024, 0, 1, 2, 1025, 0, 1, 1, 2048, 0, 1, 4326,
+    ];
+    let geo_key_directory = GeoKeyDirectory::from_tag_data(&key_directory, &[], "").unwrap();
+    let conformance = ConformanceReport::check(
+        Some(&key_directory),
+        Some(&geo_key_directory),
+        Some(&[0.5, 0.5, 0.0]),
+        Some(&[0.0, 0.0, 0.0, 8.0, 47.0, 0.0]),
+        None,
+    );
+    assert!(conformance.is_conformant());
+    assert!(conformance.conforms_to(RequirementsClass::GeodeticCRSGeoKey));
+    assert_eq!(
+        conformance.get(RequirementsClass::ProjectedCRSGeoKey),
+        &Conformance::NotApplicable
+    );
+
+    let conformance = ConformanceReport::check(None, None, None, None, None);
+    assert!(!conformance.conforms_to(RequirementsClass::GeoKeyDirectoryTag));
+    assert!(!conformance.conforms_to(RequirementsClass::Raster2ModelCRSTransformation));
+}
+
+#[cfg(feature = "decode")]
+#[test]
+fn test_conformance_of_resources() {
+    let conformance =
+        read_geotiff("resources/austrian_capitals_model_transformation_pixel_is_point.tif")
+            .conformance()
+            .clone();
+    assert_eq!(
+        conformance.conformant_classes().collect::<Vec<_>>(),
+        vec![
+            RequirementsClass::GTModelTypeGeoKey,
+            RequirementsClass::GTRasterTypeGeoKey,
+            RequirementsClass::ProjectedCRSGeoKey,
+            RequirementsClass::Raster2ModelCRSTransformation,
+        ]
+    );
+    assert_eq!(
+        conformance.get(RequirementsClass::GeoKeyDirectoryTag),
+        &Conformance::NonConformant(vec!["MinorRevision is 2 instead of 0 or 1".into()])
+    );
+    assert_eq!(
+        conformance.get(RequirementsClass::VerticalGeoKey),
+        &Conformance::NotApplicable
+    );
+
+    let geotiff = read_geotiff("resources/merc.tif");
+    let Conformance::NonConformant(violations) = geotiff
+        .conformance()
+        .get(RequirementsClass::GeoKeyDirectoryTag)
+    else {
+        panic!("Expected the GeoKeyDirectoryTag class to be violated");
+    };
+    assert!(violations.contains(&"Keys are not sorted in strictly ascending order".to_string()));
+
+    let geotiff = read_geotiff("resources/zh_dem_25.tif");
+    let conformance = geotiff.conformance();
+    assert!(!conformance.is_conformant());
+    assert!(!conformance.conforms_to(RequirementsClass::GeoKeyDirectoryTag));
+    assert!(conformance.conforms_to(RequirementsClass::Raster2ModelCRSTransformation));
+}
+
+#[cfg(feature = "decode")]
+#[test]
+fn test_conformant_geographic_image() {
+    let data = encode_gray8(2, 2, &[0; 4], |encoder| {
+        encoder
+            .write_tag(
+                Tag::GeoKeyDirectoryTag,
+                &[
+                    1u16, 1, 1, 3, 1024, 0, 1, 2, 1025, 0, 1, 1, 2048, 0, 1, 4326,
+                ][..],
+            )
+            .unwrap();
+        encoder
+            .write_tag(Tag::ModelPixelScaleTag, &[0.5, 0.5, 0.0][..])
+            .unwrap();
+        encoder
+            .write_tag(Tag::ModelTiepointTag, &[0.0, 0.0, 0.0, 8.0, 47.0, 0.0][..])
+            .unwrap();
+    });
+    let geotiff = GeoTiff::read(Cursor::new(data)).unwrap();
+    let conformance = geotiff.conformance();
+
+    assert!(conformance.is_conformant());
+    assert!(conformance.conforms_to(RequirementsClass::GeodeticCRSGeoKey));
+    assert_eq!(
+        conformance.get(RequirementsClass::ProjectedCRSGeoKey),
+        &Conformance::NotApplicable
+    );
+}
+
+#[cfg(feature = "decode")]
+#[test]
+fn test_missing_transformation_tags() {
+    let data = encode_gray8(2, 2, &[0; 4], |_| {});
+    let geotiff = GeoTiff::read(Cursor::new(data)).unwrap();
+    let conformance = geotiff.conformance();
+
+    assert_eq!(
+        conformance.get(RequirementsClass::Raster2ModelCRSTransformation),
+        &Conformance::NonConformant(vec![
+            "Neither ModelTiepointTag nor ModelTransformationTag is present".into()
+        ])
+    );
+    assert_eq!(
+        conformance.get(RequirementsClass::GTModelTypeGeoKey),
+        &Conformance::NonConformant(vec!["GTModelTypeGeoKey is missing".into()])
+    );
+    assert_eq!(conformance.conformant_classes().count(), 0);
+}
@@ -0,0 +1,108 @@
+#![cfg(feature = "decode")]
+
+use std::io::Cursor;
+
+use common::{encode_gray8, encode_gray8_images};
+use geotiff::GeoTiff;
+use tiff::encoder::{colortype, compression::Deflate, TiffEncoder};
+use tiff::tags::Tag;
+
+mod common;
+
+const GEO_KEYS: [u16; 12] = [1, 1, 0, 2, 1024, 0, 1, 1, 3072, 0, 1, 32632];
+const DATA: [u8; 6] = [1, 2, 3, 4, 0, 6];
+
+fn encode_with_pixel_scale(data: &[u8]) -> Vec<u8> {
+    encode_gray8(3, 2, data, |encoder| {
+        encoder
+            .write_tag(Tag::GeoKeyDirectoryTag, &GEO_KEYS[..])
+            .unwrap();
+        encoder
+            .write_tag(Tag::ModelPixelScaleTag, &[10.0, 10.0, 0.0][..])
+            .unwrap();
+        encoder
+            .write_tag(
+                Tag::ModelTiepointTag,
+                &[0.0, 0.0, 0.0, 1000.0, 2000.0, 0.0][..],
+            )
+            .unwrap();
+        encoder.write_tag(Tag::GdalNodata, "0").unwrap();
+        encoder.write_tag(Tag::Artist, "Jane Doe").unwrap();
+    })
+}
+
+#[test]
+fn test_content_hash() {
+    let geotiff = GeoTiff::from_bytes(&encode_with_pixel_scale(&DATA)).unwrap();
+    let hash = geotiff.content_hash().unwrap();
+
+    // The same content, compressed in strips of one row, with a transformation matrix
+    let mut buffer = Cursor::new(Vec::new());
+    let mut encoder = TiffEncoder::new(&mut buffer).unwrap();
+    let mut image = encoder
+        .new_image_with_compression::<colortype::Gray8, _>(3, 2, Deflate::default())
+        .unwrap();
+    image.rows_per_strip(1).unwrap();
+    let directory = image.encoder();
+    directory.write_tag(Tag::GdalNodata, "0").unwrap();
+    directory
+        .write_tag(
+            Tag::ModelTransformationTag,
+            &[
+                10.0, 0.0, 0.0, 1000.0, 0.0, -10.0, 0.0, 2000.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+                1.0,
+            ][..],
+        )
+        .unwrap();
+    directory
+        .write_tag(Tag::GeoKeyDirectoryTag, &GEO_KEYS[..])
+        .unwrap();
+    image.write_data(&DATA).unwrap();
+    let reencoded = GeoTiff::from_bytes(&buffer.into_inner()).unwrap();
+    assert_eq!(reencoded.content_hash().unwrap(), hash);
+    assert_eq!(
+        reencoded.primary().content_hash().unwrap(),
+        geotiff.primary().content_hash().unwrap()
+    );
+
+    let modified = GeoTiff::from_bytes(&encode_with_pixel_scale(&[1, 2, 3, 4, 0, 7])).unwrap();
+    assert_ne!(modified.content_hash().unwrap(), hash);
+
+    let without_nodata = GeoTiff::from_bytes(&encode_gray8(3, 2, &DATA, |encoder| {
+        encoder
+            .write_tag(Tag::GeoKeyDirectoryTag, &GEO_KEYS[..])
+            .unwrap();
+        encoder
+            .write_tag(Tag::ModelPixelScaleTag, &[10.0, 10.0, 0.0][..])
+            .unwrap();
+        encoder
+            .write_tag(
+                Tag::ModelTiepointTag,
+                &[0.0, 0.0, 0.0, 1000.0, 2000.0, 0.0][..],
+            )
+            .unwrap();
+    }))
+    .unwrap();
+    assert_ne!(without_nodata.content_hash().unwrap(), hash);
+}
+
+#[test]
+fn test_content_hash_ignores_overviews() {
+    let images: [(u32, u32, &[u8]); 2] = [(4, 2, &[1, 2, 3, 4, 5, 6, 7, 8]), (2, 1, &[1, 3])];
+    let without_overview = GeoTiff::from_bytes(&encode_gray8(4, 2, images[0].2, |_| ())).unwrap();
+    let with_overview = GeoTiff::from_bytes(&encode_gray8_images(&images, |index, encoder| {
+        if index == 1 {
+            encoder.write_tag(Tag::NewSubfileType, 1u32).unwrap();
+        }
+    }))
+    .unwrap();
+    assert_eq!(with_overview.images().len(), 2);
+    assert_eq!(
+        with_overview.content_hash().unwrap(),
+        without_overview.content_hash().unwrap()
+    );
+    assert_ne!(
+        with_overview.images()[1].content_hash().unwrap(),
+        without_overview.content_hash().unwrap()
+    );
+}
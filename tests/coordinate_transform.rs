@@ -0,0 +1,638 @@
+#[cfg(feature = "decode")]
+use std::io::Cursor;
+
+#[cfg(feature = "decode")]
+use common::encode_gray8;
+use geo_types::Coord;
+#[cfg(feature = "decode")]
+use geotiff::{
+    compose, AxisOrder, GeoTiff, MetadataError, OpenOptions, PixelScaleConvention, TiePoint,
+};
+use geotiff::{
+    AffineTransform, CoordinateTransform, Precision, RasterType, TiePointAndPixelScale,
+    TransformTags,
+};
+#[cfg(feature = "decode")]
+use tiff::tags::Tag;
+
+mod common;
+
+#[cfg(feature = "decode")]
+#[test]
+fn test_model_transformation_with_z() {
+    #[rustfmt::skip]
+    let matrix = [
+        2.0, 0.0, 0.5, 100.0,
+        0.0, -2.0, 0.0, 200.0,
+        0.0, 0.0, 10.0, 5.0,
+        0.0, 0.0, 0.0, 1.0,
+    ];
+    let data = encode_gray8(4, 4, &[0; 16], |encoder| {
+        encoder
+            .write_tag(Tag::ModelTransformationTag, &matrix[..])
+            .unwrap();
+    });
+    let geotiff = GeoTiff::read(Cursor::new(data)).unwrap();
+
+    let Some(CoordinateTransform::Affine3D(transform)) = geotiff.coordinate_transform() else {
+        panic!("Expected a 3D affine transform");
+    };
+    assert_eq!(transform.matrix(), &matrix);
+    assert_eq!(
+        transform.to_model(&Coord { x: 1.0, y: 1.0 }),
+        Coord { x: 102.0, y: 198.0 }
+    );
+    assert_eq!(
+        transform.to_raster(&Coord { x: 102.0, y: 198.0 }).unwrap(),
+        Coord { x: 1.0, y: 1.0 }
+    );
+    assert_eq!(transform.to_model_3d([1.0, 1.0, 2.0]), [103.0, 198.0, 25.0]);
+}
+
+#[cfg(feature = "decode")]
+fn encode_model_transformation(matrix: &[f64; 16]) -> Vec<u8> {
+    encode_gray8(4, 4, &[0; 16], |encoder| {
+        encoder
+            .write_tag(Tag::ModelTransformationTag, &matrix[..])
+            .unwrap();
+    })
+}
+
+#[cfg(feature = "decode")]
+#[test]
+fn test_invertibility_is_relative_to_pixel_size() {
+    #[rustfmt::skip]
+    let data = encode_model_transformation(&[
+        1e-9, 0.0, 0.0, 10.0,
+        0.0, -1e-9, 0.0, 50.0,
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    ]);
+    let geotiff = GeoTiff::read(Cursor::new(data)).unwrap();
+    let transform = geotiff.coordinate_transform().unwrap();
+
+    let model = transform.transform_to_model(&Coord { x: 2.0, y: 3.0 });
+    let raster = transform.transform_to_raster(&model).unwrap();
+    assert!((raster.x - 2.0).abs() < 1e-6);
+    assert!((raster.y - 3.0).abs() < 1e-6);
+}
+
+#[cfg(feature = "decode")]
+#[test]
+fn test_non_invertible_transform() {
+    // Both raster axes map onto the same model direction
+    #[rustfmt::skip]
+    let data = encode_model_transformation(&[
+        1.0, 2.0, 0.0, 10.0,
+        2.0, 4.0, 0.0, 50.0,
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    ]);
+    let geotiff = GeoTiff::read(Cursor::new(data)).unwrap();
+    let transform = geotiff.coordinate_transform().unwrap();
+
+    assert_eq!(
+        transform.transform_to_model(&Coord { x: 1.0, y: 1.0 }),
+        Coord { x: 13.0, y: 56.0 }
+    );
+    assert!(transform
+        .transform_to_raster(&Coord { x: 13.0, y: 56.0 })
+        .is_err());
+    assert_eq!(
+        geotiff.get_value_at::<u8>(&Coord { x: 13.0, y: 56.0 }, 0),
+        None
+    );
+}
+
+#[cfg(feature = "decode")]
+#[test]
+fn test_invertibility_tolerance() {
+    // Nearly degenerate: the raster axes are almost parallel in model space
+    #[rustfmt::skip]
+    let matrix = [
+        1.0, 1.0, 0.0, 0.0,
+        1.0, 1.001, 0.0, 0.0,
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    ];
+    let coord = Coord { x: 1.0, y: 1.0 };
+
+    let geotiff = GeoTiff::read(Cursor::new(encode_model_transformation(&matrix))).unwrap();
+    assert!(geotiff
+        .coordinate_transform()
+        .unwrap()
+        .transform_to_raster(&coord)
+        .is_ok());
+
+    let geotiff = OpenOptions::new()
+        .invertibility_tolerance(1e-2)
+        .read(Cursor::new(encode_model_transformation(&matrix)))
+        .unwrap();
+    assert!(geotiff
+        .coordinate_transform()
+        .unwrap()
+        .transform_to_raster(&coord)
+        .is_err());
+}
+
+#[cfg(feature = "decode")]
+#[test]
+fn test_pixel_scale_convention() {
+    let encode = |scale_y: f64| {
+        encode_gray8(2, 2, &[1, 2, 3, 4], |encoder| {
+            encoder
+                .write_tag(Tag::ModelPixelScaleTag, &[10.0, scale_y, 0.0][..])
+                .unwrap();
+            encoder
+                .write_tag(
+                    Tag::ModelTiepointTag,
+                    &[0.0, 0.0, 0.0, 100.0, 200.0, 0.0][..],
+                )
+                .unwrap();
+        })
+    };
+    let corner = Coord { x: 2.0, y: 2.0 };
+
+    let geotiff = GeoTiff::read(Cursor::new(encode(10.0))).unwrap();
+    let transform = geotiff.coordinate_transform().unwrap();
+    assert!(transform.is_north_up());
+    assert_eq!(
+        transform.transform_to_model(&corner),
+        Coord { x: 120.0, y: 180.0 }
+    );
+
+    // Read as stored, a negative ScaleY flips the raster
+    let geotiff = GeoTiff::read(Cursor::new(encode(-10.0))).unwrap();
+    let transform = geotiff.coordinate_transform().unwrap();
+    assert!(!transform.is_north_up());
+    assert_eq!(
+        transform.transform_to_model(&corner),
+        Coord { x: 120.0, y: 220.0 }
+    );
+
+    let geotiff = OpenOptions::new()
+        .pixel_scale_convention(PixelScaleConvention::NorthUp)
+        .read(Cursor::new(encode(-10.0)))
+        .unwrap();
+    let transform = geotiff.coordinate_transform().unwrap();
+    assert!(transform.is_north_up());
+    assert_eq!(
+        transform.transform_to_model(&corner),
+        Coord { x: 120.0, y: 180.0 }
+    );
+
+    let error = GeoTiff::read(Cursor::new(encode(0.0))).unwrap_err();
+    assert!(error.to_string().contains("must not be zero"));
+}
+
+#[test]
+fn test_degenerate_inverse_transform() {
+    let tie_point = [0.0, 0.0, 0.0, 100.0, 200.0, 0.0];
+    let coord = Coord { x: 110.0, y: 190.0 };
+
+    let transform = TiePointAndPixelScale::from_tag_data(&tie_point, &[10.0, 10.0, 0.0]);
+    assert!(transform.is_invertible());
+    assert_eq!(
+        transform.to_raster(&coord).unwrap(),
+        Coord { x: 1.0, y: 1.0 }
+    );
+    let transform = TiePointAndPixelScale::from_tag_data(&tie_point, &[10.0, 0.0, 0.0]);
+    assert!(!transform.is_invertible());
+    assert!(transform.to_raster(&coord).is_err());
+
+    #[cfg(feature = "tie-points")]
+    {
+        let tags = TransformTags {
+            tie_points: Some(vec![
+                0.0, 0.0, 0.0, 100.0, 200.0, 0.0, //
+                1.0, 0.0, 0.0, 110.0, 200.0, 0.0, //
+                0.0, 1.0, 0.0, 100.0, 190.0, 0.0,
+            ]),
+            ..Default::default()
+        };
+        let transform = tags.to_coordinate_transform(1e-9).unwrap().unwrap();
+        assert!(transform.transform_to_raster(&coord).is_ok());
+        let nan = Coord {
+            x: f64::NAN,
+            y: 0.0,
+        };
+        assert!(transform.transform_to_raster(&nan).is_err());
+    }
+}
+
+#[test]
+fn test_derived_affine_transforms() {
+    // 10 m pixels, rotated by 90 degrees
+    #[rustfmt::skip]
+    let transform = AffineTransform::from_tag_matrix([
+        0.0, -10.0, 0.0, 1000.0,
+        -10.0, 0.0, 0.0, 2000.0,
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    ]);
+    let pixel = |transform: &AffineTransform, x, y| transform.to_model(&Coord { x, y });
+
+    let cropped = transform.cropped(3.0, 2.0);
+    assert_eq!(pixel(&cropped, 0.0, 0.0), pixel(&transform, 3.0, 2.0));
+    assert_eq!(pixel(&cropped, 1.0, 4.0), pixel(&transform, 4.0, 6.0));
+    assert_eq!(
+        cropped.to_raster(&pixel(&transform, 4.0, 6.0)).unwrap(),
+        Coord { x: 1.0, y: 4.0 }
+    );
+    assert_eq!(
+        transform.padded(3.0, 2.0).to_tag_matrix(),
+        transform.cropped(-3.0, -2.0).to_tag_matrix()
+    );
+
+    let decimated = transform.decimated(4.0, RasterType::RasterPixelIsArea);
+    assert_eq!(pixel(&decimated, 0.0, 0.0), pixel(&transform, 0.0, 0.0));
+    assert_eq!(pixel(&decimated, 1.0, 2.0), pixel(&transform, 4.0, 8.0));
+    assert_eq!(
+        decimated.to_raster(&pixel(&transform, 4.0, 8.0)).unwrap(),
+        Coord { x: 1.0, y: 2.0 }
+    );
+    // The corner of the first pixel is kept for PixelIsPoint
+    let decimated = transform.decimated(4.0, RasterType::RasterPixelIsPoint);
+    assert_eq!(pixel(&decimated, -0.5, -0.5), pixel(&transform, -0.5, -0.5));
+
+    #[rustfmt::skip]
+    assert_eq!(
+        AffineTransform::from_tag_matrix(transform.to_tag_matrix()).to_tag_matrix(),
+        [
+            0.0, -10.0, 0.0, 1000.0,
+            -10.0, 0.0, 0.0, 2000.0,
+            0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        ]
+    );
+}
+
+#[test]
+fn test_approx_eq() {
+    let tie_point_and_scale = TransformTags {
+        pixel_scale: Some(vec![10.0, 10.0, 0.0]),
+        tie_points: Some(vec![2.0, 1.0, 0.0, 120.0, 190.0, 0.0]),
+        model_transformation: None,
+    };
+    #[rustfmt::skip]
+    let matrix = vec![
+        10.0, 0.0, 0.0, 100.0,
+        0.0, -10.0, 0.0, 200.0,
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    ];
+    let transform = |tags: &TransformTags| tags.to_coordinate_transform(1e-9).unwrap().unwrap();
+    let model_transformation = |matrix: Vec<f64>| TransformTags {
+        model_transformation: Some(matrix),
+        ..Default::default()
+    };
+
+    let affine = transform(&model_transformation(matrix.clone()));
+    assert!(transform(&tie_point_and_scale).approx_eq(&affine, 0.0));
+    assert!(affine.approx_eq(&transform(&tie_point_and_scale), 0.0));
+
+    let mut shifted = matrix.clone();
+    shifted[3] += 1e-6;
+    let shifted = transform(&model_transformation(shifted));
+    assert!(!affine.approx_eq(&shifted, 0.0));
+    assert!(affine.approx_eq(&shifted, 1e-5));
+
+    // The Z terms do not change the model X and Y coordinates
+    let mut with_z = matrix;
+    with_z[11] = 5.0;
+    let with_z = transform(&model_transformation(with_z));
+    assert!(matches!(with_z, CoordinateTransform::Affine3D(_)));
+    assert!(affine.approx_eq(&with_z, 0.0));
+
+    let CoordinateTransform::AffineTransform(affine) = affine else {
+        panic!("Expected an affine transform");
+    };
+    assert!(affine.approx_eq(&affine.cropped(0.0, 0.0), 0.0));
+    assert!(!affine.approx_eq(&affine.cropped(1.0, 0.0), 1.0));
+    assert!(affine.approx_eq(&affine.cropped(1.0, 0.0), 10.0));
+}
+
+#[cfg(feature = "tie-points")]
+#[test]
+fn test_tie_point_grid() {
+    // 3 x 2 grid of raster points every 10 pixels, whose model points are not affine
+    let model = |x: f64, y: f64| Coord {
+        x: 1000.0 + x + 0.01 * x * y,
+        y: 2000.0 - y,
+    };
+    let mut tie_points = vec![];
+    for (x, y) in [
+        (0.0, 0.0),
+        (10.0, 0.0),
+        (20.0, 0.0),
+        (0.0, 10.0),
+        (20.0, 10.0),
+        (10.0, 10.0),
+    ] {
+        let model = model(x, y);
+        tie_points.extend([x, y, 0.0, model.x, model.y, 0.0]);
+    }
+    let tags = TransformTags {
+        tie_points: Some(tie_points.clone()),
+        ..Default::default()
+    };
+    let transform = tags.to_coordinate_transform(1e-9).unwrap().unwrap();
+    let CoordinateTransform::TiePoints(tie_point_transform) = &transform else {
+        panic!("Expected a transform by tie points");
+    };
+
+    let grid = tie_point_transform.grid().unwrap();
+    assert_eq!(grid.origin(), Coord { x: 0.0, y: 0.0 });
+    assert_eq!(grid.spacing(), Coord { x: 10.0, y: 10.0 });
+    assert_eq!((grid.columns(), grid.rows()), (3, 2));
+    assert_eq!(grid.model_point(2, 1), Some(model(20.0, 10.0)));
+    assert_eq!(grid.model_point(3, 1), None);
+
+    // The model coordinates are bilinear within the cells, and extrapolated outside the grid
+    for raster in [
+        Coord { x: 5.0, y: 5.0 },
+        Coord { x: 17.5, y: 2.5 },
+        Coord { x: 25.0, y: -5.0 },
+    ] {
+        let model_coord = transform.transform_to_model(&raster);
+        assert!((model_coord - model(raster.x, raster.y)).x.abs() < 1e-9);
+        assert!((model_coord - model(raster.x, raster.y)).y.abs() < 1e-9);
+        let round_trip = transform.transform_to_raster(&model_coord).unwrap();
+        assert!((round_trip - raster).x.abs() < 1e-9);
+        assert!((round_trip - raster).y.abs() < 1e-9);
+    }
+
+    // Missing and irregular points
+    for tie_points in [tie_points[..30].to_vec(), {
+        let mut tie_points = tie_points;
+        tie_points[6] = 11.0;
+        tie_points
+    }] {
+        let tags = TransformTags {
+            tie_points: Some(tie_points),
+            ..Default::default()
+        };
+        let Some(CoordinateTransform::TiePoints(transform)) =
+            tags.to_coordinate_transform(1e-9).unwrap()
+        else {
+            panic!("Expected a transform by tie points");
+        };
+        assert!(transform.grid().is_none());
+    }
+}
+
+#[cfg(feature = "decode")]
+#[test]
+fn test_tie_point_residuals() {
+    let data = encode_gray8(2, 2, &[1, 2, 3, 4], |encoder| {
+        encoder
+            .write_tag(Tag::ModelPixelScaleTag, &[10.0, 10.0, 1.0][..])
+            .unwrap();
+        encoder
+            .write_tag(
+                Tag::ModelTiepointTag,
+                &[0.5, 0.5, 0.0, 100.0, 200.0, 50.0][..],
+            )
+            .unwrap();
+    });
+    let geotiff = GeoTiff::read(Cursor::new(data)).unwrap();
+    assert_eq!(
+        geotiff.tie_points(),
+        &[TiePoint {
+            raster: [0.5, 0.5, 0.0],
+            model: [100.0, 200.0, 50.0],
+        }]
+    );
+    let residuals = geotiff.tie_point_residuals().unwrap();
+    assert_eq!(residuals.errors, vec![Coord { x: 0.0, y: 0.0 }]);
+    assert_eq!(residuals.rms(), 0.0);
+
+    let transform = geotiff.coordinate_transform().unwrap();
+    let tie_points = TiePoint::from_tag_data(&[
+        1.5, 0.5, 0.0, 110.0, 200.0, 0.0, // Exact
+        0.5, 1.5, 0.0, 97.0, 194.0, 0.0, // Off by (3, -4)
+        0.0, // Incomplete
+    ]);
+    let residuals = transform.residuals(&tie_points);
+    assert_eq!(
+        residuals.errors,
+        vec![Coord { x: 0.0, y: 0.0 }, Coord { x: 3.0, y: -4.0 }]
+    );
+    assert!((residuals.rms() - 12.5f64.sqrt()).abs() < 1e-12);
+    assert_eq!(residuals.max(), 5.0);
+
+    let geotiff = GeoTiff::read(Cursor::new(encode_gray8(1, 1, &[0], |_| {}))).unwrap();
+    assert!(geotiff.tie_points().is_empty());
+    assert_eq!(geotiff.tie_point_residuals(), None);
+}
+
+#[cfg(feature = "decode")]
+#[test]
+fn test_fit_from_tie_points() {
+    // x = 1000 + 10 * i + j, y = 2000 - 10 * j, with a residual of +-0.5 on x
+    let tie_points = TiePoint::from_tag_data(&[
+        0.0, 0.0, 0.0, 1000.5, 2000.0, 0.0, //
+        10.0, 0.0, 0.0, 1099.5, 2000.0, 0.0, //
+        0.0, 10.0, 0.0, 1009.5, 1900.0, 0.0, //
+        10.0, 10.0, 0.0, 1110.5, 1900.0, 0.0,
+    ]);
+    let (transform, residuals) = AffineTransform::fit_from_tie_points(&tie_points).unwrap();
+    #[rustfmt::skip]
+    let expected = AffineTransform::from_tag_matrix([
+        10.0, 1.0, 0.0, 1000.0,
+        0.0, -10.0, 0.0, 2000.0,
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    ]);
+    assert!(transform.approx_eq(&expected, 1e-9));
+    assert!((residuals.rms() - 0.5).abs() < 1e-9);
+    assert!((residuals.max() - 0.5).abs() < 1e-9);
+
+    assert!(AffineTransform::fit_from_tie_points(&tie_points[..2]).is_err());
+    assert!(AffineTransform::fit_from_tie_points(&[]).is_err());
+
+    let data = encode_gray8(2, 2, &[1, 2, 3, 4], |encoder| {
+        let tie_points = tie_points
+            .iter()
+            .flat_map(|tie_point| tie_point.raster.into_iter().chain(tie_point.model))
+            .collect::<Vec<_>>();
+        encoder
+            .write_tag(Tag::ModelTiepointTag, &tie_points[..])
+            .unwrap();
+    });
+    let geotiff = OpenOptions::new()
+        .fit_tie_points(true)
+        .read(Cursor::new(&data))
+        .unwrap();
+    let Some(CoordinateTransform::AffineTransform(transform)) = geotiff.coordinate_transform()
+    else {
+        panic!("Expected an affine transform");
+    };
+    assert!(transform.approx_eq(&expected, 1e-9));
+    assert_eq!(geotiff.tie_point_residuals(), Some(residuals));
+    // The tie points are interpolated by default
+    let geotiff = GeoTiff::read(Cursor::new(&data));
+    #[cfg(feature = "tie-points")]
+    assert!(matches!(
+        geotiff.unwrap().coordinate_transform(),
+        Some(CoordinateTransform::TiePoints(_))
+    ));
+    #[cfg(not(feature = "tie-points"))]
+    assert!(geotiff.is_err());
+}
+
+#[cfg(feature = "decode")]
+#[test]
+fn test_authority_axis_order() {
+    let data = encode_gray8(4, 2, &[1, 2, 3, 4, 5, 6, 7, 8], |encoder| {
+        encoder
+            .write_tag(
+                Tag::GeoKeyDirectoryTag,
+                &[1u16, 1, 1, 2, 1024, 0, 1, 2, 2048, 0, 1, 4326][..],
+            )
+            .unwrap();
+        encoder
+            .write_tag(Tag::ModelPixelScaleTag, &[1.0, 1.0, 0.0][..])
+            .unwrap();
+        encoder
+            .write_tag(Tag::ModelTiepointTag, &[0.0, 0.0, 0.0, 8.0, 47.0, 0.0][..])
+            .unwrap();
+    });
+
+    let geotiff = GeoTiff::from_bytes(&data).unwrap();
+    assert!(!geotiff.primary().model_axes_swapped());
+    assert_eq!(geotiff.model_extent().min(), Coord { x: 8.0, y: 45.0 });
+    assert_eq!(
+        geotiff.get_value_at::<u8>(&Coord { x: 11.5, y: 45.5 }, 0),
+        Some(8)
+    );
+
+    let geotiff = OpenOptions::new()
+        .axis_order(AxisOrder::Authority)
+        .read(Cursor::new(data))
+        .unwrap();
+    assert!(geotiff.primary().model_axes_swapped());
+    assert_eq!(geotiff.model_extent().min(), Coord { x: 45.0, y: 8.0 });
+    assert_eq!(geotiff.model_extent().max(), Coord { x: 47.0, y: 12.0 });
+    assert_eq!(
+        geotiff.get_value_at::<u8>(&Coord { x: 45.5, y: 11.5 }, 0),
+        Some(8)
+    );
+}
+
+#[cfg(feature = "decode")]
+fn encode_pixel_scale_and_tie_point(scale: f64, origin: (f64, f64)) -> Vec<u8> {
+    encode_gray8(1, 1, &[0], |encoder| {
+        encoder
+            .write_tag(Tag::ModelPixelScaleTag, &[scale, scale, 0.0][..])
+            .unwrap();
+        encoder
+            .write_tag(
+                Tag::ModelTiepointTag,
+                &[0.0, 0.0, 0.0, origin.0, origin.1, 0.0][..],
+            )
+            .unwrap();
+    })
+}
+
+#[cfg(feature = "decode")]
+#[test]
+fn test_transform_pipeline() {
+    let a = GeoTiff::from_bytes(&encode_pixel_scale_and_tie_point(10.0, (1000.0, 2000.0))).unwrap();
+    let b = GeoTiff::from_bytes(&encode_pixel_scale_and_tie_point(20.0, (1100.0, 2000.0))).unwrap();
+    let a = a.coordinate_transform().unwrap();
+    let b = b.coordinate_transform().unwrap();
+
+    let pipeline = a.then(b);
+    assert_eq!(
+        pipeline.transform(&Coord { x: 10.0, y: 5.0 }).unwrap(),
+        Coord { x: 0.0, y: 2.5 }
+    );
+
+    let pipeline = compose(
+        a,
+        |coord| {
+            Ok(Coord {
+                x: coord.x + 100.0,
+                y: coord.y,
+            })
+        },
+        b,
+    );
+    assert_eq!(
+        pipeline.transform(&Coord { x: 10.0, y: 5.0 }).unwrap(),
+        Coord { x: 5.0, y: 2.5 }
+    );
+    let line = geo_types::Line::new(Coord { x: 0.0, y: 0.0 }, Coord { x: 10.0, y: 5.0 });
+    assert_eq!(
+        pipeline.transform_geometry(&line).unwrap(),
+        geo_types::Line::new(Coord { x: 0.0, y: 0.0 }, Coord { x: 5.0, y: 2.5 })
+    );
+
+    let failing = compose(
+        a,
+        |_| Err(MetadataError::Format("out of domain".to_string())),
+        b,
+    );
+    assert!(failing.transform(&Coord { x: 0.0, y: 0.0 }).is_err());
+}
+
+#[test]
+fn test_bulk_transform_precision() {
+    // A rotated raster of 0.5 m pixels in UTM, far from the origin of the CRS
+    let (sin, cos) = 0.3f64.sin_cos();
+    #[rustfmt::skip]
+    let transform = CoordinateTransform::AffineTransform(AffineTransform::from_tag_matrix([
+        0.5 * cos, 0.5 * sin, 0.0, 500_000.0,
+        0.5 * sin, -0.5 * cos, 0.0, 5_000_000.0,
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    ]));
+    let raster = (0..=20)
+        .map(|k| Coord {
+            x: 1000.0 * k as f64 + 0.25,
+            y: 777.0 * k as f64 + 0.5,
+        })
+        .collect::<Vec<_>>();
+
+    let mut exact = raster.clone();
+    transform.transform_to_model_bulk(&mut exact, Precision::F64);
+    for (coord, model) in raster.iter().zip(&exact) {
+        assert_eq!(*model, transform.transform_to_model(coord));
+    }
+    let mut fast = raster.clone();
+    transform.transform_to_model_bulk(&mut fast, Precision::F32);
+    assert_eq!(fast[0], exact[0]);
+    for ((coord, model), exact) in raster.iter().zip(&fast).zip(&exact) {
+        let offset = (coord.x - raster[0].x).abs() + (coord.y - raster[0].y).abs();
+        let bound = 2f64.powi(-22) * 0.5 * offset + 1e-9;
+        assert!((model.x - exact.x).abs() <= bound && (model.y - exact.y).abs() <= bound);
+    }
+    // The offsets are actually computed in f32
+    assert!(fast.iter().zip(&exact).any(|(fast, exact)| fast != exact));
+
+    let mut back = fast.clone();
+    transform
+        .transform_to_raster_bulk(&mut back, Precision::F32)
+        .unwrap();
+    for (back, coord) in back.iter().zip(&raster) {
+        assert!((back.x - coord.x).abs() < 1e-2 && (back.y - coord.y).abs() < 1e-2);
+    }
+
+    let pipeline = transform.then(&transform);
+    let mut coords = raster.clone();
+    pipeline
+        .transform_bulk(&mut coords, Precision::F32)
+        .unwrap();
+    for (coord, raster) in coords.iter().zip(&raster) {
+        assert!((coord.x - raster.x).abs() < 1e-2 && (coord.y - raster.y).abs() < 1e-2);
+    }
+
+    let singular = CoordinateTransform::AffineTransform(AffineTransform::from_tag_matrix([
+        1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+    ]));
+    let mut coords = raster.clone();
+    assert!(singular
+        .transform_to_raster_bulk(&mut coords, Precision::F32)
+        .is_err());
+}
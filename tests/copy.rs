@@ -0,0 +1,187 @@
+#![cfg(feature = "decode")]
+
+use std::io::Cursor;
+
+use geotiff::{
+    copy_with, ByteOrder, GeoKeyDirectory, GeoTiff, InMemoryRaster, Lineage, MetadataEdits,
+    Profile, ReadOptions, TransformTags, WriteOptions,
+};
+use tiff::decoder::Decoder;
+use tiff::tags::Tag;
+
+mod common;
+
+const VENDOR_TAG: u16 = 65000;
+const XMP_TAG: u16 = 700;
+const GDAL_METADATA_TAG: u16 = 42112;
+
+fn copy(bytes: &[u8], edits: &MetadataEdits) -> Vec<u8> {
+    let mut buffer = Cursor::new(Vec::new());
+    copy_with(bytes, &mut buffer, edits).unwrap();
+    buffer.into_inner()
+}
+
+fn read_u8(geotiff: &GeoTiff) -> Vec<u8> {
+    let image = geotiff.primary();
+    image
+        .read_window::<u8>(image.window(), &ReadOptions::default())
+        .unwrap()
+        .data
+}
+
+#[test]
+fn test_copy_preserves_unknown_tags() {
+    let data = (0..64).collect::<Vec<u8>>();
+    let (directory, doubles, ascii) = GeoKeyDirectory::from_epsg(4326).to_tag_data().unwrap();
+    let source =
+        common::encode_gray8_images(&[(8, 8, &data), (4, 4, &data[..16])], |index, encoder| {
+            if index == 0 {
+                encoder
+                    .write_tag(Tag::GeoKeyDirectoryTag, &directory[..])
+                    .unwrap();
+                if !doubles.is_empty() {
+                    encoder
+                        .write_tag(Tag::GeoDoubleParamsTag, &doubles[..])
+                        .unwrap();
+                }
+                if !ascii.is_empty() {
+                    encoder
+                        .write_tag(Tag::GeoAsciiParamsTag, &ascii[..])
+                        .unwrap();
+                }
+                encoder
+                    .write_tag(Tag::ModelPixelScaleTag, &[0.5, 0.5, 0.0][..])
+                    .unwrap();
+                encoder
+                    .write_tag(Tag::ModelTiepointTag, &[0.0, 0.0, 0.0, 2.0, 48.0, 0.0][..])
+                    .unwrap();
+                encoder
+                    .write_tag(Tag::Unknown(VENDOR_TAG), "vendor data")
+                    .unwrap();
+                encoder
+                    .write_tag(Tag::Unknown(XMP_TAG), &b"<x:xmpmeta/>"[..])
+                    .unwrap();
+                encoder
+                    .write_tag(
+                        Tag::Unknown(GDAL_METADATA_TAG),
+                        "<GDALMetadata>\n  <Item name=\"SCENE\">42</Item>\n</GDALMetadata>\n",
+                    )
+                    .unwrap();
+            } else {
+                encoder.write_tag(Tag::NewSubfileType, 1u32).unwrap();
+            }
+        });
+
+    let edits = MetadataEdits {
+        geo_key_directory: Some(GeoKeyDirectory::from_epsg(32632)),
+        transform_tags: Some(TransformTags {
+            pixel_scale: Some(vec![10.0, 10.0, 0.0]),
+            tie_points: Some(vec![0.0, 0.0, 0.0, 500000.0, 5300000.0, 0.0]),
+            model_transformation: None,
+        }),
+        nodata: Some(Some(0.0)),
+        lineage: Some(Lineage::new("reproject").with_input("source.tif")),
+        ..Default::default()
+    };
+    let copied = copy(&source, &edits);
+    let geotiff = GeoTiff::from_bytes(&copied).unwrap();
+    assert_eq!(
+        geotiff.geo_key_directory().unwrap().projected_type,
+        Some(32632)
+    );
+    assert_eq!(geotiff.nodata(), Some(0.0));
+    assert_eq!(geotiff.lineage().unwrap().inputs, ["source.tif"]);
+    assert_eq!(geotiff.gdal_metadata().unwrap().get("SCENE"), Some("42"));
+    assert_eq!(read_u8(&geotiff), data);
+    assert_eq!(geotiff.overviews().count(), 1);
+
+    let mut source_decoder = Decoder::new(Cursor::new(&source)).unwrap();
+    let mut decoder = Decoder::new(Cursor::new(&copied)).unwrap();
+    assert_eq!(
+        decoder
+            .get_tag_ascii_string(Tag::Unknown(VENDOR_TAG))
+            .unwrap(),
+        "vendor data"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            decoder.find_tag(Tag::Unknown(XMP_TAG)).unwrap().unwrap()
+        ),
+        format!(
+            "{:?}",
+            source_decoder
+                .find_tag(Tag::Unknown(XMP_TAG))
+                .unwrap()
+                .unwrap()
+        )
+    );
+
+    // Without edits, a copy of a copy is identical
+    let unchanged = copy(&copied, &MetadataEdits::default());
+    assert_eq!(copy(&unchanged, &MetadataEdits::default()), unchanged);
+}
+
+#[test]
+fn test_copy_keeps_layout() {
+    let raster = InMemoryRaster {
+        width: 40,
+        height: 35,
+        data: (0..40 * 35).map(|i| i as f64).collect(),
+        nodata: None,
+        geo_key_directory: Some(GeoKeyDirectory::from_epsg(32632)),
+        transform_tags: TransformTags::default(),
+        document_info: Default::default(),
+        lineage: None,
+        exif: None,
+    };
+    let options = WriteOptions {
+        layout: geotiff::Layout::Tiles(16),
+        byte_order: ByteOrder::BigEndian,
+        ..Profile::Cog.into()
+    };
+    let mut buffer = Cursor::new(Vec::new());
+    raster.write_with_options(&mut buffer, &options).unwrap();
+    let source = buffer.into_inner();
+
+    // The copy of a file of this crate is identical
+    assert_eq!(copy(&source, &MetadataEdits::default()), source);
+
+    let copied = copy(
+        &source,
+        &MetadataEdits {
+            nodata: Some(Some(-1.0)),
+            ..Default::default()
+        },
+    );
+    assert_eq!(copied[..8], *b"MM\0\x2a\0\0\0\x08");
+    let geotiff = GeoTiff::from_bytes(&copied).unwrap();
+    assert_eq!(geotiff.nodata(), Some(-1.0));
+    assert_eq!(geotiff.overviews().count(), 2);
+    let image = geotiff.primary();
+    let data = image
+        .read_window::<f64>(image.window(), &ReadOptions::default())
+        .unwrap()
+        .data;
+    assert_eq!(data, raster.data);
+
+    let copied = copy(
+        &copied,
+        &MetadataEdits {
+            nodata: Some(None),
+            ..Default::default()
+        },
+    );
+    assert_eq!(GeoTiff::from_bytes(&copied).unwrap().nodata(), None);
+}
+
+#[test]
+fn test_copy_rejects_unrelocatable_tags() {
+    let source = common::encode_gray8(2, 2, &[0; 4], |encoder| {
+        encoder.write_tag(Tag::Unknown(330), 0u32).unwrap();
+    });
+    let mut buffer = Cursor::new(Vec::new());
+    let error = copy_with(&source[..], &mut buffer, &MetadataEdits::default()).unwrap_err();
+    assert!(error.to_string().contains("Tag 330 of IFD 0"));
+    assert!(copy_with(&b"II\x2b\0"[..], &mut buffer, &MetadataEdits::default()).is_err());
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "decode")]
+
+use common::{encode_gray8, read_geotiff};
+use geotiff::{DateTime, DocumentInfo, GeoTiff, InMemoryRaster, TransformTags};
+use tiff::tags::Tag;
+
+mod common;
+
+#[test]
+fn test_read_document_info() {
+    let data = encode_gray8(1, 1, &[0], |encoder| {
+        encoder
+            .write_tag(Tag::ImageDescription, "Elevation model")
+            .unwrap();
+        encoder.write_tag(Tag::Artist, "Jane Doe").unwrap();
+        encoder
+            .write_tag(Tag::DateTime, "2024:05:17 08:30:00")
+            .unwrap();
+    });
+    let geotiff = GeoTiff::from_bytes(&data).unwrap();
+    let info = geotiff.document_info();
+    assert_eq!(info.description.as_deref(), Some("Elevation model"));
+    assert_eq!(info.artist.as_deref(), Some("Jane Doe"));
+    assert_eq!(info.copyright, None);
+    assert_eq!(info.date_time, DateTime::parse("2024-05-17T08:30:00"));
+
+    assert!(read_geotiff("resources/zh_dem_25.tif")
+        .document_info()
+        .is_empty());
+}
+
+#[test]
+fn test_write_document_info() {
+    let document_info = DocumentInfo {
+        description: Some("Land cover".into()),
+        artist: Some("Survey office".into()),
+        copyright: Some("CC-BY 4.0".into()),
+        date_time: DateTime::parse("2023-12-01 10:00:00"),
+        software: Some("geotiff".into()),
+        xmp: Some(b"<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"/>".to_vec()),
+    };
+    let raster = InMemoryRaster {
+        width: 1,
+        height: 1,
+        data: vec![1.0],
+        nodata: None,
+        geo_key_directory: None,
+        transform_tags: TransformTags::default(),
+        document_info: document_info.clone(),
+        lineage: None,
+        exif: None,
+    };
+    let geotiff = GeoTiff::from_bytes(&raster.to_bytes().unwrap()).unwrap();
+    assert_eq!(geotiff.document_info(), &document_info);
+}
@@ -0,0 +1,108 @@
+#![cfg(feature = "drone")]
+
+use std::io::Cursor;
+
+use common::{ascii, rationals, short};
+use geo_types::Coord;
+use geotiff::drone::{CameraModel, DroneFrame};
+use geotiff::{
+    ByteOrder, DocumentInfo, Exif, GeoTiff, InMemoryRaster, RawTag, TransformTags, WriteOptions,
+};
+
+mod common;
+
+/// A 40 x 20 frame taken at 600 m by a camera of 10 mm with pixels of 10 µm, heading east.
+fn frame(exif: Vec<RawTag>, gps: Vec<RawTag>, xmp: Option<&str>) -> GeoTiff {
+    let raster = InMemoryRaster {
+        width: 40,
+        height: 20,
+        data: vec![0.0; 40 * 20],
+        nodata: None,
+        geo_key_directory: None,
+        transform_tags: TransformTags::default(),
+        document_info: DocumentInfo {
+            xmp: xmp.map(|xmp| xmp.as_bytes().to_vec()),
+            ..Default::default()
+        },
+        lineage: None,
+        exif: Some(Exif {
+            byte_order: ByteOrder::LittleEndian,
+            exif,
+            gps: [
+                ascii(1, "N"),
+                rationals(2, &[(46, 1), (30, 1), (0, 1)]),
+                ascii(3, "E"),
+                rationals(4, &[(7, 1), (15, 1), (0, 1)]),
+                rationals(6, &[(600, 1)]),
+            ]
+            .into_iter()
+            .chain(gps)
+            .collect(),
+        }),
+    };
+    let mut buffer = Cursor::new(Vec::new());
+    raster
+        .write_with_options(&mut buffer, &WriteOptions::default())
+        .unwrap();
+    GeoTiff::from_bytes(&buffer.into_inner()).unwrap()
+}
+
+fn focal_plane() -> Vec<RawTag> {
+    vec![
+        rationals(37386, &[(10, 1)]),
+        rationals(41486, &[(100, 1)]),
+        rationals(41487, &[(100, 1)]),
+        short(41488, 4),
+    ]
+}
+
+#[test]
+fn test_drone_frame_from_exif() {
+    let geotiff = frame(focal_plane(), vec![rationals(17, &[(90, 1)])], None);
+    let image = geotiff.primary();
+    assert!(!image.is_georeferenced());
+    assert_eq!(DroneFrame::from_image(image, None), None);
+
+    let drone = DroneFrame::from_image(image, Some(500.0)).unwrap();
+    assert_eq!(
+        drone.camera,
+        CameraModel {
+            focal_length: 10.0,
+            pixel_size: (0.01, 0.01),
+        }
+    );
+    assert_eq!((drone.longitude, drone.latitude), (7.25, 46.5));
+    assert_eq!(drone.yaw, 90.0);
+    let (gsd_x, gsd_y) = drone.ground_sample_distance();
+    assert!((gsd_x - 0.1).abs() < 1e-12 && (gsd_y - 0.1).abs() < 1e-12);
+
+    let transform = drone.coordinate_transform(40, 20);
+    let center = transform.transform_to_model(&Coord { x: 20.0, y: 10.0 });
+    assert!((center.x - 7.25).abs() < 1e-12 && (center.y - 46.5).abs() < 1e-12);
+    // The top of the frame is 1 m to the east of its center, and its right 2 m to the south
+    let meters_per_degree = 6_378_137.0 * std::f64::consts::PI / 180.0;
+    let top = transform.transform_to_model(&Coord { x: 20.0, y: 0.0 });
+    let east = (top.x - 7.25) * meters_per_degree * 46.5f64.to_radians().cos();
+    assert!((east - 1.0).abs() < 1e-6 && (top.y - 46.5).abs() < 1e-12);
+    let right = transform.transform_to_model(&Coord { x: 40.0, y: 10.0 });
+    assert!(((right.y - 46.5) * meters_per_degree + 2.0).abs() < 1e-6);
+    assert_eq!(drone.geo_key_directory().geographic_type, Some(4326));
+}
+
+#[test]
+fn test_drone_frame_from_xmp() {
+    let xmp = r#"<rdf:Description drone-dji:RelativeAltitude="+100.00"
+        drone-dji:FlightYawDegree="-45.5"><drone-dji:GimbalYawDegree>30.0</drone-dji:GimbalYawDegree>
+        </rdf:Description>"#;
+    let geotiff = frame(vec![short(41989, 24)], Vec::new(), Some(xmp));
+    let drone = DroneFrame::from_image(geotiff.primary(), None).unwrap();
+    assert_eq!(drone.height_above_ground, 100.0);
+    assert_eq!(drone.yaw, 30.0);
+    // The film width for the 40 pixels of the longest side
+    assert_eq!(drone.camera.focal_length, 24.0);
+    assert!((drone.ground_sample_distance().0 - 3.75).abs() < 1e-12);
+
+    let geotiff = frame(focal_plane(), Vec::new(), None);
+    let drone = DroneFrame::from_image(geotiff.primary(), Some(550.0)).unwrap();
+    assert_eq!((drone.height_above_ground, drone.yaw), (50.0, 0.0));
+}
@@ -0,0 +1,193 @@
+#![cfg(feature = "decode")]
+
+use std::io::{Cursor, Write};
+
+use common::encode_gray8;
+use flate2::write::ZlibEncoder;
+use geotiff::{DType, DecodeContext, GeoTiff, OpenOptions};
+use tiff::encoder::compression::{Compression, Deflate, Lzw, Packbits};
+use tiff::encoder::{colortype, TiffEncoder};
+use tiff::tags::{PhotometricInterpretation, Tag};
+use tiff::{TiffError, TiffFormatError};
+
+mod common;
+
+/// Encodes an image of packed values, given as rows of `row_bytes` bytes.
+fn encode_packed(width: u32, height: u32, bits: u16, row_bytes: u32, data: &[u8]) -> Vec<u8> {
+    encode_gray8(row_bytes, height, data, |encoder| {
+        encoder.write_tag(Tag::ImageWidth, width).unwrap();
+        encoder.write_tag(Tag::BitsPerSample, bits).unwrap();
+    })
+}
+
+#[test]
+fn test_dtype_from_tags() {
+    assert_eq!(DType::from_tags(1, 1), DType::Bit);
+    assert_eq!(DType::from_tags(2, 16), DType::I16);
+    assert_eq!(DType::from_tags(3, 32), DType::F32);
+    assert_eq!(DType::from_tags(5, 32), DType::ComplexI16);
+    assert_eq!(DType::from_tags(6, 128), DType::ComplexF64);
+    assert_eq!(
+        DType::from_tags(3, 24),
+        DType::Other {
+            sample_format: 3,
+            bits_per_sample: 24
+        }
+    );
+
+    assert!(DType::U12.is_packed());
+    assert!(!DType::U16.is_packed());
+    assert!(DType::ComplexF32.is_float() && DType::ComplexF32.is_complex());
+    assert_eq!(DType::ComplexI32.bits_per_sample(), 64);
+}
+
+#[test]
+fn test_band_dtype() {
+    let geotiff = common::read_geotiff("resources/zh_dem_25.tif");
+    assert_eq!(geotiff.band_dtype(0), Some(DType::I16));
+    assert_eq!(geotiff.band_dtype(1), None);
+    assert_eq!(geotiff.band(0).unwrap().dtype(), DType::I16);
+
+    let geotiff = GeoTiff::from_bytes(&encode_gray8(1, 1, &[0], |_| {})).unwrap();
+    assert_eq!(geotiff.band_dtype(0), Some(DType::U8));
+}
+
+#[test]
+fn test_read_1_bit() {
+    #[rustfmt::skip]
+    let data = encode_packed(10, 2, 1, 2, &[
+        0b1011_0000, 0b0100_0000,
+        0b0000_0000, 0b1100_0000,
+    ]);
+    let geotiff = GeoTiff::from_bytes(&data).unwrap();
+    assert_eq!(geotiff.band_dtype(0), Some(DType::Bit));
+
+    let values = geotiff
+        .band(0)
+        .unwrap()
+        .read_window(geotiff.primary().window())
+        .unwrap();
+    #[rustfmt::skip]
+    assert_eq!(values, [
+        1.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0,
+    ]);
+
+    // With WhiteIsZero, the set bits are black
+    let data = encode_gray8(1, 1, &[0b1000_0000], |encoder| {
+        encoder.write_tag(Tag::ImageWidth, 2u32).unwrap();
+        encoder.write_tag(Tag::BitsPerSample, 1u16).unwrap();
+        encoder
+            .write_tag(
+                Tag::PhotometricInterpretation,
+                PhotometricInterpretation::WhiteIsZero.to_u16(),
+            )
+            .unwrap();
+    });
+    let geotiff = GeoTiff::from_bytes(&data).unwrap();
+    let value_at = |x| geotiff.get_value_at::<u8>(&geo_types::Coord { x, y: 0.5 }, 0);
+    assert_eq!((value_at(0.5), value_at(1.5)), (Some(0), Some(1)));
+}
+
+#[test]
+fn test_read_12_bit() {
+    #[rustfmt::skip]
+    let data = encode_packed(3, 2, 12, 5, &[
+        0xab, 0xc1, 0x23, 0xff, 0xf0,
+        0x00, 0x10, 0x02, 0x80, 0x00,
+    ]);
+    let geotiff = GeoTiff::from_bytes(&data).unwrap();
+    assert_eq!(geotiff.band_dtype(0), Some(DType::U12));
+
+    let values = geotiff
+        .band(0)
+        .unwrap()
+        .read_window(geotiff.primary().window())
+        .unwrap();
+    assert_eq!(
+        values,
+        [
+            0xabc as f64,
+            0x123 as f64,
+            0xfff as f64,
+            0x001 as f64,
+            0x002 as f64,
+            0x800 as f64
+        ]
+    );
+}
+
+/// Encodes an image of 1-bit values in rows of `row_bytes` bytes with the given compression.
+fn encode_packed_compressed<C: Compression>(
+    width: u32,
+    height: u32,
+    row_bytes: u32,
+    data: &[u8],
+    compression: C,
+) -> Vec<u8> {
+    let mut buffer = Cursor::new(Vec::new());
+    let mut encoder = TiffEncoder::new(&mut buffer).unwrap();
+    let mut image = encoder
+        .new_image_with_compression::<colortype::Gray8, _>(row_bytes, height, compression)
+        .unwrap();
+    image.encoder().write_tag(Tag::ImageWidth, width).unwrap();
+    image.encoder().write_tag(Tag::BitsPerSample, 1u16).unwrap();
+    image.write_data(data).unwrap();
+    buffer.into_inner()
+}
+
+#[test]
+fn test_read_compressed_with_context() {
+    let data = (0..64 * 200)
+        .map(|i| (i * 37 % 251) as u8)
+        .collect::<Vec<_>>();
+    let expected = GeoTiff::from_bytes(&encode_packed(512, 200, 1, 64, &data)).unwrap();
+    let expected = expected
+        .band(0)
+        .unwrap()
+        .read_window(expected.primary().window());
+
+    let mut context = DecodeContext::new();
+    for bytes in [
+        encode_packed_compressed(512, 200, 64, &data, Deflate::default()),
+        encode_packed_compressed(512, 200, 64, &data, Lzw),
+        encode_packed_compressed(512, 200, 64, &data, Packbits),
+        encode_packed_compressed(512, 200, 64, &data, Deflate::default()),
+    ] {
+        let geotiff = OpenOptions::new()
+            .read_with_context(Cursor::new(&bytes), &mut context)
+            .unwrap();
+        assert_eq!(geotiff.band_dtype(0), Some(DType::Bit));
+        let values = geotiff
+            .band(0)
+            .unwrap()
+            .read_window(geotiff.primary().window());
+        assert_eq!(values.unwrap(), *expected.as_ref().unwrap());
+
+        // The reads without a context use the one of the thread
+        let geotiff = GeoTiff::from_bytes(&bytes).unwrap();
+        let values = geotiff
+            .band(0)
+            .unwrap()
+            .read_window(geotiff.primary().window());
+        assert_eq!(values.unwrap(), *expected.as_ref().unwrap());
+    }
+}
+
+#[test]
+fn test_read_oversized_deflate_chunk() {
+    // A strip of 16 x 10 bytes whose stream decompresses to 1 MiB
+    let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder.write_all(&vec![0; 1 << 20]).unwrap();
+    let stream = encoder.finish().unwrap();
+    let data = encode_gray8(stream.len() as u32, 1, &stream, |encoder| {
+        encoder.write_tag(Tag::ImageWidth, 16u32).unwrap();
+        encoder.write_tag(Tag::ImageLength, 10u32).unwrap();
+        encoder.write_tag(Tag::RowsPerStrip, 10u32).unwrap();
+        encoder.write_tag(Tag::Compression, 8u16).unwrap();
+    });
+    assert!(matches!(
+        GeoTiff::from_bytes(&data),
+        Err(TiffError::FormatError(TiffFormatError::Format(_)))
+    ));
+}
@@ -0,0 +1,122 @@
+#![cfg(feature = "decode")]
+
+use std::io::Cursor;
+
+use common::{ascii, rationals, read_raster, write_raster, Georeferencing};
+use geotiff::{
+    copy_with, ByteOrder, DateTime, DocumentInfo, Exif, GeoTiff, GpsPosition, InMemoryRaster,
+    MetadataEdits, RawTag, TransformTags, WriteOptions,
+};
+
+mod common;
+
+fn drone_exif() -> Exif {
+    Exif {
+        byte_order: ByteOrder::LittleEndian,
+        exif: vec![
+            ascii(36867, "2024:06:01 10:20:30"),
+            RawTag {
+                tag: 37500,
+                field_type: 7,
+                count: 6,
+                bytes: b"vendor".to_vec(),
+            },
+        ],
+        gps: vec![
+            ascii(1, "N"),
+            rationals(2, &[(46, 1), (30, 1), (0, 1)]),
+            ascii(3, "W"),
+            rationals(4, &[(7, 1), (15, 1), (0, 1)]),
+            RawTag {
+                tag: 5,
+                field_type: 1,
+                count: 1,
+                bytes: vec![0],
+            },
+            rationals(6, &[(2401, 2)]),
+        ],
+    }
+}
+
+fn raster() -> InMemoryRaster {
+    let data = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+    InMemoryRaster {
+        transform_tags: TransformTags::default(),
+        document_info: DocumentInfo {
+            xmp: Some(b"<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"/>".to_vec()),
+            ..Default::default()
+        },
+        exif: Some(drone_exif()),
+        ..common::raster(Georeferencing::Wgs84, 3, 2, data, None)
+    }
+}
+
+#[test]
+fn test_parse_exif() {
+    let exif = drone_exif();
+    assert_eq!(
+        exif.capture_date_time(),
+        DateTime::parse("2024-06-01T10:20:30")
+    );
+    assert_eq!(
+        exif.gps_position(),
+        Some(GpsPosition {
+            latitude: 46.5,
+            longitude: -7.25,
+            altitude: Some(1200.5),
+        })
+    );
+    assert_eq!(exif.ascii(exif.get(36867).unwrap()).unwrap().len(), 19);
+    assert_eq!(exif.rationals(exif.get(37500).unwrap()), None);
+    assert_eq!(
+        Exif {
+            gps: Vec::new(),
+            ..exif
+        }
+        .gps_position(),
+        None
+    );
+}
+
+#[test]
+fn test_write_exif_and_xmp() {
+    let raster = raster();
+    let geotiff = read_raster(&raster, &WriteOptions::default());
+    assert_eq!(geotiff.exif(), raster.exif.as_ref());
+    assert_eq!(geotiff.document_info().xmp, raster.document_info.xmp);
+
+    // The values are converted to the byte order of the file
+    let options = WriteOptions {
+        byte_order: ByteOrder::BigEndian,
+        ..Default::default()
+    };
+    let geotiff = read_raster(&raster, &options);
+    let exif = geotiff.exif().unwrap();
+    assert_eq!(exif.byte_order, ByteOrder::BigEndian);
+    assert_eq!(exif.gps_position(), drone_exif().gps_position());
+    assert_eq!(exif.capture_date_time(), drone_exif().capture_date_time());
+    assert_eq!(exif.get(37500).unwrap().bytes, b"vendor");
+
+    let plain = InMemoryRaster {
+        exif: None,
+        ..raster.clone()
+    };
+    let geotiff = read_raster(&plain, &WriteOptions::default());
+    assert_eq!(geotiff.exif(), None);
+}
+
+#[test]
+fn test_copy_preserves_exif_and_xmp() {
+    let raster = raster();
+    let source = write_raster(&raster, &WriteOptions::default());
+    let mut buffer = Cursor::new(Vec::new());
+    let edits = MetadataEdits {
+        nodata: Some(Some(0.0)),
+        ..Default::default()
+    };
+    copy_with(&source[..], &mut buffer, &edits).unwrap();
+    let geotiff = GeoTiff::from_bytes(&buffer.into_inner()).unwrap();
+    assert_eq!(geotiff.nodata(), Some(0.0));
+    assert_eq!(geotiff.exif(), raster.exif.as_ref());
+    assert_eq!(geotiff.document_info().xmp, raster.document_info.xmp);
+}
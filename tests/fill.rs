@@ -0,0 +1,48 @@
+#![cfg(feature = "decode")]
+
+use common::encode_gray8;
+use geotiff::fill;
+use geotiff::GeoTiff;
+use tiff::tags::Tag;
+
+mod common;
+
+fn read_image(width: u32, height: u32, data: &[u8]) -> GeoTiff {
+    let data = encode_gray8(width, height, data, |encoder| {
+        encoder.write_tag(Tag::GdalNodata, "255").unwrap();
+    });
+    GeoTiff::from_bytes(&data).unwrap()
+}
+
+#[test]
+fn test_fill_nodata() {
+    #[rustfmt::skip]
+    let geotiff = read_image(5, 3, &[
+        10, 10, 10, 10, 10,
+        10, 255, 255, 255, 30,
+        10, 10, 10, 10, 10,
+    ]);
+    let band = geotiff.band(0).unwrap();
+
+    let filled = fill::fill_nodata(band, 10.0, 0).unwrap();
+    assert_eq!(filled.get(0, 0), 10.0);
+    assert!((filled.get(2, 1) - 100.0 / 9.0).abs() < 1e-9);
+    assert!(filled.data.iter().all(|value| !value.is_nan()));
+
+    // Only the direct neighbors are within reach
+    let filled = fill::fill_nodata(band, 1.0, 0).unwrap();
+    assert_eq!(filled.get(2, 1), 10.0);
+    assert_eq!(filled.get(3, 1), 50.0 / 3.0);
+
+    let smoothed = fill::fill_nodata(band, 10.0, 1).unwrap();
+    assert_eq!(smoothed.get(4, 1), 30.0);
+    assert!(smoothed.get(2, 1) < 100.0 / 9.0);
+}
+
+#[test]
+fn test_fill_nodata_out_of_reach() {
+    let geotiff = read_image(4, 1, &[5, 255, 255, 255]);
+    let filled = fill::fill_nodata(geotiff.band(0).unwrap(), 2.0, 0).unwrap();
+    assert_eq!(&filled.data[..3], &[5.0, 5.0, 5.0]);
+    assert!(filled.get(3, 0).is_nan());
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "decode")]
+
+use common::encode_gray8;
+use geotiff::focal::{self, FocalOperation, Kernel};
+use geotiff::GeoTiff;
+use tiff::tags::Tag;
+
+mod common;
+
+fn read_test_image() -> GeoTiff {
+    #[rustfmt::skip]
+    let data = encode_gray8(3, 3, &[
+        1, 2, 3,
+        4, 5, 6,
+        7, 8, 255,
+    ], |encoder| {
+        encoder.write_tag(Tag::GdalNodata, "255").unwrap();
+    });
+    GeoTiff::from_bytes(&data).unwrap()
+}
+
+#[test]
+fn test_focal_operations() {
+    let geotiff = read_test_image();
+    let band = geotiff.band(0).unwrap();
+
+    let mean = focal::focal(band, 1, FocalOperation::Mean).unwrap();
+    assert_eq!(mean.get(0, 0), 3.0);
+    assert_eq!(mean.get(1, 1), 4.5);
+    assert!(mean.get(2, 2).is_nan());
+
+    let min = focal::focal(band, 1, FocalOperation::Min).unwrap();
+    assert_eq!(min.get(2, 1), 2.0);
+    let max = focal::focal(band, 1, FocalOperation::Max).unwrap();
+    assert_eq!(max.get(1, 1), 8.0);
+    let median = focal::focal(band, 1, FocalOperation::Median).unwrap();
+    assert_eq!(median.get(1, 1), 4.5);
+    assert_eq!(median.get(0, 2), 6.0);
+}
+
+#[test]
+fn test_convolve() {
+    let geotiff = read_test_image();
+    let band = geotiff.band(0).unwrap();
+
+    let shift = Kernel::new(3, 1, vec![1.0, 0.0, 0.0]).unwrap();
+    let shifted = focal::convolve(band, &shift).unwrap();
+    assert!(shifted.get(0, 0).is_nan());
+    assert_eq!(shifted.get(1, 0), 1.0);
+    assert_eq!(shifted.get(2, 1), 5.0);
+
+    let smoothing = Kernel::new_normalized(3, 3, vec![1.0; 9]).unwrap();
+    let smoothed = focal::convolve(band, &smoothing).unwrap();
+    assert_eq!(smoothed.get(1, 1), 4.5);
+
+    let laplacian = Kernel::new(3, 3, vec![0.0, 1.0, 0.0, 1.0, -4.0, 1.0, 0.0, 1.0, 0.0]).unwrap();
+    let laplacian = focal::convolve(band, &laplacian).unwrap();
+    assert_eq!(laplacian.get(1, 1), 0.0);
+    assert!(laplacian.get(1, 2).is_nan());
+}
+
+#[test]
+fn test_invalid_kernel() {
+    assert!(Kernel::new(2, 3, vec![1.0; 6]).is_err());
+    assert!(Kernel::new(3, 3, vec![1.0; 8]).is_err());
+    assert!(Kernel::new_normalized(3, 1, vec![1.0; 4]).is_err());
+}
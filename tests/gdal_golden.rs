@@ -0,0 +1,126 @@
+#![cfg(feature = "decode")]
+
+//! Compares the transformations between raster space and model space with the values reported
+//! by GDAL, stored in tests/golden as the output of `gdalinfo -json`, see tests/golden/update.sh.
+
+use std::fs;
+use std::path::Path;
+
+use common::read_geotiff;
+use geo_types::Coord;
+use geotiff::RasterType;
+use serde_json::Value;
+
+mod common;
+
+/// The tolerance on model coordinates, relative to the size of a pixel.
+const TOLERANCE: f64 = 1e-6;
+
+fn coord(value: &Value) -> Coord {
+    let values = value.as_array().expect("Expected a coordinate array");
+    Coord {
+        x: values[0].as_f64().unwrap(),
+        y: values[1].as_f64().unwrap(),
+    }
+}
+
+fn check_golden(name: &str, golden: &Value) {
+    let geotiff = read_geotiff(Path::new("resources").join(format!("{name}.tif")));
+    let transform = geotiff.coordinate_transform().unwrap();
+    // GDAL gives the coordinates of pixel corners, which are at -0.5 in raster space for
+    // RasterPixelIsPoint
+    let offset = match geotiff.geo_key_directory.raster_type {
+        Some(RasterType::RasterPixelIsPoint) => -0.5,
+        _ => 0.0,
+    };
+    let to_model = |x: f64, y: f64| {
+        transform.transform_to_model(&Coord {
+            x: x + offset,
+            y: y + offset,
+        })
+    };
+
+    let size = coord(&golden["size"]);
+    assert_eq!(
+        (geotiff.raster_width as f64, geotiff.raster_height as f64),
+        (size.x, size.y),
+        "{name}: size"
+    );
+
+    let geo_transform = golden["geoTransform"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|value| value.as_f64().unwrap())
+        .collect::<Vec<_>>();
+    let pixel_size = geo_transform[1].hypot(geo_transform[4]);
+    let assert_close = |actual: Coord, expected: Coord, what: &str| {
+        assert!(
+            (actual.x - expected.x).abs() <= TOLERANCE * pixel_size
+                && (actual.y - expected.y).abs() <= TOLERANCE * pixel_size,
+            "{name}: {what} is {actual:?}, GDAL gives {expected:?}"
+        );
+    };
+
+    let origin = to_model(0.0, 0.0);
+    let along_x = to_model(1.0, 0.0) - origin;
+    let along_y = to_model(0.0, 1.0) - origin;
+    let actual = [
+        origin.x, along_x.x, along_y.x, origin.y, along_x.y, along_y.y,
+    ];
+    for (i, (actual, expected)) in actual.iter().zip(&geo_transform).enumerate() {
+        assert!(
+            (actual - expected).abs() <= TOLERANCE * pixel_size,
+            "{name}: geotransform coefficient {i} is {actual}, GDAL gives {expected}"
+        );
+    }
+
+    let corners = &golden["cornerCoordinates"];
+    for (corner, x, y) in [
+        ("upperLeft", 0.0, 0.0),
+        ("lowerLeft", 0.0, size.y),
+        ("lowerRight", size.x, size.y),
+        ("upperRight", size.x, 0.0),
+        ("center", size.x / 2.0, size.y / 2.0),
+    ] {
+        assert_close(to_model(x, y), coord(&corners[corner]), corner);
+    }
+    let upper_left = coord(&corners["upperLeft"]);
+    let lower_right = coord(&corners["lowerRight"]);
+    let extent = geotiff.model_extent();
+    assert_close(
+        extent.min(),
+        Coord {
+            x: upper_left.x.min(lower_right.x),
+            y: upper_left.y.min(lower_right.y),
+        },
+        "minimum of the extent",
+    );
+    assert_close(
+        extent.max(),
+        Coord {
+            x: upper_left.x.max(lower_right.x),
+            y: upper_left.y.max(lower_right.y),
+        },
+        "maximum of the extent",
+    );
+}
+
+#[test]
+fn test_gdal_golden_values() {
+    let mut names = fs::read_dir("tests/golden")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+    assert!(!names.is_empty());
+
+    for path in names {
+        let golden: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        check_golden(path.file_stem().unwrap().to_str().unwrap(), &golden);
+    }
+}
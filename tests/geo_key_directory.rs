@@ -0,0 +1,350 @@
+#[cfg(feature = "decode")]
+use common::encode_gray8;
+use geotiff::{
+    Confidence, DirectoryEntry, GeoKeyDirectory, GeoKeyDirectoryRef, GeoKeyId, GeoKeyValue,
+    GeoKeyValueRef, GeoTag, InferredEpsg, KeyDiff,
+};
+#[cfg(feature = "decode")]
+use geotiff::{GeoTiff, OpenOptions};
+#[cfg(feature = "decode")]
+use tiff::tags::Tag;
+
+mod common;
+
+#[test]
+fn test_diff() {
+    let old = GeoKeyDirectory::from_tag_data(
+        &[
+            1, 1, 1, 4, 1024, 0, 1, 1, 1025, 0, 1, 1, 1026, 34737, 4, 0, 3072, 0, 1, 32632,
+        ],
+        &[],
+        "UTM|",
+    )
+    .unwrap();
+    let new = GeoKeyDirectory::from_tag_data(
+        &[
+            1, 1, 1, 4, 1024, 0, 1, 1, 1025, 0, 1, 2, 3072, 0, 1, 32633, 3076, 0, 1, 9001,
+        ],
+        &[],
+        "",
+    )
+    .unwrap();
+
+    assert_eq!(
+        old.diff(&new),
+        vec![
+            KeyDiff::Changed {
+                key: GeoKeyId::RasterType,
+                old: GeoKeyValue::Short(1),
+                new: GeoKeyValue::Short(2),
+            },
+            KeyDiff::Removed {
+                key: GeoKeyId::Citation,
+                value: GeoKeyValue::Ascii("UTM".into()),
+            },
+            KeyDiff::Changed {
+                key: GeoKeyId::ProjectedType,
+                old: GeoKeyValue::Short(32632),
+                new: GeoKeyValue::Short(32633),
+            },
+            KeyDiff::Added {
+                key: GeoKeyId::ProjLinearUnits,
+                value: GeoKeyValue::Short(9001),
+            },
+        ]
+    );
+    assert!(old.diff(&old).is_empty());
+
+    // The same NaN in both directories is unchanged
+    let nan = GeoKeyDirectory {
+        proj_false_easting: Some(f64::NAN),
+        ..new.clone()
+    };
+    assert!(nan.diff(&nan.clone()).is_empty());
+    assert!(matches!(
+        new.diff(&nan)[..],
+        [KeyDiff::Added {
+            key: GeoKeyId::ProjFalseEasting,
+            value: GeoKeyValue::Double(value),
+        }] if value.is_nan()
+    ));
+}
+
+#[test]
+fn test_display() {
+    let directory = GeoKeyDirectory::from_tag_data(
+        &[
+            1, 1, 1, 4, 1024, 0, 1, 1, 1026, 34737, 4, 0, 3072, 0, 1, 32633, 3092, 34736, 1, 0,
+        ],
+        &[0.9996],
+        "UTM|",
+    )
+    .unwrap();
+
+    assert_eq!(
+        directory.to_string(),
+        "GeoKeyDirectory version 1, revision 1.1\n  \
+        ModelType: 1 (Projected)\n  \
+        Citation: \"UTM\"\n  \
+        ProjectedType: 32633 (WGS 84 / UTM zone 33N)\n  \
+        ProjScaleAtNatOrigin: 0.9996"
+    );
+}
+
+#[test]
+fn test_iter() {
+    let directory = GeoKeyDirectory::from_tag_data(
+        &[
+            1, 1, 1, 3, 1024, 0, 1, 1, 3092, 34736, 1, 0, 3072, 0, 1, 32633,
+        ],
+        &[0.9996],
+        "",
+    )
+    .unwrap();
+
+    assert_eq!(
+        directory.iter().collect::<Vec<_>>(),
+        vec![
+            (GeoKeyId::ModelType, GeoKeyValue::Short(1)),
+            (GeoKeyId::ProjectedType, GeoKeyValue::Short(32633)),
+            (GeoKeyId::ProjScaleAtNatOrigin, GeoKeyValue::Double(0.9996)),
+        ]
+    );
+    assert_eq!(GeoKeyDirectory::default().iter().count(), 0);
+}
+
+#[test]
+fn test_directory_entries() {
+    let entries =
+        DirectoryEntry::from_directory_data(&[1, 1, 1, 2, 1024, 0, 1, 2, 2057, 34736, 1, 0])
+            .unwrap();
+    assert_eq!(
+        entries,
+        vec![
+            DirectoryEntry {
+                key_id: GeoKeyId::ModelType,
+                location: None,
+                count: 1,
+                value_or_offset: 2,
+            },
+            DirectoryEntry {
+                key_id: GeoKeyId::GeogSemiMajorAxis,
+                location: Some(GeoTag::GeoDoubleParams),
+                count: 1,
+                value_or_offset: 0,
+            },
+        ]
+    );
+    assert_eq!(entries[0].short().unwrap(), 2);
+    assert_eq!(entries[1].double(&[6378137.0]).unwrap(), 6378137.0);
+    assert!(entries[1].short().is_err());
+    assert_eq!(entries[1].to_raw(), [2057, 34736, 1, 0]);
+    assert_eq!(u16::from(GeoKeyId::ProjectedType), 3072);
+
+    assert!(DirectoryEntry::from_raw([1, 0, 1, 0]).is_err());
+    assert!(DirectoryEntry::from_directory_data(&[1, 1, 1, 2, 1024, 0, 1, 2]).is_err());
+}
+
+#[test]
+fn test_infer_epsg() {
+    let exact = GeoKeyDirectory {
+        model_type: Some(1),
+        projected_type: Some(32632),
+        ..Default::default()
+    };
+    assert_eq!(
+        exact.infer_epsg(),
+        Some(InferredEpsg {
+            code: 32632,
+            confidence: Confidence::Exact
+        })
+    );
+
+    let by_codes = GeoKeyDirectory {
+        model_type: Some(1),
+        projected_type: Some(32767),
+        geographic_type: Some(4326),
+        projection: Some(16033),
+        ..Default::default()
+    };
+    assert_eq!(
+        by_codes.infer_epsg(),
+        Some(InferredEpsg {
+            code: 32633,
+            confidence: Confidence::High
+        })
+    );
+
+    let by_parameters = GeoKeyDirectory {
+        model_type: Some(1),
+        projected_type: Some(32767),
+        geographic_type: Some(32767),
+        geog_ellipsoid: Some(7030),
+        projection: Some(32767),
+        proj_coord_trans: Some(1),
+        proj_linear_units: Some(9001),
+        proj_nat_origin_long: Some(15.0),
+        proj_nat_origin_lat: Some(0.0),
+        proj_false_easting: Some(500000.0),
+        proj_false_northing: Some(10000000.0),
+        proj_scale_at_nat_origin: Some(0.9996),
+        ..Default::default()
+    };
+    assert_eq!(
+        by_parameters.infer_epsg(),
+        Some(InferredEpsg {
+            code: 32733,
+            confidence: Confidence::Low
+        })
+    );
+
+    let nad83_south = GeoKeyDirectory {
+        geog_ellipsoid: Some(7019),
+        ..by_parameters.clone()
+    };
+    assert_eq!(nad83_south.infer_epsg(), None);
+    let not_utm = GeoKeyDirectory {
+        proj_scale_at_nat_origin: Some(1.0),
+        ..by_parameters
+    };
+    assert_eq!(not_utm.infer_epsg(), None);
+    assert_eq!(GeoKeyDirectory::default().infer_epsg(), None);
+}
+
+#[cfg(feature = "decode")]
+#[test]
+fn test_lenient_ascii_params() {
+    let entry = |count, offset| DirectoryEntry {
+        key_id: GeoKeyId::Citation,
+        location: Some(GeoTag::GeoAsciiParams),
+        count,
+        value_or_offset: offset,
+    };
+    let data = b"WGS 84\0UTM|Caf\xe9|";
+    assert_eq!(entry(7, 0).string_lenient(data).unwrap(), "WGS 84");
+    assert_eq!(entry(3, 7).string_lenient(data).unwrap(), "UTM");
+    assert_eq!(entry(4, 7).string_lenient(data).unwrap(), "UTM");
+    assert_eq!(entry(0, 7).string_lenient(data).unwrap(), "UTM");
+    assert_eq!(entry(8, 11).string_lenient(data).unwrap(), "Caf\u{fffd}");
+    assert_eq!(entry(1, 16).string_lenient(data).unwrap(), "");
+    assert!(entry(1, 17).string_lenient(data).is_err());
+
+    let directory_data = [
+        1, 1, 1, 3, 1026, 34737, 7, 0, 2049, 34737, 3, 7, 3073, 34737, 4, 20,
+    ];
+    assert!(GeoKeyDirectory::from_tag_data(&directory_data, &[], "WGS 84\0UTM|").is_err());
+    let directory =
+        GeoKeyDirectory::from_tag_data_lenient(&directory_data, &[], b"WGS 84\0UTM|").unwrap();
+    assert_eq!(directory.citation.as_deref(), Some("WGS 84"));
+    assert_eq!(directory.geog_citation.as_deref(), Some("UTM"));
+    assert_eq!(directory.proj_citation, None);
+
+    // The tiff crate cannot encode NULs, and truncates the GeoAsciiParamsTag at the first one
+    let mut data = encode_gray8(1, 1, &[0], |encoder| {
+        encoder
+            .write_tag(Tag::GeoKeyDirectoryTag, &directory_data[..])
+            .unwrap();
+        encoder
+            .write_tag(Tag::GeoAsciiParamsTag, "WGS 84|UTM|")
+            .unwrap();
+    });
+    let start = data.windows(11).position(|w| w == b"WGS 84|UTM|").unwrap();
+    data[start + 6] = 0;
+    assert!(GeoTiff::from_bytes(&data).is_err());
+    let geotiff = OpenOptions::new()
+        .lenient_ascii_params(true)
+        .read_bytes(&data)
+        .unwrap();
+    let directory = geotiff.geo_key_directory().unwrap();
+    assert_eq!(directory.citation.as_deref(), Some("WGS 84"));
+    assert_eq!(directory.geog_citation, None);
+}
+
+#[test]
+fn test_geo_key_directory_ref() {
+    let directory_data = [
+        1, 1, 1, 4, 1024, 0, 1, 1, 1026, 34737, 4, 0, 3072, 0, 1, 32633, 3092, 34736, 1, 0,
+    ];
+    let directory = GeoKeyDirectoryRef::from_tag_data(&directory_data, &[0.9996], "UTM|").unwrap();
+    assert_eq!(directory.minor_revision(), 1);
+    assert_eq!(directory.citation(), Some("UTM"));
+    assert_eq!(directory.proj_citation(), None);
+    assert_eq!(directory.short(GeoKeyId::ProjectedType), Some(32633));
+    assert_eq!(
+        directory.double(GeoKeyId::ProjScaleAtNatOrigin),
+        Some(0.9996)
+    );
+    assert_eq!(directory.short(GeoKeyId::ProjScaleAtNatOrigin), None);
+    assert_eq!(
+        directory.iter().collect::<Vec<_>>(),
+        vec![
+            (GeoKeyId::ModelType, GeoKeyValueRef::Short(1)),
+            (GeoKeyId::Citation, GeoKeyValueRef::Ascii("UTM")),
+            (GeoKeyId::ProjectedType, GeoKeyValueRef::Short(32633)),
+            (
+                GeoKeyId::ProjScaleAtNatOrigin,
+                GeoKeyValueRef::Double(0.9996)
+            ),
+        ]
+    );
+    assert_eq!(
+        directory.to_directory(),
+        GeoKeyDirectory::from_tag_data(&directory_data, &[0.9996], "UTM|").unwrap()
+    );
+
+    assert!(GeoKeyDirectoryRef::from_tag_data(&directory_data, &[], "UTM|").is_err());
+    assert!(GeoKeyDirectoryRef::from_tag_data(&[1, 1, 1, 1, 1025, 0, 1, 3], &[], "").is_err());
+    assert!(
+        GeoKeyDirectoryRef::from_tag_data(&[1, 1, 1, 1, 1024, 34736, 1, 0], &[1.0], "").is_err()
+    );
+}
+
+#[test]
+fn test_merged_with() {
+    let base = GeoKeyDirectory {
+        model_type: Some(1),
+        citation: Some("UTM".into()),
+        projected_type: Some(32632),
+        proj_linear_units: Some(9001),
+        ..Default::default()
+    };
+    let overrides = GeoKeyDirectory {
+        model_type: Some(1),
+        projected_type: Some(32633),
+        proj_scale_at_nat_origin: Some(0.9996),
+        ..Default::default()
+    };
+
+    let (merged, conflicts) = base.merged_with(&overrides).unwrap();
+    assert_eq!(
+        merged,
+        GeoKeyDirectory {
+            model_type: Some(1),
+            citation: Some("UTM".into()),
+            projected_type: Some(32633),
+            proj_linear_units: Some(9001),
+            proj_scale_at_nat_origin: Some(0.9996),
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        conflicts,
+        vec![KeyDiff::Changed {
+            key: GeoKeyId::ProjectedType,
+            old: GeoKeyValue::Short(32632),
+            new: GeoKeyValue::Short(32633),
+        }]
+    );
+    assert_eq!(
+        base.merged_with(&GeoKeyDirectory::default()),
+        Ok((base.clone(), vec![]))
+    );
+
+    // The offsets of the ASCII parameters are 16-bit
+    let long_citation = GeoKeyDirectory {
+        geog_citation: Some("WGS 84".repeat(12_000)),
+        ..Default::default()
+    };
+    assert!(base.merged_with(&long_citation).is_err());
+    assert!(long_citation.to_tag_data().is_err());
+}
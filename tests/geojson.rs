@@ -0,0 +1,42 @@
+#![cfg(feature = "geojson")]
+
+use common::read_geotiff;
+
+mod common;
+
+fn parse_bbox(geojson: &str) -> Vec<f64> {
+    let start = geojson.find("\"bbox\":[").unwrap() + "\"bbox\":[".len();
+    let end = start + geojson[start..].find(']').unwrap();
+    geojson[start..end]
+        .split(',')
+        .map(|value| value.parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_geojson() {
+    let geotiff = read_geotiff(
+        "resources/austrian_capitals_model_tie_point_and_pixel_scale_pixel_is_area.tif",
+    );
+
+    let footprint = geotiff.footprint_geojson().unwrap();
+    assert!(footprint.starts_with("{\"type\":\"Feature\",\"geometry\":{\"type\":\"Polygon\""));
+    assert_eq!(footprint.matches('[').count(), 2 + 65);
+
+    let bbox = parse_bbox(&geotiff.bbox_geojson().unwrap());
+    let expected = [9.74, 46.51, 16.57, 48.41];
+    for (value, expected) in bbox.iter().zip(expected) {
+        assert!((value - expected).abs() < 0.01, "{bbox:?}");
+    }
+
+    let grid = geotiff.tile_grid_geojson().unwrap();
+    assert!(grid.starts_with("{\"type\":\"FeatureCollection\""));
+    assert_eq!(grid.matches("\"type\":\"Feature\",").count(), 12);
+    assert!(grid.contains("\"properties\":{\"column\":0,\"row\":11}"));
+}
+
+#[test]
+fn test_geojson_ungeoreferenced() {
+    let geotiff = read_geotiff("resources/marbles.tif");
+    assert!(geotiff.footprint_geojson().is_err());
+}
@@ -0,0 +1,15 @@
+#!/bin/sh
+# Regenerates the golden values of tests/gdal_golden.rs with GDAL, for the fixtures whose
+# transformation GDAL reports as a geotransform, i.e. not those defined by several tie points.
+set -e
+cd "$(dirname "$0")/../.."
+for name in \
+    austrian_capitals_model_tie_point_and_pixel_scale_pixel_is_area \
+    austrian_capitals_model_tie_point_and_pixel_scale_pixel_is_point \
+    austrian_capitals_model_transformation_pixel_is_area \
+    austrian_capitals_model_transformation_pixel_is_point \
+    merc \
+    zh_dem_25
+do
+    gdalinfo -json "resources/$name.tif" > "tests/golden/$name.json"
+done
@@ -0,0 +1,154 @@
+#![cfg(feature = "decode")]
+
+use std::io::Cursor;
+
+use common::encode_gray8_images;
+use geotiff::{GeoTiff, SubfileType};
+use tiff::tags::{PhotometricInterpretation, Tag};
+
+mod common;
+
+#[test]
+fn test_subfile_types() {
+    let data = encode_gray8_images(
+        &[
+            (4, 4, &[1; 16]),
+            (2, 2, &[2; 4]),
+            (4, 4, &[255; 16]),
+            (1, 1, &[3; 1]),
+        ],
+        |index, encoder| match index {
+            1 => encoder.write_tag(Tag::NewSubfileType, 1u32).unwrap(),
+            2 => {
+                encoder.write_tag(Tag::NewSubfileType, 4u32).unwrap();
+                encoder
+                    .write_tag(
+                        Tag::PhotometricInterpretation,
+                        PhotometricInterpretation::TransparencyMask.to_u16(),
+                    )
+                    .unwrap();
+            }
+            3 => encoder.write_tag(Tag::NewSubfileType, 1u32).unwrap(),
+            _ => {}
+        },
+    );
+    let geotiff = GeoTiff::read(Cursor::new(data)).unwrap();
+
+    assert_eq!(geotiff.images().len(), 4);
+    assert_eq!(geotiff.primary().index, 0);
+    assert_eq!(geotiff.primary().subfile_type, SubfileType::FullResolution);
+    assert_eq!(
+        geotiff
+            .overviews()
+            .map(|image| (image.index, image.raster_width))
+            .collect::<Vec<_>>(),
+        vec![(1, 2), (3, 1)]
+    );
+    assert!(geotiff.overviews().all(|image| image.has_raster_data()));
+
+    let masks = geotiff.masks().collect::<Vec<_>>();
+    assert_eq!(masks.len(), 1);
+    assert_eq!(masks[0].index, 2);
+    assert!(!masks[0].has_raster_data());
+    assert_eq!(geotiff.pages().count(), 0);
+}
+
+#[test]
+fn test_primary_is_first_full_resolution_image() {
+    let data = encode_gray8_images(&[(1, 1, &[7]), (3, 2, &[9; 6])], |index, encoder| {
+        if index == 0 {
+            encoder.write_tag(Tag::NewSubfileType, 1u32).unwrap();
+        }
+    });
+    let geotiff = GeoTiff::read(Cursor::new(data)).unwrap();
+
+    assert_eq!(geotiff.primary().index, 1);
+    assert_eq!(geotiff.raster_width, 3);
+    assert_eq!(geotiff.raster_height, 2);
+    assert_eq!(
+        geotiff.get_value_at::<u8>(&geo_types::Coord { x: 2.5, y: 1.5 }, 0),
+        Some(9)
+    );
+}
+
+#[test]
+fn test_georeferencing_per_image() {
+    let data = encode_gray8_images(&[(2, 2, &[1; 4]), (2, 2, &[2; 4])], |index, encoder| {
+        let (projected_type, origin_x) = match index {
+            0 => (32632u16, 1000.0),
+            _ => (32633u16, 5000.0),
+        };
+        encoder
+            .write_tag(Tag::NewSubfileType, 2u32 * index as u32)
+            .unwrap();
+        encoder
+            .write_tag(
+                Tag::GeoKeyDirectoryTag,
+                &[1u16, 1, 0, 2, 1024, 0, 1, 1, 3072, 0, 1, projected_type][..],
+            )
+            .unwrap();
+        encoder
+            .write_tag(Tag::ModelPixelScaleTag, &[10.0, 10.0, 0.0][..])
+            .unwrap();
+        encoder
+            .write_tag(
+                Tag::ModelTiepointTag,
+                &[0.0, 0.0, 0.0, origin_x, 2000.0, 0.0][..],
+            )
+            .unwrap();
+    });
+    let geotiff = GeoTiff::read(Cursor::new(data)).unwrap();
+
+    let first = geotiff.image(0).unwrap();
+    let second = geotiff.image(1).unwrap();
+    assert!(geotiff.image(2).is_none());
+    assert_eq!(
+        first.geo_key_directory().unwrap().projected_type,
+        Some(32632)
+    );
+    assert_eq!(
+        second.geo_key_directory().unwrap().projected_type,
+        Some(32633)
+    );
+    assert_eq!(geotiff.geo_key_directory.projected_type, Some(32632));
+
+    assert_eq!(first.model_bounds_outer().min().x, 1000.0);
+    assert_eq!(second.model_bounds_outer().min().x, 5000.0);
+    let coord = geo_types::Coord {
+        x: 5005.0,
+        y: 1995.0,
+    };
+    assert_eq!(first.get_value_at::<u8>(&coord, 0), None);
+    assert_eq!(second.get_value_at::<u8>(&coord, 0), Some(2));
+}
+
+#[test]
+fn test_best_level_for_resolution() {
+    let data = encode_gray8_images(
+        &[(8, 8, &[0; 64]), (4, 4, &[1; 16]), (2, 2, &[2; 4])],
+        |index, encoder| {
+            if index == 0 {
+                encoder
+                    .write_tag(Tag::ModelPixelScaleTag, &[10.0, 10.0, 0.0][..])
+                    .unwrap();
+                encoder
+                    .write_tag(
+                        Tag::ModelTiepointTag,
+                        &[0.0, 0.0, 0.0, 1000.0, 2000.0, 0.0][..],
+                    )
+                    .unwrap();
+            } else {
+                encoder.write_tag(Tag::NewSubfileType, 1u32).unwrap();
+            }
+        },
+    );
+    let geotiff = GeoTiff::read(Cursor::new(data)).unwrap();
+
+    assert_eq!(geotiff.primary().pixel_size(), Some((10.0, 10.0)));
+    assert_eq!(geotiff.image(1).unwrap().pixel_size(), None);
+    assert_eq!(geotiff.best_level_for_resolution(5.0).index, 0);
+    assert_eq!(geotiff.best_level_for_resolution(10.0).index, 0);
+    assert_eq!(geotiff.best_level_for_resolution(20.0).index, 1);
+    assert_eq!(geotiff.best_level_for_resolution(39.0).index, 1);
+    assert_eq!(geotiff.best_level_for_resolution(100.0).index, 2);
+}
@@ -1,6 +1,12 @@
-use common::read_geotiff;
-use geo_types::{Coord, Rect};
-use geotiff::{GeoKeyDirectory, RasterType};
+#![cfg(feature = "decode")]
+
+use common::{encode_gray8, read_geotiff};
+use geo_types::{coord, polygon, Coord, Rect};
+use geotiff::{
+    CoordinateTransform, GeoKeyDirectory, GeoTiff, RasterType, ReadOptions, TransformTags,
+};
+use tiff::tags::Tag;
+use tiff::TiffError;
 
 mod common;
 
@@ -144,3 +150,210 @@ fn test_load_merc() {
         )
     );
 }
+
+#[test]
+fn test_transform_geometry() {
+    let geotiff = read_geotiff("resources/zh_dem_25.tif");
+    let transform = geotiff.coordinate_transform().unwrap();
+
+    let raster_polygon = polygon![
+        (x: 0.0, y: 0.0),
+        (x: 399.0, y: 0.0),
+        (x: 399.0, y: 366.0),
+        (x: 0.0, y: 366.0),
+    ];
+    let model_polygon = transform.geometry_to_model(&raster_polygon);
+    assert_eq!(
+        model_polygon,
+        polygon![
+            (x: 677562.5, y: 253012.5),
+            (x: 687537.5, y: 253012.5),
+            (x: 687537.5, y: 243862.5),
+            (x: 677562.5, y: 243862.5),
+        ]
+    );
+    assert_eq!(
+        transform.geometry_to_raster(&model_polygon).unwrap(),
+        raster_polygon
+    );
+    assert_eq!(
+        transform.geometry_to_model(&coord! { x: 0.0, y: 0.0 }),
+        coord! { x: 677562.5, y: 253012.5 }
+    );
+}
+
+#[cfg(feature = "proj4rs")]
+#[test]
+fn test_proj4rs() {
+    let geotiff = read_geotiff("resources/merc.tif");
+    let proj = geotiff.geo_key_directory.to_proj4rs().unwrap();
+    assert_eq!(proj.projname(), "merc");
+
+    let geotiff = read_geotiff(
+        "resources/austrian_capitals_model_tie_point_and_pixel_scale_pixel_is_area.tif",
+    );
+    let proj = geotiff.geo_key_directory.to_proj4rs().unwrap();
+    assert_eq!(proj.projname(), "laea");
+}
+
+#[test]
+fn test_model_bounds() {
+    let geotiff = read_geotiff("resources/zh_dem_25.tif");
+    assert_eq!(
+        geotiff.model_bounds_outer(),
+        Rect::new(
+            coord! { x: 677562.5, y: 243862.5 },
+            coord! { x: 687537.5, y: 253012.5 }
+        )
+    );
+    assert_eq!(
+        geotiff.model_bounds_center(),
+        Rect::new(
+            coord! { x: 677575.0, y: 243875.0 },
+            coord! { x: 687525.0, y: 253000.0 }
+        )
+    );
+
+    let geotiff =
+        read_geotiff("resources/austrian_capitals_model_transformation_pixel_is_point.tif");
+    assert_eq!(
+        geotiff.model_bounds_outer(),
+        Rect::new(
+            coord! { x: 4301500.0, y: 2621500.0 },
+            coord! { x: 4808500.0, y: 2811500.0 }
+        )
+    );
+    assert_eq!(
+        geotiff.model_bounds_center(),
+        Rect::new(
+            coord! { x: 4302000.0, y: 2622000.0 },
+            coord! { x: 4808000.0, y: 2811000.0 }
+        )
+    );
+}
+
+#[test]
+fn test_from_bytes() {
+    let bytes = std::fs::read("resources/zh_dem_25.tif").unwrap();
+    let geotiff = GeoTiff::from_bytes(&bytes).unwrap();
+    assert_eq!(geotiff.raster_width, 399);
+    assert_eq!(
+        geotiff.model_extent(),
+        read_geotiff("resources/zh_dem_25.tif").model_extent()
+    );
+
+    assert!(GeoTiff::open_path("resources/missing.tif").is_err());
+}
+
+#[test]
+fn test_clone() {
+    let geotiff = read_geotiff("resources/zh_dem_25.tif");
+    let clone = geotiff.clone();
+    // The images are shared rather than copied
+    assert!(std::ptr::eq(geotiff.primary(), clone.primary()));
+    let coord = Coord {
+        x: 677575.0,
+        y: 253000.0,
+    };
+    let expected = geotiff.get_value_at::<i16>(&coord, 0);
+    assert_eq!(expected, Some(551));
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            let clone = geotiff.clone();
+            scope.spawn(move || assert_eq!(clone.get_value_at::<i16>(&coord, 0), expected));
+        }
+    });
+}
+
+#[test]
+fn test_capabilities() {
+    let capabilities = read_geotiff("resources/marbles.tif").capabilities();
+    assert_eq!(capabilities.compression, 5);
+    assert!(capabilities.compression_supported);
+    assert_eq!(capabilities.predictor, 2);
+    assert!(capabilities.predictor_supported);
+    assert_eq!(capabilities.overviews, 0);
+    assert!(capabilities.undecodable_images.is_empty());
+    assert!(!capabilities.crs_resolvable);
+    assert!(!capabilities.is_fully_supported());
+
+    let capabilities = read_geotiff("resources/zh_dem_25.tif").capabilities();
+    assert_eq!(capabilities.compression, 1);
+    assert!(!capabilities.crs_resolvable);
+
+    let capabilities =
+        read_geotiff("resources/austrian_capitals_model_transformation_pixel_is_point.tif")
+            .capabilities();
+    assert!(capabilities.crs_resolvable);
+    assert!(capabilities.is_fully_supported());
+}
+
+#[test]
+fn test_capabilities_unsupported_compression() {
+    let data = encode_gray8(2, 2, &[1, 2, 3, 4], |encoder| {
+        encoder.write_tag(Tag::Compression, 34712u16).unwrap();
+    });
+    let geotiff = GeoTiff::from_bytes(&data).unwrap();
+    assert!(!geotiff.primary().has_raster_data());
+
+    let capabilities = geotiff.capabilities();
+    assert_eq!(capabilities.compression, 34712);
+    assert!(!capabilities.compression_supported);
+    assert_eq!(capabilities.undecodable_images, vec![0]);
+    assert!(!capabilities.is_fully_supported());
+
+    let error = geotiff
+        .read_window::<u8>(geotiff.primary().window(), &ReadOptions::default())
+        .unwrap_err();
+    assert!(matches!(error, TiffError::UnsupportedError(_)), "{error}");
+    assert_eq!(
+        geotiff.get_value_at::<u8>(&Coord { x: 0.5, y: 0.5 }, 0),
+        None
+    );
+}
+
+#[test]
+fn test_display() {
+    let geotiff = read_geotiff("resources/zh_dem_25.tif");
+    assert_eq!(
+        geotiff.coordinate_transform().unwrap().to_string(),
+        "Tie point (0, 0) -> (677562.5, 253012.5), pixel scale (25, 25)"
+    );
+
+    let geotiff =
+        read_geotiff("resources/austrian_capitals_model_transformation_pixel_is_point.tif");
+    assert!(geotiff
+        .geo_key_directory
+        .to_string()
+        .contains("ProjectedType: 3035 (ETRS89-extended / LAEA Europe)"));
+    assert!(geotiff
+        .coordinate_transform()
+        .unwrap()
+        .to_string()
+        .starts_with("Affine transformation: x = 1000 * i + 0 * j + "));
+}
+
+#[test]
+fn test_from_decoder() {
+    let file = std::fs::File::open("resources/merc.tif").unwrap();
+    let mut decoder = tiff::decoder::Decoder::new(std::io::BufReader::new(file)).unwrap();
+    let geotiff = read_geotiff("resources/merc.tif");
+
+    let directory = GeoKeyDirectory::try_from(&mut decoder).unwrap();
+    assert_eq!(Some(&directory), geotiff.geo_key_directory());
+    let transform = CoordinateTransform::try_from(&mut decoder).unwrap();
+    let coord = Coord { x: 10.5, y: 20.5 };
+    assert_eq!(
+        transform.transform_to_model(&coord),
+        geotiff
+            .coordinate_transform()
+            .unwrap()
+            .transform_to_model(&coord)
+    );
+
+    let file = std::fs::File::open("resources/marbles.tif").unwrap();
+    let mut decoder = tiff::decoder::Decoder::new(std::io::BufReader::new(file)).unwrap();
+    assert!(GeoKeyDirectory::try_from(&mut decoder).is_err());
+    assert!(CoordinateTransform::try_from(&mut decoder).is_err());
+    assert!(TransformTags::try_from(&mut decoder).unwrap().is_empty());
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "decode")]
+
+use common::{raster, write_raster, Georeferencing};
+use geotiff::{GeoTiff, IfdPlacement, Layout, LayoutWarning, OpenOptions, WriteOptions};
+
+mod common;
+
+fn write(width: usize, height: usize, options: &WriteOptions) -> Vec<u8> {
+    let data = vec![0.0; width * height];
+    write_raster(
+        &raster(Georeferencing::Wgs84, width, height, data, None),
+        options,
+    )
+}
+
+fn tiled(tile_size: u32, ifd_placement: IfdPlacement) -> WriteOptions {
+    WriteOptions {
+        layout: Layout::Tiles(tile_size),
+        ifd_placement,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_layout_warnings() {
+    // Rows of 140000 doubles, each in its own strip
+    let bytes = write(140_000, 1, &WriteOptions::default());
+    let warnings = GeoTiff::from_bytes(&bytes).unwrap().layout_warnings();
+    assert_eq!(
+        warnings,
+        [LayoutWarning::HugeStrips {
+            image: 0,
+            strip_bytes: 1_120_000
+        }]
+    );
+    let options = warnings[0].suggested_options(WriteOptions::default());
+    assert_eq!(options.layout, Layout::Tiles(256));
+
+    let bytes = write(40, 20, &tiled(16, IfdPlacement::Start));
+    let warnings = GeoTiff::from_bytes(&bytes).unwrap().layout_warnings();
+    assert_eq!(
+        warnings,
+        [LayoutWarning::TinyTiles {
+            image: 0,
+            tile_width: 16,
+            tile_height: 16
+        }]
+    );
+    assert_eq!(warnings[0].image(), 0);
+
+    // A single small tile is fine
+    let bytes = write(40, 20, &tiled(128, IfdPlacement::Start));
+    assert_eq!(GeoTiff::from_bytes(&bytes).unwrap().layout_warnings(), []);
+}
+
+#[test]
+fn test_layout_warnings_data_before_ifds() {
+    let read = |ifd_placement| {
+        let bytes = write(40, 20, &tiled(128, ifd_placement));
+        OpenOptions::new()
+            .preload_index(true)
+            .read_bytes(&bytes)
+            .unwrap()
+            .layout_warnings()
+    };
+    let warnings = read(IfdPlacement::AfterData);
+    assert_eq!(warnings, [LayoutWarning::DataBeforeIfds { image: 0 }]);
+    let options = warnings[0].suggested_options(WriteOptions::default());
+    assert_eq!(options.ifd_placement, IfdPlacement::Start);
+    assert_eq!(read(IfdPlacement::BeforeData), []);
+    assert_eq!(read(IfdPlacement::Start), []);
+
+    // The chunks are not known without the index
+    let bytes = write(40, 20, &tiled(128, IfdPlacement::AfterData));
+    assert_eq!(GeoTiff::from_bytes(&bytes).unwrap().layout_warnings(), []);
+}
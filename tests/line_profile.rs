@@ -0,0 +1,62 @@
+#![cfg(feature = "decode")]
+
+use common::{raster, read_raster, Georeferencing};
+use geo_types::line_string;
+use geotiff::{GeoTiff, Resampling, WriteOptions};
+
+mod common;
+
+/// A 40 x 30 raster of 10 m pixels whose values are their column plus 100 times their row, with
+/// the nodata value 1010.
+fn geotiff() -> GeoTiff {
+    let data = (0..40 * 30)
+        .map(|i| (i % 40 + i / 40 * 100) as f64)
+        .collect();
+    read_raster(
+        &raster(Georeferencing::Utm32, 40, 30, data, Some(1010.0)),
+        &WriteOptions::default(),
+    )
+}
+
+#[test]
+fn test_profile() {
+    let geotiff = geotiff();
+    let band = geotiff.band(0).unwrap();
+    // From the center of the pixel (0, 0) to that of (10, 0), then to that of (10, 10)
+    let line = line_string![
+        (x: 1005.0, y: 1995.0),
+        (x: 1105.0, y: 1995.0),
+        (x: 1105.0, y: 1895.0),
+    ];
+
+    let profile = band.profile(&line, 30.0, Resampling::Bilinear).unwrap();
+    let distances = profile
+        .iter()
+        .map(|point| point.distance)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        distances,
+        [0.0, 30.0, 60.0, 90.0, 120.0, 150.0, 180.0, 200.0]
+    );
+    let values = profile.iter().map(|point| point.value).collect::<Vec<_>>();
+    for (value, expected) in values.iter().zip([0.0, 3.0, 6.0, 9.0, 210.0, 510.0, 810.0]) {
+        assert!((value - expected).abs() < 1e-9, "{value} != {expected}");
+    }
+    assert_eq!(profile[4].coord.x, 1105.0);
+    assert_eq!(profile[4].coord.y, 1975.0);
+
+    // The sample at 35 m is on the left edge of the pixel (4, 0)
+    let profile = band.profile(&line, 35.0, Resampling::Nearest).unwrap();
+    assert_eq!(profile[1].value, 4.0);
+    // The pixel at the end of the line is nodata
+    assert_eq!(profile.last().unwrap().distance, 200.0);
+    assert!(profile.last().unwrap().value.is_nan());
+
+    // Outside the raster
+    let line = line_string![(x: 995.0, y: 1995.0), (x: 1015.0, y: 1995.0)];
+    let profile = band.profile(&line, 10.0, Resampling::Bilinear).unwrap();
+    assert!(profile[0].value.is_nan());
+    assert_eq!(profile.len(), 3);
+
+    assert!(band.profile(&line, 0.0, Resampling::Nearest).is_err());
+}
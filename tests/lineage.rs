@@ -0,0 +1,28 @@
+#![cfg(feature = "decode")]
+
+use common::encode_gray8;
+use geotiff::composite::{composite, CompositeMethod};
+use geotiff::{GeoTiff, Lineage};
+
+mod common;
+
+#[test]
+fn test_lineage() {
+    let input = GeoTiff::from_bytes(&encode_gray8(2, 1, &[1, 2], |_| ())).unwrap();
+    assert_eq!(input.lineage(), None);
+
+    let lineage = Lineage::new("composite")
+        .with_input("s3://bucket/a & b.tif")
+        .with_input("<second>.tif")
+        .with_parameter("method", format!("{:?}", CompositeMethod::Median));
+    assert!(lineage.software.starts_with("geotiff "));
+    let raster = composite(&[input.band(0).unwrap().into()], CompositeMethod::Median)
+        .unwrap()
+        .with_lineage(lineage.clone());
+    let output = GeoTiff::from_bytes(&raster.to_bytes().unwrap()).unwrap();
+    assert_eq!(output.lineage(), Some(lineage));
+    let metadata = output.gdal_metadata().unwrap();
+    assert_eq!(metadata.get("LINEAGE_OPERATION"), Some("composite"));
+    assert_eq!(metadata.get("LINEAGE_INPUT_1"), Some("<second>.tif"));
+    assert_eq!(metadata.get("LINEAGE_PARAMETER_method"), Some("Median"));
+}
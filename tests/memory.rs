@@ -0,0 +1,191 @@
+#![cfg(feature = "decode")]
+
+use std::fs;
+use std::io::Cursor;
+
+use common::encode_gray8;
+use geo_types::Coord;
+use geotiff::{GeoTiff, OpenOptions, OutOfBounds, ReadOptions, Resampling, Window};
+use tiff::encoder::compression::Deflate;
+use tiff::encoder::{colortype, TiffEncoder};
+use tiff::tags::Tag;
+use tiff::TiffError;
+
+mod common;
+
+#[test]
+fn test_memory_budget() {
+    let data = encode_gray8(
+        16,
+        10,
+        &(0..160).map(|v| v as u8).collect::<Vec<_>>(),
+        |_| {},
+    );
+
+    let geotiff = GeoTiff::from_bytes(&data).unwrap();
+    assert_eq!(geotiff.raster_memory(), 160);
+    let options = ReadOptions::default();
+    assert_eq!(
+        geotiff
+            .estimate_memory::<f64>(Window::new(-2, 0, 4, 4), &options)
+            .unwrap(),
+        128
+    );
+    let clamp = ReadOptions {
+        out_of_bounds: OutOfBounds::Clamp,
+        ..Default::default()
+    };
+    assert_eq!(
+        geotiff
+            .estimate_memory::<f64>(Window::new(-2, 0, 4, 4), &clamp)
+            .unwrap(),
+        64
+    );
+
+    // Decoded on demand over the budget
+    let lazy = OpenOptions::new()
+        .memory_budget(159)
+        .read_bytes(&data)
+        .unwrap();
+    assert_eq!(lazy.raster_memory(), 0);
+    assert!(lazy.primary().has_raster_data());
+    assert_eq!(
+        lazy.estimate_memory::<f64>(Window::new(-2, 0, 4, 4), &options)
+            .unwrap(),
+        128 + 8
+    );
+    assert_eq!(
+        lazy.read_window::<u8>(geotiff.primary().window(), &options)
+            .unwrap(),
+        geotiff
+            .read_window::<u8>(geotiff.primary().window(), &options)
+            .unwrap()
+    );
+    assert_eq!(
+        OpenOptions::new()
+            .memory_budget(160)
+            .read_bytes(&data)
+            .unwrap()
+            .raster_memory(),
+        160
+    );
+
+    // The compressions left to the tiff crate cannot be decoded on demand
+    let unsupported = encode_gray8(16, 10, &[0; 160], |encoder| {
+        encoder.write_tag(Tag::Compression, 34712u16).unwrap();
+    });
+    assert!(matches!(
+        OpenOptions::new()
+            .memory_budget(159)
+            .read_bytes(&unsupported),
+        Err(TiffError::LimitsExceeded)
+    ));
+}
+
+#[test]
+fn test_read_window_over_budget() {
+    let values = (0..40 * 30).map(|v| v as u16 * 7).collect::<Vec<_>>();
+    let mut buffer = Cursor::new(Vec::new());
+    let mut encoder = TiffEncoder::new(&mut buffer).unwrap();
+    let mut image = encoder
+        .new_image_with_compression::<colortype::Gray16, _>(40, 30, Deflate::default())
+        .unwrap();
+    image.rows_per_strip(4).unwrap();
+    image.write_data(&values).unwrap();
+    let data = buffer.into_inner();
+
+    let path = std::env::temp_dir().join(format!("geotiff-over-budget-{}.tif", std::process::id()));
+    fs::write(&path, &data).unwrap();
+    let eager = GeoTiff::from_bytes(&data).unwrap();
+    let mut options = OpenOptions::new();
+    options.memory_budget(40 * 30 * 2 - 1);
+    let from_bytes = options.read_bytes(&data).unwrap();
+    let from_path = options.open_path(&path).unwrap();
+    assert_eq!(from_bytes.raster_memory(), 0);
+    assert_eq!(from_path.raster_memory(), 0);
+
+    let fill = ReadOptions {
+        out_of_bounds: OutOfBounds::Fill(1.0),
+        ..Default::default()
+    };
+    let bilinear = ReadOptions {
+        resampling: Resampling::Bilinear,
+        ..Default::default()
+    };
+    for lazy in [&from_bytes, &from_path] {
+        assert_eq!(
+            lazy.get_value_at::<u16>(&Coord { x: 12.5, y: 7.5 }, 0),
+            Some(values[7 * 40 + 12])
+        );
+        for window in [
+            Window::new(3, 5, 10, 7),
+            Window::new(-2, 27, 6, 5),
+            Window::new(50, 0, 2, 2),
+            eager.primary().window(),
+        ] {
+            assert_eq!(
+                lazy.read_window::<u16>(window, &fill).unwrap(),
+                eager.read_window::<u16>(window, &fill).unwrap()
+            );
+            assert_eq!(
+                lazy.read_window_scaled(window, 0.5, 1.0, &fill).unwrap(),
+                eager.read_window_scaled(window, 0.5, 1.0, &fill).unwrap()
+            );
+            assert_eq!(
+                lazy.read_window_decimated::<f64>(window, 3, 2, &bilinear)
+                    .unwrap(),
+                eager
+                    .read_window_decimated::<f64>(window, 3, 2, &bilinear)
+                    .unwrap()
+            );
+        }
+    }
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_read_window_chunked() {
+    let data = encode_gray8(
+        16,
+        10,
+        &(0..160).map(|v| v as u8).collect::<Vec<_>>(),
+        |_| {},
+    );
+    let geotiff = GeoTiff::from_bytes(&data).unwrap();
+    let options = ReadOptions::default();
+    let window = Window::new(-2, 1, 18, 9);
+
+    let mut chunks = Vec::new();
+    geotiff
+        .read_window_chunked::<u16, _>(window, &options, 18 * 2 * 4, |chunk| {
+            chunks.push(chunk);
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(
+        chunks.iter().map(|chunk| chunk.window).collect::<Vec<_>>(),
+        vec![
+            Window::new(-2, 1, 18, 4),
+            Window::new(-2, 5, 18, 4),
+            Window::new(-2, 9, 18, 1),
+        ]
+    );
+    let whole = geotiff.read_window::<u16>(window, &options).unwrap();
+    assert_eq!(
+        chunks
+            .into_iter()
+            .flat_map(|chunk| chunk.data)
+            .collect::<Vec<_>>(),
+        whole.data
+    );
+
+    let mut count = 0;
+    geotiff
+        .read_window_chunked::<u16, _>(window, &options, usize::MAX, |chunk| {
+            assert_eq!(chunk, whole);
+            count += 1;
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(count, 1);
+}
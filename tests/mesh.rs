@@ -0,0 +1,81 @@
+#![cfg(feature = "mesh")]
+
+use common::{raster, read_raster, Georeferencing};
+use geotiff::mesh::to_mesh;
+use geotiff::{InMemoryRaster, TransformTags, Window, WriteOptions};
+
+mod common;
+
+/// A 3 x 3 DEM of 10 m pixels, whose value at the center of the bottom row is nodata.
+fn dem() -> InMemoryRaster {
+    let data = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, -9999.0, 9.0];
+    raster(Georeferencing::Utm32, 3, 3, data, Some(-9999.0))
+}
+
+/// Returns whether each triangle is counter-clockwise seen from above.
+fn counter_clockwise(positions: &[[f64; 3]], indices: &[u32]) -> bool {
+    indices.chunks_exact(3).all(|triangle| {
+        let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i] as usize]);
+        (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]) > 0.0
+    })
+}
+
+#[test]
+fn test_to_mesh() {
+    let geotiff = read_raster(&dem(), &WriteOptions::default());
+    let band = geotiff.band(0).unwrap();
+    let mesh = to_mesh(band, geotiff.primary().window(), 2.0).unwrap();
+    assert_eq!(mesh.positions.len(), 8);
+    assert_eq!(mesh.positions[0], [1005.0, 1995.0, 2.0]);
+    assert_eq!(mesh.positions[7], [1025.0, 1975.0, 18.0]);
+    // The 8 triangles of the 4 quads, without the 3 touching the nodata pixel
+    assert_eq!(mesh.indices.len(), 5 * 3);
+    assert!(counter_clockwise(&mesh.positions, &mesh.indices));
+
+    // Rows going up in model space
+    let raster = InMemoryRaster {
+        transform_tags: TransformTags {
+            pixel_scale: None,
+            tie_points: None,
+            model_transformation: Some(vec![
+                10.0, 0.0, 0.0, 1000.0, 0.0, 10.0, 0.0, 2000.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+                1.0,
+            ]),
+        },
+        ..dem()
+    };
+    let geotiff = read_raster(&raster, &WriteOptions::default());
+    let band = geotiff.band(0).unwrap();
+    let mesh = to_mesh(band, Window::new(-5, 1, 10, 10), 1.0).unwrap();
+    assert_eq!(mesh.positions.len(), 5);
+    assert_eq!(mesh.indices.len(), 3);
+    assert!(counter_clockwise(&mesh.positions, &mesh.indices));
+}
+
+#[test]
+fn test_write_glb() {
+    let geotiff = read_raster(&dem(), &WriteOptions::default());
+    let band = geotiff.band(0).unwrap();
+    let mesh = to_mesh(band, geotiff.primary().window(), 1.0).unwrap();
+    let mut glb = Vec::new();
+    mesh.write_glb(&mut glb).unwrap();
+
+    let u32_at = |offset: usize| u32::from_le_bytes(glb[offset..offset + 4].try_into().unwrap());
+    assert_eq!(&glb[..4], b"glTF");
+    assert_eq!((u32_at(4), u32_at(8) as usize), (2, glb.len()));
+    let json_length = u32_at(12) as usize;
+    assert_eq!(&glb[16..20], b"JSON");
+    let json: serde_json::Value = serde_json::from_slice(&glb[20..20 + json_length]).unwrap();
+    assert_eq!(&glb[24 + json_length..28 + json_length], b"BIN\0");
+    let bin_length = u32_at(20 + json_length) as usize;
+    assert_eq!(bin_length, 8 * 12 + 15 * 4);
+    assert_eq!(json["buffers"][0]["byteLength"], bin_length);
+    assert_eq!(json["accessors"][0]["count"], 8);
+    assert_eq!(json["accessors"][1]["count"], 15);
+    // The center of the mesh, with the y axis up
+    assert_eq!(
+        json["nodes"][0]["translation"],
+        serde_json::json!([1015, 5, -1985])
+    );
+    assert_eq!(json["accessors"][0]["max"], serde_json::json!([10, 4, 10]));
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "pmtiles")]
+
+use common::{encode_gray8, read_geotiff};
+use geotiff::{GeoTiff, TileExportOptions};
+use tiff::tags::Tag;
+
+mod common;
+
+const WORLD_EDGE: f64 = 20037508.342789244;
+
+#[test]
+fn test_export_pmtiles() {
+    // The world in Web Mercator, with a nodata quadrant in the south-east
+    #[rustfmt::skip]
+    let data = encode_gray8(4, 4, &[
+        10, 20, 30, 40,
+        50, 60, 70, 80,
+        90, 100, 255, 255,
+        110, 120, 255, 255,
+    ], |encoder| {
+        encoder
+            .write_tag(
+                Tag::GeoKeyDirectoryTag,
+                &[1u16, 1, 0, 2, 1024, 0, 1, 1, 3072, 0, 1, 3857][..],
+            )
+            .unwrap();
+        encoder
+            .write_tag(
+                Tag::ModelPixelScaleTag,
+                &[WORLD_EDGE / 2.0, WORLD_EDGE / 2.0, 0.0][..],
+            )
+            .unwrap();
+        encoder
+            .write_tag(
+                Tag::ModelTiepointTag,
+                &[0.0, 0.0, 0.0, -WORLD_EDGE, WORLD_EDGE, 0.0][..],
+            )
+            .unwrap();
+        encoder.write_tag(Tag::GdalNodata, "255").unwrap();
+    });
+    let geotiff = GeoTiff::from_bytes(&data).unwrap();
+
+    let options = TileExportOptions {
+        tile_size: 16,
+        ..Default::default()
+    };
+    let mut archive = Vec::new();
+    geotiff
+        .export_pmtiles(&mut archive, 0..=1, &options)
+        .unwrap();
+
+    assert_eq!(&archive[..8], b"PMTiles\x03");
+    let u64_at =
+        |offset: usize| u64::from_le_bytes(archive[offset..offset + 8].try_into().unwrap());
+    let (root_offset, tile_data_offset) = (u64_at(8) as usize, u64_at(56) as usize);
+    // The addressed tiles, excluding the empty tile in the south-east at zoom 1
+    assert_eq!(u64_at(72), 4);
+    // PNG tiles for zoom levels 0 to 1
+    assert_eq!(archive[99..102], [2, 0, 1]);
+
+    // The number of entries, the tile identifiers as deltas, then the run lengths
+    assert_eq!(
+        archive[root_offset..root_offset + 9],
+        [4, 0, 1, 1, 2, 1, 1, 1, 1]
+    );
+    assert_eq!(
+        &archive[tile_data_offset..tile_data_offset + 8],
+        b"\x89PNG\r\n\x1a\n"
+    );
+}
+
+#[test]
+fn test_export_pmtiles_not_web_mercator() {
+    let geotiff = read_geotiff("resources/merc.tif");
+    let mut archive = Vec::new();
+    assert!(geotiff
+        .export_pmtiles(&mut archive, 0..=1, &TileExportOptions::default())
+        .is_err());
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "decode")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use geotiff::{DatasetPool, GeoTiff, OpenOptions};
+use tiff::{TiffError, TiffFormatError};
+
+const DEM: &str = "resources/zh_dem_25.tif";
+
+/// A pool opening the DEM for any key but "missing", counting the openings.
+fn counting_pool(max_per_dataset: usize) -> (DatasetPool, Arc<AtomicUsize>) {
+    let bytes = std::fs::read(DEM).unwrap();
+    let openings = Arc::new(AtomicUsize::new(0));
+    let counter = openings.clone();
+    let pool = DatasetPool::with_opener(max_per_dataset, move |key| {
+        if key == "missing" {
+            return Err(TiffError::FormatError(TiffFormatError::Format(
+                "Missing dataset".into(),
+            )));
+        }
+        counter.fetch_add(1, Ordering::SeqCst);
+        GeoTiff::from_bytes(&bytes)
+    });
+    (pool, openings)
+}
+
+#[test]
+fn test_checkout() {
+    let pool = DatasetPool::new(2, OpenOptions::new());
+    let first = pool.checkout(DEM).unwrap();
+    let second = pool.checkout(DEM).unwrap();
+    assert_eq!(first.primary().raster_width, second.primary().raster_width);
+    assert_eq!(pool.opened(DEM), 2);
+    assert!(pool.try_checkout(DEM).unwrap().is_none());
+    drop(first);
+    assert!(pool.try_checkout(DEM).unwrap().is_some());
+    assert_eq!(pool.opened(DEM), 2);
+    assert!(pool.checkout("resources/missing.tif").is_err());
+    assert_eq!(pool.opened("resources/missing.tif"), 0);
+}
+
+#[test]
+fn test_reuse_and_close_idle() {
+    let (pool, openings) = counting_pool(2);
+    for _ in 0..3 {
+        let dataset = pool.checkout("a").unwrap();
+        assert!(dataset.is_georeferenced());
+    }
+    let b = pool.checkout("b").unwrap();
+    assert_eq!(openings.load(Ordering::SeqCst), 2);
+    assert_eq!((pool.opened("a"), pool.opened("b")), (1, 1));
+
+    // Only the idle instances are closed
+    pool.close_idle();
+    assert_eq!((pool.opened("a"), pool.opened("b")), (0, 1));
+    drop(b);
+    assert!(pool.try_checkout("b").unwrap().is_some());
+    assert_eq!(openings.load(Ordering::SeqCst), 2);
+
+    // A failed opening does not use up an instance
+    assert!(pool.checkout("missing").is_err());
+    assert!(pool.checkout("missing").is_err());
+    assert_eq!(pool.opened("missing"), 0);
+}
+
+#[test]
+fn test_concurrent_checkouts() {
+    let (pool, openings) = counting_pool(2);
+    let checked_out = AtomicUsize::new(0);
+    let max_checked_out = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| {
+                for _ in 0..10 {
+                    let dataset = pool.checkout("dem").unwrap();
+                    let count = checked_out.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_checked_out.fetch_max(count, Ordering::SeqCst);
+                    assert!(dataset.is_georeferenced());
+                    checked_out.fetch_sub(1, Ordering::SeqCst);
+                }
+            });
+        }
+    });
+    assert!(max_checked_out.load(Ordering::SeqCst) <= 2);
+    assert_eq!(openings.load(Ordering::SeqCst), pool.opened("dem"));
+    assert!(pool.opened("dem") <= 2);
+}
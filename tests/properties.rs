@@ -0,0 +1,191 @@
+use geo_types::Coord;
+use geotiff::{DirectoryEntry, GeoKeyDirectory, GeoKeyId, GeoKeyValue, GeoTag, TransformTags};
+use proptest::prelude::*;
+
+const ASCII_KEYS: [u16; 4] = [1026, 2049, 3073, 4097];
+const DOUBLE_KEYS: [u16; 25] = [
+    2053, 2055, 2057, 2058, 2059, 2061, 3077, 3078, 3079, 3080, 3081, 3082, 3083, 3084, 3085, 3086,
+    3087, 3088, 3089, 3090, 3091, 3092, 3093, 3094, 3095,
+];
+const SHORT_KEYS: [u16; 16] = [
+    1024, 1025, 2048, 2050, 2051, 2052, 2054, 2056, 2060, 3072, 3074, 3075, 3076, 4096, 4098, 4099,
+];
+
+/// Generates mostly small values, which are more likely to be valid counts and offsets.
+fn count_or_offset() -> impl Strategy<Value = u16> {
+    prop_oneof![3 => 0..20u16, 1 => any::<u16>()]
+}
+
+/// Generates the value of a key of the type it is stored as.
+fn key_value(key: u16) -> BoxedStrategy<GeoKeyValue> {
+    if ASCII_KEYS.contains(&key) {
+        "[a-zA-Z0-9 /_.()-]{0,40}"
+            .prop_map(GeoKeyValue::Ascii)
+            .boxed()
+    } else if DOUBLE_KEYS.contains(&key) {
+        (-1e9..1e9f64).prop_map(GeoKeyValue::Double).boxed()
+    } else if key == u16::from(GeoKeyId::RasterType) {
+        (1..=2u16).prop_map(GeoKeyValue::Short).boxed()
+    } else {
+        any::<u16>().prop_map(GeoKeyValue::Short).boxed()
+    }
+}
+
+/// Generates the keys of a valid directory along with their values, in the order of their IDs.
+fn valid_keys() -> impl Strategy<Value = Vec<(u16, GeoKeyValue)>> {
+    let keys = ASCII_KEYS
+        .iter()
+        .chain(&DOUBLE_KEYS)
+        .chain(&SHORT_KEYS)
+        .copied()
+        .collect::<Vec<_>>();
+    prop::collection::btree_set(prop::sample::select(keys), 0..20).prop_flat_map(|keys| {
+        keys.into_iter()
+            .map(|key| key_value(key).prop_map(move |value| (key, value)))
+            .collect::<Vec<_>>()
+    })
+}
+
+/// Encodes the keys as the values of the GeoKeyDirectoryTag, GeoDoubleParamsTag and
+/// GeoAsciiParamsTag, storing the parameters in reverse order.
+fn encode_keys(keys: &[(u16, GeoKeyValue)]) -> (Vec<u16>, Vec<f64>, String) {
+    let mut directory = vec![1, 1, 0, keys.len() as u16];
+    let mut double_params = Vec::new();
+    let mut ascii_params = String::new();
+    for (key, value) in keys.iter().rev() {
+        let entry = match value {
+            GeoKeyValue::Short(value) => [*key, 0, 1, *value],
+            GeoKeyValue::Double(value) => {
+                double_params.push(*value);
+                let offset = double_params.len() as u16 - 1;
+                [*key, GeoTag::GeoDoubleParams.to_u16(), 1, offset]
+            }
+            GeoKeyValue::Ascii(value) => {
+                let offset = ascii_params.len() as u16;
+                ascii_params.push_str(value);
+                ascii_params.push('|');
+                let count = value.len() as u16 + 1;
+                [*key, GeoTag::GeoAsciiParams.to_u16(), count, offset]
+            }
+        };
+        directory.splice(4..4, entry);
+    }
+    (directory, double_params, ascii_params)
+}
+
+proptest! {
+    #[test]
+    fn test_parse_valid_directory(keys in valid_keys()) {
+        let (directory, double_params, ascii_params) = encode_keys(&keys);
+        let parsed = GeoKeyDirectory::from_tag_data(&directory, &double_params, &ascii_params)
+            .unwrap();
+        let values = parsed
+            .iter()
+            .map(|(key, value)| (u16::from(key), value))
+            .collect::<Vec<_>>();
+        prop_assert_eq!(&values, &keys);
+
+        let (directory, double_params, ascii_params) = parsed.to_tag_data().unwrap();
+        let reparsed = GeoKeyDirectory::from_tag_data(&directory, &double_params, &ascii_params)
+            .unwrap();
+        prop_assert_eq!(reparsed, parsed);
+    }
+
+    #[test]
+    fn test_parse_arbitrary_directory(
+        header in prop::array::uniform4(any::<u16>()),
+        entries in prop::collection::vec(prop::array::uniform4(count_or_offset()), 0..8),
+        key_ids in prop::collection::vec(prop::sample::select(SHORT_KEYS.to_vec()), 8),
+        locations in prop::collection::vec(
+            prop::sample::select(vec![0, 34735, 34736, 34737]),
+            8,
+        ),
+        double_params in prop::collection::vec(any::<f64>(), 0..4),
+        ascii_params in ".{0,16}",
+        length_matches in any::<bool>(),
+    ) {
+        // Use mostly known keys and locations so that the values get parsed
+        let mut directory = header.to_vec();
+        if length_matches {
+            directory[3] = entries.len() as u16;
+        }
+        for (i, entry) in entries.iter().enumerate() {
+            let key_id = if i % 3 == 0 { entry[0] } else { key_ids[i] };
+            directory.extend([key_id, locations[i], entry[2], entry[3]]);
+        }
+        let _ = GeoKeyDirectory::from_tag_data(&directory, &double_params, &ascii_params);
+    }
+
+    #[test]
+    fn test_entry_values(
+        entry in prop::array::uniform4(count_or_offset()),
+        double_params in prop::collection::vec(any::<f64>(), 0..4),
+        ascii_params in ".{0,16}",
+    ) {
+        if let Ok(entry) = DirectoryEntry::from_raw([1026, entry[1], entry[2], entry[3]]) {
+            let _ = entry.short();
+            let _ = entry.double(&double_params);
+            let _ = entry.string(&ascii_params);
+            let entry = DirectoryEntry {
+                location: Some(GeoTag::GeoAsciiParams),
+                ..entry
+            };
+            if let Ok(value) = entry.string(&ascii_params) {
+                prop_assert!(value.len() < entry.count as usize);
+            }
+        }
+    }
+
+    #[test]
+    fn test_arbitrary_transform_tags(
+        pixel_scale in prop::option::of(prop::collection::vec(any::<f64>(), 0..5)),
+        tie_points in prop::option::of(prop::collection::vec(any::<f64>(), 0..14)),
+        model_transformation in prop::option::of(prop::collection::vec(any::<f64>(), 14..18)),
+        tolerance in any::<f64>(),
+        coord in any::<(f64, f64)>(),
+    ) {
+        let tags = TransformTags { pixel_scale, tie_points, model_transformation };
+        if let Ok(Some(transform)) = tags.to_coordinate_transform(tolerance) {
+            let coord = Coord { x: coord.0, y: coord.1 };
+            let _ = transform.transform_to_model(&coord);
+            let _ = transform.transform_to_raster(&coord);
+        }
+    }
+
+    #[cfg(feature = "tie-points")]
+    #[test]
+    fn test_arbitrary_tie_points(
+        tie_points in prop::collection::vec(prop::array::uniform6(-1e6..1e6f64), 3..8),
+        coord in any::<(f64, f64)>(),
+    ) {
+        let tags = TransformTags {
+            tie_points: Some(tie_points.concat()),
+            ..Default::default()
+        };
+        if let Ok(Some(transform)) = tags.to_coordinate_transform(1e-9) {
+            let coord = Coord { x: coord.0, y: coord.1 };
+            let _ = transform.transform_to_model(&coord);
+            let _ = transform.transform_to_raster(&coord);
+        }
+    }
+
+    #[test]
+    fn test_tie_point_and_pixel_scale_round_trip(
+        tie_point in prop::array::uniform6(-1e6..1e6f64),
+        scale in (1e-3..1e3f64, 1e-3..1e3f64),
+        coord in (-1e4..1e4f64, -1e4..1e4f64),
+    ) {
+        let tags = TransformTags {
+            pixel_scale: Some(vec![scale.0, scale.1, 0.0]),
+            tie_points: Some(tie_point.to_vec()),
+            model_transformation: None,
+        };
+        let transform = tags.to_coordinate_transform(1e-9).unwrap().unwrap();
+        let coord = Coord { x: coord.0, y: coord.1 };
+        let round_trip = transform
+            .transform_to_raster(&transform.transform_to_model(&coord))
+            .unwrap();
+        prop_assert!((round_trip.x - coord.x).abs() < 1e-6);
+        prop_assert!((round_trip.y - coord.y).abs() < 1e-6);
+    }
+}
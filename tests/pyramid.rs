@@ -0,0 +1,150 @@
+#![cfg(feature = "decode")]
+
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use common::{encode_gray8, read_geotiff};
+use geotiff::{pyramid, GeoTiff, Resampling, ResamplingKernel};
+use tiff::tags::Tag;
+
+mod common;
+
+fn read_test_image() -> GeoTiff {
+    #[rustfmt::skip]
+    let data = encode_gray8(5, 4, &[
+        1, 1, 2, 3, 9,
+        1, 255, 3, 3, 9,
+        4, 5, 6, 7, 9,
+        8, 8, 255, 255, 9,
+    ], |encoder| {
+        encoder
+            .write_tag(
+                Tag::GeoKeyDirectoryTag,
+                &[1u16, 1, 0, 2, 1024, 0, 1, 1, 3072, 0, 1, 32632][..],
+            )
+            .unwrap();
+        encoder
+            .write_tag(Tag::ModelPixelScaleTag, &[10.0, 10.0, 0.0][..])
+            .unwrap();
+        encoder
+            .write_tag(
+                Tag::ModelTiepointTag,
+                &[0.0, 0.0, 0.0, 1000.0, 2000.0, 0.0][..],
+            )
+            .unwrap();
+        encoder.write_tag(Tag::GdalNodata, "255").unwrap();
+    });
+    GeoTiff::from_bytes(&data).unwrap()
+}
+
+/// The Lanczos kernel with the given number of lobes.
+#[derive(Debug)]
+struct Lanczos(usize);
+
+impl ResamplingKernel for Lanczos {
+    fn radius(&self) -> usize {
+        self.0
+    }
+
+    fn weight(&self, distance: f64) -> f64 {
+        let sinc = |x: f64| {
+            if x == 0.0 {
+                1.0
+            } else {
+                (PI * x).sin() / (PI * x)
+            }
+        };
+        if distance.abs() < self.0 as f64 {
+            sinc(distance) * sinc(distance / self.0 as f64)
+        } else {
+            0.0
+        }
+    }
+}
+
+#[test]
+fn test_build_pyramid() {
+    let geotiff = read_test_image();
+    let band = geotiff.band(0).unwrap();
+
+    let levels = pyramid::build_pyramid(band, &[1, 2, 4], Resampling::Average).unwrap();
+    assert_eq!(
+        levels
+            .iter()
+            .map(|level| (level.width, level.height))
+            .collect::<Vec<_>>(),
+        [(5, 4), (3, 2), (2, 1)]
+    );
+    assert!(levels[0].get(1, 1).is_nan());
+    assert_eq!(levels[1].data[..5], [1.0, 2.75, 9.0, 6.25, 6.5]);
+    assert_eq!(levels[1].get(2, 1), 9.0);
+    assert_eq!(levels[2].data, [4.0, 9.0]);
+
+    let levels = pyramid::build_pyramid(band, &[2], Resampling::Mode).unwrap();
+    assert_eq!(levels[0].data, [1.0, 3.0, 9.0, 8.0, 6.0, 9.0]);
+
+    let levels = pyramid::build_pyramid(band, &[2], Resampling::Bilinear).unwrap();
+    assert_eq!(levels[0].data[..3], [1.0, 2.75, 9.0]);
+
+    let levels = pyramid::build_pyramid(band, &[2], Resampling::Cubic).unwrap();
+    assert_eq!(levels[0].get(2, 0), 9.0);
+
+    let lanczos = Resampling::Kernel(Arc::new(Lanczos(3)));
+    let levels = pyramid::build_pyramid(band, &[2], lanczos).unwrap();
+    assert!((levels[0].get(2, 0) - 9.0).abs() < 1e-9);
+
+    let levels = pyramid::build_pyramid(band, &[2], Resampling::Nearest).unwrap();
+    assert!(levels[0].get(0, 0).is_nan());
+    assert_eq!(levels[0].data[1..4], [3.0, 9.0, 8.0]);
+
+    assert!(pyramid::build_pyramid(band, &[2, 0], Resampling::Average).is_err());
+}
+
+#[test]
+fn test_pyramid_georeferencing() {
+    let geotiff = read_test_image();
+    let levels =
+        pyramid::build_pyramid(geotiff.band(0).unwrap(), &[2], Resampling::Average).unwrap();
+    let level = GeoTiff::from_bytes(&levels[0].to_bytes().unwrap()).unwrap();
+    assert_eq!(level.primary().pixel_size(), Some((20.0, 20.0)));
+    let bounds = level.model_bounds_outer();
+    assert_eq!((bounds.min().x, bounds.max().y), (1000.0, 2000.0));
+
+    for path in [
+        "resources/austrian_capitals_model_tie_point_and_pixel_scale_pixel_is_point.tif",
+        "resources/austrian_capitals_model_transformation_pixel_is_point.tif",
+    ] {
+        let geotiff = read_geotiff(path);
+        let levels =
+            pyramid::build_pyramid(geotiff.band(0).unwrap(), &[2], Resampling::Nearest).unwrap();
+        let level = GeoTiff::from_bytes(&levels[0].to_bytes().unwrap()).unwrap();
+        let (bounds, level_bounds) = (geotiff.model_bounds_outer(), level.model_bounds_outer());
+        assert_eq!(bounds.min().x, level_bounds.min().x);
+        assert_eq!(bounds.max().y, level_bounds.max().y);
+    }
+}
+
+#[test]
+fn test_pyramid_levels_accumulated() {
+    let data = (0..37 * 23)
+        .map(|i| (i * 31 % 256) as u8)
+        .collect::<Vec<_>>();
+    let geotiff = GeoTiff::from_bytes(&encode_gray8(37, 23, &data, |encoder| {
+        encoder.write_tag(Tag::GdalNodata, "255").unwrap();
+    }))
+    .unwrap();
+    let band = geotiff.band(0).unwrap();
+
+    // The levels accumulated from the previous ones match those accumulated from the pixels,
+    // i.e. after a level of factor 1
+    let factors = [2, 4, 3, 8, 6, 16];
+    let levels = pyramid::build_pyramid(band, &factors, Resampling::Average).unwrap();
+    for (factor, level) in factors.into_iter().zip(&levels) {
+        let direct = pyramid::build_pyramid(band, &[1, factor], Resampling::Average).unwrap();
+        let direct = &direct[1];
+        assert_eq!((level.width, level.height), (direct.width, direct.height));
+        for (value, expected) in level.data.iter().zip(&direct.data) {
+            assert!((value - expected).abs() < 1e-9 || (value.is_nan() && expected.is_nan()));
+        }
+    }
+}
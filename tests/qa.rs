@@ -0,0 +1,77 @@
+#![cfg(feature = "decode")]
+
+use common::encode_gray8;
+use geotiff::{GeoTiff, OutOfBounds, QaRule, QaSpec, ReadOptions, Window};
+use tiff::tags::Tag;
+
+mod common;
+
+#[test]
+fn test_qa_rules() {
+    let confidence = QaRule::values(8, 2, &[2, 3]);
+    assert_eq!(confidence.extract(0b11_0000_1000), 3);
+    assert!(confidence.matches(0b10_0000_0000));
+    assert!(!confidence.matches(0b01_1111_1111));
+    assert_eq!(QaRule::values(0, 64, &[]).extract(u64::MAX), u64::MAX);
+    assert_eq!(QaRule::flag(70).extract(u64::MAX), 0);
+
+    let spec = QaSpec {
+        rules: vec![QaRule::flag(3), confidence],
+    };
+    assert!(spec.is_masked(0b1000));
+    assert!(spec.is_masked(0b11_0000_0000));
+    assert!(!spec.is_masked(0b01_0111_0111));
+    assert!(!QaSpec::default().is_masked(u64::MAX));
+}
+
+#[test]
+fn test_read_window_masked() {
+    let image = GeoTiff::from_bytes(&encode_gray8(2, 2, &[1, 2, 3, 4], |encoder| {
+        encoder.write_tag(Tag::GdalNodata, "0").unwrap();
+    }))
+    .unwrap();
+    let qa = GeoTiff::from_bytes(&encode_gray8(
+        2,
+        2,
+        &[0, 0b1000, 0b110_0000, 0b10_0000],
+        |_| (),
+    ))
+    .unwrap();
+    let qa_band = qa.band(0).unwrap();
+    let spec = QaSpec {
+        rules: vec![QaRule::flag(3), QaRule::values(5, 2, &[3])],
+    };
+
+    assert_eq!(
+        spec.valid_mask(qa_band, Window::new(0, 0, 2, 3)).unwrap(),
+        [true, false, false, true, false, false]
+    );
+    let data = image
+        .read_window_masked::<u8>(
+            image.primary().window(),
+            &ReadOptions::default(),
+            qa_band,
+            &spec,
+        )
+        .unwrap();
+    assert_eq!(data.data, [1, 0, 0, 4]);
+
+    let options = ReadOptions {
+        out_of_bounds: OutOfBounds::Fill(255.0),
+        ..Default::default()
+    };
+    let data = image
+        .read_window_masked::<u8>(Window::new(1, 1, 2, 1), &options, qa_band, &spec)
+        .unwrap();
+    assert_eq!(data.data, [4, 255]);
+
+    let small = GeoTiff::from_bytes(&encode_gray8(1, 1, &[0], |_| ())).unwrap();
+    assert!(image
+        .read_window_masked::<u8>(
+            image.primary().window(),
+            &ReadOptions::default(),
+            small.band(0).unwrap(),
+            &spec
+        )
+        .is_err());
+}
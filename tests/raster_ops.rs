@@ -0,0 +1,244 @@
+#![cfg(feature = "decode")]
+
+use common::{assert_values, grid};
+use geotiff::raster_ops::{self, Operation};
+use geotiff::{DateTime, GeoTiff, InMemoryRaster, Layout, ReadOptions, WriteMask, WriteOptions};
+use tiff::tags::Tag;
+
+mod common;
+
+#[test]
+fn test_band_operations() {
+    let a = grid(3, 2, 1000.0, &[1, 2, 3, 4, 255, 6]);
+    let b = grid(3, 2, 1010.0, &[10, 20, 30, 40, 50, 255]);
+    let (band_a, band_b) = (a.band(0).unwrap(), b.band(0).unwrap());
+
+    let sum = raster_ops::add(band_a, band_b).unwrap();
+    assert_values(&sum.data, &[f64::NAN, 12.0, 23.0, f64::NAN, f64::NAN, 56.0]);
+    assert!(sum.is_nodata(sum.get(0, 0)));
+
+    let maximum = raster_ops::apply(Operation::Max, band_a, band_b).unwrap();
+    assert_values(
+        &maximum.data,
+        &[f64::NAN, 10.0, 20.0, f64::NAN, f64::NAN, 50.0],
+    );
+
+    let misaligned = grid(3, 2, 1005.0, &[0; 6]);
+    assert!(raster_ops::sub(band_a, misaligned.band(0).unwrap()).is_err());
+}
+
+#[test]
+fn test_scalar_operations() {
+    let a = grid(3, 2, 1000.0, &[1, 2, 3, 4, 255, 6]);
+    let band = a.band(0).unwrap();
+
+    let product = raster_ops::mul(band, 2.0).unwrap();
+    assert_values(&product.data, &[2.0, 4.0, 6.0, 8.0, f64::NAN, 12.0]);
+    let quotient = raster_ops::div(band, 0.0).unwrap();
+    assert!(quotient.data.iter().all(|value| value.is_nan()));
+    assert!(a.band(1).is_none());
+}
+
+#[test]
+fn test_write_in_memory_raster() {
+    let a = grid(3, 2, 1000.0, &[1, 2, 3, 4, 255, 6]);
+    let difference = raster_ops::sub(a.band(0).unwrap(), 1.0).unwrap();
+
+    let written = GeoTiff::from_bytes(&difference.to_bytes().unwrap()).unwrap();
+    assert!(written.nodata().unwrap().is_nan());
+    assert_eq!(written.geo_key_directory(), a.geo_key_directory());
+    assert_eq!(written.model_extent(), a.model_extent());
+    let values = written
+        .band(0)
+        .unwrap()
+        .read_window(written.primary().window())
+        .unwrap();
+    assert_values(&values, &[0.0, 1.0, 2.0, 3.0, f64::NAN, 5.0]);
+}
+
+#[test]
+fn test_write_mask() {
+    let a = grid(3, 2, 1000.0, &[1, 2, 3, 4, 255, 6]);
+    let raster = raster_ops::sub(a.band(0).unwrap(), 1.0).unwrap();
+    let write = |mask| {
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        raster.write_with_options(
+            &mut buffer,
+            &WriteOptions {
+                mask,
+                ..Default::default()
+            },
+        )?;
+        GeoTiff::from_bytes(&buffer.into_inner())
+    };
+    let read_mask = |geotiff: &GeoTiff| {
+        let mask = geotiff.masks().next().unwrap();
+        mask.read_window::<u8>(mask.window(), &ReadOptions::default())
+            .unwrap()
+            .data
+    };
+
+    let written = write(WriteMask::FromNodata).unwrap();
+    assert_eq!(written.images().len(), 2);
+    assert!(written.nodata().unwrap().is_nan());
+    assert_eq!(read_mask(&written), [1, 1, 1, 1, 0, 1]);
+
+    let explicit = vec![false, true, false, true, true, false];
+    let written = write(WriteMask::Explicit(explicit)).unwrap();
+    assert_eq!(read_mask(&written), [0, 1, 0, 1, 1, 0]);
+
+    assert_eq!(write(WriteMask::None).unwrap().masks().count(), 0);
+    assert!(write(WriteMask::Explicit(vec![true; 5])).is_err());
+}
+
+#[test]
+fn test_write_rgb() {
+    let a = grid(3, 2, 1000.0, &[1, 2, 3, 4, 255, 6]);
+    let red = raster_ops::sub(a.band(0).unwrap(), 1.0).unwrap();
+    let green = raster_ops::mul(a.band(0).unwrap(), 100.0).unwrap();
+    let blue = InMemoryRaster {
+        data: vec![0.4, f64::NAN, -3.0, 7.6, f64::NAN, 255.0],
+        ..red.clone()
+    };
+    let write = |options: &WriteOptions| {
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        InMemoryRaster::write_rgb([&red, &green, &blue], &mut buffer, options)?;
+        Ok::<_, tiff::TiffError>(buffer.into_inner())
+    };
+
+    let options = WriteOptions {
+        mask: WriteMask::FromNodata,
+        alpha: true,
+        ..Default::default()
+    };
+    let bytes = write(&options).unwrap();
+    let mut decoder = tiff::decoder::Decoder::new(std::io::Cursor::new(&bytes)).unwrap();
+    assert_eq!(decoder.get_tag_u32(Tag::ExtraSamples).unwrap(), 2);
+    let written = GeoTiff::from_bytes(&bytes).unwrap();
+    assert_eq!(written.images().len(), 1);
+    assert_eq!(written.nodata(), None);
+    assert_eq!(written.model_extent(), a.model_extent());
+    let window = written
+        .read_window::<u8>(written.primary().window(), &ReadOptions::default())
+        .unwrap();
+    assert_eq!(window.num_samples, 4);
+    assert_eq!(
+        window.data,
+        [
+            0, 100, 0, 255, 1, 200, 0, 255, 2, 255, 0, 255, 3, 255, 8, 255, 0, 0, 0, 0, 5, 255,
+            255, 255
+        ]
+    );
+
+    let written = GeoTiff::from_bytes(&write(&WriteOptions::default()).unwrap()).unwrap();
+    assert_eq!(written.masks().count(), 0);
+    let window = written
+        .read_window::<u8>(written.primary().window(), &ReadOptions::default())
+        .unwrap();
+    assert_eq!(window.num_samples, 3);
+
+    let small = InMemoryRaster {
+        width: 1,
+        data: vec![1.0; 2],
+        ..red.clone()
+    };
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    assert!(InMemoryRaster::write_rgb([&red, &small, &blue], &mut buffer, &options).is_err());
+}
+
+#[test]
+fn test_write_reproducible() {
+    let a = grid(3, 2, 1000.0, &[1, 2, 3, 4, 255, 6]);
+    let mut raster = raster_ops::sub(a.band(0).unwrap(), 1.0).unwrap();
+    let mut write = |date_time, reproducible| {
+        raster.document_info.date_time = DateTime::parse(date_time);
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        let options = WriteOptions {
+            mask: WriteMask::FromNodata,
+            reproducible,
+            ..Default::default()
+        };
+        raster.write_with_options(&mut buffer, &options).unwrap();
+        buffer.into_inner()
+    };
+
+    assert_ne!(
+        write("2024-01-01 00:00:00", false),
+        write("2024-01-02 00:00:00", false)
+    );
+    let bytes = write("2024-01-01 00:00:00", true);
+    assert_eq!(bytes, write("2024-01-02 00:00:00", true));
+    assert_eq!(
+        GeoTiff::from_bytes(&bytes)
+            .unwrap()
+            .document_info()
+            .date_time,
+        None
+    );
+}
+
+#[test]
+fn test_difference() {
+    let a = grid(3, 2, 1000.0, &[1, 2, 3, 4, 255, 6]);
+    let b = grid(3, 2, 1000.0, &[1, 5, 0, 4, 4, 7]);
+    let options = raster_ops::DifferenceOptions { threshold: 1.0 };
+
+    let difference =
+        raster_ops::difference(a.band(0).unwrap(), b.band(0).unwrap(), &options).unwrap();
+    assert_values(
+        &difference.raster.data,
+        &[0.0, 3.0, -3.0, 0.0, f64::NAN, 1.0],
+    );
+    assert_eq!(
+        difference.stats,
+        raster_ops::DifferenceStats {
+            valid_pixels: 5,
+            changed_pixels: 2,
+            increased_pixels: 1,
+            decreased_pixels: 1,
+            changed_area: Some(200.0),
+            min: Some(-3.0),
+            max: Some(3.0),
+            mean: Some(0.2),
+        }
+    );
+}
+
+#[test]
+fn test_apply_into() {
+    let data = (0..40 * 30).map(|i| (i % 256) as u8).collect::<Vec<_>>();
+    let a = grid(40, 30, 1000.0, &data);
+    let b = grid(
+        40,
+        30,
+        1000.0,
+        &data.iter().rev().copied().collect::<Vec<_>>(),
+    );
+    let (band_a, band_b) = (a.band(0).unwrap(), b.band(0).unwrap());
+    let expected = raster_ops::apply(Operation::Sub, band_a, band_b).unwrap();
+
+    for layout in [Layout::Strips, Layout::Tiles(16)] {
+        let options = WriteOptions {
+            layout,
+            ..Default::default()
+        };
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        raster_ops::apply_into(Operation::Sub, band_a, band_b, &mut buffer, &options).unwrap();
+        let written = GeoTiff::from_bytes(&buffer.into_inner()).unwrap();
+        assert!(written.nodata().unwrap().is_nan());
+        assert_eq!(written.model_extent(), a.model_extent());
+        let values = written
+            .band(0)
+            .unwrap()
+            .read_window(written.primary().window())
+            .unwrap();
+        assert_values(&values, &expected.data);
+    }
+
+    let options = WriteOptions {
+        mask: WriteMask::FromNodata,
+        ..Default::default()
+    };
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    assert!(raster_ops::apply_into(Operation::Add, band_a, 1.0, &mut buffer, &options).is_err());
+}
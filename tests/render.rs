@@ -0,0 +1,102 @@
+#![cfg(feature = "decode")]
+
+use std::io::Cursor;
+
+use common::encode_gray8;
+use geotiff::{ColorRamp, GeoTiff, RenderOptions, Stretch, Window};
+use tiff::encoder::{colortype, TiffEncoder};
+use tiff::tags::Tag;
+
+mod common;
+
+#[test]
+fn test_thumbnail_grayscale() {
+    let data = encode_gray8(4, 4, &(0..16).collect::<Vec<_>>(), |encoder| {
+        encoder.write_tag(Tag::GdalNodata, "15").unwrap();
+    });
+    let geotiff = GeoTiff::from_bytes(&data).unwrap();
+
+    let options = RenderOptions {
+        stretch: Stretch::MinMax,
+        ..Default::default()
+    };
+    let thumbnail = geotiff.thumbnail_with_options(2, &options).unwrap();
+    assert_eq!((thumbnail.width, thumbnail.height), (2, 2));
+    assert_eq!(thumbnail.get_pixel(0, 0), [0, 0, 0, 255]);
+    assert_eq!(thumbnail.get_pixel(1, 0), [64, 64, 64, 255]);
+    assert_eq!(thumbnail.get_pixel(0, 1), [255, 255, 255, 255]);
+    assert_eq!(thumbnail.get_pixel(1, 1)[3], 0);
+
+    let thumbnail = geotiff.thumbnail(100).unwrap();
+    assert_eq!((thumbnail.width, thumbnail.height), (4, 4));
+}
+
+#[test]
+fn test_thumbnail_rgb() {
+    let mut buffer = Cursor::new(Vec::new());
+    let mut encoder = TiffEncoder::new(&mut buffer).unwrap();
+    encoder
+        .write_image::<colortype::RGB8>(2, 1, &[10, 0, 0, 20, 100, 50])
+        .unwrap();
+    let geotiff = GeoTiff::from_bytes(buffer.get_ref()).unwrap();
+
+    let thumbnail = geotiff.thumbnail(2).unwrap();
+    assert_eq!(thumbnail.get_pixel(0, 0), [0, 0, 0, 255]);
+    assert_eq!(thumbnail.get_pixel(1, 0), [255, 255, 255, 255]);
+}
+
+#[test]
+fn test_render_window_options() {
+    let data = encode_gray8(2, 2, &[0, 10, 20, 30], |_| {});
+    let geotiff = GeoTiff::from_bytes(&data).unwrap();
+
+    let options = RenderOptions {
+        stretch: Stretch::MinMax,
+        gamma: 2.0,
+        color_ramp: Some(ColorRamp::new(vec![(1.0, [0, 0, 255]), (0.0, [255, 0, 0])])),
+    };
+    let image = geotiff
+        .render_window(Window::new(-1, 0, 3, 2), 3, 2, &options)
+        .unwrap();
+    assert_eq!(image.get_pixel(0, 0)[3], 0);
+    assert_eq!(image.get_pixel(1, 0), [255, 0, 0, 255]);
+    // sqrt(1/3) of the way from red to blue
+    assert_eq!(image.get_pixel(2, 0), [108, 0, 147, 255]);
+    assert_eq!(image.get_pixel(2, 1), [0, 0, 255, 255]);
+
+    let options = RenderOptions {
+        stretch: Stretch::StdDev(1.0),
+        ..Default::default()
+    };
+    let image = geotiff
+        .render_window(Window::full(2, 2), 2, 2, &options)
+        .unwrap();
+    assert_eq!(image.get_pixel(0, 0), [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(1, 0), [70, 70, 70, 255]);
+    assert_eq!(image.get_pixel(1, 1), [255, 255, 255, 255]);
+}
+
+#[test]
+fn test_color_ramps() {
+    assert_eq!(ColorRamp::viridis().color_at(0.0), [68, 1, 84]);
+    assert_eq!(ColorRamp::viridis().color_at(2.0), [253, 231, 37]);
+    assert_eq!(ColorRamp::terrain().color_at(0.5), [255, 255, 153]);
+    assert_eq!(ColorRamp::grayscale().color_at(0.5), [128, 128, 128]);
+
+    let data = encode_gray8(3, 1, &[0, 100, 255], |encoder| {
+        encoder.write_tag(Tag::GdalNodata, "255").unwrap();
+    });
+    let geotiff = GeoTiff::from_bytes(&data).unwrap();
+    let options = RenderOptions {
+        color_ramp: Some(ColorRamp::from_values(vec![
+            (0.0, [0, 0, 0]),
+            (50.0, [0, 100, 0]),
+            (200.0, [0, 100, 0]),
+        ])),
+        ..Default::default()
+    };
+    let image = geotiff.thumbnail_with_options(3, &options).unwrap();
+    assert_eq!(image.get_pixel(0, 0), [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(1, 0), [0, 100, 0, 255]);
+    assert_eq!(image.get_pixel(2, 0), [0, 0, 0, 0]);
+}
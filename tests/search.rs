@@ -0,0 +1,96 @@
+#![cfg(feature = "decode")]
+
+use common::{raster, read_raster, Georeferencing};
+use geo_types::Coord;
+use geotiff::{find_pixels, GeoTiff, Layout, PixelMatch, Window, WriteOptions};
+
+mod common;
+
+/// A 40 x 30 raster of 10 m pixels whose values are their index, with the nodata value 7.
+fn geotiff() -> GeoTiff {
+    let data = (0..40 * 30).map(|i| i as f64).collect();
+    let options = WriteOptions {
+        layout: Layout::Tiles(16),
+        ..Default::default()
+    };
+    read_raster(
+        &raster(Georeferencing::Utm32, 40, 30, data, Some(7.0)),
+        &options,
+    )
+}
+
+#[test]
+fn test_find_pixels() {
+    let geotiff = geotiff();
+    let band = geotiff.band(0).unwrap();
+
+    let found = find_pixels(band, |value| value > 1195.0, band.image().window())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(found.len(), 4);
+    assert_eq!(
+        found[0],
+        PixelMatch {
+            x: 36,
+            y: 29,
+            value: 1196.0,
+            model: Some(Coord {
+                x: 1365.0,
+                y: 1705.0
+            }),
+        }
+    );
+
+    // Only the pixels of the window, without the nodata one, across tiles
+    let window = Window::new(5, -2, 13, 3);
+    let found = find_pixels(band, |value| value < 10.0 || value == 16.0, window)
+        .map(|found| found.map(|found| (found.x, found.y)))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(found, [(5, 0), (6, 0), (8, 0), (9, 0), (16, 0)]);
+
+    let limited = find_pixels(band, |value| value % 2.0 == 1.0, band.image().window())
+        .take(3)
+        .map(|found| found.unwrap().value)
+        .collect::<Vec<_>>();
+    assert_eq!(limited, [1.0, 3.0, 5.0]);
+
+    let outside = Window::new(100, 100, 5, 5);
+    assert_eq!(find_pixels(band, |_| true, outside).count(), 0);
+}
+
+#[test]
+fn test_sample_nearest_valid() {
+    let geotiff = geotiff();
+    let band = geotiff.band(0).unwrap();
+
+    // The center of the pixel (2, 3), whose value is valid
+    let coord = Coord {
+        x: 1025.0,
+        y: 1965.0,
+    };
+    let nearest = band.sample_nearest_valid(&coord, 0).unwrap().unwrap();
+    assert_eq!((nearest.pixel.x, nearest.pixel.y), (2, 3));
+    assert_eq!((nearest.pixel.value, nearest.distance), (122.0, 0.0));
+
+    // The pixel (7, 0) is nodata, and the location is nearer to the pixel on its right
+    let coord = Coord {
+        x: 1078.0,
+        y: 1995.0,
+    };
+    assert_eq!(band.sample_nearest_valid(&coord, 0).unwrap(), None);
+    let nearest = band.sample_nearest_valid(&coord, 5).unwrap().unwrap();
+    assert_eq!((nearest.pixel.x, nearest.pixel.y), (8, 0));
+    assert_eq!(nearest.pixel.value, 8.0);
+    assert!((nearest.distance - 7.0).abs() < 1e-9);
+
+    // Outside of the raster, 3 pixels to the left of the pixel (0, 5)
+    let coord = Coord {
+        x: 975.0,
+        y: 1945.0,
+    };
+    assert_eq!(band.sample_nearest_valid(&coord, 2).unwrap(), None);
+    let nearest = band.sample_nearest_valid(&coord, 3).unwrap().unwrap();
+    assert_eq!((nearest.pixel.x, nearest.pixel.y), (0, 5));
+    assert!((nearest.distance - 30.0).abs() < 1e-9);
+}
@@ -0,0 +1,99 @@
+#![cfg(feature = "decode")]
+
+use std::io::Cursor;
+
+use geo_types::coord;
+use geotiff::{DateTime, GeoTiff};
+use tiff::encoder::{colortype, TiffEncoder};
+use tiff::tags::Tag;
+
+/// Encodes a 2x1 image with 3 bands and the given GDAL_METADATA tag.
+fn encode_time_series(metadata: &str) -> GeoTiff {
+    let mut buffer = Cursor::new(Vec::new());
+    let mut encoder = TiffEncoder::new(&mut buffer).unwrap();
+    let mut image = encoder.new_image::<colortype::RGB8>(2, 1).unwrap();
+    image
+        .encoder()
+        .write_tag(Tag::Unknown(42112), metadata)
+        .unwrap();
+    image.encoder().write_tag(Tag::GdalNodata, "255").unwrap();
+    image.write_data(&[1, 2, 3, 4, 255, 6]).unwrap();
+    GeoTiff::from_bytes(buffer.get_ref()).unwrap()
+}
+
+#[test]
+fn test_gdal_metadata() {
+    let geotiff = encode_time_series(concat!(
+        "<GDALMetadata>\n",
+        "  <Item name=\"AREA_OR_POINT\">Area</Item>\n",
+        "  <Item name=\"DESCRIPTION\" sample=\"1\" role=\"description\">Red &amp; green</Item>\n",
+        "  <Item name=\"OFFSET\" sample=\"2\" role=\"offset\">1.5</Item>\n",
+        "</GDALMetadata>",
+    ));
+    let metadata = geotiff.gdal_metadata().unwrap();
+    assert_eq!(metadata.items.len(), 3);
+    assert_eq!(metadata.get("AREA_OR_POINT"), Some("Area"));
+    assert_eq!(metadata.items[1].sample, Some(1));
+    assert_eq!(metadata.items[1].role.as_deref(), Some("description"));
+    assert_eq!(metadata.items[1].value, "Red & green");
+    assert_eq!(geotiff.time_series(), None);
+    assert!(geotiff
+        .read_timeseries_at(&coord! { x: 0.5, y: 0.5 })
+        .is_err());
+}
+
+#[test]
+fn test_date_time_bands() {
+    let geotiff = encode_time_series(concat!(
+        "<GDALMetadata>",
+        "<Item name=\"DateTime\" sample=\"0\">2020:01:31 12:00:00</Item>",
+        "<Item name=\"DateTime\" sample=\"1\">2020-02-29</Item>",
+        "<Item name=\"ACQUISITIONDATETIME\" sample=\"2\">2020-03-01T06:30:00.5Z</Item>",
+        "</GDALMetadata>",
+    ));
+    let timestamps = geotiff.time_series().unwrap().timestamps;
+    assert_eq!(
+        timestamps.iter().map(|t| t.to_string()).collect::<Vec<_>>(),
+        [
+            "2020-01-31T12:00:00Z",
+            "2020-02-29T00:00:00Z",
+            "2020-03-01T06:30:00.5Z"
+        ]
+    );
+
+    let values = geotiff
+        .read_timeseries_at(&coord! { x: 1.5, y: 0.5 })
+        .unwrap();
+    assert_eq!(values, [(timestamps[0], 4.0), (timestamps[2], 6.0)]);
+    assert!(geotiff
+        .read_timeseries_at(&coord! { x: 2.5, y: 0.5 })
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_netcdf_time_bands() {
+    let geotiff = encode_time_series(concat!(
+        "<GDALMetadata>",
+        "<Item name=\"time#units\">hours since 2000-01-01 00:00:00</Item>",
+        "<Item name=\"NETCDF_DIM_time\" sample=\"0\">0</Item>",
+        "<Item name=\"NETCDF_DIM_time\" sample=\"1\">36</Item>",
+        "<Item name=\"NETCDF_DIM_time\" sample=\"2\">-24</Item>",
+        "</GDALMetadata>",
+    ));
+    let timestamps = geotiff.time_series().unwrap().timestamps;
+    assert_eq!(
+        timestamps[1],
+        DateTime::parse("2000-01-02T12:00:00").unwrap()
+    );
+    assert_eq!(timestamps[2], DateTime::parse("1999-12-31").unwrap());
+}
+
+#[test]
+fn test_date_time() {
+    assert_eq!(DateTime::parse("2021-02-29"), None);
+    assert_eq!(DateTime::parse("not a date"), None);
+    let date_time = DateTime::parse("1969-07-20 20:17:40").unwrap();
+    assert_eq!(date_time.unix_seconds(), -14182940.0);
+    assert_eq!(DateTime::from_unix_seconds(-14182940.0), date_time);
+}
@@ -0,0 +1,97 @@
+#![cfg(feature = "decode")]
+
+use std::fs;
+
+use common::encode_gray8;
+use geotiff::{GeoKeyDirectory, GeoTiff, OpenOptions, TransformTags};
+
+mod common;
+
+#[test]
+fn test_plain_tiff() {
+    let data = encode_gray8(3, 2, &[0; 6], |_| {});
+
+    let geotiff = GeoTiff::from_bytes(&data).unwrap();
+    assert!(!geotiff.is_georeferenced());
+    assert!(geotiff.geo_key_directory().is_none());
+    assert!(geotiff.coordinate_transform().is_none());
+    assert_eq!(
+        geotiff.model_extent().max(),
+        geo_types::coord! { x: 3.0, y: 2.0 }
+    );
+
+    let result = OpenOptions::new()
+        .allow_ungeoreferenced(false)
+        .read_bytes(&data);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_world_file() {
+    let directory = std::env::temp_dir().join(format!("geotiff-world-file-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("image.tif");
+    fs::write(&path, encode_gray8(2, 2, &[0; 4], |_| {})).unwrap();
+    fs::write(
+        directory.join("image.tfw"),
+        "10.0\n0.0\n0.0\n-10.0\n1005.0\n1995.0\n",
+    )
+    .unwrap();
+
+    let geotiff = OpenOptions::new()
+        .allow_ungeoreferenced(false)
+        .open_path(&path)
+        .unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert!(geotiff.is_georeferenced());
+    assert!(geotiff.geo_key_directory().is_none());
+    let extent = geotiff.model_extent();
+    assert_eq!(extent.min(), geo_types::coord! { x: 1000.0, y: 1980.0 });
+    assert_eq!(extent.max(), geo_types::coord! { x: 1020.0, y: 2000.0 });
+}
+
+#[test]
+fn test_overrides() {
+    let data = encode_gray8(4, 2, &[0; 8], |_| {});
+
+    let geotiff = OpenOptions::new()
+        .allow_ungeoreferenced(false)
+        .override_crs(32633)
+        .override_bounds(
+            geo_types::coord! { x: 1000.0, y: 2000.0 },
+            geo_types::coord! { x: 1040.0, y: 1980.0 },
+        )
+        .read_bytes(&data)
+        .unwrap();
+    let directory = geotiff.geo_key_directory().unwrap();
+    assert!(directory.is_projected());
+    assert_eq!(directory.projected_type, Some(32633));
+    let extent = geotiff.model_extent();
+    assert_eq!(extent.min(), geo_types::coord! { x: 1000.0, y: 1980.0 });
+    assert_eq!(extent.max(), geo_types::coord! { x: 1040.0, y: 2000.0 });
+
+    let geotiff = OpenOptions::new()
+        .override_crs(GeoKeyDirectory {
+            geographic_type: Some(4326),
+            ..Default::default()
+        })
+        .override_transform(TransformTags {
+            pixel_scale: Some(vec![0.5, 0.5, 0.0]),
+            tie_points: Some(vec![0.0, 0.0, 0.0, 10.0, 50.0, 0.0]),
+            model_transformation: None,
+        })
+        .read_bytes(&data)
+        .unwrap();
+    assert_eq!(
+        geotiff.geo_key_directory(),
+        Some(&GeoKeyDirectory {
+            geographic_type: Some(4326),
+            ..Default::default()
+        })
+    );
+    let extent = geotiff.model_extent();
+    assert_eq!(extent.min(), geo_types::coord! { x: 10.0, y: 49.0 });
+    assert_eq!(extent.max(), geo_types::coord! { x: 12.0, y: 50.0 });
+    assert_eq!(GeoKeyDirectory::from_epsg(4326).geographic_type, Some(4326));
+}
@@ -0,0 +1,130 @@
+#![cfg(feature = "decode")]
+
+use std::io::Cursor;
+
+use common::encode_gray8;
+use geo_types::Coord;
+use geotiff::{AngularUnit, GeoTiff, LinearUnit, OpenOptions};
+use tiff::tags::Tag;
+
+mod common;
+
+#[test]
+fn test_convert_linear() {
+    assert_eq!(
+        GeoTiff::convert_linear(1.0, LinearUnit::Foot, LinearUnit::Metre),
+        0.3048
+    );
+    assert_eq!(
+        GeoTiff::convert_linear(2.5, LinearUnit::Kilometre, LinearUnit::Metre),
+        2500.0
+    );
+    assert!(
+        (GeoTiff::convert_linear(3937.0, LinearUnit::UsSurveyFoot, LinearUnit::Metre) - 1200.0)
+            .abs()
+            < 1e-9
+    );
+    assert_eq!(
+        LinearUnit::from_code(9003, None),
+        Some(LinearUnit::UsSurveyFoot)
+    );
+    assert_eq!(
+        LinearUnit::from_code(32767, Some(2.0)),
+        Some(LinearUnit::Other(2.0))
+    );
+    assert_eq!(LinearUnit::from_code(32767, None), None);
+}
+
+fn encode_us_survey_feet() -> Vec<u8> {
+    encode_gray8(2, 2, &[0; 4], |encoder| {
+        encoder
+            .write_tag(
+                Tag::GeoKeyDirectoryTag,
+                &[
+                    1u16, 1, 1, 3, 1024, 0, 1, 1, 3072, 0, 1, 2263, 3076, 0, 1, 9003,
+                ][..],
+            )
+            .unwrap();
+        encoder
+            .write_tag(Tag::ModelPixelScaleTag, &[3937.0, 3937.0, 0.0][..])
+            .unwrap();
+        encoder
+            .write_tag(
+                Tag::ModelTiepointTag,
+                &[0.0, 0.0, 0.0, 0.0, 39370.0, 0.0][..],
+            )
+            .unwrap();
+    })
+}
+
+#[test]
+fn test_normalize_linear_units() {
+    let geotiff = GeoTiff::from_bytes(&encode_us_survey_feet()).unwrap();
+    assert_eq!(
+        geotiff.primary().model_linear_unit(),
+        Some(LinearUnit::UsSurveyFoot)
+    );
+    assert_eq!(
+        geotiff.model_extent().max(),
+        Coord {
+            x: 7874.0,
+            y: 39370.0
+        }
+    );
+
+    let geotiff = OpenOptions::new()
+        .normalize_linear_units(true)
+        .read(Cursor::new(encode_us_survey_feet()))
+        .unwrap();
+    assert_eq!(
+        geotiff.primary().model_linear_unit(),
+        Some(LinearUnit::Metre)
+    );
+    let max = geotiff.model_extent().max();
+    assert!((max.x - 2400.0).abs() < 1e-9);
+    assert!((max.y - 12000.0).abs() < 1e-9);
+    assert_eq!(geotiff.geo_key_directory.proj_linear_units, Some(9003));
+}
+
+#[test]
+fn test_normalized_angular_units() {
+    // Geographic CRS in grads with a pixel size of 0.1 grad
+    let data = encode_gray8(2, 2, &[0; 4], |encoder| {
+        encoder
+            .write_tag(
+                Tag::GeoKeyDirectoryTag,
+                &[
+                    1u16, 1, 1, 3, 1024, 0, 1, 2, 2048, 0, 1, 4807, 2054, 0, 1, 9105,
+                ][..],
+            )
+            .unwrap();
+        encoder
+            .write_tag(Tag::ModelPixelScaleTag, &[0.1, 0.1, 0.0][..])
+            .unwrap();
+        encoder
+            .write_tag(Tag::ModelTiepointTag, &[0.0, 0.0, 0.0, 10.0, 50.0, 0.0][..])
+            .unwrap();
+    });
+    let geotiff = GeoTiff::from_bytes(&data).unwrap();
+    assert_eq!(
+        geotiff.geo_key_directory.geographic_angular_unit(),
+        Some(AngularUnit::Grad)
+    );
+
+    let normalized = geotiff.normalized().unwrap();
+    let model = normalized.transform_to_model(&Coord { x: 1.0, y: 1.0 });
+    assert!((model.x - 9.09).abs() < 1e-9);
+    assert!((model.y - 44.91).abs() < 1e-9);
+    let raster = normalized.transform_to_raster(&model).unwrap();
+    assert!((raster.x - 1.0).abs() < 1e-9);
+    assert!((raster.y - 1.0).abs() < 1e-9);
+
+    assert_eq!(
+        AngularUnit::convert(
+            std::f64::consts::PI,
+            AngularUnit::Radian,
+            AngularUnit::Degree
+        ),
+        180.0
+    );
+}
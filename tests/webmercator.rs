@@ -0,0 +1,98 @@
+use geo_types::{Coord, Rect};
+use geotiff::webmercator::{
+    lon_lat_to_meters, lon_lat_to_tile, meters_to_lon_lat, meters_to_tile, resolution, tile_bounds,
+    tile_bounds_lon_lat, tile_range, zoom_for_resolution, EARTH_RADIUS, MAX_LATITUDE,
+};
+
+const HALF_WORLD: f64 = std::f64::consts::PI * EARTH_RADIUS;
+
+fn assert_close(actual: Coord, expected: Coord, epsilon: f64) {
+    assert!(
+        (actual.x - expected.x).abs() < epsilon && (actual.y - expected.y).abs() < epsilon,
+        "{actual:?} != {expected:?}"
+    );
+}
+
+#[test]
+fn test_lon_lat_meters() {
+    assert_close(
+        lon_lat_to_meters(Coord { x: 0.0, y: 0.0 }),
+        Coord { x: 0.0, y: 0.0 },
+        1e-9,
+    );
+    let corner = Coord {
+        x: 180.0,
+        y: MAX_LATITUDE,
+    };
+    assert_close(
+        lon_lat_to_meters(corner),
+        Coord {
+            x: HALF_WORLD,
+            y: HALF_WORLD,
+        },
+        1e-6,
+    );
+    // The latitude is clamped at the poles
+    assert_eq!(
+        lon_lat_to_meters(Coord { x: 180.0, y: 90.0 }),
+        lon_lat_to_meters(corner)
+    );
+    let zurich = Coord {
+        x: 8.5417,
+        y: 47.3769,
+    };
+    assert_close(meters_to_lon_lat(lon_lat_to_meters(zurich)), zurich, 1e-12);
+}
+
+#[test]
+fn test_tiles() {
+    let zurich = Coord {
+        x: 8.5417,
+        y: 47.3769,
+    };
+    assert_eq!(lon_lat_to_tile(zurich, 10), (536, 358));
+    assert_eq!(lon_lat_to_tile(zurich, 0), (0, 0));
+    // Clamped to the tiles of the zoom level
+    let outside = Coord {
+        x: 2.0 * HALF_WORLD,
+        y: -2.0 * HALF_WORLD,
+    };
+    assert_eq!(meters_to_tile(outside, 2), (3, 3));
+
+    let bounds = tile_bounds(1, 1, 0);
+    assert_close(bounds.min(), Coord { x: 0.0, y: 0.0 }, 1e-9);
+    assert_close(
+        bounds.max(),
+        Coord {
+            x: HALF_WORLD,
+            y: HALF_WORLD,
+        },
+        1e-6,
+    );
+    let bounds = tile_bounds_lon_lat(1, 0, 1);
+    assert_close(
+        bounds.min(),
+        Coord {
+            x: -180.0,
+            y: -MAX_LATITUDE,
+        },
+        1e-9,
+    );
+    assert_close(bounds.max(), Coord { x: 0.0, y: 0.0 }, 1e-9);
+
+    // Bounds around the corner shared by 4 tiles of zoom level 2
+    let bounds = Rect::new(Coord { x: -10.0, y: -10.0 }, Coord { x: 10.0, y: 10.0 });
+    assert_eq!(tile_range(bounds, 2), (1..=2, 1..=2));
+    assert_eq!(tile_range(bounds, 0), (0..=0, 0..=0));
+}
+
+#[test]
+fn test_zoom_for_resolution() {
+    assert!((resolution(0, 256) - 156_543.033_928_041).abs() < 1e-6);
+    assert_eq!(resolution(1, 512), resolution(0, 256) / 4.0);
+    // A 0.6 m image needs the zoom level 18, of 0.597 m pixels
+    assert_eq!(zoom_for_resolution(0.6, 256), 18);
+    assert_eq!(zoom_for_resolution(resolution(18, 256), 256), 18);
+    assert_eq!(zoom_for_resolution(1e6, 256), 0);
+    assert_eq!(zoom_for_resolution(0.0, 256), 30);
+}
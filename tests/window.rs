@@ -0,0 +1,484 @@
+#![cfg(feature = "decode")]
+
+use std::io::Cursor;
+use std::sync::Arc;
+
+use common::{encode_gray8, encode_gray8_images, read_raster, Georeferencing};
+use geo_types::Coord;
+use geotiff::{
+    band_to_pixel_interleaved, pixel_to_band_interleaved, Conversion, GeoTiff, Interleave,
+    Orientation, OutOfBounds, ReadOptions, Resampling, ResamplingKernel, Window, WriteOptions,
+};
+use tiff::encoder::{colortype, TiffEncoder};
+use tiff::tags::Tag;
+
+mod common;
+
+fn read_test_image() -> GeoTiff {
+    let data = encode_gray8(3, 2, &[1, 2, 3, 4, 5, 6], |encoder| {
+        encoder.write_tag(Tag::GdalNodata, "255").unwrap();
+    });
+    GeoTiff::from_bytes(&data).unwrap()
+}
+
+#[test]
+fn test_read_window_inside() {
+    let geotiff = read_test_image();
+    assert_eq!(geotiff.nodata(), Some(255.0));
+
+    let window = geotiff
+        .read_window::<u8>(Window::new(1, 0, 2, 2), &ReadOptions::default())
+        .unwrap();
+    assert_eq!(window.window, Window::new(1, 0, 2, 2));
+    assert_eq!(window.data, vec![2, 3, 5, 6]);
+    assert_eq!(window.get(1, 1, 0), 6);
+}
+
+#[test]
+fn test_read_window_out_of_bounds() {
+    let geotiff = read_test_image();
+    let window = Window::new(-1, 1, 3, 2);
+
+    let filled = geotiff
+        .read_window::<u8>(window, &ReadOptions::default())
+        .unwrap();
+    assert_eq!(filled.window, window);
+    assert_eq!(filled.data, vec![255, 4, 5, 255, 255, 255]);
+
+    let options = ReadOptions {
+        out_of_bounds: OutOfBounds::Fill(0.0),
+        ..Default::default()
+    };
+    let filled = geotiff.read_window::<f32>(window, &options).unwrap();
+    assert_eq!(filled.data, vec![0.0, 4.0, 5.0, 0.0, 0.0, 0.0]);
+
+    let options = ReadOptions {
+        out_of_bounds: OutOfBounds::Clamp,
+        ..Default::default()
+    };
+    let clamped = geotiff.read_window::<u8>(window, &options).unwrap();
+    assert_eq!(clamped.window, Window::new(0, 1, 2, 1));
+    assert_eq!(clamped.data, vec![4, 5]);
+
+    let outside = geotiff
+        .read_window::<u8>(Window::new(5, 5, 2, 2), &options)
+        .unwrap();
+    assert!(outside.window.is_empty());
+    assert!(outside.data.is_empty());
+
+    let options = ReadOptions {
+        out_of_bounds: OutOfBounds::Error,
+        ..Default::default()
+    };
+    assert!(geotiff.read_window::<u8>(window, &options).is_err());
+    assert!(geotiff
+        .read_window::<u8>(Window::new(0, 0, 3, 2), &options)
+        .is_ok());
+
+    let options = ReadOptions {
+        out_of_bounds: OutOfBounds::Fill(-1.0),
+        ..Default::default()
+    };
+    assert!(geotiff.read_window::<u8>(window, &options).is_err());
+}
+
+#[test]
+fn test_read_window_decimated() {
+    let primary = (0..16).collect::<Vec<u8>>();
+    let data = encode_gray8_images(
+        &[(4, 4, &primary), (2, 2, &[100, 101, 102, 103])],
+        |index, encoder| {
+            if index == 1 {
+                encoder.write_tag(Tag::NewSubfileType, 1u32).unwrap();
+            }
+        },
+    );
+    let geotiff = GeoTiff::from_bytes(&data).unwrap();
+    let options = ReadOptions::default();
+
+    let decimated = geotiff
+        .read_window_decimated::<u8>(Window::full(4, 4), 2, 2, &options)
+        .unwrap();
+    assert_eq!((decimated.width, decimated.height), (2, 2));
+    assert_eq!(decimated.data, vec![100, 101, 102, 103]);
+
+    let strided = geotiff
+        .primary()
+        .read_window_decimated::<u8>(Window::full(4, 4), 2, 2, &options)
+        .unwrap();
+    assert_eq!(strided.data, vec![5, 7, 13, 15]);
+
+    let full = geotiff
+        .read_window_decimated::<u8>(Window::full(4, 4), 4, 4, &options)
+        .unwrap();
+    assert_eq!(full.data, primary);
+
+    let options = ReadOptions {
+        out_of_bounds: OutOfBounds::Clamp,
+        ..Default::default()
+    };
+    let clamped = geotiff
+        .read_window_decimated::<u8>(Window::new(-4, 0, 8, 4), 4, 2, &options)
+        .unwrap();
+    assert_eq!(clamped.window, Window::full(4, 4));
+    assert_eq!(clamped.data, vec![100, 101, 102, 103]);
+}
+
+/// The kernel of bilinear interpolation.
+#[derive(Debug)]
+struct Triangle;
+
+impl ResamplingKernel for Triangle {
+    fn radius(&self) -> usize {
+        1
+    }
+
+    fn weight(&self, distance: f64) -> f64 {
+        (1.0 - distance.abs()).max(0.0)
+    }
+}
+
+#[test]
+fn test_read_window_decimated_resampling() {
+    let data = encode_gray8(4, 4, &(0..16).collect::<Vec<u8>>(), |encoder| {
+        encoder.write_tag(Tag::GdalNodata, "5").unwrap();
+    });
+    let geotiff = GeoTiff::from_bytes(&data).unwrap();
+    let read = |resampling| {
+        let options = ReadOptions {
+            resampling,
+            ..Default::default()
+        };
+        let decimated = geotiff
+            .read_window_decimated::<f32>(Window::full(4, 4), 2, 2, &options)
+            .unwrap();
+        let rounded = geotiff
+            .read_window_decimated::<u8>(Window::full(4, 4), 2, 2, &options)
+            .unwrap();
+        (decimated.data, rounded.data)
+    };
+
+    // The nodata pixel 5 is excluded
+    assert_eq!(
+        read(Resampling::Average),
+        (vec![5.0 / 3.0, 4.5, 10.5, 12.5], vec![2, 5, 11, 13])
+    );
+    assert_eq!(
+        read(Resampling::Bilinear),
+        (vec![5.0 / 3.0, 4.5, 10.5, 12.5], vec![2, 5, 11, 13])
+    );
+    assert_eq!(read(Resampling::Mode).1, vec![0, 2, 8, 10]);
+    assert_eq!(read(Resampling::Nearest).1, vec![5, 7, 13, 15]);
+    // The edge pixels are repeated, and the weight of the nodata pixel is distributed
+    assert!((read(Resampling::Cubic).0[3] - 12.5).abs() < 0.5);
+    let triangle = Resampling::Kernel(Arc::new(Triangle));
+    assert_eq!(read(triangle.clone()), read(Resampling::Bilinear));
+    assert_eq!(triangle.clone(), triangle);
+    assert_ne!(triangle, Resampling::Kernel(Arc::new(Triangle)));
+
+    let options = ReadOptions {
+        resampling: Resampling::Average,
+        out_of_bounds: OutOfBounds::Fill(255.0),
+        ..Default::default()
+    };
+    let filled = geotiff
+        .read_window_decimated::<u8>(Window::new(-4, 0, 8, 4), 2, 2, &options)
+        .unwrap();
+    assert_eq!(filled.data, vec![255, 3, 255, 12]);
+}
+
+#[test]
+fn test_read_window_conversion() {
+    let geotiff = common::read_geotiff("resources/zh_dem_25.tif");
+    let window = Window::new(0, 0, 2, 1);
+    let options = |conversion| ReadOptions {
+        conversion,
+        ..Default::default()
+    };
+
+    let values = geotiff
+        .read_window::<i16>(window, &options(Conversion::Exact))
+        .unwrap();
+    assert!(values.data.iter().all(|value| *value > 255));
+    let exact = geotiff.read_window::<f32>(window, &options(Conversion::Exact));
+    assert_eq!(
+        exact.unwrap().data,
+        values.data.iter().map(|v| *v as f32).collect::<Vec<_>>()
+    );
+
+    assert!(geotiff
+        .read_window::<u8>(window, &options(Conversion::Exact))
+        .is_err());
+    assert!(geotiff
+        .read_window::<u8>(window, &options(Conversion::Lossy))
+        .is_err());
+    let saturated = geotiff
+        .read_window_decimated::<u8>(window, 1, 1, &options(Conversion::Saturate))
+        .unwrap();
+    assert_eq!(saturated.data, [255]);
+
+    // Fractional values are only truncated by lossy conversions
+    let data = common::encode_gray8(1, 1, &[0], |_| {});
+    let raster = geotiff::InMemoryRaster {
+        data: vec![2.5],
+        ..geotiff::InMemoryRaster::with_grid_of(
+            GeoTiff::from_bytes(&data).unwrap().primary(),
+            vec![0.0],
+            None,
+        )
+    };
+    let geotiff = GeoTiff::from_bytes(&raster.to_bytes().unwrap()).unwrap();
+    let window = Window::full(1, 1);
+    assert!(geotiff
+        .read_window::<i32>(window, &options(Conversion::Exact))
+        .is_err());
+    let lossy = geotiff.read_window::<i32>(window, &options(Conversion::Lossy));
+    assert_eq!(lossy.unwrap().data, [2]);
+}
+
+#[test]
+fn test_read_window_u16_as_f32() {
+    let mut buffer = Cursor::new(Vec::new());
+    let mut encoder = TiffEncoder::new(&mut buffer).unwrap();
+    let data = (0..11 * 3 * 3).map(|i| i * 600).collect::<Vec<u16>>();
+    let image = encoder.new_image::<colortype::RGB16>(11, 3).unwrap();
+    image.write_data(&data).unwrap();
+    let geotiff = GeoTiff::from_bytes(buffer.get_ref()).unwrap();
+
+    // Windows inside the raster take the fast path, the others fill the pixels outside
+    for window in [
+        Window::full(11, 3),
+        Window::new(1, 1, 9, 2),
+        Window::new(-1, 2, 13, 2),
+    ] {
+        let options = ReadOptions::default();
+        let values = geotiff.read_window::<f32>(window, &options).unwrap();
+        let expected = geotiff.read_window::<f64>(window, &options).unwrap();
+        let expected = expected.data.iter().map(|v| *v as f32).collect::<Vec<_>>();
+        assert_eq!(values.data, expected);
+    }
+    let values = geotiff
+        .read_window::<f32>(Window::new(10, 2, 1, 1), &ReadOptions::default())
+        .unwrap();
+    assert_eq!(values.data, [57600.0, 58200.0, 58800.0]);
+}
+
+#[test]
+fn test_read_window_scaled() {
+    let mut buffer = Cursor::new(Vec::new());
+    let mut encoder = TiffEncoder::new(&mut buffer).unwrap();
+    let data = (0..11 * 2).map(|i| i * 1000).collect::<Vec<u16>>();
+    let mut image = encoder.new_image::<colortype::Gray16>(11, 2).unwrap();
+    image.encoder().write_tag(Tag::GdalNodata, "3000").unwrap();
+    image.write_data(&data).unwrap();
+    let geotiff = GeoTiff::from_bytes(buffer.get_ref()).unwrap();
+
+    // Windows inside the raster take the fast path, the others are scaled after reading
+    let options = ReadOptions::default();
+    for window in [Window::full(11, 2), Window::new(-1, 0, 13, 2)] {
+        let values = geotiff
+            .read_window_scaled(window, 1e-4, -0.1, &options)
+            .unwrap();
+        let expected = geotiff.read_window::<f64>(window, &options).unwrap();
+        for (value, expected) in values.data.iter().zip(&expected.data) {
+            if *expected == 3000.0 {
+                assert!(value.is_nan());
+            } else {
+                assert_eq!(*value, *expected as f32 * 1e-4 - 0.1);
+            }
+        }
+    }
+    let values = geotiff
+        .read_window_scaled(Window::new(1, 0, 3, 1), 0.5, 1.0, &options)
+        .unwrap();
+    assert_eq!(values.data[..2], [501.0, 1001.0]);
+    assert!(values.data[2].is_nan());
+}
+
+#[test]
+fn test_read_window_interleave() {
+    let mut buffer = Cursor::new(Vec::new());
+    let mut encoder = TiffEncoder::new(&mut buffer).unwrap();
+    let image = encoder.new_image::<colortype::RGB8>(2, 1).unwrap();
+    image.write_data(&[1, 2, 3, 4, 5, 6]).unwrap();
+    let geotiff = GeoTiff::from_bytes(buffer.get_ref()).unwrap();
+
+    let options = ReadOptions {
+        interleave: Interleave::Band,
+        ..Default::default()
+    };
+    let bands = geotiff
+        .read_window::<u8>(Window::new(0, 0, 3, 1), &options)
+        .unwrap();
+    assert_eq!(bands.data, [1, 4, 0, 2, 5, 0, 3, 6, 0]);
+    assert_eq!(bands.get(1, 0, 2), 6);
+
+    let pixels = bands.with_interleave(Interleave::Pixel);
+    assert_eq!(pixels.data, [1, 2, 3, 4, 5, 6, 0, 0, 0]);
+    assert_eq!(pixels.get(1, 0, 2), 6);
+
+    let decimated = geotiff
+        .read_window_decimated::<u8>(Window::full(2, 1), 1, 1, &options)
+        .unwrap();
+    assert_eq!(decimated.data, [4, 5, 6]);
+
+    assert_eq!(pixel_to_band_interleaved(&[1, 2, 3, 4], 2), [1, 3, 2, 4]);
+    assert_eq!(band_to_pixel_interleaved(&[1, 3, 2, 4], 2), [1, 2, 3, 4]);
+}
+
+#[test]
+fn test_read_window_decimated_area_weighted() {
+    let data = encode_gray8(3, 2, &[0, 3, 6, 0, 3, 6], |_| {});
+    let geotiff = GeoTiff::from_bytes(&data).unwrap();
+    let options = ReadOptions {
+        resampling: Resampling::Average,
+        ..Default::default()
+    };
+    // Each output pixel covers one source pixel and a half
+    let decimated = geotiff
+        .read_window_decimated::<f64>(Window::full(3, 2), 2, 1, &options)
+        .unwrap();
+    assert_eq!(decimated.data, vec![1.0, 5.0]);
+}
+
+#[test]
+fn test_read_window_orientation() {
+    // A 4 x 3 raster of 10 m pixels whose values are their column plus 10 times their row
+    let data = (0..12).map(|i| (i % 4 + i / 4 * 10) as f64).collect();
+    let raster = common::raster(Georeferencing::Utm32, 4, 3, data, None);
+    let geotiff = read_raster(&raster, &WriteOptions::default());
+    let window = Window::new(1, 0, 3, 2);
+    let read = |flip_y, transpose| {
+        let options = ReadOptions {
+            orientation: Orientation { flip_y, transpose },
+            ..Default::default()
+        };
+        geotiff.read_window::<u8>(window, &options).unwrap()
+    };
+
+    let flipped = read(true, false);
+    assert_eq!((flipped.width, flipped.height), (3, 2));
+    assert_eq!(flipped.data, vec![11, 12, 13, 1, 2, 3]);
+    let transposed = read(false, true);
+    assert_eq!((transposed.width, transposed.height), (2, 3));
+    assert_eq!(transposed.data, vec![1, 11, 2, 12, 3, 13]);
+    let both = read(true, true);
+    assert_eq!(both.data, vec![11, 1, 12, 2, 13, 3]);
+    assert_eq!(
+        both.clone().with_orientation(Orientation::default()),
+        read(false, false)
+    );
+    assert_eq!(read(false, false).with_orientation(both.orientation), both);
+
+    // The pixels of the data are at the model coordinates of the pixels they were read from
+    let image_transform = geotiff.coordinate_transform().unwrap();
+    for data in [read(false, false), flipped, transposed, both] {
+        let transform = data.coordinate_transform(geotiff.primary()).unwrap();
+        for (x, y) in (0..data.width).flat_map(|x| (0..data.height).map(move |y| (x, y))) {
+            let value = data.get(x, y, 0) as usize;
+            let source = Coord {
+                x: (value % 10) as f64 + 0.5,
+                y: (value / 10) as f64 + 0.5,
+            };
+            let center = Coord {
+                x: x as f64 + 0.5,
+                y: y as f64 + 0.5,
+            };
+            let model = transform.transform_to_model(&center);
+            let expected = image_transform.transform_to_model(&source);
+            assert!((model.x - expected.x).abs() < 1e-9 && (model.y - expected.y).abs() < 1e-9);
+        }
+    }
+
+    // Decimated reads are oriented too, and band interleaved data alike
+    let options = ReadOptions {
+        orientation: Orientation {
+            flip_y: true,
+            transpose: false,
+        },
+        interleave: Interleave::Band,
+        ..Default::default()
+    };
+    let decimated = geotiff
+        .read_window_decimated::<u8>(Window::new(0, 0, 4, 2), 2, 2, &options)
+        .unwrap();
+    assert_eq!(decimated.data, vec![11, 13, 1, 3]);
+    let transform = decimated.coordinate_transform(geotiff.primary()).unwrap();
+    let corner = transform.transform_to_model(&Coord { x: 0.0, y: 2.0 });
+    assert_eq!((corner.x, corner.y), (1000.0, 2000.0));
+    assert!(geotiff
+        .read_window_into::<u8>(window, &mut [0; 6], 3, &options)
+        .is_err());
+}
+
+#[test]
+fn test_read_window_into() {
+    let geotiff = read_test_image();
+
+    // Read into the bottom right 2x2 corner of a 3x3 buffer
+    let mut buffer = [0u8; 9];
+    let window = geotiff
+        .read_window_into(
+            Window::new(1, 1, 2, 2),
+            &mut buffer[4..],
+            3,
+            &ReadOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(window, Window::new(1, 1, 2, 2));
+    assert_eq!(buffer, [0, 0, 0, 0, 5, 6, 0, 255, 255]);
+
+    let options = ReadOptions {
+        out_of_bounds: OutOfBounds::Clamp,
+        ..Default::default()
+    };
+    let mut buffer = [0.0f32; 4];
+    let window = geotiff
+        .read_window_into(Window::new(2, 0, 4, 4), &mut buffer, 2, &options)
+        .unwrap();
+    assert_eq!(window, Window::new(2, 0, 1, 2));
+    assert_eq!(buffer, [3.0, 0.0, 6.0, 0.0]);
+
+    // The buffer must hold the whole window
+    let mut buffer = [0u8; 5];
+    let full = geotiff.primary().window();
+    let options = ReadOptions::default();
+    assert!(geotiff
+        .read_window_into(full, &mut buffer, 3, &options)
+        .is_err());
+    assert!(geotiff
+        .read_window_into(full, &mut [0u8; 6], 2, &options)
+        .is_err());
+}
+
+#[test]
+fn test_align_window() {
+    let geotiff = common::read_geotiff("resources/zh_dem_25.tif");
+    assert_eq!(geotiff.block_size(), (399, 10));
+
+    assert_eq!(
+        geotiff.align_window(Window::new(5, 15, 10, 10)),
+        Some(Window::new(0, 10, 399, 20))
+    );
+    assert_eq!(
+        geotiff.align_window(Window::new(-10, 355, 20, 20)),
+        Some(Window::new(0, 350, 399, 16))
+    );
+    assert_eq!(geotiff.align_window(Window::new(400, 0, 10, 10)), None);
+
+    let windows = geotiff.optimal_read_windows(Window::new(0, 5, 10, 50), 399 * 25);
+    assert_eq!(
+        windows,
+        vec![
+            Window::new(0, 0, 399, 20),
+            Window::new(0, 20, 399, 20),
+            Window::new(0, 40, 399, 20),
+        ]
+    );
+    assert_eq!(
+        geotiff
+            .optimal_read_windows(Window::full(399, 366), 1)
+            .len(),
+        37
+    );
+}
@@ -0,0 +1,402 @@
+#![cfg(feature = "decode")]
+
+use std::io::Cursor;
+
+use common::{read_raster, write_raster, Georeferencing};
+use geotiff::{
+    check_baseline_tiff, copy_with, icc_color_space, pyramid, ByteOrder, Compression, Conformance,
+    DocumentInfo, GeoKeyDirectory, GeoTiff, IfdPlacement, InMemoryRaster, Layout, Lineage,
+    MetadataEdits, Overviews, Profile, ReadOptions, Resampling, WriteMask, WriteOptions,
+};
+
+mod common;
+
+fn raster(width: usize, height: usize) -> InMemoryRaster {
+    let data = (0..width * height)
+        .map(|i| if i % 7 == 0 { -1.0 } else { i as f64 / 4.0 })
+        .collect();
+    common::raster(Georeferencing::Utm32, width, height, data, Some(-1.0))
+        .with_lineage(Lineage::new("test"))
+}
+
+fn read_all(geotiff: &GeoTiff) -> Vec<f64> {
+    let image = geotiff.primary();
+    image
+        .read_window::<f64>(image.window(), &ReadOptions::default())
+        .unwrap()
+        .data
+}
+
+#[test]
+fn test_cog_profile() {
+    let raster = raster(40, 35);
+    let options = WriteOptions {
+        layout: Layout::Tiles(16),
+        mask: WriteMask::FromNodata,
+        ..Profile::Cog.into()
+    };
+    let bytes = write_raster(&raster, &options);
+    // The first IFD follows the header
+    assert_eq!(bytes[4..8], 8u32.to_le_bytes());
+
+    let geotiff = GeoTiff::from_bytes(&bytes).unwrap();
+    let capabilities = geotiff.capabilities();
+    assert!(capabilities.tiled);
+    assert_eq!((capabilities.compression, capabilities.predictor), (8, 3));
+    assert_eq!(capabilities.overviews, 2);
+    assert_eq!(read_all(&geotiff), raster.data);
+    assert_eq!(geotiff.nodata(), Some(-1.0));
+    assert_eq!(geotiff.lineage().unwrap().operation, "test");
+
+    let overviews = geotiff.overviews().collect::<Vec<_>>();
+    assert_eq!(
+        (overviews[0].raster_width, overviews[0].raster_height),
+        (20, 18)
+    );
+    assert_eq!(
+        (overviews[1].raster_width, overviews[1].raster_height),
+        (10, 9)
+    );
+    let overview = overviews[1]
+        .read_window::<f64>(overviews[1].window(), &ReadOptions::default())
+        .unwrap();
+    assert_eq!(overview.data[1], raster.get(6, 2));
+    assert_eq!(overview.data[4], -1.0);
+
+    let mask = geotiff.masks().next().unwrap();
+    let mask = mask
+        .read_window::<u8>(mask.window(), &ReadOptions::default())
+        .unwrap();
+    assert_eq!(mask.data[..8], [0, 1, 1, 1, 1, 1, 1, 0]);
+}
+
+#[test]
+fn test_gdal_compatible_profile() {
+    let raster = raster(300, 20);
+    let geotiff = read_raster(&raster, &Profile::GdalCompatible.into());
+    let capabilities = geotiff.capabilities();
+    assert!(!capabilities.tiled);
+    assert_eq!((capabilities.compression, capabilities.predictor), (8, 3));
+    assert_eq!(capabilities.overviews, 0);
+    assert_eq!(read_all(&geotiff), raster.data);
+
+    let options = WriteOptions {
+        compression: Compression::Lzw,
+        predictor: false,
+        overviews: Overviews::Factors(vec![3]),
+        ..Profile::GdalCompatible.into()
+    };
+    let geotiff = read_raster(&raster, &options);
+    assert_eq!(geotiff.capabilities().compression, 5);
+    assert_eq!(read_all(&geotiff), raster.data);
+    assert_eq!(geotiff.overviews().next().unwrap().raster_width, 100);
+
+    let options = WriteOptions {
+        overviews: Overviews::Factors(vec![2, 0]),
+        ..Profile::GdalCompatible.into()
+    };
+    let mut buffer = Cursor::new(Vec::new());
+    assert!(raster.write_with_options(&mut buffer, &options).is_err());
+    let bands = [&raster, &raster, &raster];
+    assert!(InMemoryRaster::write_rgb(bands, &mut buffer, &options).is_err());
+}
+
+#[test]
+fn test_baseline_profile() {
+    let raster = raster(5, 3);
+    let geotiff = read_raster(&raster, &Profile::BaselineGeoTiff.into());
+    assert_eq!(geotiff.capabilities().compression, 1);
+    assert_eq!(geotiff.nodata(), None);
+    assert_eq!(geotiff.lineage(), None);
+    assert_eq!(
+        geotiff.geo_key_directory().unwrap().projected_type,
+        Some(32632)
+    );
+    assert_eq!(read_all(&geotiff), raster.data);
+}
+
+#[test]
+fn test_rgb_profile() {
+    let band = |offset: f64| InMemoryRaster {
+        data: (0..40 * 20).map(|i| (i % 200) as f64 + offset).collect(),
+        ..raster(40, 20)
+    };
+    let (red, green, blue) = (band(0.0), band(20.0), band(40.0));
+    let options = WriteOptions {
+        layout: Layout::Tiles(16),
+        alpha: true,
+        mask: WriteMask::FromNodata,
+        ..Profile::Cog.into()
+    };
+    let mut buffer = Cursor::new(Vec::new());
+    InMemoryRaster::write_rgb([&red, &green, &blue], &mut buffer, &options).unwrap();
+    let geotiff = GeoTiff::from_bytes(&buffer.into_inner()).unwrap();
+    assert_eq!(geotiff.capabilities().predictor, 2);
+    assert_eq!(geotiff.overviews().count(), 2);
+    let image = geotiff.primary();
+    let data = image
+        .read_window::<u8>(image.window(), &ReadOptions::default())
+        .unwrap();
+    assert_eq!(data.data[..8], [0, 20, 40, 255, 1, 21, 41, 255]);
+    assert_eq!(data.data[4 * 41..4 * 41 + 4], [41, 61, 81, 255]);
+}
+
+fn violations(bytes: &[u8]) -> Vec<String> {
+    match check_baseline_tiff(bytes) {
+        Conformance::NonConformant(violations) => violations,
+        _ => Vec::new(),
+    }
+}
+
+#[test]
+fn test_layout_controls() {
+    let raster = InMemoryRaster {
+        document_info: DocumentInfo {
+            description: Some("abcd".into()),
+            ..Default::default()
+        },
+        ..raster(3, 1)
+    };
+    let baseline = write_raster(&raster, &Profile::BaselineGeoTiff.into());
+    assert_eq!(check_baseline_tiff(&baseline), Conformance::Conformant);
+
+    let cog = violations(&write_raster(&raster, &Profile::Cog.into()));
+    assert_eq!(
+        cog,
+        [
+            "IFD 0 lacks the required StripOffsets tag",
+            "IFD 0 lacks the required StripByteCounts tag",
+            "IFD 0 is tiled, which is an extension of TIFF 6.0",
+            "IFD 0 has the compression 8, which is not a baseline scheme",
+            "IFD 0 has the predictor 3, which is an extension of TIFF 6.0",
+        ]
+    );
+
+    let options = WriteOptions {
+        ifd_placement: IfdPlacement::BeforeData,
+        ..Profile::BaselineGeoTiff.into()
+    };
+    let bytes = write_raster(&raster, &options);
+    assert_eq!(bytes[4..8], 8u32.to_le_bytes());
+    assert_eq!(check_baseline_tiff(&bytes), Conformance::Conformant);
+    assert_eq!(read_all(&GeoTiff::from_bytes(&bytes).unwrap()), raster.data);
+
+    let options = WriteOptions {
+        sort_tags: false,
+        ..Profile::BaselineGeoTiff.into()
+    };
+    let bytes = write_raster(&raster, &options);
+    assert!(violations(&bytes)[0].contains("not sorted"));
+    assert_eq!(read_all(&GeoTiff::from_bytes(&bytes).unwrap()), raster.data);
+
+    let options = WriteOptions {
+        alignment: 1,
+        ..Profile::BaselineGeoTiff.into()
+    };
+    let bytes = write_raster(&raster, &options);
+    assert!(bytes.len() < baseline.len());
+    assert!(violations(&bytes)[0].contains("not word-aligned"));
+    assert_eq!(read_all(&GeoTiff::from_bytes(&bytes).unwrap()), raster.data);
+
+    let options = WriteOptions {
+        alignment: 8,
+        mask: WriteMask::FromNodata,
+        ..Profile::BaselineGeoTiff.into()
+    };
+    let bytes = write_raster(&raster, &options);
+    let first_ifd = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+    assert_eq!(first_ifd % 8, 0);
+    assert_eq!(check_baseline_tiff(&bytes), Conformance::Conformant);
+    assert_eq!(read_all(&GeoTiff::from_bytes(&bytes).unwrap()), raster.data);
+
+    let options = WriteOptions {
+        alignment: 0,
+        ..Default::default()
+    };
+    assert!(raster
+        .write_with_options(&mut Cursor::new(Vec::new()), &options)
+        .is_err());
+    assert!(violations(b"II\x2b\0")[0].contains("BigTIFF"));
+}
+
+#[test]
+fn test_big_endian() {
+    let raster = InMemoryRaster {
+        geo_key_directory: Some(GeoKeyDirectory {
+            projected_type: Some(32767),
+            proj_coord_trans: Some(1),
+            proj_nat_origin_long: Some(9.0),
+            proj_nat_origin_lat: Some(0.0),
+            proj_scale_at_nat_origin: Some(0.9996),
+            proj_false_easting: Some(500000.0),
+            proj_false_northing: Some(0.0),
+            ..GeoKeyDirectory::from_epsg(32632)
+        }),
+        ..raster(40, 35)
+    };
+    let options = WriteOptions {
+        byte_order: ByteOrder::BigEndian,
+        ..Profile::BaselineGeoTiff.into()
+    };
+    let bytes = write_raster(&raster, &options);
+    assert_eq!(bytes[..4], *b"MM\0\x2a");
+    assert_eq!(check_baseline_tiff(&bytes), Conformance::Conformant);
+    let geotiff = GeoTiff::from_bytes(&bytes).unwrap();
+    assert_eq!(geotiff.byte_order(), ByteOrder::BigEndian);
+    assert_eq!(read_all(&geotiff), raster.data);
+    assert_eq!(
+        geotiff.geo_key_directory(),
+        raster.geo_key_directory.as_ref()
+    );
+    assert_eq!(geotiff.write_options().byte_order, ByteOrder::BigEndian);
+
+    for predictor in [false, true] {
+        let options = WriteOptions {
+            layout: Layout::Tiles(16),
+            predictor,
+            byte_order: ByteOrder::BigEndian,
+            mask: WriteMask::FromNodata,
+            ..Profile::Cog.into()
+        };
+        let geotiff = read_raster(&raster, &options);
+        assert_eq!(read_all(&geotiff), raster.data);
+        assert_eq!(geotiff.nodata(), Some(-1.0));
+        assert_eq!(geotiff.masks().count(), 1);
+
+        // Copying the file keeps its encoding
+        let copied = read_raster(&raster, &geotiff.write_options());
+        assert_eq!(copied.byte_order(), ByteOrder::BigEndian);
+        let capabilities = copied.capabilities();
+        assert!(capabilities.tiled);
+        assert_eq!(capabilities.compression, 8);
+        assert_eq!(capabilities.predictor, if predictor { 3 } else { 1 });
+        assert_eq!(read_all(&copied), raster.data);
+    }
+
+    let little = read_raster(&raster, &WriteOptions::default());
+    assert_eq!(little.byte_order(), ByteOrder::LittleEndian);
+    assert_eq!(little.write_options().layout, Layout::Strips);
+}
+
+/// A minimal ICC profile header of the given data color space.
+fn icc_profile(color_space: &[u8; 4]) -> Vec<u8> {
+    let mut profile = vec![0; 132];
+    profile[..4].copy_from_slice(&132u32.to_be_bytes());
+    profile[12..16].copy_from_slice(b"mntr");
+    profile[16..20].copy_from_slice(color_space);
+    profile[20..24].copy_from_slice(b"XYZ ");
+    profile[36..40].copy_from_slice(b"acsp");
+    profile
+}
+
+#[test]
+fn test_icc_profile() {
+    let band = raster(4, 3);
+    let profile = icc_profile(b"RGB ");
+    assert_eq!(icc_color_space(&profile), Some("RGB "));
+    assert_eq!(icc_color_space(&profile[..100]), None);
+    let options = WriteOptions {
+        icc_profile: Some(profile.clone()),
+        ..Default::default()
+    };
+    let mut buffer = Cursor::new(Vec::new());
+    InMemoryRaster::write_rgb([&band, &band, &band], &mut buffer, &options).unwrap();
+    let bytes = buffer.into_inner();
+    let geotiff = GeoTiff::from_bytes(&bytes).unwrap();
+    assert_eq!(geotiff.icc_profile(), Some(&profile[..]));
+
+    // The profile is preserved by copies
+    let mut buffer = Cursor::new(Vec::new());
+    copy_with(&bytes[..], &mut buffer, &MetadataEdits::default()).unwrap();
+    let geotiff = GeoTiff::from_bytes(&buffer.into_inner()).unwrap();
+    assert_eq!(geotiff.icc_profile(), Some(&profile[..]));
+
+    // Single-band rasters have no color profile
+    let geotiff = read_raster(&band, &options);
+    assert_eq!(geotiff.icc_profile(), None);
+
+    let options = WriteOptions {
+        icc_profile: Some(icc_profile(b"GRAY")),
+        ..Default::default()
+    };
+    let mut buffer = Cursor::new(Vec::new());
+    assert!(InMemoryRaster::write_rgb([&band, &band, &band], &mut buffer, &options).is_err());
+}
+
+#[test]
+fn test_compress_many_tiles() {
+    // Enough tiles to be compressed in several batches by the workers of the thread pool
+    let raster = raster(300, 200);
+    let placements = [
+        IfdPlacement::AfterData,
+        IfdPlacement::BeforeData,
+        IfdPlacement::Start,
+    ];
+    for (compression, ifd_placement) in [Compression::Lzw, Compression::Deflate]
+        .into_iter()
+        .flat_map(|compression| placements.map(|placement| (compression, placement)))
+    {
+        let options = WriteOptions {
+            layout: Layout::Tiles(16),
+            compression,
+            predictor: true,
+            ifd_placement,
+            overviews: Overviews::Factors(vec![2]),
+            ..Default::default()
+        };
+        let bytes = write_raster(&raster, &options);
+        assert_eq!(write_raster(&raster, &options), bytes);
+        let geotiff = GeoTiff::from_bytes(&bytes).unwrap();
+        assert_eq!(read_all(&geotiff), raster.data);
+        assert_eq!(geotiff.images().len(), 2);
+
+        // The offsets are relative to the start of the file, and the writer is left at its end
+        let mut buffer = Cursor::new(b"abc".to_vec());
+        buffer.set_position(3);
+        raster.write_with_options(&mut buffer, &options).unwrap();
+        assert_eq!(buffer.position() as usize, bytes.len() + 3);
+        assert_eq!(buffer.get_ref()[3..], bytes);
+    }
+}
+
+#[test]
+fn test_average_overviews() {
+    // The averages accumulated from the chunks, while they are written or beforehand when the
+    // overviews come first, match those of the whole raster
+    let raster = raster(100, 70);
+    let factors = vec![2, 4, 3];
+    let geotiff = read_raster(&raster, &WriteOptions::default());
+    let levels =
+        pyramid::build_pyramid(geotiff.band(0).unwrap(), &factors, Resampling::Average).unwrap();
+    let placements = [
+        IfdPlacement::AfterData,
+        IfdPlacement::BeforeData,
+        IfdPlacement::Start,
+    ];
+    for (layout, ifd_placement) in [Layout::Strips, Layout::Tiles(16)]
+        .into_iter()
+        .flat_map(|layout| placements.map(|placement| (layout, placement)))
+    {
+        let options = WriteOptions {
+            layout,
+            ifd_placement,
+            overviews: Overviews::Factors(factors.clone()),
+            overview_resampling: Resampling::Average,
+            ..Default::default()
+        };
+        let geotiff = read_raster(&raster, &options);
+        assert_eq!(geotiff.images().len(), 4);
+        for (overview, level) in geotiff.images()[1..].iter().zip(&levels) {
+            let data = overview
+                .read_window::<f64>(overview.window(), &ReadOptions::default())
+                .unwrap()
+                .data;
+            let expected = level
+                .data
+                .iter()
+                .map(|&value| if value.is_nan() { -1.0 } else { value })
+                .collect::<Vec<_>>();
+            assert_eq!(data, expected);
+        }
+    }
+}